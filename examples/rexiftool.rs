@@ -20,6 +20,14 @@ struct Cli {
 
     #[arg(long)]
     debug: bool,
+
+    /// Print a report of unrecognized Exif tags
+    #[arg(long)]
+    unknown_tags: bool,
+
+    /// Print the structure tree of the file, for debugging
+    #[arg(long)]
+    dump: bool,
 }
 
 #[cfg(feature = "json_dump")]
@@ -59,7 +67,7 @@ fn run(cli: &Cli) -> Result<(), Box<dyn Error>> {
 
     let path = Path::new(&cli.file);
     if path.is_file() {
-        let _ = parse_file(&mut parser, path, cli.json);
+        let _ = parse_file(&mut parser, path, cli);
     } else if path.is_dir() {
         parse_dir(path, parser, cli)?;
     }
@@ -85,7 +93,7 @@ fn parse_dir(path: &Path, mut parser: MediaParser, cli: &Cli) -> Result<(), Box<
                 }
                 println!("File: {:?}", entry.path().as_os_str());
                 println!("------------------------------------------------");
-                let _ = parse_file(&mut parser, entry.path(), cli.json);
+                let _ = parse_file(&mut parser, entry.path(), cli);
             }
             Err(e) => {
                 eprintln!("Read dir entry failed: {e}");
@@ -99,11 +107,28 @@ fn parse_dir(path: &Path, mut parser: MediaParser, cli: &Cli) -> Result<(), Box<
 fn parse_file<P: AsRef<Path>>(
     parser: &mut MediaParser,
     path: P,
-    json: bool,
+    cli: &Cli,
 ) -> Result<(), nom_exif::Error> {
     let ms = MediaSource::file_path(path).inspect_err(handle_parsing_error)?;
+    if cli.dump {
+        let root = parser.debug_dump(ms).inspect_err(handle_parsing_error)?;
+        if cli.json {
+            #[cfg(feature = "json_dump")]
+            match serde_json::to_string_pretty(&root) {
+                Ok(s) => println!("{s}"),
+                Err(e) => eprintln!("Error: {e}"),
+            }
+        } else {
+            print!("{root}");
+        }
+        return Ok(());
+    }
     let values = if ms.has_exif() {
         let iter: ExifIter = parser.parse(ms).inspect_err(handle_parsing_error)?;
+        if cli.unknown_tags {
+            iter.unknown_tags().iter().for_each(|x| println!("{x}"));
+            return Ok(());
+        }
         iter.into_iter()
             .filter_map(|mut x| {
                 let res = x.take_result();
@@ -127,7 +152,7 @@ fn parse_file<P: AsRef<Path>>(
             .map(|x| (x.0.to_string(), x.1))
             .collect::<Vec<_>>()
     };
-    if json {
+    if cli.json {
         #[cfg(feature = "json_dump")]
         use std::collections::HashMap;
 
@@ -135,7 +160,7 @@ fn parse_file<P: AsRef<Path>>(
         match serde_json::to_string_pretty(
             &values
                 .into_iter()
-                .map(|x| (x.0.to_string(), x.1))
+                .map(|x| (x.0.to_string(), x.1.to_string()))
                 .collect::<HashMap<_, _>>(),
         ) {
             Ok(s) => {
@@ -156,7 +181,7 @@ fn handle_parsing_error(e: &nom_exif::Error) {
         nom_exif::Error::UnrecognizedFileFormat => {
             eprintln!("Unrecognized file format, consider filing a bug @ https://github.com/mindeng/nom-exif.");
         }
-        nom_exif::Error::ParseFailed(_) | nom_exif::Error::IOError(_) => {
+        _ => {
             eprintln!("Error: {e}");
         }
     }
@@ -0,0 +1,386 @@
+use chrono::{DateTime, FixedOffset};
+
+use crate::{
+    error::ParsingError,
+    video::{TrackInfo, TrackInfoTag},
+};
+
+type Guid = [u8; 16];
+
+// GUIDs are stored in little-endian form, e.g. the Header Object GUID is
+// 75B22630-668E-11CF-A6D9-00AA0062CE6C.
+const HEADER_OBJECT: &Guid = b"\x30\x26\xB2\x75\x8E\x66\xCF\x11\xA6\xD9\x00\xAA\x00\x62\xCE\x6C";
+const FILE_PROPERTIES_OBJECT: &Guid =
+    b"\xA1\xDC\xAB\x8C\x47\xA9\xCF\x11\x8E\xE4\x00\xC0\x0C\x20\x53\x65";
+const CONTENT_DESCRIPTION_OBJECT: &Guid =
+    b"\x33\x26\xB2\x75\x8E\x66\xCF\x11\xA6\xD9\x00\xAA\x00\x62\xCE\x6C";
+const EXTENDED_CONTENT_DESCRIPTION_OBJECT: &Guid =
+    b"\x40\xA4\xD0\xD2\x07\xE3\xD2\x11\x97\xF0\x00\xA0\xC9\x5E\xA8\x50";
+
+/// Header Object: GUID, size (u64), number of objects (u32), reserved (2
+/// bytes).
+const HEADER_SIZE: usize = 30;
+const OBJECT_HEADER_SIZE: usize = 24;
+
+/// Max size of the Header Object, which is loaded into memory as a whole, to
+/// avoid exhausting memory on corrupted files. It may contain pictures, so
+/// the limit is more generous than others.
+const MAX_HEADER_OBJECT_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Seconds between 1601-01-01 (the epoch of FILETIME) and 1970-01-01.
+const FILETIME_UNIX_DIFF_SECS: i64 = 11_644_473_600;
+
+// Value types of Extended Content Description
+const TYPE_UNICODE: u16 = 0;
+const TYPE_BYTE_ARRAY: u16 = 1;
+
+pub(crate) fn check_asf(input: &[u8]) -> bool {
+    input.starts_with(HEADER_OBJECT)
+}
+
+/// Parses ASF (*.asf, *.wmv, *.wma) files. All the metadata is located in
+/// the Header Object at the start of files, which is loaded into memory.
+///
+/// Refer to: [Advanced Systems Format (ASF) Specification](https://learn.microsoft.com/en-us/windows/win32/wmformat/overview-of-the-asf-format)
+pub(crate) fn parse_asf(input: &[u8]) -> Result<TrackInfo, ParsingError> {
+    if input.len() < HEADER_SIZE {
+        return Err(ParsingError::Need(HEADER_SIZE - input.len()));
+    }
+    if !check_asf(input) {
+        return Err("not an ASF file".into());
+    }
+    let size = u64_at(input, 16);
+    if size > MAX_HEADER_OBJECT_SIZE || size < HEADER_SIZE as u64 {
+        return Err(ParsingError::Failed(format!(
+            "invalid ASF Header Object size: {size}"
+        )));
+    }
+    let size = size as usize;
+    if input.len() < size {
+        return Err(ParsingError::Need(size - input.len()));
+    }
+
+    let mut info = AsfInfo::default();
+    for (guid, data) in Objects(&input[HEADER_SIZE..size]) {
+        match guid {
+            FILE_PROPERTIES_OBJECT => info.parse_file_properties(data),
+            CONTENT_DESCRIPTION_OBJECT => info.parse_content_description(data),
+            EXTENDED_CONTENT_DESCRIPTION_OBJECT => info.parse_extended_content_description(data),
+            _ => (),
+        }
+    }
+    Ok(info.into())
+}
+
+#[derive(Debug, Clone, Default)]
+struct AsfInfo {
+    duration_ms: Option<u64>,
+    create_date: Option<DateTime<FixedOffset>>,
+
+    title: Option<String>,
+    author: Option<String>,
+    description: Option<String>,
+
+    /// `WM/AlbumTitle`
+    album: Option<String>,
+    /// `WM/ToolName`
+    software: Option<String>,
+    /// MIME type of `WM/Picture`
+    picture: Option<String>,
+}
+
+impl AsfInfo {
+    fn parse_file_properties(&mut self, data: &[u8]) {
+        if data.len() < 68 {
+            return;
+        }
+        let flags = u32::from_le_bytes(data[64..68].try_into().unwrap());
+        // The Broadcast flag means the creation date and the durations are
+        // invalid, e.g.: the file is being written.
+        if flags & 0x01 != 0 {
+            return;
+        }
+
+        self.create_date = filetime_to_date(u64_at(data, 24));
+        // The play duration (in 100-nanosecond units) includes the preroll
+        // (in milliseconds)
+        let duration = (u64_at(data, 40) / 10_000).saturating_sub(u64_at(data, 56));
+        self.duration_ms = Some(duration).filter(|x| *x > 0);
+    }
+
+    fn parse_content_description(&mut self, data: &[u8]) {
+        let Some(lengths) = data.get(..10) else {
+            return;
+        };
+        let mut strings = &data[10..];
+        let mut fields = [None, None, None, None];
+        for (i, len) in lengths.chunks_exact(2).take(4).enumerate() {
+            let len = u16::from_le_bytes([len[0], len[1]]) as usize;
+            let Some((s, rest)) = strings.split_at_checked(len) else {
+                break;
+            };
+            fields[i] = utf16_string(s);
+            strings = rest;
+        }
+        // Copyright is ignored
+        let [title, author, _, description] = fields;
+        self.title = title;
+        self.author = author;
+        self.description = description;
+    }
+
+    fn parse_extended_content_description(&mut self, data: &[u8]) {
+        let Some((count, mut data)) = data.split_at_checked(2) else {
+            return;
+        };
+        for _ in 0..u16::from_le_bytes([count[0], count[1]]) {
+            let Some((name, value_type, value, rest)) = parse_descriptor(data) else {
+                break;
+            };
+            data = rest;
+
+            match (name.as_str(), value_type) {
+                ("WM/AlbumTitle", TYPE_UNICODE) => self.album = utf16_string(value),
+                ("WM/ToolName", TYPE_UNICODE) => self.software = utf16_string(value),
+                ("WM/Picture", TYPE_BYTE_ARRAY) if self.picture.is_none() => {
+                    // Picture type (u8), data length (u32), then the MIME type
+                    self.picture = value.get(5..).and_then(utf16_string);
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+/// Parses a content descriptor, returns `(name, value_type, value, remain)`.
+fn parse_descriptor(data: &[u8]) -> Option<(String, u16, &[u8], &[u8])> {
+    let (name_len, data) = data.split_at_checked(2)?;
+    let (name, data) =
+        data.split_at_checked(u16::from_le_bytes([name_len[0], name_len[1]]) as usize)?;
+    let (header, data) = data.split_at_checked(4)?;
+    let value_type = u16::from_le_bytes([header[0], header[1]]);
+    let (value, remain) =
+        data.split_at_checked(u16::from_le_bytes([header[2], header[3]]) as usize)?;
+    Some((
+        utf16_string(name).unwrap_or_default(),
+        value_type,
+        value,
+        remain,
+    ))
+}
+
+/// Iterates over the objects in the Header Object, yields `(guid, data)`.
+struct Objects<'a>(&'a [u8]);
+
+impl<'a> Iterator for Objects<'a> {
+    type Item = (&'a Guid, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0.len() < OBJECT_HEADER_SIZE {
+            return None;
+        }
+        let guid: &Guid = self.0[..16].try_into().unwrap();
+        let size = u64_at(self.0, 16);
+        if size < OBJECT_HEADER_SIZE as u64 || size > self.0.len() as u64 {
+            tracing::warn!(size, "Ignored: invalid ASF object size");
+            return None;
+        }
+        let (object, rest) = self.0.split_at(size as usize);
+        self.0 = rest;
+        Some((guid, &object[OBJECT_HEADER_SIZE..]))
+    }
+}
+
+/// Decodes a null-terminated UTF-16LE string.
+fn utf16_string(data: &[u8]) -> Option<String> {
+    let units = data
+        .chunks_exact(2)
+        .map(|x| u16::from_le_bytes([x[0], x[1]]))
+        .take_while(|x| *x != 0);
+    let s: String = char::decode_utf16(units)
+        .map(|x| x.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect();
+    Some(s.trim().to_owned()).filter(|x| !x.is_empty())
+}
+
+/// Converts a FILETIME (100-nanosecond intervals since 1601-01-01 UTC).
+fn filetime_to_date(t: u64) -> Option<DateTime<FixedOffset>> {
+    if t == 0 {
+        return None;
+    }
+    let secs = (t / 10_000_000) as i64 - FILETIME_UNIX_DIFF_SECS;
+    let nanos = (t % 10_000_000) as u32 * 100;
+    DateTime::from_timestamp(secs, nanos).map(|x| x.fixed_offset())
+}
+
+fn u64_at(input: &[u8], pos: usize) -> u64 {
+    u64::from_le_bytes(input[pos..pos + 8].try_into().unwrap())
+}
+
+impl From<AsfInfo> for TrackInfo {
+    fn from(value: AsfInfo) -> Self {
+        let mut info = TrackInfo::default();
+        if let Some(duration) = value.duration_ms {
+            info.put(TrackInfoTag::DurationMs, duration.into());
+        }
+        if let Some(date) = value.create_date {
+            info.put(TrackInfoTag::CreateDate, date.into());
+        }
+
+        let texts = [
+            (TrackInfoTag::Title, value.title),
+            (TrackInfoTag::Artist, value.author),
+            (TrackInfoTag::Description, value.description),
+            (TrackInfoTag::Album, value.album),
+            (TrackInfoTag::Software, value.software),
+            (TrackInfoTag::Picture, value.picture),
+        ];
+        for (tag, text) in texts {
+            if let Some(text) = text {
+                info.put(tag, text.into());
+            }
+        }
+        info
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{MediaParser, MediaSource};
+    use test_case::test_case;
+
+    fn object(guid: &Guid, data: &[u8]) -> Vec<u8> {
+        let size = (OBJECT_HEADER_SIZE + data.len()) as u64;
+        [&guid[..], &size.to_le_bytes(), data].concat()
+    }
+
+    fn utf16(s: &str) -> Vec<u8> {
+        s.encode_utf16()
+            .chain([0])
+            .flat_map(|x| x.to_le_bytes())
+            .collect()
+    }
+
+    fn descriptor(name: &str, value_type: u16, value: &[u8]) -> Vec<u8> {
+        let name = utf16(name);
+        [
+            &(name.len() as u16).to_le_bytes()[..],
+            &name,
+            &value_type.to_le_bytes(),
+            &(value.len() as u16).to_le_bytes(),
+            value,
+        ]
+        .concat()
+    }
+
+    /// Makes an ASF file which lasts 12.345 seconds, created at
+    /// 2010-06-15T08:30:00Z.
+    pub(crate) fn make_asf(broadcast: bool, picture_size: usize) -> Vec<u8> {
+        // 2010-06-15T08:30:00Z in FILETIME
+        let created = (1_276_590_600 + FILETIME_UNIX_DIFF_SECS as u64) * 10_000_000;
+        let preroll = 3000_u64;
+        let file_properties = object(
+            FILE_PROPERTIES_OBJECT,
+            &[
+                &[0; 16][..],
+                &0_u64.to_le_bytes(),
+                &created.to_le_bytes(),
+                &100_u64.to_le_bytes(),
+                &((12_345 + preroll) * 10_000).to_le_bytes(),
+                &(12_345 * 10_000_u64).to_le_bytes(),
+                &preroll.to_le_bytes(),
+                &(broadcast as u32 | 0x02).to_le_bytes(),
+                &[0; 12],
+            ]
+            .concat(),
+        );
+
+        let strings = [utf16("Title"), utf16("Author"), vec![], utf16("Desc")];
+        let mut content = Vec::new();
+        for s in strings.iter().chain([&vec![]]) {
+            content.extend((s.len() as u16).to_le_bytes());
+        }
+        content.extend(strings.concat());
+        let content_description = object(CONTENT_DESCRIPTION_OBJECT, &content);
+
+        let picture = [
+            &[3][..],
+            &(picture_size as u32).to_le_bytes(),
+            &utf16("image/jpeg"),
+            &utf16(""),
+            &vec![0xFF; picture_size],
+        ]
+        .concat();
+        let descriptors = [
+            descriptor("WM/AlbumTitle", TYPE_UNICODE, &utf16("Album")),
+            descriptor("WM/TrackNumber", 3, &5_u32.to_le_bytes()),
+            descriptor("WM/Picture", TYPE_BYTE_ARRAY, &picture),
+            descriptor("WM/ToolName", TYPE_UNICODE, &utf16("Windows Media Encoder")),
+        ];
+        let extended = object(
+            EXTENDED_CONTENT_DESCRIPTION_OBJECT,
+            &[
+                &(descriptors.len() as u16).to_le_bytes()[..],
+                &descriptors.concat(),
+            ]
+            .concat(),
+        );
+
+        let objects = [file_properties, content_description, extended].concat();
+        let header_size = (HEADER_SIZE + objects.len()) as u64;
+        [
+            &HEADER_OBJECT[..],
+            &header_size.to_le_bytes(),
+            &3_u32.to_le_bytes(),
+            &[1, 2],
+            &objects,
+            // Data Object
+            &[0x36, 0x26, 0xB2, 0x75],
+            &[0; 1024],
+        ]
+        .concat()
+    }
+
+    #[test_case(false, 16; "small picture")]
+    #[test_case(false, 60 * 1024; "large picture")]
+    #[test_case(true, 16; "broadcast")]
+    fn asf(broadcast: bool, picture_size: usize) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let data = make_asf(broadcast, picture_size);
+        let mut parser = MediaParser::new();
+        let ms = MediaSource::unseekable(Cursor::new(data)).unwrap();
+        assert!(ms.has_track());
+        let info: TrackInfo = parser.parse(ms).unwrap();
+
+        if broadcast {
+            assert_eq!(info.get(TrackInfoTag::DurationMs), None);
+            assert_eq!(info.get(TrackInfoTag::CreateDate), None);
+        } else {
+            assert_eq!(info.get(TrackInfoTag::DurationMs), Some(&12345_u64.into()));
+            assert_eq!(
+                info.get(TrackInfoTag::CreateDate).unwrap().to_string(),
+                "2010-06-15T08:30:00+00:00"
+            );
+        }
+        assert_eq!(info.get(TrackInfoTag::Title), Some(&"Title".into()));
+        assert_eq!(info.get(TrackInfoTag::Artist), Some(&"Author".into()));
+        assert_eq!(info.get(TrackInfoTag::Description), Some(&"Desc".into()));
+        assert_eq!(info.get(TrackInfoTag::Album), Some(&"Album".into()));
+        assert_eq!(
+            info.get(TrackInfoTag::Software),
+            Some(&"Windows Media Encoder".into())
+        );
+        assert_eq!(info.get(TrackInfoTag::Picture), Some(&"image/jpeg".into()));
+    }
+
+    #[test]
+    fn asf_invalid_header_size() {
+        let mut data = make_asf(false, 16);
+        data[16..24].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(matches!(parse_asf(&data), Err(ParsingError::Failed(_))));
+    }
+}
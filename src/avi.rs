@@ -0,0 +1,260 @@
+use chrono::{DateTime, FixedOffset, NaiveDateTime};
+
+use crate::{
+    riff::{check_riff, to_string, to_text, u32_at, Chunks, InfoList, RiffForm, RiffParser},
+    video::{TrackInfo, TrackInfoTag},
+};
+
+pub(crate) fn check_avi(input: &[u8]) -> bool {
+    check_riff(input, AviInfo::FORM_TYPE)
+}
+
+/// Parses AVI (RIFF) files chunk by chunk. Only the header list (`hdrl`) and
+/// the `INFO` list are loaded, other chunks (e.g. the `movi` list, which
+/// contains the media data) are skipped.
+///
+/// Refer to: [AVI RIFF File Reference](https://learn.microsoft.com/en-us/windows/win32/directshow/avi-riff-file-reference)
+pub(crate) type AviParser = RiffParser<AviInfo>;
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct AviInfo {
+    /// From the main AVI header (`avih`)
+    micro_sec_per_frame: u32,
+    total_frames: u32,
+    width: u32,
+    height: u32,
+
+    /// The first video stream
+    video: Option<VideoStream>,
+
+    /// `IDIT` in `hdrl`
+    digitization_date: Option<DateTime<FixedOffset>>,
+    info_list: InfoList,
+}
+
+#[derive(Debug, Clone, Default)]
+struct VideoStream {
+    /// FourCC of the codec, from the stream header (`strh`), or the stream
+    /// format (`strf`) if the former is absent
+    codec: Option<String>,
+    scale: u32,
+    rate: u32,
+    length: u32,
+    width: u32,
+    height: u32,
+}
+
+impl RiffForm for AviInfo {
+    const FORM_TYPE: &'static [u8; 4] = b"AVI ";
+
+    fn wants(_: &[u8; 4], list_type: Option<&[u8]>) -> bool {
+        matches!(list_type, Some(b"hdrl" | b"INFO"))
+    }
+
+    fn parse_chunk(&mut self, _: &[u8; 4], data: &[u8]) {
+        let (list_type, list) = data.split_at(4);
+        if list_type == b"hdrl" {
+            self.parse_hdrl(list);
+        } else {
+            self.info_list.parse(list);
+        }
+    }
+}
+
+impl AviInfo {
+    fn parse_hdrl(&mut self, list: &[u8]) {
+        for (id, data) in Chunks(list) {
+            match id {
+                b"avih" if data.len() >= 40 => {
+                    self.micro_sec_per_frame = u32_at(data, 0);
+                    self.total_frames = u32_at(data, 16);
+                    self.width = u32_at(data, 32);
+                    self.height = u32_at(data, 36);
+                }
+                b"LIST" if data.starts_with(b"strl") && self.video.is_none() => {
+                    self.video = parse_video_stream(&data[4..]);
+                }
+                b"IDIT" => {
+                    // e.g.: "SAT DEC 31 12:00:00 2005\n"
+                    let s = to_string(data);
+                    self.digitization_date =
+                        NaiveDateTime::parse_from_str(&s, "%a %b %d %H:%M:%S %Y")
+                            .ok()
+                            .map(|x| x.and_utc().fixed_offset());
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+/// Parses a stream list (`strl`), returns `None` if it's not a video stream.
+fn parse_video_stream(list: &[u8]) -> Option<VideoStream> {
+    let mut stream = None;
+    for (id, data) in Chunks(list) {
+        match id {
+            b"strh" if data.len() >= 36 => {
+                if &data[..4] != b"vids" {
+                    return None;
+                }
+                stream = Some(VideoStream {
+                    codec: to_text(&data[4..8]),
+                    scale: u32_at(data, 20),
+                    rate: u32_at(data, 24),
+                    length: u32_at(data, 32),
+                    ..Default::default()
+                });
+            }
+            // BITMAPINFOHEADER
+            b"strf" if data.len() >= 20 => {
+                let stream = stream.as_mut()?;
+                stream.width = (u32_at(data, 4) as i32).unsigned_abs();
+                stream.height = (u32_at(data, 8) as i32).unsigned_abs();
+                if stream.codec.is_none() {
+                    stream.codec = to_text(&data[16..20]);
+                }
+            }
+            _ => (),
+        }
+    }
+    stream
+}
+
+impl From<AviInfo> for TrackInfo {
+    fn from(value: AviInfo) -> Self {
+        let mut info = TrackInfo::default();
+        let video = value.video.unwrap_or_default();
+
+        let (width, height) = if video.width > 0 && video.height > 0 {
+            (video.width, video.height)
+        } else {
+            (value.width, value.height)
+        };
+        if width > 0 && height > 0 {
+            info.put(TrackInfoTag::ImageWidth, width.into());
+            info.put(TrackInfoTag::ImageHeight, height.into());
+        }
+
+        // Prefer the stream header, since the main header counts the frames
+        // in the first RIFF chunk only, for OpenDML (AVI 2.0) files.
+        let (rate, duration_ms) = if video.scale > 0 && video.rate > 0 {
+            let rate = video.rate as f64 / video.scale as f64;
+            let duration = video.length as u64 * video.scale as u64 * 1000 / video.rate as u64;
+            (Some(rate), duration)
+        } else if value.micro_sec_per_frame > 0 {
+            let rate = 1_000_000.0 / value.micro_sec_per_frame as f64;
+            let duration = value.total_frames as u64 * value.micro_sec_per_frame as u64 / 1000;
+            (Some(rate), duration)
+        } else {
+            (None, 0)
+        };
+        if let Some(rate) = rate {
+            // Keep 3 decimal places, e.g.: 29.97
+            info.put(
+                TrackInfoTag::FrameRate,
+                ((rate * 1000.0).round() / 1000.0).into(),
+            );
+        }
+        if duration_ms > 0 {
+            info.put(TrackInfoTag::DurationMs, duration_ms.into());
+        }
+
+        if let Some(codec) = video.codec {
+            info.put(TrackInfoTag::VideoCodec, codec.into());
+        }
+
+        // `ICRD` takes precedence over `IDIT`
+        if let Some(date) = value.digitization_date {
+            info.put(TrackInfoTag::CreateDate, date.into());
+        }
+        value.info_list.put_into(&mut info);
+        info
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{
+        riff::tests::{chunk, le, list},
+        MediaParser, MediaSource,
+    };
+    use test_case::test_case;
+
+    /// Makes an AVI file of a 640x480 MJPG video stream at 30000/1001 fps,
+    /// whose `movi` list contains `frames` frames of `frame_size` bytes.
+    pub(crate) fn make_avi(frames: u32, frame_size: usize, info_at_end: bool) -> Vec<u8> {
+        let avih = chunk(
+            b"avih",
+            &le(&[33367, 0, 0, 0x10, frames, 0, 1, 0, 640, 480, 0, 0, 0, 0]),
+        );
+        let strh = chunk(
+            b"strh",
+            &[
+                &b"vidsMJPG"[..],
+                &le(&[0, 0, 0, 1001, 30000, 0, frames, 0, 0, 0, 0, 0]),
+            ]
+            .concat(),
+        );
+        let strf = chunk(
+            b"strf",
+            &[
+                &le(&[40, 640, (-480_i32) as u32, 0x180001])[..],
+                b"MJPG",
+                &le(&[0; 5]),
+            ]
+            .concat(),
+        );
+        let hdrl = list(b"hdrl", &[avih, list(b"strl", &[strh, strf])]);
+        let info = list(
+            b"INFO",
+            &[
+                chunk(b"ISFT", b"CanonMVI06\0"),
+                chunk(b"ICRD", b"2005-12-31 12:34:56\0"),
+            ],
+        );
+        let frame = chunk(b"00dc", &vec![0xAB; frame_size]);
+        let movi = list(b"movi", &vec![frame; frames as usize]);
+        let idx1 = chunk(b"idx1", &le(&vec![0; 4 * frames as usize]));
+
+        let body = if info_at_end {
+            [&b"AVI "[..], &hdrl, &movi, &idx1, &info].concat()
+        } else {
+            [
+                &b"AVI "[..],
+                &hdrl,
+                &info,
+                &chunk(b"JUNK", &[0; 11]),
+                &movi,
+                &idx1,
+            ]
+            .concat()
+        };
+        chunk(b"RIFF", &body)
+    }
+
+    #[test_case(false; "info before movi")]
+    #[test_case(true; "info at end")]
+    fn avi(info_at_end: bool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let data = make_avi(90, 10 * 1024, info_at_end);
+        let mut parser = MediaParser::new();
+        let ms = MediaSource::unseekable(Cursor::new(data)).unwrap();
+        assert!(ms.has_track());
+        let info: TrackInfo = parser.parse(ms).unwrap();
+
+        assert_eq!(info.get(TrackInfoTag::ImageWidth), Some(&640_u32.into()));
+        assert_eq!(info.get(TrackInfoTag::ImageHeight), Some(&480_u32.into()));
+        assert_eq!(info.get(TrackInfoTag::FrameRate), Some(&29.97.into()));
+        assert_eq!(info.get(TrackInfoTag::DurationMs), Some(&3003_u64.into()));
+        assert_eq!(info.get(TrackInfoTag::VideoCodec), Some(&"MJPG".into()));
+        assert_eq!(info.get(TrackInfoTag::Software), Some(&"CanonMVI06".into()));
+        assert_eq!(
+            info.get(TrackInfoTag::CreateDate).unwrap().to_string(),
+            "2005-12-31T12:34:56+00:00"
+        );
+    }
+}
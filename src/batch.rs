@@ -0,0 +1,183 @@
+//! Parallel scanning of many files, e.g. for photo indexers, see [`scan`] &
+//! [`scan_dir`]. Requires the `batch` feature.
+//!
+//! Files are parsed on the [rayon](https://docs.rs/rayon) thread pool, each
+//! thread reuses its own [`MediaParser`], so that the parsing buffers are not
+//! reallocated for every file.
+//!
+//! ```rust
+//! use nom_exif::{batch::{self, MediaInfo}, ExifTag};
+//! use rayon::prelude::*;
+//!
+//! let images: Vec<_> = batch::scan_dir("./testdata")
+//!     .filter_map(|(path, res)| match res {
+//!         Ok(MediaInfo::Image(exif)) => Some((path, exif.get(ExifTag::Make).cloned())),
+//!         _ => None,
+//!     })
+//!     .collect();
+//! assert!(images.iter().any(|(_, make)| make == &Some("vivo".into())));
+//! ```
+
+use std::{
+    cell::RefCell,
+    fs::{self, ReadDir},
+    path::{Path, PathBuf},
+};
+
+use rayon::iter::{ParallelBridge, ParallelIterator};
+
+use crate::{Exif, ExifIter, MediaParser, MediaSource, TrackInfo};
+
+/// Metadata of a file scanned by [`scan`] or [`scan_dir`].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum MediaInfo {
+    /// The Exif data of an image
+    Image(Box<Exif>),
+
+    /// The track info of a video/audio file
+    Track(Box<TrackInfo>),
+}
+
+impl MediaInfo {
+    /// Parses the file at `path` with `parser`, as an image if it has Exif
+    /// data, or as a video/audio file otherwise.
+    pub fn parse(parser: &mut MediaParser, path: impl AsRef<Path>) -> crate::Result<Self> {
+        let ms = MediaSource::file_path(path)?;
+        if ms.has_track() {
+            Ok(MediaInfo::Track(Box::new(parser.parse(ms)?)))
+        } else {
+            let iter: ExifIter = parser.parse(ms)?;
+            Ok(MediaInfo::Image(Box::new(iter.into())))
+        }
+    }
+}
+
+thread_local! {
+    static PARSER: RefCell<MediaParser> = RefCell::new(MediaParser::new());
+}
+
+/// Parses the files in `paths` in parallel, returns a parallel iterator of
+/// `(path, result)` pairs, in no particular order.
+///
+/// Files which are neither images with Exif data nor videos/audios result in
+/// `Err`, just like [`MediaParser::parse`].
+///
+/// ```rust
+/// use nom_exif::batch;
+/// use rayon::prelude::*;
+///
+/// let results: Vec<_> = batch::scan(["./testdata/exif.jpg", "./testdata/meta.mov"]).collect();
+/// assert_eq!(results.len(), 2);
+/// assert!(results.iter().all(|(_, res)| res.is_ok()));
+/// ```
+pub fn scan<I>(paths: I) -> impl ParallelIterator<Item = (PathBuf, crate::Result<MediaInfo>)>
+where
+    I: IntoIterator,
+    I::IntoIter: Send,
+    I::Item: Into<PathBuf>,
+{
+    paths
+        .into_iter()
+        .map(Into::into)
+        .par_bridge()
+        .map(scan_file)
+}
+
+/// Parses all files under `dir` (recursively) in parallel, see [`scan`].
+///
+/// Symbolic links to directories are not followed. Directories which can't
+/// be read are yielded along with the I/O errors.
+pub fn scan_dir(
+    dir: impl Into<PathBuf>,
+) -> impl ParallelIterator<Item = (PathBuf, crate::Result<MediaInfo>)> {
+    walk_dir(dir.into())
+        .par_bridge()
+        .map(|(path, err)| match err {
+            Some(e) => (path, Err(e.into())),
+            None => scan_file(path),
+        })
+}
+
+fn scan_file(path: PathBuf) -> (PathBuf, crate::Result<MediaInfo>) {
+    let res = PARSER.with_borrow_mut(|parser| MediaInfo::parse(parser, &path));
+    (path, res)
+}
+
+/// Walks `dir` recursively, yields the files, or the directories which can't
+/// be read along with the errors.
+fn walk_dir(dir: PathBuf) -> impl Iterator<Item = (PathBuf, Option<std::io::Error>)> {
+    let mut pending = vec![dir];
+    let mut current: Option<(PathBuf, ReadDir)> = None;
+    std::iter::from_fn(move || loop {
+        let Some((dir, entries)) = current.as_mut() else {
+            let dir = pending.pop()?;
+            match fs::read_dir(&dir) {
+                Ok(entries) => current = Some((dir, entries)),
+                Err(e) => return Some((dir, Some(e))),
+            }
+            continue;
+        };
+        let entry = match entries.next() {
+            Some(Ok(entry)) => entry,
+            Some(Err(e)) => return Some((dir.clone(), Some(e))),
+            None => {
+                current = None;
+                continue;
+            }
+        };
+        let path = entry.path();
+        match entry.file_type() {
+            Ok(t) if t.is_dir() => pending.push(path),
+            Ok(t) if t.is_file() || (t.is_symlink() && path.is_file()) => {
+                return Some((path, None))
+            }
+            Ok(_) => (),
+            Err(e) => return Some((path, Some(e))),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scan_dir() {
+        let results: Vec<_> = super::scan_dir("./testdata").collect();
+        let get = |name: &str| {
+            results
+                .iter()
+                .find(|x| x.0.file_name().unwrap() == name)
+                .map(|x| x.1.as_ref())
+                .unwrap()
+        };
+
+        let Ok(MediaInfo::Image(exif)) = get("exif.jpg") else {
+            panic!("exif.jpg is not parsed as an image");
+        };
+        assert_eq!(
+            exif.get(crate::ExifTag::Make).and_then(|x| x.as_str()),
+            Some("vivo")
+        );
+        assert!(matches!(get("meta.mov"), Ok(MediaInfo::Track(_))));
+        assert!(get("exif.jpg.txt").is_err());
+
+        // The same as parsing the files one by one
+        let mut parser = MediaParser::new();
+        for (path, res) in results {
+            let expect = MediaInfo::parse(&mut parser, &path);
+            assert_eq!(res.is_ok(), expect.is_ok(), "{path:?}");
+        }
+    }
+
+    #[test]
+    fn scan_errors() {
+        let results: Vec<_> = scan(["./testdata/no-such-file.jpg"]).collect();
+        assert!(results[0].1.is_err());
+
+        let results: Vec<_> = super::scan_dir("./testdata/no-such-dir").collect();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].1.is_err());
+    }
+}
@@ -11,15 +11,22 @@ mod idat;
 mod iinf;
 mod iloc;
 mod ilst;
+mod iprp;
+mod iref;
 mod keys;
 mod meta;
+mod moof;
 mod mvhd;
+mod stbl;
 mod tkhd;
-pub use ilst::IlstBox;
+pub(crate) use iinf::InfeBox;
+pub use ilst::{parse_itunes_items, IlstBox};
 pub use keys::KeysBox;
 pub use meta::MetaBox;
+pub use moof::{parse_fragment_duration, parse_prft, FragmentedTrack};
 pub use mvhd::MvhdBox;
-pub use tkhd::parse_video_tkhd_in_moov;
+pub use stbl::{parse_frame_rate, parse_sample_count, TrackSamples};
+pub use tkhd::{find_track, find_video_track, parse_video_tkhd_in_moov};
 
 const MAX_BODY_LEN: usize = 2000 * 1024 * 1024;
 
@@ -156,8 +163,9 @@ impl<'a> BoxHolder<'a> {
         let (_, header) = BoxHeader::parse(input)?;
         tracing::debug!(box_type = header.box_type, ?header, "Got");
 
-        let box_size = usize::try_from(header.box_size)
-            .expect("header box size should always fit into a `usize`.");
+        let Ok(box_size) = usize::try_from(header.box_size) else {
+            return fail(input);
+        };
 
         let (remain, data) = streaming::take(box_size)(input)?;
 
@@ -222,9 +230,10 @@ where
             break Ok((rem, header));
         }
 
-        if remain.len() < header.body_size() as usize {
+        if (remain.len() as u64) < header.body_size() {
+            let need = header.body_size() - remain.len() as u64;
             return Err(nom::Err::Incomplete(Needed::new(
-                header.body_size() as usize - remain.len(),
+                need.try_into().unwrap_or(usize::MAX),
             )));
         }
 
@@ -302,7 +311,7 @@ impl<O, T: ParseBody<O>> ParseBox<O> for T {
         );
 
         // limit parsing size
-        let box_size = header.body_size() as usize;
+        let box_size = usize::try_from(header.body_size()).unwrap_or(usize::MAX);
         if box_size > MAX_BODY_LEN {
             tracing::error!(?header.box_type, ?box_size, "Box is too big");
             return fail(remain);
@@ -321,6 +330,18 @@ impl<O, T: ParseBody<O>> ParseBox<O> for T {
     }
 }
 
+/// Parses a time/duration field of a full box, which is 64-bit in version 1,
+/// and 32-bit otherwise.
+fn be_u32_or_u64(version: u8) -> impl Fn(&[u8]) -> IResult<&[u8], u64> {
+    move |input| {
+        if version == 1 {
+            number::complete::be_u64(input)
+        } else {
+            number::complete::be_u32(input).map(|(remain, x)| (remain, x as u64))
+        }
+    }
+}
+
 fn parse_cstr(input: &[u8]) -> IResult<&[u8], String> {
     let (remain, s) = map_res(streaming::take_till(|b| b == 0), |bs: &[u8]| {
         if bs.is_empty() {
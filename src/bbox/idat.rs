@@ -1,6 +1,6 @@
 use std::ops::Range;
 
-use nom::{bytes::streaming, IResult};
+use nom::{bytes::streaming, combinator::fail, IResult};
 
 use crate::bbox::BoxHeader;
 
@@ -15,7 +15,9 @@ impl<'a> IdatBox<'a> {
     pub fn parse(input: &'a [u8]) -> IResult<&'a [u8], IdatBox> {
         let (remain, header) = BoxHeader::parse(input)?;
 
-        let box_size = usize::try_from(header.box_size).expect("box size must fit into a `usize`.");
+        let Ok(box_size) = usize::try_from(header.box_size) else {
+            return fail(input);
+        };
 
         let (remain, data) = streaming::take(box_size - header.header_size)(remain)?;
 
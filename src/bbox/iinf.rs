@@ -17,6 +17,11 @@ use super::{parse_cstr, ParseBody, ParseBox};
 pub struct IinfBox {
     pub(crate) header: FullBoxHeader,
     pub(crate) entries: HashMap<String, InfeBox>,
+    /// All `mime` items, since there may be several of them (e.g. the XMP
+    /// of the primary image & of an HDR gain map)
+    mime_entries: Vec<InfeBox>,
+    /// Item types keyed by item ids
+    item_types: HashMap<u32, String>,
 }
 
 impl ParseBody<IinfBox> for IinfBox {
@@ -32,12 +37,29 @@ impl ParseBody<IinfBox> for IinfBox {
         let (remain, entries) =
             many_m_n(item_count as usize, item_count as usize, InfeBox::parse_box)(remain)?;
 
+        let mime_entries = entries
+            .iter()
+            .filter(|e| e.item_type.as_deref() == Some("mime"))
+            .cloned()
+            .collect();
+        let item_types = entries
+            .iter()
+            .filter_map(|e| Some((e.id, e.item_type.clone()?)))
+            .collect();
         let entries = entries
             .into_iter()
             .map(|e| (e.key().to_owned(), e))
             .collect::<HashMap<_, _>>();
 
-        Ok((remain, IinfBox { header, entries }))
+        Ok((
+            remain,
+            IinfBox {
+                header,
+                entries,
+                mime_entries,
+                item_types,
+            },
+        ))
     }
 }
 
@@ -45,6 +67,25 @@ impl IinfBox {
     pub fn get_infe(&self, item_type: &'static str) -> Option<&InfeBox> {
         self.entries.get(item_type)
     }
+
+    /// Returns the first `mime` item with the given content type.
+    pub fn get_mime_infe(&self, content_type: &str) -> Option<&InfeBox> {
+        self.mime_entries
+            .iter()
+            .find(|x| x.content_type.as_deref() == Some(content_type))
+    }
+
+    /// Returns the type of item `id`, e.g. "hvc1", "grid".
+    pub fn item_type(&self, id: u32) -> Option<&str> {
+        self.item_types.get(&id).map(|x| x.as_str())
+    }
+
+    /// Returns all `mime` items with the given content type.
+    pub fn mime_infes<'a>(&'a self, content_type: &'a str) -> impl Iterator<Item = &'a InfeBox> {
+        self.mime_entries
+            .iter()
+            .filter(move |x| x.content_type.as_deref() == Some(content_type))
+    }
 }
 
 /// Info entry box
@@ -55,7 +96,7 @@ pub(crate) struct InfeBox {
     pub protection_index: u16,
     pub item_type: Option<String>, // version >= 2
     pub item_name: String,
-    content_type: Option<String>,
+    pub content_type: Option<String>,
     content_encoding: Option<String>,
     uri_type: Option<String>,
 }
@@ -9,7 +9,7 @@ use nom::sequence::tuple;
 
 use crate::EntryValue;
 
-use super::BoxHeader;
+use super::{BoxHeader, BoxHolder};
 
 /// Represents an [item list atom][1].
 ///
@@ -81,6 +81,73 @@ impl IlstItem {
     }
 }
 
+/// Parses the items of an iTunes-style item list (atom-path:
+/// `moov/udta/meta/ilst`), which are identified by their atom types (e.g.
+/// `©nam`), rather than by the indexes of a `keys` atom.
+///
+/// Custom items (`----`) are named `----:<mean>:<name>`, e.g.:
+/// `----:com.apple.iTunes:iTunSMPB`. An item yields several values if it
+/// contains several `data` atoms, e.g. multiple cover images in `covr`.
+pub fn parse_itunes_items(ilst_body: &[u8]) -> Vec<(String, EntryValue)> {
+    let mut items = Vec::new();
+    let mut remain = ilst_body;
+    while let Ok((rem, item)) = BoxHolder::parse(remain) {
+        remain = rem;
+
+        let mut key = item.box_type().to_owned();
+        let (mut mean, mut name) = (None, None);
+        let mut children = item.body_data();
+        while let Ok((rem, bbox)) = BoxHolder::parse(children) {
+            children = rem;
+            // `mean`, `name` & `data` all start with version & flags (or
+            // the type indicator)
+            let Some(body) = bbox.body_data().get(4..) else {
+                continue;
+            };
+            match bbox.box_type() {
+                "mean" => mean = Some(String::from_utf8_lossy(body).into_owned()),
+                "name" => name = Some(String::from_utf8_lossy(body).into_owned()),
+                "data" if body.len() >= 4 => {
+                    if let (Some(mean), Some(name)) = (&mean, &name) {
+                        key = format!("{}:{mean}:{name}", item.box_type());
+                    }
+                    let type_code =
+                        u32::from_be_bytes(bbox.body_data()[..4].try_into().unwrap()) & 0xff_ffff;
+                    // skip the locale
+                    let value = parse_itunes_value(&key, type_code, &body[4..]);
+                    items.push((key.clone(), value));
+                }
+                _ => (),
+            }
+        }
+    }
+    items
+}
+
+/// Parses the data of an iTunes-style item. Values of unknown types are kept
+/// as `EntryValue::Undefined`, e.g. JPEG (13) & PNG (14) images in `covr`.
+fn parse_itunes_value(key: &str, type_code: u32, data: &[u8]) -> EntryValue {
+    let be_u16_at = |pos: usize| u16::from_be_bytes([data[pos], data[pos + 1]]);
+    match (key, type_code) {
+        // Track/disk number & total number, in implicit (0) type
+        ("trkn" | "disk", 0) if data.len() >= 6 => {
+            EntryValue::U16Array(vec![be_u16_at(2), be_u16_at(4)])
+        }
+        // ID3v1 genre number plus 1
+        ("gnre", 0) if data.len() == 2 => EntryValue::U16(be_u16_at(0)),
+        (_, 1 | 21..=24) => {
+            parse_value(type_code, data).unwrap_or_else(|_| EntryValue::Undefined(data.to_vec()))
+        }
+        (_, 2) => {
+            let chars = data
+                .chunks_exact(2)
+                .map(|x| u16::from_be_bytes([x[0], x[1]]));
+            EntryValue::Text(String::from_utf16_lossy(&chars.collect::<Vec<_>>()))
+        }
+        _ => EntryValue::Undefined(data.to_vec()),
+    }
+}
+
 /// Parse ilst item data to value, see [Well-known
 /// types](https://developer.apple.com/documentation/quicktime-file-format/well-known_types)
 #[tracing::instrument(skip(data))]
@@ -136,7 +203,10 @@ fn parse_value(type_code: u32, data: &[u8]) -> crate::Result<EntryValue> {
 
 #[cfg(test)]
 mod tests {
-    use crate::{bbox::travel_while, testkit::read_sample};
+    use crate::{
+        bbox::travel_while,
+        testkit::{bbox, full_box, read_sample},
+    };
 
     use super::*;
     use test_case::test_case;
@@ -172,6 +242,51 @@ mod tests {
                 );
     }
 
+    fn data(type_code: u32, value: &[u8]) -> Vec<u8> {
+        bbox(
+            b"data",
+            &[&type_code.to_be_bytes()[..], &[0; 4], value].concat(),
+        )
+    }
+
+    #[test]
+    fn itunes_items() {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let ilst = [
+            bbox(b"\xa9nam", &data(1, b"Title")),
+            bbox(b"trkn", &data(0, &[0, 0, 0, 3, 0, 12, 0, 0])),
+            bbox(b"tmpo", &data(21, &[0, 120])),
+            bbox(b"covr", &[data(13, b"jpeg"), data(14, b"png")].concat()),
+            bbox(
+                b"----",
+                &[
+                    full_box(b"mean", b"com.apple.iTunes"),
+                    full_box(b"name", b"iTunSMPB"),
+                    data(1, b" 00000000"),
+                ]
+                .concat(),
+            ),
+        ]
+        .concat();
+
+        assert_eq!(
+            parse_itunes_items(&ilst),
+            [
+                ("\u{a9}nam", EntryValue::Text("Title".into())),
+                ("trkn", EntryValue::U16Array(vec![3, 12])),
+                ("tmpo", EntryValue::I16(120)),
+                ("covr", EntryValue::Undefined(b"jpeg".to_vec())),
+                ("covr", EntryValue::Undefined(b"png".to_vec())),
+                (
+                    "----:com.apple.iTunes:iTunSMPB",
+                    EntryValue::Text(" 00000000".into())
+                ),
+            ]
+            .map(|(k, v)| (k.to_owned(), v))
+        );
+    }
+
     #[test_case("embedded-in-heic.mov")]
     fn heic_mov_ilst(path: &str) {
         let _ = tracing_subscriber::fmt().with_test_writer().try_init();
@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+
+use nom::{
+    combinator::{cond, fail},
+    multi::many_m_n,
+    number::complete::{be_u16, be_u32, u8},
+    IResult,
+};
+
+use super::{find_box, BoxHolder, FullBoxHeader, ParseBody, ParseBox};
+
+/// Represents the `iprp` box (item properties) in a HEIF/HEIC file.
+///
+/// Only the properties we care about are parsed, see [`ItemProperty`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IprpBox {
+    /// Properties in `ipco`, property index starts from 1 in `ipma`.
+    properties: Vec<ItemProperty>,
+
+    /// item id -> property indexes (1-based), in association order
+    associations: HashMap<u32, Vec<u16>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ItemProperty {
+    /// `irot`: anti-clockwise rotation in units of 90 degrees.
+    Rotation(u8),
+
+    /// `imir`: 0 means mirroring about a vertical axis (left <-> right), 1
+    /// means mirroring about a horizontal axis (top <-> bottom).
+    Mirror(u8),
+
+    /// `ispe`: image spatial extents (width, height).
+    Extents(u32, u32),
+
+    /// `colr` with an ICC profile (colour type `prof` or `rICC`).
+    IccProfile(Vec<u8>),
+
+    /// `auxC`: type URN of an auxiliary image, e.g. an alpha plane or an HDR
+    /// gain map.
+    AuxiliaryType(String),
+
+    Other,
+}
+
+impl IprpBox {
+    /// Parses an `iprp` box, `input` should include the box header.
+    pub fn parse(input: &[u8]) -> IResult<&[u8], IprpBox> {
+        let (remain, bbox) = BoxHolder::parse(input)?;
+        if bbox.box_type() != "iprp" {
+            return fail(input);
+        }
+
+        let properties = match find_box(bbox.body_data(), "ipco")? {
+            (_, Some(ipco)) => parse_properties(ipco.body_data())?.1,
+            (_, None) => Vec::new(),
+        };
+
+        let mut associations = HashMap::new();
+        let mut data = bbox.body_data();
+        while !data.is_empty() {
+            let (rem, b) = BoxHolder::parse(data)?;
+            if b.box_type() == "ipma" {
+                let (_, ipma) = IpmaBox::parse_box(b.data)?;
+                associations.extend(ipma.entries);
+            }
+            data = rem;
+        }
+
+        Ok((
+            remain,
+            IprpBox {
+                properties,
+                associations,
+            },
+        ))
+    }
+
+    /// Returns properties associated with `item_id`, in association order.
+    pub fn item_properties(&self, item_id: u32) -> impl Iterator<Item = &ItemProperty> {
+        self.associations
+            .get(&item_id)
+            .into_iter()
+            .flatten()
+            .filter_map(|idx| self.properties.get((*idx as usize).checked_sub(1)?))
+    }
+
+    /// Returns the (smallest) id of the items which have a property matching
+    /// `predicate`.
+    pub fn find_item(&self, predicate: impl Fn(&ItemProperty) -> bool) -> Option<u32> {
+        self.associations
+            .keys()
+            .filter(|id| self.item_properties(**id).any(&predicate))
+            .min()
+            .copied()
+    }
+}
+
+fn parse_properties(input: &[u8]) -> IResult<&[u8], Vec<ItemProperty>> {
+    let mut properties = Vec::new();
+    let mut remain = input;
+    while !remain.is_empty() {
+        let (rem, bbox) = BoxHolder::parse(remain)?;
+        let body = bbox.body_data();
+        let prop = match bbox.box_type() {
+            "irot" => u8(body).map(|(_, x)| ItemProperty::Rotation(x & 0x03))?,
+            "imir" => u8(body).map(|(_, x)| ItemProperty::Mirror(x & 0x01))?,
+            "ispe" => {
+                let (_, ispe) = IspeBox::parse_box(bbox.data)?;
+                ItemProperty::Extents(ispe.width, ispe.height)
+            }
+            "colr" => match body.split_at_checked(4) {
+                Some((b"prof" | b"rICC", icc)) => ItemProperty::IccProfile(icc.to_vec()),
+                _ => ItemProperty::Other,
+            },
+            // full box, the type is a NUL-terminated string after the header
+            "auxC" => body
+                .get(4..)
+                .and_then(|x| x.split(|b| *b == 0).next())
+                .and_then(|x| std::str::from_utf8(x).ok())
+                .map(|x| ItemProperty::AuxiliaryType(x.to_owned()))
+                .unwrap_or(ItemProperty::Other),
+            _ => ItemProperty::Other,
+        };
+        properties.push(prop);
+        remain = rem;
+    }
+    Ok((remain, properties))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct IspeBox {
+    header: FullBoxHeader,
+    width: u32,
+    height: u32,
+}
+
+impl ParseBody<IspeBox> for IspeBox {
+    fn parse_body(body: &[u8], header: FullBoxHeader) -> IResult<&[u8], IspeBox> {
+        let (remain, width) = be_u32(body)?;
+        let (remain, height) = be_u32(remain)?;
+        Ok((
+            remain,
+            IspeBox {
+                header,
+                width,
+                height,
+            },
+        ))
+    }
+}
+
+/// Item property association box.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct IpmaBox {
+    header: FullBoxHeader,
+    entries: HashMap<u32, Vec<u16>>,
+}
+
+impl ParseBody<IpmaBox> for IpmaBox {
+    fn parse_body(body: &[u8], header: FullBoxHeader) -> IResult<&[u8], IpmaBox> {
+        let (mut remain, entry_count) = be_u32(body)?;
+        let mut entries = HashMap::new();
+
+        for _ in 0..entry_count {
+            let (rem, item_id) = if header.version < 1 {
+                be_u16(remain).map(|(rem, x)| (rem, x as u32))?
+            } else {
+                be_u32(remain)?
+            };
+            let (rem, num) = u8(rem)?;
+            let (rem, indexes) = many_m_n(num as usize, num as usize, |i| {
+                let (i, idx) = cond(header.flags & 1 == 1, be_u16)(i)?;
+                match idx {
+                    // essential bit + 15 bits index
+                    Some(idx) => Ok((i, idx & 0x7fff)),
+                    // essential bit + 7 bits index
+                    None => u8(i).map(|(i, x)| (i, (x & 0x7f) as u16)),
+                }
+            })(rem)?;
+            entries.insert(item_id, indexes);
+            remain = rem;
+        }
+
+        Ok((remain, IpmaBox { header, entries }))
+    }
+}
+
+/// Primary item box.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PitmBox {
+    header: FullBoxHeader,
+    pub item_id: u32,
+}
+
+impl ParseBody<PitmBox> for PitmBox {
+    fn parse_body(body: &[u8], header: FullBoxHeader) -> IResult<&[u8], PitmBox> {
+        let (remain, item_id) = if header.version == 0 {
+            be_u16(body).map(|(rem, x)| (rem, x as u32))?
+        } else {
+            be_u32(body)?
+        };
+        Ok((remain, PitmBox { header, item_id }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bbox::travel_while,
+        testkit::{bbox, read_sample},
+    };
+    use test_case::test_case;
+
+    #[test_case("exif.heic", 4032, 3024)]
+    fn iprp_box(path: &str, width: u32, height: u32) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let buf = read_sample(path).unwrap();
+        let (_, meta) = travel_while(&buf, |b| b.box_type() != "meta").unwrap();
+        let meta = meta.unwrap();
+        // meta is a full box
+        let (_, Some(pitm)) = find_box(&meta.body_data()[4..], "pitm").unwrap() else {
+            panic!("pitm not found");
+        };
+        let (_, pitm) = PitmBox::parse_box(pitm.data).unwrap();
+        let (_, Some(iprp)) = find_box(&meta.body_data()[4..], "iprp").unwrap() else {
+            panic!("iprp not found");
+        };
+        let (_, iprp) = IprpBox::parse(iprp.data).unwrap();
+
+        let props = iprp.item_properties(pitm.item_id).collect::<Vec<_>>();
+        assert!(props.contains(&&ItemProperty::Extents(width, height)));
+    }
+
+    #[test]
+    fn auxiliary_type() {
+        let mut ipco = bbox(b"auxC", b"\0\0\0\0urn:mpeg:hevc:2015:auxid:1\0");
+        ipco.extend(bbox(b"irot", &[1]));
+        // version 0, flags 0, 2 items: item 1 -> [2], item 3 -> [1, 2]
+        let ipma = [0, 0, 0, 0, 0, 0, 0, 2, 0, 1, 1, 2, 0, 3, 2, 1, 2];
+        let mut body = bbox(b"ipco", &ipco);
+        body.extend(bbox(b"ipma", &ipma));
+        let (_, iprp) = IprpBox::parse(&bbox(b"iprp", &body)).unwrap();
+
+        let alpha = "urn:mpeg:hevc:2015:auxid:1";
+        assert_eq!(
+            iprp.find_item(|x| *x == ItemProperty::AuxiliaryType(alpha.into())),
+            Some(3)
+        );
+        assert_eq!(iprp.find_item(|x| *x == ItemProperty::Rotation(1)), Some(1));
+        assert_eq!(iprp.find_item(|x| *x == ItemProperty::Mirror(0)), None);
+    }
+}
@@ -0,0 +1,86 @@
+use nom::{
+    combinator::fail,
+    multi::many_m_n,
+    number::complete::{be_u16, be_u32},
+    IResult,
+};
+
+use crate::bbox::FullBoxHeader;
+
+use super::{BoxHolder, ParseBody};
+
+/// Item reference box, describes how the items of a `meta` box refer to each
+/// other, e.g. a thumbnail (`thmb`) or an auxiliary image (`auxl`) of the
+/// primary image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IrefBox {
+    header: FullBoxHeader,
+    /// (reference type, from item id, to item ids)
+    references: Vec<(String, u32, Vec<u32>)>,
+}
+
+impl ParseBody<IrefBox> for IrefBox {
+    fn parse_body(body: &[u8], header: FullBoxHeader) -> IResult<&[u8], IrefBox> {
+        let id = |input| -> IResult<&[u8], u32> {
+            if header.version == 0 {
+                be_u16(input).map(|(rem, x)| (rem, x as u32))
+            } else {
+                be_u32(input)
+            }
+        };
+
+        let mut references = Vec::new();
+        let mut remain = body;
+        while !remain.is_empty() {
+            let (rem, bbox) = BoxHolder::parse(remain)?;
+            if rem.len() >= remain.len() {
+                fail::<_, (), _>(remain)?;
+            }
+            remain = rem;
+
+            let (data, from) = id(bbox.body_data())?;
+            let (data, count) = be_u16(data)?;
+            let (_, to) = many_m_n(count as usize, count as usize, id)(data)?;
+            references.push((bbox.box_type().to_owned(), from, to));
+        }
+
+        Ok((remain, IrefBox { header, references }))
+    }
+}
+
+impl IrefBox {
+    /// Returns the ids of the items which refer to item `to` with the
+    /// reference type `ref_type`, e.g. the thumbnails (`thmb`) of an image.
+    pub fn items_referring_to<'a>(
+        &'a self,
+        ref_type: &'a str,
+        to: u32,
+    ) -> impl Iterator<Item = u32> + 'a {
+        self.references
+            .iter()
+            .filter(move |(t, _, ids)| t == ref_type && ids.contains(&to))
+            .map(|(_, from, _)| *from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bbox::ParseBox;
+
+    #[test]
+    fn iref_box() {
+        let mut data = vec![0, 0, 0, 0, b'i', b'r', b'e', b'f', 0, 0, 0, 0];
+        // thmb: 2 -> [1]
+        data.extend([0, 0, 0, 14, b't', b'h', b'm', b'b', 0, 2, 0, 1, 0, 1]);
+        // dimg: 1 -> [3, 4]
+        data.extend([0, 0, 0, 16, b'd', b'i', b'm', b'g', 0, 1, 0, 2, 0, 3, 0, 4]);
+        data[3] = data.len() as u8;
+
+        let (remain, iref) = IrefBox::parse_box(&data).unwrap();
+        assert!(remain.is_empty());
+        assert_eq!(iref.items_referring_to("thmb", 1).collect::<Vec<_>>(), [2]);
+        assert_eq!(iref.items_referring_to("dimg", 4).collect::<Vec<_>>(), [1]);
+        assert_eq!(iref.items_referring_to("thmb", 2).count(), 0);
+    }
+}
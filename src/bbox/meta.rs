@@ -4,7 +4,13 @@ use nom::{combinator::fail, multi::many0, IResult, Needed};
 
 use crate::bbox::FullBoxHeader;
 
-use super::{iinf::IinfBox, iloc::IlocBox, BoxHolder, ParseBody, ParseBox};
+use super::{
+    iinf::IinfBox,
+    iloc::IlocBox,
+    iprp::{IprpBox, ItemProperty, PitmBox},
+    iref::IrefBox,
+    BoxHolder, ParseBody, ParseBox,
+};
 
 /// Representing the `meta` box in a HEIF/HEIC file.
 #[derive(Clone, PartialEq, Eq)]
@@ -12,6 +18,9 @@ pub struct MetaBox {
     header: FullBoxHeader,
     iinf: Option<IinfBox>,
     iloc: Option<IlocBox>,
+    pitm: Option<PitmBox>,
+    iprp: Option<IprpBox>,
+    iref: Option<IrefBox>,
     // idat: Option<IdatBox<'a>>,
 }
 
@@ -58,6 +67,21 @@ impl ParseBody<MetaBox> for MetaBox {
             .transpose()?
             .map(|x| x.1);
 
+        // pitm & iprp are only used for auxiliary info (e.g. image rotation),
+        // so don't fail the whole meta box if they are broken.
+        let pitm = boxes
+            .get("pitm")
+            .and_then(|pitm| PitmBox::parse_box(pitm.data).ok())
+            .map(|x| x.1);
+        let iprp = boxes
+            .get("iprp")
+            .and_then(|iprp| IprpBox::parse(iprp.data).ok())
+            .map(|x| x.1);
+        let iref = boxes
+            .get("iref")
+            .and_then(|iref| IrefBox::parse_box(iref.data).ok())
+            .map(|x| x.1);
+
         // parse idat box
         // let idat = boxes
         //     .get("idat")
@@ -71,6 +95,9 @@ impl ParseBody<MetaBox> for MetaBox {
                 header,
                 iinf,
                 iloc,
+                pitm,
+                iprp,
+                iref,
                 // idat,
             },
         ))
@@ -78,6 +105,55 @@ impl ParseBody<MetaBox> for MetaBox {
 }
 
 impl MetaBox {
+    /// Returns the transformation (`irot` & `imir`) of the primary item,
+    /// expressed as an Exif Orientation value (1~8).
+    ///
+    /// Returns `None` if there is no transformative property.
+    pub fn primary_orientation(&self) -> Option<u16> {
+        let item_id = self.pitm.as_ref()?.item_id;
+        let iprp = self.iprp.as_ref()?;
+
+        let mut rotation = None;
+        let mut mirror = None;
+        for prop in iprp.item_properties(item_id) {
+            match prop {
+                ItemProperty::Rotation(x) => rotation = Some(*x),
+                ItemProperty::Mirror(x) => mirror = Some(*x),
+                _ => (),
+            }
+        }
+        if rotation.is_none() && mirror.is_none() {
+            return None;
+        }
+
+        // Mirroring about a horizontal axis equals to mirroring about a
+        // vertical axis followed by a 180 degrees rotation.
+        let rotation = rotation.unwrap_or(0) + if mirror == Some(1) { 2 } else { 0 };
+        let orientation = match (mirror.is_some(), rotation % 4) {
+            (false, 0) => 1,
+            (false, 1) => 8,
+            (false, 2) => 3,
+            (false, _) => 6,
+            (true, 0) => 2,
+            (true, 1) => 5,
+            (true, 2) => 4,
+            (true, _) => 7,
+        };
+        Some(orientation)
+    }
+
+    /// Returns the ICC profile of the primary item.
+    pub fn primary_icc_profile(&self) -> Option<&[u8]> {
+        let item_id = self.pitm.as_ref()?.item_id;
+        self.iprp
+            .as_ref()?
+            .item_properties(item_id)
+            .find_map(|prop| match prop {
+                ItemProperty::IccProfile(icc) => Some(icc.as_slice()),
+                _ => None,
+            })
+    }
+
     #[tracing::instrument(skip_all)]
     pub fn exif_data<'a>(&self, input: &'a [u8]) -> IResult<&'a [u8], Option<&'a [u8]>> {
         self.iinf
@@ -112,14 +188,76 @@ impl MetaBox {
 
     #[tracing::instrument(skip_all)]
     pub fn exif_data_offset(&self) -> Option<Range<usize>> {
-        self.iinf
-            .as_ref()
-            .and_then(|iinf| iinf.get_infe("Exif"))
-            .and_then(|exif_infe| {
-                self.iloc
-                    .as_ref()
-                    .and_then(|iloc| iloc.item_offset_len(exif_infe.id))
+        self.item_data_offset(self.exif_item_id()?)
+    }
+
+    /// Returns the id of the Exif item.
+    pub fn exif_item_id(&self) -> Option<u32> {
+        Some(self.iinf.as_ref()?.get_infe("Exif")?.id)
+    }
+
+    /// Returns the file offset range of the XMP item, i.e. the `mime` item
+    /// whose content type is `application/rdf+xml`.
+    #[tracing::instrument(skip_all)]
+    pub fn xmp_data_offset(&self) -> Option<Range<usize>> {
+        self.item_data_offset(self.xmp_item_id()?)
+    }
+
+    /// Returns the id of the (first) XMP item.
+    pub fn xmp_item_id(&self) -> Option<u32> {
+        Some(self.iinf.as_ref()?.get_mime_infe("application/rdf+xml")?.id)
+    }
+
+    /// Returns the file offset ranges of all XMP items, e.g. the XMP of the
+    /// primary image & of an HDR gain map.
+    pub fn xmp_data_offsets(&self) -> Vec<Range<usize>> {
+        let Some(iinf) = self.iinf.as_ref() else {
+            return Vec::new();
+        };
+        iinf.mime_infes("application/rdf+xml")
+            .filter_map(|x| self.item_data_offset(x.id))
+            .collect()
+    }
+
+    /// Returns the id of the auxiliary image item (`auxC`) of `aux_type`.
+    pub fn auxiliary_item(&self, aux_type: &str) -> Option<u32> {
+        self.iprp.as_ref()?.find_item(|prop| match prop {
+            ItemProperty::AuxiliaryType(x) => x == aux_type,
+            _ => false,
+        })
+    }
+
+    /// Returns the ids of the thumbnail items (`thmb` references) of the
+    /// primary item.
+    pub fn primary_thumbnail_items(&self) -> Vec<u32> {
+        let (Some(pitm), Some(iref)) = (self.pitm.as_ref(), self.iref.as_ref()) else {
+            return Vec::new();
+        };
+        iref.items_referring_to("thmb", pitm.item_id).collect()
+    }
+
+    /// Returns the type of item `item_id`, e.g. "hvc1".
+    pub fn item_type(&self, item_id: u32) -> Option<&str> {
+        self.iinf.as_ref()?.item_type(item_id)
+    }
+
+    /// Returns the (width, height) of item `item_id`, i.e. its `ispe`
+    /// property.
+    pub fn item_extents(&self, item_id: u32) -> Option<(u32, u32)> {
+        self.iprp
+            .as_ref()?
+            .item_properties(item_id)
+            .find_map(|prop| match prop {
+                ItemProperty::Extents(w, h) => Some((*w, *h)),
+                _ => None,
             })
+    }
+
+    /// Returns the file offset range of the data of item `item_id`.
+    pub fn item_data_offset(&self, item_id: u32) -> Option<Range<usize>> {
+        self.iloc
+            .as_ref()
+            .and_then(|iloc| iloc.item_offset_len(item_id))
             .and_then(|(construction_method, offset, length)| {
                 let start = offset as usize;
                 let end = (offset + length) as usize;
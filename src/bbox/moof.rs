@@ -0,0 +1,276 @@
+use chrono::{DateTime, Utc};
+use nom::{
+    combinator::cond,
+    number::complete::{be_u32, be_u64},
+    sequence::tuple,
+    IResult,
+};
+
+use super::{
+    be_u32_or_u64, find_box, find_track, find_video_track, stbl::MdhdBox, tkhd::TkhdBox, BoxHolder,
+    FullBoxHeader, ParseBody, ParseBox,
+};
+
+/// A track of a fragmented movie, whose samples are described by `moof`
+/// boxes rather than by `moov/trak/mdia/minf/stbl`.
+///
+/// Refer to: ISO/IEC 14496-12, 8.8 Movie Fragments
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FragmentedTrack {
+    pub track_id: u32,
+    /// Time scale of the track, from `mdhd`
+    pub time_scale: u32,
+    /// Default sample duration, from `moov/mvex/trex`
+    default_sample_duration: u32,
+}
+
+impl FragmentedTrack {
+    /// Finds the video track (or the audio track for audio only files) of a
+    /// fragmented movie. Returns `None` if the movie isn't fragmented, i.e.
+    /// there is no `mvex` box in the moov body.
+    pub fn parse(moov_body: &[u8]) -> crate::Result<Option<FragmentedTrack>> {
+        let (_, Some(mvex)) = find_box(moov_body, "mvex")? else {
+            return Ok(None);
+        };
+        let trak = match find_video_track(moov_body)? {
+            Some(trak) => trak,
+            None => match find_track(moov_body, b"soun")? {
+                Some(trak) => trak,
+                None => return Ok(None),
+            },
+        };
+
+        let (_, Some(tkhd)) = find_box(trak.body_data(), "tkhd")? else {
+            return Err("tkhd not found".into());
+        };
+        let (_, tkhd) = TkhdBox::parse_box(tkhd.data).map_err(|_| "parse tkhd failed")?;
+        let (_, Some(mdhd)) = find_box(trak.body_data(), "mdia/mdhd")? else {
+            return Err("mdhd not found".into());
+        };
+        let (_, mdhd) = MdhdBox::parse_box(mdhd.data).map_err(|_| "parse mdhd failed")?;
+
+        let mut default_sample_duration = 0;
+        for bbox in Children(mvex.body_data()).filter(|b| b.box_type() == "trex") {
+            let (_, trex) = TrexBox::parse_box(bbox.data).map_err(|_| "parse trex failed")?;
+            if trex.track_id == tkhd.track_id() {
+                default_sample_duration = trex.default_sample_duration;
+                break;
+            }
+        }
+
+        Ok(Some(FragmentedTrack {
+            track_id: tkhd.track_id(),
+            time_scale: mdhd.time_scale,
+            default_sample_duration,
+        }))
+    }
+
+    /// Parses the track fragments (`traf`) of this track in a `moof` box.
+    ///
+    /// Returns the base media decode time (from `tfdt`, if present) & the
+    /// total duration of the samples, both in time scale units. Returns
+    /// `None` if the fragment doesn't contain this track.
+    pub fn parse_moof(&self, moof_body: &[u8]) -> crate::Result<Option<(Option<u64>, u64)>> {
+        let mut found = None;
+        for traf in Children(moof_body).filter(|b| b.box_type() == "traf") {
+            let (_, Some(tfhd)) = find_box(traf.body_data(), "tfhd")? else {
+                continue;
+            };
+            let (_, tfhd) = TfhdBox::parse_box(tfhd.data).map_err(|_| "parse tfhd failed")?;
+            if tfhd.track_id != self.track_id {
+                continue;
+            }
+            let default_duration = tfhd
+                .default_sample_duration
+                .unwrap_or(self.default_sample_duration);
+
+            let (mut start, mut duration) = found.unwrap_or((None, 0));
+            for bbox in Children(traf.body_data()) {
+                match bbox.box_type() {
+                    "tfdt" => {
+                        let (_, time) = parse_tfdt(bbox.data).map_err(|_| "parse tfdt failed")?;
+                        start = start.or(Some(time));
+                    }
+                    "trun" => {
+                        let (_, n) = parse_trun_duration(bbox.data, default_duration)
+                            .map_err(|_| "parse trun failed")?;
+                        duration += n;
+                    }
+                    _ => (),
+                }
+            }
+            found = Some((start, duration));
+        }
+        Ok(found)
+    }
+}
+
+/// Returns `fragment_duration` in `moov/mvex/mehd`, which is the duration of
+/// the whole fragmented movie, in the time scale of `mvhd`.
+pub fn parse_fragment_duration(moov_body: &[u8]) -> crate::Result<Option<u64>> {
+    let (_, Some(mehd)) = find_box(moov_body, "mvex/mehd")? else {
+        return Ok(None);
+    };
+    let res: IResult<_, _> = FullBoxHeader::parse(mehd.data)
+        .and_then(|(remain, header)| be_u32_or_u64(header.version)(remain));
+    let (_, duration) = res.map_err(|_| "parse mehd failed")?;
+    Ok(Some(duration).filter(|x| *x > 0))
+}
+
+/// Parses a producer reference time box (`prft`), returns the wall-clock
+/// time (NTP timestamp) when the fragment following it was produced.
+pub fn parse_prft(input: &[u8]) -> crate::Result<Option<DateTime<Utc>>> {
+    let res: IResult<_, _> = tuple((FullBoxHeader::parse, be_u32, be_u64))(input);
+    let (_, (_, _, ntp)) = res.map_err(|_| "parse prft failed")?;
+
+    // seconds since midnight, January 1, 1900
+    const NTP_UNIX_OFFSET: i64 = 2_208_988_800;
+    let secs = (ntp >> 32) as i64 - NTP_UNIX_OFFSET;
+    let nanos = ((ntp & 0xffff_ffff) * 1_000_000_000) >> 32;
+    Ok(DateTime::from_timestamp(secs, nanos as u32).filter(|_| secs > 0))
+}
+
+/// Represents a track extends box (atom-path: `moov/mvex/trex`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TrexBox {
+    header: FullBoxHeader,
+    track_id: u32,
+    default_sample_duration: u32,
+}
+
+impl ParseBody<TrexBox> for TrexBox {
+    fn parse_body(body: &[u8], header: FullBoxHeader) -> IResult<&[u8], TrexBox> {
+        // skip default_sample_description_index
+        let (remain, (track_id, _, default_sample_duration)) =
+            tuple((be_u32, be_u32, be_u32))(body)?;
+        Ok((
+            remain,
+            TrexBox {
+                header,
+                track_id,
+                default_sample_duration,
+            },
+        ))
+    }
+}
+
+/// Represents a track fragment header box (atom-path: `moof/traf/tfhd`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TfhdBox {
+    header: FullBoxHeader,
+    track_id: u32,
+    default_sample_duration: Option<u32>,
+}
+
+impl ParseBody<TfhdBox> for TfhdBox {
+    fn parse_body(body: &[u8], header: FullBoxHeader) -> IResult<&[u8], TfhdBox> {
+        let flags = header.flags;
+        let (remain, (track_id, _, _, default_sample_duration)) = tuple((
+            be_u32,
+            cond(flags & 0x01 != 0, be_u64), // base_data_offset
+            cond(flags & 0x02 != 0, be_u32), // sample_description_index
+            cond(flags & 0x08 != 0, be_u32),
+        ))(body)?;
+        Ok((
+            remain,
+            TfhdBox {
+                header,
+                track_id,
+                default_sample_duration,
+            },
+        ))
+    }
+}
+
+/// Returns `baseMediaDecodeTime` in `moof/traf/tfdt`.
+fn parse_tfdt(input: &[u8]) -> IResult<&[u8], u64> {
+    let (remain, header) = FullBoxHeader::parse(input)?;
+    be_u32_or_u64(header.version)(remain)
+}
+
+/// Returns the total duration of the samples in `moof/traf/trun`, samples
+/// without an explicit duration last `default_duration`.
+fn parse_trun_duration(input: &[u8], default_duration: u32) -> IResult<&[u8], u64> {
+    let (remain, header) = FullBoxHeader::parse(input)?;
+    let flags = header.flags;
+    let (remain, (sample_count, _, _)) = tuple((
+        be_u32,
+        cond(flags & 0x01 != 0, be_u32), // data_offset
+        cond(flags & 0x04 != 0, be_u32), // first_sample_flags
+    ))(remain)?;
+
+    if flags & 0x100 == 0 {
+        return Ok((remain, sample_count as u64 * default_duration as u64));
+    }
+
+    // duration, size, flags & composition time offset
+    let entry_size = [0x100, 0x200, 0x400, 0x800]
+        .iter()
+        .filter(|x| flags & *x != 0)
+        .count()
+        * 4;
+    let duration = remain
+        .chunks_exact(entry_size)
+        .take(sample_count as usize)
+        .map(|x| u32::from_be_bytes([x[0], x[1], x[2], x[3]]) as u64)
+        .sum();
+    Ok((remain, duration))
+}
+
+/// Iterates over the child boxes in a box body, stops at the first invalid
+/// box.
+struct Children<'a>(&'a [u8]);
+
+impl<'a> Iterator for Children<'a> {
+    type Item = BoxHolder<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (remain, bbox) = BoxHolder::parse(self.0).ok()?;
+        self.0 = remain;
+        Some(bbox)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    fn full_box(box_type: &str, version: u8, flags: u32, body: &[u8]) -> Vec<u8> {
+        let mut data = ((body.len() + 12) as u32).to_be_bytes().to_vec();
+        data.extend(box_type.as_bytes());
+        data.extend((flags | (version as u32) << 24).to_be_bytes());
+        data.extend(body);
+        data
+    }
+
+    fn be(values: &[u32]) -> Vec<u8> {
+        values.iter().flat_map(|x| x.to_be_bytes()).collect()
+    }
+
+    #[test_case(0x000, &[3], 3000; "default duration")]
+    #[test_case(0x001, &[3, 100], 3000; "data offset")]
+    #[test_case(0x305, &[3, 100, 0, 1000, 10, 1500, 20, 2000, 30], 4500; "sample durations")]
+    #[test_case(0xf00, &[2, 1000, 10, 0, 0, 1001, 20, 0, 0], 2001; "all sample fields")]
+    fn trun(flags: u32, body: &[u32], duration: u64) {
+        let data = full_box("trun", 0, flags, &be(body));
+        assert_eq!(parse_trun_duration(&data, 1000).unwrap().1, duration);
+    }
+
+    #[test]
+    fn prft() {
+        // 2023-10-01T08:30:15.5Z
+        let secs = 1696149015_u64 + 2_208_988_800;
+        let ntp = secs << 32 | 0x8000_0000;
+        let data = full_box(
+            "prft",
+            0,
+            0,
+            &[&be(&[1])[..], &ntp.to_be_bytes(), &be(&[0])].concat(),
+        );
+        assert_eq!(
+            parse_prft(&data).unwrap().unwrap().to_rfc3339(),
+            "2023-10-01T08:30:15.500+00:00"
+        );
+    }
+}
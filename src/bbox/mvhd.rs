@@ -1,11 +1,12 @@
 use chrono::{DateTime, Duration, FixedOffset, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
 use nom::{bytes::complete::take, number::complete::be_u32, sequence::tuple};
 
-use super::{FullBoxHeader, ParseBody};
+use super::{be_u32_or_u64, FullBoxHeader, ParseBody};
 
 /// Represents a [movie header atom][1].
 ///
-/// mvhd is a fullbox which contains version & flags.
+/// mvhd is a fullbox which contains version & flags. Times & duration are
+/// 64-bit in version 1, which is used by long (e.g. >4 GB) recordings.
 ///
 /// atom-path: moov/mvhd
 ///
@@ -15,10 +16,10 @@ pub struct MvhdBox {
     header: FullBoxHeader,
 
     /// seconds since midnight, January 1, 1904
-    creation_time: u32,
+    creation_time: u64,
 
     /// seconds since midnight, January 1, 1904
-    modification_time: u32,
+    modification_time: u64,
 
     /// The number of time units that pass per second in its time coordinate
     /// system.
@@ -29,42 +30,54 @@ pub struct MvhdBox {
     /// # convert to seconds
     ///
     /// seconds = duration / time_scale
-    duration: u32,
+    duration: u64,
     // omit 76 bytes...
     next_track_id: u32,
 }
 
 impl MvhdBox {
     pub fn duration_ms(&self) -> u64 {
-        ((self.duration as f64) / (self.time_scale as f64) * 1000_f64) as u64
+        self.to_ms(self.duration)
     }
 
-    fn creation_time_naive(&self) -> NaiveDateTime {
-        NaiveDate::from_ymd_opt(1904, 1, 1)
-            .unwrap()
-            .and_hms_opt(0, 0, 0)
-            .unwrap()
-            + Duration::seconds(self.creation_time as i64)
+    /// Converts a duration in the movie's time scale units to milliseconds,
+    /// e.g.: the fragment duration in `mvex/mehd`.
+    pub fn to_ms(&self, duration: u64) -> u64 {
+        ((duration as f64) / (self.time_scale as f64) * 1000_f64) as u64
     }
 
-    pub fn creation_time(&self) -> DateTime<FixedOffset> {
-        self.creation_time_utc().fixed_offset()
+    /// Returns `None` if the creation time is out of range, or isn't set
+    /// (0), e.g.: in fragmented movies of live streams.
+    fn creation_time_naive(&self) -> Option<NaiveDateTime> {
+        if self.creation_time == 0 {
+            return None;
+        }
+        let secs = Duration::try_seconds(self.creation_time.try_into().ok()?)?;
+        NaiveDate::from_ymd_opt(1904, 1, 1)?
+            .and_hms_opt(0, 0, 0)?
+            .checked_add_signed(secs)
+    }
+
+    pub fn creation_time(&self) -> Option<DateTime<FixedOffset>> {
+        self.creation_time_utc().map(|x| x.fixed_offset())
     }
 
     #[allow(dead_code)]
-    pub fn creation_time_local(&self) -> DateTime<Local> {
-        Local.from_utc_datetime(&self.creation_time_naive())
+    pub fn creation_time_local(&self) -> Option<DateTime<Local>> {
+        self.creation_time_naive()
+            .map(|x| Local.from_utc_datetime(&x))
     }
 
-    pub fn creation_time_utc(&self) -> DateTime<Utc> {
-        self.creation_time_naive().and_utc()
+    pub fn creation_time_utc(&self) -> Option<DateTime<Utc>> {
+        self.creation_time_naive().map(|x| x.and_utc())
     }
 }
 
 impl ParseBody<MvhdBox> for MvhdBox {
     fn parse_body(body: &[u8], header: FullBoxHeader) -> nom::IResult<&[u8], MvhdBox> {
+        let time = be_u32_or_u64(header.version);
         let (remain, (creation_time, modification_time, time_scale, duration, _, next_track_id)) =
-            tuple((be_u32, be_u32, be_u32, be_u32, take(76usize), be_u32))(body)?;
+            tuple((&time, &time, be_u32, &time, take(76usize), be_u32))(body)?;
 
         Ok((
             remain,
@@ -118,8 +131,8 @@ mod tests {
 
         // time is represented in seconds since midnight, January 1, 1904,
         // preferably using coordinated universal time (UTC).
-        let created = mvhd.creation_time_utc();
-        assert_eq!(created, mvhd.creation_time());
+        let created = mvhd.creation_time_utc().unwrap();
+        assert_eq!(created, mvhd.creation_time().unwrap());
         assert_eq!(
             created.to_rfc3339_opts(chrono::SecondsFormat::Micros, true),
             time_utc
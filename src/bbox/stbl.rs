@@ -0,0 +1,328 @@
+use nom::{
+    combinator::fail,
+    number::complete::{be_u32, be_u64},
+    sequence::tuple,
+    IResult,
+};
+
+use super::{be_u32_or_u64, find_box, BoxHolder, FullBoxHeader, ParseBody, ParseBox};
+
+/// Max number of samples to be parsed in a track, to avoid exhausting memory
+/// on corrupted files.
+const MAX_SAMPLES: usize = 1 << 22;
+
+/// Location & timing of a sample in a track, see [`TrackSamples`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SampleInfo {
+    /// Absolute offset of the sample data in the file.
+    pub offset: u64,
+    pub size: u32,
+
+    /// Decoding time in the track's time scale units.
+    pub time: u64,
+    pub duration: u32,
+}
+
+/// Samples of a track, parsed from the sample table (atom-path:
+/// `trak/mdia/minf/stbl`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrackSamples {
+    /// Format of the first sample description in `stsd`, e.g.: "gpmd",
+    /// "camm", "tx3g".
+    pub format: String,
+
+    /// The number of time units that pass per second, from `mdhd`.
+    pub time_scale: u32,
+
+    pub samples: Vec<SampleInfo>,
+}
+
+impl TrackSamples {
+    /// Parses sample tables of a track, `trak_body` is the body data of a
+    /// `trak` box.
+    pub fn parse(trak_body: &[u8]) -> crate::Result<TrackSamples> {
+        let (_, Some(mdia)) = find_box(trak_body, "mdia")? else {
+            return Err("mdia not found".into());
+        };
+        let (_, Some(mdhd)) = find_box(mdia.body_data(), "mdhd")? else {
+            return Err("mdhd not found".into());
+        };
+        let (_, mdhd) = MdhdBox::parse_box(mdhd.data).map_err(|_| "parse mdhd failed")?;
+        let (_, Some(stbl)) = find_box(mdia.body_data(), "minf/stbl")? else {
+            return Err("stbl not found".into());
+        };
+
+        let mut format = None;
+        let mut times = Vec::new();
+        let mut chunks = Vec::new();
+        let mut chunk_offsets = Vec::new();
+        let mut sizes = SampleSizes::default();
+
+        let mut remain = stbl.body_data();
+        while !remain.is_empty() {
+            let (rem, bbox) = BoxHolder::parse(remain)?;
+            let res = match bbox.box_type() {
+                "stsd" => parse_stsd(bbox.data).map(|(_, x)| format = Some(x)),
+                "stts" => parse_stts(bbox.data).map(|(_, x)| times = x),
+                "stsc" => parse_table(bbox.data, 12, |x| {
+                    let (x, first_chunk) = be_u32(x)?;
+                    let (x, samples_per_chunk) = be_u32(x)?;
+                    let (x, _) = be_u32(x)?;
+                    Ok((x, (first_chunk, samples_per_chunk)))
+                })
+                .map(|(_, x)| chunks = x),
+                "stco" => parse_table(bbox.data, 4, |x| be_u32(x).map(|(x, o)| (x, o as u64)))
+                    .map(|(_, x)| chunk_offsets = x),
+                "co64" => parse_table(bbox.data, 8, be_u64).map(|(_, x)| chunk_offsets = x),
+                "stsz" => parse_stsz(bbox.data).map(|(_, x)| sizes = x),
+                _ => Ok(()),
+            };
+            res.map_err(|_| format!("parse {} failed", bbox.box_type()))?;
+            remain = rem;
+        }
+
+        let Some(format) = format else {
+            return Err("stsd not found".into());
+        };
+
+        let samples = build_samples(&times, &chunks, &chunk_offsets, &sizes);
+        Ok(TrackSamples {
+            format,
+            time_scale: mdhd.time_scale,
+            samples,
+        })
+    }
+}
+
+/// Returns the average frame (sample) rate of a track, computed from the time
+/// scale in `mdhd` and the sample durations in `stts`. `trak_body` is the body
+/// data of a `trak` box.
+///
+/// Returns `None` if the track contains no samples.
+pub fn parse_frame_rate(trak_body: &[u8]) -> crate::Result<Option<f64>> {
+    let (_, Some(mdia)) = find_box(trak_body, "mdia")? else {
+        return Ok(None);
+    };
+    let (_, Some(mdhd)) = find_box(mdia.body_data(), "mdhd")? else {
+        return Ok(None);
+    };
+    let (_, mdhd) = MdhdBox::parse_box(mdhd.data).map_err(|_| "parse mdhd failed")?;
+    let (_, Some(stts)) = find_box(mdia.body_data(), "minf/stbl/stts")? else {
+        return Ok(None);
+    };
+    let (_, times) = parse_stts(stts.data).map_err(|_| "parse stts failed")?;
+
+    let (count, duration) = times
+        .iter()
+        .fold((0_u64, 0_u64), |(count, duration), (n, delta)| {
+            (
+                count + *n as u64,
+                duration.saturating_add(*n as u64 * *delta as u64),
+            )
+        });
+    if count == 0 || duration == 0 || mdhd.time_scale == 0 {
+        return Ok(None);
+    }
+
+    let rate = count as f64 * mdhd.time_scale as f64 / duration as f64;
+    // Keep 3 decimal places, e.g.: 29.97
+    Ok(Some((rate * 1000.0).round() / 1000.0))
+}
+
+/// Returns the number of samples (e.g. frames) of a track, from `stsz`.
+/// `trak_body` is the body data of a `trak` box.
+pub fn parse_sample_count(trak_body: &[u8]) -> crate::Result<Option<u32>> {
+    let (_, Some(stsz)) = find_box(trak_body, "mdia/minf/stbl/stsz")? else {
+        return Ok(None);
+    };
+    let res: IResult<_, _> = tuple((FullBoxHeader::parse, be_u32, be_u32))(stsz.data);
+    let (_, (_, _, count)) = res.map_err(|_| "parse stsz failed")?;
+    Ok(Some(count))
+}
+
+/// Parses `stts` into a table of `(sample_count, sample_delta)`.
+fn parse_stts(input: &[u8]) -> IResult<&[u8], Vec<(u32, u32)>> {
+    parse_table(input, 8, |x| {
+        let (x, count) = be_u32(x)?;
+        let (x, delta) = be_u32(x)?;
+        Ok((x, (count, delta)))
+    })
+}
+
+fn build_samples(
+    times: &[(u32, u32)],
+    chunks: &[(u32, u32)],
+    chunk_offsets: &[u64],
+    sizes: &SampleSizes,
+) -> Vec<SampleInfo> {
+    let mut sizes = sizes.iter();
+    let mut times = times
+        .iter()
+        .flat_map(|(count, delta)| std::iter::repeat(*delta).take(*count as usize))
+        .scan(0_u64, |time, delta| {
+            let t = *time;
+            *time = time.saturating_add(delta as u64);
+            Some((t, delta))
+        });
+
+    let mut samples = Vec::new();
+    let mut chunk_entry = 0;
+    'outer: for (idx, chunk_offset) in chunk_offsets.iter().enumerate() {
+        // chunk index starts from 1 in stsc
+        let chunk_idx = idx as u64 + 1;
+        while chunks
+            .get(chunk_entry + 1)
+            .is_some_and(|(first, _)| *first as u64 <= chunk_idx)
+        {
+            chunk_entry += 1;
+        }
+        let Some((_, samples_per_chunk)) = chunks.get(chunk_entry) else {
+            break;
+        };
+
+        let mut offset = *chunk_offset;
+        for _ in 0..*samples_per_chunk {
+            let Some(size) = sizes.next() else {
+                break 'outer;
+            };
+            if samples.len() >= MAX_SAMPLES {
+                tracing::warn!(max = MAX_SAMPLES, "too many samples, ignore the rest");
+                break 'outer;
+            }
+
+            let (time, duration) = times.next().unwrap_or_default();
+            samples.push(SampleInfo {
+                offset,
+                size,
+                time,
+                duration,
+            });
+            offset = offset.saturating_add(size as u64);
+        }
+    }
+
+    samples
+}
+
+#[derive(Debug, Default)]
+enum SampleSizes {
+    Fixed(u32, u32),
+    Table(Vec<u32>),
+    #[default]
+    None,
+}
+
+impl SampleSizes {
+    fn iter(&self) -> Box<dyn Iterator<Item = u32> + '_> {
+        match self {
+            SampleSizes::Fixed(size, count) => {
+                Box::new(std::iter::repeat(*size).take(*count as usize))
+            }
+            SampleSizes::Table(sizes) => Box::new(sizes.iter().copied()),
+            SampleSizes::None => Box::new(std::iter::empty()),
+        }
+    }
+}
+
+fn parse_stsd(input: &[u8]) -> IResult<&[u8], String> {
+    let (remain, header) = FullBoxHeader::parse(input)?;
+    if header.box_type != "stsd" {
+        return fail(input);
+    }
+    let (remain, _entry_count) = be_u32(remain)?;
+    // sample description entries have the same layout as a box header
+    let (_, entry) = BoxHolder::parse(remain)?;
+    Ok((remain, entry.box_type().to_owned()))
+}
+
+fn parse_stsz(input: &[u8]) -> IResult<&[u8], SampleSizes> {
+    let (remain, _) = FullBoxHeader::parse(input)?;
+    let (remain, sample_size) = be_u32(remain)?;
+    if sample_size != 0 {
+        let (remain, count) = be_u32(remain)?;
+        return Ok((remain, SampleSizes::Fixed(sample_size, count)));
+    }
+    let (remain, sizes) = parse_entries(remain, 4, be_u32)?;
+    Ok((remain, SampleSizes::Table(sizes)))
+}
+
+/// Parses a full box which contains an entry count followed by a table of
+/// fixed size entries.
+fn parse_table<'a, T>(
+    input: &'a [u8],
+    entry_size: usize,
+    parse_entry: impl FnMut(&'a [u8]) -> IResult<&'a [u8], T>,
+) -> IResult<&'a [u8], Vec<T>> {
+    let (remain, _) = FullBoxHeader::parse(input)?;
+    parse_entries(remain, entry_size, parse_entry)
+}
+
+/// Parses an entry count followed by `count` entries.
+fn parse_entries<'a, T>(
+    input: &'a [u8],
+    entry_size: usize,
+    mut parse_entry: impl FnMut(&'a [u8]) -> IResult<&'a [u8], T>,
+) -> IResult<&'a [u8], Vec<T>> {
+    let (mut remain, count) = be_u32(input)?;
+
+    // Check the size first, to avoid allocating too much memory for
+    // corrupted files.
+    if (count as usize).saturating_mul(entry_size) > remain.len() {
+        return fail(remain);
+    }
+
+    let mut entries = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let (rem, entry) = parse_entry(remain)?;
+        entries.push(entry);
+        remain = rem;
+    }
+    Ok((remain, entries))
+}
+
+/// Represents a media header atom (atom-path: `trak/mdia/mdhd`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct MdhdBox {
+    header: FullBoxHeader,
+    pub(super) time_scale: u32,
+}
+
+impl ParseBody<MdhdBox> for MdhdBox {
+    fn parse_body(body: &[u8], header: FullBoxHeader) -> IResult<&[u8], MdhdBox> {
+        // skip creation_time & modification_time
+        let time = be_u32_or_u64(header.version);
+        let (remain, (_, _, time_scale)) = tuple((&time, &time, be_u32))(body)?;
+        Ok((remain, MdhdBox { header, time_scale }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bbox::travel_while, testkit::read_sample};
+    use test_case::test_case;
+
+    #[test_case("meta.mp4")]
+    #[test_case("meta.mov")]
+    fn track_samples(path: &str) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let buf = read_sample(path).unwrap();
+        let (_, moov) = travel_while(&buf, |b| b.box_type() != "moov").unwrap();
+        let moov = moov.unwrap();
+        let (_, trak) = travel_while(moov.body_data(), |b| b.box_type() != "trak").unwrap();
+        let trak = trak.unwrap();
+
+        let track = TrackSamples::parse(trak.body_data()).unwrap();
+        assert!(track.time_scale > 0);
+        assert!(!track.samples.is_empty());
+
+        // Samples are laid out in the file in order, and data of all samples
+        // should be inside the file.
+        for x in track.samples.windows(2) {
+            assert!(x[0].time + x[0].duration as u64 == x[1].time);
+        }
+        let last = track.samples.last().unwrap();
+        assert!(last.offset + last.size as u64 <= buf.len() as u64);
+    }
+}
@@ -1,14 +1,16 @@
 use nom::{
     bytes::complete::take,
+    combinator::map_res,
     number::complete::{be_u16, be_u32, be_u64},
     sequence::tuple,
 };
 
-use super::{find_box, travel_while, BoxHolder, FullBoxHeader, ParseBody, ParseBox};
+use super::{be_u32_or_u64, find_box, travel_while, BoxHolder, FullBoxHeader, ParseBody, ParseBox};
 
 /// Represents a [movie header atom][1].
 ///
-/// tkhd is a fullbox which contains version & flags.
+/// tkhd is a fullbox which contains version & flags. Times & duration are
+/// 64-bit in version 1.
 ///
 /// atom-path: moov/trak/tkhd
 ///
@@ -18,27 +20,27 @@ pub struct TkhdBox {
     header: FullBoxHeader,
 
     /// seconds since midnight, January 1, 1904
-    creation_time: u32,
+    creation_time: u64,
 
     /// seconds since midnight, January 1, 1904
-    modification_time: u32,
+    modification_time: u64,
 
     track_id: u32,
     // reserved: u32,
-    duration: u32,
+    duration: u64,
     // reserved2: u64,
     layer: u16,
     alt_group: u16,
     volume: u16,
     // reserved3: u16,
-
-    // matrix: [u8; 36],
+    matrix: [u32; 9],
     pub width: u32,
     pub height: u32,
 }
 
 impl ParseBody<TkhdBox> for TkhdBox {
     fn parse_body(body: &[u8], header: FullBoxHeader) -> nom::IResult<&[u8], TkhdBox> {
+        let time = be_u32_or_u64(header.version);
         let (
             remain,
             (
@@ -52,24 +54,29 @@ impl ParseBody<TkhdBox> for TkhdBox {
                 alt_group,
                 volume,
                 _,
-                _,
+                matrix,
                 width,
                 _,
                 height,
                 _,
             ),
         ) = tuple((
+            &time,
+            &time,
             be_u32,
             be_u32,
-            be_u32,
-            be_u32,
-            be_u32,
+            &time,
             be_u64,
             be_u16,
             be_u16,
             be_u16,
             be_u16,
-            take(36usize),
+            map_res(take(36usize), |x: &[u8]| {
+                x.chunks_exact(4)
+                    .map(|c| u32::from_be_bytes([c[0], c[1], c[2], c[3]]))
+                    .collect::<Vec<_>>()
+                    .try_into()
+            }),
             be_u16,
             be_u16,
             be_u16,
@@ -87,6 +94,7 @@ impl ParseBody<TkhdBox> for TkhdBox {
                 layer,
                 alt_group,
                 volume,
+                matrix,
                 width: width as u32,
                 height: height as u32,
             },
@@ -94,6 +102,27 @@ impl ParseBody<TkhdBox> for TkhdBox {
     }
 }
 
+impl TkhdBox {
+    pub fn track_id(&self) -> u32 {
+        self.track_id
+    }
+
+    /// Returns the clockwise rotation in degrees (0, 90, 180 or 270) described
+    /// by the transformation matrix.
+    ///
+    /// Only pure rotations are recognized, other matrices are treated as 0.
+    pub fn rotation(&self) -> u32 {
+        // matrix values are 16.16 fixed-point numbers: a b u / c d v / x y w
+        const ONE: i32 = 0x10000;
+        match (self.matrix[0] as i32, self.matrix[1] as i32) {
+            (0, ONE) => 90,
+            (x, 0) if x == -ONE => 180,
+            (0, x) if x == -ONE => 270,
+            _ => 0,
+        }
+    }
+}
+
 /// Try to find a video track's tkhd in moov body. atom-path: "moov/trak/tkhd".
 pub fn parse_video_tkhd_in_moov(input: &[u8]) -> crate::Result<Option<TkhdBox>> {
     let Some(bbox) = find_video_track(input)? else {
@@ -106,13 +135,20 @@ pub fn parse_video_tkhd_in_moov(input: &[u8]) -> crate::Result<Option<TkhdBox>>
     Ok(Some(tkhd))
 }
 
-fn find_video_track(input: &[u8]) -> crate::Result<Option<BoxHolder>> {
+/// Finds the first video (`vide`) track in the moov body.
+pub fn find_video_track(input: &[u8]) -> crate::Result<Option<BoxHolder>> {
+    find_track(input, b"vide")
+}
+
+/// Finds the first track with the `handler` type in the moov body, e.g.:
+/// `vide` for videos, `pict` for image sequences.
+pub fn find_track<'a>(input: &'a [u8], handler: &[u8; 4]) -> crate::Result<Option<BoxHolder<'a>>> {
     let (_, bbox) = travel_while(input, |b| {
-        // find video track
+        // find the track
         if b.box_type() != "trak" {
             true
         } else {
-            // got a 'trak', to check if it's a 'vide' trak
+            // got a 'trak', to check its handler type
 
             let found = find_box(b.body_data(), "mdia/hdlr");
             let Ok(bbox) = found else {
@@ -123,11 +159,11 @@ fn find_video_track(input: &[u8]) -> crate::Result<Option<BoxHolder>> {
             };
 
             // component subtype
-            if hdlr.body_data().len() < 4 {
+            if hdlr.body_data().len() < 12 {
                 return true;
             }
             let subtype = &hdlr.body_data()[8..12]; // Safe-slice
-            if subtype == b"vide" {
+            if subtype == handler {
                 // found it!
                 false
             } else {
@@ -135,7 +171,7 @@ fn find_video_track(input: &[u8]) -> crate::Result<Option<BoxHolder>> {
             }
         }
     })
-    .map_err(|e| format!("find vide trak failed: {e:?}"))?;
+    .map_err(|e| format!("find trak failed: {e:?}"))?;
 
     Ok(bbox)
 }
@@ -147,9 +183,9 @@ mod tests {
     use super::*;
     use test_case::test_case;
 
-    #[test_case("meta.mov", 720, 1280)]
-    #[test_case("meta.mp4", 1920, 1080)]
-    fn tkhd_box(path: &str, width: u32, height: u32) {
+    #[test_case("meta.mov", 720, 1280, 0)]
+    #[test_case("meta.mp4", 1920, 1080, 90)]
+    fn tkhd_box(path: &str, width: u32, height: u32, rotation: u32) {
         let _ = tracing_subscriber::fmt().with_test_writer().try_init();
 
         let buf = read_sample(path).unwrap();
@@ -160,5 +196,6 @@ mod tests {
 
         assert_eq!(tkhd.width, width);
         assert_eq!(tkhd.height, height);
+        assert_eq!(tkhd.rotation(), rotation);
     }
 }
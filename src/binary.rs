@@ -0,0 +1,502 @@
+//! A compact, versioned binary encoding of parsed metadata, see
+//! [`Exif::to_bytes`](crate::Exif::to_bytes) &
+//! [`TrackInfo::to_bytes`](crate::TrackInfo::to_bytes).
+//!
+//! Layout: `magic ("NXMD") | version (u8) | kind (u8) | body`. Integers in
+//! the body are LEB128 varints (zigzag encoded if signed), floats are stored
+//! as their little-endian bits, and sequences are prefixed with their
+//! lengths.
+
+use std::sync::Arc;
+
+use chrono::{DateTime, FixedOffset};
+
+use crate::{
+    values::Rational, video::Chapter, xmp::XmpPackets, EntryValue, GPSInfo, LatLng, URational,
+};
+
+const MAGIC: &[u8; 4] = b"NXMD";
+
+/// Bump it when the layout of the body changes, data of other versions are
+/// rejected.
+const VERSION: u8 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub(crate) enum Kind {
+    Exif = b'E',
+    TrackInfo = b'T',
+}
+
+pub(crate) struct Writer(Vec<u8>);
+
+impl Writer {
+    pub fn new(kind: Kind) -> Self {
+        let mut buf = MAGIC.to_vec();
+        buf.extend([VERSION, kind as u8]);
+        Self(buf)
+    }
+
+    pub fn put<T: Bin>(&mut self, v: &T) -> &mut Self {
+        v.put(self);
+        self
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.0
+    }
+
+    fn byte(&mut self, b: u8) {
+        self.0.push(b);
+    }
+
+    fn varint(&mut self, mut v: u64) {
+        while v >= 0x80 {
+            self.0.push(v as u8 | 0x80);
+            v >>= 7;
+        }
+        self.0.push(v as u8);
+    }
+
+    fn bytes(&mut self, b: &[u8]) {
+        self.varint(b.len() as u64);
+        self.0.extend_from_slice(b);
+    }
+}
+
+pub(crate) struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8], kind: Kind) -> crate::Result<Self> {
+        let Some((header, body)) = data.split_at_checked(MAGIC.len() + 2) else {
+            return Err("binary metadata is too short".into());
+        };
+        if &header[..4] != MAGIC {
+            return Err("not binary metadata".into());
+        }
+        if header[4] != VERSION {
+            return Err(format!("unsupported binary metadata version: {}", header[4]).into());
+        }
+        if header[5] != kind as u8 {
+            return Err(format!("binary metadata is not {kind:?}").into());
+        }
+        Ok(Self(body))
+    }
+
+    pub fn get<T: Bin>(&mut self) -> crate::Result<T> {
+        T::get(self)
+    }
+
+    /// Makes sure all the data has been consumed.
+    pub fn finish(self) -> crate::Result<()> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err("trailing bytes in binary metadata".into())
+        }
+    }
+
+    fn byte(&mut self) -> crate::Result<u8> {
+        let (b, rest) = self.0.split_first().ok_or(truncated())?;
+        self.0 = rest;
+        Ok(*b)
+    }
+
+    fn varint(&mut self) -> crate::Result<u64> {
+        let mut v = 0u64;
+        for shift in (0..64).step_by(7) {
+            let b = self.byte()?;
+            v |= ((b & 0x7f) as u64) << shift;
+            if b & 0x80 == 0 {
+                return Ok(v);
+            }
+        }
+        Err("invalid varint in binary metadata".into())
+    }
+
+    fn bytes(&mut self) -> crate::Result<&'a [u8]> {
+        let n = usize::try_from(self.varint()?).map_err(|_| truncated())?;
+        let (b, rest) = self.0.split_at_checked(n).ok_or(truncated())?;
+        self.0 = rest;
+        Ok(b)
+    }
+
+    /// Reads a sequence length, which is bounded by the remaining bytes (each
+    /// item takes at least one byte), so that corrupted lengths won't cause
+    /// huge allocations.
+    fn len(&mut self) -> crate::Result<usize> {
+        let n = self.varint()?;
+        if n > self.0.len() as u64 {
+            return Err(truncated());
+        }
+        Ok(n as usize)
+    }
+}
+
+fn truncated() -> crate::Error {
+    "binary metadata is truncated".into()
+}
+
+/// A value which can be encoded in the binary format.
+pub(crate) trait Bin: Sized {
+    fn put(&self, w: &mut Writer);
+    fn get(r: &mut Reader) -> crate::Result<Self>;
+}
+
+impl Bin for u8 {
+    fn put(&self, w: &mut Writer) {
+        w.byte(*self);
+    }
+    fn get(r: &mut Reader) -> crate::Result<Self> {
+        r.byte()
+    }
+}
+
+macro_rules! impl_uint {
+    ($($t:ty),*) => {$(
+        impl Bin for $t {
+            fn put(&self, w: &mut Writer) {
+                w.varint(*self as u64);
+            }
+            fn get(r: &mut Reader) -> crate::Result<Self> {
+                <$t>::try_from(r.varint()?).map_err(|_| "integer overflow in binary metadata".into())
+            }
+        }
+    )*};
+}
+impl_uint!(u16, u32, u64, usize);
+
+macro_rules! impl_int {
+    ($($t:ty),*) => {$(
+        impl Bin for $t {
+            fn put(&self, w: &mut Writer) {
+                let v = *self as i64;
+                w.varint(((v << 1) ^ (v >> 63)) as u64);
+            }
+            fn get(r: &mut Reader) -> crate::Result<Self> {
+                let v = r.varint()?;
+                let v = (v >> 1) as i64 ^ -((v & 1) as i64);
+                <$t>::try_from(v).map_err(|_| "integer overflow in binary metadata".into())
+            }
+        }
+    )*};
+}
+impl_int!(i8, i16, i32, i64);
+
+impl Bin for f32 {
+    fn put(&self, w: &mut Writer) {
+        w.0.extend(self.to_bits().to_le_bytes());
+    }
+    fn get(r: &mut Reader) -> crate::Result<Self> {
+        let b = [r.byte()?, r.byte()?, r.byte()?, r.byte()?];
+        Ok(f32::from_bits(u32::from_le_bytes(b)))
+    }
+}
+
+impl Bin for f64 {
+    fn put(&self, w: &mut Writer) {
+        w.0.extend(self.to_bits().to_le_bytes());
+    }
+    fn get(r: &mut Reader) -> crate::Result<Self> {
+        let mut b = [0; 8];
+        for x in b.iter_mut() {
+            *x = r.byte()?;
+        }
+        Ok(f64::from_bits(u64::from_le_bytes(b)))
+    }
+}
+
+impl Bin for char {
+    fn put(&self, w: &mut Writer) {
+        w.varint(*self as u64);
+    }
+    fn get(r: &mut Reader) -> crate::Result<Self> {
+        u32::try_from(r.varint()?)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or("invalid char in binary metadata".into())
+    }
+}
+
+impl Bin for String {
+    fn put(&self, w: &mut Writer) {
+        w.bytes(self.as_bytes());
+    }
+    fn get(r: &mut Reader) -> crate::Result<Self> {
+        Ok(String::from_utf8(r.bytes()?.to_vec())?)
+    }
+}
+
+impl<T: Bin> Bin for Option<T> {
+    fn put(&self, w: &mut Writer) {
+        match self {
+            None => w.byte(0),
+            Some(v) => {
+                w.byte(1);
+                v.put(w);
+            }
+        }
+    }
+    fn get(r: &mut Reader) -> crate::Result<Self> {
+        match r.byte()? {
+            0 => Ok(None),
+            1 => Ok(Some(T::get(r)?)),
+            x => Err(format!("invalid option tag in binary metadata: {x}").into()),
+        }
+    }
+}
+
+impl<T: Bin> Bin for Vec<T> {
+    fn put(&self, w: &mut Writer) {
+        w.varint(self.len() as u64);
+        for x in self {
+            x.put(w);
+        }
+    }
+    fn get(r: &mut Reader) -> crate::Result<Self> {
+        let n = r.len()?;
+        (0..n).map(|_| T::get(r)).collect()
+    }
+}
+
+impl<T: Bin> Bin for Arc<T> {
+    fn put(&self, w: &mut Writer) {
+        T::put(self, w);
+    }
+    fn get(r: &mut Reader) -> crate::Result<Self> {
+        Ok(Arc::new(T::get(r)?))
+    }
+}
+
+impl<A: Bin, B: Bin> Bin for (A, B) {
+    fn put(&self, w: &mut Writer) {
+        self.0.put(w);
+        self.1.put(w);
+    }
+    fn get(r: &mut Reader) -> crate::Result<Self> {
+        Ok((A::get(r)?, B::get(r)?))
+    }
+}
+
+impl<T: Bin + Copy> Bin for Rational<T> {
+    fn put(&self, w: &mut Writer) {
+        self.0.put(w);
+        self.1.put(w);
+    }
+    fn get(r: &mut Reader) -> crate::Result<Self> {
+        Ok(Rational(T::get(r)?, T::get(r)?))
+    }
+}
+
+impl Bin for DateTime<FixedOffset> {
+    fn put(&self, w: &mut Writer) {
+        self.to_rfc3339().put(w);
+    }
+    fn get(r: &mut Reader) -> crate::Result<Self> {
+        let s = String::get(r)?;
+        DateTime::parse_from_rfc3339(&s)
+            .map_err(|e| format!("invalid time in binary metadata: {e}").into())
+    }
+}
+
+impl Bin for EntryValue {
+    fn put(&self, w: &mut Writer) {
+        macro_rules! put {
+            ($ty:expr, $v:expr) => {{
+                w.byte($ty);
+                $v.put(w);
+            }};
+        }
+        match self {
+            EntryValue::Text(v) => put!(0, v),
+            EntryValue::URational(v) => put!(1, v),
+            EntryValue::IRational(v) => put!(2, v),
+            EntryValue::U8(v) => put!(3, v),
+            EntryValue::U16(v) => put!(4, v),
+            EntryValue::U32(v) => put!(5, v),
+            EntryValue::U64(v) => put!(6, v),
+            EntryValue::I8(v) => put!(7, v),
+            EntryValue::I16(v) => put!(8, v),
+            EntryValue::I32(v) => put!(9, v),
+            EntryValue::I64(v) => put!(10, v),
+            EntryValue::F32(v) => put!(11, v),
+            EntryValue::F64(v) => put!(12, v),
+            EntryValue::Time(v) => put!(13, v),
+            EntryValue::Undefined(v) => put!(14, v),
+            EntryValue::URationalArray(v) => put!(15, v),
+            EntryValue::IRationalArray(v) => put!(16, v),
+            EntryValue::U8Array(v) => put!(17, v),
+            EntryValue::U16Array(v) => put!(18, v),
+            EntryValue::U32Array(v) => put!(19, v),
+            EntryValue::F64Array(v) => put!(20, v),
+            EntryValue::I8Array(v) => put!(21, v),
+            EntryValue::I16Array(v) => put!(22, v),
+            EntryValue::I32Array(v) => put!(23, v),
+            EntryValue::F32Array(v) => put!(24, v),
+        }
+    }
+
+    fn get(r: &mut Reader) -> crate::Result<Self> {
+        let v = match r.byte()? {
+            0 => EntryValue::Text(r.get()?),
+            1 => EntryValue::URational(r.get()?),
+            2 => EntryValue::IRational(r.get()?),
+            3 => EntryValue::U8(r.get()?),
+            4 => EntryValue::U16(r.get()?),
+            5 => EntryValue::U32(r.get()?),
+            6 => EntryValue::U64(r.get()?),
+            7 => EntryValue::I8(r.get()?),
+            8 => EntryValue::I16(r.get()?),
+            9 => EntryValue::I32(r.get()?),
+            10 => EntryValue::I64(r.get()?),
+            11 => EntryValue::F32(r.get()?),
+            12 => EntryValue::F64(r.get()?),
+            13 => EntryValue::Time(r.get()?),
+            14 => EntryValue::Undefined(r.get()?),
+            15 => EntryValue::URationalArray(r.get()?),
+            16 => EntryValue::IRationalArray(r.get()?),
+            17 => EntryValue::U8Array(r.get()?),
+            18 => EntryValue::U16Array(r.get()?),
+            19 => EntryValue::U32Array(r.get()?),
+            20 => EntryValue::F64Array(r.get()?),
+            21 => EntryValue::I8Array(r.get()?),
+            22 => EntryValue::I16Array(r.get()?),
+            23 => EntryValue::I32Array(r.get()?),
+            24 => EntryValue::F32Array(r.get()?),
+            x => return Err(format!("invalid value type in binary metadata: {x}").into()),
+        };
+        Ok(v)
+    }
+}
+
+impl Bin for LatLng {
+    fn put(&self, w: &mut Writer) {
+        w.put(&self.0).put(&self.1).put(&self.2);
+    }
+    fn get(r: &mut Reader) -> crate::Result<Self> {
+        Ok(LatLng(r.get()?, r.get()?, r.get()?))
+    }
+}
+
+impl Bin for GPSInfo {
+    fn put(&self, w: &mut Writer) {
+        w.put(&self.latitude_ref)
+            .put(&self.latitude)
+            .put(&self.longitude_ref)
+            .put(&self.longitude)
+            .put(&self.altitude_ref)
+            .put(&self.altitude)
+            .put(&self.speed_ref)
+            .put(&self.speed)
+            .put(&self.date_stamp)
+            .put(&self.time_stamp.map(Vec::from))
+            .put(&self.img_direction_ref)
+            .put(&self.img_direction)
+            .put(&self.dest_bearing_ref)
+            .put(&self.dest_bearing)
+            .put(&self.dop);
+    }
+
+    fn get(r: &mut Reader) -> crate::Result<Self> {
+        Ok(GPSInfo {
+            latitude_ref: r.get()?,
+            latitude: r.get()?,
+            longitude_ref: r.get()?,
+            longitude: r.get()?,
+            altitude_ref: r.get()?,
+            altitude: r.get()?,
+            speed_ref: r.get()?,
+            speed: r.get()?,
+            date_stamp: r.get()?,
+            time_stamp: r
+                .get::<Option<Vec<URational>>>()?
+                .map(|x| x.try_into())
+                .transpose()
+                .map_err(|_| "invalid GPS time stamp in binary metadata")?,
+            img_direction_ref: r.get()?,
+            img_direction: r.get()?,
+            dest_bearing_ref: r.get()?,
+            dest_bearing: r.get()?,
+            dop: r.get()?,
+        })
+    }
+}
+
+impl Bin for XmpPackets {
+    fn put(&self, w: &mut Writer) {
+        w.put(&self.standard).put(&self.extended);
+    }
+    fn get(r: &mut Reader) -> crate::Result<Self> {
+        Ok(XmpPackets {
+            standard: r.get()?,
+            extended: r.get()?,
+        })
+    }
+}
+
+impl Bin for Chapter {
+    fn put(&self, w: &mut Writer) {
+        w.put(&self.edition)
+            .put(&self.start_ms)
+            .put(&self.end_ms)
+            .put(&self.title);
+    }
+    fn get(r: &mut Reader) -> crate::Result<Self> {
+        Ok(Chapter {
+            edition: r.get()?,
+            start_ms: r.get()?,
+            end_ms: r.get()?,
+            title: r.get()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case(EntryValue::Text("写真".into()))]
+    #[test_case(EntryValue::IRational((-1, 3).into()))]
+    #[test_case(EntryValue::U64(u64::MAX))]
+    #[test_case(EntryValue::I64(i64::MIN))]
+    #[test_case(EntryValue::F32(-1.5))]
+    #[test_case(EntryValue::Time(DateTime::parse_from_rfc3339("2024-05-06T07:08:09.123-03:00").unwrap()))]
+    #[test_case(EntryValue::URationalArray(vec![(1, 2).into(), (3, 4).into()]))]
+    #[test_case(EntryValue::I16Array(vec![-1, 0, i16::MAX]))]
+    #[test_case(EntryValue::F64Array(vec![f64::NAN.abs(), 0.1]))]
+    fn entry_value(v: EntryValue) {
+        let mut w = Writer::new(Kind::Exif);
+        w.put(&v);
+        let data = w.finish();
+
+        let mut r = Reader::new(&data, Kind::Exif).unwrap();
+        let decoded: EntryValue = r.get().unwrap();
+        r.finish().unwrap();
+        // NaN != NaN
+        assert_eq!(format!("{decoded:?}"), format!("{v:?}"));
+
+        // Truncated data
+        let mut r = Reader::new(&data[..data.len() - 1], Kind::Exif).unwrap();
+        assert!(r.get::<EntryValue>().is_err());
+    }
+
+    #[test]
+    fn header() {
+        let data = Writer::new(Kind::Exif).finish();
+        assert_eq!(data, b"NXMD\x01E");
+        Reader::new(&data, Kind::Exif).unwrap().finish().unwrap();
+        assert!(Reader::new(&data, Kind::TrackInfo).is_err());
+        assert!(Reader::new(b"NXMD\x02E", Kind::Exif).is_err());
+        assert!(Reader::new(b"NXMD", Kind::Exif).is_err());
+    }
+
+    #[test]
+    fn corrupted_length() {
+        // A huge length followed by nothing
+        let data = b"NXMD\x01E\xff\xff\xff\xff\x0f";
+        let mut r = Reader::new(data, Kind::Exif).unwrap();
+        assert!(r.get::<Vec<u8>>().is_err());
+    }
+}
@@ -1,8 +1,6 @@
-use std::{
-    collections::VecDeque,
-    fmt::Debug,
-    sync::{atomic::AtomicUsize, Arc},
-};
+use std::{collections::VecDeque, fmt::Debug, sync::atomic::AtomicUsize};
+
+use bytes::Bytes;
 
 use crate::parser::INIT_BUF_SIZE;
 
@@ -12,7 +10,7 @@ const INIT_POOLED_BUF: usize = 2;
 const MAX_POOLED_BUF: usize = 8;
 
 pub(crate) struct Buffers {
-    shared: VecDeque<Arc<Vec<u8>>>,
+    shared: VecDeque<Bytes>,
     pool: VecDeque<Vec<u8>>,
     acquired: AtomicUsize,
 }
@@ -36,12 +34,12 @@ impl Buffers {
     }
 
     #[tracing::instrument(skip_all)]
-    pub fn release_to_share(&mut self, buf: Vec<u8>) -> Arc<Vec<u8>> {
-        let arc = Arc::new(buf);
-        self.shared.push_back(arc.clone());
+    pub fn release_to_share(&mut self, buf: Vec<u8>) -> Bytes {
+        let bytes = Bytes::from(buf);
+        self.shared.push_back(bytes.clone());
         self.checked_sub_acquired();
         tracing::debug!(?self, "buffers status");
-        arc
+        bytes
     }
 
     #[tracing::instrument(skip_all)]
@@ -72,19 +70,17 @@ impl Buffers {
     fn recycle(&mut self) -> Option<Vec<u8>> {
         let mut remain = VecDeque::new();
         let buf = loop {
-            let Some(arc) = self.shared.pop_front() else {
+            let Some(bytes) = self.shared.pop_front() else {
                 break None;
             };
-            match Arc::try_unwrap(arc) {
-                Ok(mut buf) => {
-                    // recycled
-                    Self::clean(&mut buf);
-                    break Some(buf);
-                }
-                Err(arc) => {
-                    // still being used, put it back
-                    remain.push_back(arc);
-                }
+            if bytes.is_unique() {
+                // recycled, without copying since it's not shared anymore
+                let mut buf = Vec::from(bytes);
+                Self::clean(&mut buf);
+                break Some(buf);
+            } else {
+                // still being used, put it back
+                remain.push_back(bytes);
             }
         };
         self.shared.append(&mut remain);
@@ -0,0 +1,300 @@
+use std::{
+    any::{Any, TypeId},
+    collections::{HashMap, VecDeque},
+    fmt::Debug,
+    io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+    time::SystemTime,
+};
+
+use crate::{ExifIter, Telemetry, TrackInfo};
+
+/// Identifies the content of a media file in a [`ParseCache`].
+///
+/// - Use [`CacheKey::for_file`] to make a key from the file's identity, i.e.
+///   (device, inode, size, mtime) on Unix, or (path, size, mtime) on other
+///   platforms. A modified file gets a new key, so stale results won't be
+///   returned.
+///
+/// - Use [`CacheKey::custom`] if you have a better identity for the content,
+///   e.g. a content hash, or an ID in your database.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey(KeyInner);
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum KeyInner {
+    File {
+        id: FileId,
+        size: u64,
+        mtime: Option<SystemTime>,
+    },
+    Custom(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum FileId {
+    #[allow(unused)]
+    Inode { dev: u64, ino: u64 },
+    #[allow(unused)]
+    Path(PathBuf),
+}
+
+impl CacheKey {
+    /// Makes a key from the file's metadata, the file content won't be read.
+    pub fn for_file<P: AsRef<Path>>(path: P) -> io::Result<CacheKey> {
+        let path = path.as_ref();
+        let meta = std::fs::metadata(path)?;
+
+        #[cfg(unix)]
+        let id = {
+            use std::os::unix::fs::MetadataExt;
+            FileId::Inode {
+                dev: meta.dev(),
+                ino: meta.ino(),
+            }
+        };
+        #[cfg(not(unix))]
+        let id = FileId::Path(path.canonicalize()?);
+
+        Ok(CacheKey(KeyInner::File {
+            id,
+            size: meta.len(),
+            mtime: meta.modified().ok(),
+        }))
+    }
+
+    /// Makes a key from a caller-provided identity.
+    pub fn custom<T: Into<String>>(key: T) -> CacheKey {
+        CacheKey(KeyInner::Custom(key.into()))
+    }
+}
+
+/// Parse outputs which can be stored in a [`ParseCache`].
+pub trait CacheableOutput: Clone + Send + Sync + 'static {
+    /// Converts into a value which doesn't reference any parsing buffer.
+    fn into_cacheable(self) -> Self {
+        self
+    }
+}
+
+impl CacheableOutput for ExifIter {
+    fn into_cacheable(self) -> Self {
+        self.into_owned_static()
+    }
+}
+
+impl CacheableOutput for TrackInfo {}
+impl CacheableOutput for Telemetry {}
+
+type Entries = HashMap<(CacheKey, TypeId), Arc<dyn Any + Send + Sync>>;
+
+struct Inner {
+    capacity: usize,
+    entries: Entries,
+    // insertion order, for evicting the oldest entries
+    order: VecDeque<(CacheKey, TypeId)>,
+}
+
+/// A cache of parse results, used by [`MediaParser::parse_cached`] &
+/// [`MediaParser::parse_file_cached`] to skip parsing (and reading) the files
+/// which have been parsed before. This is useful for repeated scans of large
+/// media libraries.
+///
+/// - Each output type ([`ExifIter`], [`TrackInfo`], [`Telemetry`]) is cached
+///   separately for a key.
+/// - Errors are not cached.
+/// - When the cache is full, the oldest entries are evicted first.
+///
+/// `ParseCache` is cheap to clone, and the cloned ones share the same
+/// storage, so it can be shared between multiple parsers and threads.
+///
+/// [`MediaParser::parse_cached`]: crate::MediaParser::parse_cached
+/// [`MediaParser::parse_file_cached`]: crate::MediaParser::parse_file_cached
+///
+/// ## Example
+///
+/// ```rust
+/// use nom_exif::*;
+///
+/// fn main() -> Result<()> {
+///     let cache = ParseCache::new(1000);
+///     let mut parser = MediaParser::new();
+///
+///     for _ in 0..2 {
+///         // The file is only parsed the first time
+///         let iter: ExifIter = parser.parse_file_cached(&cache, "./testdata/exif.jpg")?;
+///         let exif: Exif = iter.into();
+///         assert_eq!(exif.get(ExifTag::Make).unwrap().as_str(), Some("vivo"));
+///     }
+///     assert_eq!(cache.len(), 1);
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone)]
+pub struct ParseCache {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl Debug for ParseCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let inner = self.lock();
+        f.debug_struct("ParseCache")
+            .field("capacity", &inner.capacity)
+            .field("len", &inner.entries.len())
+            .finish()
+    }
+}
+
+impl ParseCache {
+    /// Creates a cache which holds at most `capacity` results.
+    pub fn new(capacity: usize) -> ParseCache {
+        ParseCache {
+            inner: Arc::new(Mutex::new(Inner {
+                capacity,
+                entries: HashMap::new(),
+                order: VecDeque::new(),
+            })),
+        }
+    }
+
+    /// Returns the number of cached results.
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes all cached results for `key`.
+    pub fn remove(&self, key: &CacheKey) {
+        let mut inner = self.lock();
+        inner.entries.retain(|(k, _), _| k != key);
+        inner.order.retain(|(k, _)| k != key);
+    }
+
+    /// Removes all cached results.
+    pub fn clear(&self) {
+        let mut inner = self.lock();
+        inner.entries.clear();
+        inner.order.clear();
+    }
+
+    pub(crate) fn get<O: CacheableOutput>(&self, key: &CacheKey) -> Option<O> {
+        let inner = self.lock();
+        inner
+            .entries
+            .get(&(key.clone(), TypeId::of::<O>()))
+            .and_then(|x| x.downcast_ref::<O>())
+            .cloned()
+    }
+
+    pub(crate) fn insert<O: CacheableOutput>(&self, key: CacheKey, value: O) {
+        let mut inner = self.lock();
+        if inner.capacity == 0 {
+            return;
+        }
+
+        let k = (key, TypeId::of::<O>());
+        if inner.entries.insert(k.clone(), Arc::new(value)).is_none() {
+            inner.order.push_back(k);
+        }
+        while inner.entries.len() > inner.capacity {
+            let Some(oldest) = inner.order.pop_front() else {
+                break;
+            };
+            inner.entries.remove(&oldest);
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        // The cache is always in a consistent state, so just ignore poisoning
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testkit::open_sample, Exif, ExifTag, MediaParser, MediaSource, TrackInfoTag};
+
+    #[test]
+    fn parse_cached() {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let cache = ParseCache::new(10);
+        let mut parser = MediaParser::new();
+        let key = CacheKey::custom("exif.jpg");
+
+        let iter: ExifIter = parser
+            .parse_cached(&cache, key.clone(), || {
+                MediaSource::file(open_sample("exif.jpg")?)
+            })
+            .unwrap();
+        let expect: Exif = iter.into();
+
+        // Cached, the source won't be opened
+        let iter: ExifIter = parser
+            .parse_cached(
+                &cache,
+                key.clone(),
+                || -> crate::Result<MediaSource<std::fs::File>> { panic!("should be cached") },
+            )
+            .unwrap();
+        let exif: Exif = iter.into();
+        assert_eq!(exif.get(ExifTag::Make), expect.get(ExifTag::Make));
+
+        // Different output types are cached separately
+        let res = parser.parse_cached::<_, _, TrackInfo, _>(&cache, key.clone(), || {
+            MediaSource::file(open_sample("exif.jpg")?)
+        });
+        assert!(res.is_err());
+        assert_eq!(cache.len(), 1);
+
+        cache.remove(&key);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn file_key() {
+        let dir = std::env::temp_dir().join("nom-exif-cache-test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("{}.mov", std::process::id()));
+        std::fs::copy("./testdata/meta.mov", &path).unwrap();
+
+        let cache = ParseCache::new(10);
+        let mut parser = MediaParser::new();
+        let key = CacheKey::for_file(&path).unwrap();
+        assert_eq!(key, CacheKey::for_file(&path).unwrap());
+
+        let info: TrackInfo = parser.parse_file_cached(&cache, &path).unwrap();
+        assert_eq!(info.get(TrackInfoTag::Make), Some(&"Apple".into()));
+        assert_eq!(cache.len(), 1);
+
+        // Modified files get new keys
+        let mut data = std::fs::read(&path).unwrap();
+        data.push(0);
+        std::fs::write(&path, data).unwrap();
+        assert_ne!(key, CacheKey::for_file(&path).unwrap());
+        let _: TrackInfo = parser.parse_file_cached(&cache, &path).unwrap();
+        assert_eq!(cache.len(), 2);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn evict_oldest() {
+        let cache = ParseCache::new(2);
+        for i in 0..3 {
+            cache.insert(CacheKey::custom(i.to_string()), TrackInfo::default());
+        }
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get::<TrackInfo>(&CacheKey::custom("0")).is_none());
+        assert!(cache.get::<TrackInfo>(&CacheKey::custom("2")).is_some());
+
+        let cache = ParseCache::new(0);
+        cache.insert(CacheKey::custom("0"), TrackInfo::default());
+        assert!(cache.is_empty());
+    }
+}
@@ -0,0 +1,185 @@
+use std::io::{Cursor, Read, Write};
+
+use crate::{
+    exif::{check_exif_header, is_image_data_tag, EXIF_IDENT},
+    file::{Mime, MimeImage},
+    jpeg::{self, MarkerCode, EXTENDED_XMP_SIGNATURE, XMP_SIGNATURE},
+    xmp::extract_xmp,
+    Error, Exif, ExifIter, ExifWriter, MediaParser, MediaSource,
+};
+
+/// Reads the Exif & XMP data of `src`, and writes `dst` (a JPEG file) into
+/// `writer` with its Exif & XMP data replaced by them, e.g. to transplant the
+/// metadata of a RAW file into its exported JPEG file.
+///
+/// `src` can be any file whose Exif/XMP data can be parsed by a
+/// [`MediaParser`], e.g. JPEG, TIFF, HEIF, DNG or CR2 files. Only the kinds of
+/// metadata found in `src` are replaced, the other segments and the image
+/// data of `dst` are copied as is.
+///
+/// *Note*:
+///
+/// - Exif data is re-serialized with [`ExifWriter::from_exif`], so entries
+///   which store offsets of the original data (e.g. strip offsets/counts, the
+///   thumbnail) are dropped. Entries describing the layout of the source
+///   image data (e.g. `ImageWidth`, `Compression`, `BitsPerSample`), DNG
+///   specific entries and ICC profiles are dropped as well, since they don't
+///   apply to `dst`.
+///
+/// - Only the standard XMP packet is copied, the extended XMP (if any) is
+///   not.
+///
+/// Returns [`Error::WriteFailed`] if `dst` is not a JPEG file, or if `src`
+/// contains neither Exif nor XMP data.
+///
+/// ## Example
+///
+/// ```rust
+/// use nom_exif::*;
+/// use std::fs::File;
+///
+/// fn main() -> Result<()> {
+///     let mut output = Vec::new();
+///     copy_metadata(
+///         File::open("./testdata/exif.heic")?,
+///         File::open("./testdata/no-exif.jpg")?,
+///         &mut output,
+///     )?;
+///
+///     let ms = MediaSource::seekable(std::io::Cursor::new(output))?;
+///     let iter: ExifIter = MediaParser::new().parse(ms)?;
+///     let exif: Exif = iter.into();
+///     assert_eq!(exif.get(ExifTag::Model), Some(&"iPhone 12 Pro".into()));
+///     Ok(())
+/// }
+/// ```
+pub fn copy_metadata<S: Read, D: Read, W: Write>(
+    mut src: S,
+    mut dst: D,
+    writer: W,
+) -> crate::Result<()> {
+    let mut dst_data = Vec::new();
+    dst.read_to_end(&mut dst_data)?;
+    let mime = Mime::try_from(&dst_data[..])?;
+    if mime != Mime::Image(MimeImage::Jpeg) {
+        return Err(Error::WriteFailed(
+            format!("copying metadata into {mime:?} files is not supported").into(),
+        ));
+    }
+
+    let mut src_data = Vec::new();
+    src.read_to_end(&mut src_data)?;
+    let exif = parse_exif(&src_data).map(|exif| {
+        let mut writer = ExifWriter::from_exif(&exif);
+        writer.retain(0, |code| !is_image_data_tag(code));
+        writer
+    });
+    let xmp = Mime::try_from(&src_data[..])
+        .ok()
+        .and_then(|mime| extract_xmp(mime, &src_data, None).ok().flatten());
+    if exif.is_none() && xmp.is_none() {
+        return Err(Error::WriteFailed(
+            "no Exif or XMP data found in the source file".into(),
+        ));
+    }
+
+    let app1 = MarkerCode::APP1.code();
+    let mut inserts = Vec::new();
+    if let Some(exif) = exif.as_ref() {
+        let mut payload = EXIF_IDENT.as_bytes().to_vec();
+        payload.extend(exif.to_tiff_bytes()?);
+        inserts.push(payload);
+    }
+    if let Some(xmp) = xmp.as_ref() {
+        let mut payload = XMP_SIGNATURE.to_vec();
+        payload.extend_from_slice(xmp.standard.as_bytes());
+        inserts.push(payload);
+    }
+    let inserts = inserts.iter().map(|x| (app1, &x[..])).collect::<Vec<_>>();
+
+    let keep = |code: u8, payload: &[u8]| {
+        if code != app1 {
+            return true;
+        }
+        let replaced = (exif.is_some() && check_exif_header(payload))
+            || (xmp.is_some()
+                && (payload.starts_with(XMP_SIGNATURE)
+                    || payload.starts_with(EXTENDED_XMP_SIGNATURE)));
+        !replaced
+    };
+    jpeg::rewrite_segments(&dst_data, writer, keep, &inserts)
+}
+
+fn parse_exif(data: &[u8]) -> Option<Exif> {
+    let ms = MediaSource::seekable(Cursor::new(data)).ok()?;
+    let iter: ExifIter = MediaParser::new().parse(ms).ok()?;
+    Some(iter.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testkit::read_sample, ExifTag, Xmp};
+
+    fn copy(src: &str, dst: &str) -> crate::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        copy_metadata(
+            Cursor::new(read_sample(src)?),
+            Cursor::new(read_sample(dst)?),
+            &mut output,
+        )?;
+        Ok(output)
+    }
+
+    #[test]
+    fn copy_exif() {
+        let output = copy("exif.heic", "exif.jpg").unwrap();
+        let src = parse_exif(&read_sample("exif.heic").unwrap()).unwrap();
+        let exif = parse_exif(&output).unwrap();
+        for tag in [ExifTag::Make, ExifTag::Model, ExifTag::DateTimeOriginal] {
+            assert!(exif.get(tag).is_some());
+            assert_eq!(exif.get(tag), src.get(tag));
+        }
+        // Tags of the original Exif data of exif.jpg are gone
+        assert_eq!(exif.get(ExifTag::UserComment), None);
+        assert!(exif.get_gps_info().unwrap().is_some());
+
+        // The image data is kept
+        let dst = read_sample("exif.jpg").unwrap();
+        let (_, sos) = jpeg::parse_segments(&dst).unwrap();
+        let (_, output_sos) = jpeg::parse_segments(&output).unwrap();
+        assert_eq!(dst[sos..], output[output_sos..]);
+    }
+
+    #[test]
+    fn copy_tiff() {
+        let output = copy("tif.tif", "no-exif.jpg").unwrap();
+        let src = parse_exif(&read_sample("tif.tif").unwrap()).unwrap();
+        let exif = parse_exif(&output).unwrap();
+        assert!(src.get(ExifTag::ImageWidth).is_some());
+        assert_eq!(exif.get(ExifTag::ImageWidth), None);
+        assert_eq!(
+            exif.get(ExifTag::ImageDescription),
+            src.get(ExifTag::ImageDescription)
+        );
+    }
+
+    #[test]
+    fn copy_xmp() {
+        let output = copy("exif.heic", "no-exif.jpg").unwrap();
+        let parse = |data| -> crate::Result<Xmp> {
+            let ms = MediaSource::seekable(Cursor::new(data))?;
+            MediaParser::new().parse(ms)
+        };
+        let src = parse(read_sample("exif.heic").unwrap()).unwrap();
+        assert_eq!(parse(output).unwrap(), src);
+    }
+
+    #[test]
+    fn copy_failed() {
+        // No metadata in the source file
+        assert!(copy("no-exif.jpg", "exif.jpg").is_err());
+        // Unsupported destination file
+        assert!(copy("exif.jpg", "exif.heic").is_err());
+    }
+}
@@ -0,0 +1,535 @@
+use crate::{
+    error::ParsingError,
+    id3::Mp3Parser,
+    riff::to_text,
+    video::{TrackInfo, TrackInfoTag},
+};
+
+/// Size of the `DSD ` chunk & the `fmt ` chunk at the start of DSF files.
+const DSF_HEADER_SIZE: usize = 28 + 52;
+const DFF_HEADER_SIZE: usize = 16;
+const CHUNK_HEADER_SIZE: usize = 12;
+
+/// Max size of a DSDIFF chunk which is loaded into memory (e.g. `PROP`,
+/// `DIIN`), to avoid exhausting memory on corrupted files.
+const MAX_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Bytes needed from the start of a `DST ` chunk to parse the `FRTE` chunk
+/// at the start of it.
+const DST_PROBE_SIZE: usize = CHUNK_HEADER_SIZE * 2 + 6;
+
+/// Checks the `DSD ` chunk at the start of DSF files.
+pub(crate) fn check_dsf(input: &[u8]) -> bool {
+    input.len() >= CHUNK_HEADER_SIZE
+        && input.starts_with(b"DSD ")
+        && u64::from_le_bytes(input[4..12].try_into().unwrap()) == 28
+}
+
+/// Checks the `FRM8` header of DSDIFF files, and the form type of it.
+pub(crate) fn check_dff(input: &[u8]) -> bool {
+    input.len() >= DFF_HEADER_SIZE && input.starts_with(b"FRM8") && &input[12..16] == b"DSD "
+}
+
+/// Info of DSD audio, which is shared by DSF & DSDIFF files.
+#[derive(Debug, Clone, Default)]
+struct DsdInfo {
+    sample_rate: u32,
+    channels: u32,
+    /// Number of samples per channel
+    sample_count: Option<u64>,
+    /// Frame count & frame rate in `FRTE`, for DST compressed DSDIFF files
+    dst_frames: Option<(u32, u16)>,
+    /// "DSD" (uncompressed), or "DST"
+    codec: Option<String>,
+
+    /// `DITI` & `DIAR` in `DIIN` (DSDIFF)
+    title: Option<String>,
+    artist: Option<String>,
+
+    /// Tags of the ID3v2 tag
+    tag: Option<TrackInfo>,
+}
+
+impl DsdInfo {
+    fn duration_ms(&self) -> Option<u64> {
+        if let Some((frames, rate)) = self.dst_frames.filter(|x| x.1 > 0) {
+            return Some(frames as u64 * 1000 / rate as u64);
+        }
+        let count = self.sample_count?;
+        (self.sample_rate > 0).then(|| count * 1000 / self.sample_rate as u64)
+    }
+}
+
+/// Parses DSF files: the `DSD ` & `fmt ` chunks at the beginning, then the
+/// ID3v2 tag which the `DSD ` chunk points to (at the end of the file), the
+/// audio data in between is skipped with `ClearAndSkip`.
+///
+/// Refer to: Sony DSF File Format Specification, version 1.01
+#[derive(Debug, Default)]
+pub(crate) struct DsfParser {
+    /// Set after the header is parsed
+    info: Option<DsdInfo>,
+    /// Set when the ID3v2 tag is being parsed
+    tag: Option<Mp3Parser>,
+}
+
+impl DsfParser {
+    /// Parses `input`, which starts where the last call stopped.
+    pub(crate) fn parse(&mut self, input: &[u8]) -> Result<TrackInfo, ParsingError> {
+        if let Some(parser) = self.tag.as_mut() {
+            let tag = parser.parse(input)?;
+            let mut info = self.info.take().unwrap_or_default();
+            info.tag = Some(tag);
+            return Ok(info.into());
+        }
+
+        if input.len() < DSF_HEADER_SIZE {
+            return Err(ParsingError::Need(DSF_HEADER_SIZE - input.len()));
+        }
+        if !check_dsf(input) || &input[28..32] != b"fmt " {
+            return Err("invalid DSF header".into());
+        }
+        self.info = Some(DsdInfo {
+            channels: u32_le(input, 52),
+            sample_rate: u32_le(input, 56),
+            sample_count: Some(u64::from_le_bytes(input[64..72].try_into().unwrap())),
+            codec: Some("DSD".to_owned()),
+            ..Default::default()
+        });
+
+        // Pointer to the metadata chunk, 0 if there is no metadata
+        let pointer = u64::from_le_bytes(input[20..28].try_into().unwrap());
+        match usize::try_from(pointer) {
+            Ok(pointer) if pointer >= DSF_HEADER_SIZE => {
+                tracing::debug!(pointer, "skip to the ID3v2 tag");
+                self.tag = Some(Mp3Parser::tag_only());
+                Err(ParsingError::ClearAndSkip(pointer))
+            }
+            _ => Ok(self.info.take().unwrap_or_default().into()),
+        }
+    }
+
+    /// Returns the info parsed from the header, if the file ends before the
+    /// ID3v2 tag is parsed.
+    pub(crate) fn finish(&mut self) -> Option<TrackInfo> {
+        self.info.take().map(Into::into)
+    }
+}
+
+#[derive(Debug, Default)]
+enum DffState {
+    #[default]
+    Header,
+    Chunks,
+    /// Parsing the ID3v2 tag in an `ID3 ` chunk
+    Tag(Box<Mp3Parser>),
+}
+
+enum Step {
+    Consumed(usize),
+    Need(usize),
+    /// Skip `n` bytes, which extend beyond the input.
+    Skip(usize),
+    Done,
+}
+
+impl Step {
+    fn advance(n: usize, input: &[u8]) -> Step {
+        if n > input.len() {
+            Step::Skip(n)
+        } else {
+            Step::Consumed(n)
+        }
+    }
+}
+
+/// Parses DSDIFF files chunk by chunk: `PROP` & `DIIN` are loaded, the
+/// sound data (`DSD ` or `DST `) is skipped, and the ID3v2 tag in the
+/// `ID3 ` chunk (an unofficial extension, written by most taggers) is
+/// parsed. Chunks after the `ID3 ` chunk are not parsed, since it's usually
+/// the last one.
+///
+/// Refer to: Philips DSDIFF File Format Specification, version 1.5
+#[derive(Debug, Default)]
+pub(crate) struct DffParser {
+    state: DffState,
+    /// Size of the remaining chunks in the `FRM8` chunk.
+    remain: u64,
+    info: DsdInfo,
+}
+
+impl DffParser {
+    /// Parses `input`, which starts where the last call stopped.
+    pub(crate) fn parse(&mut self, input: &[u8]) -> Result<TrackInfo, ParsingError> {
+        let mut pos = 0;
+        loop {
+            if let DffState::Tag(parser) = &mut self.state {
+                return match parser.parse(&input[pos..]) {
+                    Ok(tag) => {
+                        self.info.tag = Some(tag);
+                        Ok(std::mem::take(&mut self.info).into())
+                    }
+                    Err(ParsingError::Need(_)) if pos > 0 => Err(ParsingError::ClearAndSkip(pos)),
+                    Err(ParsingError::ClearAndSkip(n)) => Err(ParsingError::ClearAndSkip(pos + n)),
+                    Err(e) => Err(e),
+                };
+            }
+
+            match self.step(&input[pos..])? {
+                Step::Consumed(n) => pos += n,
+                Step::Done => return Ok(std::mem::take(&mut self.info).into()),
+                Step::Need(n) if pos > 0 => {
+                    tracing::debug!(n, "need more bytes");
+                    return Err(ParsingError::ClearAndSkip(pos));
+                }
+                Step::Need(n) => return Err(ParsingError::Need(n)),
+                Step::Skip(n) => return Err(ParsingError::ClearAndSkip(pos + n)),
+            }
+        }
+    }
+
+    /// Returns the info collected so far, if the file ends before all the
+    /// chunks are parsed.
+    pub(crate) fn finish(&mut self) -> Option<TrackInfo> {
+        match self.state {
+            DffState::Header => None,
+            _ => Some(std::mem::take(&mut self.info).into()),
+        }
+    }
+
+    fn step(&mut self, input: &[u8]) -> Result<Step, ParsingError> {
+        match self.state {
+            DffState::Header => {
+                if input.len() < DFF_HEADER_SIZE {
+                    return Ok(Step::Need(DFF_HEADER_SIZE - input.len()));
+                }
+                if !check_dff(input) {
+                    return Err("invalid DSDIFF header".into());
+                }
+                // The form size includes the form type
+                self.remain = u64_be(input, 4).saturating_sub(4);
+                self.state = DffState::Chunks;
+                Ok(Step::Consumed(DFF_HEADER_SIZE))
+            }
+            DffState::Chunks => {
+                if self.remain < CHUNK_HEADER_SIZE as u64 {
+                    return Ok(Step::Done);
+                }
+                let step = self.parse_chunk(input)?;
+                if let Step::Consumed(n) | Step::Skip(n) = step {
+                    self.remain = self.remain.saturating_sub(n as u64);
+                }
+                Ok(step)
+            }
+            DffState::Tag(_) => unreachable!(),
+        }
+    }
+
+    fn parse_chunk(&mut self, input: &[u8]) -> Result<Step, ParsingError> {
+        if input.len() < CHUNK_HEADER_SIZE {
+            return Ok(Step::Need(CHUNK_HEADER_SIZE - input.len()));
+        }
+        let id: &[u8; 4] = input[..4].try_into().unwrap();
+        let size = u64_be(input, 4);
+        let Some(total) = chunk_total_size(size) else {
+            return Err(ParsingError::Failed(format!(
+                "DSDIFF chunk is too big: {size}"
+            )));
+        };
+
+        match id {
+            b"ID3 " => {
+                self.state = DffState::Tag(Box::new(Mp3Parser::tag_only()));
+                return Ok(Step::Consumed(CHUNK_HEADER_SIZE));
+            }
+            b"PROP" | b"DIIN" if total <= MAX_CHUNK_SIZE => {
+                if total > input.len() {
+                    return Ok(Step::Need(total - input.len()));
+                }
+                let data = &input[CHUNK_HEADER_SIZE..CHUNK_HEADER_SIZE + size as usize];
+                if id == b"PROP" {
+                    self.parse_prop(data);
+                } else {
+                    self.parse_diin(data);
+                }
+                return Ok(Step::Consumed(total));
+            }
+            b"DSD " if self.info.channels > 0 => {
+                // 1 bit per sample
+                self.info.sample_count = Some(size * 8 / self.info.channels as u64);
+            }
+            b"DST " => {
+                let probe = DST_PROBE_SIZE.min(total);
+                if input.len() < probe {
+                    return Ok(Step::Need(probe - input.len()));
+                }
+                let frte = &input[CHUNK_HEADER_SIZE..probe];
+                if frte.len() == DST_PROBE_SIZE - CHUNK_HEADER_SIZE && frte.starts_with(b"FRTE") {
+                    let frames = u32::from_be_bytes(frte[12..16].try_into().unwrap());
+                    let rate = u16::from_be_bytes([frte[16], frte[17]]);
+                    self.info.dst_frames = Some((frames, rate));
+                }
+            }
+            _ => (),
+        }
+
+        tracing::debug!(id = ?String::from_utf8_lossy(id), total, "skip chunk");
+        Ok(Step::advance(total, input))
+    }
+
+    /// Parses the local chunks of the sound property chunk (`PROP`).
+    fn parse_prop(&mut self, data: &[u8]) {
+        let Some(chunks) = data.strip_prefix(b"SND ") else {
+            return;
+        };
+        for (id, data) in Chunks(chunks) {
+            match id {
+                b"FS  " if data.len() >= 4 => {
+                    self.info.sample_rate = u32::from_be_bytes(data[..4].try_into().unwrap());
+                }
+                b"CHNL" if data.len() >= 2 => {
+                    self.info.channels = u16::from_be_bytes([data[0], data[1]]) as u32;
+                }
+                b"CMPR" if data.len() >= 4 => self.info.codec = to_text(&data[..4]),
+                _ => (),
+            }
+        }
+    }
+
+    /// Parses the local chunks of the edited master information chunk
+    /// (`DIIN`).
+    fn parse_diin(&mut self, data: &[u8]) {
+        for (id, data) in Chunks(data) {
+            // count & text
+            let text = || {
+                let count = u32::from_be_bytes(data.get(..4)?.try_into().unwrap()) as usize;
+                to_text(data.get(4..4usize.saturating_add(count))?)
+            };
+            match id {
+                b"DITI" => self.info.title = text(),
+                b"DIAR" => self.info.artist = text(),
+                _ => (),
+            }
+        }
+    }
+}
+
+/// Size of a chunk whose data is `size` bytes, including the header and the
+/// pad byte.
+fn chunk_total_size(size: u64) -> Option<usize> {
+    let total = size.checked_add(CHUNK_HEADER_SIZE as u64 + (size & 1))?;
+    usize::try_from(total).ok()
+}
+
+/// Iterates over the local chunks of a DSDIFF chunk, yields `(id, data)`.
+struct Chunks<'a>(&'a [u8]);
+
+impl<'a> Iterator for Chunks<'a> {
+    type Item = (&'a [u8; 4], &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0.len() < CHUNK_HEADER_SIZE {
+            return None;
+        }
+        let id: &[u8; 4] = self.0[..4].try_into().unwrap();
+        let size = u64_be(self.0, 4);
+        let data = self.0.get(CHUNK_HEADER_SIZE..chunk_total_size(size)?)?;
+        let data = &data[..size as usize];
+        self.0 = self.0.get(chunk_total_size(size)?..).unwrap_or_default();
+        Some((id, data))
+    }
+}
+
+fn u32_le(input: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes(input[pos..pos + 4].try_into().unwrap())
+}
+
+fn u64_be(input: &[u8], pos: usize) -> u64 {
+    u64::from_be_bytes(input[pos..pos + 8].try_into().unwrap())
+}
+
+impl From<DsdInfo> for TrackInfo {
+    fn from(value: DsdInfo) -> Self {
+        // The ID3v2 tag takes precedence over `DIIN`, and the duration is
+        // computed from the sound data rather than read from `TLEN`
+        let duration = value.duration_ms();
+        let mut info = value.tag.unwrap_or_default();
+        if let Some(duration) = duration {
+            info.put(TrackInfoTag::DurationMs, duration.into());
+        }
+        if let Some(codec) = value.codec {
+            info.put(TrackInfoTag::AudioCodec, codec.into());
+        }
+        let texts = [
+            (TrackInfoTag::Title, value.title),
+            (TrackInfoTag::Artist, value.artist),
+        ];
+        for (tag, text) in texts {
+            if let Some(text) = text.filter(|_| info.get(tag).is_none()) {
+                info.put(tag, text.into());
+            }
+        }
+        info
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{id3::tests::make_mp3, MediaParser, MediaSource};
+    use test_case::test_case;
+
+    /// Makes a DSF file of stereo DSD64 (2.8224 MHz) audio, which lasts 1.5
+    /// seconds, with an ID3v2.4 tag if `with_tag`.
+    pub(crate) fn make_dsf(with_tag: bool) -> Vec<u8> {
+        let data = [&b"data"[..], &(12_u64 + 8192).to_le_bytes(), &[0x69; 8192]].concat();
+        let tag = if with_tag {
+            make_mp3(4, 100, false)
+        } else {
+            Vec::new()
+        };
+        let pointer = if with_tag {
+            (DSF_HEADER_SIZE + data.len()) as u64
+        } else {
+            0
+        };
+        let total = (DSF_HEADER_SIZE + data.len() + tag.len()) as u64;
+
+        let mut fmt = b"fmt ".to_vec();
+        fmt.extend(52_u64.to_le_bytes());
+        // version, format ID (DSD raw), channel type (stereo), channel num,
+        // sampling frequency & bits per sample
+        for x in [1_u32, 0, 2, 2, 2_822_400, 1] {
+            fmt.extend(x.to_le_bytes());
+        }
+        fmt.extend((2_822_400_u64 * 3 / 2).to_le_bytes());
+        fmt.extend([4096_u32, 0].iter().flat_map(|x| x.to_le_bytes()));
+
+        [
+            &b"DSD "[..],
+            &28_u64.to_le_bytes(),
+            &total.to_le_bytes(),
+            &pointer.to_le_bytes(),
+            &fmt,
+            &data,
+            &tag,
+        ]
+        .concat()
+    }
+
+    fn chunk(id: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut chunk = id.to_vec();
+        chunk.extend((data.len() as u64).to_be_bytes());
+        chunk.extend(data);
+        if data.len() % 2 == 1 {
+            chunk.push(0);
+        }
+        chunk
+    }
+
+    fn text(id: &[u8], s: &str) -> Vec<u8> {
+        chunk(
+            id,
+            &[&(s.len() as u32).to_be_bytes()[..], s.as_bytes()].concat(),
+        )
+    }
+
+    /// Makes a DSDIFF file of stereo DSD64 audio, with an ID3v2.3 tag if
+    /// `with_tag`. The sound data lasts 100 ms if uncompressed, or 1 second
+    /// if DST compressed.
+    pub(crate) fn make_dff(compression: &[u8; 4], with_tag: bool) -> Vec<u8> {
+        let cmpr = [&compression[..], b"\x0enot compressed\0"].concat();
+        let prop = chunk(
+            b"PROP",
+            &[
+                &b"SND "[..],
+                &chunk(b"FS  ", &2_822_400_u32.to_be_bytes()),
+                &chunk(b"CHNL", b"\0\x02SLFTSRGT"),
+                &chunk(b"CMPR", &cmpr),
+            ]
+            .concat(),
+        );
+        let sound = if compression == b"DST " {
+            // 75 frames, at 75 frames per second
+            let frte = chunk(
+                b"FRTE",
+                &[&75_u32.to_be_bytes()[..], &75_u16.to_be_bytes()].concat(),
+            );
+            chunk(b"DST ", &[&frte[..], &[0; 1000]].concat())
+        } else {
+            chunk(b"DSD ", &[0x69; 2_822_400 * 2 / 8 / 10])
+        };
+        let diin = chunk(
+            b"DIIN",
+            &[text(b"DIAR", "DIIN Artist"), text(b"DITI", "DIIN Title")].concat(),
+        );
+
+        let mut body = [
+            &b"DSD "[..],
+            &chunk(b"FVER", &[1, 5, 0, 0]),
+            &prop,
+            &sound,
+            &diin,
+        ]
+        .concat();
+        if with_tag {
+            body.extend(chunk(b"ID3 ", &make_mp3(3, 100, false)));
+        }
+        chunk(b"FRM8", &body)
+    }
+
+    #[test_case(true; "with tag")]
+    #[test_case(false; "without tag")]
+    fn dsf(with_tag: bool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let data = make_dsf(with_tag);
+        let mut parser = MediaParser::new();
+        let ms = MediaSource::unseekable(Cursor::new(data)).unwrap();
+        assert!(ms.has_track());
+        let info: TrackInfo = parser.parse(ms).unwrap();
+
+        assert_eq!(info.get(TrackInfoTag::DurationMs), Some(&1500_u64.into()));
+        assert_eq!(info.get(TrackInfoTag::AudioCodec), Some(&"DSD".into()));
+        if with_tag {
+            assert_eq!(info.get(TrackInfoTag::Title), Some(&"Title".into()));
+            assert_eq!(info.get(TrackInfoTag::Album), Some(&"Album".into()));
+            assert_eq!(info.get(TrackInfoTag::Picture), Some(&"image/png".into()));
+        } else {
+            assert_eq!(info.get(TrackInfoTag::Title), None);
+        }
+    }
+
+    #[test]
+    fn dsf_truncated_tag() {
+        let mut data = make_dsf(true);
+        // Truncated in the frames of the tag
+        data.truncate(DSF_HEADER_SIZE + 12 + 8192 + 20);
+        let mut parser = MediaParser::new();
+        let ms = MediaSource::unseekable(Cursor::new(data)).unwrap();
+        let info: TrackInfo = parser.parse(ms).unwrap();
+        assert_eq!(info.get(TrackInfoTag::DurationMs), Some(&1500_u64.into()));
+    }
+
+    #[test_case(b"DSD ", true, 100, "Title"; "dsd with tag")]
+    #[test_case(b"DSD ", false, 100, "DIIN Title"; "dsd without tag")]
+    #[test_case(b"DST ", false, 1000, "DIIN Title"; "dst")]
+    fn dff(compression: &[u8; 4], with_tag: bool, duration: u64, title: &str) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let data = make_dff(compression, with_tag);
+        let mut parser = MediaParser::new();
+        let ms = MediaSource::unseekable(Cursor::new(data)).unwrap();
+        assert!(ms.has_track());
+        let info: TrackInfo = parser.parse(ms).unwrap();
+
+        assert_eq!(info.get(TrackInfoTag::DurationMs), Some(&duration.into()));
+        let codec = std::str::from_utf8(compression).unwrap().trim();
+        assert_eq!(info.get(TrackInfoTag::AudioCodec), Some(&codec.into()));
+        assert_eq!(info.get(TrackInfoTag::Title), Some(&title.into()));
+        let artist = if with_tag { "Artist" } else { "DIIN Artist" };
+        assert_eq!(info.get(TrackInfoTag::Artist), Some(&artist.into()));
+    }
+}
@@ -0,0 +1,609 @@
+use std::{fmt::Display, io};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bbox::BoxHeader,
+    error::{ParsedError, ParsingError, ParsingErrorState},
+    exif::{check_exif_header, dump_tiff},
+    file::{Mime, MimeImage, MimeVideo},
+    heif, jp2, jpeg,
+    parser_core::Limits,
+    raf::parse_raf_header,
+};
+
+/// Images are loaded into memory for dumping, at most this many bytes.
+pub(crate) const MAX_LOAD_SIZE: usize = 64 * 1024 * 1024;
+
+const CONTAINER_BOXES: &[&str] = &[
+    "moov", "trak", "mdia", "minf", "stbl", "dinf", "edts", "udta", "mvex", "moof", "traf", "iprp",
+    "ipco", "meta", "ilst",
+];
+
+/// The kind of a [`DumpNode`].
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DumpKind {
+    /// The whole file
+    File,
+    /// A JPEG segment
+    Segment,
+    /// An ISOBMFF (HEIF/MP4/QuickTime) box
+    Box,
+    /// A HEIF item
+    Item,
+    /// TIFF header (Exif data)
+    Tiff,
+    /// An IFD
+    Ifd,
+    /// An IFD entry
+    Entry,
+    /// Out-of-line data of an IFD entry
+    Data,
+}
+
+impl Display for DumpKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            DumpKind::File => "file",
+            DumpKind::Segment => "segment",
+            DumpKind::Box => "box",
+            DumpKind::Item => "item",
+            DumpKind::Tiff => "tiff",
+            DumpKind::Ifd => "ifd",
+            DumpKind::Entry => "entry",
+            DumpKind::Data => "data",
+        };
+        s.fmt(f)
+    }
+}
+
+/// A node of the structure tree which the parser has seen, see
+/// [`MediaParser::debug_dump`](crate::MediaParser::debug_dump).
+///
+/// `offset` and `size` are in bytes, and relative to the beginning of the
+/// file. `note` describes the value of an entry, or what's wrong with the
+/// node (e.g. truncated data).
+///
+/// The tree can be printed as text with `Display`, or be serialized as JSON
+/// when the `json_dump` feature is enabled.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DumpNode {
+    pub kind: DumpKind,
+    pub name: String,
+    pub offset: u64,
+    pub size: u64,
+    pub note: Option<String>,
+    pub children: Vec<DumpNode>,
+}
+
+impl DumpNode {
+    pub(crate) fn new(kind: DumpKind, name: &str, offset: u64, size: u64) -> Self {
+        Self {
+            kind,
+            name: name.to_owned(),
+            offset,
+            size,
+            note: None,
+            children: Vec::new(),
+        }
+    }
+
+    pub(crate) fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.note = Some(note.into());
+        self
+    }
+
+    /// Moves the node and all its children by `n` bytes, for nodes which are
+    /// dumped from a part of the file.
+    fn shift_offset(&mut self, n: u64) {
+        self.offset += n;
+        self.children.iter_mut().for_each(|x| x.shift_offset(n));
+    }
+
+    /// Finds the first node (depth-first, including `self`) with the given
+    /// kind & name.
+    pub fn find(&self, kind: DumpKind, name: &str) -> Option<&DumpNode> {
+        if self.kind == kind && self.name == name {
+            return Some(self);
+        }
+        self.children.iter().find_map(|x| x.find(kind, name))
+    }
+
+    fn fmt_indent(&self, f: &mut std::fmt::Formatter<'_>, depth: usize) -> std::fmt::Result {
+        write!(
+            f,
+            "{:indent$}{} {} @0x{:x} size={}",
+            "",
+            self.kind,
+            self.name,
+            self.offset,
+            self.size,
+            indent = depth * 2
+        )?;
+        if let Some(note) = self.note.as_ref() {
+            write!(f, ": {note}")?;
+        }
+        writeln!(f)?;
+        self.children
+            .iter()
+            .try_for_each(|x| x.fmt_indent(f, depth + 1))
+    }
+}
+
+impl Display for DumpNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.fmt_indent(f, 0)
+    }
+}
+
+pub(crate) fn mime_name(mime: Mime) -> &'static str {
+    match mime {
+        Mime::Image(MimeImage::Jpeg) => "JPEG",
+        Mime::Image(MimeImage::Heic) => "HEIC",
+        Mime::Image(MimeImage::Heif) => "HEIF",
+        Mime::Image(MimeImage::Avif) => "AVIF",
+        Mime::Image(MimeImage::Tiff) => "TIFF",
+        Mime::Image(MimeImage::Raf) => "RAF",
+        Mime::Image(MimeImage::Pef) => "PEF",
+        Mime::Image(MimeImage::Srw) => "SRW",
+        Mime::Image(MimeImage::Jp2) => "JPEG 2000",
+        Mime::Video(MimeVideo::QuickTime) => "QuickTime",
+        Mime::Video(MimeVideo::Mp4) => "MP4",
+        Mime::Video(MimeVideo::_3gpp) => "3GPP",
+        Mime::Video(MimeVideo::Webm) => "WebM",
+        Mime::Video(MimeVideo::Matroska) => "Matroska",
+        Mime::Video(MimeVideo::Gif) => "GIF",
+        Mime::Video(MimeVideo::Avi) => "AVI",
+        Mime::Video(MimeVideo::Wav) => "WAV",
+        Mime::Video(MimeVideo::Mp3) => "MP3",
+        Mime::Video(MimeVideo::Asf) => "ASF",
+        Mime::Video(MimeVideo::MpegTs) => "MPEG-TS",
+        Mime::Video(MimeVideo::Mxf) => "MXF",
+        Mime::Video(MimeVideo::Dsf) => "DSF",
+        Mime::Video(MimeVideo::Dff) => "DSDIFF",
+        Mime::Xmp => "XMP",
+    }
+}
+
+/// Dumps an image which has been loaded into memory, `truncated` indicates
+/// that only the first [`MAX_LOAD_SIZE`] bytes have been loaded. Boxes nested
+/// deeper than `max_box_depth` are not dumped.
+pub(crate) fn dump_image(
+    img: MimeImage,
+    data: &[u8],
+    truncated: bool,
+    max_box_depth: usize,
+) -> DumpNode {
+    let mut root = DumpNode::new(
+        DumpKind::File,
+        mime_name(Mime::Image(img)),
+        0,
+        data.len() as u64,
+    );
+    if truncated {
+        root.note = Some(format!("only the first {MAX_LOAD_SIZE} bytes are dumped"));
+    }
+    match img {
+        MimeImage::Jpeg => root.children = dump_jpeg(data),
+        MimeImage::Heic | MimeImage::Heif | MimeImage::Avif => {
+            root.children = dump_boxes(data, 0, 0, max_box_depth);
+            if let Some(item) = dump_heif_exif(data) {
+                root.children.push(item);
+            }
+        }
+        MimeImage::Tiff | MimeImage::Pef | MimeImage::Srw => root.children.push(dump_tiff(data, 0)),
+        MimeImage::Raf => root.children = dump_raf(data),
+        MimeImage::Jp2 => root.children = dump_jp2(data, max_box_depth),
+    }
+    root
+}
+
+fn dump_raf(data: &[u8]) -> Vec<DumpNode> {
+    let Ok((_, (offset, len))) = parse_raf_header(data) else {
+        return vec![DumpNode::new(DumpKind::Data, "RAF header", 0, 0).with_note("truncated")];
+    };
+    let header = DumpNode::new(DumpKind::Data, "RAF header", 0, 0x5c);
+    let mut jpeg = DumpNode::new(DumpKind::Data, "JPEG", offset as u64, len as u64);
+    let start = (offset as usize).min(data.len());
+    let end = start.saturating_add(len as usize).min(data.len());
+    jpeg.children = dump_jpeg(&data[start..end]);
+    jpeg.children
+        .iter_mut()
+        .for_each(|x| x.shift_offset(offset as u64));
+    vec![header, jpeg]
+}
+
+/// Returns the root node for the video formats which can't be dumped.
+pub(crate) fn dump_unsupported(mime: Mime) -> DumpNode {
+    DumpNode::new(DumpKind::File, mime_name(mime), 0, 0)
+        .with_note("dumping this format is not supported yet")
+}
+
+fn jpeg_marker_name(marker: u8) -> String {
+    match marker {
+        0xD8 => "SOI".to_owned(),
+        0xD9 => "EOI".to_owned(),
+        0xDA => "SOS".to_owned(),
+        0xDB => "DQT".to_owned(),
+        0xC4 => "DHT".to_owned(),
+        0xCC => "DAC".to_owned(),
+        0xDD => "DRI".to_owned(),
+        0xFE => "COM".to_owned(),
+        0xC0..=0xCF => format!("SOF{}", marker - 0xC0),
+        0xD0..=0xD7 => format!("RST{}", marker - 0xD0),
+        0xE0..=0xEF => format!("APP{}", marker - 0xE0),
+        _ => format!("0x{marker:02x}"),
+    }
+}
+
+fn dump_jpeg(data: &[u8]) -> Vec<DumpNode> {
+    let mut nodes = Vec::new();
+    let mut pos = 0;
+    loop {
+        let Some(&[ff, marker]) = data.get(pos..pos + 2) else {
+            if pos < data.len() {
+                nodes.push(
+                    DumpNode::new(
+                        DumpKind::Segment,
+                        "?",
+                        pos as u64,
+                        (data.len() - pos) as u64,
+                    )
+                    .with_note("unexpected end of data"),
+                );
+            }
+            break;
+        };
+        if ff != 0xFF {
+            nodes.push(
+                DumpNode::new(DumpKind::Segment, "?", pos as u64, 0)
+                    .with_note(format!("invalid marker 0x{ff:02x}{marker:02x}")),
+            );
+            break;
+        }
+        if marker == 0xFF {
+            // fill byte
+            pos += 1;
+            continue;
+        }
+
+        let name = jpeg_marker_name(marker);
+        if matches!(marker, 0xD8 | 0xD9 | 0xD0..=0xD7 | 0x01) {
+            nodes.push(DumpNode::new(DumpKind::Segment, &name, pos as u64, 2));
+            pos += 2;
+            if marker == 0xD9 {
+                break;
+            }
+            continue;
+        }
+
+        let Some(&[hi, lo]) = data.get(pos + 2..pos + 4) else {
+            nodes.push(
+                DumpNode::new(DumpKind::Segment, &name, pos as u64, 2)
+                    .with_note("truncated segment length"),
+            );
+            break;
+        };
+        let len = u16::from_be_bytes([hi, lo]) as usize;
+        let size = 2 + len;
+        let mut node = DumpNode::new(DumpKind::Segment, &name, pos as u64, size as u64);
+        let Some(payload) = data.get(pos + 4..pos + size) else {
+            nodes.push(node.with_note(format!("truncated, {} bytes available", data.len() - pos)));
+            break;
+        };
+
+        if marker == 0xE1 && check_exif_header(payload) {
+            node.note = Some("Exif".to_owned());
+            node.children
+                .push(dump_tiff(&payload[6..], (pos + 4 + 6) as u64));
+        } else if marker == 0xE1 && payload.starts_with(jpeg::EXTENDED_XMP_SIGNATURE) {
+            node.note = Some("extended XMP".to_owned());
+        } else if marker == 0xE1 && payload.starts_with(b"http://ns.adobe.com/") {
+            node.note = Some("XMP".to_owned());
+        }
+        nodes.push(node);
+
+        if marker == 0xDA {
+            let last = nodes.last_mut().unwrap();
+            last.note = Some("followed by entropy-coded image data".to_owned());
+            break;
+        }
+        pos += size;
+    }
+    nodes
+}
+
+/// Dumps the boxes in `data`, `base` is the offset of `data` in the file.
+fn dump_boxes(data: &[u8], base: u64, depth: usize, max_depth: usize) -> Vec<DumpNode> {
+    let mut nodes = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        let offset = base + pos as u64;
+        let remain = &data[pos..];
+        let header = match BoxHeader::parse(remain) {
+            Ok((_, header)) => header,
+            Err(e) => {
+                let note = match e {
+                    nom::Err::Incomplete(_) => "truncated box header".to_owned(),
+                    e => format!("invalid box header: {e}"),
+                };
+                nodes.push(
+                    DumpNode::new(DumpKind::Box, "?", offset, remain.len() as u64).with_note(note),
+                );
+                break;
+            }
+        };
+        let (node, size) = dump_box(&header, remain, offset, depth, max_depth);
+        nodes.push(node);
+        pos = pos.saturating_add(size);
+    }
+    nodes
+}
+
+/// Dumps a box (with its children), `data` starts with the box header.
+/// Returns the node & the number of bytes it occupies in `data`.
+fn dump_box(
+    header: &BoxHeader,
+    data: &[u8],
+    offset: u64,
+    depth: usize,
+    max_depth: usize,
+) -> (DumpNode, usize) {
+    let box_type = header.box_type.as_str();
+    let mut node = DumpNode::new(DumpKind::Box, box_type, offset, header.box_size);
+    let size = if header.box_size == 0 {
+        // The box extends to the end of the file
+        node.size = data.len() as u64;
+        node.note = Some("extends to the end of the file".to_owned());
+        data.len()
+    } else if header.box_size > data.len() as u64 {
+        node.note = Some(format!("truncated, {} bytes available", data.len()));
+        return (node, data.len());
+    } else {
+        header.box_size as usize
+    };
+
+    if CONTAINER_BOXES.contains(&box_type) {
+        if depth >= max_depth {
+            node.note = Some("too deep".to_owned());
+        } else {
+            let mut start = header.header_size;
+            let body = &data[start..size];
+            // `meta` is a full box in ISOBMFF, but not in QuickTime
+            if box_type == "meta" && body.starts_with(&[0, 0, 0, 0]) {
+                start += 4;
+            }
+            node.children = dump_boxes(
+                &data[start..size],
+                offset + start as u64,
+                depth + 1,
+                max_depth,
+            );
+        }
+    }
+    (node, size)
+}
+
+/// Dumps the top level boxes of a JPEG 2000 file, with the Exif & XMP data
+/// annotated.
+fn dump_jp2(data: &[u8], max_depth: usize) -> Vec<DumpNode> {
+    let mut nodes = dump_boxes(data, 0, 0, max_depth);
+    for node in nodes.iter_mut() {
+        let start = node.offset as usize;
+        let Some(body) = data
+            .get(start..start.saturating_add(node.size as usize))
+            .and_then(|x| BoxHeader::parse(x).ok())
+            .map(|x| x.0)
+        else {
+            continue;
+        };
+        let body_start = (data.len() - body.len()) as u64;
+        match node.name.as_str() {
+            "uuid" => {
+                if let Some(tiff) = jp2::exif_uuid_data(body) {
+                    node.note = Some("Exif".to_owned());
+                    let tiff_start = body_start + (body.len() - tiff.len()) as u64;
+                    node.children.push(dump_tiff(tiff, tiff_start));
+                } else if body.starts_with(jp2::XMP_UUID) {
+                    node.note = Some("XMP".to_owned());
+                }
+            }
+            "xml " => node.note = Some("XML".to_owned()),
+            _ => (),
+        }
+    }
+    nodes
+}
+
+/// Dumps the Exif item of a HEIF file, which is located by the `meta` box.
+fn dump_heif_exif(data: &[u8]) -> Option<DumpNode> {
+    let (_, meta) = heif::parse_meta_box(data).ok()?;
+    let range = meta?.exif_data_offset()?;
+    let mut node = DumpNode::new(
+        DumpKind::Item,
+        "Exif",
+        range.start as u64,
+        range.len() as u64,
+    );
+    let Some(item) = data.get(range.clone()) else {
+        return Some(node.with_note("out of range"));
+    };
+
+    // The item starts with the offset of the TIFF header, then the Exif
+    // header
+    let tiff_start = item
+        .get(..4)
+        .map(|x| u32::from_be_bytes([x[0], x[1], x[2], x[3]]) as usize + 4)
+        .filter(|x| *x >= 10 && check_exif_header(&item[x - 6..]));
+    match tiff_start.and_then(|x| item.get(x..).map(|data| (x, data))) {
+        Some((start, tiff)) => node
+            .children
+            .push(dump_tiff(tiff, (range.start + start) as u64)),
+        None => node.note = Some("invalid Exif header".to_owned()),
+    }
+    Some(node)
+}
+
+/// Streams the top-level boxes of an ISOBMFF file, to be used with
+/// `load_and_parse`. Containers are loaded (and dumped) entirely, other boxes
+/// (e.g. `mdat`) are skipped.
+#[derive(Debug)]
+pub(crate) struct BoxScanner {
+    offset: u64,
+    nodes: Vec<DumpNode>,
+    /// The box which is being loaded, kept in case that the file is
+    /// truncated
+    pending: Option<DumpNode>,
+    max_depth: usize,
+}
+
+impl Default for BoxScanner {
+    fn default() -> Self {
+        Self::new(Limits::default().max_box_depth)
+    }
+}
+
+impl BoxScanner {
+    pub fn new(max_depth: usize) -> Self {
+        Self {
+            offset: 0,
+            nodes: Vec::new(),
+            pending: None,
+            max_depth,
+        }
+    }
+
+    /// Dumps the box at the beginning of `data`. Returns `Ok` when the
+    /// scanning can't continue, `ClearAndSkip` to go to the next box, or
+    /// `Need` if more data is required.
+    pub fn scan(&mut self, data: &[u8]) -> Result<(), ParsingErrorState> {
+        let header = match BoxHeader::parse(data) {
+            Ok((_, header)) => header,
+            Err(nom::Err::Incomplete(_)) => {
+                return Err(ParsingErrorState::new(ParsingError::Need(16), None));
+            }
+            Err(e) => {
+                self.nodes.push(
+                    DumpNode::new(DumpKind::Box, "?", self.offset, data.len() as u64)
+                        .with_note(format!("invalid box header: {e}")),
+                );
+                return Ok(());
+            }
+        };
+
+        if header.box_size == 0 {
+            let node = DumpNode::new(DumpKind::Box, &header.box_type, self.offset, 0)
+                .with_note("extends to the end of the file");
+            self.nodes.push(node);
+            return Ok(());
+        }
+
+        let load = CONTAINER_BOXES.contains(&header.box_type.as_str())
+            && header.box_size <= MAX_LOAD_SIZE as u64;
+        let node = if load {
+            if (data.len() as u64) < header.box_size {
+                self.pending = Some(
+                    DumpNode::new(
+                        DumpKind::Box,
+                        &header.box_type,
+                        self.offset,
+                        header.box_size,
+                    )
+                    .with_note("truncated"),
+                );
+                let need = header.box_size as usize - data.len();
+                return Err(ParsingErrorState::new(ParsingError::Need(need), None));
+            }
+            dump_box(&header, data, self.offset, 0, self.max_depth).0
+        } else {
+            DumpNode::new(
+                DumpKind::Box,
+                &header.box_type,
+                self.offset,
+                header.box_size,
+            )
+        };
+        self.pending = None;
+        self.nodes.push(node);
+
+        let Ok(size) = usize::try_from(header.box_size) else {
+            return Ok(());
+        };
+        self.offset += header.box_size;
+        Err(ParsingErrorState::new(
+            ParsingError::ClearAndSkip(size),
+            None,
+        ))
+    }
+
+    /// Returns the root node, `res` is the result of `load_and_parse`.
+    pub fn finish(mut self, mime: Mime, res: Result<(), ParsedError>) -> DumpNode {
+        let mut root = DumpNode::new(DumpKind::File, mime_name(mime), 0, self.offset);
+        match res {
+            Ok(()) | Err(ParsedError::NoEnoughBytes) => (),
+            // Reached the end of the file
+            Err(ParsedError::IOError(e)) if e.kind() == io::ErrorKind::UnexpectedEof => (),
+            Err(e) => root.note = Some(e.to_string()),
+        }
+        if let Some(node) = self.pending.take() {
+            self.nodes.push(node);
+        }
+        root.children = self.nodes;
+        root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testkit::open_sample, MediaParser, MediaSource};
+    use test_case::test_case;
+
+    #[test_case("exif.jpg", DumpKind::Entry, "Make", Some("vivo"))]
+    #[test_case("exif.jpg", DumpKind::Segment, "APP1", Some("Exif"))]
+    #[test_case("exif.heic", DumpKind::Entry, "Make", Some("Apple"))]
+    #[test_case("exif.heic", DumpKind::Box, "iinf", None)]
+    #[test_case("tif.tif", DumpKind::Ifd, "ifd0", None)]
+    #[test_case("meta.mov", DumpKind::Box, "mdat", None)]
+    #[test_case("meta.mov", DumpKind::Box, "mvhd", None)]
+    #[test_case("meta.mp4", DumpKind::Box, "tkhd", None)]
+    fn debug_dump(path: &str, kind: DumpKind, name: &str, note: Option<&str>) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let mut parser = MediaParser::new();
+        let ms = MediaSource::unseekable(open_sample(path).unwrap()).unwrap();
+        let root = parser.debug_dump(ms).unwrap();
+        assert_eq!(root.kind, DumpKind::File);
+
+        let node = root.find(kind, name).unwrap();
+        if let Some(note) = note {
+            assert_eq!(node.note.as_deref(), Some(note));
+        }
+        assert!(root.to_string().contains(&format!("{kind} {name} @0x")));
+    }
+
+    #[test]
+    fn truncated() {
+        let data = [
+            0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10, b'J', b'F', b'I', b'F', 0xFF, 0xE1,
+        ];
+        let nodes = dump_jpeg(&data);
+        assert_eq!(nodes[0].name, "SOI");
+        assert_eq!(
+            nodes[1].note.as_deref(),
+            Some("truncated, 10 bytes available")
+        );
+
+        let mut scanner = BoxScanner::default();
+        let data = [0, 0, 0, 0x20, b'm', b'o', b'o', b'v', 0, 0];
+        assert!(matches!(
+            scanner.scan(&data).unwrap_err().err,
+            ParsingError::Need(22)
+        ));
+        let root = scanner.finish(Mime::Video(MimeVideo::Mp4), Ok(()));
+        assert_eq!(root.children[0].note.as_deref(), Some("truncated"));
+    }
+}
@@ -1,4 +1,5 @@
 pub(crate) mod element;
 pub(crate) mod webm;
+pub(crate) mod writer;
 
 mod vint;
@@ -198,7 +198,7 @@ pub(crate) fn next_element_header(
     })
 }
 
-fn get_cstr(cursor: &mut Cursor<&[u8]>, size: usize) -> Option<String> {
+pub(crate) fn get_cstr(cursor: &mut Cursor<&[u8]>, size: usize) -> Option<String> {
     if cursor.remaining() < size {
         return None;
     }
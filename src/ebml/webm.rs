@@ -1,21 +1,22 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, VecDeque},
     fmt::Debug,
     io::{BufRead, Cursor},
 };
 
 use bytes::Buf;
-use chrono::{DateTime, NaiveDate, Utc};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
 use nom::{error::ErrorKind, multi::many_till};
 use thiserror::Error;
 
 use crate::{
     ebml::element::{
-        find_element_by_id, get_as_f64, get_as_u64, next_element_header, parse_ebml_doc_type,
-        EBMLGlobalId, TopElementId,
+        find_element_by_id, get_as_f64, get_as_u64, get_cstr, next_element_header,
+        parse_ebml_doc_type, EBMLGlobalId, TopElementId,
     },
     error::ParsingError,
-    video::{TrackInfo, TrackInfoTag},
+    parser_core::Limits,
+    video::{Chapter, TrackInfo, TrackInfoTag},
 };
 
 use super::{
@@ -31,20 +32,60 @@ pub struct EbmlFileInfo {
     doc_type: String,
     segment_info: SegmentInfo,
     tracks_info: TracksInfo,
+    tags: FileTags,
+    chapters: Vec<Chapter>,
 }
 
 impl From<EbmlFileInfo> for TrackInfo {
     fn from(value: EbmlFileInfo) -> Self {
         let mut info = TrackInfo::default();
-        if let Some(date) = value.segment_info.date {
+        if let Some(date) = value.segment_info.date.or(value.tags.date_recorded) {
             info.put(TrackInfoTag::CreateDate, date.into());
         }
+        if let Some(title) = value.tags.title {
+            info.put(TrackInfoTag::Title, title.into());
+        }
+        if let Some(encoder) = value.tags.encoder {
+            info.put(TrackInfoTag::Software, encoder.into());
+        }
         info.put(
             TrackInfoTag::DurationMs,
             ((value.segment_info.duration / 1000.0 / 1000.0) as u64).into(),
         );
-        info.put(TrackInfoTag::ImageWidth, value.tracks_info.width.into());
-        info.put(TrackInfoTag::ImageHeight, value.tracks_info.height.into());
+        let tracks = value.tracks_info;
+        info.put(TrackInfoTag::ImageWidth, tracks.video.width.into());
+        info.put(TrackInfoTag::ImageHeight, tracks.video.height.into());
+        if let Some(projection) = tracks.video.projection {
+            let name = match projection {
+                0 => "rectangular".to_owned(),
+                1 => "equirectangular".to_owned(),
+                2 => "cubemap".to_owned(),
+                3 => "mesh".to_owned(),
+                x => x.to_string(),
+            };
+            info.put(TrackInfoTag::Projection, name.into());
+        }
+        let colour = tracks.video.colour;
+        let codes = [
+            (TrackInfoTag::ColorPrimaries, colour.primaries),
+            (
+                TrackInfoTag::TransferCharacteristics,
+                colour.transfer_characteristics,
+            ),
+            (TrackInfoTag::MatrixCoefficients, colour.matrix_coefficients),
+        ];
+        for (tag, code) in codes {
+            if let Some(code) = code.and_then(|x| u32::try_from(x).ok()) {
+                info.put(tag, code.into());
+            }
+        }
+        if !tracks.block_additions.is_empty() {
+            info.put(
+                TrackInfoTag::BlockAdditionMapping,
+                tracks.block_additions.join(", ").into(),
+            );
+        }
+        info.chapters = value.chapters;
         info
     }
 }
@@ -100,6 +141,7 @@ pub(crate) fn parse_webm(input: &[u8]) -> Result<EbmlFileInfo, ParsingError> {
     if let Ok(seeks) = parse_seeks(input, pos) {
         let info_seek = seeks.get(&(SegmentId::Info as u32)).cloned();
         let tracks_seek = seeks.get(&(SegmentId::Tracks as u32)).cloned();
+        let chapters_seek = seeks.get(&(SegmentId::Chapters as u32)).cloned();
         if let Some(pos) = info_seek {
             let info = parse_segment_info(input, pos as usize)?;
             tracing::debug!(?info);
@@ -116,6 +158,12 @@ pub(crate) fn parse_webm(input: &[u8]) -> Result<EbmlFileInfo, ParsingError> {
                 file_info.tracks_info = info;
             }
         }
+        // Chapters are optional, ignore them if they are not loaded
+        if let Some(data) = chapters_seek.and_then(|pos| input.get(pos as usize..)) {
+            if let Ok(chapters) = parse_chapters(data) {
+                file_info.chapters = chapters;
+            }
+        }
     }
 
     if !info_set {
@@ -153,10 +201,191 @@ pub(crate) fn parse_webm(input: &[u8]) -> Result<EbmlFileInfo, ParsingError> {
     Ok(file_info)
 }
 
+/// Parses EBML based files by following the `SeekHead` index, to be used
+/// with `load_and_parse`.
+///
+/// The `Info`, `Tracks`, `Tags` and `Chapters` elements are located with the
+/// index, and
+/// then reached by `ClearAndSkip` (which seeks on seekable sources), so that
+/// the (possibly huge) `Cluster`s before them are not read. Files without a
+/// usable index are parsed linearly by [`parse_webm`].
+#[derive(Debug)]
+pub(crate) struct WebmParser {
+    /// File offset of the data being parsed
+    offset: u64,
+    /// Elements to be parsed, sorted by file offset. `None` if the header
+    /// hasn't been parsed yet.
+    targets: Option<VecDeque<(u64, SegmentId)>>,
+    info: EbmlFileInfo,
+    /// Elements larger than this are not loaded, see
+    /// [`MediaParserBuilder::max_ebml_element_size`](crate::MediaParserBuilder::max_ebml_element_size)
+    max_element_size: usize,
+}
+
+impl Default for WebmParser {
+    fn default() -> Self {
+        Self::new(Limits::default().max_ebml_element_size)
+    }
+}
+
+impl WebmParser {
+    pub fn new(max_element_size: usize) -> Self {
+        Self {
+            offset: 0,
+            targets: None,
+            info: EbmlFileInfo::default(),
+            max_element_size,
+        }
+    }
+
+    pub fn parse(&mut self, input: &[u8]) -> Result<EbmlFileInfo, ParsingError> {
+        let Some(targets) = self.targets.as_mut() else {
+            match self.parse_header(input)? {
+                Some(targets) => self.targets = Some(targets),
+                None => return parse_webm(input),
+            }
+            return self.seek_next();
+        };
+
+        let Some(&(_, id)) = targets.front() else {
+            return Ok(std::mem::take(&mut self.info));
+        };
+        let header = next_element_header(&mut Cursor::new(input))?;
+        if header.id != id as u64 {
+            tracing::debug!(?header, ?id, "unexpected element at the seek position");
+            targets.pop_front();
+            return self.seek_next();
+        }
+        if header.data_size > self.max_element_size {
+            tracing::warn!(
+                ?id,
+                size = header.data_size,
+                "element is too large, skip it"
+            );
+            targets.pop_front();
+            return self.seek_next();
+        }
+        match id {
+            SegmentId::Info => {
+                if let Some(info) = parse_segment_info(input, 0)? {
+                    self.info.segment_info = info;
+                }
+            }
+            SegmentId::Tracks => {
+                if let Some(info) = parse_tracks_info(input, 0)? {
+                    self.info.tracks_info = info;
+                }
+            }
+            SegmentId::Tags => self.info.tags = parse_tags(input)?,
+            SegmentId::Chapters => self.info.chapters = parse_chapters(input)?,
+            _ => (),
+        }
+        targets.pop_front();
+        self.seek_next()
+    }
+
+    /// Parses the EBML header & the `SeekHead` at the beginning of the
+    /// `Segment`, returns the elements to be parsed, or `None` if there is no
+    /// usable index.
+    fn parse_header(
+        &mut self,
+        input: &[u8],
+    ) -> Result<Option<VecDeque<(u64, SegmentId)>>, ParsingError> {
+        let mut cursor = Cursor::new(input);
+        let doc_type = parse_ebml_doc_type(&mut cursor)?;
+        let header = next_element_header(&mut cursor)?;
+        if header.id != TopElementId::Segment as u64 {
+            return Err(ParseWebmFailed::NotWebmFile.into());
+        }
+        let segment_pos = cursor.position();
+
+        // The SeekHead should be the first element of the Segment, except
+        // for Void & CRC-32 elements
+        let header = loop {
+            let header = next_element_header(&mut cursor)?;
+            if header.id != EBMLGlobalId::Void as u64 && header.id != EBMLGlobalId::Crc32 as u64 {
+                break header;
+            }
+            self.check_size(&header)?;
+            if cursor.remaining() < header.data_size {
+                return Err(ParsingError::Need(header.data_size - cursor.remaining()));
+            }
+            cursor.consume(header.data_size);
+        };
+        if header.id != SegmentId::SeekHead as u64 {
+            tracing::debug!(?header, "SeekHead not found");
+            return Ok(None);
+        }
+        self.check_size(&header)?;
+        if cursor.remaining() < header.data_size {
+            return Err(ParsingError::Need(header.data_size - cursor.remaining()));
+        }
+        let mut cur = Cursor::new(&cursor.chunk()[..header.data_size]);
+        let seeks = parse_seek_head(&mut cur)?;
+        let end = cursor.position() + header.data_size as u64;
+
+        // Seek positions are relative to the beginning of the Segment data
+        let mut targets = VecDeque::new();
+        for id in [
+            SegmentId::Info,
+            SegmentId::Tracks,
+            SegmentId::Tags,
+            SegmentId::Chapters,
+        ] {
+            let Some(pos) = seeks
+                .get(&(id as u32))
+                .and_then(|x| x.checked_add(segment_pos))
+            else {
+                continue;
+            };
+            if pos < end {
+                tracing::debug!(?id, pos, "invalid seek position");
+                return Ok(None);
+            }
+            targets.push_back((pos, id));
+        }
+        if !targets.iter().any(|x| x.1 == SegmentId::Info)
+            || !targets.iter().any(|x| x.1 == SegmentId::Tracks)
+        {
+            tracing::debug!(?seeks, "Info or Tracks is not indexed");
+            return Ok(None);
+        }
+
+        targets.make_contiguous().sort_by_key(|x| x.0);
+        tracing::debug!(?targets);
+        self.info.doc_type = doc_type;
+        Ok(Some(targets))
+    }
+
+    fn check_size(&self, header: &ElementHeader) -> Result<(), ParsingError> {
+        if header.data_size > self.max_element_size {
+            return Err(ParsingError::Failed(format!(
+                "EBML element is too large: {} bytes",
+                header.data_size
+            )));
+        }
+        Ok(())
+    }
+
+    /// Skips to the next element to be parsed, or returns the parsed info if
+    /// all elements have been parsed.
+    fn seek_next(&mut self) -> Result<EbmlFileInfo, ParsingError> {
+        let next = self.targets.as_ref().and_then(|x| x.front()).map(|x| x.0);
+        let Some(pos) = next else {
+            return Ok(std::mem::take(&mut self.info));
+        };
+        let n = usize::try_from(pos - self.offset)
+            .map_err(|_| ParsingError::Failed("seek position is too big".into()))?;
+        self.offset = pos;
+        Err(ParsingError::ClearAndSkip(n))
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 struct TracksInfo {
-    width: u32,
-    height: u32,
+    video: VideoTrackInfo,
+    /// Types (or names) of the `BlockAdditionMapping`s of the video track
+    block_additions: Vec<String>,
 }
 
 #[tracing::instrument(skip(input))]
@@ -195,12 +424,7 @@ fn parse_tracks_info(input: &[u8], pos: usize) -> Result<Option<TracksInfo>, Par
                 return Err(nom::Err::Error((Z, ErrorKind::Fail)));
             }
 
-            let track = parse_track(&cursor.chunk()[..header.data_size]).map(|x| {
-                x.map(|x| TracksInfo {
-                    width: x.width,
-                    height: x.height,
-                })
-            })?;
+            let track = parse_track(&cursor.chunk()[..header.data_size])?;
 
             Ok((Z, track))
         },
@@ -237,8 +461,10 @@ fn parse_tracks_info(input: &[u8], pos: usize) -> Result<Option<TracksInfo>, Par
     // }
 }
 
-fn parse_track(input: &[u8]) -> Result<Option<VideoTrackInfo>, ParseWebmFailed> {
+fn parse_track(input: &[u8]) -> Result<Option<TracksInfo>, ParseWebmFailed> {
     let mut cursor = Cursor::new(input);
+    let mut video = None;
+    let mut block_additions = Vec::new();
 
     while cursor.has_remaining() {
         let header = next_element_header(&mut cursor)?;
@@ -253,10 +479,22 @@ fn parse_track(input: &[u8]) -> Result<Option<VideoTrackInfo>, ParseWebmFailed>
         };
 
         if id == TracksId::VideoTrack {
-            return parse_video_track(&input[pos..pos + header.data_size]);
+            let Some(data) = input.get(pos..pos.saturating_add(header.data_size)) else {
+                return Err(ParseWebmFailed::InvalidWebmFile(
+                    "video track is too big".into(),
+                ));
+            };
+            video = parse_video_track(data)?;
+        } else if id == TracksId::BlockAdditionMapping {
+            if let Some(data) = input.get(pos..pos.saturating_add(header.data_size)) {
+                block_additions.extend(parse_block_addition_mapping(data));
+            }
         }
     }
-    Ok(None)
+    Ok(video.map(|video| TracksInfo {
+        video,
+        block_additions,
+    }))
 }
 
 fn parse_video_track(input: &[u8]) -> Result<Option<VideoTrackInfo>, ParseWebmFailed> {
@@ -277,6 +515,18 @@ fn parse_video_track(input: &[u8]) -> Result<Option<VideoTrackInfo>, ParseWebmFa
         info.height = v as u32;
     }
 
+    for (id, data) in children(input) {
+        if id == TracksId::Colour as u64 {
+            info.colour = parse_colour(data);
+        } else if id == TracksId::Projection as u64 {
+            // ProjectionType defaults to 0 (rectangular)
+            let projection_type = children(data)
+                .find(|x| x.0 == TracksId::ProjectionType as u64)
+                .and_then(|x| as_u64(x.1));
+            info.projection = Some(projection_type.unwrap_or_default());
+        }
+    }
+
     if info == VideoTrackInfo::default() {
         Ok(None)
     } else {
@@ -288,6 +538,71 @@ fn parse_video_track(input: &[u8]) -> Result<Option<VideoTrackInfo>, ParseWebmFa
 struct VideoTrackInfo {
     width: u32,
     height: u32,
+    colour: Colour,
+    /// `ProjectionType`, e.g. 1 for equirectangular (360) videos
+    projection: Option<u64>,
+}
+
+/// `Colour` of a video track, the values are defined in ITU-T H.273, e.g.:
+/// `TransferCharacteristics` 16 (PQ) or 18 (HLG) for HDR videos.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Colour {
+    matrix_coefficients: Option<u64>,
+    transfer_characteristics: Option<u64>,
+    primaries: Option<u64>,
+}
+
+fn parse_colour(input: &[u8]) -> Colour {
+    let mut colour = Colour::default();
+    for (id, data) in children(input) {
+        let field = match id {
+            x if x == TracksId::MatrixCoefficients as u64 => &mut colour.matrix_coefficients,
+            x if x == TracksId::TransferCharacteristics as u64 => {
+                &mut colour.transfer_characteristics
+            }
+            x if x == TracksId::Primaries as u64 => &mut colour.primaries,
+            _ => continue,
+        };
+        // 2 means unspecified
+        *field = as_u64(data).filter(|x| *x != 2);
+    }
+    colour
+}
+
+/// Returns `BlockAddIDType` as a FourCC (e.g. "dvcC" for Dolby Vision), or
+/// `BlockAddIDName` if the type isn't a FourCC.
+fn parse_block_addition_mapping(input: &[u8]) -> Option<String> {
+    let mut fourcc = None;
+    let mut name = None;
+    for (id, data) in children(input) {
+        if id == TracksId::BlockAddIdType as u64 {
+            fourcc = as_u64(data)
+                .and_then(|x| u32::try_from(x).ok())
+                .map(u32::to_be_bytes)
+                .filter(|x| x.iter().all(u8::is_ascii_alphanumeric))
+                .map(|x| String::from_utf8_lossy(&x).into_owned());
+        } else if id == TracksId::BlockAddIdName as u64 {
+            name = get_cstr(&mut Cursor::new(data), data.len()).filter(|x| !x.is_empty());
+        }
+    }
+    fourcc.or(name)
+}
+
+/// Iterates over the child elements in `input`, yields `(id, data)`, stops
+/// at the first invalid element.
+pub(crate) fn children(input: &[u8]) -> impl Iterator<Item = (u64, &[u8])> {
+    let mut cursor = Cursor::new(input);
+    std::iter::from_fn(move || {
+        let header = next_element_header(&mut cursor).ok()?;
+        let pos = cursor.position() as usize;
+        let data = input.get(pos..pos.saturating_add(header.data_size))?;
+        cursor.consume(header.data_size);
+        Some((header.id, data))
+    })
+}
+
+fn as_u64(data: &[u8]) -> Option<u64> {
+    get_as_u64(&mut Cursor::new(data), data.len())
 }
 
 #[derive(Debug, Clone, Default)]
@@ -363,6 +678,200 @@ fn parse_segment_info_body(cursor: &mut Cursor<&[u8]>) -> Result<SegmentInfo, Pa
     Ok(info)
 }
 
+/// File level tags, i.e. the tags which don't target a track, chapter, etc.
+#[derive(Debug, Clone, Default)]
+struct FileTags {
+    title: Option<String>,
+    encoder: Option<String>,
+    date_recorded: Option<DateTime<Utc>>,
+}
+
+#[tracing::instrument(skip(input))]
+fn parse_tags(input: &[u8]) -> Result<FileTags, ParsingError> {
+    let mut cursor = Cursor::new(input);
+    let header = next_element_header(&mut cursor)?;
+    tracing::debug!(tags_header = ?header);
+
+    if cursor.remaining() < header.data_size {
+        return Err(ParsingError::Need(header.data_size - cursor.remaining()));
+    }
+
+    let mut tags = FileTags::default();
+    let body = &cursor.chunk()[..header.data_size];
+    let mut cursor = Cursor::new(body);
+    while cursor.has_remaining() {
+        // Don't bubble Need error to caller here, since the data is complete
+        let Ok(header) = next_element_header(&mut cursor) else {
+            break;
+        };
+        let pos = cursor.position() as usize;
+        let Some(data) = body.get(pos..pos.saturating_add(header.data_size)) else {
+            break;
+        };
+        cursor.consume(header.data_size);
+
+        if header.id == TagsId::Tag as u64 {
+            parse_tag(data, &mut tags);
+        }
+    }
+
+    Ok(tags)
+}
+
+fn parse_tag(input: &[u8], tags: &mut FileTags) {
+    let mut simple_tags = Vec::new();
+    let mut cursor = Cursor::new(input);
+    while cursor.has_remaining() {
+        let Ok(header) = next_element_header(&mut cursor) else {
+            return;
+        };
+        let pos = cursor.position() as usize;
+        let Some(data) = input.get(pos..pos.saturating_add(header.data_size)) else {
+            return;
+        };
+        cursor.consume(header.data_size);
+
+        if header.id == TagsId::Targets as u64 {
+            if targets_any_uid(data) {
+                return;
+            }
+        } else if header.id == TagsId::SimpleTag as u64 {
+            simple_tags.extend(parse_simple_tag(data));
+        }
+    }
+
+    for (name, value) in simple_tags {
+        match name.as_str() {
+            "TITLE" => tags.title = Some(value),
+            "ENCODER" => tags.encoder = Some(value),
+            "DATE_RECORDED" => {
+                tags.date_recorded = ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%d %H:%M:%S"]
+                    .iter()
+                    .find_map(|fmt| NaiveDateTime::parse_from_str(&value, fmt).ok())
+                    .map(|x| x.and_utc());
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Returns true if the `Targets` element targets a track, chapter, etc.
+pub(crate) fn targets_any_uid(input: &[u8]) -> bool {
+    let mut cursor = Cursor::new(input);
+    while cursor.has_remaining() {
+        let Ok(header) = next_element_header(&mut cursor) else {
+            return false;
+        };
+        if header.id != TagsId::TargetTypeValue as u64 && header.id != TagsId::TargetType as u64 {
+            if let Some(uid) = get_as_u64(&mut cursor, header.data_size) {
+                if uid != 0 {
+                    return true;
+                }
+            }
+            continue;
+        }
+        cursor.consume(std::cmp::min(cursor.remaining(), header.data_size));
+    }
+    false
+}
+
+fn parse_simple_tag(input: &[u8]) -> Option<(String, String)> {
+    let mut name = None;
+    let mut value = None;
+    let mut cursor = Cursor::new(input);
+    while cursor.has_remaining() {
+        let header = next_element_header(&mut cursor).ok()?;
+        if header.id == TagsId::TagName as u64 {
+            name = Some(get_cstr(&mut cursor, header.data_size)?);
+        } else if header.id == TagsId::TagString as u64 {
+            value = Some(get_cstr(&mut cursor, header.data_size)?);
+        } else {
+            cursor.consume(std::cmp::min(cursor.remaining(), header.data_size));
+        }
+    }
+    Some((name?, value?))
+}
+
+/// Max nesting depth of `ChapterAtom`s which are parsed.
+const MAX_CHAPTER_DEPTH: usize = 8;
+
+#[tracing::instrument(skip(input))]
+fn parse_chapters(input: &[u8]) -> Result<Vec<Chapter>, ParsingError> {
+    let mut cursor = Cursor::new(input);
+    let header = next_element_header(&mut cursor)?;
+    tracing::debug!(chapters_header = ?header);
+
+    if cursor.remaining() < header.data_size {
+        return Err(ParsingError::Need(header.data_size - cursor.remaining()));
+    }
+
+    let mut chapters = Vec::new();
+    let body = &cursor.chunk()[..header.data_size];
+    let editions = children(body).filter(|x| x.0 == ChaptersId::EditionEntry as u64);
+    for (edition, (_, data)) in editions.enumerate() {
+        let hidden = children(data)
+            .any(|(id, x)| id == ChaptersId::EditionFlagHidden as u64 && as_u64(x) == Some(1));
+        if hidden {
+            continue;
+        }
+        for (id, data) in children(data) {
+            if id == ChaptersId::ChapterAtom as u64 {
+                parse_chapter_atom(data, edition, 0, &mut chapters);
+            }
+        }
+    }
+    Ok(chapters)
+}
+
+/// Parses a `ChapterAtom`, and the nested `ChapterAtom`s in it.
+fn parse_chapter_atom(input: &[u8], edition: usize, depth: usize, chapters: &mut Vec<Chapter>) {
+    let mut chapter = Chapter {
+        edition,
+        start_ms: 0,
+        end_ms: None,
+        title: None,
+    };
+    let mut visible = true;
+    let mut nested = Vec::new();
+    for (id, data) in children(input) {
+        match id {
+            // In nanoseconds
+            x if x == ChaptersId::ChapterTimeStart as u64 => {
+                chapter.start_ms = as_u64(data).unwrap_or_default() / 1_000_000;
+            }
+            x if x == ChaptersId::ChapterTimeEnd as u64 => {
+                chapter.end_ms = as_u64(data).map(|x| x / 1_000_000);
+            }
+            x if x == ChaptersId::ChapterFlagHidden as u64 => visible &= as_u64(data) != Some(1),
+            x if x == ChaptersId::ChapterFlagEnabled as u64 => visible &= as_u64(data) != Some(0),
+            x if x == ChaptersId::ChapterDisplay as u64 && chapter.title.is_none() => {
+                chapter.title = children(data)
+                    .find(|x| x.0 == ChaptersId::ChapString as u64)
+                    .map(|x| utf8_string(x.1));
+            }
+            x if x == ChaptersId::ChapterAtom as u64 => nested.push(data),
+            _ => (),
+        }
+    }
+
+    // Nested chapters of a hidden chapter are hidden too
+    if !visible {
+        return;
+    }
+    chapters.push(chapter);
+    if depth < MAX_CHAPTER_DEPTH {
+        for data in nested {
+            parse_chapter_atom(data, edition, depth + 1, chapters);
+        }
+    }
+}
+
+/// Converts a UTF-8 string element, which may be null-padded.
+fn utf8_string(data: &[u8]) -> String {
+    let end = data.iter().position(|x| *x == 0).unwrap_or(data.len());
+    String::from_utf8_lossy(&data[..end]).into_owned()
+}
+
 fn parse_seeks(input: &[u8], pos: usize) -> Result<HashMap<u32, u64>, ParsingError> {
     let mut cursor = Cursor::new(&input[pos..]);
     // find SeekHead element
@@ -402,7 +911,9 @@ impl Debug for SeekEntry {
 }
 
 #[tracing::instrument(skip_all)]
-fn parse_seek_head(input: &mut Cursor<&[u8]>) -> Result<HashMap<u32, u64>, ParseWebmFailed> {
+pub(crate) fn parse_seek_head(
+    input: &mut Cursor<&[u8]>,
+) -> Result<HashMap<u32, u64>, ParseWebmFailed> {
     let mut entries = HashMap::new();
     while input.has_remaining() {
         match parse_seek_entry(input) {
@@ -480,17 +991,43 @@ fn parse_seek_entry(input: &mut Cursor<&[u8]>) -> Result<Option<SeekEntry>, Pars
     Ok(Some(SeekEntry { seek_id, seek_pos }))
 }
 
-#[derive(Debug, Clone, Copy)]
-enum SegmentId {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SegmentId {
     SeekHead = 0x114D9B74,
     Info = 0x1549A966,
     Tracks = 0x1654AE6B,
     Cluster = 0x1F43B675,
     Cues = 0x1C53BB6B,
+    Tags = 0x1254C367,
+    Chapters = 0x1043A770,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ChaptersId {
+    EditionEntry = 0x45B9,
+    EditionFlagHidden = 0x45BD,
+    ChapterAtom = 0xB6,
+    ChapterTimeStart = 0x91,
+    ChapterTimeEnd = 0x92,
+    ChapterFlagHidden = 0x98,
+    ChapterFlagEnabled = 0x4598,
+    ChapterDisplay = 0x80,
+    ChapString = 0x85,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum TagsId {
+    Tag = 0x7373,
+    Targets = 0x63C0,
+    TargetTypeValue = 0x68CA,
+    TargetType = 0x63CA,
+    SimpleTag = 0x67C8,
+    TagName = 0x45A3,
+    TagString = 0x4487,
 }
 
 #[derive(Debug, Clone, Copy)]
-enum InfoId {
+pub(crate) enum InfoId {
     TimestampScale = 0x2AD7B1,
     Duration = 0x4489,
     Date = 0x4461,
@@ -503,6 +1040,15 @@ enum TracksId {
     VideoTrack = 0xE0,
     PixelWidth = 0xB0,
     PixelHeight = 0xBA,
+    Colour = 0x55B0,
+    MatrixCoefficients = 0x55B1,
+    TransferCharacteristics = 0x55BA,
+    Primaries = 0x55BB,
+    Projection = 0x7670,
+    ProjectionType = 0x7671,
+    BlockAdditionMapping = 0x41E4,
+    BlockAddIdName = 0x41A4,
+    BlockAddIdType = 0x41E7,
 }
 
 impl TryFrom<u64> for TracksId {
@@ -514,6 +1060,15 @@ impl TryFrom<u64> for TracksId {
             x if x == Self::VideoTrack as u64 => Self::VideoTrack,
             x if x == Self::PixelWidth as u64 => Self::PixelWidth,
             x if x == Self::PixelHeight as u64 => Self::PixelHeight,
+            x if x == Self::Colour as u64 => Self::Colour,
+            x if x == Self::MatrixCoefficients as u64 => Self::MatrixCoefficients,
+            x if x == Self::TransferCharacteristics as u64 => Self::TransferCharacteristics,
+            x if x == Self::Primaries as u64 => Self::Primaries,
+            x if x == Self::Projection as u64 => Self::Projection,
+            x if x == Self::ProjectionType as u64 => Self::ProjectionType,
+            x if x == Self::BlockAdditionMapping as u64 => Self::BlockAdditionMapping,
+            x if x == Self::BlockAddIdName as u64 => Self::BlockAddIdName,
+            x if x == Self::BlockAddIdType as u64 => Self::BlockAddIdType,
             o => return Err(UnknowEbmlIDError(o)),
         };
         Ok(id)
@@ -534,7 +1089,7 @@ impl TryFrom<u64> for InfoId {
 }
 
 #[derive(Debug, Clone, Copy)]
-enum SeekHeadId {
+pub(crate) enum SeekHeadId {
     Seek = 0x4DBB,
     SeekId = 0x53AB,
     SeekPosition = 0x53AC,
@@ -549,6 +1104,8 @@ impl TryFrom<u64> for SegmentId {
             x if x == Self::Tracks as u64 => Self::Tracks,
             x if x == Self::Cluster as u64 => Self::Cluster,
             x if x == Self::Cues as u64 => Self::Cues,
+            x if x == Self::Tags as u64 => Self::Tags,
+            x if x == Self::Chapters as u64 => Self::Chapters,
             o => return Err(UnknowEbmlIDError(o)),
         };
         Ok(id)
@@ -639,3 +1196,158 @@ impl From<ParseWebmFailed> for nom::Err<(&[u8], ErrorKind)> {
         nom::Err::Error((&[], ErrorKind::Fail))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    fn element(id: u32, data: &[u8]) -> Vec<u8> {
+        let mut element: Vec<u8> = id
+            .to_be_bytes()
+            .into_iter()
+            .skip_while(|x| *x == 0)
+            .collect();
+        // 8-byte data size
+        element.push(0x01);
+        element.extend(&(data.len() as u64).to_be_bytes()[1..]);
+        element.extend(data);
+        element
+    }
+
+    fn uint(id: TracksId, v: u32) -> Vec<u8> {
+        element(id as u32, &v.to_be_bytes())
+    }
+
+    fn chapter_atom(start_ms: u64, title: &str, extra: &[Vec<u8>]) -> Vec<u8> {
+        let mut atom = [
+            element(
+                ChaptersId::ChapterTimeStart as u32,
+                &(start_ms * 1_000_000).to_be_bytes(),
+            ),
+            element(
+                ChaptersId::ChapterDisplay as u32,
+                &element(ChaptersId::ChapString as u32, title.as_bytes()),
+            ),
+        ]
+        .concat();
+        atom.extend(extra.concat());
+        element(ChaptersId::ChapterAtom as u32, &atom)
+    }
+
+    #[test]
+    fn chapters() {
+        let hidden = element(ChaptersId::ChapterFlagHidden as u32, &[1]);
+        let end = element(ChaptersId::ChapterTimeEnd as u32, &[0x3B, 0x9A, 0xCA, 0x00]);
+        let edition = [
+            chapter_atom(0, "Intro", &[end]),
+            chapter_atom(
+                1000,
+                "Part 1",
+                &[
+                    chapter_atom(1000, "Part 1.1", &[]),
+                    chapter_atom(1500, "Hidden", &[hidden]),
+                ],
+            ),
+            chapter_atom(2000, "Crédits", &[]),
+        ];
+        let hidden_edition = [
+            element(ChaptersId::EditionFlagHidden as u32, &[1]),
+            chapter_atom(0, "Hidden edition", &[]),
+        ];
+        let editions = [
+            element(ChaptersId::EditionEntry as u32, &edition.concat()),
+            element(ChaptersId::EditionEntry as u32, &hidden_edition.concat()),
+            element(
+                ChaptersId::EditionEntry as u32,
+                &chapter_atom(0, "Director's cut", &[]),
+            ),
+        ];
+        let data = element(SegmentId::Chapters as u32, &editions.concat());
+
+        let chapters = parse_chapters(&data).unwrap();
+        let summary = chapters
+            .iter()
+            .map(|x| (x.edition, x.start_ms, x.end_ms, x.title.as_deref().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            [
+                (0, 0, Some(1000), "Intro"),
+                (0, 1000, None, "Part 1"),
+                (0, 1000, None, "Part 1.1"),
+                (0, 2000, None, "Crédits"),
+                (2, 0, None, "Director's cut"),
+            ]
+        );
+    }
+
+    #[test_case(true; "hdr 360")]
+    #[test_case(false; "sdr")]
+    fn video_track(hdr: bool) {
+        let mut video = [
+            uint(TracksId::PixelWidth, 3840),
+            uint(TracksId::PixelHeight, 1920),
+        ]
+        .concat();
+        let mut entry = vec![];
+        if hdr {
+            let colour = [
+                uint(TracksId::MatrixCoefficients, 9),
+                uint(TracksId::TransferCharacteristics, 16),
+                uint(TracksId::Primaries, 2),
+            ];
+            video.extend(element(TracksId::Colour as u32, &colour.concat()));
+            video.extend(element(
+                TracksId::Projection as u32,
+                &uint(TracksId::ProjectionType, 1),
+            ));
+            let mapping = [
+                element(TracksId::BlockAddIdName as u32, b"Dolby Vision"),
+                uint(TracksId::BlockAddIdType, u32::from_be_bytes(*b"dvcC")),
+            ];
+            entry.extend(element(
+                TracksId::BlockAdditionMapping as u32,
+                &mapping.concat(),
+            ));
+        }
+        entry.extend(element(TracksId::VideoTrack as u32, &video));
+        let tracks = element(
+            SegmentId::Tracks as u32,
+            &element(TracksId::TrackEntry as u32, &entry),
+        );
+
+        let tracks_info = parse_tracks_info(&tracks, 0).unwrap().unwrap();
+        let info: TrackInfo = EbmlFileInfo {
+            tracks_info,
+            ..Default::default()
+        }
+        .into();
+
+        assert_eq!(info.get(TrackInfoTag::ImageWidth), Some(&3840_u32.into()));
+        if hdr {
+            assert_eq!(
+                info.get(TrackInfoTag::Projection),
+                Some(&"equirectangular".into())
+            );
+            assert_eq!(
+                info.get(TrackInfoTag::TransferCharacteristics),
+                Some(&16_u32.into())
+            );
+            assert_eq!(
+                info.get(TrackInfoTag::MatrixCoefficients),
+                Some(&9_u32.into())
+            );
+            // Unspecified
+            assert_eq!(info.get(TrackInfoTag::ColorPrimaries), None);
+            assert_eq!(
+                info.get(TrackInfoTag::BlockAdditionMapping),
+                Some(&"dvcC".into())
+            );
+        } else {
+            assert_eq!(info.get(TrackInfoTag::Projection), None);
+            assert_eq!(info.get(TrackInfoTag::TransferCharacteristics), None);
+            assert_eq!(info.get(TrackInfoTag::BlockAdditionMapping), None);
+        }
+    }
+}
@@ -0,0 +1,614 @@
+use std::collections::HashMap;
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+
+use chrono::{DateTime, FixedOffset, NaiveDate, Utc};
+
+use crate::{
+    file::{Mime, MimeVideo},
+    patch::{read_at, sniff_mime},
+    Error,
+};
+
+use super::{
+    element::{next_element_header, EBMLGlobalId, TopElementId},
+    webm::{children, parse_seek_head, targets_any_uid, InfoId, SeekHeadId, SegmentId, TagsId},
+};
+
+/// `TargetTypeValue` of tags which apply to the whole file.
+const TARGET_MOVIE: u8 = 50;
+
+/// Writes the file level tags (`SimpleTag`s of the `Tag` element which
+/// doesn't target a track, chapter, etc.) into Matroska/WebM (`*.mkv`,
+/// `*.webm`) files. Tags of other targets are kept as they are.
+///
+/// Only the `Tags` element is rebuilt (and the `SeekHead` index if the
+/// `Tags` element is moved), the media data is kept as it is:
+///
+/// - If the new `Tags` element fits in the space of the old one (including
+///   the `Void` elements following it), it's written in place, and the rest
+///   of the space is filled with a `Void` element.
+/// - Otherwise, the new `Tags` element is appended to the end of the
+///   `Segment` (which must be the last element of the file), the old one is
+///   turned into a `Void` element, and its position in the `SeekHead` is
+///   updated, which must fit in the space of the old `SeekHead`.
+///
+/// `CRC-32` elements of the rebuilt elements are removed.
+///
+/// ## Example
+///
+/// ```rust
+/// use nom_exif::*;
+/// use std::fs::File;
+///
+/// fn main() -> Result<()> {
+///     let mut writer = MatroskaTagWriter::new();
+///     writer.set_title("nom-exif").set_tag("ARTIST", "nom-exif authors");
+///
+///     let mut output = Vec::new();
+///     writer.write(File::open("./testdata/webm_480.webm")?, &mut output)?;
+///
+///     let ms = MediaSource::seekable(std::io::Cursor::new(output))?;
+///     let info: TrackInfo = MediaParser::new().parse(ms)?;
+///     assert_eq!(info.get(TrackInfoTag::Title), Some(&"nom-exif".into()));
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MatroskaTagWriter {
+    /// Tags to be set (or removed if the value is `None`), in the order of
+    /// setting.
+    tags: Vec<(String, Option<String>)>,
+    date: Option<DateTime<FixedOffset>>,
+}
+
+impl MatroskaTagWriter {
+    pub fn new() -> MatroskaTagWriter {
+        MatroskaTagWriter::default()
+    }
+
+    /// Sets the `TITLE` tag.
+    pub fn set_title(&mut self, title: impl Into<String>) -> &mut Self {
+        self.set_tag("TITLE", title)
+    }
+
+    /// Sets the `DATE_RECORDED` tag (in UTC), and the `DateUTC` of the
+    /// segment `Info` if it exists, which is preferred by readers.
+    pub fn set_date(&mut self, date: DateTime<FixedOffset>) -> &mut Self {
+        self.date = Some(date);
+        let text = date.with_timezone(&Utc).format("%Y-%m-%d %H:%M:%S");
+        self.set_tag("DATE_RECORDED", text.to_string())
+    }
+
+    /// Sets a `SimpleTag` of the file level tags, e.g. `ARTIST`, the tag is
+    /// added if it doesn't exist.
+    pub fn set_tag(&mut self, name: impl Into<String>, value: impl Into<String>) -> &mut Self {
+        self.edit_tag(name.into(), Some(value.into()))
+    }
+
+    /// Removes all `SimpleTag`s named `name` from the file level tags.
+    pub fn remove_tag(&mut self, name: impl Into<String>) -> &mut Self {
+        self.edit_tag(name.into(), None)
+    }
+
+    fn edit_tag(&mut self, name: String, value: Option<String>) -> &mut Self {
+        self.tags.retain(|(k, _)| *k != name);
+        self.tags.push((name, value));
+        self
+    }
+
+    /// Reads a Matroska/WebM file from `reader`, and writes it into `writer`
+    /// with the tags updated.
+    ///
+    /// Returns [`Error::WriteFailed`] if the file is not a Matroska/WebM
+    /// file, or the `Tags` element can't be placed, see
+    /// [`MatroskaTagWriter`].
+    pub fn write<R: Read, W: Write>(&self, mut reader: R, mut writer: W) -> crate::Result<()> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        let mut file = Cursor::new(data);
+        self.write_in_place(&mut file)?;
+        writer.write_all(file.get_ref())?;
+        Ok(())
+    }
+
+    /// The same as [`MatroskaTagWriter::write`], but the tags are updated in
+    /// place, i.e. only the `Tags`, `SeekHead` & `Info` elements are read &
+    /// rewritten, the `Cluster`s are skipped by seeking.
+    pub fn write_in_place<F: Read + Write + Seek>(&self, mut file: F) -> crate::Result<()> {
+        let mime = sniff_mime(&mut file)?;
+        if !matches!(mime, Mime::Video(MimeVideo::Matroska | MimeVideo::Webm)) {
+            return Err(Error::WriteFailed(
+                format!("writing Matroska tags of {mime:?} files is not supported").into(),
+            ));
+        }
+
+        let len = file.seek(SeekFrom::End(0))?;
+        let ebml = read_element(&mut file, 0, len)?;
+        let segment = read_element(&mut file, ebml.range.end, len)?;
+        if segment.id != TopElementId::Segment as u64 {
+            return Err("Segment element not found".into());
+        }
+        let seg_start = segment.data_start();
+        let seg_end = if segment.unknown_size {
+            len
+        } else {
+            segment.range.end
+        };
+
+        // Top level elements of the Segment, the scan stops at an element of
+        // unknown size (e.g. a live Cluster)
+        let mut elements = Vec::new();
+        let mut pos = seg_start;
+        while pos < seg_end {
+            let element = read_element(&mut file, pos, seg_end)?;
+            if element.unknown_size {
+                break;
+            }
+            pos = element.range.end;
+            elements.push(element);
+        }
+        let find = |id: SegmentId| elements.iter().position(|x| x.id == id as u64);
+
+        let seek_head = find(SegmentId::SeekHead).map(|i| &elements[i]);
+        let seeks = match seek_head {
+            Some(x) => {
+                let data = read_data(&mut file, x)?;
+                parse_seek_head(&mut Cursor::new(&data[..])).map_err(|e| e.to_string())?
+            }
+            None => Default::default(),
+        };
+        // Elements after an unknown-sized element are located by the index
+        let old_tags = match find(SegmentId::Tags) {
+            Some(i) => Some(elements[i].clone()),
+            None => match seeks.get(&(SegmentId::Tags as u32)) {
+                Some(pos) => Some(read_element(&mut file, seg_start + pos, seg_end)?)
+                    .filter(|x| x.id == SegmentId::Tags as u64),
+                None => None,
+            },
+        };
+        let old_data = match &old_tags {
+            Some(x) => Some(read_data(&mut file, x)?),
+            None => None,
+        };
+        if old_tags.is_some() || self.tags.iter().any(|x| x.1.is_some()) {
+            let new_tags = self.rebuild_tags(old_data.as_deref())?;
+            let layout = Layout {
+                len,
+                segment: &segment,
+                elements: &elements,
+                seek_head,
+                seeks,
+            };
+            write_tags(&mut file, layout, old_tags.as_ref(), &new_tags)?;
+        }
+
+        if let Some(date) = self.date {
+            if let Some(i) = find(SegmentId::Info) {
+                patch_info_date(&mut file, &elements[i], date)?;
+            }
+        }
+        file.flush()?;
+        Ok(())
+    }
+
+    /// Rebuilds the `Tags` element (`data` is the body of the old one), the
+    /// file level `Tag` is updated, or added if it doesn't exist.
+    fn rebuild_tags(&self, data: Option<&[u8]>) -> crate::Result<Vec<u8>> {
+        let mut body = Vec::new();
+        let mut done = false;
+        for (id, child) in data.map(children).into_iter().flatten() {
+            if id == TagsId::Tag as u64 && !done && is_file_level(child) {
+                body.extend(self.rebuild_tag(Some(child)));
+                done = true;
+            } else if id != EBMLGlobalId::Crc32 as u64 && id != EBMLGlobalId::Void as u64 {
+                body.extend(element(id, child));
+            }
+        }
+        if !done && self.tags.iter().any(|x| x.1.is_some()) {
+            body.extend(self.rebuild_tag(None));
+        }
+        Ok(element(SegmentId::Tags as u64, &body))
+    }
+
+    /// Rebuilds a file level `Tag` element (`data` is the body of the old
+    /// one), the first `SimpleTag` of each edited name is replaced, the
+    /// others are removed.
+    fn rebuild_tag(&self, data: Option<&[u8]>) -> Vec<u8> {
+        let mut body = Vec::new();
+        let mut written = Vec::new();
+        match data {
+            Some(data) => {
+                for (id, child) in children(data) {
+                    if id == EBMLGlobalId::Crc32 as u64 || id == EBMLGlobalId::Void as u64 {
+                        continue;
+                    }
+                    let edit = (id == TagsId::SimpleTag as u64)
+                        .then(|| simple_tag_name(child))
+                        .flatten()
+                        .and_then(|name| self.tags.iter().find(|x| x.0 == name));
+                    match edit {
+                        Some((name, value)) => {
+                            if let (Some(value), false) = (value, written.contains(&name)) {
+                                body.extend(simple_tag(name, value));
+                            }
+                            written.push(name);
+                        }
+                        None => body.extend(element(id, child)),
+                    }
+                }
+            }
+            None => {
+                let targets = element(TagsId::TargetTypeValue as u64, &[TARGET_MOVIE]);
+                body.extend(element(TagsId::Targets as u64, &targets));
+            }
+        }
+        for (name, value) in self.tags.iter() {
+            if let (Some(value), false) = (value, written.contains(&name)) {
+                body.extend(simple_tag(name, value));
+            }
+        }
+        element(TagsId::Tag as u64, &body)
+    }
+}
+
+/// The top level layout of a Matroska file.
+struct Layout<'a> {
+    len: u64,
+    segment: &'a Element,
+    /// Top level elements of the Segment.
+    elements: &'a [Element],
+    seek_head: Option<&'a Element>,
+    /// Positions of the indexed elements, relative to the Segment data.
+    seeks: HashMap<u32, u64>,
+}
+
+/// Writes the new `Tags` element in place of `old_tags` if it fits, or
+/// appends it to the end of the Segment, see [`MatroskaTagWriter`].
+fn write_tags<F: Write + Seek>(
+    file: &mut F,
+    mut layout: Layout,
+    old_tags: Option<&Element>,
+    new_tags: &[u8],
+) -> crate::Result<()> {
+    let segment = layout.segment;
+    let seg_start = segment.data_start();
+    let seg_end = segment.range.end;
+    let in_place = old_tags
+        .map(|x| (x.range.start, space_of(layout.elements, x)))
+        .filter(|(_, space)| fits(new_tags.len() as u64, *space));
+    if let Some((start, space)) = in_place {
+        write_padded(file, start, new_tags, space)?;
+    } else {
+        if seg_end != layout.len {
+            return Err(Error::WriteFailed(
+                "the Segment is not the last element of the file".into(),
+            ));
+        }
+        // All of the rewritten data is prepared before writing
+        let tags_pos = layout.len - seg_start;
+        layout.seeks.insert(SegmentId::Tags as u32, tags_pos);
+        let new_seek_head = match layout.seek_head {
+            Some(x) => {
+                let bytes = encode_seek_head(&layout.seeks);
+                let space = space_of(layout.elements, x);
+                if !fits(bytes.len() as u64, space) {
+                    return Err(Error::WriteFailed(
+                        "no space for updating the SeekHead".into(),
+                    ));
+                }
+                Some((x.range.start, bytes, space))
+            }
+            None => None,
+        };
+        let segment_size = if segment.unknown_size {
+            None
+        } else {
+            let size = seg_end - seg_start + new_tags.len() as u64;
+            let bytes = encode_size(size, segment.size_width)
+                .ok_or_else(|| Error::WriteFailed("the Segment size can't be updated".into()))?;
+            Some(bytes)
+        };
+
+        file.seek(SeekFrom::Start(layout.len))?;
+        file.write_all(new_tags)?;
+        if let Some(bytes) = segment_size {
+            file.seek(SeekFrom::Start(seg_start - bytes.len() as u64))?;
+            file.write_all(&bytes)?;
+        }
+        if let Some((start, bytes, space)) = new_seek_head {
+            write_padded(file, start, &bytes, space)?;
+        }
+        if let Some(old) = old_tags {
+            write_padded(file, old.range.start, &[], old.range.end - old.range.start)?;
+        }
+    }
+    Ok(())
+}
+
+/// An element located in the file.
+#[derive(Debug, Clone)]
+struct Element {
+    id: u64,
+    // Including the header
+    range: Range<u64>,
+    header_size: u64,
+    /// Width of the size VINT.
+    size_width: usize,
+    unknown_size: bool,
+}
+
+impl Element {
+    fn data_start(&self) -> u64 {
+        self.range.start + self.header_size
+    }
+}
+
+/// Reads the header of the element at `pos`, the element must end before
+/// `end` unless its size is unknown.
+fn read_element<F: Read + Seek>(file: &mut F, pos: u64, end: u64) -> crate::Result<Element> {
+    let buf = read_at(file, pos, end.saturating_sub(pos).min(12) as usize)?;
+    let header = next_element_header(&mut Cursor::new(&buf[..]))?;
+    let id_width = buf[0].leading_zeros() as usize + 1;
+    let size_width = header.header_size - id_width;
+    let unknown_size = header.data_size as u64 == (1 << (7 * size_width)) - 1;
+
+    let size = (header.header_size + header.data_size) as u64;
+    if !unknown_size && size > end - pos {
+        return Err(format!("invalid element size: {size}").into());
+    }
+    Ok(Element {
+        id: header.id,
+        range: pos..if unknown_size { end } else { pos + size },
+        header_size: header.header_size as u64,
+        size_width,
+        unknown_size,
+    })
+}
+
+fn read_data<F: Read + Seek>(file: &mut F, element: &Element) -> crate::Result<Vec<u8>> {
+    let start = element.data_start();
+    read_at(file, start, (element.range.end - start) as usize)
+}
+
+/// Returns the size of `element` plus the `Void` elements following it.
+fn space_of(elements: &[Element], element: &Element) -> u64 {
+    let end = elements
+        .iter()
+        .skip_while(|x| x.range.start != element.range.start)
+        .skip(1)
+        .take_while(|x| x.id == EBMLGlobalId::Void as u64)
+        .last()
+        .map_or(element.range.end, |x| x.range.end);
+    end - element.range.start
+}
+
+/// Returns true if `size` bytes can be written into `space`, the rest of
+/// which is filled with a `Void` element (at least 2 bytes).
+fn fits(size: u64, space: u64) -> bool {
+    size == space || size + 2 <= space
+}
+
+/// Writes `data` at `pos`, then fills the rest of `space` with a `Void`
+/// element.
+fn write_padded<F: Write + Seek>(
+    file: &mut F,
+    pos: u64,
+    data: &[u8],
+    space: u64,
+) -> crate::Result<()> {
+    file.seek(SeekFrom::Start(pos))?;
+    file.write_all(data)?;
+    let rest = space - data.len() as u64;
+    if rest > 0 {
+        let header = void_header(rest);
+        file.write_all(&header)?;
+        std::io::copy(
+            &mut std::io::repeat(0).take(rest - header.len() as u64),
+            file,
+        )?;
+    }
+    Ok(())
+}
+
+/// Returns true if the `Tag` element doesn't target a track, chapter, etc.
+fn is_file_level(tag: &[u8]) -> bool {
+    match children(tag).find(|x| x.0 == TagsId::Targets as u64) {
+        Some((_, targets)) => !targets_any_uid(targets),
+        None => true,
+    }
+}
+
+fn simple_tag_name(data: &[u8]) -> Option<String> {
+    let (_, name) = children(data).find(|x| x.0 == TagsId::TagName as u64)?;
+    Some(String::from_utf8_lossy(name).into_owned())
+}
+
+fn simple_tag(name: &str, value: &str) -> Vec<u8> {
+    let mut body = element(TagsId::TagName as u64, name.as_bytes());
+    body.extend(element(TagsId::TagString as u64, value.as_bytes()));
+    element(TagsId::SimpleTag as u64, &body)
+}
+
+/// Patches the `DateUTC` of the segment `Info` in place, its `CRC-32` is
+/// turned into a `Void` element.
+fn patch_info_date<F: Read + Write + Seek>(
+    file: &mut F,
+    info: &Element,
+    date: DateTime<FixedOffset>,
+) -> crate::Result<()> {
+    // Nanoseconds since 2001-01-01T00:00:00 UTC
+    let base = NaiveDate::from_ymd_opt(2001, 1, 1)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+        .and_utc();
+    let nanos = (date.with_timezone(&Utc) - base)
+        .num_nanoseconds()
+        .ok_or_else(|| Error::WriteFailed("date is out of range".into()))?;
+
+    let data = read_data(file, info)?;
+    let mut cursor = Cursor::new(&data[..]);
+    while (cursor.position() as usize) < data.len() {
+        let start = cursor.position();
+        let Ok(header) = next_element_header(&mut cursor) else {
+            break;
+        };
+        let pos = info.data_start() + cursor.position();
+        if header.id == InfoId::Date as u64 && header.data_size == 8 {
+            file.seek(SeekFrom::Start(pos))?;
+            file.write_all(&nanos.to_be_bytes())?;
+        } else if header.id == EBMLGlobalId::Crc32 as u64 {
+            let size = (header.header_size + header.data_size) as u64;
+            file.seek(SeekFrom::Start(info.data_start() + start))?;
+            file.write_all(&void_header(size))?;
+            std::io::copy(&mut std::io::repeat(0).take(header.data_size as u64), file)?;
+        }
+        cursor.set_position(cursor.position() + header.data_size as u64);
+    }
+    Ok(())
+}
+
+fn encode_seek_head(seeks: &HashMap<u32, u64>) -> Vec<u8> {
+    let mut seeks = seeks.iter().collect::<Vec<_>>();
+    seeks.sort_by_key(|x| *x.1);
+    let mut body = Vec::new();
+    for (id, pos) in seeks {
+        let mut seek = element(SeekHeadId::SeekId as u64, &trim_zeros(*id as u64));
+        seek.extend(element(SeekHeadId::SeekPosition as u64, &trim_zeros(*pos)));
+        body.extend(element(SeekHeadId::Seek as u64, &seek));
+    }
+    element(SegmentId::SeekHead as u64, &body)
+}
+
+/// Big endian bytes of `v` without the leading zeros (at least 1 byte).
+fn trim_zeros(v: u64) -> Vec<u8> {
+    let bytes = v.to_be_bytes();
+    let skip = (v.leading_zeros() as usize / 8).min(7);
+    bytes[skip..].to_vec() // Safe-slice
+}
+
+/// Encodes an element with the shortest size VINT.
+fn element(id: u64, data: &[u8]) -> Vec<u8> {
+    let mut bytes = trim_zeros(id);
+    let size = data.len() as u64;
+    let width = (1..=8).find(|w| size < (1 << (7 * w)) - 1).unwrap_or(8);
+    bytes.extend(encode_size(size, width).unwrap_or_default());
+    bytes.extend_from_slice(data);
+    bytes
+}
+
+/// Encodes `size` as a VINT of `width` bytes, returns `None` if it doesn't
+/// fit (all ones is reserved for unknown sizes).
+fn encode_size(size: u64, width: usize) -> Option<Vec<u8>> {
+    if !(1..=8).contains(&width) || size >= (1 << (7 * width)) - 1 {
+        return None;
+    }
+    let bytes = (size | 1 << (7 * width)).to_be_bytes();
+    Some(bytes[8 - width..].to_vec()) // Safe-slice
+}
+
+/// The header of a `Void` element whose total size is `total` (>= 2).
+fn void_header(total: u64) -> Vec<u8> {
+    let mut bytes = vec![EBMLGlobalId::Void as u8];
+    for width in 1..=8 {
+        if let Some(size) = total
+            .checked_sub(1 + width as u64)
+            .and_then(|x| encode_size(x, width))
+        {
+            bytes.extend(size);
+            break;
+        }
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        testkit::read_sample, EntryValue, MediaParser, MediaSource, TrackInfo, TrackInfoTag,
+    };
+    use test_case::test_case;
+
+    fn parse_track(data: Vec<u8>) -> TrackInfo {
+        let ms = MediaSource::seekable(Cursor::new(data)).unwrap();
+        MediaParser::new().parse(ms).unwrap()
+    }
+
+    #[test_case("mkv_640x360.mkv")]
+    #[test_case("webm_480.webm")]
+    fn write_tags(path: &str) {
+        let data = read_sample(path).unwrap();
+        let date = DateTime::parse_from_rfc3339("2024-05-06T07:08:09+08:00").unwrap();
+        let mut writer = MatroskaTagWriter::new();
+        writer
+            .set_title("nom-exif")
+            .set_date(date)
+            .set_tag("ARTIST", "nom-exif authors");
+        let mut output = Vec::new();
+        writer.write(Cursor::new(&data), &mut output).unwrap();
+        // The Tags element is moved to the end
+        assert!(output.len() > data.len());
+
+        // The same as writing in place
+        let mut file = Cursor::new(data.clone());
+        writer.write_in_place(&mut file).unwrap();
+        assert_eq!(file.into_inner(), output);
+
+        let info = parse_track(output.clone());
+        let original = parse_track(data.clone());
+        assert_eq!(info.get(TrackInfoTag::Title), Some(&"nom-exif".into()));
+        assert_eq!(
+            info.get(TrackInfoTag::CreateDate),
+            Some(&EntryValue::Time(
+                DateTime::parse_from_rfc3339("2024-05-05T23:08:09+00:00").unwrap()
+            ))
+        );
+        for tag in [TrackInfoTag::Software, TrackInfoTag::DurationMs] {
+            assert_eq!(info.get(tag), original.get(tag));
+        }
+
+        // Smaller tags are written in place
+        let mut writer = MatroskaTagWriter::new();
+        writer.remove_tag("TITLE").remove_tag("ENCODER");
+        let mut removed = Vec::new();
+        writer.write(Cursor::new(&output), &mut removed).unwrap();
+        assert_eq!(removed.len(), output.len());
+        let info = parse_track(removed);
+        assert_eq!(info.get(TrackInfoTag::Title), None);
+        assert_eq!(info.get(TrackInfoTag::Software), None);
+
+        let mut writer = MatroskaTagWriter::new();
+        writer.set_tag("ENCODER", "x");
+        let mut output = Vec::new();
+        writer.write(Cursor::new(&data), &mut output).unwrap();
+        assert_eq!(output.len(), data.len());
+        assert_eq!(
+            parse_track(output).get(TrackInfoTag::Software),
+            Some(&"x".into())
+        );
+    }
+
+    #[test]
+    fn write_tags_failed() {
+        let mut writer = MatroskaTagWriter::new();
+        writer.set_title("nom-exif");
+        for path in ["exif.jpg", "meta.mp4"] {
+            let data = read_sample(path).unwrap();
+            assert!(writer.write(Cursor::new(data), &mut Vec::new()).is_err());
+        }
+    }
+
+    #[test]
+    fn encode_element() {
+        assert_eq!(element(0xEC, &[]), [0xEC, 0x80]);
+        assert_eq!(element(0x4489, &[0; 126]).len(), 2 + 1 + 126);
+        assert_eq!(element(0x4489, &[0; 127]).len(), 2 + 2 + 127);
+        for total in [2, 3, 128, 129, 20000] {
+            let header = void_header(total);
+            let size = next_element_header(&mut Cursor::new(&header[..])).unwrap();
+            assert_eq!((size.header_size + size.data_size) as u64, total);
+        }
+    }
+}
@@ -8,6 +8,7 @@ use thiserror::Error;
 type FallbackError = Box<dyn std::error::Error + Send + Sync>;
 
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum Error {
     #[error("parse failed: {0}")]
     ParseFailed(FallbackError),
@@ -18,6 +19,16 @@ pub enum Error {
     /// If you encounter this error, please consider filing a bug on github
     #[error("unrecognized file format")]
     UnrecognizedFileFormat,
+
+    /// Metadata can't be written, e.g. a value is not representable in the
+    /// target format, or the Exif data is too large to fit in a JPEG segment.
+    #[error("write failed: {0}")]
+    WriteFailed(FallbackError),
+
+    /// Parsing is aborted by the cancel flag of the parser, see
+    /// [`MediaParser::cancel_on`](crate::MediaParser::cancel_on).
+    #[error("parsing cancelled")]
+    Cancelled,
 }
 
 #[derive(Debug, Error)]
@@ -115,6 +126,17 @@ impl From<&str> for ParsingError {
     }
 }
 
+impl ParsedError {
+    /// Returns true if the input ended before parsing is done.
+    pub fn is_eof(&self) -> bool {
+        match self {
+            ParsedError::NoEnoughBytes => true,
+            ParsedError::IOError(e) => e.kind() == io::ErrorKind::UnexpectedEof,
+            ParsedError::Failed(_) => false,
+        }
+    }
+}
+
 impl From<std::io::Error> for ParsedError {
     fn from(value: std::io::Error) -> Self {
         Self::IOError(value)
@@ -125,6 +147,7 @@ impl From<ParsedError> for crate::Error {
     fn from(value: ParsedError) -> Self {
         match value {
             ParsedError::NoEnoughBytes => Self::ParseFailed(value.into()),
+            ParsedError::IOError(e) if is_cancelled(&e) => Self::Cancelled,
             ParsedError::IOError(e) => Self::IOError(e),
             ParsedError::Failed(e) => Self::ParseFailed(e.into()),
         }
@@ -137,10 +160,27 @@ use crate::parser::ParsingState;
 
 impl From<io::Error> for Error {
     fn from(value: io::Error) -> Self {
+        if is_cancelled(&value) {
+            return Cancelled;
+        }
         ParseFailed(value.into())
     }
 }
 
+/// The payload of the I/O errors returned when parsing is cancelled, so that
+/// they can be told apart from real I/O errors.
+#[derive(Debug, Error)]
+#[error("parsing cancelled")]
+struct CancelledError;
+
+pub(crate) fn cancelled_error() -> io::Error {
+    io::Error::other(CancelledError)
+}
+
+fn is_cancelled(e: &io::Error) -> bool {
+    e.get_ref().is_some_and(|x| x.is::<CancelledError>())
+}
+
 impl From<String> for Error {
     fn from(src: String) -> Error {
         ParseFailed(src.into())
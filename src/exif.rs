@@ -1,30 +1,74 @@
 use crate::error::{nom_error_to_parsing_error_with_state, ParsingError, ParsingErrorState};
 use crate::file::MimeImage;
-use crate::parser::{BufParser, ParsingState, ShareBuf};
+use crate::parser::{BufParser, ParsingState};
+use crate::parser_core::{parse_loaded, ParserCore};
 use crate::skip::Skip;
 use crate::slice::SubsliceRange;
-use crate::{heif, jpeg, MediaParser, MediaSource};
+use crate::xmp::XmpPackets;
+use crate::{heif, jp2, jpeg, raf, MediaParser, MediaSource};
 #[allow(deprecated)]
 use crate::{partial_vec::PartialVec, FileFormat};
-use exif_exif::check_exif_header2;
-pub use exif_exif::Exif;
+pub use bytes::parse_exif_bytes;
+pub use composite::Composites;
+pub use custom::CustomTag;
+pub use exif_exif::{DngKind, Exif};
 use exif_iter::input_into_iter;
-pub use exif_iter::{ExifIter, ParsedExifEntry};
+pub use exif_iter::{ExifIter, ParsedExifEntry, RawEntry, UnknownTag};
+pub use geotiff::{GeoKey, GeoKeyValue, GeoTiffInfo, Tiepoint};
 pub use gps::{GPSInfo, LatLng};
+pub use group::IfdGroup;
+pub use makernote::{
+    AppleMakerNote, CanonCameraSettings, CanonExposureMode, CanonMakerNote, CanonShotInfo,
+    ImageStabilization, MakerNote, NikonLensData, NikonMakerNote, SonyLensSpec, SonyMakerNote,
+};
+#[cfg(feature = "makernotes")]
+pub use makernote::{
+    FilmSimulation, FilterColor, FujifilmMakerNote, OlympusMakerNote, PanasonicMakerNote,
+};
+use nom::{number::streaming as number, sequence::tuple};
+pub(crate) use raw_ifd::{
+    ifd_fields, read_fields, u16_at, u16_bytes, u32_at, u32_bytes, IfdField, RawField,
+};
+pub use scan::scan_exif;
 pub use tags::ExifTag;
+pub use text::TextEncoding;
+pub use writer::ExifWriter;
+pub(crate) use writer::{
+    encode_ifd, encode_value, ifd_size, is_image_data_tag, read_ifd, sub_ifd_of, EncodedValue,
+};
 
 use std::io::Read;
 use std::ops::Range;
+use std::sync::Arc;
 
 pub(crate) mod ifd;
-pub(crate) use exif_exif::{check_exif_header, TiffHeader};
+pub(crate) use dump::dump_tiff;
+pub(crate) use exif_exif::{
+    check_exif_header, check_exif_header2, TiffHeader, EXIF_IDENT, IFD_ENTRY_SIZE,
+};
 pub(crate) use travel::IfdHeaderTravel;
 
+#[cfg(test)]
+pub(crate) use makernote::tests::{build_ifd, tiff_with_maker_note};
+
+mod bytes;
+mod composite;
+mod custom;
+mod dump;
 mod exif_exif;
 mod exif_iter;
+mod geotiff;
 mod gps;
+mod group;
+mod makernote;
+pub(crate) mod print_conv;
+mod raw_ifd;
+mod rw2;
+mod scan;
 mod tags;
+mod text;
 mod travel;
+mod writer;
 
 /// *Deprecated*: Please use [`crate::MediaParser`] instead.
 ///
@@ -75,30 +119,140 @@ pub(crate) fn parse_exif_iter<R: Read, S: Skip<R>>(
         extract_exif_range(mime_img, buf, state)
     })?;
 
-    range_to_iter(parser, out)
+    let core = parser.core_mut();
+    range_to_iter(out, |range| share_core_buf(core, range))
+}
+
+/// Parses the Exif data in `data`, which holds the whole media source, the
+/// returned iterator shares `data`.
+pub(crate) fn parse_loaded_exif_iter(
+    mime_img: MimeImage,
+    data: &::bytes::Bytes,
+) -> Result<ExifIter, crate::Error> {
+    let (out, pos) = parse_loaded(data, |buf, state| extract_exif_range(mime_img, buf, state))?;
+    range_to_iter(out, |range| {
+        let range = range.start + pos..range.end + pos;
+        let offset = range.start as u64;
+        (PartialVec::new(data.clone(), range), offset)
+    })
+}
+
+type ExifRangeResult = Result<Option<ExifRange>, ParsingErrorState>;
+
+/// The extracted Exif data, along with the info provided by the container.
+#[derive(Debug)]
+struct ExifRange {
+    data: ExifData,
+    /// The parsed TIFF header, if it has been skipped
+    header: Option<TiffHeader>,
+    /// Orientation of the primary item of HEIF files
+    orientation: Option<u16>,
+    /// Frame count of HEIF image sequences
+    frames: Option<u32>,
+    xmp: Option<XmpPackets>,
+}
+
+impl ExifRange {
+    fn new(data: ExifData) -> Self {
+        Self {
+            data,
+            header: None,
+            orientation: None,
+            frames: None,
+            xmp: None,
+        }
+    }
+}
+
+/// Location of the extracted Exif data.
+enum ExifData {
+    /// A range of the parsing buffer, can be shared without copying
+    Range(Range<usize>),
+    /// Exif data reassembled from multiple pieces
+    Owned(Vec<u8>),
 }
 
-type ExifRangeResult = Result<Option<(Range<usize>, Option<TiffHeader>)>, ParsingErrorState>;
+impl std::fmt::Debug for ExifData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Range(range) => f.debug_tuple("Range").field(range).finish(),
+            Self::Owned(vec) => f.debug_struct("Owned").field("len", &vec.len()).finish(),
+        }
+    }
+}
 
 fn extract_exif_range(img: MimeImage, buf: &[u8], state: Option<ParsingState>) -> ExifRangeResult {
+    let raf_jpeg = img == MimeImage::Raf && matches!(state, Some(ParsingState::RafJpeg));
+    if img == MimeImage::Jpeg || raf_jpeg {
+        let (_, segments) = jpeg::collect_app1_segments(buf)
+            .map_err(|e| nom_error_to_parsing_error_with_state(e, state))?;
+        let data = match segments.exif.as_slice() {
+            [] => None,
+            [data] => buf.subslice_range(data).map(ExifData::Range),
+            pieces => Some(ExifData::Owned(pieces.concat())),
+        };
+        return Ok(data.map(|data| ExifRange {
+            xmp: jpeg_xmp(&segments),
+            ..ExifRange::new(data)
+        }));
+    }
+
     let (exif_data, state) = extract_exif_with_mime(img, buf, state)?;
-    let header = state.and_then(|x| match x {
-        ParsingState::TiffHeader(h) => Some(h),
-        ParsingState::HeifExifSize(_) => None,
-    });
-    Ok(exif_data
-        .and_then(|x| buf.subslice_range(x))
-        .map(|x| (x, header)))
+    let Some(range) = exif_data.and_then(|x| buf.subslice_range(x)) else {
+        return Ok(None);
+    };
+    let mut out = ExifRange::new(ExifData::Range(range));
+    match state {
+        Some(ParsingState::TiffHeader(h)) => out.header = Some(h),
+        Some(ParsingState::HeifExifSize(_, orientation, frames)) => {
+            (out.orientation, out.frames) = (orientation, frames)
+        }
+        Some(ParsingState::RafJpeg | ParsingState::DataSize(_)) | None => (),
+    };
+    Ok(Some(out))
 }
 
+pub(crate) fn jpeg_xmp(segments: &jpeg::App1Segments) -> Option<XmpPackets> {
+    let standard = match String::from_utf8(segments.xmp?.to_vec()) {
+        Ok(s) => s,
+        Err(e) => {
+            tracing::warn!(?e, "invalid XMP data");
+            return None;
+        }
+    };
+    let extended = segments
+        .extended_xmp()
+        .and_then(|x| String::from_utf8(x).ok());
+    Some(XmpPackets { standard, extended })
+}
+
+/// Shares `range` of the unread data of `core`, along with its offset in the
+/// media source.
+fn share_core_buf(core: &mut ParserCore, range: Range<usize>) -> (PartialVec, u64) {
+    let offset = core.source_offset() + range.start as u64;
+    (core.share_buf(range), offset)
+}
+
+/// Creates an [`ExifIter`] from the extracted Exif data, `share` returns the
+/// data of a range (along with its offset in the media source).
 fn range_to_iter(
-    parser: &mut impl ShareBuf,
-    out: Option<(Range<usize>, Option<TiffHeader>)>,
+    out: Option<ExifRange>,
+    share: impl FnOnce(Range<usize>) -> (PartialVec, u64),
 ) -> Result<ExifIter, crate::Error> {
-    if let Some((range, header)) = out {
-        tracing::debug!(?range, ?header, "Got Exif data");
-        let input: PartialVec = parser.share_buf(range);
-        let iter = input_into_iter(input, header)?;
+    if let Some(out) = out {
+        tracing::debug!(?out.data, ?out.header, ?out.orientation, "Got Exif data");
+        let (input, source_offset): (PartialVec, _) = match out.data {
+            ExifData::Range(range) => {
+                let (input, offset) = share(range);
+                (input, Some(offset))
+            }
+            ExifData::Owned(vec) => (vec.into(), None),
+        };
+        let mut iter = input_into_iter(input, out.header)?;
+        iter.tiff_offset = source_offset.and_then(|x| x.checked_sub(iter.input_offset().into()));
+        iter.container_orientation = out.orientation;
+        iter.sequence_frames = out.frames;
+        iter.xmp = out.xmp.map(Arc::new);
 
         Ok(iter)
     } else {
@@ -125,7 +279,8 @@ pub(crate) async fn parse_exif_iter_async<
         })
         .await?;
 
-    range_to_iter(parser, out)
+    let core = parser.core_mut();
+    range_to_iter(out, |range| share_core_buf(core, range))
 }
 
 pub(crate) fn extract_exif_with_mime(
@@ -137,10 +292,19 @@ pub(crate) fn extract_exif_with_mime(
         crate::file::MimeImage::Jpeg => jpeg::extract_exif_data(buf)
             .map(|res| (res.1, state.clone()))
             .map_err(|e| nom_error_to_parsing_error_with_state(e, state))?,
-        crate::file::MimeImage::Heic | crate::file::MimeImage::Heif => {
-            heif_extract_exif(state, buf)?
-        }
-        crate::file::MimeImage::Tiff => {
+        crate::file::MimeImage::Heic
+        | crate::file::MimeImage::Heif
+        | crate::file::MimeImage::Avif => heif_extract_exif(state, buf)?,
+        crate::file::MimeImage::Jp2 => (jp2::extract_exif(buf)?, None),
+        crate::file::MimeImage::Raf => match state {
+            Some(ParsingState::RafJpeg) => jpeg::extract_exif_data(buf)
+                .map(|res| (res.1, state.clone()))
+                .map_err(|e| nom_error_to_parsing_error_with_state(e, state))?,
+            _ => return Err(raf::skip_to_jpeg(buf, state)),
+        },
+        crate::file::MimeImage::Tiff
+        | crate::file::MimeImage::Pef
+        | crate::file::MimeImage::Srw => {
             let (header, data_start) = match state {
                 Some(ParsingState::TiffHeader(ref h)) => (h.to_owned(), 0),
                 None => {
@@ -158,10 +322,9 @@ pub(crate) fn extract_exif_with_mime(
                 _ => unreachable!(),
             };
 
-            // full fill TIFF data
-            let mut iter =
-                IfdHeaderTravel::new(&buf[data_start..], header.ifd0_offset, header.endian);
-            iter.travel_ifd(0)
+            // full fill TIFF data, including all pages
+            IfdHeaderTravel::new(&buf[data_start..], header.ifd0_offset, header.endian)
+                .travel_pages()
                 .map_err(|e| ParsingErrorState::new(e, state.clone()))?;
 
             (Some(buf), state)
@@ -170,12 +333,57 @@ pub(crate) fn extract_exif_with_mime(
     Ok((exif_data, state))
 }
 
+/// Returns `input[start..start + size]` if it has been loaded, otherwise
+/// skips to `start` & continues with [`ParsingState::DataSize`].
+pub(crate) fn data_or_skip(
+    input: &[u8],
+    start: usize,
+    size: usize,
+) -> Result<Option<&[u8]>, ParsingErrorState> {
+    match input.get(start..start + size) {
+        Some(data) => Ok(Some(data)),
+        None => Err(ParsingErrorState::new(
+            ParsingError::ClearAndSkip(start),
+            Some(ParsingState::DataSize(size)),
+        )),
+    }
+}
+
+/// Returns the data of the entry `tag` in ifd0 of a TIFF file, e.g. the XMP
+/// packet or the ICC profile. Inline values (4 bytes at most) are ignored.
+pub(crate) fn tiff_ifd0_data(input: &[u8], tag: u16) -> Result<Option<&[u8]>, ParsingErrorState> {
+    let map_err = |e| nom_error_to_parsing_error_with_state(e, None);
+    let (_, header) = TiffHeader::parse(input).map_err(map_err)?;
+    let endian = header.endian;
+    let ifd0_offset = header.ifd0_offset as usize;
+    let Some(ifd0) = input.get(ifd0_offset..) else {
+        let need = ParsingError::Need(ifd0_offset - input.len() + 2);
+        return Err(ParsingErrorState::new(need, None));
+    };
+
+    let (mut remain, entry_num) = number::u16(endian)(ifd0).map_err(map_err)?;
+    for _ in 0..entry_num {
+        let (rest, (entry_tag, _, count, offset)) = tuple((
+            number::u16(endian),
+            number::u16(endian),
+            number::u32(endian),
+            number::u32(endian),
+        ))(remain)
+        .map_err(map_err)?;
+        remain = rest;
+        if entry_tag == tag && count > 4 {
+            return data_or_skip(input, offset as usize, count as usize);
+        }
+    }
+    Ok(None)
+}
+
 fn heif_extract_exif(
     state: Option<ParsingState>,
     buf: &[u8],
 ) -> Result<(Option<&[u8]>, Option<ParsingState>), ParsingErrorState> {
     let (data, state) = match state {
-        Some(ParsingState::HeifExifSize(size)) => {
+        Some(ParsingState::HeifExifSize(size, ..)) => {
             let (_, data) = nom::bytes::streaming::take(size)(buf)
                 .map_err(|e| nom_error_to_parsing_error_with_state(e, state.clone()))?;
             (Some(data), state)
@@ -186,12 +394,15 @@ fn heif_extract_exif(
 
             if let Some(meta) = meta {
                 if let Some(range) = meta.exif_data_offset() {
+                    let (_, frames) = heif::parse_sequence_frames(buf)
+                        .map_err(|e| nom_error_to_parsing_error_with_state(e, None))?;
+                    let orientation = meta.primary_orientation();
+                    let state = ParsingState::HeifExifSize(range.len(), orientation, frames);
                     if range.end > buf.len() {
-                        let state = ParsingState::HeifExifSize(range.len());
                         let clear_and_skip = ParsingError::ClearAndSkip(range.start);
                         return Err(ParsingErrorState::new(clear_and_skip, Some(state)));
                     } else {
-                        (Some(&buf[range]), None)
+                        (Some(&buf[range]), Some(state))
                     }
                 } else {
                     return Err(ParsingErrorState::new(
@@ -264,6 +475,22 @@ mod tests {
         assert_eq!(gps_info.format_iso6709(), gps_str);
     }
 
+    #[test]
+    fn gps_heading() {
+        let f = open_sample("exif.heic").unwrap();
+        let iter = parse_exif(f, None).unwrap().unwrap();
+        let gps = iter.parse_gps_info().unwrap().unwrap();
+        assert_eq!(gps.img_direction_ref, Some('T'));
+        assert_eq!(gps.img_direction, Some((443187, 1672).into()));
+        assert_eq!(gps.dest_bearing_ref, Some('T'));
+        assert_eq!(gps.dest_bearing, Some((443187, 1672).into()));
+        assert_eq!(gps.dop, None);
+
+        let (lat, lng, alt) = gps.to_decimal();
+        assert_eq!(format!("{lat:.5},{lng:.5}"), "43.29013,84.22713");
+        assert_eq!(alt.map(|x| format!("{x:.3}")).as_deref(), Some("1595.950"));
+    }
+
     #[cfg(feature = "async")]
     #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
     #[test_case("exif.heic", "+43.29013+084.22713+1595.950CRSWGS_84/")]
@@ -332,8 +559,15 @@ mod tests {
                 altitude,
                 speed_ref,
                 speed,
+                date_stamp: Some("2023:07:09".into()),
+                time_stamp: Some([(12, 1).into(), (36, 1).into(), (33, 1).into()]),
+                ..Default::default()
             }
-        )
+        );
+        assert_eq!(
+            gps.utc_timestamp().map(|x| x.to_rfc3339()).as_deref(),
+            Some("2023-07-09T12:36:33+00:00")
+        );
     }
 
     #[test_case("exif.heic")]
@@ -0,0 +1,54 @@
+use crate::ExifIter;
+
+use super::{check_exif_header, exif_exif::EXIF_IDENT, exif_iter::input_into_iter};
+
+/// Parses raw Exif data, and returns an [`ExifIter`].
+///
+/// Use this function if you have already extracted the Exif payload, e.g.
+/// by another container parser, from a database, or from a PNG `eXIf`
+/// chunk. `data` should start with a TIFF header (`II*\0` / `MM\0*`); an
+/// optional `Exif\0\0` prefix (as used in JPEG APP1 segments) is accepted
+/// too.
+///
+/// The data is copied, so the returned `ExifIter` doesn't borrow from `data`.
+///
+/// ## Example
+///
+/// ```rust
+/// use nom_exif::*;
+///
+/// let jpeg = std::fs::read("./testdata/exif.jpg").unwrap();
+/// // Exif payload of the APP1 segment, starts with "Exif\0\0"
+/// let exif: Exif = parse_exif_bytes(&jpeg[6..]).unwrap().into();
+/// assert_eq!(exif.get(ExifTag::Make).unwrap().as_str(), Some("vivo"));
+/// ```
+pub fn parse_exif_bytes(data: &[u8]) -> crate::Result<ExifIter> {
+    let data = if check_exif_header(data) {
+        &data[EXIF_IDENT.len()..]
+    } else {
+        data
+    };
+    input_into_iter(data.to_vec(), None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testkit::read_sample, Exif, ExifTag};
+
+    #[test]
+    fn exif_bytes() {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let buf = read_sample("exif.jpg").unwrap();
+        // APP1 payload, with & without the "Exif\0\0" prefix
+        for data in [&buf[6..], &buf[12..]] {
+            let exif: Exif = parse_exif_bytes(data).unwrap().into();
+            assert_eq!(exif.get(ExifTag::Make).unwrap().as_str(), Some("vivo"));
+        }
+
+        parse_exif_bytes(b"").unwrap_err();
+        parse_exif_bytes(b"Exif\0\0").unwrap_err();
+        parse_exif_bytes(&buf).unwrap_err();
+    }
+}
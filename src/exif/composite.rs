@@ -0,0 +1,127 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{EntryValue, Exif, ExifTag};
+
+use super::print_conv::number;
+
+/// Values computed from other entries, like exiftool's Composite tags, see
+/// [`Exif::composites`].
+///
+/// A field is `None` if the entries it is computed from are missing or
+/// invalid.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq)]
+#[non_exhaustive]
+pub struct Composites {
+    /// F number, from [`ExifTag::FNumber`], or [`ExifTag::ApertureValue`]
+    /// (APEX) if absent
+    pub aperture: Option<f64>,
+
+    /// Exposure time in seconds, from [`ExifTag::ExposureTime`], or
+    /// [`ExifTag::ShutterSpeedValue`] (APEX) if absent
+    pub shutter_speed: Option<f64>,
+
+    /// Focal length in 35mm film equivalent (mm), from
+    /// [`ExifTag::FocalLengthIn35mmFilm`], or computed from the focal length
+    /// and the sensor size (derived from the focal plane resolutions) if
+    /// absent
+    pub focal_length_35mm: Option<f64>,
+
+    /// Light value, i.e. the exposure value normalized to ISO 100, computed
+    /// from the aperture, shutter speed & ISO
+    pub light_value: Option<f64>,
+
+    /// Image size in megapixels
+    pub megapixels: Option<f64>,
+
+    /// GPS position such as `22 deg 31' 52.08" N, 114 deg 1' 17.33" E`
+    pub gps_position: Option<String>,
+}
+
+/// Diagonal of a 35mm film frame (36 x 24 mm) in mm.
+const FULL_FRAME_DIAGONAL: f64 = 43.266_615_305_567_87;
+
+impl Composites {
+    pub(crate) fn new(exif: &Exif) -> Self {
+        let value = |tag: ExifTag| exif.get(tag).and_then(number).filter(|x| x.is_finite());
+        let positive = |tag: ExifTag| value(tag).filter(|x| *x > 0.0);
+
+        let aperture = positive(ExifTag::FNumber)
+            .or_else(|| value(ExifTag::ApertureValue).map(|av| 2f64.powf(av / 2.0)));
+        let shutter_speed = positive(ExifTag::ExposureTime)
+            .or_else(|| value(ExifTag::ShutterSpeedValue).map(|tv| 2f64.powf(-tv)));
+
+        let dimensions = exif.stored_dimensions().filter(|(w, h)| *w > 0 && *h > 0);
+        let focal_length_35mm = positive(ExifTag::FocalLengthIn35mmFilm).or_else(|| {
+            let focal_length = positive(ExifTag::FocalLength)?;
+            let (w, h) = dimensions?;
+            // Pixels per mm
+            let unit = match exif.get(ExifTag::FocalPlaneResolutionUnit) {
+                None => 25.4,
+                Some(x) => match number(x)? as u32 {
+                    2 => 25.4,
+                    3 => 10.0,
+                    4 => 1.0,
+                    5 => 0.001,
+                    _ => return None,
+                },
+            };
+            let x_res = positive(ExifTag::FocalPlaneXResolution)? / unit;
+            let y_res = positive(ExifTag::FocalPlaneYResolution)? / unit;
+            let diagonal = (w as f64 / x_res).hypot(h as f64 / y_res);
+            Some(focal_length * FULL_FRAME_DIAGONAL / diagonal)
+        });
+
+        let iso = exif
+            .get(ExifTag::ISOSpeedRatings)
+            .and_then(first_number)
+            .filter(|x| *x > 0.0);
+        let light_value = match (aperture, shutter_speed, iso) {
+            (Some(a), Some(t), Some(iso)) if t > 0.0 => Some((a * a * 100.0 / (t * iso)).log2()),
+            _ => None,
+        };
+
+        let megapixels = dimensions.map(|(w, h)| w as f64 * h as f64 / 1_000_000.0);
+
+        let gps_position = exif
+            .get_gps_info()
+            .ok()
+            .flatten()
+            .filter(|x| x.latitude_ref != '\0' && x.longitude_ref != '\0')
+            .map(|gps| {
+                let dms = |x: &crate::LatLng, r: char| {
+                    // Normalize the degrees, e.g. 22/1, 31/1, 5208/100
+                    let degrees = x.as_degrees();
+                    let d = degrees.trunc();
+                    let m = ((degrees - d) * 60.0).trunc();
+                    let s = (degrees - d - m / 60.0) * 3600.0;
+                    format!("{d} deg {m}' {s:.2}\" {r}")
+                };
+                format!(
+                    "{}, {}",
+                    dms(&gps.latitude, gps.latitude_ref),
+                    dms(&gps.longitude, gps.longitude_ref)
+                )
+            });
+
+        Self {
+            aperture,
+            shutter_speed,
+            focal_length_35mm,
+            light_value,
+            megapixels,
+            gps_position,
+        }
+    }
+}
+
+/// Returns the first component of a numeric value, e.g. ISO values may be
+/// stored as arrays.
+fn first_number(v: &EntryValue) -> Option<f64> {
+    match v {
+        EntryValue::U16Array(x) => x.first().map(|x| *x as f64),
+        EntryValue::U32Array(x) => x.first().map(|x| *x as f64),
+        _ => number(v),
+    }
+}
@@ -0,0 +1,68 @@
+use crate::{
+    values::{DataFormat, EntryData, ParseEntryError},
+    EntryValue,
+};
+
+use super::{IfdGroup, RawEntry};
+
+/// A user defined tag, so that proprietary tags are reported with names (and
+/// typed values) instead of being unrecognized, see
+/// [`MediaParser::register_tag`](crate::MediaParser::register_tag) &
+/// [`ExifIter::register_tag`](crate::ExifIter::register_tag).
+///
+/// Custom tags only apply to the tag codes which are not recognized as an
+/// [`ExifTag`](crate::ExifTag).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomTag {
+    /// Raw tag code
+    pub code: u16,
+
+    /// Tag name, e.g. "SerialNumber"
+    pub name: String,
+
+    /// Expected data format code, e.g. 2 for ASCII, 3 for SHORT. If the
+    /// entry is stored in another format (e.g. 7 for UNDEFINED), its raw bytes
+    /// are decoded in the expected format. `None` to keep the stored format.
+    pub data_format: Option<u16>,
+
+    /// The group of IFDs where the tag is defined, `None` for all IFDs.
+    pub group: Option<IfdGroup>,
+}
+
+impl CustomTag {
+    /// Creates a tag which applies to all IFDs, and keeps the stored data
+    /// format.
+    pub fn new(code: u16, name: impl Into<String>) -> Self {
+        Self {
+            code,
+            name: name.into(),
+            data_format: None,
+            group: None,
+        }
+    }
+
+    pub(crate) fn matches(&self, code: u16, group: IfdGroup) -> bool {
+        self.code == code && (self.group.is_none() || self.group == Some(group))
+    }
+
+    /// Decodes the raw bytes of `raw` in the expected data format, returns
+    /// `None` if the entry is stored in the expected format already, or if
+    /// the expected format is invalid.
+    pub(crate) fn decode(
+        &self,
+        raw: &RawEntry,
+        endian: nom::number::Endianness,
+        tz: &Option<String>,
+    ) -> Option<Result<EntryValue, ParseEntryError>> {
+        let code = self.data_format.filter(|x| *x != raw.data_format())?;
+        let data_format = DataFormat::try_from(code).ok()?;
+        let entry = EntryData {
+            endian,
+            tag: self.code,
+            data: raw.data(),
+            data_format,
+            components_num: (raw.data().len() / data_format.component_size()) as u32,
+        };
+        Some(EntryValue::parse(&entry, tz))
+    }
+}
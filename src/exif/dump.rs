@@ -0,0 +1,218 @@
+use std::collections::HashSet;
+
+use nom::number::Endianness;
+
+use crate::{
+    dump::{DumpKind, DumpNode},
+    values::{DataFormat, EntryData},
+    EntryValue, ExifTag,
+};
+
+use super::{
+    exif_exif::{TiffHeader, IFD_ENTRY_SIZE},
+    exif_iter::SUBIFD_TAGS,
+    u16_at, u32_at,
+};
+
+const MAX_IFDS: usize = 16;
+const MAX_IFD_DEPTH: usize = 8;
+const MAX_VALUE_LEN: usize = 64;
+
+/// Dumps the IFD tree of TIFF data, `base` is the offset of `data` in the
+/// file.
+pub(crate) fn dump_tiff(data: &[u8], base: u64) -> DumpNode {
+    let mut node = DumpNode::new(DumpKind::Tiff, "TIFF header", base, data.len() as u64);
+    let header = match TiffHeader::parse(data) {
+        Ok((_, header)) => header,
+        Err(_) => return node.with_note("invalid TIFF header"),
+    };
+    let endian = match header.endian {
+        Endianness::Big => "big endian",
+        _ => "little endian",
+    };
+    node.note = Some(endian.to_owned());
+
+    let mut dumper = TiffDumper {
+        data,
+        base,
+        endian: header.endian,
+        visited: HashSet::new(),
+    };
+    let mut offset = header.ifd0_offset;
+    for idx in 0..MAX_IFDS {
+        if offset == 0 {
+            break;
+        }
+        let (ifd, next) = dumper.dump_ifd(&format!("ifd{idx}"), offset, 0);
+        node.children.push(ifd);
+        offset = next;
+    }
+
+    node
+}
+
+struct TiffDumper<'a> {
+    data: &'a [u8],
+    base: u64,
+    endian: Endianness,
+    visited: HashSet<u32>,
+}
+
+impl TiffDumper<'_> {
+    /// Returns the IFD node & the offset of the next IFD.
+    fn dump_ifd(&mut self, name: &str, offset: u32, depth: usize) -> (DumpNode, u32) {
+        let mut node = DumpNode::new(DumpKind::Ifd, name, self.base + offset as u64, 0);
+        if !self.visited.insert(offset) {
+            return (node.with_note("visited already (reference loop)"), 0);
+        }
+        if depth > MAX_IFD_DEPTH {
+            return (node.with_note("too deep"), 0);
+        }
+
+        let pos = offset as usize;
+        let Some(num) = self.u16_at(pos) else {
+            return (node.with_note("offset out of range"), 0);
+        };
+        node.size = 2 + num as u64 * IFD_ENTRY_SIZE as u64 + 4;
+
+        for i in 0..num as usize {
+            let pos = pos + 2 + i * IFD_ENTRY_SIZE;
+            let Some(entry) = self.data.get(pos..pos + IFD_ENTRY_SIZE) else {
+                node.note = Some(format!("truncated, {i} of {num} entries available"));
+                return (node, 0);
+            };
+            node.children.push(self.dump_entry(pos, entry, depth));
+        }
+
+        let next = self
+            .u32_at(pos + 2 + num as usize * IFD_ENTRY_SIZE)
+            .unwrap_or(0);
+        (node, next)
+    }
+
+    fn dump_entry(&mut self, pos: usize, entry: &[u8], depth: usize) -> DumpNode {
+        let endian = self.endian;
+        let tag = self.u16_at(pos).unwrap_or_default();
+        let format = self.u16_at(pos + 2).unwrap_or_default();
+        let count = self.u32_at(pos + 4).unwrap_or_default();
+        let value_or_offset = self.u32_at(pos + 8).unwrap_or_default();
+
+        let name = ExifTag::try_from(tag)
+            .map(|x| x.to_string())
+            .unwrap_or_else(|_| format!("0x{tag:04x}"));
+        let node = DumpNode::new(
+            DumpKind::Entry,
+            &name,
+            self.base + pos as u64,
+            IFD_ENTRY_SIZE as u64,
+        );
+
+        let Ok(data_format) = DataFormat::try_from(format) else {
+            return node.with_note(format!("invalid data format {format}"));
+        };
+        let size = (count as usize).saturating_mul(data_format.component_size());
+
+        if SUBIFD_TAGS.contains(&tag) {
+            let mut node = node;
+            let (ifd, _) = self.dump_ifd(&name, value_or_offset, depth + 1);
+            node.children.push(ifd);
+            return node;
+        }
+
+        let (data, mut node) = if size <= 4 {
+            (&entry[8..8 + size], node)
+        } else {
+            let start = value_or_offset as usize;
+            let Some(data) = self.data.get(start..start.saturating_add(size)) else {
+                return node.with_note(format!(
+                    "data out of range, offset: 0x{value_or_offset:x}, size: {size}"
+                ));
+            };
+            let mut node = node;
+            node.children.push(DumpNode::new(
+                DumpKind::Data,
+                "value",
+                self.base + start as u64,
+                size as u64,
+            ));
+            (data, node)
+        };
+
+        let entry = EntryData {
+            endian,
+            tag,
+            data,
+            data_format,
+            components_num: count,
+        };
+        let value = EntryValue::parse(&entry, &None);
+        if tag == ExifTag::SubIFDs.code() {
+            let offsets = match &value {
+                Ok(EntryValue::U32(x)) => vec![*x],
+                Ok(EntryValue::U32Array(x)) => x.clone(),
+                _ => Vec::new(),
+            };
+            for (i, offset) in offsets.into_iter().enumerate() {
+                let (ifd, _) = self.dump_ifd(&format!("SubIFD{i}"), offset, depth + 1);
+                node.children.push(ifd);
+            }
+        }
+        node.note = Some(match value {
+            Ok(v) => {
+                let mut s = v.to_string();
+                if s.len() > MAX_VALUE_LEN {
+                    let end = (0..=MAX_VALUE_LEN)
+                        .rev()
+                        .find(|x| s.is_char_boundary(*x))
+                        .unwrap_or(0);
+                    s.truncate(end);
+                    s.push_str("...");
+                }
+                s
+            }
+            Err(e) => format!("invalid value: {e}"),
+        });
+        node
+    }
+
+    fn u16_at(&self, pos: usize) -> Option<u16> {
+        u16_at(self.data, pos, self.endian)
+    }
+
+    fn u32_at(&self, pos: usize) -> Option<u32> {
+        u32_at(self.data, pos, self.endian)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dump_ifd_loop() {
+        // IFD0 with an ExifOffset entry which points to IFD0 itself
+        let mut data = b"II*\0\x08\0\0\0".to_vec();
+        data.extend_from_slice(&[0x01, 0x00]);
+        data.extend_from_slice(&[0x69, 0x87, 0x04, 0x00, 0x01, 0, 0, 0, 0x08, 0, 0, 0]);
+        data.extend_from_slice(&0_u32.to_le_bytes());
+
+        let node = dump_tiff(&data, 100);
+        assert_eq!(node.note.as_deref(), Some("little endian"));
+        let ifd0 = &node.children[0];
+        assert_eq!((ifd0.offset, ifd0.size), (108, 18));
+        let entry = &ifd0.children[0];
+        assert_eq!(entry.name, "ExifOffset");
+        assert_eq!(
+            entry.children[0].note.as_deref(),
+            Some("visited already (reference loop)")
+        );
+
+        // Truncated
+        let node = dump_tiff(&data[..20], 0);
+        assert!(node.children[0]
+            .note
+            .as_deref()
+            .unwrap()
+            .starts_with("truncated"));
+    }
+}
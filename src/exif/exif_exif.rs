@@ -2,23 +2,63 @@ use nom::{
     branch::alt, bytes::complete::tag, combinator, number::Endianness, sequence, IResult, Needed,
 };
 
-use crate::{EntryValue, ExifIter, ExifTag, GPSInfo, ParsedExifEntry};
+use chrono::{DateTime, FixedOffset, Timelike};
+use std::{fmt::Display, sync::Arc};
 
-use super::ifd::ParsedImageFileDirectory;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    binary,
+    fingerprint::Fingerprint,
+    report::{pretty_value, write_group},
+    xmp::XmpPackets,
+    Composites, EntryValue, ExifIter, ExifTag, GPSInfo, GeoTiffInfo, MakerNote, Orientation,
+    ParsedExifEntry,
+};
+
+use super::{group::IfdGroup, ifd::ParsedImageFileDirectory, rw2::RW2_MAGIC};
 
 /// Represents parsed Exif information, can be converted from an [`ExifIter`]
 /// like this: `let exif: Exif = iter.into()`.
+///
+/// With the `serde` feature, all the entries are serialized (keyed by IFDs
+/// & raw tag codes), along with the GPS info & XMP packets, but the decoded
+/// MakerNote is not, see [`Exif::get_maker_note`].
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, Debug, PartialEq)]
 pub struct Exif {
     ifds: Vec<ParsedImageFileDirectory>,
+    // IFD index of each page, see `ParsedExifEntry::page_index`
+    pages: Vec<usize>,
     gps_info: Option<GPSInfo>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    maker_note: Option<MakerNote>,
+    container_orientation: Option<u16>,
+    sequence_frames: Option<u32>,
+    xmp: Option<Arc<XmpPackets>>,
+}
+
+/// The kind of a DNG file, see [`Exif::dng_kind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DngKind {
+    /// A DNG file without Apple ProRAW specific data
+    Standard,
+    /// Apple ProRAW, i.e. a DNG file with Apple computational photography
+    /// data, e.g. semantic masks
+    AppleProRaw,
 }
 
 impl Exif {
-    fn new(gps_info: Option<GPSInfo>) -> Exif {
+    fn new(gps_info: Option<GPSInfo>, container_orientation: Option<u16>) -> Exif {
         Exif {
             ifds: Vec::new(),
+            pages: Vec::new(),
             gps_info,
+            maker_note: None,
+            container_orientation,
+            sequence_frames: None,
+            xmp: None,
         }
     }
 
@@ -62,6 +102,9 @@ impl Exif {
     /// - 0: ifd0 (the main image)
     /// - 1: ifd1 (thumbnail image)
     ///
+    /// IFDs referenced by [`ExifTag::SubIFDs`] get their own indexes as well,
+    /// see [`ParsedExifEntry::ifd_index`].
+    ///
     /// *Note*:
     ///
     /// - The parsing error related to this tag won't be reported by this
@@ -92,6 +135,54 @@ impl Exif {
         self.ifds.get(ifd).and_then(|ifd| ifd.get(tag))
     }
 
+    /// Get entry value for the raw tag code `code` in the specified `ifd` (0:
+    /// the main image, 1: the thumbnail, etc.), so that tags appearing in
+    /// several IFDs (e.g. `Orientation` or `ImageWidth` of ifd0 & ifd1) can
+    /// be told apart. It's the same as [`Self::get_by_ifd_tag_code`].
+    ///
+    /// ```rust
+    /// use nom_exif::*;
+    ///
+    /// let ms = MediaSource::file_path("./testdata/exif.jpg").unwrap();
+    /// let iter: ExifIter = MediaParser::new().parse(ms).unwrap();
+    /// let exif: Exif = iter.into();
+    ///
+    /// let code = ExifTag::ExifImageWidth.code();
+    /// let width = |ifd| exif.get_by_code_in(ifd, code).and_then(|x| x.as_u32());
+    /// assert_eq!(width(0), Some(3072));
+    /// assert_eq!(width(1), Some(240));
+    /// ```
+    pub fn get_by_code_in(&self, ifd: usize, code: u16) -> Option<&EntryValue> {
+        self.get_by_ifd_tag_code(ifd, code)
+    }
+
+    /// Returns the entries of the specified `ifd` in ascending order of tag
+    /// codes, entries of the Exif & GPS sub-IFDs are included in ifd0.
+    pub(crate) fn ifd_entries(&self, ifd: usize) -> Vec<(u16, &EntryValue)> {
+        let mut entries: Vec<_> = self
+            .ifds
+            .get(ifd)
+            .map(|x| x.entries.iter().map(|(k, v)| (*k, &v.value)).collect())
+            .unwrap_or_default();
+        entries.sort_by_key(|x| x.0);
+        entries
+    }
+
+    /// Returns the number of pages which have entries, see
+    /// [`ParsedExifEntry::page_index`].
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Get entry value for the specified `tag` in the specified `page`, e.g.
+    /// the `DateTime` of a page in a multi-page TIFF file.
+    ///
+    /// Page 0 is ifd0, so `get_by_page(0, tag)` is the same as
+    /// [`Self::get`].
+    pub fn get_by_page(&self, page: usize, tag: ExifTag) -> Option<&EntryValue> {
+        self.get_by_ifd_tag_code(*self.pages.get(page)?, tag.code())
+    }
+
     /// Get entry values for the specified `tags` in ifd0 (the main image).
     ///
     /// Please note that this method will ignore errors encountered during the
@@ -131,20 +222,498 @@ impl Exif {
         Ok(self.gps_info.clone())
     }
 
+    /// Get the decoded MakerNote, see [`ExifIter::parse_maker_note`].
+    ///
+    /// It's always `None` for an `Exif` deserialized with serde, since the
+    /// decoded MakerNote is not serialized.
+    pub fn get_maker_note(&self) -> Option<&MakerNote> {
+        self.maker_note.as_ref()
+    }
+
+    /// Get parsed GeoTIFF georeferencing information of ifd0, returns `None`
+    /// if there is no GeoKey directory.
+    pub fn get_geotiff_info(&self) -> Option<GeoTiffInfo> {
+        GeoTiffInfo::from_entries(|x| self.get(x))
+    }
+
+    /// Returns the index of the IFD which describes the full resolution
+    /// image.
+    ///
+    /// This is ifd0 for most files. DNG files usually store a reduced
+    /// resolution preview in ifd0 ([`ExifTag::NewSubfileType`] is 1), and the
+    /// raw image in one of the IFDs referenced by [`ExifTag::SubIFDs`]
+    /// (`NewSubfileType` is 0). The IFD can be accessed with
+    /// [`Self::get_by_ifd_tag_code`].
+    pub fn main_image_ifd(&self) -> usize {
+        let subfile_type = |ifd| {
+            self.get_by_ifd_tag_code(ifd, ExifTag::NewSubfileType.code())
+                .and_then(|x| x.as_u32())
+        };
+        // Bit 0 means a reduced resolution version of another image
+        if !subfile_type(0).is_some_and(|x| x & 1 == 1) {
+            return 0;
+        }
+        (1..self.ifds.len())
+            .find(|ifd| subfile_type(*ifd) == Some(0))
+            .unwrap_or(0)
+    }
+
+    /// Returns the kind of a DNG file, or `None` if it's not a DNG file (there
+    /// is no [`ExifTag::DNGVersion`] in ifd0).
+    ///
+    /// Apple ProRAW files are told apart by the Apple [`ExifTag::ProfileName`]
+    /// of ifd0, or by the semantic masks (IFDs with a
+    /// [`ExifTag::SemanticName`], e.g. "Skin" or "Sky") referenced by
+    /// [`ExifTag::SubIFDs`].
+    pub fn dng_kind(&self) -> Option<DngKind> {
+        self.get(ExifTag::DNGVersion)?;
+
+        let apple_profile = self
+            .get(ExifTag::ProfileName)
+            .and_then(|x| x.as_str())
+            .is_some_and(|x| x.starts_with("Apple"));
+        let semantic_masks = (1..self.ifds.len()).any(|ifd| {
+            self.get_by_ifd_tag_code(ifd, ExifTag::SemanticName.code())
+                .is_some()
+        });
+        if apple_profile || semantic_masks {
+            Some(DngKind::AppleProRaw)
+        } else {
+            Some(DngKind::Standard)
+        }
+    }
+
+    /// Returns the `(width, height)` a viewer should present, i.e. the stored
+    /// pixel dimensions with the image orientation applied.
+    ///
+    /// The orientation is taken from the container if it provides one (e.g.
+    /// HEIF `irot`/`imir` properties), otherwise from
+    /// [`ExifTag::Orientation`]. Dimensions are swapped when the orientation
+    /// implies a 90/270 degrees rotation.
+    ///
+    /// For DNG files, the dimensions are taken from the full resolution
+    /// image, see [`Self::main_image_ifd`].
+    ///
+    /// Returns `None` if the dimensions are unknown.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use nom_exif::*;
+    ///
+    /// fn main() -> Result<()> {
+    ///     let mut parser = MediaParser::new();
+    ///
+    ///     let ms = MediaSource::file_path("./testdata/exif.heic")?;
+    ///     let iter: ExifIter = parser.parse(ms)?;
+    ///     let exif: Exif = iter.into();
+    ///
+    ///     assert_eq!(exif.display_dimensions(), Some((3024, 4032)));
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn display_dimensions(&self) -> Option<(u32, u32)> {
+        let (width, height) = self.stored_dimensions()?;
+        let orientation = self.orientation().unwrap_or_default();
+        Some(orientation.apply_to_dimensions(width, height))
+    }
+
+    /// Returns the stored pixel dimensions of the full resolution image,
+    /// without the orientation applied.
+    pub(crate) fn stored_dimensions(&self) -> Option<(u32, u32)> {
+        fn to_u32(v: &EntryValue) -> Option<u32> {
+            v.as_u32().or_else(|| v.as_u16().map(|x| x as u32))
+        }
+
+        let main = self.main_image_ifd();
+        let width = self
+            .get(ExifTag::ExifImageWidth)
+            .or_else(|| self.get_by_ifd_tag_code(main, ExifTag::ImageWidth.code()))
+            .and_then(to_u32)?;
+        let height = self
+            .get(ExifTag::ExifImageHeight)
+            .or_else(|| self.get_by_ifd_tag_code(main, ExifTag::ImageHeight.code()))
+            .and_then(to_u32)?;
+        Some((width, height))
+    }
+
+    /// Returns the image orientation.
+    ///
+    /// The orientation is taken from the container if it provides one (e.g.
+    /// HEIF `irot`/`imir` properties), otherwise from
+    /// [`ExifTag::Orientation`].
+    ///
+    /// Returns `None` if there is no orientation, or its value is invalid.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use nom_exif::*;
+    ///
+    /// fn main() -> Result<()> {
+    ///     let mut parser = MediaParser::new();
+    ///
+    ///     let ms = MediaSource::file_path("./testdata/exif.heic")?;
+    ///     let iter: ExifIter = parser.parse(ms)?;
+    ///     let exif: Exif = iter.into();
+    ///
+    ///     assert_eq!(exif.orientation(), Some(Orientation::Rotate90));
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn orientation(&self) -> Option<Orientation> {
+        self.container_orientation
+            .or_else(|| self.get(ExifTag::Orientation).and_then(|x| x.as_u16()))
+            .and_then(|x| Orientation::try_from(x).ok())
+    }
+
+    /// Returns the time of a date/time tag, i.e. [`ExifTag::DateTimeOriginal`],
+    /// [`ExifTag::CreateDate`] or [`ExifTag::ModifyDate`], combined with its
+    /// fractional seconds (`SubSecTime*`) and its own time zone
+    /// (`OffsetTime*`).
+    ///
+    /// If the tag has no time zone of its own, the time zone inferred while
+    /// parsing is kept, i.e. the zone of another `OffsetTime*` tag, or the
+    /// local time zone if there is none.
+    ///
+    /// Returns `None` for the other tags, or if the tag is not found.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use nom_exif::*;
+    ///
+    /// fn main() -> Result<()> {
+    ///     let mut parser = MediaParser::new();
+    ///
+    ///     let ms = MediaSource::file_path("./testdata/exif.jpg")?;
+    ///     let iter: ExifIter = parser.parse(ms)?;
+    ///     let exif: Exif = iter.into();
+    ///
+    ///     let t = exif.date_time(ExifTag::ModifyDate).unwrap();
+    ///     assert_eq!(t.to_rfc3339(), "2023-07-09T20:36:33.616+08:00");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn date_time(&self, tag: ExifTag) -> Option<DateTime<FixedOffset>> {
+        let (subsec, offset) = match tag {
+            ExifTag::DateTimeOriginal => (ExifTag::SubSecTimeOriginal, ExifTag::OffsetTimeOriginal),
+            ExifTag::CreateDate => (ExifTag::SubSecTimeDigitized, ExifTag::OffsetTimeDigitized),
+            ExifTag::ModifyDate => (ExifTag::SubSecTime, ExifTag::OffsetTime),
+            _ => return None,
+        };
+        let mut t = self.get(tag)?.as_time()?;
+
+        let offset = self
+            .get(offset)
+            .and_then(|x| x.as_str())
+            .and_then(|x| x.trim().parse::<FixedOffset>().ok());
+        if let Some(offset) = offset {
+            t = t.naive_local().and_local_timezone(offset).single()?;
+        }
+
+        let nanos = self
+            .get(subsec)
+            .and_then(|x| x.as_str())
+            .and_then(parse_subsec);
+        if let Some(nanos) = nanos {
+            t = t.with_nanosecond(nanos)?;
+        }
+        Some(t)
+    }
+
+    /// Returns the time of a date/time tag as a [`time::OffsetDateTime`], see
+    /// [`Self::date_time`].
+    #[cfg(feature = "time")]
+    pub fn offset_date_time(&self, tag: ExifTag) -> Option<time::OffsetDateTime> {
+        let t = self.date_time(tag)?;
+        let offset = time::UtcOffset::from_whole_seconds(t.offset().local_minus_utc()).ok()?;
+        let nanos = t.timestamp_nanos_opt()?;
+        let t = time::OffsetDateTime::from_unix_timestamp_nanos(nanos.into()).ok()?;
+        Some(t.to_offset(offset))
+    }
+
+    /// Returns the time of a date/time tag as a [`jiff::Zoned`], see
+    /// [`Self::date_time`] & [`EntryValue::as_zoned`].
+    #[cfg(feature = "jiff")]
+    pub fn zoned_date_time(&self, tag: ExifTag) -> Option<jiff::Zoned> {
+        EntryValue::Time(self.date_time(tag)?).as_zoned()
+    }
+
+    /// Returns the frame count of an image sequence, see
+    /// [`ExifIter::sequence_frame_count`].
+    pub fn sequence_frame_count(&self) -> Option<u32> {
+        self.sequence_frames
+    }
+
+    /// Returns the XMP packet found along with the Exif data, see
+    /// [`ExifIter::xmp`].
+    pub fn xmp(&self) -> Option<&str> {
+        self.xmp.as_ref().map(|x| x.standard.as_str())
+    }
+
+    /// Returns the extended XMP packet of JPEG files, see
+    /// [`ExifIter::extended_xmp`].
+    pub fn extended_xmp(&self) -> Option<&str> {
+        self.xmp.as_ref().and_then(|x| x.extended.as_deref())
+    }
+
+    /// Returns the values computed from other entries, like exiftool's
+    /// Composite tags, e.g. the 35mm equivalent focal length & the light
+    /// value, see [`Composites`].
+    ///
+    /// ```rust
+    /// use nom_exif::*;
+    ///
+    /// let mut parser = MediaParser::new();
+    /// let ms = MediaSource::file_path("./testdata/exif.jpg").unwrap();
+    /// let iter: ExifIter = parser.parse(ms).unwrap();
+    /// let exif: Exif = iter.into();
+    ///
+    /// let composites = exif.composites();
+    /// assert_eq!(composites.aperture, Some(1.75));
+    /// assert_eq!(
+    ///     composites.gps_position.as_deref(),
+    ///     Some("22 deg 31' 52.08\" N, 114 deg 1' 17.33\" E")
+    /// );
+    /// ```
+    pub fn composites(&self) -> Composites {
+        Composites::new(self)
+    }
+
+    /// Returns a canonical hash of the parsed entries (keyed by IFDs & raw
+    /// tag codes) and XMP packets, so that identical metadata can be detected
+    /// cheaply, e.g. for deduplication.
+    ///
+    /// The hash doesn't depend on the order of the entries, and is stable
+    /// across platforms & runs. The decoded MakerNote is not included, nor
+    /// anything derived from the entries (e.g. [`Exif::get_gps_info`]).
+    /// Times are hashed without their time zones if there is no `OffsetTime*`
+    /// tag, since the local time zone is assumed for them when parsing.
+    ///
+    /// ```rust
+    /// use nom_exif::*;
+    ///
+    /// let mut parser = MediaParser::new();
+    /// let ms = MediaSource::file_path("./testdata/exif.jpg").unwrap();
+    /// let iter: ExifIter = parser.parse(ms).unwrap();
+    /// let a: Exif = iter.clone().into();
+    /// let b: Exif = iter.into();
+    /// assert_eq!(a.fingerprint(), b.fingerprint());
+    /// ```
+    pub fn fingerprint(&self) -> u64 {
+        // Without `OffsetTime*` tags, times are parsed in the local time zone
+        // of the host, so only their local dates & times are hashed
+        let has_offset = [
+            ExifTag::OffsetTime,
+            ExifTag::OffsetTimeOriginal,
+            ExifTag::OffsetTimeDigitized,
+        ]
+        .into_iter()
+        .any(|x| self.get(x).is_some());
+
+        let mut fp = Fingerprint::default();
+        for (code, ifd, value) in self {
+            let key = format!("{ifd}:{code}");
+            match value {
+                EntryValue::Time(t) if !has_offset => {
+                    fp.add_local_time(key.as_bytes(), t.naive_local())
+                }
+                _ => fp.add(key.as_bytes(), value),
+            }
+        }
+        if let Some(xmp) = self.xmp() {
+            fp.add_text(b"xmp", xmp);
+        }
+        if let Some(xmp) = self.extended_xmp() {
+            fp.add_text(b"extended_xmp", xmp);
+        }
+        fp.finish()
+    }
+
+    /// Encodes the parsed metadata in a compact, versioned binary format, so
+    /// that it can be cached (e.g. by indexing services) and reloaded with
+    /// [`Exif::from_bytes`] without re-reading the original file.
+    ///
+    /// Like with `serde`, all the entries are encoded along with the GPS info
+    /// & XMP packets, but the decoded MakerNote is not.
+    ///
+    /// ```rust
+    /// use nom_exif::*;
+    ///
+    /// let mut parser = MediaParser::new();
+    /// let ms = MediaSource::file_path("./testdata/exif.jpg").unwrap();
+    /// let iter: ExifIter = parser.parse(ms).unwrap();
+    /// let exif: Exif = iter.into();
+    ///
+    /// let bytes = exif.to_bytes();
+    /// let cached = Exif::from_bytes(&bytes).unwrap();
+    /// assert_eq!(cached.get(ExifTag::Make), exif.get(ExifTag::Make));
+    /// assert_eq!(cached.fingerprint(), exif.fingerprint());
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = binary::Writer::new(binary::Kind::Exif);
+        w.put(&self.ifds.len());
+        for ifd in self.ifds.iter() {
+            let mut entries: Vec<_> = ifd.entries.iter().collect();
+            entries.sort_by_key(|(code, _)| **code);
+            w.put(&entries.len());
+            for (code, entry) in entries {
+                w.put(code).put(&entry.value);
+            }
+        }
+        w.put(&self.pages)
+            .put(&self.gps_info)
+            .put(&self.container_orientation)
+            .put(&self.sequence_frames)
+            .put(&self.xmp);
+        w.finish()
+    }
+
+    /// Decodes an `Exif` encoded by [`Exif::to_bytes`].
+    ///
+    /// Returns an error if the data is corrupted, or was encoded by an
+    /// incompatible version of this crate.
+    pub fn from_bytes(data: &[u8]) -> crate::Result<Exif> {
+        let mut r = binary::Reader::new(data, binary::Kind::Exif)?;
+        let ifds = r
+            .get::<Vec<Vec<(u16, EntryValue)>>>()?
+            .into_iter()
+            .map(|entries| {
+                let mut ifd = ParsedImageFileDirectory::new();
+                for (code, value) in entries {
+                    ifd.put(code, value);
+                }
+                ifd
+            })
+            .collect();
+        let exif = Exif {
+            ifds,
+            pages: r.get()?,
+            gps_info: r.get()?,
+            maker_note: None,
+            container_orientation: r.get()?,
+            sequence_frames: r.get()?,
+            xmp: r.get()?,
+        };
+        r.finish()?;
+        Ok(exif)
+    }
+
+    /// Returns an `Exif` which is `Send + Sync + 'static` and detached from
+    /// the parsing buffer.
+    ///
+    /// All entry values of an `Exif` are copied out of the parsing buffer
+    /// when converting from an [`ExifIter`], so this is a no-op. It exists
+    /// for symmetry with [`ExifIter::into_owned_static`], and to make the
+    /// guarantee explicit.
+    pub fn into_owned(self) -> Exif {
+        self
+    }
+
     fn put(&mut self, res: &mut ParsedExifEntry) {
         while self.ifds.len() < res.ifd_index() + 1 {
             self.ifds.push(ParsedImageFileDirectory::new());
         }
+        // The first entry of a page is always in the IFD of the page itself
+        while self.pages.len() < res.page_index() + 1 {
+            self.pages.push(res.ifd_index());
+        }
         if let Some(v) = res.take_value() {
             self.ifds[res.ifd_index()].put(res.tag_code(), v);
         }
     }
 }
 
+/// Parses the fractional seconds of a `SubSecTime*` value, e.g. "616" is
+/// 0.616 seconds, returns the nanoseconds.
+fn parse_subsec(s: &str) -> Option<u32> {
+    let digits = s.trim_matches(|c: char| c == ' ' || c == '\0');
+    if digits.is_empty() || !digits.bytes().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    // Keep nanosecond precision only
+    let digits = &digits[..digits.len().min(9)];
+    let nanos = digits.parse::<u32>().ok()?;
+    Some(nanos * 10u32.pow(9 - digits.len() as u32))
+}
+
+/// Iterates over all the parsed entries as `(tag_code, ifd_index, &value)`,
+/// ordered by IFD indexes & tag codes. Entries of the Exif & GPS sub-IFDs are
+/// included in ifd0, the same as [`Exif::get_by_ifd_tag_code`].
+///
+/// ```rust
+/// use nom_exif::*;
+///
+/// let ms = MediaSource::file_path("./testdata/exif.jpg").unwrap();
+/// let iter: ExifIter = MediaParser::new().parse(ms).unwrap();
+/// let exif: Exif = iter.into();
+///
+/// for (code, ifd, value) in &exif {
+///     println!("ifd{ifd}.0x{code:04x}: {value}");
+/// }
+/// assert!((&exif).into_iter().any(|x| x == (0x0110, 0, &"vivo X90 Pro+".into())));
+/// ```
+impl<'a> IntoIterator for &'a Exif {
+    type Item = (u16, usize, &'a EntryValue);
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let mut entries = Vec::new();
+        for (ifd, entries_of_ifd) in self.ifds.iter().enumerate() {
+            let mut codes = entries_of_ifd.entries.keys().copied().collect::<Vec<_>>();
+            codes.sort_unstable();
+            entries.extend(
+                codes
+                    .into_iter()
+                    .filter_map(|code| Some((code, ifd, entries_of_ifd.get(code)?))),
+            );
+        }
+        entries.into_iter()
+    }
+}
+
+/// Renders all the entries grouped by IFDs, in the style of `exiftool -g`,
+/// e.g. `println!("{exif}")` prints:
+///
+/// ```text
+/// ---- IFD0 ----
+/// Make                            : vivo
+/// Model                           : vivo X90 Pro+
+/// ...
+/// ExposureTime                    : 1/100
+/// ```
+///
+/// Entries of the Exif & GPS sub-IFDs are grouped with the IFD which
+/// references them, use the `Display` implementation of [`ExifIter`] to tell
+/// them apart.
+impl Display for Exif {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (ifd, entries) in self.ifds.iter().enumerate() {
+            let group = match self.pages.iter().position(|x| *x == ifd) {
+                Some(page) => IfdGroup::page(page),
+                None => IfdGroup::SubIfd,
+            };
+            let mut codes: Vec<_> = entries.entries.keys().copied().collect();
+            codes.sort_unstable();
+            let rows = codes.into_iter().filter_map(|code| {
+                let tag = ExifTag::try_from(code).ok();
+                let name = tag.map_or_else(|| format!("0x{code:04x}"), |t| t.to_string());
+                Some((name, pretty_value(tag, entries.get(code)?)))
+            });
+            write_group(f, group, rows)?;
+        }
+        Ok(())
+    }
+}
+
 impl From<ExifIter> for Exif {
     fn from(iter: ExifIter) -> Self {
         let gps_info = iter.parse_gps_info().ok().flatten();
-        let mut exif = Exif::new(gps_info);
+        let mut exif = Exif::new(gps_info, iter.container_orientation);
+        exif.sequence_frames = iter.sequence_frames;
+        exif.xmp = iter.xmp.clone();
+        exif.maker_note = iter.parse_maker_note();
 
         for mut it in iter {
             exif.put(&mut it);
@@ -159,6 +728,8 @@ impl From<ExifIter> for Exif {
 pub(crate) struct TiffHeader {
     pub endian: Endianness,
     pub ifd0_offset: u32,
+    // Panasonic RW2, whose ifd0 uses non-standard tag codes
+    pub rw2: bool,
 }
 
 impl Default for TiffHeader {
@@ -166,6 +737,7 @@ impl Default for TiffHeader {
         Self {
             endian: Endianness::Big,
             ifd0_offset: 0,
+            rw2: false,
         }
     }
 }
@@ -176,14 +748,15 @@ impl TiffHeader {
     pub fn parse(input: &[u8]) -> IResult<&[u8], TiffHeader> {
         use nom::number::streaming::{u16, u32};
         let (remain, endian) = TiffHeader::parse_endian(input)?;
-        let (_, (_, offset)) = sequence::tuple((
-            combinator::verify(u16(endian), |magic| *magic == 0x2a),
+        let (_, (magic, offset)) = sequence::tuple((
+            combinator::verify(u16(endian), |magic| is_tiff_magic(*magic)),
             u32(endian),
         ))(remain)?;
 
         let header = Self {
             endian,
             ifd0_offset: offset,
+            rw2: is_rw2_magic(magic),
         };
 
         Ok((remain, header))
@@ -195,10 +768,8 @@ impl TiffHeader {
             return Ok((remain, 0));
         }
 
-        // 12 bytes per entry
-        let size = (num as usize)
-            .checked_mul(IFD_ENTRY_SIZE)
-            .expect("should fit");
+        // 12 bytes per entry, u16::MAX * 12 always fits in a usize
+        let size = num as usize * IFD_ENTRY_SIZE;
 
         if size > remain.len() {
             return Err(nom::Err::Incomplete(Needed::new(size - remain.len())));
@@ -229,10 +800,29 @@ impl TiffHeader {
     }
 }
 
-/// data.len() MUST >= 6
+/// Standard TIFF magic number (42).
+const TIFF_MAGIC: u16 = 0x2a;
+
+/// Olympus ORF files use `IIRO`/`MMOR` (or `IIRS`) instead of the standard
+/// TIFF signature, the rest of the header is the same.
+#[cfg(feature = "raw_formats")]
+const ORF_MAGICS: [u16; 2] = [0x4f52, 0x5352];
+
+fn is_tiff_magic(magic: u16) -> bool {
+    #[cfg(feature = "raw_formats")]
+    if ORF_MAGICS.contains(&magic) {
+        return true;
+    }
+    magic == TIFF_MAGIC || is_rw2_magic(magic)
+}
+
+fn is_rw2_magic(magic: u16) -> bool {
+    cfg!(feature = "raw_formats") && magic == RW2_MAGIC
+}
+
+/// Returns true if `data` starts with an Exif header (`Exif\0\0`).
 pub(crate) fn check_exif_header(data: &[u8]) -> bool {
     use nom::bytes::complete;
-    assert!(data.len() >= 6);
 
     complete::tag::<_, _, nom::error::Error<_>>(EXIF_IDENT)(data).is_ok()
 }
@@ -250,6 +840,7 @@ pub(crate) const EXIF_IDENT: &str = "Exif\0\0";
 #[cfg(test)]
 mod tests {
     use std::io::Read;
+    use std::process::Command;
     use std::thread;
 
     use crate::partial_vec::PartialVec;
@@ -263,6 +854,57 @@ mod tests {
 
     use super::*;
 
+    #[test_case(
+        "exif.jpg",
+        "1.75|100.03|23.00|6.08|12.58|22 deg 31' 52.08\" N, 114 deg 1' 17.33\" E"
+    )]
+    #[test_case(
+        "exif.heic",
+        "1.60|171.00|26.00|10.42|12.19|43 deg 17' 24.46\" N, 84 deg 13' 37.66\" E"
+    )]
+    fn composites(path: &str, expect: &str) {
+        let iter = crate::parse_exif(open_sample(path).unwrap(), None)
+            .unwrap()
+            .unwrap();
+        let c = Exif::from(iter).composites();
+        let f = |x: Option<f64>| x.map(|x| format!("{x:.2}")).unwrap_or_default();
+        let s = format!(
+            "{}|{}|{}|{}|{}|{}",
+            f(c.aperture),
+            f(c.shutter_speed.map(|x| 1.0 / x)),
+            f(c.focal_length_35mm),
+            f(c.light_value),
+            f(c.megapixels),
+            c.gps_position.unwrap_or_default()
+        );
+        assert_eq!(s, expect);
+    }
+
+    #[test]
+    fn composites_computed() {
+        let mut writer = crate::ExifWriter::new();
+        writer
+            .set(ExifTag::ApertureValue, (4_u32, 1_u32))
+            .set(ExifTag::ShutterSpeedValue, (7_i32, 1_i32))
+            .set(ExifTag::ISOSpeedRatings, 100_u16)
+            .set(ExifTag::FocalLength, (50_u32, 1_u32))
+            .set(ExifTag::FocalPlaneXResolution, (1000_u32, 1_u32))
+            .set(ExifTag::FocalPlaneYResolution, (1000_u32, 1_u32))
+            .set(ExifTag::FocalPlaneResolutionUnit, 3_u16)
+            .set(ExifTag::ExifImageWidth, 3600_u32)
+            .set(ExifTag::ExifImageHeight, 2400_u32);
+        let data = writer.to_tiff_bytes().unwrap();
+        let exif: Exif = crate::parse_exif_bytes(&data).unwrap().into();
+
+        let c = exif.composites();
+        assert_eq!(c.aperture, Some(4.0));
+        assert_eq!(c.shutter_speed, Some(1.0 / 128.0));
+        assert_eq!(c.focal_length_35mm.map(|x| x.round()), Some(50.0));
+        assert_eq!(c.light_value, Some(11.0));
+        assert_eq!(c.megapixels, Some(8.64));
+        assert_eq!(c.gps_position, None);
+    }
+
     #[test]
     fn header() {
         let _ = tracing_subscriber::fmt().with_test_writer().try_init();
@@ -275,10 +917,31 @@ mod tests {
             TiffHeader {
                 endian: Endianness::Big,
                 ifd0_offset: 8,
+                rw2: false,
             }
         );
     }
 
+    #[test_case(b"IIRO\x08\0\0\0", Endianness::Little)]
+    #[test_case(b"IIRS\x08\0\0\0", Endianness::Little)]
+    #[test_case(b"MMOR\0\0\0\x08", Endianness::Big)]
+    fn orf_header(buf: &[u8], endian: Endianness) {
+        let res = TiffHeader::parse(buf);
+        if cfg!(feature = "raw_formats") {
+            let (_, header) = res.unwrap();
+            assert_eq!(
+                header,
+                TiffHeader {
+                    endian,
+                    ifd0_offset: 8,
+                    rw2: false,
+                }
+            );
+        } else {
+            assert!(res.is_err());
+        }
+    }
+
     #[test_case("exif.jpg")]
     fn exif_iter_gps(path: &str) {
         let buf = read_sample(path).unwrap();
@@ -322,6 +985,179 @@ mod tests {
         assert_eq!(jh.join().unwrap().trim(), expect.trim());
     }
 
+    fn assert_send_sync_static<T: Send + Sync + 'static>(_: &T) {}
+
+    #[test_case("exif.jpg")]
+    #[test_case("exif.heic")]
+    fn into_owned(path: &str) {
+        let mut parser = crate::MediaParser::new();
+        let ms = crate::MediaSource::file(open_sample(path).unwrap()).unwrap();
+        let iter: ExifIter = parser.parse(ms).unwrap();
+        let expect = iter_to_str(iter.clone());
+
+        let owned = iter.clone().into_owned_static();
+        assert_send_sync_static(&owned);
+        drop(iter);
+
+        let jh = thread::spawn(move || iter_to_str(owned));
+        assert_eq!(jh.join().unwrap(), expect);
+
+        let ms = crate::MediaSource::file(open_sample(path).unwrap()).unwrap();
+        let iter: ExifIter = parser.parse(ms).unwrap();
+        let exif = Exif::from(iter).into_owned();
+        assert_send_sync_static(&exif);
+        assert!(exif.get(ExifTag::Make).is_some());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test_case("exif.jpg")]
+    #[test_case("exif.heic")]
+    fn serde_exif(path: &str) {
+        let mut parser = crate::MediaParser::new();
+        let ms = crate::MediaSource::file(open_sample(path).unwrap()).unwrap();
+        let iter: ExifIter = parser.parse(ms).unwrap();
+
+        let make = iter
+            .clone_and_rewind()
+            .find(|x| x.tag() == Some(ExifTag::Make))
+            .unwrap();
+        let json = serde_json::to_value(&make).unwrap();
+        assert_eq!(json["tag"], 0x010f);
+        assert_eq!(json["name"], "Make");
+        assert_eq!(
+            serde_json::from_value::<EntryValue>(json["value"].clone()).unwrap(),
+            *make.get_value().unwrap()
+        );
+        assert!(json["error"].is_null());
+
+        let mut exif: Exif = iter.into();
+        let json = serde_json::to_string(&exif).unwrap();
+        let de: Exif = serde_json::from_str(&json).unwrap();
+        assert!(de.get_gps_info().unwrap().is_some());
+        // The decoded MakerNote is not serialized
+        assert!(de.get_maker_note().is_none());
+        exif.maker_note = None;
+        assert_eq!(de, exif);
+    }
+
+    #[test_case("exif.jpg")]
+    #[test_case("tif.tif")]
+    fn into_iter(path: &str) {
+        let mut parser = crate::MediaParser::new();
+        let ms = crate::MediaSource::file(open_sample(path).unwrap()).unwrap();
+        let iter: ExifIter = parser.parse(ms).unwrap();
+        let expect = iter
+            .clone_and_rewind()
+            .filter(|x| x.has_value())
+            .map(|x| (x.ifd_index(), x.tag_code()))
+            .collect::<std::collections::BTreeSet<_>>();
+
+        let exif: Exif = iter.into();
+        let entries = (&exif).into_iter().collect::<Vec<_>>();
+        assert_eq!(entries.len(), expect.len());
+        assert!(entries
+            .windows(2)
+            .all(|x| (x[0].1, x[0].0) < (x[1].1, x[1].0)));
+        for (code, ifd, value) in entries {
+            assert!(expect.contains(&(ifd, code)));
+            assert_eq!(exif.get_by_ifd_tag_code(ifd, code), Some(value));
+        }
+    }
+
+    #[test_case("616", Some(616_000_000))]
+    #[test_case("05 ", Some(50_000_000))]
+    #[test_case("1234567891", Some(123_456_789))]
+    #[test_case("", None)]
+    #[test_case("abc", None)]
+    fn parse_subsec(s: &str, nanos: Option<u32>) {
+        assert_eq!(super::parse_subsec(s), nanos);
+    }
+
+    #[test]
+    fn date_time() {
+        let mut parser = crate::MediaParser::new();
+        let ms = crate::MediaSource::file(open_sample("exif.jpg").unwrap()).unwrap();
+        let iter: ExifIter = parser.parse(ms).unwrap();
+        let exif: Exif = iter.into();
+
+        let t = exif.date_time(ExifTag::DateTimeOriginal).unwrap();
+        assert_eq!(t.to_rfc3339(), "2023-07-09T20:36:33.616+08:00");
+        assert!(exif.date_time(ExifTag::Make).is_none());
+
+        #[cfg(feature = "time")]
+        {
+            let t2 = exif.offset_date_time(ExifTag::DateTimeOriginal).unwrap();
+            assert_eq!(
+                t2.unix_timestamp_nanos(),
+                t.timestamp_nanos_opt().unwrap().into()
+            );
+            assert_eq!(t2.offset().whole_seconds(), 8 * 3600);
+        }
+
+        #[cfg(feature = "jiff")]
+        {
+            let t3 = exif.zoned_date_time(ExifTag::DateTimeOriginal).unwrap();
+            assert_eq!(t3.to_string(), "2023-07-09T20:36:33.616+08:00[+08:00]");
+        }
+    }
+
+    /// Times without `OffsetTime*` tags are parsed in the local time zone,
+    /// which must not change the fingerprint. The time zone (`TZ`) is global
+    /// to the process, so it's set for child processes running this test.
+    #[test]
+    fn fingerprint_time_zone() {
+        const CHILD: &str = "NOM_EXIF_FINGERPRINT_CHILD";
+        if std::env::var_os(CHILD).is_some() {
+            let exif = parse_exif(read_sample("exif.jpg").unwrap());
+            let mut writer = crate::ExifWriter::from_exif(&exif);
+            for tag in [
+                ExifTag::OffsetTime,
+                ExifTag::OffsetTimeOriginal,
+                ExifTag::OffsetTimeDigitized,
+            ] {
+                writer.remove(tag);
+            }
+            let exif = parse_exif(writer.to_tiff_bytes().unwrap());
+            let t = exif.get(ExifTag::DateTimeOriginal).unwrap().as_time();
+            println!("offset: {}", t.unwrap().offset());
+            println!("fingerprint: {:016x}", exif.fingerprint());
+            return;
+        }
+
+        let run = |tz: &str| {
+            let output = Command::new(std::env::current_exe().unwrap())
+                .args([
+                    "--exact",
+                    "exif::exif_exif::tests::fingerprint_time_zone",
+                    "--nocapture",
+                ])
+                .env(CHILD, "1")
+                .env("TZ", tz)
+                .output()
+                .unwrap();
+            assert!(output.status.success());
+            // The output may follow the test name on the same line
+            let stdout = String::from_utf8(output.stdout).unwrap();
+            let line = |prefix| {
+                stdout
+                    .lines()
+                    .find_map(|x| Some(x.split_once(prefix)?.1))
+                    .unwrap()
+                    .to_owned()
+            };
+            (line("offset: "), line("fingerprint: "))
+        };
+        let (utc, shanghai) = (run("UTC0"), run("CST-8"));
+        assert_ne!(utc.0, shanghai.0);
+        assert_eq!(utc.1, shanghai.1);
+    }
+
+    fn parse_exif(data: Vec<u8>) -> Exif {
+        let ms = crate::MediaSource::from_vec(data).unwrap();
+        let iter: ExifIter = crate::MediaParser::new().parse(ms).unwrap();
+        iter.into()
+    }
+
     fn iter_to_str(it: impl Iterator<Item = ParsedExifEntry>) -> String {
         let ss = it
             .map(|x| {
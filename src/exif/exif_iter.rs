@@ -1,4 +1,9 @@
-use std::{fmt::Debug, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{Debug, Display},
+    ops::Range,
+    sync::Arc,
+};
 
 use nom::{
     number::{complete, Endianness},
@@ -7,13 +12,24 @@ use nom::{
 use thiserror::Error;
 
 use crate::{
+    json::JsonObject,
+    parser_core::Limits,
     partial_vec::{AssociatedInput, PartialVec},
+    report::{pretty_value, write_group},
     slice::SliceChecked,
     values::{DataFormat, EntryData, IRational, ParseEntryError, URational},
+    xmp::XmpPackets,
     EntryValue, ExifTag,
 };
 
-use super::{exif_exif::IFD_ENTRY_SIZE, tags::ExifTagCode, GPSInfo, TiffHeader};
+use super::{
+    exif_exif::IFD_ENTRY_SIZE,
+    makernote, print_conv,
+    rw2::{is_panasonic_specific, map_rw2_tag},
+    tags::ExifTagCode,
+    text::{decode_user_comment, decode_xp_text, is_xp_tag, TextEncoding},
+    CustomTag, GPSInfo, GeoTiffInfo, IfdGroup, MakerNote, TiffHeader,
+};
 
 /// Parses header from input data, and returns an [`ExifIter`].
 ///
@@ -57,6 +73,7 @@ pub(crate) fn input_into_iter(
         None,
     )?;
 
+    ifd0.rw2 = header.rw2;
     let tz = ifd0.find_tz_offset();
     ifd0.tz = tz.clone();
     let iter: ExifIter = ExifIter::new(input, header, tz, ifd0);
@@ -85,8 +102,44 @@ pub struct ExifIter {
     tz: Option<String>,
     ifd0: IfdIter,
 
+    /// Orientation (as an Exif Orientation value) provided by the container,
+    /// e.g.: HEIF `irot`/`imir` properties of the primary item.
+    pub(crate) container_orientation: Option<u16>,
+
+    /// Frame count of an image sequence, e.g.: HEIF image sequences.
+    pub(crate) sequence_frames: Option<u32>,
+
+    /// XMP packets found along with the Exif data, e.g.: in JPEG APP1
+    /// segments.
+    pub(crate) xmp: Option<Arc<XmpPackets>>,
+
+    /// Offset of the TIFF header in the media source, if known, see
+    /// [`RawEntry::file_offset`].
+    pub(crate) tiff_offset: Option<u64>,
+
+    /// See [`ExifIter::register_tag`].
+    pub(crate) custom_tags: Arc<Vec<CustomTag>>,
+
+    /// See [`ExifIter::set_text_encoding`].
+    pub(crate) text_encoding: TextEncoding,
+
+    /// See [`MediaParserBuilder`](crate::MediaParserBuilder).
+    pub(crate) limits: Limits,
+
     // Iterating status
     ifds: Vec<IfdIter>,
+
+    // Offsets of IFDs which have been entered, used to break reference loops
+    // in corrupted files.
+    visited: HashSet<u32>,
+
+    // Only iterates over the entries of `ifd0` (which is the scoped IFD), see
+    // `ExifIter::scoped`
+    scoped: bool,
+
+    // Number of IFD indexes which have been assigned, see
+    // `ParsedExifEntry::ifd_index`
+    ifd_count: usize,
 }
 
 impl Debug for ExifIter {
@@ -101,6 +154,46 @@ impl Debug for ExifIter {
     }
 }
 
+/// Renders a snapshot of all the entries (the iterator itself is not
+/// advanced) grouped by [`IfdGroup`]s, in the style of `exiftool -g`, e.g.
+/// `println!("{iter}")` prints:
+///
+/// ```text
+/// ---- IFD0 ----
+/// Make                            : vivo
+/// ...
+/// ---- ExifIFD ----
+/// ExposureTime                    : 1/100
+/// ...
+/// ---- GPS ----
+/// GPSLatitudeRef                  : N
+/// ```
+///
+/// Entries which failed to parse are rendered as `<error: ...>`.
+impl Display for ExifIter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut groups: Vec<(IfdGroup, Vec<(String, String)>)> = Vec::new();
+        for entry in self.clone_and_rewind() {
+            let name = entry
+                .name()
+                .map_or_else(|| format!("0x{:04x}", entry.tag_code()), str::to_owned);
+            let value = match entry.get_result() {
+                Ok(v) => pretty_value(entry.tag(), v),
+                Err(e) => format!("<error: {e}>"),
+            };
+            let group = entry.ifd_group();
+            match groups.iter_mut().find(|(g, _)| *g == group) {
+                Some((_, rows)) => rows.push((name, value)),
+                None => groups.push((group, vec![(name, value)])),
+            }
+        }
+        for (group, rows) in groups {
+            write_group(f, group, rows)?;
+        }
+        Ok(())
+    }
+}
+
 impl Clone for ExifIter {
     fn clone(&self) -> Self {
         self.clone_and_rewind()
@@ -115,12 +208,23 @@ impl ExifIter {
         ifd0: IfdIter,
     ) -> ExifIter {
         let ifds = vec![ifd0.clone()];
+        let visited = HashSet::from([ifd0.offset]);
         ExifIter {
             input: Arc::new(input.into()),
             tiff_header,
             tz,
             ifd0,
+            container_orientation: None,
+            sequence_frames: None,
+            xmp: None,
+            tiff_offset: None,
+            custom_tags: Arc::default(),
+            text_encoding: TextEncoding::default(),
+            limits: Limits::default(),
             ifds,
+            visited,
+            scoped: false,
+            ifd_count: 1,
         }
     }
 
@@ -131,15 +235,229 @@ impl ExifIter {
     pub fn clone_and_rewind(&self) -> Self {
         let ifd0 = self.ifd0.clone_and_rewind();
         let ifds = vec![ifd0.clone()];
+        let visited = HashSet::from([ifd0.offset]);
         Self {
             input: self.input.clone(),
             tiff_header: self.tiff_header.clone(),
             tz: self.tz.clone(),
             ifd0,
+            container_orientation: self.container_orientation,
+            sequence_frames: self.sequence_frames,
+            xmp: self.xmp.clone(),
+            tiff_offset: self.tiff_offset,
+            custom_tags: self.custom_tags.clone(),
+            text_encoding: self.text_encoding,
+            limits: self.limits,
             ifds,
+            visited,
+            scoped: self.scoped,
+            ifd_count: 1,
         }
     }
 
+    /// Returns an iterator over the entries of the Exif sub-IFD (of ifd0)
+    /// only, or `None` if there is no Exif sub-IFD.
+    ///
+    /// Like the other scoped iterators (see [`Self::ifd`]), it doesn't enter
+    /// other IFDs, e.g. the Interop IFD referenced by the Exif sub-IFD.
+    ///
+    /// Calling this method won't affect the iterator's state.
+    ///
+    /// ```rust
+    /// use nom_exif::*;
+    ///
+    /// let ms = MediaSource::file_path("./testdata/exif.jpg").unwrap();
+    /// let iter: ExifIter = MediaParser::new().parse(ms).unwrap();
+    /// let tags = iter.exif_ifd().unwrap().filter_map(|x| x.tag()).collect::<Vec<_>>();
+    /// assert!(tags.contains(&ExifTag::ExposureTime));
+    /// assert!(!tags.contains(&ExifTag::Make));
+    /// ```
+    pub fn exif_ifd(&self) -> Option<ExifIter> {
+        self.find_ifd(|x| x.ifd_idx == 0 && x.group() == IfdGroup::ExifIfd)
+    }
+
+    /// Returns an iterator over the entries of the GPS sub-IFD (of ifd0)
+    /// only, or `None` if there is no GPS sub-IFD, see [`Self::exif_ifd`].
+    ///
+    /// Calling this method won't affect the iterator's state.
+    pub fn gps_ifd(&self) -> Option<ExifIter> {
+        self.find_ifd(|x| x.ifd_idx == 0 && x.group() == IfdGroup::Gps)
+    }
+
+    /// Returns an iterator over the entries of the Interop IFD (referenced by
+    /// [`ExifTag::InteropOffset`] of the Exif sub-IFD) only, or `None` if
+    /// there is no Interop IFD, see [`Self::exif_ifd`].
+    ///
+    /// The Interop IFD is not entered when iterating over all the entries,
+    /// since its tag codes (e.g. 0x0001 `InteropIndex`) overlap the GPS ones.
+    /// For the same reason, its entries are reported as unrecognized tags,
+    /// use [`ParsedExifEntry::tag_code`] to get the tag codes.
+    ///
+    /// Calling this method won't affect the iterator's state.
+    ///
+    /// ```rust
+    /// use nom_exif::*;
+    ///
+    /// let ms = MediaSource::file_path("./testdata/exif.jpg").unwrap();
+    /// let iter: ExifIter = MediaParser::new().parse(ms).unwrap();
+    /// let mut interop = iter.interop_ifd().unwrap();
+    /// let index = interop.find(|x| x.tag_code() == 0x0001).unwrap();
+    /// assert_eq!(index.get_value(), Some(&"R98".into()));
+    /// ```
+    pub fn interop_ifd(&self) -> Option<ExifIter> {
+        let entry = self
+            .exif_ifd()?
+            .find(|x| x.tag() == Some(ExifTag::InteropOffset))?;
+        let offset = entry.get_value()?.as_u32()?;
+        let data = offset
+            .checked_sub(self.input_offset())
+            .and_then(|pos| self.input.get(pos as usize..))
+            .filter(|x| !x.is_empty())?;
+        let mut ifd = IfdIter::try_new(
+            entry.ifd,
+            self.input.partial(data),
+            offset,
+            self.tiff_header.endian,
+            self.tz.clone(),
+        )
+        .ok()?
+        .tag_code(ExifTag::InteropOffset.code());
+        ifd.page = entry.page;
+        Some(self.scoped(ifd))
+    }
+
+    /// Returns an iterator over the entries of the IFD whose index is `n`
+    /// (see [`ParsedExifEntry::ifd_index`]) only, e.g. `ifd(1)` for the
+    /// thumbnail of JPEG files. Returns `None` if there is no such IFD.
+    ///
+    /// The entries of the Exif & GPS sub-IFDs are not included, although
+    /// they have the same IFD index, use [`Self::exif_ifd`] &
+    /// [`Self::gps_ifd`] to get them.
+    ///
+    /// Calling this method won't affect the iterator's state.
+    ///
+    /// ```rust
+    /// use nom_exif::*;
+    ///
+    /// let ms = MediaSource::file_path("./testdata/exif.jpg").unwrap();
+    /// let iter: ExifIter = MediaParser::new().parse(ms).unwrap();
+    /// let mut ifd1 = iter.ifd(1).unwrap();
+    /// assert!(ifd1.all(|x| x.ifd_index() == 1));
+    /// assert!(iter.ifd(2).is_none());
+    /// ```
+    pub fn ifd(&self, n: usize) -> Option<ExifIter> {
+        self.find_ifd(|x| x.ifd_idx == n && (x.tag_code.is_none() || x.group() == IfdGroup::SubIfd))
+    }
+
+    /// Finds the first IFD matching `f` by iterating over all the entries,
+    /// and returns a scoped iterator of it.
+    fn find_ifd(&self, f: impl Fn(&IfdIter) -> bool) -> Option<ExifIter> {
+        let mut iter = self.clone_and_rewind();
+        loop {
+            // Each IFD is on the top of the stack once one of its entries
+            // has been returned (or before the first one, for ifd0)
+            if let Some(ifd) = iter.ifds.last().filter(|x| f(x)) {
+                return Some(self.scoped(ifd.clone_and_rewind()));
+            }
+            iter.next()?;
+        }
+    }
+
+    /// Returns an iterator over the entries of `ifd` only.
+    fn scoped(&self, ifd: IfdIter) -> ExifIter {
+        let mut iter = self.clone_and_rewind();
+        iter.ifd0 = ifd;
+        iter.scoped = true;
+        iter.clone_and_rewind()
+    }
+
+    /// Registers a custom tag, so that entries with the tag code are reported
+    /// with its name (and decoded in its data format), see [`CustomTag`].
+    ///
+    /// ```rust
+    /// use nom_exif::*;
+    ///
+    /// // ifd0 with a proprietary entry: 0xabcd, UNDEFINED, "ok\0"
+    /// let mut data = b"II*\0\x08\0\0\0\x01\0".to_vec();
+    /// data.extend([0xcd, 0xab, 7, 0, 3, 0, 0, 0, b'o', b'k', 0, 0, 0, 0, 0, 0]);
+    ///
+    /// let mut iter = parse_exif_bytes(&data).unwrap();
+    /// iter.register_tag(CustomTag {
+    ///     data_format: Some(2), // ASCII
+    ///     ..CustomTag::new(0xabcd, "CameraInfo")
+    /// });
+    /// let entry = iter.next().unwrap();
+    /// assert_eq!(entry.name(), Some("CameraInfo"));
+    /// assert_eq!(entry.get_value(), Some(&"ok".into()));
+    /// ```
+    pub fn register_tag(&mut self, tag: CustomTag) {
+        Arc::make_mut(&mut self.custom_tags).push(tag);
+    }
+
+    /// Sets the encoding used to decode the text of
+    /// [`ExifTag::UserComment`] whose character code is undefined (or not
+    /// supported, e.g. JIS), UTF-8 by default.
+    ///
+    /// `UserComment` values stored as UNDEFINED are reported as
+    /// [`EntryValue::Text`], with the 8-byte character code prefix removed.
+    ///
+    /// ```rust
+    /// use nom_exif::*;
+    ///
+    /// // ifd0 with a UserComment: UNDEFINED, undefined character code, "café"
+    /// let mut data = b"II*\0\x08\0\0\0\x01\0".to_vec();
+    /// data.extend([0x86, 0x92, 7, 0, 12, 0, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0]);
+    /// data.extend(b"\0\0\0\0\0\0\0\0caf\xe9");
+    ///
+    /// let mut iter = parse_exif_bytes(&data).unwrap();
+    /// iter.set_text_encoding(TextEncoding::Latin1);
+    /// let entry = iter.next().unwrap();
+    /// assert_eq!(entry.get_value(), Some(&"café".into()));
+    /// ```
+    pub fn set_text_encoding(&mut self, encoding: TextEncoding) {
+        self.text_encoding = encoding;
+    }
+
+    /// Renders all the entries as a JSON object keyed by exiftool style tag
+    /// names (i.e. the names of [`ExifTag`]s) with human readable values,
+    /// like the objects printed by `exiftool -json` (without `SourceFile`).
+    ///
+    /// Unrecognized tags (except the ones registered by
+    /// [`Self::register_tag`]), sub-IFD pointers and entries which failed to
+    /// parse are skipped. If a tag appears in several IFDs (e.g. `ImageWidth` of
+    /// ifd0 & ifd1), the first one wins, the same as exiftool.
+    ///
+    /// Calling this method won't affect the iterator's state.
+    ///
+    /// ```rust
+    /// use nom_exif::*;
+    ///
+    /// let ms = MediaSource::file_path("./testdata/exif.jpg").unwrap();
+    /// let iter: ExifIter = MediaParser::new().parse(ms).unwrap();
+    /// let json = iter.to_json();
+    /// assert!(json.contains(r#""Make": "vivo""#));
+    /// ```
+    pub fn to_json(&self) -> String {
+        let mut obj = JsonObject::default();
+        for entry in self.clone_and_rewind() {
+            let (Some(name), Some(value)) = (entry.name(), entry.get_value()) else {
+                continue;
+            };
+            if !matches!(
+                entry.tag(),
+                Some(
+                    ExifTag::ExifOffset
+                        | ExifTag::GPSInfo
+                        | ExifTag::InteropOffset
+                        | ExifTag::SubIFDs
+                )
+            ) {
+                obj.insert(name, value);
+            }
+        }
+        obj.finish()
+    }
+
     /// Try to find and parse gps information.
     ///
     /// Calling this method won't affect the iterator's state.
@@ -174,9 +492,19 @@ impl ExifIter {
             Err(e) => return Err(e.clone().into()),
         };
 
+        let Some(data) = offset
+            .checked_sub(iter.input_offset())
+            .and_then(|pos| iter.input.get(pos as usize..))
+            .filter(|x| !x.is_empty())
+        else {
+            return Err(EntryError(ParseEntryError::InvalidData(
+                "gps offset out of range".into(),
+            ))
+            .into());
+        };
         let mut gps_subifd = match IfdIter::try_new(
             gps.ifd,
-            iter.input.partial(&iter.input[offset as usize..]),
+            iter.input.partial(data),
             offset,
             iter.tiff_header.endian,
             iter.tz.clone(),
@@ -187,13 +515,223 @@ impl ExifIter {
         Ok(gps_subifd.parse_gps_info())
     }
 
+    /// Returns the offset (relative to the TIFF header) of the input data.
+    ///
+    /// It's not 0 when the data before ifd0 has been skipped, e.g. for TIFF
+    /// files whose ifd0 is located at the end of the file.
+    pub(crate) fn input_offset(&self) -> u32 {
+        let ifd0_pos = self.ifd0.input.range.start - self.input.range.start;
+        self.ifd0.offset.saturating_sub(ifd0_pos as u32)
+    }
+
+    /// Try to find and parse GeoTIFF georeferencing information in ifd0,
+    /// returns `None` if there is no GeoKey directory.
+    ///
+    /// Calling this method won't affect the iterator's state.
+    pub fn parse_geotiff_info(&self) -> Option<GeoTiffInfo> {
+        const GEOTIFF_TAGS: [ExifTag; 6] = [
+            ExifTag::GeoKeyDirectory,
+            ExifTag::GeoDoubleParams,
+            ExifTag::GeoAsciiParams,
+            ExifTag::ModelPixelScale,
+            ExifTag::ModelTiepoint,
+            ExifTag::ModelTransformation,
+        ];
+        let entries = self
+            .clone_and_rewind()
+            .filter(|x| x.ifd_index() == 0)
+            .filter_map(|mut x| {
+                let tag = x.tag().filter(|t| GEOTIFF_TAGS.contains(t))?;
+                Some((tag, x.take_value()?))
+            })
+            .collect::<HashMap<_, _>>();
+        GeoTiffInfo::from_entries(|x| entries.get(&x))
+    }
+
+    /// Try to find and decode the `MakerNote` of the Exif sub-IFD according
+    /// to the `Make` of ifd0, see [`MakerNote`] for the supported
+    /// manufacturers.
+    ///
+    /// Returns `None` if there is no MakerNote, or if its format is not
+    /// supported.
+    ///
+    /// Calling this method won't affect the iterator's state.
+    pub fn parse_maker_note(&self) -> Option<MakerNote> {
+        let make =
+            self.ifd0
+                .clone_and_rewind()
+                .find_map(|(code, entry)| match (code?.code(), entry) {
+                    (x, IfdEntry::Entry(v)) if x == ExifTag::Make.code() => {
+                        v.as_str().map(|x| x.to_owned())
+                    }
+                    _ => None,
+                })?;
+        let offset = self.maker_note_offset()?;
+        MakerNote::parse(&make, &self.input, offset as usize, self.tiff_header.endian)
+    }
+
+    /// Returns an iterator over the entries of the MakerNote IFD, or `None` if
+    /// the MakerNote is not an IFD, or its format is unknown, see
+    /// [`MakerNote`] for the supported manufacturers.
+    ///
+    /// The entries are reported as unrecognized tags in the
+    /// [`IfdGroup::MakerNote`] group, use [`ParsedExifEntry::tag_code`] to
+    /// get the vendor specific tag codes, or register [`CustomTag`]s to name
+    /// them. Like [`Self::exif_ifd`], the iterator doesn't enter other IFDs.
+    ///
+    /// Calling this method won't affect the iterator's state.
+    pub fn maker_note_ifd(&self) -> Option<ExifIter> {
+        let make = self.clone_and_rewind().find_map(|x| match x.tag() {
+            Some(ExifTag::Make) => x.get_value()?.as_str().map(|x| x.to_owned()),
+            _ => None,
+        })?;
+        let offset = self.maker_note_offset()?;
+        let located =
+            makernote::locate_ifd(&make, &self.input, offset as usize, self.tiff_header.endian)?;
+        let data = self
+            .input
+            .get(located.base.checked_add(located.offset)?..)?;
+        let ifd = IfdIter::try_new(
+            0,
+            self.input.partial(data),
+            located.offset as u32,
+            located.endian,
+            self.tz.clone(),
+        )
+        .ok()?
+        .tag_code(ExifTag::MakerNote.code());
+
+        let mut iter = self.scoped(ifd);
+        // Offsets of the entries are relative to the base
+        iter.tiff_offset = self.tiff_offset.map(|x| x + located.base as u64);
+        Some(iter)
+    }
+
+    /// Returns the offset (relative to the TIFF header) of the MakerNote data.
+    pub(crate) fn maker_note_offset(&self) -> Option<u32> {
+        self.ifd0
+            .clone_and_rewind()
+            .find_exif_iter()?
+            .find_entry_offset(ExifTag::MakerNote.code())
+    }
+
+    /// Returns an iterator over the entries of the specified `ifd` (0: the
+    /// main image, 1: the thumbnail, etc.), so that tags appearing in several
+    /// IFDs (e.g. `ImageWidth` of ifd0 & ifd1) can be told apart. Entries of
+    /// the Exif & GPS sub-IFDs belong to ifd0.
+    ///
+    /// Calling this method won't affect the iterator's state.
+    ///
+    /// ```rust
+    /// use nom_exif::*;
+    ///
+    /// let ms = MediaSource::file_path("./testdata/exif.jpg").unwrap();
+    /// let iter: ExifIter = MediaParser::new().parse(ms).unwrap();
+    /// let width = iter
+    ///     .entries_in(1)
+    ///     .find(|x| x.tag() == Some(ExifTag::ExifImageWidth))
+    ///     .unwrap();
+    /// assert_eq!(width.get_value(), Some(&240_u32.into()));
+    /// ```
+    pub fn entries_in(&self, ifd: usize) -> impl Iterator<Item = ParsedExifEntry> {
+        self.clone_and_rewind()
+            .filter(move |x| x.ifd_index() == ifd)
+    }
+
+    /// Collects all entries with unrecognized tags (i.e. the tags not defined
+    /// in [`ExifTag`]), including their IFD index, data format and raw data.
+    ///
+    /// This is a reporting mode to help finding out which vendor specific
+    /// tags appear in the wild. Print the results (see [`UnknownTag`]'s
+    /// `Display` impl), and consider filing an issue to get them supported.
+    ///
+    /// Calling this method won't affect the iterator's state.
+    pub fn unknown_tags(&self) -> Vec<UnknownTag> {
+        let mut iter = self.clone_and_rewind();
+        iter.ifds.iter_mut().for_each(|x| x.record_unknown = true);
+        iter.filter_map(|x| x.unknown.map(|x| *x)).collect()
+    }
+
+    /// Returns the number of pages, i.e. the number of IFDs chained from
+    /// ifd0. Each page of a multi-page TIFF file is described by an IFD, see
+    /// [`ParsedExifEntry::page_index`].
+    ///
+    /// Calling this method won't affect the iterator's state.
+    pub fn page_count(&self) -> usize {
+        let mut visited = HashSet::from([self.ifd0.offset]);
+        let mut ifd = self.ifd0.clone();
+        let mut count = 1;
+        while let Some(next) = ifd.next_ifd() {
+            if !visited.insert(next.offset) {
+                break;
+            }
+            count += 1;
+            ifd = next;
+        }
+        count
+    }
+
+    /// Returns the frame count of an image sequence, e.g.: a HEIF image
+    /// sequence (*.heics, Apple burst photos), whose frames are stored in a
+    /// track. The Exif data is the one of the primary image.
+    ///
+    /// Returns `None` for still images.
+    pub fn sequence_frame_count(&self) -> Option<u32> {
+        self.sequence_frames
+    }
+
+    /// Returns the XMP packet found along with the Exif data, currently only
+    /// the XMP of JPEG files is extracted. Parse an [`Xmp`](crate::Xmp)
+    /// for the XMP of other formats.
+    pub fn xmp(&self) -> Option<&str> {
+        self.xmp.as_ref().map(|x| x.standard.as_str())
+    }
+
+    /// Returns the extended XMP packet of JPEG files, which is reassembled
+    /// from multiple APP1 segments, since it's too large to fit in one.
+    ///
+    /// The extended XMP is referenced by the `xmpNote:HasExtendedXMP`
+    /// property of the standard XMP packet, see [`Self::xmp`].
+    pub fn extended_xmp(&self) -> Option<&str> {
+        self.xmp.as_ref().and_then(|x| x.extended.as_deref())
+    }
+
+    /// Converts into an `ExifIter` which owns a copy of the Exif data only,
+    /// so it's fully detached from the parsing buffer of
+    /// [`MediaParser`](crate::MediaParser).
+    ///
+    /// An `ExifIter` returned by [`MediaParser::parse`](crate::MediaParser::parse)
+    /// keeps the whole parsing buffer alive (and prevents it from being
+    /// reused). Use this method when you need to keep the `ExifIter` for a
+    /// long time, e.g. put it into a cache or send it to another thread.
+    ///
+    /// Like [`clone_and_rewind`](Self::clone_and_rewind), the iteration index
+    /// of the returned `ExifIter` is reset.
+    pub fn into_owned_static(self) -> ExifIter {
+        self.to_owned()
+    }
+
     pub(crate) fn to_owned(&self) -> ExifIter {
-        ExifIter::new(
-            self.input.to_vec(),
+        let data = bytes::Bytes::copy_from_slice(&self.input);
+        let base = self.input.range.start;
+
+        // IFD0 references a sub range of the input data, move it to the new
+        // buffer as well.
+        let mut ifd0 = self.ifd0.clone_and_rewind();
+        let start = ifd0.input.range.start - base;
+        ifd0.input = PartialVec::new(data.clone(), start..start + ifd0.input.len());
+
+        let len = data.len();
+        let mut iter = ExifIter::new(
+            PartialVec::new(data, 0..len),
             self.tiff_header.clone(),
             self.tz.clone(),
-            self.ifd0.clone_and_rewind(),
-        )
+            ifd0,
+        );
+        iter.container_orientation = self.container_orientation;
+        iter.sequence_frames = self.sequence_frames;
+        iter.xmp = self.xmp.clone();
+        iter
     }
 }
 
@@ -208,22 +746,70 @@ impl From<EntryError> for crate::Error {
 }
 
 /// Represents a parsed IFD entry. Used as iterator items in [`ExifIter`].
+///
+/// With the `serde` feature, an entry is serialized as a struct of `ifd`,
+/// `page`, `tag` (the raw tag code), `name` (the name of the [`ExifTag`], or
+/// null if unrecognized), `value` and `error` (one of them is null).
 #[derive(Clone)]
 pub struct ParsedExifEntry {
     // 0: ifd0, 1: ifd1
     ifd: usize,
+    // Index in the IFD chain starting from ifd0
+    page: usize,
+    group: IfdGroup,
     tag: ExifTagCode,
     res: Option<Result<EntryValue, EntryError>>,
+
+    // Only recorded in the mode of `ExifIter::unknown_tags`
+    unknown: Option<Box<UnknownTag>>,
+
+    raw: RawEntry,
+
+    // See `ExifIter::register_tag`
+    custom: Option<CustomTag>,
 }
 
 impl ParsedExifEntry {
     /// Get the IFD index value where this entry is located.
     /// - 0: ifd0 (main image)
     /// - 1: ifd1 (thumbnail)
+    ///
+    /// IFDs referenced by [`ExifTag::SubIFDs`] (e.g. the raw images of DNG
+    /// files) are numbered in the order they are encountered, so they come
+    /// before ifd1 if the file has both.
     pub fn ifd_index(&self) -> usize {
         self.ifd
     }
 
+    /// Get the page index where this entry is located.
+    ///
+    /// Pages are the IFDs chained from ifd0, e.g. each page of a multi-page
+    /// TIFF file is described by an IFD. Entries of sub-IFDs (Exif, GPS and
+    /// [`ExifTag::SubIFDs`]) belong to the page which references them.
+    ///
+    /// For JPEG/HEIF files, page 1 (if any) is the thumbnail.
+    pub fn page_index(&self) -> usize {
+        self.page
+    }
+
+    /// Get the logical group of the IFD where this entry is located, e.g.
+    /// [`IfdGroup::ExifIfd`] for `ExposureTime`, mirroring exiftool's group
+    /// names. Unlike [`Self::ifd_index`], it tells the entries of the Exif &
+    /// GPS sub-IFDs apart from the ones of ifd0.
+    ///
+    /// ```rust
+    /// use nom_exif::*;
+    ///
+    /// let ms = MediaSource::file_path("./testdata/exif.jpg").unwrap();
+    /// let mut iter: ExifIter = MediaParser::new().parse(ms).unwrap();
+    /// let entry = iter.find(|x| x.tag() == Some(ExifTag::GPSLatitude)).unwrap();
+    /// assert_eq!(entry.ifd_index(), 0);
+    /// assert_eq!(entry.ifd_group(), IfdGroup::Gps);
+    /// ```
+    pub fn ifd_group(&self) -> IfdGroup {
+        self.group
+    }
+
     /// Get recognized Exif tag of this entry, maybe return `None` if the tag
     /// is unrecognized.
     ///
@@ -239,6 +825,23 @@ impl ParsedExifEntry {
         }
     }
 
+    /// Returns the name of the tag, i.e. the name of the recognized
+    /// [`ExifTag`], or of the [`CustomTag`] registered for the tag code.
+    /// Returns `None` if the tag is unrecognized.
+    pub fn name(&self) -> Option<&str> {
+        match (self.tag(), &self.custom) {
+            (Some(tag), _) => Some(tag.name()),
+            (None, Some(custom)) => Some(&custom.name),
+            (None, None) => None,
+        }
+    }
+
+    /// Returns the [`CustomTag`] registered for this entry, if any, see
+    /// [`ExifIter::register_tag`].
+    pub fn custom_tag(&self) -> Option<&CustomTag> {
+        self.custom.as_ref()
+    }
+
     /// Get the raw tag code of this entry.
     ///
     /// In case you have some custom defined tags which doesn't exist in
@@ -258,6 +861,25 @@ impl ParsedExifEntry {
         self.res.as_ref().map(|e| e.is_ok()).is_some_and(|b| b)
     }
 
+    /// Get the raw form of this entry as stored in the file, i.e. its data
+    /// format, components count, value bytes and offsets, see [`RawEntry`].
+    ///
+    /// ```rust
+    /// use nom_exif::*;
+    ///
+    /// let ms = MediaSource::file_path("./testdata/exif.jpg").unwrap();
+    /// let mut iter: ExifIter = MediaParser::new().parse(ms).unwrap();
+    /// let make = iter.find(|x| x.tag() == Some(ExifTag::Make)).unwrap();
+    /// let raw = make.raw_entry();
+    /// assert_eq!(raw.data_format(), 2); // ASCII
+    /// assert_eq!(raw.components_num(), 5);
+    /// assert_eq!(raw.data(), b"vivo\0");
+    /// assert!(raw.file_offset().is_some());
+    /// ```
+    pub fn raw_entry(&self) -> &RawEntry {
+        &self.raw
+    }
+
     /// Get the parsed entry value of this entry.
     pub fn get_value(&self) -> Option<&EntryValue> {
         match self.res.as_ref() {
@@ -266,6 +888,35 @@ impl ParsedExifEntry {
         }
     }
 
+    /// Returns a human readable description of the entry value, e.g.
+    /// "Normal program" for an `ExposureProgram` of 2, "1/250" for an
+    /// `ExposureTime` of 0.004, or "Auto, Fired" for a `Flash` of 0x19.
+    ///
+    /// Returns `None` if the entry has no value, or if the tag has no
+    /// interpretation (e.g. `Make`); use the `Display` text of the value in
+    /// that case.
+    ///
+    /// ```rust
+    /// use nom_exif::*;
+    ///
+    /// let mut parser = MediaParser::new();
+    /// let ms = MediaSource::file_path("./testdata/exif.jpg").unwrap();
+    /// let iter: ExifIter = parser.parse(ms).unwrap();
+    ///
+    /// for entry in iter {
+    ///     let Some(value) = entry.get_value() else { continue };
+    ///     let text = entry
+    ///         .interpreted_value()
+    ///         .unwrap_or_else(|| value.to_string());
+    ///     if entry.tag() == Some(ExifTag::ExposureTime) {
+    ///         assert_eq!(text, "1/100");
+    ///     }
+    /// }
+    /// ```
+    pub fn interpreted_value(&self) -> Option<String> {
+        print_conv::interpret(self.tag()?, self.get_value()?)
+    }
+
     /// Takes out the parsed entry value of this entry.
     ///
     /// If you need to convert this `ExifIter` to an [`crate::Exif`], please
@@ -317,19 +968,35 @@ impl ParsedExifEntry {
         }
     }
 
-    fn make_ok(ifd: usize, tag: ExifTagCode, v: EntryValue) -> Self {
+    fn make_ok(ifd: usize, page: usize, group: IfdGroup, tag: ExifTagCode, v: EntryValue) -> Self {
         Self {
             ifd,
+            page,
+            group,
             tag,
             res: Some(Ok(v)),
+            unknown: None,
+            raw: RawEntry::default(),
+            custom: None,
         }
     }
 
-    fn make_err(ifd: usize, tag: ExifTagCode, e: ParseEntryError) -> Self {
+    fn make_err(
+        ifd: usize,
+        page: usize,
+        group: IfdGroup,
+        tag: ExifTagCode,
+        e: ParseEntryError,
+    ) -> Self {
         Self {
             ifd,
+            page,
+            group,
             tag,
             res: Some(Err(EntryError(e))),
+            unknown: None,
+            raw: RawEntry::default(),
+            custom: None,
         }
     }
 }
@@ -348,7 +1015,176 @@ impl Debug for ParsedExifEntry {
     }
 }
 
-const MAX_IFD_DEPTH: usize = 8;
+#[cfg(feature = "serde")]
+impl serde::Serialize for ParsedExifEntry {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let error = match &self.res {
+            Some(Err(e)) => Some(e.to_string()),
+            _ => None,
+        };
+        let mut s = serializer.serialize_struct("ParsedExifEntry", 7)?;
+        s.serialize_field("ifd", &self.ifd)?;
+        s.serialize_field("page", &self.page)?;
+        s.serialize_field("group", &self.group.to_string())?;
+        s.serialize_field("tag", &self.tag_code())?;
+        s.serialize_field("name", &self.name())?;
+        s.serialize_field("value", &self.get_value())?;
+        s.serialize_field("error", &error)?;
+        s.end()
+    }
+}
+
+/// The raw form of an IFD entry as stored in the file, see
+/// [`ParsedExifEntry::raw_entry`].
+///
+/// It's meant for forensic and debugging tools which need to inspect exactly
+/// what is in the file, not only the decoded [`EntryValue`].
+#[derive(Clone, Default)]
+pub struct RawEntry {
+    data_format: u16,
+    components_num: u32,
+    data: PartialVec,
+    // Offsets relative to the TIFF header
+    entry_offset: u32,
+    value_offset: u32,
+    tiff_offset: Option<u64>,
+}
+
+impl RawEntry {
+    /// Raw data format (type) code, e.g. 2 for ASCII, 7 for UNDEFINED.
+    pub fn data_format(&self) -> u16 {
+        self.data_format
+    }
+
+    /// Number of components (the count field of the entry).
+    pub fn components_num(&self) -> u32 {
+        self.components_num
+    }
+
+    /// Raw value bytes, in the byte order of the TIFF header.
+    ///
+    /// For invalid data formats, this is the 4-byte value/offset field; it's
+    /// empty if the value is located out of the Exif data.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Offset of the 12-byte IFD entry, relative to the TIFF header.
+    pub fn entry_offset(&self) -> u32 {
+        self.entry_offset
+    }
+
+    /// Offset of the value bytes, relative to the TIFF header. For values
+    /// which fit in 4 bytes, this is the offset of the value/offset field of
+    /// the entry itself.
+    pub fn value_offset(&self) -> u32 {
+        self.value_offset
+    }
+
+    /// Absolute offset of the value bytes in the media source (i.e. relative
+    /// to the start of the reader which has been parsed).
+    ///
+    /// Returns `None` if the position of the Exif data in the source is
+    /// unknown, e.g. when it has been reassembled from multiple JPEG APP1
+    /// segments, or when it's parsed from bytes directly.
+    pub fn file_offset(&self) -> Option<u64> {
+        self.tiff_offset?.checked_add(self.value_offset.into())
+    }
+}
+
+impl Debug for RawEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RawEntry")
+            .field("data_format", &self.data_format)
+            .field("components_num", &self.components_num)
+            .field("data len", &self.data.len())
+            .field("entry_offset", &self.entry_offset)
+            .field("value_offset", &self.value_offset)
+            .field("file_offset", &self.file_offset())
+            .finish()
+    }
+}
+
+/// An entry with an unrecognized tag, see [`ExifIter::unknown_tags`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownTag {
+    /// IFD index, 0: ifd0 (main image), 1: ifd1 (thumbnail)
+    pub ifd: usize,
+
+    /// Raw tag code
+    pub code: u16,
+
+    /// Raw data format code, e.g. 2 for ASCII, 7 for UNDEFINED
+    pub data_format: u16,
+
+    /// Number of components
+    pub components_num: u32,
+
+    /// Raw entry data, in the byte order of the TIFF header. For invalid
+    /// data formats, this is the 4-byte value/offset field.
+    pub data: Vec<u8>,
+}
+
+impl Display for UnknownTag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const MAX_DUMP: usize = 32;
+
+        write!(
+            f,
+            "ifd{} 0x{:04x} format={} count={} data=",
+            self.ifd, self.code, self.data_format, self.components_num
+        )?;
+        for b in self.data.iter().take(MAX_DUMP) {
+            write!(f, "{b:02x}")?;
+        }
+        if self.data.len() > MAX_DUMP {
+            write!(f, "...({} bytes)", self.data.len())?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) const MAX_SUB_IFDS: usize = 4;
+
+impl ExifIter {
+    /// Attaches the custom tag registered for the (unrecognized) tag of
+    /// `entry`, and decodes its raw bytes in the expected data format.
+    fn apply_custom_tag(&self, entry: &mut ParsedExifEntry) {
+        if entry.tag().is_some() {
+            return;
+        }
+        let code = entry.tag_code();
+        let group = entry.group;
+        let Some(custom) = self.custom_tags.iter().find(|x| x.matches(code, group)) else {
+            return;
+        };
+        if let Some(res) = custom.decode(&entry.raw, self.tiff_header.endian, &self.tz) {
+            entry.res = Some(res.map_err(EntryError));
+        }
+        entry.custom = Some(custom.clone());
+    }
+
+    /// Decodes the text of an UNDEFINED `UserComment` entry (see
+    /// [`ExifIter::set_text_encoding`]), or of a Windows XP* entry, e.g.
+    /// `XPTitle`.
+    fn decode_text(&self, entry: &mut ParsedExifEntry) {
+        let Some(tag) = entry.tag() else {
+            return;
+        };
+        let s = match &entry.res {
+            Some(Ok(EntryValue::Undefined(data))) if tag == ExifTag::UserComment => {
+                decode_user_comment(data, self.tiff_header.endian, self.text_encoding)
+            }
+            Some(Ok(EntryValue::U8Array(data) | EntryValue::Undefined(data))) if is_xp_tag(tag) => {
+                decode_xp_text(data)
+            }
+            _ => return,
+        };
+        entry.res = Some(Ok(EntryValue::Text(s)));
+    }
+}
 
 impl Iterator for ExifIter {
     type Item = ParsedExifEntry;
@@ -361,23 +1197,54 @@ impl Iterator for ExifIter {
                 return None;
             }
 
-            if self.ifds.len() > MAX_IFD_DEPTH {
-                self.ifds.clear();
+            if self.ifds.len() > self.limits.max_ifd_depth {
                 tracing::error!(
                     ifds_depth = self.ifds.len(),
-                    "ifd depth is too deep, just go back to ifd0"
+                    "ifd depth is too deep, ignore the deeper ones"
                 );
-                self.ifds.push(self.ifd0.clone_with_state());
+                self.ifds.truncate(self.limits.max_ifd_depth);
             }
 
             let mut ifd = self.ifds.pop()?;
-            let cur_ifd_idx = ifd.ifd_idx;
+            ifd.limit_entries(self.limits.max_ifd_entries);
+            let (cur_ifd_idx, page, group) = (ifd.ifd_idx, ifd.page, ifd.group());
+            let is_page = ifd.tag_code.is_none();
             match ifd.next() {
                 Some((tag_code, entry)) => {
+                    let unknown = ifd.unknown.take().map(Box::new);
+                    let mut raw = ifd.raw.take().unwrap_or_default();
+                    raw.tiff_offset = self.tiff_offset;
                     // tracing::debug!(ifd = ifd.ifd_idx, ?tag_code, ?entry, "next tag entry");
 
                     match entry {
+                        IfdEntry::IfdNew(new_ifd) if self.scoped => {
+                            // Scoped iterators don't enter other IFDs, but
+                            // sub-IFD pointers are still returned as entries
+                            if new_ifd.ifd_idx == ifd.ifd_idx {
+                                self.ifds.push(ifd);
+                                let mut res = ParsedExifEntry::make_ok(
+                                    new_ifd.ifd_idx,
+                                    page,
+                                    group,
+                                    tag_code.unwrap(),
+                                    EntryValue::U32(new_ifd.offset),
+                                );
+                                res.raw = raw;
+                                return Some(res);
+                            }
+                        }
                         IfdEntry::IfdNew(new_ifd) => {
+                            if !self.visited.insert(new_ifd.offset) {
+                                tracing::warn!(
+                                    offset = new_ifd.offset,
+                                    "IFD has been visited, ignore it to avoid loops"
+                                );
+                                if new_ifd.ifd_idx == ifd.ifd_idx {
+                                    self.ifds.push(ifd);
+                                }
+                                continue;
+                            }
+
                             let is_subifd = if new_ifd.ifd_idx == ifd.ifd_idx {
                                 // Push the current ifd before enter sub-ifd.
                                 self.ifds.push(ifd);
@@ -392,29 +1259,88 @@ impl Iterator for ExifIter {
                                 false
                             };
 
+                            let mut new_ifd = new_ifd;
+                            if !is_subifd {
+                                new_ifd.ifd_idx = self.ifd_count;
+                                self.ifd_count += 1;
+                                if is_page {
+                                    new_ifd.page = page + 1;
+                                }
+                            }
                             let (ifd_idx, offset) = (new_ifd.ifd_idx, new_ifd.offset);
                             self.ifds.push(new_ifd);
 
                             if is_subifd {
                                 // Return sub-ifd as an entry
-                                return Some(ParsedExifEntry::make_ok(
+                                let mut res = ParsedExifEntry::make_ok(
                                     ifd_idx,
+                                    page,
+                                    group,
                                     tag_code.unwrap(),
                                     EntryValue::U32(offset),
-                                ));
+                                );
+                                res.raw = raw;
+                                return Some(res);
+                            }
+                        }
+                        IfdEntry::SubIfds(v, sub_ifds) => {
+                            let mut res = ParsedExifEntry::make_ok(
+                                ifd.ifd_idx,
+                                page,
+                                group,
+                                tag_code.unwrap(),
+                                v,
+                            );
+                            res.raw = raw;
+                            self.ifds.push(ifd);
+                            if self.scoped {
+                                return Some(res);
+                            }
+
+                            // Unlike Exif/GPS sub-IFDs, each of SubIFDs (e.g.
+                            // the raw images in DNG files) describes a
+                            // separate image, so it gets a new IFD index.
+                            let mut sub_ifds = sub_ifds
+                                .into_iter()
+                                .filter(|x| self.visited.insert(x.offset))
+                                .take(MAX_SUB_IFDS)
+                                .collect::<Vec<_>>();
+                            for sub_ifd in sub_ifds.iter_mut() {
+                                sub_ifd.ifd_idx = self.ifd_count;
+                                self.ifd_count += 1;
+                                tracing::debug!(?sub_ifd, "got new SubIFDs IFD");
                             }
+                            self.ifds.extend(sub_ifds.into_iter().rev());
+                            return Some(res);
                         }
                         IfdEntry::Entry(v) => {
-                            let res =
-                                Some(ParsedExifEntry::make_ok(ifd.ifd_idx, tag_code.unwrap(), v));
+                            let mut res = ParsedExifEntry::make_ok(
+                                ifd.ifd_idx,
+                                page,
+                                group,
+                                tag_code.unwrap(),
+                                v,
+                            );
+                            res.unknown = unknown;
+                            res.raw = raw;
+                            self.apply_custom_tag(&mut res);
+                            self.decode_text(&mut res);
                             self.ifds.push(ifd);
-                            return res;
+                            return Some(res);
                         }
                         IfdEntry::Err(e) => {
                             tracing::warn!(?tag_code, ?e, "parse ifd entry error");
-                            let res =
-                                Some(ParsedExifEntry::make_err(ifd.ifd_idx, tag_code.unwrap(), e));
-                            return res;
+                            let mut res = ParsedExifEntry::make_err(
+                                ifd.ifd_idx,
+                                page,
+                                group,
+                                tag_code.unwrap(),
+                                e,
+                            );
+                            res.unknown = unknown;
+                            res.raw = raw;
+                            self.apply_custom_tag(&mut res);
+                            return Some(res);
                         }
                     }
                 }
@@ -442,6 +1368,20 @@ pub(crate) struct IfdIter {
     // Iterating status
     index: u16,
     pos: usize,
+
+    // Record the raw data of unrecognized entries, see
+    // `ExifIter::unknown_tags`
+    record_unknown: bool,
+    unknown: Option<UnknownTag>,
+
+    // Raw form of the last entry, see `ParsedExifEntry::raw_entry`
+    raw: Option<RawEntry>,
+
+    // ifd0 of Panasonic RW2 files, see `rw2::map_rw2_tag`
+    rw2: bool,
+
+    // See `ParsedExifEntry::page_index`
+    page: usize,
 }
 
 impl Debug for IfdIter {
@@ -516,6 +1456,11 @@ impl IfdIter {
             // Skip the first two bytes, which is the entry num
             pos: 2,
             index: 0,
+            record_unknown: false,
+            unknown: None,
+            raw: None,
+            rw2: false,
+            page: 0,
         })
     }
 
@@ -529,9 +1474,12 @@ impl IfdIter {
         ))(entry_data)
         .ok()?;
 
-        if tag == 0 {
+        // Tag 0 is `GPSVersionID` in the GPS IFD, it means the end of the
+        // entries elsewhere
+        if tag == 0 && !self.is_gps_ifd() {
             return None;
         }
+        let tag = self.tag_code_of(tag).code();
 
         let df: DataFormat = match data_format.try_into() {
             Ok(df) => df,
@@ -545,8 +1493,111 @@ impl IfdIter {
         Some((tag, res))
     }
 
-    fn get_data_pos(&self, value_or_offset: u32) -> u32 {
-        value_or_offset.saturating_sub(self.offset)
+    /// Returns the raw form of the entry at `pos` (of the IFD data).
+    fn raw_entry(&self, pos: usize) -> Option<(u16, RawEntry)> {
+        let entry_data = self.input.slice_checked(pos..pos + IFD_ENTRY_SIZE)?;
+        let endian = self.endian;
+        let (_, (code, data_format, components_num, value_or_offset)) = tuple((
+            complete::u16::<_, nom::error::Error<_>>(endian),
+            complete::u16(endian),
+            complete::u32(endian),
+            complete::u32(endian),
+        ))(entry_data)
+        .ok()?;
+
+        let entry_offset = self.offset.checked_add(pos as u32)?;
+        let inline = |size: usize| {
+            let data = self.input.partial(&entry_data[8..8 + size]); // Safe-slice
+            (data, entry_offset + 8)
+        };
+        let (data, value_offset) = match DataFormat::try_from(data_format) {
+            Ok(df) => {
+                let size = (components_num as usize).saturating_mul(df.component_size());
+                if size <= 4 {
+                    inline(size)
+                } else {
+                    let data = match self.data_range(value_or_offset, size) {
+                        Some(range) => PartialVec::new(self.input.data.clone(), range),
+                        None => PartialVec::default(),
+                    };
+                    (data, value_or_offset)
+                }
+            }
+            Err(_) => inline(4),
+        };
+
+        let raw = RawEntry {
+            data_format,
+            components_num,
+            data,
+            entry_offset,
+            value_offset,
+            tiff_offset: None,
+        };
+        Some((code, raw))
+    }
+
+    fn unknown_entry(&self, code: u16, raw: &RawEntry) -> Option<UnknownTag> {
+        if code == 0 || self.tag_code_of(code).tag().is_some() {
+            return None;
+        }
+
+        Some(UnknownTag {
+            ifd: self.ifd_idx,
+            code,
+            data_format: raw.data_format,
+            components_num: raw.components_num,
+            data: raw.data().to_vec(),
+        })
+    }
+
+    /// Returns the group of the entries of this IFD.
+    fn group(&self) -> IfdGroup {
+        match self.tag_code.map(|x| x.code()) {
+            None => IfdGroup::page(self.page),
+            Some(code) if code == ExifTag::ExifOffset.code() => IfdGroup::ExifIfd,
+            Some(code) if code == ExifTag::GPSInfo.code() => IfdGroup::Gps,
+            Some(code) if code == ExifTag::InteropOffset.code() => IfdGroup::Interop,
+            Some(code) if code == ExifTag::MakerNote.code() => IfdGroup::MakerNote,
+            Some(_) => IfdGroup::SubIfd,
+        }
+    }
+
+    fn is_gps_ifd(&self) -> bool {
+        self.tag_code
+            .is_some_and(|x| x.code() == ExifTag::GPSInfo.code())
+    }
+
+    fn tag_code_of(&self, code: u16) -> ExifTagCode {
+        if matches!(self.group(), IfdGroup::Interop | IfdGroup::MakerNote) {
+            // Interop & vendor specific tags are not defined in `ExifTag`,
+            // the codes overlap the standard ones
+            ExifTagCode::Code(code)
+        } else if self.rw2 {
+            map_rw2_tag(code)
+        } else {
+            code.into()
+        }
+    }
+
+    /// Returns the range (in the underlying data) of `size` bytes at
+    /// `offset` (relative to the TIFF header), which may be located before
+    /// this IFD, e.g. when the IFDs have been rewritten at the end of the
+    /// data.
+    fn data_range(&self, offset: u32, size: usize) -> Option<Range<usize>> {
+        let start = if offset >= self.offset {
+            self.input
+                .range
+                .start
+                .checked_add((offset - self.offset) as usize)?
+        } else {
+            self.input
+                .range
+                .start
+                .checked_sub((self.offset - offset) as usize)?
+        };
+        let end = start.checked_add(size)?;
+        (end <= self.input.range.end).then_some(start..end)
     }
 
     fn parse_entry(
@@ -561,25 +1612,23 @@ impl IfdIter {
         let component_size = data_format.component_size();
 
         // get entry data
-        let size = components_num as usize * component_size;
+        let size = (components_num as usize).saturating_mul(component_size);
         let data = if size <= 4 {
             &entry_data[8..8 + size] // Safe-slice
         } else {
-            let start = self.get_data_pos(value_or_offset) as usize;
-            let end = start + size;
-            let Some(data) = self.input.slice_checked(start..end) else {
+            let Some(range) = self.data_range(value_or_offset, size) else {
                 tracing::warn!(
-                    "entry data overflow, self.offset: {:08x} tag: {:04x} start: {:08x} end: {:08x} ifd data len {:08x}",
+                    "entry data overflow, self.offset: {:08x} tag: {:04x} offset: {:08x} size: {:08x} ifd data len {:08x}",
                     self.offset,
                     tag,
-                    start,
-                    end,
+                    value_or_offset,
+                    size,
                     self.input.len(),
                 );
                 return (tag, IfdEntry::Err(ParseEntryError::EntrySizeTooBig));
             };
 
-            data
+            &self.input.data[range] // Safe-slice
         };
 
         if SUBIFD_TAGS.contains(&tag) {
@@ -596,6 +1645,23 @@ impl IfdIter {
             components_num,
         };
         match EntryValue::parse(&entry, &self.tz) {
+            Ok(v) if tag == ExifTag::SubIFDs.code() => {
+                let offsets = match &v {
+                    EntryValue::U32(x) => vec![*x],
+                    EntryValue::U32Array(x) => x.clone(),
+                    _ => Vec::new(),
+                };
+                let sub_ifds = offsets
+                    .into_iter()
+                    .filter_map(|offset| {
+                        match self.new_ifd_iter(self.ifd_idx, offset, Some(tag))? {
+                            IfdEntry::IfdNew(iter) => Some(iter),
+                            _ => None,
+                        }
+                    })
+                    .collect();
+                (tag, IfdEntry::SubIfds(v, sub_ifds))
+            }
             Ok(v) => (tag, IfdEntry::Entry(v)),
             Err(e) => (tag, IfdEntry::Err(e)),
         }
@@ -607,16 +1673,20 @@ impl IfdIter {
         value_or_offset: u32,
         tag: Option<u16>,
     ) -> Option<IfdEntry> {
-        let pos = self.get_data_pos(value_or_offset) as usize;
-        if pos < self.input.len() {
+        let range = self.data_range(value_or_offset, 0)?;
+        if range.start < self.input.range.end {
             match IfdIter::try_new(
                 ifd_idx,
-                self.input.partial(&self.input[pos..]),
+                AssociatedInput::new(self.input.data.clone(), range.start..self.input.range.end),
                 value_or_offset,
                 self.endian,
                 self.tz.clone(),
             ) {
-                Ok(iter) => return Some(IfdEntry::IfdNew(iter.tag_code_maybe(tag))),
+                Ok(mut iter) => {
+                    iter.record_unknown = self.record_unknown;
+                    iter.page = self.page;
+                    return Some(IfdEntry::IfdNew(iter.tag_code_maybe(tag)));
+                }
                 Err(e) => {
                     tracing::warn!(?tag, ?e, "Create next/sub IFD failed");
                 }
@@ -633,25 +1703,71 @@ impl IfdIter {
         None
     }
 
+    /// Skips the remaining entries if `max` entries have been iterated, the
+    /// next IFD in the chain is still followed.
+    fn limit_entries(&mut self, max: usize) {
+        if self.index as usize >= max && self.index < self.entry_num {
+            tracing::warn!(
+                ifd = self.ifd_idx,
+                entry_num = self.entry_num,
+                "too many IFD entries, ignore the remaining ones"
+            );
+            self.index = self.entry_num;
+            self.pos = 2 + self.entry_num as usize * IFD_ENTRY_SIZE;
+        }
+    }
+
+    /// Returns the next IFD in the chain (the next page).
+    fn next_ifd(&self) -> Option<IfdIter> {
+        let pos = 2 + self.entry_num as usize * IFD_ENTRY_SIZE;
+        let (_, offset) =
+            complete::u32::<_, nom::error::Error<_>>(self.endian)(self.input.get(pos..)?).ok()?;
+        if offset == 0 {
+            return None;
+        }
+        match self.new_ifd_iter(self.ifd_idx + 1, offset, None)? {
+            IfdEntry::IfdNew(iter) => Some(iter),
+            _ => None,
+        }
+    }
+
     pub fn find_exif_iter(&self) -> Option<IfdIter> {
         let endian = self.endian;
         // find ExifOffset
         for i in 0..self.entry_num {
             let pos = self.pos + i as usize * IFD_ENTRY_SIZE;
             let (_, tag) =
-                complete::u16::<_, nom::error::Error<_>>(endian)(&self.input[pos..]).ok()?;
+                complete::u16::<_, nom::error::Error<_>>(endian)(self.input.get(pos..)?).ok()?;
             if tag == ExifTag::ExifOffset.code() {
                 let entry_data = self.input.slice_checked(pos..pos + IFD_ENTRY_SIZE)?;
                 let (_, entry) = self.parse_tag_entry(entry_data)?;
                 match entry {
                     IfdEntry::IfdNew(iter) => return Some(iter),
-                    IfdEntry::Entry(_) | IfdEntry::Err(_) => return None,
+                    IfdEntry::SubIfds(..) | IfdEntry::Entry(_) | IfdEntry::Err(_) => return None,
                 }
             }
         }
         None
     }
 
+    /// Returns the value offset (relative to the TIFF header) of the entry
+    /// `tag`, which is meaningful only if the value doesn't fit in the entry.
+    pub fn find_entry_offset(&self, tag: u16) -> Option<u32> {
+        let endian = self.endian;
+        (0..self.entry_num as usize).find_map(|i| {
+            let pos = 2 + i * IFD_ENTRY_SIZE;
+            let entry = self.input.slice_checked(pos..pos + IFD_ENTRY_SIZE)?;
+            let (_, (code, _, _, offset)) = tuple((
+                complete::u16::<_, nom::error::Error<_>>(endian),
+                complete::u16(endian),
+                complete::u32(endian),
+                complete::u32(endian),
+            ))(entry)
+            .ok()?;
+            (code == tag).then_some(offset)
+        })
+    }
+
     pub fn find_tz_offset(&self) -> Option<String> {
         let iter = self.find_exif_iter()?;
         let mut offset = None;
@@ -697,16 +1813,18 @@ impl IfdIter {
                     }
                 }
                 ExifTag::GPSLatitude => {
-                    if let Some(v) = entry.as_urational_array() {
+                    // Ignore malformed values which have less than 3 components
+                    if let Some(v) = entry.as_urational_array().filter(|v| v.len() >= 3) {
                         gps.latitude = v.iter().collect();
-                    } else if let Some(v) = entry.as_irational_array() {
+                    } else if let Some(v) = entry.as_irational_array().filter(|v| v.len() >= 3) {
                         gps.latitude = v.iter().collect();
                     }
                 }
                 ExifTag::GPSLongitude => {
-                    if let Some(v) = entry.as_urational_array() {
+                    // Ignore malformed values which have less than 3 components
+                    if let Some(v) = entry.as_urational_array().filter(|v| v.len() >= 3) {
                         gps.longitude = v.iter().collect();
-                    } else if let Some(v) = entry.as_irational_array() {
+                    } else if let Some(v) = entry.as_irational_array().filter(|v| v.len() >= 3) {
                         gps.longitude = v.iter().collect();
                     }
                 }
@@ -729,6 +1847,21 @@ impl IfdIter {
                         gps.speed = Some((*v).into());
                     }
                 }
+                ExifTag::GPSDateStamp => {
+                    gps.date_stamp = entry.as_str().map(|x| x.trim().to_owned());
+                }
+                ExifTag::GPSTimeStamp => {
+                    if let Some(v) = entry.as_urational_array().filter(|v| v.len() >= 3) {
+                        gps.time_stamp = Some([v[0], v[1], v[2]]);
+                    } else if let Some(v) = entry.as_irational_array().filter(|v| v.len() >= 3) {
+                        gps.time_stamp = Some([v[0].into(), v[1].into(), v[2].into()]);
+                    }
+                }
+                ExifTag::GPSImgDirectionRef => gps.img_direction_ref = entry.as_char(),
+                ExifTag::GPSImgDirection => gps.img_direction = entry.as_any_urational(),
+                ExifTag::GPSDestBearingRef => gps.dest_bearing_ref = entry.as_char(),
+                ExifTag::GPSDestBearing => gps.dest_bearing = entry.as_any_urational(),
+                ExifTag::GPSDOP => gps.dop = entry.as_any_urational(),
                 _ => (),
             }
         }
@@ -740,18 +1873,13 @@ impl IfdIter {
             None
         }
     }
-
-    fn clone_with_state(&self) -> IfdIter {
-        let mut it = self.clone();
-        it.index = self.index;
-        it.pos = self.pos;
-        it
-    }
 }
 
 #[derive(Debug)]
 pub(crate) enum IfdEntry {
     IfdNew(IfdIter), // ifd index
+    // Value of `SubIFDs` tag, and the IFDs it points to
+    SubIfds(EntryValue, Vec<IfdIter>),
     Entry(EntryValue),
     Err(ParseEntryError),
 }
@@ -805,6 +1933,13 @@ impl IfdEntry {
         }
     }
 
+    /// Returns the value of a RATIONAL or SRATIONAL entry as a `URational`.
+    fn as_any_urational(&self) -> Option<URational> {
+        self.as_urational()
+            .copied()
+            .or_else(|| self.as_irational().map(|v| (*v).into()))
+    }
+
     fn as_str(&self) -> Option<&str> {
         if let IfdEntry::Entry(e) = self {
             e.as_str()
@@ -819,8 +1954,26 @@ pub(crate) const SUBIFD_TAGS: &[u16] = &[ExifTag::ExifOffset.code(), ExifTag::GP
 impl Iterator for IfdIter {
     type Item = (Option<ExifTagCode>, IfdEntry);
 
-    #[tracing::instrument(skip(self))]
     fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (code, entry) = self.next_entry()?;
+            // Panasonic specific tags in RW2 ifd0 overlap GPS tag codes, skip
+            // them so they won't be mixed up with GPS tags. They are still
+            // reported by `ExifIter::unknown_tags`.
+            if self.rw2
+                && !self.record_unknown
+                && code.is_some_and(|x| x.tag().is_none() && is_panasonic_specific(x.code()))
+            {
+                continue;
+            }
+            return Some((code, entry));
+        }
+    }
+}
+
+impl IfdIter {
+    #[tracing::instrument(skip(self))]
+    fn next_entry(&mut self) -> Option<(Option<ExifTagCode>, IfdEntry)> {
         // tracing::debug!(
         //     ifd = self.ifd_idx,
         //     index = self.index,
@@ -847,7 +2000,8 @@ impl Iterator for IfdIter {
 
             // next IFD offset
             let (_, offset) =
-                complete::u32::<_, nom::error::Error<_>>(endian)(&self.input[self.pos..]).ok()?;
+                complete::u32::<_, nom::error::Error<_>>(endian)(self.input.get(self.pos..)?)
+                    .ok()?;
 
             if offset == 0 {
                 // IFD parsing completed
@@ -863,18 +2017,25 @@ impl Iterator for IfdIter {
         let entry_data = self
             .input
             .slice_checked(self.pos..self.pos + IFD_ENTRY_SIZE)?;
+        let raw = self.raw_entry(self.pos);
         self.index += 1;
         self.pos += IFD_ENTRY_SIZE;
 
+        if self.record_unknown {
+            self.unknown = raw
+                .as_ref()
+                .and_then(|(code, raw)| self.unknown_entry(*code, raw));
+        }
+        self.raw = raw.map(|(_, raw)| raw);
         let (tag, res) = self.parse_tag_entry(entry_data)?;
 
-        Some((Some(tag.into()), res)) // Safe-slice
+        Some((Some(self.tag_code_of(tag)), res)) // Safe-slice
     }
 }
 
 #[cfg(test)]
 mod tests {
-
+    use super::*;
     use crate::exif::extract_exif_with_mime;
     use crate::exif::input_into_iter;
     use crate::file::MimeImage;
@@ -898,4 +2059,541 @@ mod tests {
         };
         assert_eq!(iter.tz, expect);
     }
+
+    #[test_case("exif.jpg")]
+    #[test_case("exif.heic")]
+    fn into_owned_static(path: &str) {
+        let mut parser = crate::MediaParser::new();
+        let ms = crate::MediaSource::file(crate::testkit::open_sample(path).unwrap()).unwrap();
+        let iter: ExifIter = parser.parse(ms).unwrap();
+
+        let owned = iter.clone().into_owned_static();
+        // Only the Exif data is kept, and nothing references the parsing
+        // buffer any more
+        assert!(owned.input.data.len() < iter.input.data.len());
+        assert_eq!(owned.input.data.len(), iter.input.len());
+        assert_eq!(owned.input.data.as_ptr(), owned.ifd0.input.data.as_ptr());
+        assert_eq!(&owned.ifd0.input[..], &iter.ifd0.input[..]);
+    }
+
+    #[test_case("exif.jpg")]
+    #[test_case("exif.heic")]
+    #[test_case("tif.tif")]
+    fn in_memory(path: &str) {
+        let mut parser = crate::MediaParser::new();
+        let data = bytes::Bytes::from(read_sample(path).unwrap());
+        // The caller keeps holding the data, which is shared rather than
+        // copied
+        let ms = crate::MediaSource::from_bytes(data.clone()).unwrap();
+        let iter: ExifIter = parser.parse(ms).unwrap();
+        assert_eq!(iter.input.data.as_ptr(), data.as_ptr());
+        assert_eq!(iter.ifd0.input.data.as_ptr(), data.as_ptr());
+        assert!(data.as_ptr_range().contains(&iter.input.as_ptr()));
+
+        let ms = crate::MediaSource::from_vec(data.to_vec()).unwrap();
+        let ptr = ms.data.as_ref().unwrap().as_ptr();
+        let iter: ExifIter = parser.parse(ms).unwrap();
+        assert_eq!(iter.input.data.as_ptr(), ptr);
+
+        let ms = crate::MediaSource::file(crate::testkit::open_sample(path).unwrap()).unwrap();
+        let expect: ExifIter = parser.parse(ms).unwrap();
+        assert_eq!(
+            iter.map(|mut x| (x.tag_code(), x.take_result().ok()))
+                .collect::<Vec<_>>(),
+            expect
+                .map(|mut x| (x.tag_code(), x.take_result().ok()))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    fn tiff_with_entry(entry: [u8; 12], next_ifd: u32) -> Vec<u8> {
+        let mut data = b"II*\0\x08\0\0\0".to_vec();
+        // IFD0: Make & another entry
+        data.extend_from_slice(&[0x02, 0x00]);
+        data.extend_from_slice(&[0x0f, 0x01, 0x02, 0x00, 0x04, 0x00, 0x00, 0x00]);
+        data.extend_from_slice(b"abc\0");
+        data.extend_from_slice(&entry);
+        data.extend_from_slice(&next_ifd.to_le_bytes());
+        data
+    }
+
+    const EXIF_OFFSET_TO_IFD0: [u8; 12] = [0x69, 0x87, 0x04, 0x00, 0x01, 0, 0, 0, 0x08, 0, 0, 0];
+    const GPS_OFFSET_TO_IFD0: [u8; 12] = [0x25, 0x88, 0x04, 0x00, 0x01, 0, 0, 0, 0x08, 0, 0, 0];
+
+    #[test_case(tiff_with_entry(EXIF_OFFSET_TO_IFD0, 0))]
+    #[test_case(tiff_with_entry(GPS_OFFSET_TO_IFD0, 0))]
+    #[test_case(tiff_with_entry(EXIF_OFFSET_TO_IFD0, 8))]
+    #[test_case(tiff_with_entry(GPS_OFFSET_TO_IFD0, 0xffff_fff0))]
+    fn exif_iter_reference_loop(data: Vec<u8>) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let iter = input_into_iter(data, None).unwrap();
+        let _ = iter.parse_gps_info();
+        let entries = iter.take(100).collect::<Vec<_>>();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].tag(), Some(crate::ExifTag::Make));
+        assert_eq!(entries[0].get_value().and_then(|x| x.as_str()), Some("abc"));
+    }
+
+    fn ifd(entries: &[(u16, u16, u32, u32)], next_ifd: u32) -> Vec<u8> {
+        let mut data = (entries.len() as u16).to_le_bytes().to_vec();
+        for (tag, format, num, value) in entries {
+            data.extend_from_slice(&tag.to_le_bytes());
+            data.extend_from_slice(&format.to_le_bytes());
+            data.extend_from_slice(&num.to_le_bytes());
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(&next_ifd.to_le_bytes());
+        data
+    }
+
+    /// A DNG-like TIFF: a preview in ifd0, the raw image and another preview
+    /// in SubIFDs, and a thumbnail in ifd1.
+    fn dng() -> Vec<u8> {
+        let mut data = b"II*\0\x08\0\0\0".to_vec();
+        data.extend(ifd(
+            &[
+                (0x00fe, 4, 1, 1),
+                (0x0100, 3, 1, 256),
+                (0x0101, 3, 1, 171),
+                (0x010f, 2, 4, u32::from_le_bytes(*b"abc\0")),
+                // IFD data format, points to the offsets at 98
+                (0x014a, 13, 2, 98),
+                (0xc612, 1, 4, u32::from_le_bytes([1, 4, 0, 0])),
+                (0xc614, 2, 4, u32::from_le_bytes(*b"xyz\0")),
+            ],
+            178,
+        ));
+        data.extend([106_u32, 148].iter().flat_map(|x| x.to_le_bytes()));
+        data.extend(ifd(
+            &[
+                (0x00fe, 4, 1, 0),
+                (0x0100, 4, 1, 6000),
+                (0x0101, 4, 1, 4000),
+            ],
+            0,
+        ));
+        data.extend(ifd(&[(0x00fe, 4, 1, 1), (0x0100, 4, 1, 1024)], 0));
+        data.extend(ifd(&[(0x00fe, 4, 1, 1)], 0));
+        data
+    }
+
+    #[test]
+    fn dng_sub_ifds() {
+        use crate::{Exif, MediaParser, MediaSource};
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let mut parser = MediaParser::new();
+        let ms = MediaSource::seekable(std::io::Cursor::new(dng())).unwrap();
+        let iter: ExifIter = parser.parse(ms).unwrap();
+        let entries = iter
+            .clone()
+            .map(|x| (x.ifd_index(), x.tag_code()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            entries,
+            [
+                (0, 0x00fe),
+                (0, 0x0100),
+                (0, 0x0101),
+                (0, 0x010f),
+                (0, 0x014a),
+                (1, 0x00fe),
+                (1, 0x0100),
+                (1, 0x0101),
+                (2, 0x00fe),
+                (2, 0x0100),
+                (0, 0xc612),
+                (0, 0xc614),
+                (3, 0x00fe),
+            ]
+        );
+
+        let exif: Exif = iter.into();
+        assert_eq!(
+            exif.get(ExifTag::SubIFDs),
+            Some(&EntryValue::U32Array(vec![106, 148]))
+        );
+        assert_eq!(
+            exif.get(ExifTag::DNGVersion),
+            Some(&EntryValue::U8Array(vec![1, 4, 0, 0]))
+        );
+        assert_eq!(
+            exif.get(ExifTag::UniqueCameraModel)
+                .and_then(|x| x.as_str()),
+            Some("xyz")
+        );
+        assert_eq!(exif.main_image_ifd(), 1);
+        assert_eq!(exif.display_dimensions(), Some((6000, 4000)));
+    }
+
+    #[test]
+    fn dng_kind() {
+        use crate::{DngKind, Exif, MediaParser, MediaSource};
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let parse = |data: Vec<u8>| -> Exif {
+            let mut parser = MediaParser::new();
+            let ms = MediaSource::seekable(std::io::Cursor::new(data)).unwrap();
+            let iter: ExifIter = parser.parse(ms).unwrap();
+            iter.into()
+        };
+        let entry = |tag: u16, format: u16, num: u32, value: u32| {
+            ifd(&[(tag, format, num, value)], 0)[2..14].to_vec()
+        };
+
+        assert_eq!(parse(dng()).dng_kind(), Some(DngKind::Standard));
+
+        // A semantic mask in the 2nd SubIFD, instead of its ImageWidth
+        let mut data = dng();
+        data.splice(162..174, entry(0xcd2e, 2, 4, u32::from_le_bytes(*b"Sky\0")));
+        assert_eq!(parse(data).dng_kind(), Some(DngKind::AppleProRaw));
+
+        // An Apple ProfileName in ifd0, instead of its UniqueCameraModel
+        let mut data = dng();
+        let profile = b"Apple ProRAW\0";
+        data.splice(
+            82..94,
+            entry(0xc6f8, 2, profile.len() as u32, data.len() as u32),
+        );
+        data.extend(profile);
+        assert_eq!(parse(data).dng_kind(), Some(DngKind::AppleProRaw));
+
+        let data = crate::testkit::read_sample("tif.tif").unwrap();
+        assert_eq!(parse(data).dng_kind(), None);
+    }
+
+    #[test]
+    fn multi_page_tiff() {
+        use crate::{Exif, MediaParser, MediaSource};
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let mut data = b"II*\0\x08\0\0\0".to_vec();
+        data.extend(ifd(&[(0x0100, 3, 1, 640), (0x0101, 3, 1, 480)], 38));
+        data.extend(ifd(&[(0x0100, 3, 1, 320), (0x0101, 3, 1, 240)], 68));
+        data.extend(ifd(&[(0x0100, 3, 1, 160)], 0));
+
+        let mut parser = MediaParser::new();
+        let ms = MediaSource::seekable(std::io::Cursor::new(data)).unwrap();
+        let iter: ExifIter = parser.parse(ms).unwrap();
+        assert_eq!(iter.page_count(), 3);
+        let entries = iter
+            .clone()
+            .map(|x| (x.page_index(), x.ifd_index(), x.tag_code()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            entries,
+            [
+                (0, 0, 0x0100),
+                (0, 0, 0x0101),
+                (1, 1, 0x0100),
+                (1, 1, 0x0101),
+                (2, 2, 0x0100),
+            ]
+        );
+
+        let exif: Exif = iter.into();
+        assert_eq!(exif.page_count(), 3);
+        assert_eq!(
+            exif.get_by_page(1, ExifTag::ImageWidth),
+            Some(&EntryValue::U16(320))
+        );
+        assert_eq!(
+            exif.get_by_page(2, ExifTag::ImageWidth),
+            Some(&EntryValue::U16(160))
+        );
+        assert_eq!(exif.get_by_page(2, ExifTag::ImageHeight), None);
+        assert_eq!(exif.get_by_page(3, ExifTag::ImageWidth), None);
+    }
+
+    #[cfg(feature = "raw_formats")]
+    #[test]
+    fn rw2() {
+        use crate::{Exif, MediaParser, MediaSource};
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let mut data = b"IIU\0\x18\0\0\0".to_vec();
+        data.resize(0x18, 0);
+        data.extend(ifd(
+            &[
+                // PanasonicRawVersion, SensorWidth & ISO
+                (0x0001, 7, 4, u32::from_le_bytes(*b"0310")),
+                (0x0002, 3, 1, 5200),
+                (0x0017, 3, 1, 200),
+                (0x010f, 2, 4, u32::from_le_bytes(*b"abc\0")),
+                (0x0110, 2, 4, u32::from_le_bytes(*b"xyz\0")),
+                (0x8769, 4, 1, 102),
+            ],
+            0,
+        ));
+        data.extend(ifd(&[(0x9003, 2, 20, 120)], 0));
+        data.extend_from_slice(b"2024:01:02 03:04:05\0");
+
+        let mut parser = MediaParser::new();
+        let ms = MediaSource::seekable(std::io::Cursor::new(data)).unwrap();
+        let iter: ExifIter = parser.parse(ms).unwrap();
+        assert_eq!(
+            iter.unknown_tags()
+                .iter()
+                .map(|x| (x.ifd, x.code))
+                .collect::<Vec<_>>(),
+            [(0, 0x0001), (0, 0x0002)]
+        );
+
+        let exif: Exif = iter.into();
+        assert_eq!(exif.get(ExifTag::ISOSpeedRatings), Some(&200_u16.into()));
+        assert_eq!(
+            exif.get(ExifTag::Make).and_then(|x| x.as_str()),
+            Some("abc")
+        );
+        assert_eq!(
+            exif.get(ExifTag::Model).and_then(|x| x.as_str()),
+            Some("xyz")
+        );
+        assert_eq!(
+            exif.get(ExifTag::DateTimeOriginal)
+                .and_then(|x| x.as_time())
+                .map(|x| x.naive_local().to_string()),
+            Some("2024-01-02 03:04:05".into())
+        );
+        assert_eq!(exif.get(ExifTag::GPSLatitudeRef), None);
+        assert_eq!(exif.get(ExifTag::GPSLatitude), None);
+    }
+
+    #[test]
+    fn unknown_tags() {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let data = tiff_with_entry([0xcd, 0xab, 0x07, 0x00, 0x03, 0, 0, 0, 1, 2, 3, 0], 0);
+        let mut iter = input_into_iter(data, None).unwrap();
+        iter.next().unwrap();
+
+        let tags = iter.unknown_tags();
+        assert_eq!(
+            tags,
+            vec![UnknownTag {
+                ifd: 0,
+                code: 0xabcd,
+                data_format: 7,
+                components_num: 3,
+                data: vec![1, 2, 3],
+            }]
+        );
+        assert_eq!(
+            tags[0].to_string(),
+            "ifd0 0xabcd format=7 count=3 data=010203"
+        );
+        // The iterator's state is not affected
+        assert_eq!(iter.next().unwrap().tag_code(), 0xabcd);
+
+        // Invalid data format
+        let data = tiff_with_entry([0xcd, 0xab, 0x99, 0x00, 0x01, 0, 0, 0, 1, 2, 3, 4], 0);
+        let tags = input_into_iter(data, None).unwrap().unknown_tags();
+        assert_eq!(tags[0].data_format, 0x99);
+        assert_eq!(tags[0].data, vec![1, 2, 3, 4]);
+
+        // Recognized tags are not reported
+        let buf = read_sample("exif.jpg").unwrap();
+        let (data, _) = extract_exif_with_mime(MimeImage::Jpeg, &buf, None).unwrap();
+        let range = data.and_then(|x| buf.subslice_range(x)).unwrap();
+        let iter = input_into_iter((buf, range), None).unwrap();
+        let tags = iter.unknown_tags();
+        let expect = iter
+            .filter(|x| x.tag().is_none())
+            .map(|x| (x.ifd_index(), x.tag_code()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tags.iter().map(|x| (x.ifd, x.code)).collect::<Vec<_>>(),
+            expect
+        );
+    }
+
+    #[test_case("exif.jpg", true)]
+    #[test_case("exif.jpg", false)]
+    #[test_case("exif.heic", true)]
+    #[test_case("exif.heic", false)]
+    #[test_case("tif.tif", true)]
+    fn raw_entries(path: &str, seekable: bool) {
+        use crate::{MediaParser, MediaSource};
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let buf = read_sample(path).unwrap();
+        let reader = std::io::Cursor::new(buf.clone());
+        let mut parser = MediaParser::new();
+        let iter: ExifIter = if seekable {
+            parser.parse(MediaSource::seekable(reader).unwrap())
+        } else {
+            parser.parse(MediaSource::unseekable(reader).unwrap())
+        }
+        .unwrap();
+
+        let mut n = 0;
+        for entry in iter {
+            let raw = entry.raw_entry();
+            if raw.data().is_empty() {
+                continue;
+            }
+            let start = raw.file_offset().unwrap() as usize;
+            assert_eq!(&buf[start..start + raw.data().len()], raw.data());
+
+            // The entry at `entry_offset` is the one which refers to the value
+            let tiff = start - raw.value_offset() as usize;
+            let code = match &buf[tiff..tiff + 2] {
+                b"MM" => entry.tag_code().to_be_bytes(),
+                b"II" => entry.tag_code().to_le_bytes(),
+                x => panic!("invalid TIFF header {x:?}"),
+            };
+            let pos = tiff + raw.entry_offset() as usize;
+            assert_eq!(buf[pos..pos + 2], code);
+            n += 1;
+        }
+        assert!(n > 10);
+    }
+
+    #[test]
+    fn custom_tags() {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        // 0xabcd: U16 * 2 stored as UNDEFINED
+        let data = tiff_with_entry([0xcd, 0xab, 0x07, 0x00, 0x04, 0, 0, 0, 1, 0, 2, 0], 0);
+        let mut iter = input_into_iter(data, None).unwrap();
+        iter.register_tag(CustomTag {
+            data_format: Some(3),
+            ..CustomTag::new(0xabcd, "Pair")
+        });
+        // Tags of other groups & recognized tags are not affected
+        iter.register_tag(CustomTag {
+            group: Some(IfdGroup::Gps),
+            ..CustomTag::new(0xabcd, "GpsPair")
+        });
+        iter.register_tag(CustomTag::new(ExifTag::Make.code(), "Brand"));
+
+        let entries = iter.clone().collect::<Vec<_>>();
+        assert_eq!(entries[0].name(), Some("Make"));
+        assert!(entries[0].custom_tag().is_none());
+        assert_eq!(entries[1].name(), Some("Pair"));
+        assert_eq!(entries[1].custom_tag().unwrap().code, 0xabcd);
+        assert_eq!(
+            entries[1].get_value(),
+            Some(&EntryValue::U16Array(vec![1, 2]))
+        );
+        assert!(iter.to_json().contains(r#""Pair": "1 2""#));
+
+        // Invalid data formats can be fixed as well
+        let data = tiff_with_entry([0xcd, 0xab, 0x99, 0x00, 0x01, 0, 0, 0, 1, 0, 0, 0], 0);
+        let mut iter = input_into_iter(data, None).unwrap();
+        iter.register_tag(CustomTag {
+            data_format: Some(4),
+            ..CustomTag::new(0xabcd, "Count")
+        });
+        let entry = iter.nth(1).unwrap();
+        assert_eq!(entry.get_value(), Some(&EntryValue::U32(1)));
+
+        // Without a registration
+        let mut iter = input_into_iter(
+            tiff_with_entry([0xcd, 0xab, 0x07, 0x00, 0x04, 0, 0, 0, 1, 0, 2, 0], 0),
+            None,
+        )
+        .unwrap();
+        let entry = iter.nth(1).unwrap();
+        assert_eq!(entry.name(), None);
+        assert_eq!(
+            entry.get_value(),
+            Some(&EntryValue::Undefined(vec![1, 0, 2, 0]))
+        );
+    }
+
+    #[test]
+    fn scoped_ifds() {
+        use crate::{MediaParser, MediaSource};
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let ms = MediaSource::file_path("testdata/exif.jpg").unwrap();
+        let mut iter: ExifIter = MediaParser::new().parse(ms).unwrap();
+        iter.next().unwrap();
+        let tags = |iter: Option<ExifIter>| {
+            iter.unwrap()
+                .map(|x| (x.ifd_index(), x.tag_code()))
+                .collect::<Vec<_>>()
+        };
+
+        let ifd0 = tags(iter.ifd(0));
+        assert!(ifd0.contains(&(0, ExifTag::Make.code())));
+        assert!(ifd0.contains(&(0, ExifTag::ExifOffset.code())));
+        assert!(!ifd0.contains(&(0, ExifTag::ExposureTime.code())));
+
+        let exif = tags(iter.exif_ifd());
+        assert!(exif.contains(&(0, ExifTag::ExposureTime.code())));
+        assert!(exif.contains(&(0, ExifTag::InteropOffset.code())));
+        assert!(!exif.contains(&(0, ExifTag::Make.code())));
+
+        let gps = tags(iter.gps_ifd());
+        assert!(gps.contains(&(0, ExifTag::GPSLatitude.code())));
+        assert!(!gps.contains(&(0, ExifTag::ExposureTime.code())));
+
+        let interop = iter.interop_ifd().unwrap();
+        assert!(interop.clone().all(|x| x.tag().is_none()));
+        assert_eq!(tags(Some(interop)), [(0, 0x0001), (0, 0x0002)]);
+
+        let ifd1 = tags(iter.ifd(1));
+        assert!(ifd1.contains(&(1, ExifTag::Compression.code())));
+        assert!(ifd1.iter().all(|x| x.0 == 1));
+        assert!(iter.ifd(2).is_none());
+
+        // Scoped iterators can be cloned & rewound
+        let exif_ifd = iter.exif_ifd().unwrap();
+        assert_eq!(exif_ifd.clone().count(), exif.len());
+        assert_eq!(exif_ifd.clone_and_rewind().count(), exif.len());
+
+        // The flat iteration covers all of them, except the Interop IFD
+        let all = tags(Some(iter.clone_and_rewind()));
+        assert_eq!(all.len(), ifd0.len() + exif.len() + gps.len() + ifd1.len());
+
+        // Groups
+        let groups =
+            |iter: Option<ExifIter>| iter.unwrap().map(|x| x.ifd_group()).collect::<HashSet<_>>();
+        assert_eq!(groups(iter.ifd(0)), HashSet::from([IfdGroup::Ifd0]));
+        assert_eq!(groups(iter.exif_ifd()), HashSet::from([IfdGroup::ExifIfd]));
+        assert_eq!(groups(iter.gps_ifd()), HashSet::from([IfdGroup::Gps]));
+        assert_eq!(
+            groups(iter.interop_ifd()),
+            HashSet::from([IfdGroup::Interop])
+        );
+        assert_eq!(groups(iter.ifd(1)), HashSet::from([IfdGroup::Ifd1]));
+        assert_eq!(
+            groups(Some(iter.clone_and_rewind())),
+            HashSet::from([
+                IfdGroup::Ifd0,
+                IfdGroup::ExifIfd,
+                IfdGroup::Gps,
+                IfdGroup::Ifd1
+            ])
+        );
+    }
+
+    #[test]
+    fn scoped_sub_ifds() {
+        use crate::{MediaParser, MediaSource};
+
+        let ms = MediaSource::seekable(std::io::Cursor::new(dng())).unwrap();
+        let iter: ExifIter = MediaParser::new().parse(ms).unwrap();
+        let codes = |n| {
+            iter.ifd(n)
+                .map(|x| x.map(|x| x.tag_code()).collect::<Vec<_>>())
+                .unwrap_or_default()
+        };
+        assert_eq!(
+            codes(0),
+            [0x00fe, 0x0100, 0x0101, 0x010f, 0x014a, 0xc612, 0xc614]
+        );
+        assert_eq!(codes(1), [0x00fe, 0x0100, 0x0101]);
+        assert_eq!(codes(2), [0x00fe, 0x0100]);
+        assert_eq!(codes(3), [0x00fe]);
+        assert!(iter
+            .ifd(1)
+            .unwrap()
+            .all(|x| x.ifd_group() == IfdGroup::SubIfd));
+        assert!(codes(4).is_empty());
+        assert!(iter.exif_ifd().is_none());
+    }
 }
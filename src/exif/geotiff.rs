@@ -0,0 +1,227 @@
+use crate::{EntryValue, ExifTag};
+
+/// Georeferencing information stored in the GeoTIFF tags
+/// ([`ExifTag::GeoKeyDirectory`], [`ExifTag::ModelPixelScale`],
+/// [`ExifTag::ModelTiepoint`], etc.) of ifd0.
+///
+/// See: [GeoTIFF](http://geotiff.maptools.org/spec/geotiff2.4.html).
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct GeoTiffInfo {
+    /// KeyDirectoryVersion, KeyRevision, MinorRevision, e.g. (1, 1, 0)
+    pub version: (u16, u16, u16),
+    pub keys: Vec<GeoKey>,
+
+    /// Size of a raster pixel in model space, (x, y, z)
+    pub pixel_scale: Option<[f64; 3]>,
+    pub tiepoints: Vec<Tiepoint>,
+    /// A 4x4 matrix in row-major order, which transforms raster space to
+    /// model space.
+    pub transformation: Option<[f64; 16]>,
+}
+
+/// A key of the GeoKey directory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoKey {
+    pub id: u16,
+    pub value: GeoKeyValue,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeoKeyValue {
+    Short(Vec<u16>),
+    Double(Vec<f64>),
+    Ascii(String),
+}
+
+/// Maps a point in raster space (i, j, k) to a point in model space (x, y,
+/// z).
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Tiepoint {
+    pub raster: [f64; 3],
+    pub model: [f64; 3],
+}
+
+impl GeoTiffInfo {
+    /// GTModelTypeGeoKey, 1: projected, 2: geographic, 3: geocentric
+    pub const MODEL_TYPE_KEY: u16 = 1024;
+    /// GTRasterTypeGeoKey, 1: PixelIsArea, 2: PixelIsPoint
+    pub const RASTER_TYPE_KEY: u16 = 1025;
+    /// GeographicTypeGeoKey
+    pub const GEOGRAPHIC_TYPE_KEY: u16 = 2048;
+    /// ProjectedCSTypeGeoKey
+    pub const PROJECTED_CS_TYPE_KEY: u16 = 3072;
+
+    /// Returns the value of the GeoKey `id`.
+    pub fn get_key(&self, id: u16) -> Option<&GeoKeyValue> {
+        self.keys.iter().find(|x| x.id == id).map(|x| &x.value)
+    }
+
+    /// Returns the EPSG code of the coordinate reference system, i.e. the
+    /// projected CRS, or the geographic CRS if it's not projected. Returns
+    /// `None` if the CRS is user-defined (32767).
+    pub fn epsg(&self) -> Option<u16> {
+        const USER_DEFINED: u16 = 32767;
+        [Self::PROJECTED_CS_TYPE_KEY, Self::GEOGRAPHIC_TYPE_KEY]
+            .iter()
+            .find_map(|id| match self.get_key(*id)? {
+                GeoKeyValue::Short(v) => v.first().copied(),
+                _ => None,
+            })
+            .filter(|x| *x != USER_DEFINED)
+    }
+
+    /// Builds `GeoTiffInfo` from the entry values of ifd0, returns `None` if
+    /// there is no GeoKey directory.
+    pub(crate) fn from_entries<'a>(
+        get: impl Fn(ExifTag) -> Option<&'a EntryValue>,
+    ) -> Option<GeoTiffInfo> {
+        let dir = match get(ExifTag::GeoKeyDirectory)? {
+            EntryValue::U16Array(v) => v.as_slice(),
+            _ => return None,
+        };
+        let [version, revision, minor, num, entries @ ..] = dir else {
+            tracing::warn!("Ignored: GeoKey directory is too short");
+            return None;
+        };
+        let doubles = get(ExifTag::GeoDoubleParams)
+            .map(as_f64s)
+            .unwrap_or_default();
+        let ascii = get(ExifTag::GeoAsciiParams)
+            .and_then(|x| x.as_str())
+            .unwrap_or_default();
+
+        let keys = entries
+            .chunks_exact(4)
+            .take(*num as usize)
+            .filter_map(|x| {
+                let (id, location, count, offset) = (x[0], x[1], x[2] as usize, x[3] as usize);
+                let range = offset..offset.saturating_add(count);
+                let value = match location {
+                    0 => GeoKeyValue::Short(vec![offset as u16]),
+                    x if x == ExifTag::GeoKeyDirectory.code() => {
+                        GeoKeyValue::Short(dir.get(range)?.to_vec())
+                    }
+                    x if x == ExifTag::GeoDoubleParams.code() => {
+                        GeoKeyValue::Double(doubles.get(range)?.to_vec())
+                    }
+                    x if x == ExifTag::GeoAsciiParams.code() => {
+                        // Strings are terminated with '|'
+                        let s = ascii.get(range)?;
+                        GeoKeyValue::Ascii(s.strip_suffix('|').unwrap_or(s).to_owned())
+                    }
+                    _ => {
+                        tracing::warn!(id, location, "Ignored: unknown GeoKey location");
+                        return None;
+                    }
+                };
+                Some(GeoKey { id, value })
+            })
+            .collect();
+
+        let pixel_scale = get(ExifTag::ModelPixelScale)
+            .map(as_f64s)
+            .and_then(|x| x.try_into().ok());
+        let tiepoints = get(ExifTag::ModelTiepoint)
+            .map(as_f64s)
+            .unwrap_or_default()
+            .chunks_exact(6)
+            .map(|x| Tiepoint {
+                raster: [x[0], x[1], x[2]],
+                model: [x[3], x[4], x[5]],
+            })
+            .collect();
+        let transformation = get(ExifTag::ModelTransformation)
+            .map(as_f64s)
+            .and_then(|x| x.try_into().ok());
+
+        Some(GeoTiffInfo {
+            version: (*version, *revision, *minor),
+            keys,
+            pixel_scale,
+            tiepoints,
+            transformation,
+        })
+    }
+}
+
+fn as_f64s(v: &EntryValue) -> Vec<f64> {
+    match v {
+        EntryValue::F64(x) => vec![*x],
+        EntryValue::F64Array(x) => x.clone(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn geotiff_entries() -> HashMap<ExifTag, EntryValue> {
+        let mut entries = HashMap::new();
+        entries.insert(
+            ExifTag::GeoKeyDirectory,
+            EntryValue::U16Array(vec![
+                1, 1, 0, 5, //
+                1024, 0, 1, 1, //
+                1026, 34737, 8, 0, //
+                2057, 34736, 1, 1, //
+                3072, 0, 1, 32633, //
+                4096, 34735, 1, 0, // stored in the directory itself
+            ]),
+        );
+        entries.insert(
+            ExifTag::GeoDoubleParams,
+            EntryValue::F64Array(vec![0.0, 6378137.0]),
+        );
+        entries.insert(ExifTag::GeoAsciiParams, EntryValue::Text("UTM 33N|".into()));
+        entries.insert(
+            ExifTag::ModelPixelScale,
+            EntryValue::F64Array(vec![30.0, 30.0, 0.0]),
+        );
+        entries.insert(
+            ExifTag::ModelTiepoint,
+            EntryValue::F64Array(vec![0.0, 0.0, 0.0, 350807.4, 5316081.3, 0.0]),
+        );
+        entries
+    }
+
+    #[test]
+    fn geotiff_info() {
+        let entries = geotiff_entries();
+        let info = GeoTiffInfo::from_entries(|x| entries.get(&x)).unwrap();
+        assert_eq!(info.version, (1, 1, 0));
+        assert_eq!(
+            info.get_key(GeoTiffInfo::MODEL_TYPE_KEY),
+            Some(&GeoKeyValue::Short(vec![1]))
+        );
+        assert_eq!(
+            info.get_key(1026),
+            Some(&GeoKeyValue::Ascii("UTM 33N".into()))
+        );
+        assert_eq!(
+            info.get_key(2057),
+            Some(&GeoKeyValue::Double(vec![6378137.0]))
+        );
+        assert_eq!(info.get_key(4096), Some(&GeoKeyValue::Short(vec![1])));
+        assert_eq!(info.epsg(), Some(32633));
+        assert_eq!(info.pixel_scale, Some([30.0, 30.0, 0.0]));
+        assert_eq!(
+            info.tiepoints,
+            [Tiepoint {
+                raster: [0.0; 3],
+                model: [350807.4, 5316081.3, 0.0]
+            }]
+        );
+        assert_eq!(info.transformation, None);
+    }
+
+    #[test]
+    fn no_geotiff() {
+        let mut entries = geotiff_entries();
+        entries.insert(ExifTag::GeoKeyDirectory, EntryValue::U16Array(vec![1, 1]));
+        assert!(GeoTiffInfo::from_entries(|x| entries.get(&x)).is_none());
+        entries.remove(&ExifTag::GeoKeyDirectory);
+        assert!(GeoTiffInfo::from_entries(|x| entries.get(&x)).is_none());
+    }
+}
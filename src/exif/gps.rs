@@ -1,11 +1,19 @@
 use std::str::FromStr;
 
+use chrono::{DateTime, NaiveDate, Utc};
 use iso6709parse::{parse_string_representation, ISO6709Coord};
 
-use crate::values::{IRational, URational};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    json::{float, quote},
+    values::{IRational, URational},
+};
 
 /// Represents gps information stored in [`GPSInfo`](crate::ExifTag::GPSInfo)
 /// subIFD.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct GPSInfo {
     /// N, S
@@ -30,22 +38,44 @@ pub struct GPSInfo {
     /// - N: knots
     pub speed_ref: Option<char>,
     pub speed: Option<URational>,
+
+    /// UTC date, e.g. "2023:07:09"
+    pub date_stamp: Option<String>,
+    /// UTC time of day: hour, minute, second
+    pub time_stamp: Option<[URational; 3]>,
+
+    /// Reference of the image direction & the destination bearing
+    /// - T: true direction
+    /// - M: magnetic direction
+    pub img_direction_ref: Option<char>,
+    /// Direction of the image when it was captured, in degrees (0 ~ 359.99)
+    pub img_direction: Option<URational>,
+    pub dest_bearing_ref: Option<char>,
+    /// Bearing to the destination point, in degrees (0 ~ 359.99)
+    pub dest_bearing: Option<URational>,
+
+    /// Dilution of precision of the measurement
+    pub dop: Option<URational>,
 }
 
 /// degree, minute, second,
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct LatLng(pub URational, pub URational, pub URational);
 
+impl LatLng {
+    /// Returns the unsigned value in decimal degrees.
+    pub fn as_degrees(&self) -> f64 {
+        self.0.as_float() + self.1.as_float() / 60.0 + self.2.as_float() / 3600.0
+    }
+}
+
 impl GPSInfo {
     /// Returns an ISO 6709 geographic point location string such as
     /// `+48.8577+002.295/`.
     pub fn format_iso6709(&self) -> String {
-        let latitude = self.latitude.0.as_float()
-            + self.latitude.1.as_float() / 60.0
-            + self.latitude.2.as_float() / 3600.0;
-        let longitude = self.longitude.0.as_float()
-            + self.longitude.1.as_float() / 60.0
-            + self.longitude.2.as_float() / 3600.0;
+        let latitude = self.latitude.as_degrees();
+        let longitude = self.longitude.as_degrees();
         let altitude = self.altitude.as_float();
         format!(
             "{}{latitude:08.5}{}{longitude:09.5}{}/",
@@ -63,6 +93,108 @@ impl GPSInfo {
         )
     }
 
+    /// Returns the location in decimal degrees as `(latitude, longitude,
+    /// altitude)`, latitude/longitude are negative for south/west, and
+    /// altitude (in meters) is negative for below sea level, or `None` if
+    /// unknown.
+    ///
+    /// ```rust
+    /// use nom_exif::*;
+    ///
+    /// let gps = GPSInfo {
+    ///     latitude_ref: 'S',
+    ///     latitude: [(33, 1), (51, 1), (36, 1)].into(),
+    ///     longitude_ref: 'E',
+    ///     longitude: [(151, 1), (12, 1), (36, 1)].into(),
+    ///     altitude: (35, 1).into(),
+    ///     ..Default::default()
+    /// };
+    /// let (lat, lng, alt) = gps.to_decimal();
+    /// assert_eq!(format!("{lat:.2}, {lng:.2}"), "-33.86, 151.21");
+    /// assert_eq!(alt, Some(35.0));
+    /// ```
+    pub fn to_decimal(&self) -> (f64, f64, Option<f64>) {
+        let sign = |negative: bool| if negative { -1.0 } else { 1.0 };
+        let latitude = sign(self.latitude_ref == 'S') * self.latitude.as_degrees();
+        let longitude = sign(self.longitude_ref == 'W') * self.longitude.as_degrees();
+        // An altitude of x/0 means unknown
+        let altitude =
+            (self.altitude.1 != 0).then(|| sign(self.altitude_ref == 1) * self.altitude.as_float());
+        (latitude, longitude, altitude)
+    }
+
+    /// Returns a GeoJSON `Feature` whose geometry is a `Point` of `[longitude,
+    /// latitude(, altitude)]`, see [`GPSInfo::to_decimal`]. The other fields
+    /// which are present (e.g. `speed`, `img_direction`, `dop`) are reported
+    /// as the feature's properties.
+    pub fn to_geojson(&self) -> String {
+        let (latitude, longitude, altitude) = self.to_decimal();
+        let mut coordinates = vec![float(longitude), float(latitude)];
+        coordinates.extend(altitude.map(float));
+
+        let mut properties = Vec::new();
+        let mut push = |k: &str, v: String| properties.push(format!("{}:{v}", quote(k)));
+        if let Some(date) = &self.date_stamp {
+            push("date_stamp", quote(date));
+        }
+        if let Some([h, m, s]) = &self.time_stamp {
+            let time = format!(
+                "{:02}:{:02}:{:02}",
+                h.as_float(),
+                m.as_float(),
+                s.as_float()
+            );
+            push("time_stamp", quote(&time));
+        }
+        let mut push_value = |k: &str, r: Option<char>, v: Option<URational>| {
+            if let Some(v) = v {
+                push(k, float(v.as_float()));
+                if let Some(r) = r {
+                    push(&format!("{k}_ref"), quote(&r.to_string()));
+                }
+            }
+        };
+        push_value("speed", self.speed_ref, self.speed);
+        push_value("img_direction", self.img_direction_ref, self.img_direction);
+        push_value("dest_bearing", self.dest_bearing_ref, self.dest_bearing);
+        push_value("dop", None, self.dop);
+
+        format!(
+            r#"{{"type":"Feature","geometry":{{"type":"Point","coordinates":[{}]}},"properties":{{{}}}}}"#,
+            coordinates.join(","),
+            properties.join(",")
+        )
+    }
+
+    /// Returns the UTC date time of the GPS fix by merging the `date_stamp`
+    /// & `time_stamp` fields (fractions of a second are kept), returns `None`
+    /// if any of them is missing or invalid.
+    ///
+    /// It's useful to correct the camera clock against the GPS time.
+    ///
+    /// ```rust
+    /// use nom_exif::*;
+    ///
+    /// let gps = GPSInfo {
+    ///     date_stamp: Some("2023:07:09".into()),
+    ///     time_stamp: Some([(12, 1).into(), (36, 1).into(), (3350, 100).into()]),
+    ///     ..Default::default()
+    /// };
+    /// let t = gps.utc_timestamp().unwrap();
+    /// assert_eq!(t.to_rfc3339(), "2023-07-09T12:36:33.500+00:00");
+    /// ```
+    pub fn utc_timestamp(&self) -> Option<DateTime<Utc>> {
+        let date =
+            NaiveDate::parse_from_str(self.date_stamp.as_deref()?.trim(), "%Y:%m:%d").ok()?;
+        let [h, m, s] = self.time_stamp?;
+        if h.1 == 0 || m.1 == 0 || s.1 == 0 {
+            return None;
+        }
+        let nanos = (s.0 % s.1) as u64 * 1_000_000_000 / s.1 as u64;
+        let time = date.and_hms_nano_opt(h.0 / h.1, m.0 / m.1, s.0 / s.1, nanos as u32)?;
+        Some(time.and_utc())
+    }
+
     fn format_float(f: f64) -> String {
         if f.fract() == 0.0 {
             f.to_string()
@@ -322,4 +454,59 @@ mod tests {
             "+40.68917-074.04444-33.333CRSWGS_84/"
         );
     }
+
+    #[test]
+    fn gps_utc_timestamp() {
+        let mut gps = GPSInfo {
+            date_stamp: Some("2023:07:09".into()),
+            time_stamp: Some([(23, 1).into(), (59, 1).into(), (59, 1).into()]),
+            ..Default::default()
+        };
+        assert_eq!(
+            gps.utc_timestamp().map(|x| x.to_rfc3339()).as_deref(),
+            Some("2023-07-09T23:59:59+00:00")
+        );
+
+        gps.time_stamp = Some([(24, 1).into(), (0, 1).into(), (0, 1).into()]);
+        assert_eq!(gps.utc_timestamp(), None);
+        gps.time_stamp = Some([(12, 1).into(), (0, 0).into(), (0, 1).into()]);
+        assert_eq!(gps.utc_timestamp(), None);
+        gps.time_stamp = Some([(12, 1).into(), (0, 1).into(), (0, 1).into()]);
+        gps.date_stamp = Some("2023-07-09".into());
+        assert_eq!(gps.utc_timestamp(), None);
+        gps.date_stamp = None;
+        assert_eq!(gps.utc_timestamp(), None);
+    }
+
+    #[test]
+    fn gps_decimal_geojson() {
+        let mut gps = GPSInfo {
+            latitude_ref: 'S',
+            latitude: [(33, 1), (51, 1), (2445, 100)].into(),
+            longitude_ref: 'E',
+            longitude: [(151, 1), (12, 1), (5507, 100)].into(),
+            altitude_ref: 1,
+            altitude: (3, 2).into(),
+            ..Default::default()
+        };
+        let (lat, lng, alt) = gps.to_decimal();
+        assert_eq!(format!("{lat:.6},{lng:.6}"), "-33.856792,151.215297");
+        assert_eq!(alt, Some(-1.5));
+        assert_eq!(
+            gps.to_geojson(),
+            r#"{"type":"Feature","geometry":{"type":"Point","coordinates":[151.2152972222,-33.8567916667,-1.5]},"properties":{}}"#
+        );
+
+        gps.altitude = (0, 0).into();
+        gps.date_stamp = Some("2023:07:09".into());
+        gps.time_stamp = Some([(12, 1).into(), (36, 1).into(), (33, 1).into()]);
+        gps.img_direction_ref = Some('M');
+        gps.img_direction = Some((181, 2).into());
+        gps.dop = Some((5, 2).into());
+        assert_eq!(gps.to_decimal().2, None);
+        assert_eq!(
+            gps.to_geojson(),
+            r#"{"type":"Feature","geometry":{"type":"Point","coordinates":[151.2152972222,-33.8567916667]},"properties":{"date_stamp":"2023:07:09","time_stamp":"12:36:33","img_direction":90.5,"img_direction_ref":"M","dop":2.5}}"#
+        );
+    }
 }
@@ -0,0 +1,61 @@
+use std::fmt::Display;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The logical group of IFD entries, mirroring exiftool's "IFD0",
+/// "ExifIFD", "GPS" ... group names.
+///
+/// Unlike [`ParsedExifEntry::ifd_index`](crate::ParsedExifEntry::ifd_index),
+/// the group distinguishes the entries of the Exif & GPS sub-IFDs from the
+/// ones of the IFD which references them.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum IfdGroup {
+    /// The main image
+    Ifd0,
+    /// The thumbnail image (for JPEG/HEIF files), or the second page of
+    /// multi-page TIFF files
+    Ifd1,
+    /// Pages after ifd1 of multi-page TIFF files, e.g. `Ifd(2)` is IFD2
+    Ifd(usize),
+    /// The Exif sub-IFD, referenced by [`ExifTag::ExifOffset`](crate::ExifTag::ExifOffset)
+    ExifIfd,
+    /// The GPS sub-IFD, referenced by [`ExifTag::GPSInfo`](crate::ExifTag::GPSInfo)
+    Gps,
+    /// The Interop IFD, referenced by [`ExifTag::InteropOffset`](crate::ExifTag::InteropOffset)
+    /// of the Exif sub-IFD
+    Interop,
+    /// IFDs referenced by [`ExifTag::SubIFDs`](crate::ExifTag::SubIFDs),
+    /// e.g. the raw images of DNG files
+    SubIfd,
+    /// The MakerNote IFD, see [`ExifIter::maker_note_ifd`](crate::ExifIter::maker_note_ifd)
+    MakerNote,
+}
+
+impl IfdGroup {
+    /// Returns the group of page `page` (i.e. the IFDs chained from ifd0).
+    pub(crate) fn page(page: usize) -> Self {
+        match page {
+            0 => Self::Ifd0,
+            1 => Self::Ifd1,
+            n => Self::Ifd(n),
+        }
+    }
+}
+
+impl Display for IfdGroup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ifd0 => f.write_str("IFD0"),
+            Self::Ifd1 => f.write_str("IFD1"),
+            Self::Ifd(n) => write!(f, "IFD{n}"),
+            Self::ExifIfd => f.write_str("ExifIFD"),
+            Self::Gps => f.write_str("GPS"),
+            Self::Interop => f.write_str("InteropIFD"),
+            Self::SubIfd => f.write_str("SubIFD"),
+            Self::MakerNote => f.write_str("MakerNotes"),
+        }
+    }
+}
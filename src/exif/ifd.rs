@@ -1,7 +1,10 @@
 use crate::EntryValue;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 /// https://www.media.mit.edu/pia/Research/deepview/exif.html
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
 #[derive(Clone, Debug, PartialEq)]
 pub(crate) struct ParsedImageFileDirectory {
     pub entries: HashMap<u16, ParsedIdfEntry>,
@@ -15,6 +18,7 @@ impl ParsedImageFileDirectory {
     }
 }
 
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
 #[derive(Clone, Debug, PartialEq)]
 pub(crate) struct ParsedIdfEntry {
     pub value: EntryValue,
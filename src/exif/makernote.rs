@@ -0,0 +1,458 @@
+use nom::{
+    number::{complete, Endianness},
+    sequence::tuple,
+};
+
+use crate::{
+    slice::SliceChecked,
+    values::{DataFormat, EntryData},
+    EntryValue,
+};
+
+use super::{exif_exif::IFD_ENTRY_SIZE, TiffHeader};
+
+pub use apple::AppleMakerNote;
+pub use canon::{
+    CanonCameraSettings, CanonExposureMode, CanonMakerNote, CanonShotInfo, ImageStabilization,
+};
+#[cfg(feature = "makernotes")]
+pub use fujifilm::{FilmSimulation, FilterColor, FujifilmMakerNote};
+pub use nikon::{NikonLensData, NikonMakerNote};
+#[cfg(feature = "makernotes")]
+pub use olympus::OlympusMakerNote;
+#[cfg(feature = "makernotes")]
+pub use panasonic::PanasonicMakerNote;
+pub use sony::{SonyLensSpec, SonyMakerNote};
+
+mod apple;
+mod canon;
+#[cfg(feature = "makernotes")]
+mod fujifilm;
+mod nikon;
+#[cfg(feature = "makernotes")]
+mod olympus;
+#[cfg(feature = "makernotes")]
+mod panasonic;
+mod sony;
+
+/// Manufacturer specific information decoded from the `MakerNote` tag, see
+/// [`ExifIter::parse_maker_note`](crate::ExifIter::parse_maker_note).
+///
+/// The format of a MakerNote is decided by the manufacturer (the `Make` tag),
+/// only the following ones are supported for now:
+///
+/// - Apple
+/// - Canon
+/// - Nikon
+/// - Sony
+/// - Fujifilm, Olympus (OM System) & Panasonic: requires the `makernotes`
+///   feature
+#[derive(Debug, Clone, PartialEq)]
+pub enum MakerNote {
+    Apple(AppleMakerNote),
+    Canon(CanonMakerNote),
+    Nikon(NikonMakerNote),
+    Sony(SonyMakerNote),
+    #[cfg(feature = "makernotes")]
+    Fujifilm(FujifilmMakerNote),
+    #[cfg(feature = "makernotes")]
+    Olympus(OlympusMakerNote),
+    #[cfg(feature = "makernotes")]
+    Panasonic(PanasonicMakerNote),
+}
+
+impl MakerNote {
+    /// Decodes the MakerNote of a camera made by `make`.
+    ///
+    /// `tiff` is the TIFF data starting from the TIFF header, `offset` is the
+    /// offset of the MakerNote data in it.
+    pub(crate) fn parse(
+        make: &str,
+        tiff: &[u8],
+        offset: usize,
+        endian: Endianness,
+    ) -> Option<MakerNote> {
+        let make = make.trim();
+        if make.starts_with("Apple") {
+            return AppleMakerNote::parse(tiff, offset).map(MakerNote::Apple);
+        }
+        if make.starts_with("Canon") {
+            // A plain IFD, offsets are relative to the TIFF header
+            let entries = MakerNoteEntries::parse(tiff, offset, endian)?;
+            return Some(MakerNote::Canon(CanonMakerNote::from_entries(&entries)));
+        }
+        if make.starts_with("NIKON") {
+            return NikonMakerNote::parse(tiff, offset, endian).map(MakerNote::Nikon);
+        }
+        if make.starts_with("SONY") {
+            return SonyMakerNote::parse(tiff, offset, endian).map(MakerNote::Sony);
+        }
+        #[cfg(feature = "makernotes")]
+        if make.starts_with("FUJIFILM") {
+            return FujifilmMakerNote::parse(tiff, offset).map(MakerNote::Fujifilm);
+        }
+        #[cfg(feature = "makernotes")]
+        if make.starts_with("OLYMPUS") || make.starts_with("OM Digital") {
+            return OlympusMakerNote::parse(tiff, offset, endian).map(MakerNote::Olympus);
+        }
+        #[cfg(feature = "makernotes")]
+        if make.starts_with("Panasonic") {
+            return PanasonicMakerNote::parse(tiff, offset, endian).map(MakerNote::Panasonic);
+        }
+        tracing::debug!(make, "unsupported MakerNote");
+        None
+    }
+
+    pub fn as_apple(&self) -> Option<&AppleMakerNote> {
+        match self {
+            MakerNote::Apple(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    pub fn as_canon(&self) -> Option<&CanonMakerNote> {
+        match self {
+            MakerNote::Canon(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    pub fn as_nikon(&self) -> Option<&NikonMakerNote> {
+        match self {
+            MakerNote::Nikon(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    pub fn as_sony(&self) -> Option<&SonyMakerNote> {
+        match self {
+            MakerNote::Sony(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "makernotes")]
+    pub fn as_fujifilm(&self) -> Option<&FujifilmMakerNote> {
+        match self {
+            MakerNote::Fujifilm(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "makernotes")]
+    pub fn as_olympus(&self) -> Option<&OlympusMakerNote> {
+        match self {
+            MakerNote::Olympus(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "makernotes")]
+    pub fn as_panasonic(&self) -> Option<&PanasonicMakerNote> {
+        match self {
+            MakerNote::Panasonic(x) => Some(x),
+            _ => None,
+        }
+    }
+}
+
+/// Location of the IFD of a MakerNote, see [`locate_ifd`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct MakerNoteIfd {
+    /// Base of the offsets in the IFD, relative to the TIFF header
+    pub base: usize,
+    /// Offset of the IFD, relative to `base`
+    pub offset: usize,
+    pub endian: Endianness,
+}
+
+/// Locates the IFD of the MakerNote at `offset` of `tiff` (which starts from
+/// the TIFF header) made by `make`, in the same ways as the vendor specific
+/// decoders. Returns `None` if the MakerNote is not an IFD, or the format is
+/// unknown.
+pub(crate) fn locate_ifd(
+    make: &str,
+    tiff: &[u8],
+    offset: usize,
+    endian: Endianness,
+) -> Option<MakerNoteIfd> {
+    let data = tiff.get(offset..)?;
+    let byte_order = |at: usize| match data.get(at..at + 2)? {
+        b"II" => Some(Endianness::Little),
+        b"MM" => Some(Endianness::Big),
+        _ => None,
+    };
+    // (base, IFD offset relative to base, endian)
+    let (base, ifd, endian) = match make.trim() {
+        x if x.starts_with("Apple") && data.starts_with(b"Apple iOS\0") => {
+            (offset, 14, byte_order(12)?)
+        }
+        x if x.starts_with("FUJIFILM") && data.starts_with(b"FUJIFILM") => {
+            let ifd = u32::from_le_bytes(data.get(8..12)?.try_into().ok()?);
+            (offset, ifd as usize, Endianness::Little)
+        }
+        x if x.starts_with("NIKON") && data.starts_with(b"Nikon\0\x02") => {
+            let (_, header) = TiffHeader::parse(data.get(10..)?).ok()?;
+            (offset + 10, header.ifd0_offset as usize, header.endian)
+        }
+        x if x.starts_with("NIKON") && data.starts_with(b"Nikon\0\x01") => (0, offset + 8, endian),
+        x if x.starts_with("SONY")
+            && (data.starts_with(b"SONY DSC ") || data.starts_with(b"SONY CAM ")) =>
+        {
+            (0, offset + 12, endian)
+        }
+        x if x.starts_with("Panasonic") && data.starts_with(b"Panasonic\0") => {
+            (0, offset + 12, endian)
+        }
+        _ if data.starts_with(b"OLYMPUS\0") => (offset, 12, byte_order(8)?),
+        _ if data.starts_with(b"OM SYSTEM\0") => (offset, 16, byte_order(12)?),
+        _ if data.starts_with(b"OLYMP\0") => (0, offset + 8, endian),
+        // Plain IFDs, offsets are relative to the TIFF header
+        x if x.starts_with("Canon") || x.starts_with("NIKON") || x.starts_with("SONY") => {
+            (0, offset, endian)
+        }
+        _ => return None,
+    };
+    Some(MakerNoteIfd {
+        base,
+        offset: ifd,
+        endian,
+    })
+}
+
+/// Entries of a MakerNote IFD in order, (tag code, value).
+#[derive(Debug, Default)]
+pub(crate) struct MakerNoteEntries(Vec<(u16, EntryValue)>);
+
+impl MakerNoteEntries {
+    /// Parses a TIFF style IFD at `offset` of `tiff`, value offsets are
+    /// relative to the start of `tiff`. Returns `None` if the IFD is out of
+    /// range, invalid entries are ignored.
+    pub fn parse(tiff: &[u8], offset: usize, endian: Endianness) -> Option<MakerNoteEntries> {
+        let ifd = tiff.get(offset..)?;
+        let (_, num) = complete::u16::<_, nom::error::Error<_>>(endian)(ifd).ok()?;
+        let entries = ifd
+            .get(2..)?
+            .chunks_exact(IFD_ENTRY_SIZE)
+            .take(num as usize)
+            .filter_map(|x| parse_entry(tiff, x, endian))
+            .collect();
+        Some(MakerNoteEntries(entries))
+    }
+
+    pub fn get(&self, code: u16) -> Option<&EntryValue> {
+        self.0.iter().find(|x| x.0 == code).map(|x| &x.1)
+    }
+
+    pub fn get_str(&self, code: u16) -> Option<String> {
+        self.get(code)?
+            .as_str()
+            .map(|x| x.trim().to_owned())
+            .filter(|x| !x.is_empty())
+    }
+
+    pub fn get_u32(&self, code: u16) -> Option<u32> {
+        match self.get(code)? {
+            EntryValue::U16(x) => Some(*x as u32),
+            EntryValue::U32(x) => Some(*x),
+            _ => None,
+        }
+    }
+
+    /// Returns an array of shorts, which is how most binary tables (e.g.
+    /// Canon's CameraSettings) are stored.
+    pub fn get_u16_array(&self, code: u16) -> Option<&[u16]> {
+        match self.get(code)? {
+            EntryValue::U16Array(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    /// Returns the raw data of an `Undefined` or byte array entry.
+    pub fn get_bytes(&self, code: u16) -> Option<&[u8]> {
+        match self.get(code)? {
+            EntryValue::Undefined(x) | EntryValue::U8Array(x) => Some(x),
+            _ => None,
+        }
+    }
+}
+
+fn parse_entry(tiff: &[u8], entry: &[u8], endian: Endianness) -> Option<(u16, EntryValue)> {
+    let (_, (tag, data_format, components_num, value_or_offset)) = tuple((
+        complete::u16::<_, nom::error::Error<_>>(endian),
+        complete::u16(endian),
+        complete::u32(endian),
+        complete::u32(endian),
+    ))(entry)
+    .ok()?;
+    let data_format = DataFormat::try_from(data_format).ok()?;
+    let size = (components_num as usize).saturating_mul(data_format.component_size());
+    let data = if size <= 4 {
+        &entry[8..8 + size] // Safe-slice
+    } else {
+        let start = value_or_offset as usize;
+        tiff.slice_checked(start..start.saturating_add(size))?
+    };
+    let entry = EntryData {
+        endian,
+        tag,
+        data,
+        data_format,
+        components_num,
+    };
+    match EntryValue::parse(&entry, &None) {
+        Ok(v) => Some((tag, v)),
+        Err(e) => {
+            tracing::debug!(tag, ?e, "Ignored: invalid MakerNote entry");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{parse_exif_bytes, Exif};
+    use test_case::test_case;
+
+    /// An IFD entry for building test data: (tag, data format, components
+    /// num, data in little endian)
+    pub(crate) type TestEntry<'a> = (u16, u16, u32, &'a [u8]);
+
+    /// Builds a little endian IFD located at `offset`, the data of entries
+    /// (if it doesn't fit in 4 bytes) follows the IFD.
+    pub(crate) fn build_ifd(offset: usize, entries: &[TestEntry]) -> Vec<u8> {
+        let mut data_offset = offset + 2 + entries.len() * IFD_ENTRY_SIZE + 4;
+        let mut ifd = (entries.len() as u16).to_le_bytes().to_vec();
+        let mut data: Vec<u8> = Vec::new();
+        for (tag, format, num, value) in entries {
+            ifd.extend(tag.to_le_bytes());
+            ifd.extend(format.to_le_bytes());
+            ifd.extend(num.to_le_bytes());
+            if value.len() <= 4 {
+                ifd.extend(*value);
+                ifd.extend(vec![0; 4 - value.len()]);
+            } else {
+                ifd.extend((data_offset as u32).to_le_bytes());
+                data.extend(*value);
+                data_offset += value.len();
+            }
+        }
+        ifd.extend([0; 4]);
+        ifd.extend(data);
+        ifd
+    }
+
+    /// Builds little endian TIFF data with a `Make` tag & a `MakerNote` tag,
+    /// `maker_note` builds the MakerNote data at the given offset.
+    pub(crate) fn tiff_with_maker_note(
+        make: &str,
+        maker_note: impl Fn(usize) -> Vec<u8>,
+    ) -> Vec<u8> {
+        let make = format!("{make}\0");
+        let ifd0 = |exif_offset: u32| {
+            build_ifd(
+                8,
+                &[
+                    (0x010f, 2, make.len() as u32, make.as_bytes()),
+                    (0x8769, 4, 1, &exif_offset.to_le_bytes()),
+                ],
+            )
+        };
+        let exif_offset = 8 + ifd0(0).len();
+        let exif = |maker_note: &[u8]| {
+            build_ifd(
+                exif_offset,
+                &[(0x927c, 7, maker_note.len() as u32, maker_note)],
+            )
+        };
+        let maker_note_offset = exif_offset + exif(&[0; 5]).len() - 5;
+        let maker_note = maker_note(maker_note_offset);
+
+        let mut data = b"II*\0\x08\0\0\0".to_vec();
+        data.extend(ifd0(exif_offset as u32));
+        data.extend(exif(&maker_note));
+        data
+    }
+
+    #[test]
+    fn maker_note_entries() {
+        let tiff = tiff_with_maker_note("Canon", |offset| {
+            build_ifd(
+                offset,
+                &[
+                    (0x0001, 3, 3, &[6, 0, 1, 0, 0xff, 0xff]),
+                    (0x0006, 2, 8, b"IMG:XYZ\0"),
+                    (0x0010, 4, 1, &[1, 2, 0, 0]),
+                    // invalid data format
+                    (0x0011, 99, 1, &[0; 4]),
+                ],
+            )
+        });
+        let iter = parse_exif_bytes(&tiff).unwrap();
+        let offset = iter.maker_note_offset().unwrap();
+        let entries = MakerNoteEntries::parse(&tiff, offset as usize, Endianness::Little).unwrap();
+        assert_eq!(entries.0.len(), 3);
+        assert_eq!(entries.get_u16_array(0x0001), Some(&[6, 1, 0xffff][..]));
+        assert_eq!(entries.get_str(0x0006).as_deref(), Some("IMG:XYZ"));
+        assert_eq!(entries.get_u32(0x0010), Some(0x0201));
+        assert!(entries.get(0x0011).is_none());
+
+        let exif: Exif = iter.into();
+        assert!(exif.get_maker_note().unwrap().as_canon().is_some());
+    }
+
+    #[test]
+    fn unsupported_maker_note() {
+        let tiff = tiff_with_maker_note("vivo", |offset| build_ifd(offset, &[]));
+        let iter = parse_exif_bytes(&tiff).unwrap();
+        assert!(iter.maker_note_offset().is_some());
+        assert!(iter.parse_maker_note().is_none());
+    }
+
+    #[test_case("Canon", b"" ; "plain")]
+    #[test_case("SONY", b"SONY DSC \0\0\0" ; "sony")]
+    #[test_case("Panasonic", b"Panasonic\0\0\0" ; "panasonic")]
+    #[test_case("NIKON", b"Nikon\0\x01\0" ; "nikon type 1")]
+    #[test_case("NIKON", b"Nikon\0\x02\x10\0\0II*\0\x08\0\0\0" ; "nikon type 2")]
+    #[test_case("Apple", b"Apple iOS\0\0\x01II" ; "apple")]
+    fn maker_note_ifd(make: &str, header: &[u8]) {
+        use crate::{ExifIter, IfdGroup, MediaParser, MediaSource};
+
+        let tiff = tiff_with_maker_note(make, |offset| {
+            // Offsets are relative to the MakerNote for Apple, or to the
+            // nested TIFF header for Nikon type 2
+            let ifd_offset = match make {
+                "Apple" => header.len(),
+                "NIKON" if header.len() > 10 => 8,
+                _ => offset + header.len(),
+            };
+            let mut data = header.to_vec();
+            data.extend(build_ifd(
+                ifd_offset,
+                &[(0x0001, 3, 1, &[7, 0]), (0x0002, 2, 6, b"abcde\0")],
+            ));
+            data
+        });
+
+        let ms = MediaSource::seekable(std::io::Cursor::new(&tiff)).unwrap();
+        let iter: ExifIter = MediaParser::new().parse(ms).unwrap();
+        let entries = iter.maker_note_ifd().unwrap().collect::<Vec<_>>();
+        assert_eq!(entries.len(), 2);
+        assert!(entries.iter().all(|x| x.ifd_group() == IfdGroup::MakerNote));
+        assert!(entries.iter().all(|x| x.tag().is_none()));
+        assert_eq!(entries[0].tag_code(), 0x0001);
+        assert_eq!(entries[0].get_value(), Some(&EntryValue::U16(7)));
+        assert_eq!(entries[1].get_value(), Some(&"abcde".into()));
+
+        let raw = entries[1].raw_entry();
+        let start = raw.file_offset().unwrap() as usize;
+        assert_eq!(&tiff[start..start + 6], b"abcde\0");
+    }
+
+    #[test]
+    fn no_maker_note_ifd() {
+        let tiff = tiff_with_maker_note("vivo", |offset| build_ifd(offset, &[]));
+        assert!(parse_exif_bytes(&tiff).unwrap().maker_note_ifd().is_none());
+    }
+}
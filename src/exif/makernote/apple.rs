@@ -0,0 +1,136 @@
+use nom::number::Endianness;
+
+use super::MakerNoteEntries;
+
+const MAKER_NOTE_VERSION: u16 = 0x0001;
+const ACCELERATION_VECTOR: u16 = 0x0008;
+const HDR_IMAGE_TYPE: u16 = 0x000a;
+const BURST_UUID: u16 = 0x000b;
+const FOCUS_DISTANCE_RANGE: u16 = 0x000c;
+const CONTENT_IDENTIFIER: u16 = 0x0011;
+const IMAGE_CAPTURE_TYPE: u16 = 0x0014;
+const HDR_HEADROOM: u16 = 0x0021;
+const PHOTO_IDENTIFIER: u16 = 0x002b;
+const HDR_GAIN: u16 = 0x0030;
+
+/// Apple (iPhone & iPad) MakerNote, see [`MakerNote`](crate::MakerNote).
+///
+/// See: [Apple Tags](https://exiftool.org/TagNames/Apple.html).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AppleMakerNote {
+    pub version: Option<i32>,
+
+    /// Identifier shared by the image & the video of a Live Photo, which is
+    /// the same as [`TrackInfoTag::ContentIdentifier`](crate::TrackInfoTag)
+    /// of the video.
+    pub content_identifier: Option<String>,
+    /// Identifier shared by the images of a burst
+    pub burst_uuid: Option<String>,
+    pub photo_identifier: Option<String>,
+
+    /// Acceleration of the device in g, (x, y, z)
+    pub acceleration_vector: Option<[f64; 3]>,
+    /// (near, far) in meters
+    pub focus_distance_range: Option<(f64, f64)>,
+    /// 3: HDR image, 4: the original image of an HDR image
+    pub hdr_image_type: Option<i32>,
+    pub hdr_headroom: Option<f64>,
+    pub hdr_gain: Option<f64>,
+    /// 1: ProRAW, 2: Portrait, 10: Photo, 11: Manual focus, 12: Scene
+    pub image_capture_type: Option<i32>,
+}
+
+impl AppleMakerNote {
+    /// Decodes an Apple MakerNote at `offset` of `tiff`, which starts with
+    /// "Apple iOS\0", followed by a version & a byte order mark ("MM"), then
+    /// an IFD. Offsets are relative to the start of the MakerNote.
+    pub(crate) fn parse(tiff: &[u8], offset: usize) -> Option<AppleMakerNote> {
+        let data = tiff.get(offset..)?;
+        if !data.starts_with(b"Apple iOS\0") {
+            return None;
+        }
+        let endian = match data.get(12..14)? {
+            b"MM" => Endianness::Big,
+            b"II" => Endianness::Little,
+            _ => return None,
+        };
+        let entries = MakerNoteEntries::parse(data, 14, endian)?;
+        Some(AppleMakerNote::from_entries(&entries))
+    }
+
+    fn from_entries(entries: &MakerNoteEntries) -> AppleMakerNote {
+        let get_i32 = |code| entries.get(code).and_then(|x| x.as_i32());
+        let get_rational = |code| {
+            entries
+                .get(code)
+                .and_then(|x| x.as_irational())
+                .filter(|x| x.1 != 0)
+                .map(|x| x.as_float())
+        };
+        let get_rationals = |code| {
+            entries
+                .get(code)
+                .and_then(|x| x.as_irational_array())
+                .filter(|x| x.iter().all(|x| x.1 != 0))
+                .map(|x| x.iter().map(|x| x.as_float()).collect::<Vec<_>>())
+        };
+
+        AppleMakerNote {
+            version: get_i32(MAKER_NOTE_VERSION),
+            content_identifier: entries.get_str(CONTENT_IDENTIFIER),
+            burst_uuid: entries.get_str(BURST_UUID),
+            photo_identifier: entries.get_str(PHOTO_IDENTIFIER),
+            acceleration_vector: get_rationals(ACCELERATION_VECTOR).and_then(|x| x.try_into().ok()),
+            focus_distance_range: get_rationals(FOCUS_DISTANCE_RANGE).and_then(|x| match x[..] {
+                [a, b] => Some((a, b)),
+                _ => None,
+            }),
+            hdr_image_type: get_i32(HDR_IMAGE_TYPE),
+            hdr_headroom: get_rational(HDR_HEADROOM),
+            hdr_gain: get_rational(HDR_GAIN),
+            image_capture_type: get_i32(IMAGE_CAPTURE_TYPE),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testkit::open_sample, Exif, ExifIter, MediaParser, MediaSource};
+
+    #[test]
+    fn apple_maker_note() {
+        let ms = MediaSource::file(open_sample("exif.heic").unwrap()).unwrap();
+        let iter: ExifIter = MediaParser::new().parse(ms).unwrap();
+        let exif: Exif = iter.into();
+        let apple = exif.get_maker_note().unwrap().as_apple().unwrap();
+
+        assert_eq!(apple.version, Some(14));
+        assert_eq!(
+            apple.content_identifier.as_deref(),
+            Some("242684F6-0839-44E0-8E39-A8FE43182C29")
+        );
+        assert_eq!(
+            apple.photo_identifier.as_deref(),
+            Some("84B48415-AE86-47B4-A3C2-7AE89FC9B264")
+        );
+        assert_eq!(apple.image_capture_type, Some(10));
+        assert_eq!(apple.hdr_image_type, None);
+
+        let round = |x: f64| (x * 1000.0).round() / 1000.0;
+        let [x, y, z] = apple.acceleration_vector.unwrap();
+        assert_eq!((round(x), round(y), round(z)), (0.02, -0.973, 0.03));
+        let (near, far) = apple.focus_distance_range.unwrap();
+        assert_eq!((round(near), round(far)), (4.5, 3.152));
+        assert_eq!(round(apple.hdr_headroom.unwrap()), 1.188);
+    }
+
+    #[test]
+    fn invalid_header() {
+        let mut data = b"Apple iOS\0\0\x01XX".to_vec();
+        data.extend([0; 6]);
+        assert!(AppleMakerNote::parse(&data, 0).is_none());
+        data[12..14].copy_from_slice(b"MM");
+        assert_eq!(AppleMakerNote::parse(&data, 0), Some(Default::default()));
+    }
+}
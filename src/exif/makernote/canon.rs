@@ -0,0 +1,399 @@
+use super::MakerNoteEntries;
+
+const CAMERA_SETTINGS: u16 = 0x0001;
+const SHOT_INFO: u16 = 0x0004;
+const IMAGE_TYPE: u16 = 0x0006;
+const FIRMWARE_VERSION: u16 = 0x0007;
+const OWNER_NAME: u16 = 0x0009;
+const SERIAL_NUMBER: u16 = 0x000c;
+const MODEL_ID: u16 = 0x0010;
+const LENS_MODEL: u16 = 0x0095;
+
+/// Canon MakerNote, see [`MakerNote`](crate::MakerNote).
+///
+/// Only the commonly used tags & fields are decoded, see:
+/// [Canon Tags](https://exiftool.org/TagNames/Canon.html).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CanonMakerNote {
+    pub camera_settings: Option<CanonCameraSettings>,
+    pub shot_info: Option<CanonShotInfo>,
+
+    /// e.g. "Canon EOS 5D Mark IV"
+    pub image_type: Option<String>,
+    /// e.g. "Firmware Version 1.0.2"
+    pub firmware_version: Option<String>,
+    pub owner_name: Option<String>,
+    pub serial_number: Option<u32>,
+    pub model_id: Option<u32>,
+    /// Lens name recorded by newer models, e.g. "EF24-105mm f/4L IS USM"
+    pub lens_model: Option<String>,
+}
+
+/// Fields of the Canon `CameraSettings` table, raw enumerated values are kept
+/// as is unless noted.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CanonCameraSettings {
+    /// 1: Macro, 2: Normal
+    pub macro_mode: Option<i16>,
+    /// Self timer in seconds, 0 means off
+    pub self_timer: Option<f64>,
+    /// 1: Economy, 2: Normal, 3: Fine, 4: RAW, 5: Superfine, 7: CRAW, ...
+    pub quality: Option<i16>,
+    /// 0: Off, 1: Auto, 2: On, 3: Red-eye reduction, ...
+    pub flash_mode: Option<i16>,
+    /// 0: Single, 1: Continuous, ...
+    pub continuous_drive: Option<i16>,
+    /// 0: One-shot AF, 1: AI Servo AF, 2: AI Focus AF, 3: Manual Focus, ...
+    pub focus_mode: Option<i16>,
+    /// 0: Default, 1: Spot, 2: Average, 3: Evaluative, 4: Partial,
+    /// 5: Center-weighted average
+    pub metering_mode: Option<i16>,
+    pub exposure_mode: Option<CanonExposureMode>,
+
+    /// Canon lens ID, see [`CanonMakerNote::lens_name`]
+    pub lens_type: Option<u16>,
+    /// In mm
+    pub max_focal_length: Option<f64>,
+    /// In mm
+    pub min_focal_length: Option<f64>,
+    /// Max aperture of the lens at the current focal length, as an f-number
+    pub max_aperture: Option<f64>,
+    /// Min aperture of the lens at the current focal length, as an f-number
+    pub min_aperture: Option<f64>,
+
+    pub image_stabilization: Option<ImageStabilization>,
+}
+
+/// Fields of the Canon `ShotInfo` table.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CanonShotInfo {
+    /// ISO speed without the auto ISO adjustment
+    pub base_iso: Option<f64>,
+    /// Auto ISO adjustment in percent
+    pub auto_iso: Option<f64>,
+    /// In EV
+    pub exposure_compensation: Option<f64>,
+    pub f_number: Option<f64>,
+    /// In seconds
+    pub exposure_time: Option<f64>,
+    pub target_aperture: Option<f64>,
+    /// In seconds
+    pub target_exposure_time: Option<f64>,
+    /// 0: Auto, 1: Daylight, 2: Cloudy, 3: Tungsten, 4: Fluorescent, 5: Flash,
+    /// 6: Custom, ...
+    pub white_balance: Option<i16>,
+    /// Index of the image in a burst sequence
+    pub sequence_number: Option<i16>,
+    /// In degrees Celsius, only recorded by some models
+    pub camera_temperature: Option<i16>,
+    pub flash_guide_number: Option<f64>,
+    /// In meters, may be infinity
+    pub focus_distance_upper: Option<f64>,
+    /// In meters, may be infinity
+    pub focus_distance_lower: Option<f64>,
+}
+
+/// Canon exposure mode, i.e. the mode dial position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CanonExposureMode {
+    /// Easy shooting modes (e.g. full auto, portrait, landscape)
+    Easy,
+    ProgramAE,
+    ShutterSpeedPriorityAE,
+    AperturePriorityAE,
+    Manual,
+    DepthOfFieldAE,
+    /// Manual depth of field
+    MDep,
+    Bulb,
+    FlexiblePriorityAE,
+    Unknown(i16),
+}
+
+/// Image stabilization mode of the lens or camera body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageStabilization {
+    Off,
+    On,
+    /// Stabilize only while the shutter is released
+    ShootOnly,
+    Panning,
+    /// Enhanced stabilization for movies
+    Dynamic,
+    Unknown(i16),
+}
+
+impl From<i16> for CanonExposureMode {
+    fn from(v: i16) -> Self {
+        match v {
+            0 => Self::Easy,
+            1 => Self::ProgramAE,
+            2 => Self::ShutterSpeedPriorityAE,
+            3 => Self::AperturePriorityAE,
+            4 => Self::Manual,
+            5 => Self::DepthOfFieldAE,
+            6 => Self::MDep,
+            7 => Self::Bulb,
+            8 => Self::FlexiblePriorityAE,
+            x => Self::Unknown(x),
+        }
+    }
+}
+
+impl From<i16> for ImageStabilization {
+    fn from(v: i16) -> Self {
+        // 0x100 is set by some models, e.g. "On (2)", the meaning is unknown
+        match if v >= 0 { v & 0xff } else { v } {
+            0 => Self::Off,
+            1 => Self::On,
+            2 => Self::ShootOnly,
+            3 => Self::Panning,
+            4 => Self::Dynamic,
+            _ => Self::Unknown(v),
+        }
+    }
+}
+
+impl CanonMakerNote {
+    pub(crate) fn from_entries(entries: &MakerNoteEntries) -> CanonMakerNote {
+        CanonMakerNote {
+            camera_settings: entries
+                .get_u16_array(CAMERA_SETTINGS)
+                .map(CanonCameraSettings::parse),
+            shot_info: entries.get_u16_array(SHOT_INFO).map(CanonShotInfo::parse),
+            image_type: entries.get_str(IMAGE_TYPE),
+            firmware_version: entries.get_str(FIRMWARE_VERSION),
+            owner_name: entries.get_str(OWNER_NAME),
+            serial_number: entries.get_u32(SERIAL_NUMBER),
+            model_id: entries.get_u32(MODEL_ID),
+            lens_model: entries.get_str(LENS_MODEL),
+        }
+    }
+
+    /// Returns the lens name, i.e. the `LensModel` tag, or the name of the
+    /// `LensType` (looked up in a table of common lenses) for older models.
+    pub fn lens_name(&self) -> Option<&str> {
+        self.lens_model.as_deref().or_else(|| {
+            let lens_type = self.camera_settings.as_ref()?.lens_type?;
+            canon_lens_name(lens_type)
+        })
+    }
+}
+
+impl CanonCameraSettings {
+    /// `values` is an array of int16s, starts with its size in bytes.
+    fn parse(values: &[u16]) -> CanonCameraSettings {
+        let get = |i: usize| values.get(i).map(|x| *x as i16);
+        // Focal lengths are in focal units per mm
+        let focal_units = get(25).filter(|x| *x > 0).unwrap_or(1) as f64;
+        let focal_length = |i| get(i).filter(|x| *x > 0).map(|x| x as f64 / focal_units);
+        let aperture = |i| get(i).filter(|x| *x > 0).map(aperture_value);
+
+        CanonCameraSettings {
+            macro_mode: get(1),
+            self_timer: get(2)
+                .filter(|x| *x >= 0)
+                .map(|x| (x & 0xfff) as f64 / 10.0),
+            quality: get(3),
+            flash_mode: get(4),
+            continuous_drive: get(5),
+            focus_mode: get(7),
+            metering_mode: get(17),
+            exposure_mode: get(20).map(CanonExposureMode::from),
+            lens_type: values.get(22).copied().filter(|x| *x != 0 && *x != 0xffff),
+            max_focal_length: focal_length(23),
+            min_focal_length: focal_length(24),
+            max_aperture: aperture(26),
+            min_aperture: aperture(27),
+            image_stabilization: get(34).filter(|x| *x != -1).map(ImageStabilization::from),
+        }
+    }
+}
+
+impl CanonShotInfo {
+    /// `values` is an array of int16s, starts with its size in bytes.
+    fn parse(values: &[u16]) -> CanonShotInfo {
+        let get = |i: usize| values.get(i).map(|x| *x as i16);
+        let non_zero = |i| get(i).filter(|x| *x != 0);
+        let distance = |i| {
+            values.get(i).filter(|x| **x != 0).map(|x| match x {
+                0xffff => f64::INFINITY,
+                x => *x as f64 / 100.0,
+            })
+        };
+
+        CanonShotInfo {
+            base_iso: non_zero(2).map(|x| (x as f64 / 32.0).exp2() * 100.0 / 32.0),
+            auto_iso: non_zero(1).map(|x| (x as f64 / 32.0).exp2() * 100.0),
+            exposure_compensation: get(6).map(canon_ev),
+            f_number: non_zero(21).map(aperture_value),
+            exposure_time: non_zero(22).map(exposure_time_value),
+            target_aperture: non_zero(4).map(aperture_value),
+            target_exposure_time: get(5).filter(|x| *x > -1000).map(exposure_time_value),
+            white_balance: get(7),
+            sequence_number: get(9),
+            camera_temperature: non_zero(12).map(|x| x - 128),
+            flash_guide_number: get(13).filter(|x| *x != -1).map(|x| x as f64 / 32.0),
+            focus_distance_upper: distance(19),
+            focus_distance_lower: distance(20),
+        }
+    }
+}
+
+/// Converts a Canon EV value, which is in 1/32 EV, while the fractions 0x0c
+/// & 0x14 mean 1/3 & 2/3 EV.
+fn canon_ev(v: i16) -> f64 {
+    let sign = if v < 0 { -1.0 } else { 1.0 };
+    let v = (v as i32).abs();
+    let frac = match v & 0x1f {
+        0x0c => 32.0 / 3.0,
+        0x14 => 64.0 / 3.0,
+        x => x as f64,
+    };
+    sign * ((v & !0x1f) as f64 + frac) / 32.0
+}
+
+/// APEX aperture value -> f-number
+fn aperture_value(v: i16) -> f64 {
+    (canon_ev(v) / 2.0).exp2()
+}
+
+/// APEX time value -> exposure time in seconds
+fn exposure_time_value(v: i16) -> f64 {
+    (-canon_ev(v)).exp2()
+}
+
+/// Names of common Canon lenses by `LensType`. Some IDs are shared by third
+/// party lenses, only the Canon one is returned.
+fn canon_lens_name(lens_type: u16) -> Option<&'static str> {
+    let name = match lens_type {
+        1 => "Canon EF 50mm f/1.8",
+        2 => "Canon EF 28mm f/2.8",
+        3 => "Canon EF 135mm f/2.8 Soft",
+        4 => "Canon EF 35-105mm f/3.5-4.5",
+        5 => "Canon EF 35-70mm f/3.5-4.5",
+        6 => "Canon EF 28-70mm f/3.5-4.5",
+        7 => "Canon EF 100-300mm f/5.6L",
+        8 => "Canon EF 100-300mm f/5.6",
+        9 => "Canon EF 70-210mm f/4",
+        10 => "Canon EF 50mm f/2.5 Macro",
+        11 => "Canon EF 35mm f/2",
+        13 => "Canon EF 15mm f/2.8 Fisheye",
+        124 => "Canon MP-E 65mm f/2.8 1-5x Macro Photo",
+        125 => "Canon TS-E 24mm f/3.5L",
+        4142 => "Canon EF-S 18-135mm f/3.5-5.6 IS STM",
+        4154 => "Canon EF-S 24mm f/2.8 STM",
+        4156 => "Canon EF 50mm f/1.8 STM",
+        _ => return None,
+    };
+    Some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        exif::makernote::tests::{build_ifd, tiff_with_maker_note},
+        parse_exif_bytes, MakerNote,
+    };
+    use test_case::test_case;
+
+    #[test_case(0, 0.0)]
+    #[test_case(0x20, 1.0)]
+    #[test_case(0x0c, 1.0 / 3.0)]
+    #[test_case(0x34, 1.0 + 2.0 / 3.0)]
+    #[test_case(-0x2c, -(1.0 + 1.0 / 3.0))]
+    #[test_case(0x10, 0.5)]
+    fn ev(v: i16, expect: f64) {
+        assert!((canon_ev(v) - expect).abs() < 1e-9);
+    }
+
+    #[test_case(0, ImageStabilization::Off)]
+    #[test_case(257, ImageStabilization::On)]
+    #[test_case(3, ImageStabilization::Panning)]
+    #[test_case(9, ImageStabilization::Unknown(9))]
+    fn image_stabilization(v: i16, expect: ImageStabilization) {
+        assert_eq!(ImageStabilization::from(v), expect);
+    }
+
+    fn table(len: usize, values: &[(usize, u16)]) -> Vec<u8> {
+        let mut table = vec![0_u16; len];
+        table[0] = len as u16 * 2;
+        values.iter().for_each(|(i, v)| table[*i] = *v);
+        table.iter().flat_map(|x| x.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn canon_maker_note() {
+        let camera_settings = table(
+            47,
+            &[
+                (1, 2),
+                (2, 100),
+                (7, 1),
+                (20, 3),
+                (22, 4156),
+                (23, 50),
+                (24, 50),
+                (25, 1),
+                (26, 64),
+                (27, 320),
+                (34, 257),
+            ],
+        );
+        let shot_info = table(
+            34,
+            &[
+                (2, 160),
+                (6, 0x0c),
+                (12, 153),
+                (19, 250),
+                (20, 0xffff),
+                (21, 0x40),
+                (22, 224),
+            ],
+        );
+        let tiff = tiff_with_maker_note("Canon", |offset| {
+            build_ifd(
+                offset,
+                &[
+                    (CAMERA_SETTINGS, 3, 47, &camera_settings),
+                    (SHOT_INFO, 3, 34, &shot_info),
+                    (IMAGE_TYPE, 2, 16, b"Canon EOS 80D\0\0\0"),
+                    (MODEL_ID, 4, 1, &0x80000350_u32.to_le_bytes()),
+                ],
+            )
+        });
+        let iter = parse_exif_bytes(&tiff).unwrap();
+        let Some(MakerNote::Canon(canon)) = iter.parse_maker_note() else {
+            panic!("Canon MakerNote not found");
+        };
+
+        assert_eq!(canon.image_type.as_deref(), Some("Canon EOS 80D"));
+        assert_eq!(canon.model_id, Some(0x80000350));
+        assert_eq!(canon.lens_name(), Some("Canon EF 50mm f/1.8 STM"));
+
+        let settings = canon.camera_settings.as_ref().unwrap();
+        assert_eq!(settings.macro_mode, Some(2));
+        assert_eq!(settings.self_timer, Some(10.0));
+        assert_eq!(settings.focus_mode, Some(1));
+        assert_eq!(
+            settings.exposure_mode,
+            Some(CanonExposureMode::AperturePriorityAE)
+        );
+        assert_eq!(settings.max_focal_length, Some(50.0));
+        assert_eq!(settings.max_aperture, Some(2.0));
+        assert_eq!(settings.min_aperture, Some(32.0));
+        assert_eq!(settings.image_stabilization, Some(ImageStabilization::On));
+
+        let shot = canon.shot_info.as_ref().unwrap();
+        assert_eq!(shot.base_iso, Some(100.0));
+        assert!((shot.exposure_compensation.unwrap() - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(shot.camera_temperature, Some(25));
+        assert_eq!(shot.focus_distance_upper, Some(2.5));
+        assert_eq!(shot.focus_distance_lower, Some(f64::INFINITY));
+        assert_eq!(shot.f_number, Some(2.0));
+        assert_eq!(shot.exposure_time, Some(1.0 / 128.0));
+        assert_eq!(shot.target_aperture, None);
+    }
+}
@@ -0,0 +1,213 @@
+use nom::number::Endianness;
+
+use super::MakerNoteEntries;
+
+const VERSION: u16 = 0x0000;
+const SERIAL_NUMBER: u16 = 0x0010;
+const SATURATION: u16 = 0x1003;
+const FILM_MODE: u16 = 0x1401;
+const MIN_FOCAL_LENGTH: u16 = 0x1404;
+const MAX_FOCAL_LENGTH: u16 = 0x1405;
+const IMAGE_COUNT: u16 = 0x1438;
+
+/// Fujifilm MakerNote, see [`MakerNote`](crate::MakerNote).
+///
+/// See: [FujiFilm Tags](https://exiftool.org/TagNames/FujiFilm.html).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FujifilmMakerNote {
+    /// e.g. "0130"
+    pub version: Option<String>,
+    /// Internal serial number of the camera
+    pub serial_number: Option<String>,
+    pub film_simulation: Option<FilmSimulation>,
+    /// In mm
+    pub min_focal_length: Option<f64>,
+    /// In mm
+    pub max_focal_length: Option<f64>,
+    /// Number of images taken by the camera, wraps at 32768
+    pub image_count: Option<u16>,
+}
+
+/// Fujifilm film simulation mode, decided by the `FilmMode` tag, or the
+/// `Saturation` tag for monochrome simulations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilmSimulation {
+    /// F0/Standard
+    Provia,
+    /// F1/Studio Portrait
+    StudioPortrait,
+    /// F1a/Studio Portrait Enhanced Saturation
+    StudioPortraitEnhancedSaturation,
+    /// F1b/Studio Portrait Smooth Skin Tone
+    Astia,
+    /// F1c/Studio Portrait Increased Sharpness
+    StudioPortraitIncreasedSharpness,
+    /// F2/Fujichrome or F4/Velvia
+    Velvia,
+    /// F3/Studio Portrait Ex
+    StudioPortraitEx,
+    ProNegStd,
+    ProNegHi,
+    ClassicChrome,
+    Eterna,
+    ClassicNegative,
+    /// Eterna Bleach Bypass
+    BleachBypass,
+    NostalgicNeg,
+    RealaAce,
+    Monochrome(FilterColor),
+    Acros(FilterColor),
+    Sepia,
+    /// Raw `FilmMode` value
+    Unknown(u16),
+}
+
+/// Color filter of monochrome film simulations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterColor {
+    None,
+    Red,
+    Yellow,
+    Green,
+}
+
+impl FujifilmMakerNote {
+    /// Decodes a Fujifilm MakerNote at `offset` of `tiff`, which starts with
+    /// "FUJIFILM" & the offset of the IFD. It's always little endian, and
+    /// offsets are relative to the start of the MakerNote.
+    pub(crate) fn parse(tiff: &[u8], offset: usize) -> Option<FujifilmMakerNote> {
+        let data = tiff.get(offset..)?;
+        if !data.starts_with(b"FUJIFILM") {
+            return None;
+        }
+        let ifd_offset = u32::from_le_bytes(data.get(8..12)?.try_into().ok()?);
+        let entries = MakerNoteEntries::parse(data, ifd_offset as usize, Endianness::Little)?;
+        Some(FujifilmMakerNote::from_entries(&entries))
+    }
+
+    fn from_entries(entries: &MakerNoteEntries) -> FujifilmMakerNote {
+        let get_u16 = |code| entries.get(code).and_then(|x| x.as_u16());
+        let get_rational = |code| {
+            entries
+                .get(code)
+                .and_then(|x| x.as_urational())
+                .filter(|x| x.1 != 0)
+                .map(|x| x.as_float())
+        };
+        let film_simulation = get_u16(SATURATION)
+            .and_then(FilmSimulation::from_saturation)
+            .or_else(|| get_u16(FILM_MODE).map(FilmSimulation::from));
+
+        FujifilmMakerNote {
+            version: entries
+                .get_bytes(VERSION)
+                .map(|x| String::from_utf8_lossy(x).into_owned()),
+            serial_number: entries.get_str(SERIAL_NUMBER),
+            film_simulation,
+            min_focal_length: get_rational(MIN_FOCAL_LENGTH),
+            max_focal_length: get_rational(MAX_FOCAL_LENGTH),
+            image_count: get_u16(IMAGE_COUNT).map(|x| x & 0x7fff),
+        }
+    }
+}
+
+impl FilmSimulation {
+    /// Monochrome film simulations are recorded in `Saturation`, returns
+    /// `None` for other saturation values.
+    fn from_saturation(v: u16) -> Option<FilmSimulation> {
+        let filter = |x: u16| match x & 0x0f {
+            1 => FilterColor::Red,
+            2 => FilterColor::Yellow,
+            3 => FilterColor::Green,
+            _ => FilterColor::None,
+        };
+        let sim = match v {
+            0x300..=0x303 => Self::Monochrome(filter(v)),
+            0x310 => Self::Sepia,
+            0x500..=0x503 => Self::Acros(filter(v)),
+            _ => return None,
+        };
+        Some(sim)
+    }
+}
+
+impl From<u16> for FilmSimulation {
+    fn from(v: u16) -> Self {
+        match v {
+            0x000 => Self::Provia,
+            0x100 => Self::StudioPortrait,
+            0x110 => Self::StudioPortraitEnhancedSaturation,
+            0x120 => Self::Astia,
+            0x130 => Self::StudioPortraitIncreasedSharpness,
+            0x200 | 0x400 => Self::Velvia,
+            0x300 => Self::StudioPortraitEx,
+            0x500 => Self::ProNegStd,
+            0x501 => Self::ProNegHi,
+            0x600 => Self::ClassicChrome,
+            0x700 => Self::Eterna,
+            0x800 => Self::ClassicNegative,
+            0x900 => Self::BleachBypass,
+            0xa00 => Self::NostalgicNeg,
+            0xb00 => Self::RealaAce,
+            x => Self::Unknown(x),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        exif::makernote::tests::{build_ifd, tiff_with_maker_note},
+        parse_exif_bytes, MakerNote,
+    };
+    use test_case::test_case;
+
+    #[test_case(None, Some(0x600), Some(FilmSimulation::ClassicChrome))]
+    #[test_case(Some(0x0), Some(0x400), Some(FilmSimulation::Velvia))]
+    #[test_case(
+        Some(0x502),
+        Some(0x0),
+        Some(FilmSimulation::Acros(FilterColor::Yellow))
+    )]
+    #[test_case(Some(0x300), None, Some(FilmSimulation::Monochrome(FilterColor::None)))]
+    #[test_case(Some(0x80), Some(0xf00), Some(FilmSimulation::Unknown(0xf00)))]
+    #[test_case(None, None, None)]
+    fn film_simulation(
+        saturation: Option<u16>,
+        film_mode: Option<u16>,
+        expect: Option<FilmSimulation>,
+    ) {
+        let saturation = saturation.map(|x| x.to_le_bytes());
+        let film_mode = film_mode.map(|x| x.to_le_bytes());
+        let min_focal_length = [18, 0, 0, 0, 1, 0, 0, 0];
+        let mut entries = vec![
+            (VERSION, 7, 4, &b"0130"[..]),
+            (SERIAL_NUMBER, 2, 8, b"FF02B12\0"),
+            (MIN_FOCAL_LENGTH, 5, 1, &min_focal_length),
+            (IMAGE_COUNT, 3, 1, &[0x39, 0x80]),
+        ];
+        if let Some(x) = saturation.as_ref() {
+            entries.push((SATURATION, 3, 1, x));
+        }
+        if let Some(x) = film_mode.as_ref() {
+            entries.push((FILM_MODE, 3, 1, x));
+        }
+        let tiff = tiff_with_maker_note("FUJIFILM", |_| {
+            let mut data = b"FUJIFILM\x0c\0\0\0".to_vec();
+            data.extend(build_ifd(12, &entries));
+            data
+        });
+
+        let iter = parse_exif_bytes(&tiff).unwrap();
+        let Some(MakerNote::Fujifilm(fuji)) = iter.parse_maker_note() else {
+            panic!("Fujifilm MakerNote not found");
+        };
+        assert_eq!(fuji.version.as_deref(), Some("0130"));
+        assert_eq!(fuji.serial_number.as_deref(), Some("FF02B12"));
+        assert_eq!(fuji.min_focal_length, Some(18.0));
+        assert_eq!(fuji.max_focal_length, None);
+        assert_eq!(fuji.image_count, Some(0x39));
+        assert_eq!(fuji.film_simulation, expect);
+    }
+}
@@ -0,0 +1,310 @@
+use nom::number::Endianness;
+
+use crate::exif::TiffHeader;
+
+use super::MakerNoteEntries;
+
+const MAKER_NOTE_VERSION: u16 = 0x0001;
+const SERIAL_NUMBER: u16 = 0x001d;
+const LENS_TYPE: u16 = 0x0083;
+const SHOT_INFO: u16 = 0x0091;
+const LENS_DATA: u16 = 0x0098;
+const SHUTTER_COUNT: u16 = 0x00a7;
+
+/// Nikon MakerNote, see [`MakerNote`](crate::MakerNote).
+///
+/// The `ShotInfo` & `LensData` tables of newer models are encrypted, they
+/// are decrypted with the serial number & the shutter count. See: [Nikon
+/// Tags](https://exiftool.org/TagNames/Nikon.html).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NikonMakerNote {
+    /// e.g. "0210"
+    pub version: Option<String>,
+    pub serial_number: Option<String>,
+    /// Number of shutter actuations of the camera body
+    pub shutter_count: Option<u32>,
+    /// Bit flags, 0x01: MF, 0x02: D, 0x04: G, 0x08: VR, ...
+    pub lens_type: Option<u8>,
+    pub lens_data: Option<NikonLensData>,
+
+    /// e.g. "0210"
+    pub shot_info_version: Option<String>,
+    /// The decrypted `ShotInfo` data (including the version), whose layout
+    /// differs by models.
+    pub shot_info: Option<Vec<u8>>,
+}
+
+/// Fields of the Nikon `LensData` table.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NikonLensData {
+    /// e.g. "0204"
+    pub version: String,
+    pub lens_id_number: Option<u8>,
+    pub lens_f_stops: Option<f64>,
+    /// In mm
+    pub min_focal_length: Option<f64>,
+    /// In mm
+    pub max_focal_length: Option<f64>,
+    /// As an f-number
+    pub max_aperture_at_min_focal: Option<f64>,
+    /// As an f-number
+    pub max_aperture_at_max_focal: Option<f64>,
+    pub mcu_version: Option<u8>,
+
+    /// In mm
+    pub focal_length: Option<f64>,
+    /// In meters
+    pub focus_distance: Option<f64>,
+    /// As an f-number
+    pub effective_max_aperture: Option<f64>,
+
+    /// The raw bytes from `LensIDNumber` to `MCUVersion`, see
+    /// [`NikonMakerNote::lens_id`].
+    lens_id_bytes: Option<[u8; 7]>,
+}
+
+impl NikonMakerNote {
+    /// Decodes a Nikon MakerNote at `offset` of `tiff`, which may be:
+    ///
+    /// - "Nikon\0\x02..." followed by a TIFF header, offsets are relative to
+    ///   the embedded TIFF header (most models)
+    /// - "Nikon\0\x01\0" followed by an IFD (early Coolpix models)
+    /// - a plain IFD (e.g. D1, E990)
+    pub(crate) fn parse(tiff: &[u8], offset: usize, endian: Endianness) -> Option<NikonMakerNote> {
+        let data = tiff.get(offset..)?;
+        let entries = if data.starts_with(b"Nikon\0\x02") {
+            let tiff = data.get(10..)?;
+            let (_, header) = TiffHeader::parse(tiff).ok()?;
+            MakerNoteEntries::parse(tiff, header.ifd0_offset as usize, header.endian)?
+        } else if data.starts_with(b"Nikon\0\x01") {
+            MakerNoteEntries::parse(tiff, offset + 8, endian)?
+        } else {
+            MakerNoteEntries::parse(tiff, offset, endian)?
+        };
+        Some(NikonMakerNote::from_entries(&entries))
+    }
+
+    fn from_entries(entries: &MakerNoteEntries) -> NikonMakerNote {
+        let serial_number = entries.get_str(SERIAL_NUMBER);
+        let shutter_count = entries.get_u32(SHUTTER_COUNT);
+        // Non-numeric serial numbers are keyed as 0x60, like most models do
+        let key = (
+            serial_number
+                .as_deref()
+                .and_then(|x| x.parse::<u32>().ok())
+                .unwrap_or(0x60),
+            shutter_count.unwrap_or_default(),
+        );
+        let decrypted = |code, encrypted: fn(&str) -> bool| {
+            let mut data = entries.get_bytes(code)?.to_vec();
+            let version = String::from_utf8_lossy(data.get(..4)?).into_owned();
+            if encrypted(&version) {
+                decrypt(&mut data[4..], key.0, key.1);
+            }
+            Some((version, data))
+        };
+
+        let shot_info = decrypted(SHOT_INFO, |v| v >= "0200");
+        let (shot_info_version, shot_info) = shot_info.unzip();
+        NikonMakerNote {
+            version: entries
+                .get_bytes(MAKER_NOTE_VERSION)
+                .map(|x| String::from_utf8_lossy(x).trim_end_matches('\0').to_owned()),
+            serial_number,
+            shutter_count,
+            lens_type: entries.get(LENS_TYPE).and_then(|x| x.as_u8()),
+            lens_data: decrypted(LENS_DATA, |v| v.starts_with("02"))
+                .and_then(|(version, data)| NikonLensData::parse(version, &data)),
+            shot_info_version,
+            shot_info,
+        }
+    }
+
+    /// Returns the composite lens ID used to identify Nikon lenses, i.e. the
+    /// hex values of `LensIDNumber`, `LensFStops`, `MinFocalLength`,
+    /// `MaxFocalLength`, `MaxApertureAtMinFocal`, `MaxApertureAtMaxFocal`,
+    /// `MCUVersion` & `LensType`, e.g. "8A 3C 37 37 30 30 24 06".
+    pub fn lens_id(&self) -> Option<String> {
+        let bytes = self.lens_data.as_ref()?.lens_id_bytes?;
+        let id = bytes
+            .iter()
+            .chain(std::iter::once(&self.lens_type?))
+            .map(|x| format!("{x:02X}"))
+            .collect::<Vec<_>>();
+        Some(id.join(" "))
+    }
+}
+
+impl NikonLensData {
+    /// `data` is the decrypted data, starts with the version.
+    fn parse(version: String, data: &[u8]) -> Option<NikonLensData> {
+        // (FocusDistance, FocalLength, LensIDNumber, EffectiveMaxAperture)
+        let (focus_distance, focal_length, lens_id, max_aperture) = match version.as_str() {
+            "0100" => (None, None, 6, None),
+            "0101" | "0201" | "0202" | "0203" => (Some(9), Some(10), 11, Some(18)),
+            "0204" => (Some(10), Some(11), 12, Some(19)),
+            _ => {
+                tracing::debug!(version, "unsupported Nikon LensData version");
+                return None;
+            }
+        };
+        let get = |i: Option<usize>| data.get(i?).copied().filter(|x| *x != 0);
+        let focal_length_value = |x: u8| 5.0 * (x as f64 / 24.0).exp2();
+        let aperture_value = |x: u8| (x as f64 / 24.0).exp2();
+        let lens_id_bytes: Option<[u8; 7]> = data
+            .get(lens_id..lens_id + 7)
+            .and_then(|x| x.try_into().ok());
+        let id = |i: usize| lens_id_bytes.map(|x| x[i]).filter(|x| *x != 0);
+
+        Some(NikonLensData {
+            lens_id_number: lens_id_bytes.map(|x| x[0]),
+            lens_f_stops: id(1).map(|x| x as f64 / 12.0),
+            min_focal_length: id(2).map(focal_length_value),
+            max_focal_length: id(3).map(focal_length_value),
+            max_aperture_at_min_focal: id(4).map(aperture_value),
+            max_aperture_at_max_focal: id(5).map(aperture_value),
+            mcu_version: lens_id_bytes.map(|x| x[6]),
+            focal_length: get(focal_length).map(focal_length_value),
+            focus_distance: get(focus_distance).map(|x| 0.01 * 10_f64.powf(x as f64 / 40.0)),
+            effective_max_aperture: get(max_aperture).map(aperture_value),
+            lens_id_bytes,
+            version,
+        })
+    }
+}
+
+/// Decrypts (or encrypts, since it's a XOR cipher) Nikon encrypted data.
+fn decrypt(data: &mut [u8], serial: u32, shutter_count: u32) {
+    let key = shutter_count.to_le_bytes().iter().fold(0, |k, x| k ^ x);
+    let ci = XLAT[0][(serial & 0xff) as usize];
+    let mut cj = XLAT[1][key as usize];
+    let mut ck = 0x60_u8;
+    for x in data.iter_mut() {
+        cj = cj.wrapping_add(ci.wrapping_mul(ck));
+        ck = ck.wrapping_add(1);
+        *x ^= cj;
+    }
+}
+
+#[rustfmt::skip]
+const XLAT: [[u8; 256]; 2] = [
+    [
+        0xc1, 0xbf, 0x6d, 0x0d, 0x59, 0xc5, 0x13, 0x9d, 0x83, 0x61, 0x6b, 0x4f, 0xc7, 0x7f, 0x3d, 0x3d,
+        0x53, 0x59, 0xe3, 0xc7, 0xe9, 0x2f, 0x95, 0xa7, 0x95, 0x1f, 0xdf, 0x7f, 0x2b, 0x29, 0xc7, 0x0d,
+        0xdf, 0x07, 0xef, 0x71, 0x89, 0x3d, 0x13, 0x3d, 0x3b, 0x13, 0xfb, 0x0d, 0x89, 0xc1, 0x65, 0x1f,
+        0xb3, 0x0d, 0x6b, 0x29, 0xe3, 0xfb, 0xef, 0xa3, 0x6b, 0x47, 0x7f, 0x95, 0x35, 0xa7, 0x47, 0x4f,
+        0xc7, 0xf1, 0x59, 0x95, 0x35, 0x11, 0x29, 0x61, 0xf1, 0x3d, 0xb3, 0x2b, 0x0d, 0x43, 0x89, 0xc1,
+        0x9d, 0x9d, 0x89, 0x65, 0xf1, 0xe9, 0xdf, 0xbf, 0x3d, 0x7f, 0x53, 0x97, 0xe5, 0xe9, 0x95, 0x17,
+        0x1d, 0x3d, 0x8b, 0xfb, 0xc7, 0xe3, 0x67, 0xa7, 0x07, 0xf1, 0x71, 0xa7, 0x53, 0xb5, 0x29, 0x89,
+        0xe5, 0x2b, 0xa7, 0x17, 0x29, 0xe9, 0x4f, 0xc5, 0x65, 0x6d, 0x6b, 0xef, 0x0d, 0x89, 0x49, 0x2f,
+        0xb3, 0x43, 0x53, 0x65, 0x1d, 0x49, 0xa3, 0x13, 0x89, 0x59, 0xef, 0x6b, 0xef, 0x65, 0x1d, 0x0b,
+        0x59, 0x13, 0xe3, 0x4f, 0x9d, 0xb3, 0x29, 0x43, 0x2b, 0x07, 0x1d, 0x95, 0x59, 0x59, 0x47, 0xfb,
+        0xe5, 0xe9, 0x61, 0x47, 0x2f, 0x35, 0x7f, 0x17, 0x7f, 0xef, 0x7f, 0x95, 0x95, 0x71, 0xd3, 0xa3,
+        0x0b, 0x71, 0xa3, 0xad, 0x0b, 0x3b, 0xb5, 0xfb, 0xa3, 0xbf, 0x4f, 0x83, 0x1d, 0xad, 0xe9, 0x2f,
+        0x71, 0x65, 0xa3, 0xe5, 0x07, 0x35, 0x3d, 0x0d, 0xb5, 0xe9, 0xe5, 0x47, 0x3b, 0x9d, 0xef, 0x35,
+        0xa3, 0xbf, 0xb3, 0xdf, 0x53, 0xd3, 0x97, 0x53, 0x49, 0x71, 0x07, 0x35, 0x61, 0x71, 0x2f, 0x43,
+        0x2f, 0x11, 0xdf, 0x17, 0x97, 0xfb, 0x95, 0x3b, 0x7f, 0x6b, 0xd3, 0x25, 0xbf, 0xad, 0xc7, 0xc5,
+        0xc5, 0xb5, 0x8b, 0xef, 0x2f, 0xd3, 0x07, 0x6b, 0x25, 0x49, 0x95, 0x25, 0x49, 0x6d, 0x71, 0xc7,
+    ],
+    [
+        0xa7, 0xbc, 0xc9, 0xad, 0x91, 0xdf, 0x85, 0xe5, 0xd4, 0x78, 0xd5, 0x17, 0x46, 0x7c, 0x29, 0x4c,
+        0x4d, 0x03, 0xe9, 0x25, 0x68, 0x11, 0x86, 0xb3, 0xbd, 0xf7, 0x6f, 0x61, 0x22, 0xa2, 0x26, 0x34,
+        0x2a, 0xbe, 0x1e, 0x46, 0x14, 0x68, 0x9d, 0x44, 0x18, 0xc2, 0x40, 0xf4, 0x7e, 0x5f, 0x1b, 0xad,
+        0x0b, 0x94, 0xb6, 0x67, 0xb4, 0x0b, 0xe1, 0xea, 0x95, 0x9c, 0x66, 0xdc, 0xe7, 0x5d, 0x6c, 0x05,
+        0xda, 0xd5, 0xdf, 0x7a, 0xef, 0xf6, 0xdb, 0x1f, 0x82, 0x4c, 0xc0, 0x68, 0x47, 0xa1, 0xbd, 0xee,
+        0x39, 0x50, 0x56, 0x4a, 0xdd, 0xdf, 0xa5, 0xf8, 0xc6, 0xda, 0xca, 0x90, 0xca, 0x01, 0x42, 0x9d,
+        0x8b, 0x0c, 0x73, 0x43, 0x75, 0x05, 0x94, 0xde, 0x24, 0xb3, 0x80, 0x34, 0xe5, 0x2c, 0xdc, 0x9b,
+        0x3f, 0xca, 0x33, 0x45, 0xd0, 0xdb, 0x5f, 0xf5, 0x52, 0xc3, 0x21, 0xda, 0xe2, 0x22, 0x72, 0x6b,
+        0x3e, 0xd0, 0x5b, 0xa8, 0x87, 0x8c, 0x06, 0x5d, 0x0f, 0xdd, 0x09, 0x19, 0x93, 0xd0, 0xb9, 0xfc,
+        0x8b, 0x0f, 0x84, 0x60, 0x33, 0x1c, 0x9b, 0x45, 0xf1, 0xf0, 0xa3, 0x94, 0x3a, 0x12, 0x77, 0x33,
+        0x4d, 0x44, 0x78, 0x28, 0x3c, 0x9e, 0xfd, 0x65, 0x57, 0x16, 0x94, 0x6b, 0xfb, 0x59, 0xd0, 0xc8,
+        0x22, 0x36, 0xdb, 0xd2, 0x63, 0x98, 0x43, 0xa1, 0x04, 0x87, 0x86, 0xf7, 0xa6, 0x26, 0xbb, 0xd6,
+        0x59, 0x4d, 0xbf, 0x6a, 0x2e, 0xaa, 0x2b, 0xef, 0xe6, 0x78, 0xb6, 0x4e, 0xe0, 0x2f, 0xdc, 0x7c,
+        0xbe, 0x57, 0x19, 0x32, 0x7e, 0x2a, 0xd0, 0xb8, 0xba, 0x29, 0x00, 0x3c, 0x52, 0x7d, 0xa8, 0x49,
+        0x3b, 0x2d, 0xeb, 0x25, 0x49, 0xfa, 0xa3, 0xaa, 0x39, 0xa7, 0xc5, 0xa7, 0x50, 0x11, 0x36, 0xfb,
+        0xc6, 0x67, 0x4a, 0xf5, 0xa5, 0x12, 0x65, 0x7e, 0xb0, 0xdf, 0xaf, 0x4e, 0xb3, 0x61, 0x7f, 0x2f,
+    ],
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        exif::makernote::tests::{build_ifd, tiff_with_maker_note, TestEntry},
+        parse_exif_bytes, MakerNote,
+    };
+    use test_case::test_case;
+
+    const SERIAL: u32 = 3012345;
+    const SHUTTER_COUNT_VALUE: u32 = 12345;
+
+    #[test]
+    fn decryption() {
+        let mut data = b"some data to be encrypted".to_vec();
+        decrypt(&mut data, SERIAL, SHUTTER_COUNT_VALUE);
+        assert_ne!(data, b"some data to be encrypted");
+        decrypt(&mut data, SERIAL, SHUTTER_COUNT_VALUE);
+        assert_eq!(data, b"some data to be encrypted");
+    }
+
+    /// An encrypted LensData 0204 of a 50mm f/1.8 lens.
+    fn lens_data() -> Vec<u8> {
+        let mut data = b"0204".to_vec();
+        data.extend([
+            0x00, 0x00, 0x00, 0x00, 0x05, 0x00, 0x50, 0x50, 0x8a, 0x3c, 0x50, 0x50, 0x14, 0x14,
+            0x24, 0x18,
+        ]);
+        decrypt(&mut data[4..], SERIAL, SHUTTER_COUNT_VALUE);
+        data
+    }
+
+    #[test_case("type3")]
+    #[test_case("type1")]
+    #[test_case("plain")]
+    fn nikon_maker_note(header: &str) {
+        let lens_data = lens_data();
+        let serial = format!("{SERIAL}\0");
+        let entries: &[TestEntry] = &[
+            (MAKER_NOTE_VERSION, 7, 4, b"0210"),
+            (SERIAL_NUMBER, 2, serial.len() as u32, serial.as_bytes()),
+            (LENS_TYPE, 1, 1, &[0x06]),
+            (LENS_DATA, 7, lens_data.len() as u32, &lens_data),
+            (SHUTTER_COUNT, 4, 1, &SHUTTER_COUNT_VALUE.to_le_bytes()),
+        ];
+        let tiff = tiff_with_maker_note("NIKON CORPORATION", |offset| match header {
+            "type3" => {
+                let mut data = b"Nikon\0\x02\x10\0\0II*\0\x08\0\0\0".to_vec();
+                data.extend(build_ifd(8, entries));
+                data
+            }
+            "type1" => {
+                let mut data = b"Nikon\0\x01\0".to_vec();
+                data.extend(build_ifd(offset + 8, entries));
+                data
+            }
+            _ => build_ifd(offset, entries),
+        });
+
+        let iter = parse_exif_bytes(&tiff).unwrap();
+        let Some(MakerNote::Nikon(nikon)) = iter.parse_maker_note() else {
+            panic!("Nikon MakerNote not found");
+        };
+        assert_eq!(nikon.version.as_deref(), Some("0210"));
+        assert_eq!(nikon.serial_number.as_deref(), Some("3012345"));
+        assert_eq!(nikon.shutter_count, Some(12345));
+        assert_eq!(nikon.lens_id().as_deref(), Some("8A 3C 50 50 14 14 24 06"));
+
+        let lens = nikon.lens_data.as_ref().unwrap();
+        let round = |x: Option<f64>| x.map(|x| (x * 10.0).round() / 10.0);
+        assert_eq!(lens.version, "0204");
+        assert_eq!(lens.lens_id_number, Some(0x8a));
+        assert_eq!(lens.lens_f_stops, Some(5.0));
+        assert_eq!(round(lens.min_focal_length), Some(50.4));
+        assert_eq!(lens.min_focal_length, lens.max_focal_length);
+        assert_eq!(round(lens.max_aperture_at_min_focal), Some(1.8));
+        assert_eq!(lens.mcu_version, Some(0x24));
+        assert_eq!(lens.focal_length, lens.max_focal_length);
+        assert_eq!(round(lens.focus_distance), Some(1.0));
+        assert_eq!(lens.effective_max_aperture, Some(2.0));
+    }
+}
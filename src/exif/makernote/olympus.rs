@@ -0,0 +1,192 @@
+use nom::number::Endianness;
+
+use crate::EntryValue;
+
+use super::MakerNoteEntries;
+
+const EQUIPMENT: u16 = 0x2010;
+const CAMERA_SETTINGS: u16 = 0x2020;
+
+// Tags of the Equipment sub-IFD
+const SERIAL_NUMBER: u16 = 0x0101;
+const LENS_SERIAL_NUMBER: u16 = 0x0202;
+const LENS_MODEL: u16 = 0x0203;
+
+// Tags of the CameraSettings sub-IFD
+const ART_FILTER: u16 = 0x0529;
+
+/// Olympus (and OM System) MakerNote, see [`MakerNote`](crate::MakerNote).
+///
+/// See: [Olympus Tags](https://exiftool.org/TagNames/Olympus.html).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OlympusMakerNote {
+    pub serial_number: Option<String>,
+    /// e.g. "OLYMPUS M.12-40mm F2.8"
+    pub lens_model: Option<String>,
+    pub lens_serial_number: Option<String>,
+    /// Art filter ID, 0 means off, see [`OlympusMakerNote::art_filter_name`]
+    pub art_filter: Option<u16>,
+}
+
+impl OlympusMakerNote {
+    /// Decodes an Olympus MakerNote at `offset` of `tiff`, which may start
+    /// with:
+    ///
+    /// - "OLYMPUS\0" & a byte order mark, followed by an IFD, offsets are
+    ///   relative to the start of the MakerNote
+    /// - "OM SYSTEM\0\0\0" & a byte order mark, the same as above
+    /// - "OLYMP\0" (older models), followed by an IFD, offsets are relative
+    ///   to the TIFF header
+    pub(crate) fn parse(
+        tiff: &[u8],
+        offset: usize,
+        endian: Endianness,
+    ) -> Option<OlympusMakerNote> {
+        let data = tiff.get(offset..)?;
+        let byte_order = |at: usize| match data.get(at..at + 2)? {
+            b"II" => Some(Endianness::Little),
+            b"MM" => Some(Endianness::Big),
+            _ => None,
+        };
+        let (base, ifd_offset, endian) = if data.starts_with(b"OLYMPUS\0") {
+            (data, 12, byte_order(8)?)
+        } else if data.starts_with(b"OM SYSTEM\0") {
+            (data, 16, byte_order(12)?)
+        } else if data.starts_with(b"OLYMP\0") {
+            (tiff, offset + 8, endian)
+        } else {
+            return None;
+        };
+
+        let entries = MakerNoteEntries::parse(base, ifd_offset, endian)?;
+        // Sub-IFDs are stored as offsets (of type IFD or LONG)
+        let sub_ifd = |code| {
+            let offset = entries.get_u32(code)?;
+            MakerNoteEntries::parse(base, offset as usize, endian)
+        };
+        let equipment = sub_ifd(EQUIPMENT).unwrap_or_default();
+        let camera_settings = sub_ifd(CAMERA_SETTINGS).unwrap_or_default();
+
+        Some(OlympusMakerNote {
+            serial_number: equipment.get_str(SERIAL_NUMBER),
+            lens_model: equipment.get_str(LENS_MODEL),
+            lens_serial_number: equipment.get_str(LENS_SERIAL_NUMBER),
+            // int16u[4], the first one is the filter ID
+            art_filter: camera_settings.get(ART_FILTER).and_then(|x| match x {
+                EntryValue::U16Array(x) => x.first().copied(),
+                x => x.as_u16(),
+            }),
+        })
+    }
+
+    /// Returns the name of [`OlympusMakerNote::art_filter`], e.g. "Pop Art".
+    pub fn art_filter_name(&self) -> Option<&'static str> {
+        let name = match self.art_filter? {
+            0 => "Off",
+            1 => "Soft Focus",
+            2 => "Pop Art",
+            3 => "Pale & Light Color",
+            4 => "Light Tone",
+            5 => "Pin Hole",
+            6 => "Grainy Film",
+            9 => "Diorama",
+            10 => "Cross Process",
+            12 => "Fish Eye",
+            13 => "Drawing",
+            14 => "Gentle Sepia",
+            15 => "Pale & Light Color II",
+            16 => "Pop Art II",
+            17 => "Pin Hole II",
+            18 => "Pin Hole III",
+            19 => "Grainy Film II",
+            20 => "Dramatic Tone",
+            21 => "Punk",
+            22 => "Soft Focus 2",
+            23 => "Sparkle",
+            24 => "Watercolor",
+            25 => "Key Line",
+            26 => "Key Line II",
+            27 => "Miniature",
+            28 => "Reflection",
+            29 => "Fragmented",
+            31 => "Cross Process II",
+            32 => "Dramatic Tone II",
+            33 => "Watercolor I",
+            34 => "Watercolor II",
+            35 => "Diorama II",
+            36 => "Vintage",
+            37 => "Vintage II",
+            38 => "Vintage III",
+            39 => "Partial Color",
+            40 => "Partial Color II",
+            41 => "Partial Color III",
+            42 => "Bleach Bypass",
+            43 => "Bleach Bypass II",
+            44 => "Instant Film",
+            _ => return None,
+        };
+        Some(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        exif::makernote::tests::{build_ifd, tiff_with_maker_note, TestEntry},
+        parse_exif_bytes, MakerNote,
+    };
+    use test_case::test_case;
+
+    #[test_case(b"OLYMPUS\0II\x03\0", false)]
+    #[test_case(b"OM SYSTEM\0\0\0II\x04\0", false)]
+    #[test_case(b"OLYMP\0\x01\0", true)]
+    fn olympus_maker_note(header: &[u8], tiff_based: bool) {
+        let tiff = tiff_with_maker_note("OLYMPUS IMAGING CORP.", |offset| {
+            let base = if tiff_based { offset } else { 0 };
+            let ifd_offset = base + header.len();
+            // main IFD (2 entries), then the 2 sub-IFDs
+            let equipment_offset = ifd_offset + 2 + 2 * 12 + 4;
+            let equipment: &[TestEntry] = &[
+                (SERIAL_NUMBER, 2, 10, b"BHP123456\0"),
+                (LENS_MODEL, 2, 23, b"OLYMPUS M.12-40mm F2.8\0"),
+            ];
+            let equipment = build_ifd(equipment_offset, equipment);
+            let settings_offset = equipment_offset + equipment.len();
+            let settings = build_ifd(
+                settings_offset,
+                &[(ART_FILTER, 3, 4, &[2, 0, 0, 0, 0, 0, 0, 0])],
+            );
+
+            let mut data = header.to_vec();
+            data.extend(build_ifd(
+                ifd_offset,
+                &[
+                    (EQUIPMENT, 13, 1, &(equipment_offset as u32).to_le_bytes()),
+                    (
+                        CAMERA_SETTINGS,
+                        4,
+                        1,
+                        &(settings_offset as u32).to_le_bytes(),
+                    ),
+                ],
+            ));
+            data.extend(equipment);
+            data.extend(settings);
+            data
+        });
+
+        let iter = parse_exif_bytes(&tiff).unwrap();
+        let Some(MakerNote::Olympus(olympus)) = iter.parse_maker_note() else {
+            panic!("Olympus MakerNote not found");
+        };
+        assert_eq!(olympus.serial_number.as_deref(), Some("BHP123456"));
+        assert_eq!(
+            olympus.lens_model.as_deref(),
+            Some("OLYMPUS M.12-40mm F2.8")
+        );
+        assert_eq!(olympus.lens_serial_number, None);
+        assert_eq!(olympus.art_filter, Some(2));
+        assert_eq!(olympus.art_filter_name(), Some("Pop Art"));
+    }
+}
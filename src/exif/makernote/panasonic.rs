@@ -0,0 +1,116 @@
+use nom::number::Endianness;
+
+use super::{ImageStabilization, MakerNoteEntries};
+
+const IMAGE_STABILIZATION: u16 = 0x001a;
+const INTERNAL_SERIAL_NUMBER: u16 = 0x0025;
+const LENS_TYPE: u16 = 0x0051;
+const LENS_SERIAL_NUMBER: u16 = 0x0052;
+const ACCESSORY_TYPE: u16 = 0x0053;
+const ACCESSORY_SERIAL_NUMBER: u16 = 0x0054;
+
+/// Panasonic MakerNote, see [`MakerNote`](crate::MakerNote).
+///
+/// See: [Panasonic Tags](https://exiftool.org/TagNames/Panasonic.html).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PanasonicMakerNote {
+    /// Lens name, e.g. "LUMIX G VARIO 12-32/F3.5-5.6"
+    pub lens_type: Option<String>,
+    pub lens_serial_number: Option<String>,
+    /// e.g. a teleconverter
+    pub accessory_type: Option<String>,
+    pub accessory_serial_number: Option<String>,
+    pub internal_serial_number: Option<String>,
+    pub image_stabilization: Option<ImageStabilization>,
+}
+
+impl PanasonicMakerNote {
+    /// Decodes a Panasonic MakerNote at `offset` of `tiff`, which starts with
+    /// "Panasonic\0\0\0", followed by an IFD, offsets are relative to the
+    /// TIFF header.
+    pub(crate) fn parse(
+        tiff: &[u8],
+        offset: usize,
+        endian: Endianness,
+    ) -> Option<PanasonicMakerNote> {
+        if !tiff.get(offset..)?.starts_with(b"Panasonic\0") {
+            return None;
+        }
+        let entries = MakerNoteEntries::parse(tiff, offset + 12, endian)?;
+        // Unused values are filled with "NO-ACCESSORY" or "0000000000"
+        let get_str = |code| {
+            entries
+                .get_str(code)
+                .filter(|x| x != "NO-ACCESSORY" && x.bytes().any(|b| b != b'0'))
+        };
+
+        Some(PanasonicMakerNote {
+            lens_type: get_str(LENS_TYPE),
+            lens_serial_number: get_str(LENS_SERIAL_NUMBER),
+            accessory_type: get_str(ACCESSORY_TYPE),
+            accessory_serial_number: get_str(ACCESSORY_SERIAL_NUMBER),
+            internal_serial_number: entries
+                .get_bytes(INTERNAL_SERIAL_NUMBER)
+                .map(|x| String::from_utf8_lossy(x).trim_end_matches('\0').to_owned())
+                .filter(|x| !x.is_empty()),
+            image_stabilization: entries
+                .get(IMAGE_STABILIZATION)
+                .and_then(|x| x.as_u16())
+                .map(|x| match x {
+                    // On, Mode 1/2/3
+                    2 | 4 | 6 => ImageStabilization::On,
+                    3 => ImageStabilization::Off,
+                    5 => ImageStabilization::Panning,
+                    x => ImageStabilization::Unknown(x as i16),
+                }),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        exif::makernote::tests::{build_ifd, tiff_with_maker_note},
+        parse_exif_bytes, MakerNote,
+    };
+
+    #[test]
+    fn panasonic_maker_note() {
+        let tiff = tiff_with_maker_note("Panasonic", |offset| {
+            let mut data = b"Panasonic\0\0\0".to_vec();
+            data.extend(build_ifd(
+                offset + 12,
+                &[
+                    (IMAGE_STABILIZATION, 3, 1, &[5, 0]),
+                    (INTERNAL_SERIAL_NUMBER, 7, 16, b"F541803280157\0\0\0"),
+                    (LENS_TYPE, 2, 29, b"LUMIX G VARIO 12-32/F3.5-5.6\0"),
+                    (LENS_SERIAL_NUMBER, 2, 14, b"XA5FB001234 \0\0"),
+                    (ACCESSORY_TYPE, 2, 13, b"NO-ACCESSORY\0"),
+                    (ACCESSORY_SERIAL_NUMBER, 2, 11, b"0000000000\0"),
+                ],
+            ));
+            data
+        });
+
+        let iter = parse_exif_bytes(&tiff).unwrap();
+        let Some(MakerNote::Panasonic(panasonic)) = iter.parse_maker_note() else {
+            panic!("Panasonic MakerNote not found");
+        };
+        assert_eq!(
+            panasonic.lens_type.as_deref(),
+            Some("LUMIX G VARIO 12-32/F3.5-5.6")
+        );
+        assert_eq!(panasonic.lens_serial_number.as_deref(), Some("XA5FB001234"));
+        assert_eq!(panasonic.accessory_type, None);
+        assert_eq!(panasonic.accessory_serial_number, None);
+        assert_eq!(
+            panasonic.internal_serial_number.as_deref(),
+            Some("F541803280157")
+        );
+        assert_eq!(
+            panasonic.image_stabilization,
+            Some(ImageStabilization::Panning)
+        );
+    }
+}
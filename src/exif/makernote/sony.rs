@@ -0,0 +1,208 @@
+use nom::number::Endianness;
+
+use super::{ImageStabilization, MakerNoteEntries};
+
+const TAG_9050: u16 = 0x9050;
+const MODEL_ID: u16 = 0xb001;
+const IMAGE_STABILIZATION: u16 = 0xb026;
+const LENS_TYPE: u16 = 0xb027;
+const LENS_SPEC: u16 = 0xb02a;
+
+/// Sony MakerNote, see [`MakerNote`](crate::MakerNote).
+///
+/// Some binary tables (e.g. `Tag9050`) are enciphered, they are deciphered
+/// before being decoded. See: [Sony
+/// Tags](https://exiftool.org/TagNames/Sony.html).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SonyMakerNote {
+    pub model_id: Option<u16>,
+    /// SteadyShot, i.e. [`ImageStabilization::On`] or
+    /// [`ImageStabilization::Off`]
+    pub image_stabilization: Option<ImageStabilization>,
+    /// Sony lens ID of A-mount lenses, E-mount lenses are reported as 65535
+    /// (or 0xffffffff) by most models
+    pub lens_type: Option<u32>,
+    pub lens_spec: Option<SonyLensSpec>,
+    /// Number of shutter actuations, only recorded by SLT, NEX & ILCE models
+    pub shutter_count: Option<u32>,
+}
+
+/// Decoded Sony `LensSpec` tag.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SonyLensSpec {
+    /// In mm
+    pub min_focal_length: f64,
+    /// In mm, equals `min_focal_length` for prime lenses
+    pub max_focal_length: f64,
+    /// As an f-number
+    pub max_aperture_at_min_focal: f64,
+    /// As an f-number
+    pub max_aperture_at_max_focal: f64,
+}
+
+impl SonyMakerNote {
+    /// Decodes a Sony MakerNote at `offset` of `tiff`, which is an IFD
+    /// optionally prefixed with "SONY DSC \0\0\0" or "SONY CAM \0\0\0",
+    /// offsets are relative to the TIFF header.
+    pub(crate) fn parse(tiff: &[u8], offset: usize, endian: Endianness) -> Option<SonyMakerNote> {
+        let data = tiff.get(offset..)?;
+        let offset = if data.starts_with(b"SONY DSC ") || data.starts_with(b"SONY CAM ") {
+            offset + 12
+        } else {
+            offset
+        };
+        let entries = MakerNoteEntries::parse(tiff, offset, endian)?;
+        Some(SonyMakerNote::from_entries(&entries))
+    }
+
+    fn from_entries(entries: &MakerNoteEntries) -> SonyMakerNote {
+        let tag_9050 = entries.get_bytes(TAG_9050).map(decipher);
+        SonyMakerNote {
+            model_id: entries.get(MODEL_ID).and_then(|x| x.as_u16()),
+            image_stabilization: entries
+                .get_u32(IMAGE_STABILIZATION)
+                .filter(|x| *x <= 1)
+                .map(|x| ImageStabilization::from(x as i16)),
+            lens_type: entries.get_u32(LENS_TYPE),
+            lens_spec: entries.get_bytes(LENS_SPEC).and_then(SonyLensSpec::parse),
+            shutter_count: tag_9050
+                .as_deref()
+                .and_then(|x| x.get(0x3a..0x3e))
+                .map(|x| u32::from_le_bytes([x[0], x[1], x[2], 0]))
+                .filter(|x| *x != 0),
+        }
+    }
+
+    /// Returns a lens description built from the `LensSpec` tag, e.g.
+    /// "18-55mm F3.5-5.6" or "50mm F1.8".
+    pub fn lens_model(&self) -> Option<String> {
+        let spec = self.lens_spec.as_ref()?;
+        let range = |a: f64, b: f64| {
+            if a == b {
+                format!("{a}")
+            } else {
+                format!("{a}-{b}")
+            }
+        };
+        Some(format!(
+            "{}mm F{}",
+            range(spec.min_focal_length, spec.max_focal_length),
+            range(
+                spec.max_aperture_at_min_focal,
+                spec.max_aperture_at_max_focal
+            ),
+        ))
+    }
+}
+
+impl SonyLensSpec {
+    /// `LensSpec` is 8 bytes: flags, 2 BCD bytes of each focal length, a BCD
+    /// byte of each aperture (e.g. 0x35 means 3.5), flags.
+    fn parse(data: &[u8]) -> Option<SonyLensSpec> {
+        let data = data.get(..8)?;
+        let bcd = |bytes: &[u8]| {
+            bytes.iter().try_fold(0_u32, |v, x| {
+                let (hi, lo) = (x >> 4, x & 0x0f);
+                (hi < 10 && lo < 10).then_some(v * 100 + hi as u32 * 10 + lo as u32)
+            })
+        };
+        let min_focal_length = bcd(&data[1..3])? as f64;
+        if min_focal_length == 0.0 {
+            return None;
+        }
+        Some(SonyLensSpec {
+            min_focal_length,
+            max_focal_length: match bcd(&data[3..5])? {
+                0 => min_focal_length,
+                x => x as f64,
+            },
+            max_aperture_at_min_focal: bcd(&data[5..6])? as f64 / 10.0,
+            max_aperture_at_max_focal: match bcd(&data[6..7])? {
+                0 => bcd(&data[5..6])? as f64 / 10.0,
+                x => x as f64 / 10.0,
+            },
+        })
+    }
+}
+
+/// Deciphers Sony enciphered data, which is a substitution cipher of
+/// `c = b ^ 3 % 249`, bytes >= 249 are kept as is.
+fn decipher(data: &[u8]) -> Vec<u8> {
+    let mut table = [0_u8; 256];
+    for b in 0..256_u32 {
+        let c = if b < 249 { b * b * b % 249 } else { b };
+        table[c as usize] = b as u8;
+    }
+    data.iter().map(|x| table[*x as usize]).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        exif::makernote::tests::{build_ifd, tiff_with_maker_note, TestEntry},
+        parse_exif_bytes, MakerNote,
+    };
+    use test_case::test_case;
+
+    fn encipher(data: &[u8]) -> Vec<u8> {
+        data.iter()
+            .map(|x| match *x as u32 {
+                b if b < 249 => (b * b * b % 249) as u8,
+                b => b as u8,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn decipher_data() {
+        let data = (0..=255).collect::<Vec<u8>>();
+        assert_eq!(decipher(&encipher(&data)), data);
+        assert_eq!(encipher(&[2, 3]), [8, 27]);
+    }
+
+    #[test_case(&[0, 0, 0x18, 0, 0x55, 0x35, 0x56, 0], "18-55mm F3.5-5.6")]
+    #[test_case(&[0, 0, 0x50, 0, 0, 0x18, 0, 0], "50mm F1.8")]
+    #[test_case(&[0, 0x01, 0x35, 0x01, 0x35, 0x28, 0x28, 0], "135mm F2.8")]
+    fn lens_model(spec: &[u8], expect: &str) {
+        let sony = SonyMakerNote {
+            lens_spec: SonyLensSpec::parse(spec),
+            ..Default::default()
+        };
+        assert_eq!(sony.lens_model().as_deref(), Some(expect));
+    }
+
+    #[test_case(true)]
+    #[test_case(false)]
+    fn sony_maker_note(with_header: bool) {
+        let mut tag_9050 = vec![0; 0x100];
+        tag_9050[0x3a..0x3e].copy_from_slice(&[0x39, 0x30, 0, 0xff]);
+        let tag_9050 = encipher(&tag_9050);
+        let entries: &[TestEntry] = &[
+            (TAG_9050, 7, tag_9050.len() as u32, &tag_9050),
+            (MODEL_ID, 3, 1, &[0x24, 0x01]),
+            (IMAGE_STABILIZATION, 4, 1, &[1, 0, 0, 0]),
+            (LENS_TYPE, 4, 1, &[0xff, 0xff, 0, 0]),
+            (LENS_SPEC, 1, 8, &[0, 0, 0x24, 0, 0x70, 0x28, 0x28, 0]),
+        ];
+        let tiff = tiff_with_maker_note("SONY", |offset| {
+            if with_header {
+                let mut data = b"SONY DSC \0\0\0".to_vec();
+                data.extend(build_ifd(offset + 12, entries));
+                data
+            } else {
+                build_ifd(offset, entries)
+            }
+        });
+
+        let iter = parse_exif_bytes(&tiff).unwrap();
+        let Some(MakerNote::Sony(sony)) = iter.parse_maker_note() else {
+            panic!("Sony MakerNote not found");
+        };
+        assert_eq!(sony.model_id, Some(0x0124));
+        assert_eq!(sony.image_stabilization, Some(ImageStabilization::On));
+        assert_eq!(sony.lens_type, Some(65535));
+        assert_eq!(sony.lens_model().as_deref(), Some("24-70mm F2.8"));
+        assert_eq!(sony.shutter_count, Some(12345));
+    }
+}
@@ -0,0 +1,472 @@
+//! Human readable interpretation of Exif values, e.g. `ExposureProgram` 2 is
+//! "Normal program", see [`ParsedExifEntry::interpreted_value`].
+//!
+//! Descriptions of enumerated values follow the wording of the Exif 2.32
+//! spec, other values are formatted the way exiftool prints them, e.g.
+//! `ExposureTime` as "1/250", `FocalLength` as "6.9 mm".
+//!
+//! [`ParsedExifEntry::interpreted_value`]: crate::ParsedExifEntry::interpreted_value
+
+use crate::{EntryValue, ExifTag};
+
+type Table = &'static [(u32, &'static str)];
+
+const ORIENTATION: Table = &[
+    (1, "Horizontal (normal)"),
+    (2, "Mirror horizontal"),
+    (3, "Rotate 180"),
+    (4, "Mirror vertical"),
+    (5, "Mirror horizontal and rotate 270 CW"),
+    (6, "Rotate 90 CW"),
+    (7, "Mirror horizontal and rotate 90 CW"),
+    (8, "Rotate 270 CW"),
+];
+
+const RESOLUTION_UNIT: Table = &[(1, "None"), (2, "inches"), (3, "cm")];
+
+const YCBCR_POSITIONING: Table = &[(1, "Centered"), (2, "Co-sited")];
+
+const COMPRESSION: Table = &[
+    (1, "Uncompressed"),
+    (2, "CCITT 1D"),
+    (3, "T4/Group 3 Fax"),
+    (4, "T6/Group 4 Fax"),
+    (5, "LZW"),
+    (6, "JPEG (old-style)"),
+    (7, "JPEG"),
+    (8, "Adobe Deflate"),
+    (32773, "PackBits"),
+    (34892, "Lossy JPEG"),
+];
+
+const PHOTOMETRIC_INTERPRETATION: Table = &[
+    (0, "WhiteIsZero"),
+    (1, "BlackIsZero"),
+    (2, "RGB"),
+    (3, "RGB Palette"),
+    (4, "Transparency Mask"),
+    (5, "CMYK"),
+    (6, "YCbCr"),
+    (8, "CIELab"),
+    (32803, "Color Filter Array"),
+    (34892, "Linear Raw"),
+];
+
+const PLANAR_CONFIGURATION: Table = &[(1, "Chunky"), (2, "Planar")];
+
+const EXPOSURE_PROGRAM: Table = &[
+    (0, "Not defined"),
+    (1, "Manual"),
+    (2, "Normal program"),
+    (3, "Aperture priority"),
+    (4, "Shutter priority"),
+    (5, "Creative program"),
+    (6, "Action program"),
+    (7, "Portrait mode"),
+    (8, "Landscape mode"),
+];
+
+const SENSITIVITY_TYPE: Table = &[
+    (0, "Unknown"),
+    (1, "Standard output sensitivity"),
+    (2, "Recommended exposure index"),
+    (3, "ISO speed"),
+    (
+        4,
+        "Standard output sensitivity and recommended exposure index",
+    ),
+    (5, "Standard output sensitivity and ISO speed"),
+    (6, "Recommended exposure index and ISO speed"),
+    (
+        7,
+        "Standard output sensitivity, recommended exposure index and ISO speed",
+    ),
+];
+
+const METERING_MODE: Table = &[
+    (0, "Unknown"),
+    (1, "Average"),
+    (2, "Center-weighted average"),
+    (3, "Spot"),
+    (4, "Multi-spot"),
+    (5, "Pattern"),
+    (6, "Partial"),
+    (255, "Other"),
+];
+
+const LIGHT_SOURCE: Table = &[
+    (0, "Unknown"),
+    (1, "Daylight"),
+    (2, "Fluorescent"),
+    (3, "Tungsten (incandescent light)"),
+    (4, "Flash"),
+    (9, "Fine weather"),
+    (10, "Cloudy weather"),
+    (11, "Shade"),
+    (12, "Daylight fluorescent (D 5700 - 7100K)"),
+    (13, "Day white fluorescent (N 4600 - 5500K)"),
+    (14, "Cool white fluorescent (W 3800 - 4500K)"),
+    (15, "White fluorescent (WW 3250 - 3800K)"),
+    (16, "Warm white fluorescent (L 2600 - 3250K)"),
+    (17, "Standard light A"),
+    (18, "Standard light B"),
+    (19, "Standard light C"),
+    (20, "D55"),
+    (21, "D65"),
+    (22, "D75"),
+    (23, "D50"),
+    (24, "ISO studio tungsten"),
+    (255, "Other light source"),
+];
+
+const COLOR_SPACE: Table = &[(1, "sRGB"), (2, "Adobe RGB"), (0xffff, "Uncalibrated")];
+
+const SENSING_METHOD: Table = &[
+    (1, "Not defined"),
+    (2, "One-chip color area sensor"),
+    (3, "Two-chip color area sensor"),
+    (4, "Three-chip color area sensor"),
+    (5, "Color sequential area sensor"),
+    (7, "Trilinear sensor"),
+    (8, "Color sequential linear sensor"),
+];
+
+const FILE_SOURCE: Table = &[
+    (0, "Others"),
+    (1, "Scanner of transparent type"),
+    (2, "Scanner of reflex type"),
+    (3, "Digital camera"),
+];
+
+const SCENE_TYPE: Table = &[(1, "Directly photographed")];
+
+const CUSTOM_RENDERED: Table = &[(0, "Normal process"), (1, "Custom process")];
+
+const EXPOSURE_MODE: Table = &[
+    (0, "Auto exposure"),
+    (1, "Manual exposure"),
+    (2, "Auto bracket"),
+];
+
+const WHITE_BALANCE: Table = &[(0, "Auto white balance"), (1, "Manual white balance")];
+
+const SCENE_CAPTURE_TYPE: Table = &[
+    (0, "Standard"),
+    (1, "Landscape"),
+    (2, "Portrait"),
+    (3, "Night scene"),
+];
+
+const GAIN_CONTROL: Table = &[
+    (0, "None"),
+    (1, "Low gain up"),
+    (2, "High gain up"),
+    (3, "Low gain down"),
+    (4, "High gain down"),
+];
+
+const CONTRAST: Table = &[(0, "Normal"), (1, "Soft"), (2, "Hard")];
+
+const SATURATION: Table = &[(0, "Normal"), (1, "Low saturation"), (2, "High saturation")];
+
+const SUBJECT_DISTANCE_RANGE: Table = &[
+    (0, "Unknown"),
+    (1, "Macro"),
+    (2, "Close view"),
+    (3, "Distant view"),
+];
+
+const GPS_ALTITUDE_REF: Table = &[(0, "Above sea level"), (1, "Below sea level")];
+
+const GPS_MEASURE_MODE: Table = &[
+    (2, "2-dimensional measurement"),
+    (3, "3-dimensional measurement"),
+];
+
+const GPS_DIFFERENTIAL: Table = &[(0, "No correction"), (1, "Differential corrected")];
+
+type TextTable = &'static [(&'static str, &'static str)];
+
+const GPS_LATITUDE_REF: TextTable = &[("N", "North"), ("S", "South")];
+
+const GPS_LONGITUDE_REF: TextTable = &[("E", "East"), ("W", "West")];
+
+const GPS_STATUS: TextTable = &[
+    ("A", "Measurement in progress"),
+    ("V", "Measurement interrupted"),
+];
+
+const GPS_SPEED_REF: TextTable = &[("K", "km/h"), ("M", "mph"), ("N", "knots")];
+
+const GPS_DIRECTION_REF: TextTable = &[("T", "True direction"), ("M", "Magnetic direction")];
+
+const GPS_DISTANCE_REF: TextTable = &[("K", "Kilometers"), ("M", "Miles"), ("N", "Nautical miles")];
+
+/// Returns the human readable description of `value` of the `tag`, or `None`
+/// if the tag has no interpretation or the value has an unexpected type.
+///
+/// Enumerated values which are not defined by the spec are described as
+/// "Unknown (N)".
+pub(crate) fn interpret(tag: ExifTag, value: &EntryValue) -> Option<String> {
+    use ExifTag::*;
+
+    let table = match tag {
+        Orientation => ORIENTATION,
+        ResolutionUnit | FocalPlaneResolutionUnit => RESOLUTION_UNIT,
+        YCbCrPositioning => YCBCR_POSITIONING,
+        Compression => COMPRESSION,
+        PhotometricInterpretation => PHOTOMETRIC_INTERPRETATION,
+        PlanarConfiguration => PLANAR_CONFIGURATION,
+        ExposureProgram => EXPOSURE_PROGRAM,
+        SensitivityType => SENSITIVITY_TYPE,
+        MeteringMode => METERING_MODE,
+        LightSource => LIGHT_SOURCE,
+        ColorSpace => COLOR_SPACE,
+        SensingMethod => SENSING_METHOD,
+        FileSource => FILE_SOURCE,
+        SceneType => SCENE_TYPE,
+        CustomRendered => CUSTOM_RENDERED,
+        ExposureMode => EXPOSURE_MODE,
+        WhiteBalanceMode => WHITE_BALANCE,
+        SceneCaptureType => SCENE_CAPTURE_TYPE,
+        GainControl => GAIN_CONTROL,
+        Contrast | Sharpness => CONTRAST,
+        Saturation => SATURATION,
+        SubjectDistanceRange => SUBJECT_DISTANCE_RANGE,
+        GPSAltitudeRef => GPS_ALTITUDE_REF,
+        GPSMeasureMode => GPS_MEASURE_MODE,
+        GPSDifferential => GPS_DIFFERENTIAL,
+
+        GPSLatitudeRef | GPSDestLatitudeRef => return lookup_text(GPS_LATITUDE_REF, value),
+        GPSLongitudeRef | GPSDestLongitudeRef => return lookup_text(GPS_LONGITUDE_REF, value),
+        GPSStatus => return lookup_text(GPS_STATUS, value),
+        GPSSpeedRef => return lookup_text(GPS_SPEED_REF, value),
+        GPSTrackRef | GPSImgDirectionRef | GPSDestBearingRef => {
+            return lookup_text(GPS_DIRECTION_REF, value)
+        }
+        GPSDestDistanceRef => return lookup_text(GPS_DISTANCE_REF, value),
+
+        Flash => return uint(value).map(flash),
+        ComponentsConfiguration => return components_configuration(value),
+        ExposureTime => return number(value).map(exposure_time),
+        ShutterSpeedValue => return number(value).map(|x| exposure_time(2f64.powf(-x))),
+        FNumber => return number(value).map(f_number),
+        ApertureValue | MaxApertureValue => {
+            return number(value).map(|x| f_number(2f64.powf(x / 2.0)))
+        }
+        ExposureBiasValue => return number(value).map(exposure_bias),
+        FocalLength => return number(value).map(|x| format!("{x:.1} mm")),
+        FocalLengthIn35mmFilm => return number(value).map(|x| format!("{x:.0} mm")),
+        SubjectDistance => return number(value).map(|x| format!("{} m", trim(x, 2))),
+        GPSAltitude => return number(value).map(|x| format!("{} m", trim(x, 1))),
+        GPSLatitude | GPSLongitude | GPSDestLatitude | GPSDestLongitude => return degrees(value),
+        _ => return None,
+    };
+
+    // Undefined values, e.g. `FileSource`, are stored as a single byte
+    let v = match value {
+        EntryValue::Undefined(x) if x.len() == 1 => x[0].into(),
+        _ => uint(value)?,
+    };
+    Some(lookup(table, v))
+}
+
+fn lookup(table: Table, v: u32) -> String {
+    table
+        .iter()
+        .find(|(k, _)| *k == v)
+        .map(|(_, s)| s.to_string())
+        .unwrap_or_else(|| format!("Unknown ({v})"))
+}
+
+fn lookup_text(table: TextTable, value: &EntryValue) -> Option<String> {
+    let v = value.as_str()?.trim_end_matches(['\0', ' ']);
+    let s = table
+        .iter()
+        .find(|(k, _)| *k == v)
+        .map(|(_, s)| s.to_string())
+        .unwrap_or_else(|| format!("Unknown ({v})"));
+    Some(s)
+}
+
+/// Returns an integer value, single element arrays are accepted as well.
+fn uint(value: &EntryValue) -> Option<u32> {
+    match value {
+        EntryValue::U8(x) => Some((*x).into()),
+        EntryValue::U16(x) => Some((*x).into()),
+        EntryValue::U32(x) => Some(*x),
+        EntryValue::U8Array(x) if x.len() == 1 => Some(x[0].into()),
+        EntryValue::U16Array(x) if x.len() == 1 => Some(x[0].into()),
+        EntryValue::U32Array(x) if x.len() == 1 => Some(x[0]),
+        _ => None,
+    }
+}
+
+/// Returns a numeric value as `f64`, rationals with a zero denominator are
+/// rejected.
+pub(crate) fn number(value: &EntryValue) -> Option<f64> {
+    let rational = |n: f64, d: f64| (d != 0.0).then(|| n / d);
+    match value {
+        EntryValue::URational(x) => rational(x.0.into(), x.1.into()),
+        EntryValue::IRational(x) => rational(x.0.into(), x.1.into()),
+        EntryValue::URationalArray(x) if x.len() == 1 => rational(x[0].0.into(), x[0].1.into()),
+        EntryValue::IRationalArray(x) if x.len() == 1 => rational(x[0].0.into(), x[0].1.into()),
+        EntryValue::F32(x) => Some((*x).into()),
+        EntryValue::F64(x) => Some(*x),
+        EntryValue::I16(x) => Some((*x).into()),
+        EntryValue::I32(x) => Some((*x).into()),
+        _ => uint(value).map(f64::from),
+    }
+}
+
+/// Formats `x` with at most `precision` decimals, trailing zeros are dropped.
+fn trim(x: f64, precision: usize) -> String {
+    let s = format!("{x:.precision$}");
+    if s.contains('.') {
+        s.trim_end_matches('0').trim_end_matches('.').to_owned()
+    } else {
+        s
+    }
+}
+
+/// Formats an exposure time in seconds, e.g. "1/250", "0.5", "2".
+pub(crate) fn exposure_time(secs: f64) -> String {
+    if secs > 0.0 && secs < 0.25001 {
+        format!("1/{}", (1.0 / secs).round())
+    } else {
+        trim(secs, 1)
+    }
+}
+
+pub(crate) fn f_number(x: f64) -> String {
+    format!("{x:.1}")
+}
+
+/// Formats an exposure bias in EV, e.g. "+0.7", "0", "-1".
+fn exposure_bias(x: f64) -> String {
+    let s = trim(x, 1);
+    match s.as_str() {
+        "0" | "-0" => "0".to_owned(),
+        _ if x > 0.0 => format!("+{s}"),
+        _ => s,
+    }
+}
+
+/// Describes the bit fields of a `Flash` value, e.g. "Auto, Fired, Red-eye
+/// reduction".
+fn flash(v: u32) -> String {
+    if v & 0x20 != 0 {
+        return "No flash function".to_owned();
+    }
+    if v == 0 {
+        return "No flash".to_owned();
+    }
+
+    let mut parts = Vec::new();
+    match (v >> 3) & 0x3 {
+        1 => parts.push("On"),
+        2 => parts.push("Off"),
+        3 => parts.push("Auto"),
+        _ => (),
+    }
+    parts.push(if v & 0x1 != 0 {
+        "Fired"
+    } else {
+        "Did not fire"
+    });
+    if v & 0x40 != 0 {
+        parts.push("Red-eye reduction");
+    }
+    match (v >> 1) & 0x3 {
+        2 => parts.push("Return not detected"),
+        3 => parts.push("Return detected"),
+        _ => (),
+    }
+    parts.join(", ")
+}
+
+/// Describes the channels of a `ComponentsConfiguration` value, e.g. "Y, Cb,
+/// Cr, -".
+fn components_configuration(value: &EntryValue) -> Option<String> {
+    let bytes: &[u8] = match value {
+        EntryValue::Undefined(x) => x,
+        EntryValue::U8Array(x) => x,
+        _ => return None,
+    };
+    let s = bytes
+        .iter()
+        .map(|x| match x {
+            1 => "Y",
+            2 => "Cb",
+            3 => "Cr",
+            4 => "R",
+            5 => "G",
+            6 => "B",
+            _ => "-",
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    Some(s)
+}
+
+/// Formats a coordinate of degrees, minutes & seconds, e.g. `22 deg 31'
+/// 41.18"`.
+fn degrees(value: &EntryValue) -> Option<String> {
+    let values = value.as_urational_array()?;
+    let mut deg = 0.0;
+    for (x, unit) in values.iter().zip([1.0, 60.0, 3600.0]) {
+        if x.1 == 0 {
+            return None;
+        }
+        deg += f64::from(x.0) / f64::from(x.1) / unit;
+    }
+    let d = deg.trunc();
+    let m = ((deg - d) * 60.0).trunc();
+    let s = (deg - d - m / 60.0) * 3600.0;
+    Some(format!("{d} deg {m}' {s:.2}\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case(ExifTag::ExposureProgram, EntryValue::U16(2), "Normal program")]
+    #[test_case(ExifTag::ExposureProgram, EntryValue::U16(42), "Unknown (42)")]
+    #[test_case(ExifTag::MeteringMode, EntryValue::U16(5), "Pattern")]
+    #[test_case(ExifTag::WhiteBalanceMode, EntryValue::U16(0), "Auto white balance")]
+    #[test_case(ExifTag::ColorSpace, EntryValue::U16(0xffff), "Uncalibrated")]
+    #[test_case(ExifTag::SceneCaptureType, EntryValue::U16(3), "Night scene")]
+    #[test_case(ExifTag::Orientation, EntryValue::U16Array(vec![6]), "Rotate 90 CW")]
+    #[test_case(ExifTag::FileSource, EntryValue::Undefined(vec![3]), "Digital camera")]
+    #[test_case(ExifTag::Flash, EntryValue::U16(0x10), "Off, Did not fire")]
+    #[test_case(
+        ExifTag::Flash,
+        EntryValue::U16(0x59),
+        "Auto, Fired, Red-eye reduction"
+    )]
+    #[test_case(ExifTag::Flash, EntryValue::U16(0x20), "No flash function")]
+    #[test_case(ExifTag::ComponentsConfiguration, EntryValue::Undefined(vec![1, 2, 3, 0]), "Y, Cb, Cr, -")]
+    #[test_case(ExifTag::GPSLatitudeRef, "S".into(), "South")]
+    #[test_case(ExifTag::GPSSpeedRef, "K".into(), "km/h")]
+    #[test_case(ExifTag::ExposureTime, EntryValue::URational((9997, 1000000).into()), "1/100")]
+    #[test_case(ExifTag::ExposureTime, EntryValue::URational((2, 1).into()), "2")]
+    #[test_case(ExifTag::ShutterSpeedValue, EntryValue::IRational((8, 1).into()), "1/256")]
+    #[test_case(ExifTag::FNumber, EntryValue::URational((18, 10).into()), "1.8")]
+    #[test_case(ExifTag::ApertureValue, EntryValue::URational((2, 1).into()), "2.0")]
+    #[test_case(ExifTag::ExposureBiasValue, EntryValue::IRational((-2, 3).into()), "-0.7")]
+    #[test_case(ExifTag::ExposureBiasValue, EntryValue::IRational((0, 1).into()), "0")]
+    #[test_case(ExifTag::FocalLength, EntryValue::URational((69, 10).into()), "6.9 mm")]
+    #[test_case(ExifTag::GPSAltitude, EntryValue::URational((3500, 100).into()), "35 m")]
+    #[test_case(ExifTag::GPSLatitude, EntryValue::URationalArray(vec![(22, 1).into(), (31, 1).into(), (4118, 100).into()]), "22 deg 31' 41.18\"")]
+    fn interpret(tag: ExifTag, value: EntryValue, expect: &str) {
+        assert_eq!(super::interpret(tag, &value).unwrap(), expect);
+    }
+
+    #[test_case(ExifTag::Make, "vivo".into())]
+    #[test_case(ExifTag::ExposureProgram, "2".into())]
+    #[test_case(ExifTag::FNumber, EntryValue::URational((18, 0).into()))]
+    fn not_interpreted(tag: ExifTag, value: EntryValue) {
+        assert_eq!(super::interpret(tag, &value), None);
+    }
+}
@@ -0,0 +1,174 @@
+use std::ops::Range;
+
+use nom::number::{complete, Endianness};
+
+use crate::values::DataFormat;
+
+use super::exif_exif::IFD_ENTRY_SIZE;
+
+/// Reads a u16 at `pos` of the TIFF data.
+pub(crate) fn u16_at(data: &[u8], pos: usize, endian: Endianness) -> Option<u16> {
+    let data = data.get(pos..)?;
+    complete::u16::<_, nom::error::Error<_>>(endian)(data)
+        .ok()
+        .map(|x| x.1)
+}
+
+/// Reads a u32 at `pos` of the TIFF data.
+pub(crate) fn u32_at(data: &[u8], pos: usize, endian: Endianness) -> Option<u32> {
+    let data = data.get(pos..)?;
+    complete::u32::<_, nom::error::Error<_>>(endian)(data)
+        .ok()
+        .map(|x| x.1)
+}
+
+pub(crate) fn u16_bytes(x: u16, endian: Endianness) -> [u8; 2] {
+    match endian {
+        Endianness::Little => x.to_le_bytes(),
+        _ => x.to_be_bytes(),
+    }
+}
+
+pub(crate) fn u32_bytes(x: u32, endian: Endianness) -> [u8; 4] {
+    match endian {
+        Endianness::Little => x.to_le_bytes(),
+        _ => x.to_be_bytes(),
+    }
+}
+
+/// An IFD entry located at `pos` of the TIFF data, the value is not checked.
+#[derive(Debug, Clone)]
+pub(crate) struct IfdField {
+    pub pos: usize,
+    pub code: u16,
+    // `None` for unknown data formats
+    pub format: Option<DataFormat>,
+    pub count: u32,
+    // The value field, i.e. the value itself if it fits in 4 bytes, or its
+    // offset
+    pub value: u32,
+}
+
+impl IfdField {
+    /// Size of the value, 0 for unknown data formats.
+    pub fn size(&self) -> usize {
+        self.format.map_or(0, |x| {
+            x.component_size().saturating_mul(self.count as usize)
+        })
+    }
+
+    /// Range of the value in the TIFF data, which may be out of the data.
+    pub fn value_range(&self) -> Range<usize> {
+        let size = self.size();
+        let start = if size > 4 {
+            self.value as usize
+        } else {
+            self.pos + 8
+        };
+        start..start.saturating_add(size)
+    }
+}
+
+/// Reads the entries of the IFD at `offset`, returns them along with the
+/// position of the next IFD pointer (which may be out of the data). Returns
+/// `None` if the entries are out of the data.
+pub(crate) fn ifd_fields(
+    data: &[u8],
+    offset: usize,
+    endian: Endianness,
+) -> Option<(Vec<IfdField>, usize)> {
+    let num = u16_at(data, offset, endian)? as usize;
+    let start = offset + 2;
+    let end = start + num * IFD_ENTRY_SIZE;
+    let fields = (start..end)
+        .step_by(IFD_ENTRY_SIZE)
+        .map(|pos| {
+            Some(IfdField {
+                pos,
+                code: u16_at(data, pos, endian)?,
+                format: DataFormat::try_from(u16_at(data, pos + 2, endian)?).ok(),
+                count: u32_at(data, pos + 4, endian)?,
+                value: u32_at(data, pos + 8, endian)?,
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some((fields, end))
+}
+
+/// An IFD entry as it is stored in the TIFF data.
+pub(crate) struct RawField {
+    pub code: u16,
+    pub format: DataFormat,
+    pub count: u32,
+    // The value itself if it fits in 4 bytes, or its offset
+    pub field: [u8; 4],
+    // Range of the value in the TIFF data
+    pub value: Range<usize>,
+}
+
+/// Reads the entries of the IFD at `offset`, returns them along with the
+/// offset of the next IFD. Entries of unknown formats are skipped.
+pub(crate) fn read_fields(
+    data: &[u8],
+    offset: usize,
+    endian: Endianness,
+) -> crate::Result<(Vec<RawField>, u32)> {
+    if u16_at(data, offset, endian).is_none() {
+        return Err("invalid TIFF data; IFD not found".into());
+    }
+    let (fields, next_pos) =
+        ifd_fields(data, offset, endian).ok_or("invalid TIFF data; IFD is truncated")?;
+
+    let mut raw = Vec::with_capacity(fields.len());
+    for x in fields {
+        let Some(format) = x.format else {
+            continue;
+        };
+        let range = x.value_range();
+        if data.get(range.clone()).is_none() {
+            return Err("invalid TIFF data; entry value is out of range".into());
+        }
+        raw.push(RawField {
+            code: x.code,
+            format,
+            count: x.count,
+            field: u32_bytes(x.value, endian),
+            value: range,
+        });
+    }
+    let next = u32_at(data, next_pos, endian).unwrap_or_default();
+    Ok((raw, next))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fields() {
+        // 2 entries: a SHORT inline, and 2 LONGs out of line; then an entry
+        // of an unknown format
+        let mut data = b"II*\0\x08\0\0\0\x03\0".to_vec();
+        data.extend_from_slice(&[0x12, 0x01, 3, 0, 1, 0, 0, 0, 6, 0, 0, 0]);
+        data.extend_from_slice(&[0x00, 0x01, 4, 0, 2, 0, 0, 0, 0x32, 0, 0, 0]);
+        data.extend_from_slice(&[0x01, 0x01, 99, 0, 1, 0, 0, 0, 0, 0, 0, 0]);
+        data.extend_from_slice(&[0; 4]);
+        data.extend_from_slice(&[1, 0, 0, 0, 2, 0, 0, 0]);
+
+        let (fields, next_pos) = ifd_fields(&data, 8, Endianness::Little).unwrap();
+        assert_eq!(fields.len(), 3);
+        assert_eq!(next_pos, 8 + 2 + 36);
+        assert_eq!(fields[0].value_range(), 18..20);
+        assert_eq!(fields[1].value_range(), 0x32..0x3a);
+        assert_eq!(fields[2].size(), 0);
+
+        let (raw, next) = read_fields(&data, 8, Endianness::Little).unwrap();
+        assert_eq!(raw.len(), 2);
+        assert_eq!(next, 0);
+        assert_eq!(&data[raw[1].value.clone()], &[1, 0, 0, 0, 2, 0, 0, 0]);
+
+        // Truncated
+        assert!(ifd_fields(&data[..30], 8, Endianness::Little).is_none());
+        assert!(read_fields(&data[..40], 8, Endianness::Big).is_err());
+    }
+}
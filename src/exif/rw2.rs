@@ -0,0 +1,24 @@
+use super::tags::ExifTagCode;
+use crate::ExifTag;
+
+/// Panasonic RW2 files use `IIU\0` instead of the standard TIFF signature.
+pub(crate) const RW2_MAGIC: u16 = 0x55;
+
+/// Maps a tag code in ifd0 of RW2 files ("PanasonicRaw" tags, see
+/// <https://exiftool.org/TagNames/Panasonic.html#Raw>) to the standard one.
+///
+/// Standard TIFF tags (e.g. `Make`, `Model`, `ExifOffset`) keep their codes
+/// in RW2 files, only the Panasonic specific ones need to be mapped.
+pub(crate) fn map_rw2_tag(code: u16) -> ExifTagCode {
+    match code {
+        0x0017 => ExifTagCode::Tag(ExifTag::ISOSpeedRatings),
+        // Panasonic specific tags overlap GPS tag codes
+        x if is_panasonic_specific(x) => ExifTagCode::Code(x),
+        x => x.into(),
+    }
+}
+
+/// Returns true if `code` is a Panasonic specific tag of RW2 ifd0.
+pub(crate) fn is_panasonic_specific(code: u16) -> bool {
+    code < 0x0100
+}
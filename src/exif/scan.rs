@@ -0,0 +1,120 @@
+use crate::{partial_vec::PartialVec, ExifIter};
+
+use super::{exif_exif::EXIF_IDENT, exif_iter::input_into_iter};
+
+/// Maximum number of entries to be checked when validating a candidate.
+const VALIDATE_ENTRIES: usize = 8;
+
+/// Scans `data` for Exif data at any location, and returns an [`ExifIter`] for
+/// the first candidate which can be parsed.
+///
+/// This is a recovery mode for data which can't be handled by
+/// [`MediaParser`](crate::MediaParser), e.g.: files with a corrupted
+/// container, Exif data stored in a nonstandard segment, or raw memory dumps.
+///
+/// Both `Exif\0\0` signatures and bare TIFF headers (`II*\0` / `MM\0*`) are
+/// searched for. A candidate is accepted only if at least one of its first
+/// few IFD0 entries is a recognized tag with a valid value.
+///
+/// **Note**: This is much slower than the regular parsing routines, and may
+/// produce false positives on arbitrary data. Don't use it unless the regular
+/// parsing has failed.
+///
+/// ## Example
+///
+/// ```rust
+/// use nom_exif::*;
+///
+/// // Exif data which is hidden in a bunch of garbage
+/// let exif = std::fs::read("./testdata/exif.jpg").unwrap();
+/// let mut data = vec![0x5a; 100];
+/// data.extend_from_slice(&exif[6..]);
+///
+/// let iter = scan_exif(&data).unwrap();
+/// let exif: Exif = iter.into();
+/// assert_eq!(exif.get(ExifTag::Make).unwrap().as_str(), Some("vivo"));
+/// ```
+pub fn scan_exif(data: &[u8]) -> crate::Result<ExifIter> {
+    let candidates = find_tiff_header_candidates(data);
+    if candidates.is_empty() {
+        return Err("Exif not found".into());
+    }
+
+    let data = bytes::Bytes::copy_from_slice(data);
+    for pos in candidates {
+        let input = PartialVec::new(data.clone(), pos..data.len());
+        let Ok(iter) = input_into_iter(input, None) else {
+            continue;
+        };
+
+        let valid = iter
+            .clone_and_rewind()
+            .take(VALIDATE_ENTRIES)
+            .any(|x| x.tag().is_some() && x.has_value());
+        if valid {
+            tracing::debug!(pos, "Exif found by scanning");
+            return Ok(iter);
+        }
+    }
+
+    Err("Exif not found".into())
+}
+
+/// Returns positions of all possible TIFF headers in ascending order.
+fn find_tiff_header_candidates(data: &[u8]) -> Vec<usize> {
+    let mut candidates = data
+        .windows(EXIF_IDENT.len())
+        .enumerate()
+        .filter(|(_, w)| *w == EXIF_IDENT.as_bytes())
+        .map(|(pos, _)| pos + EXIF_IDENT.len())
+        .chain(
+            data.windows(4)
+                .enumerate()
+                .filter(|(_, w)| *w == b"II*\0" || *w == b"MM\0*")
+                .map(|(pos, _)| pos),
+        )
+        .collect::<Vec<_>>();
+    candidates.sort_unstable();
+    candidates.dedup();
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testkit::read_sample, Exif, ExifTag};
+    use test_case::test_case;
+
+    #[test_case("exif.jpg", 0, "vivo")]
+    #[test_case("exif.jpg", 1000, "vivo")]
+    #[test_case("exif.heic", 3, "Apple")]
+    #[test_case("broken.jpg", 10, "SAMSUNG")]
+    fn scan(path: &str, garbage: usize, make: &str) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let mut data = vec![0x4d; garbage];
+        data.extend(read_sample(path).unwrap());
+
+        let exif: Exif = scan_exif(&data).unwrap().into();
+        assert_eq!(exif.get(ExifTag::Make).unwrap().as_str(), Some(make));
+    }
+
+    #[test_case("no-exif.jpg")]
+    #[test_case("webm_480.webm")]
+    fn scan_nothing(path: &str) {
+        let data = read_sample(path).unwrap();
+        scan_exif(&data).unwrap_err();
+    }
+
+    #[test]
+    fn scan_broken_container() {
+        let mut data = read_sample("exif.jpg").unwrap();
+        // Corrupt the SOI & APP1 markers
+        data[..4].copy_from_slice(b"\0\0\0\0");
+        let exif: Exif = scan_exif(&data).unwrap().into();
+        assert_eq!(
+            exif.get(ExifTag::Model).unwrap().as_str(),
+            Some("vivo X90 Pro+")
+        );
+    }
+}
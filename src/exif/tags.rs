@@ -1,13 +1,17 @@
 //! Define exif tags and related enums, see
 //! https://exiftool.org/TagNames/EXIF.html
 
-use std::fmt::{Debug, Display};
+use std::{
+    fmt::{Debug, Display},
+    str::FromStr,
+    sync::LazyLock,
+};
 
-#[cfg(feature = "json_dump")]
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 #[allow(unused)]
-#[cfg_attr(feature = "json_dump", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Eq, PartialEq, Hash, Clone, Copy)]
 pub(crate) enum ExifTagCode {
     /// Recognized Exif tag
@@ -72,7 +76,7 @@ impl Display for ExifTagCode {
 /// See [`ParsedExifEntry::tag_code`](crate::ParsedExifEntry::tag_code) and
 /// [`Exif::get_by_tag_code`](crate::Exif::get_by_tag_code).
 #[allow(unused)]
-#[cfg_attr(feature = "json_dump", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Eq, PartialEq, Hash, Clone, Copy)]
 #[non_exhaustive]
 pub enum ExifTag {
@@ -210,12 +214,117 @@ pub enum ExifTag {
     SamplesPerPixel = 0x0000_0115,
     RowsPerStrip = 0x0000_0116,
     PlanarConfiguration = 0x0000_011c,
+    NewSubfileType = 0x0000_00fe,
+    SubIFDs = 0x0000_014a,
+
+    // Exif 3.0, the values may be UTF-8 strings
+    ImageTitle = 0x0000_a436,
+    Photographer = 0x0000_a437,
+    ImageEditor = 0x0000_a438,
+    CameraFirmware = 0x0000_a439,
+    RAWDevelopingSoftware = 0x0000_a43a,
+    ImageEditingSoftware = 0x0000_a43b,
+    MetadataEditingSoftware = 0x0000_a43c,
+
+    // Windows, UCS-2LE strings stored as BYTE arrays
+    XPTitle = 0x0000_9c9b,
+    XPComment = 0x0000_9c9c,
+    XPAuthor = 0x0000_9c9d,
+    XPKeywords = 0x0000_9c9e,
+    XPSubject = 0x0000_9c9f,
+
+    // GeoTIFF, see http://geotiff.maptools.org/spec/geotiff2.4.html
+    ModelPixelScale = 0x0000_830e,
+    ModelTiepoint = 0x0000_8482,
+    ModelTransformation = 0x0000_85d8,
+    GeoKeyDirectory = 0x0000_87af,
+    GeoDoubleParams = 0x0000_87b0,
+    GeoAsciiParams = 0x0000_87b1,
+
+    // DNG, see https://exiftool.org/TagNames/DNG.html
+    DNGVersion = 0x0000_c612,
+    DNGBackwardVersion = 0x0000_c613,
+    UniqueCameraModel = 0x0000_c614,
+    ProfileName = 0x0000_c6f8,
+    NoiseProfile = 0x0000_c761,
+    ProfileGainTableMap = 0x0000_cd2d,
+    SemanticName = 0x0000_cd2e,
+    SemanticInstanceID = 0x0000_cd30,
+    MaskSubArea = 0x0000_cd38,
 }
 
 impl ExifTag {
     pub const fn code(self) -> u16 {
         self as u16
     }
+
+    /// Returns the name of the tag, e.g. "DateTimeOriginal", which is the
+    /// same as its `Display` text, and is accepted by [`ExifTag::from_str`].
+    pub fn name(self) -> &'static str {
+        self.into()
+    }
+
+    /// All the recognized tags, in ascending order of tag codes.
+    fn all() -> &'static [ExifTag] {
+        static ALL: LazyLock<Vec<ExifTag>> =
+            LazyLock::new(|| (0..=u16::MAX).filter_map(|x| x.try_into().ok()).collect());
+        &ALL
+    }
+}
+
+/// Common aliases of tag names, e.g. the names in the Exif specification
+/// which differ from exiftool's.
+const ALIASES: [(&str, ExifTag); 17] = [
+    ("DateTime", ExifTag::ModifyDate),
+    ("DateTimeDigitized", ExifTag::CreateDate),
+    ("ISO", ExifTag::ISOSpeedRatings),
+    ("ISOSpeed", ExifTag::ISOSpeedRatings),
+    ("PhotographicSensitivity", ExifTag::ISOSpeedRatings),
+    ("ImageLength", ExifTag::ImageHeight),
+    ("PixelXDimension", ExifTag::ExifImageWidth),
+    ("PixelYDimension", ExifTag::ExifImageHeight),
+    ("ExifImageLength", ExifTag::ExifImageHeight),
+    ("ExifIFDPointer", ExifTag::ExifOffset),
+    ("GPSInfoIFDPointer", ExifTag::GPSInfo),
+    ("InteroperabilityIFDPointer", ExifTag::InteropOffset),
+    ("JPEGInterchangeFormat", ExifTag::ThumbnailOffset),
+    ("JPEGInterchangeFormatLength", ExifTag::ThumbnailLength),
+    ("WhiteBalance", ExifTag::WhiteBalanceMode),
+    ("FocalLengthIn35mmFormat", ExifTag::FocalLengthIn35mmFilm),
+    ("ExposureCompensation", ExifTag::ExposureBiasValue),
+];
+
+/// Looks up a tag by its name (case-insensitive, see [`ExifTag::name`]), a
+/// common alias (e.g. "DateTime" for [`ExifTag::ModifyDate`], "ISO" for
+/// [`ExifTag::ISOSpeedRatings`]), or a hex tag code (e.g. "0x9003").
+///
+/// ```rust
+/// use nom_exif::*;
+///
+/// assert_eq!("DateTimeOriginal".parse::<ExifTag>().unwrap(), ExifTag::DateTimeOriginal);
+/// assert_eq!("datetimeoriginal".parse::<ExifTag>().unwrap(), ExifTag::DateTimeOriginal);
+/// assert_eq!("ISO".parse::<ExifTag>().unwrap(), ExifTag::ISOSpeedRatings);
+/// assert_eq!("0x010f".parse::<ExifTag>().unwrap(), ExifTag::Make);
+/// assert!("NoSuchTag".parse::<ExifTag>().is_err());
+/// ```
+impl FromStr for ExifTag {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim();
+        if let Some(hex) = name.strip_prefix("0x").or_else(|| name.strip_prefix("0X")) {
+            if let Ok(code) = u16::from_str_radix(hex, 16) {
+                return ExifTag::try_from(code);
+            }
+        }
+        ExifTag::all()
+            .iter()
+            .map(|x| (x.name(), *x))
+            .chain(ALIASES)
+            .find(|(x, _)| x.eq_ignore_ascii_case(name))
+            .map(|(_, tag)| tag)
+            .ok_or_else(|| format!("Unrecognized ExifTag name {s:?}").into())
+    }
 }
 
 impl Display for ExifTag {
@@ -351,6 +460,35 @@ impl From<ExifTag> for &str {
             ExifTag::SamplesPerPixel => "SamplesPerPixel",
             ExifTag::RowsPerStrip => "RowsPerStrip",
             ExifTag::PlanarConfiguration => "PlanarConfiguration",
+            ExifTag::NewSubfileType => "NewSubfileType",
+            ExifTag::SubIFDs => "SubIFDs",
+            ExifTag::DNGVersion => "DNGVersion",
+            ExifTag::DNGBackwardVersion => "DNGBackwardVersion",
+            ExifTag::UniqueCameraModel => "UniqueCameraModel",
+            ExifTag::ProfileName => "ProfileName",
+            ExifTag::NoiseProfile => "NoiseProfile",
+            ExifTag::ProfileGainTableMap => "ProfileGainTableMap",
+            ExifTag::SemanticName => "SemanticName",
+            ExifTag::SemanticInstanceID => "SemanticInstanceID",
+            ExifTag::MaskSubArea => "MaskSubArea",
+            ExifTag::ImageTitle => "ImageTitle",
+            ExifTag::Photographer => "Photographer",
+            ExifTag::ImageEditor => "ImageEditor",
+            ExifTag::CameraFirmware => "CameraFirmware",
+            ExifTag::RAWDevelopingSoftware => "RAWDevelopingSoftware",
+            ExifTag::ImageEditingSoftware => "ImageEditingSoftware",
+            ExifTag::MetadataEditingSoftware => "MetadataEditingSoftware",
+            ExifTag::XPTitle => "XPTitle",
+            ExifTag::XPComment => "XPComment",
+            ExifTag::XPAuthor => "XPAuthor",
+            ExifTag::XPKeywords => "XPKeywords",
+            ExifTag::XPSubject => "XPSubject",
+            ExifTag::ModelPixelScale => "ModelPixelScale",
+            ExifTag::ModelTiepoint => "ModelTiepoint",
+            ExifTag::ModelTransformation => "ModelTransformation",
+            ExifTag::GeoKeyDirectory => "GeoKeyDirectory",
+            ExifTag::GeoDoubleParams => "GeoDoubleParams",
+            ExifTag::GeoAsciiParams => "GeoAsciiParams",
         }
     }
 }
@@ -484,6 +622,35 @@ impl TryFrom<u16> for ExifTag {
             x if x == SamplesPerPixel.code() => Self::SamplesPerPixel,
             x if x == RowsPerStrip.code() => Self::RowsPerStrip,
             x if x == PlanarConfiguration.code() => Self::PlanarConfiguration,
+            x if x == NewSubfileType.code() => Self::NewSubfileType,
+            x if x == SubIFDs.code() => Self::SubIFDs,
+            x if x == DNGVersion.code() => Self::DNGVersion,
+            x if x == DNGBackwardVersion.code() => Self::DNGBackwardVersion,
+            x if x == UniqueCameraModel.code() => Self::UniqueCameraModel,
+            x if x == ProfileName.code() => Self::ProfileName,
+            x if x == NoiseProfile.code() => Self::NoiseProfile,
+            x if x == ProfileGainTableMap.code() => Self::ProfileGainTableMap,
+            x if x == SemanticName.code() => Self::SemanticName,
+            x if x == SemanticInstanceID.code() => Self::SemanticInstanceID,
+            x if x == MaskSubArea.code() => Self::MaskSubArea,
+            x if x == ImageTitle.code() => Self::ImageTitle,
+            x if x == Photographer.code() => Self::Photographer,
+            x if x == ImageEditor.code() => Self::ImageEditor,
+            x if x == CameraFirmware.code() => Self::CameraFirmware,
+            x if x == RAWDevelopingSoftware.code() => Self::RAWDevelopingSoftware,
+            x if x == ImageEditingSoftware.code() => Self::ImageEditingSoftware,
+            x if x == MetadataEditingSoftware.code() => Self::MetadataEditingSoftware,
+            x if x == XPTitle.code() => Self::XPTitle,
+            x if x == XPComment.code() => Self::XPComment,
+            x if x == XPAuthor.code() => Self::XPAuthor,
+            x if x == XPKeywords.code() => Self::XPKeywords,
+            x if x == XPSubject.code() => Self::XPSubject,
+            x if x == ModelPixelScale.code() => Self::ModelPixelScale,
+            x if x == ModelTiepoint.code() => Self::ModelTiepoint,
+            x if x == ModelTransformation.code() => Self::ModelTransformation,
+            x if x == GeoKeyDirectory.code() => Self::GeoKeyDirectory,
+            x if x == GeoDoubleParams.code() => Self::GeoDoubleParams,
+            x if x == GeoAsciiParams.code() => Self::GeoAsciiParams,
 
             o => return Err(format!("Unrecognized ExifTag 0x{o:04x}").into()),
         };
@@ -492,14 +659,52 @@ impl TryFrom<u16> for ExifTag {
     }
 }
 
-#[allow(unused)]
-pub enum Orientation {
-    Horizontal,
-    MirrorHorizontal,
-    Rotate,
-    MirrorVertical,
-    MirrorHorizontalRotate270,
-    Rotate90,
-    MirrorHorizontalRotate90,
-    Rotate270,
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case(0xc612, ExifTag::DNGVersion)]
+    #[test_case(0xc614, ExifTag::UniqueCameraModel)]
+    #[test_case(0x014a, ExifTag::SubIFDs)]
+    #[test_case(0xc6f8, ExifTag::ProfileName)]
+    #[test_case(0xc761, ExifTag::NoiseProfile)]
+    #[test_case(0xcd2d, ExifTag::ProfileGainTableMap)]
+    #[test_case(0xcd2e, ExifTag::SemanticName)]
+    #[test_case(0x87af, ExifTag::GeoKeyDirectory)]
+    #[test_case(0xa436, ExifTag::ImageTitle)]
+    #[test_case(0xa437, ExifTag::Photographer)]
+    #[test_case(0xa43c, ExifTag::MetadataEditingSoftware)]
+    #[test_case(0x9c9b, ExifTag::XPTitle)]
+    #[test_case(0x9c9f, ExifTag::XPSubject)]
+    fn tag_codes(code: u16, tag: ExifTag) {
+        assert_eq!(ExifTag::try_from(code).unwrap(), tag);
+        assert_eq!(tag.code(), code);
+        assert_eq!(tag.to_string(), format!("{tag:?}"));
+    }
+
+    #[test_case("Make", Some(ExifTag::Make))]
+    #[test_case(" gpslatitude ", Some(ExifTag::GPSLatitude))]
+    #[test_case("DATETIME", Some(ExifTag::ModifyDate))]
+    #[test_case("PixelXDimension", Some(ExifTag::ExifImageWidth))]
+    #[test_case("0x9003", Some(ExifTag::DateTimeOriginal))]
+    #[test_case("0x1234", None)]
+    #[test_case("0xzz", None)]
+    #[test_case("", None)]
+    fn from_str(name: &str, tag: Option<ExifTag>) {
+        assert_eq!(name.parse::<ExifTag>().ok(), tag);
+    }
+
+    #[test]
+    fn names() {
+        assert!(ExifTag::all().len() > 100);
+        for tag in ExifTag::all() {
+            assert_eq!(tag.name().parse::<ExifTag>().unwrap(), *tag);
+            assert_eq!(ExifTag::try_from(tag.code()).unwrap(), *tag);
+        }
+        for (alias, tag) in ALIASES {
+            assert!(ExifTag::all().iter().all(|x| x.name() != alias), "{alias}");
+            assert_eq!(alias.parse::<ExifTag>().unwrap(), tag);
+        }
+    }
 }
@@ -0,0 +1,154 @@
+use nom::number::Endianness;
+
+use crate::ExifTag;
+
+/// The encoding used to decode text whose character set is unknown, e.g. a
+/// [`UserComment`](crate::ExifTag::UserComment) with an "undefined" (or JIS)
+/// character code, see [`ExifIter::set_text_encoding`](crate::ExifIter::set_text_encoding).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum TextEncoding {
+    /// UTF-8, invalid sequences are replaced with U+FFFD
+    #[default]
+    Utf8,
+    /// ISO 8859-1, i.e. each byte is a character
+    Latin1,
+}
+
+impl TextEncoding {
+    pub(crate) fn decode(&self, data: &[u8]) -> String {
+        match self {
+            Self::Utf8 => String::from_utf8_lossy(data).into_owned(),
+            Self::Latin1 => data.iter().map(|x| *x as char).collect(),
+        }
+    }
+}
+
+const ASCII: &[u8; 8] = b"ASCII\0\0\0";
+const JIS: &[u8; 8] = b"JIS\0\0\0\0\0";
+const UNICODE: &[u8; 8] = b"UNICODE\0";
+
+/// Decodes a `UserComment` value, whose first 8 bytes identify the character
+/// code:
+///
+/// - "ASCII": decoded with `fallback` as well, since non-ASCII bytes are
+///   often found in practice
+/// - "UNICODE": UTF-16, in the byte order of its BOM if any, otherwise in the
+///   byte order of the TIFF data
+/// - "JIS" (JIS X 0208) & undefined (all zeros): decoded with `fallback`
+///
+/// Trailing NULs & spaces are trimmed.
+pub(crate) fn decode_user_comment(
+    data: &[u8],
+    endian: Endianness,
+    fallback: TextEncoding,
+) -> String {
+    let (code, text) = data.split_at_checked(8).unwrap_or((&[], data));
+    let s = if code == UNICODE {
+        decode_utf16(text, endian)
+    } else if code == ASCII || code == JIS || code.iter().all(|x| *x == 0) {
+        fallback.decode(text)
+    } else {
+        // Missing or unknown character code
+        fallback.decode(data)
+    };
+    s.trim_end_matches(['\0', ' ']).to_owned()
+}
+
+/// Encodes `s` as a `UserComment` value, with the "ASCII" character code if
+/// possible, otherwise "UNICODE".
+pub(crate) fn encode_user_comment(s: &str, endian: Endianness) -> Vec<u8> {
+    if s.is_ascii() {
+        [ASCII.as_slice(), s.as_bytes()].concat()
+    } else {
+        let mut data = UNICODE.to_vec();
+        data.extend(s.encode_utf16().flat_map(|x| match endian {
+            Endianness::Little => x.to_le_bytes(),
+            _ => x.to_be_bytes(),
+        }));
+        data
+    }
+}
+
+/// Returns whether `tag` is one of the Windows XP* tags, e.g. `XPTitle`,
+/// whose values are UCS-2LE strings stored as BYTE arrays.
+pub(crate) fn is_xp_tag(tag: ExifTag) -> bool {
+    matches!(
+        tag,
+        ExifTag::XPTitle
+            | ExifTag::XPComment
+            | ExifTag::XPAuthor
+            | ExifTag::XPKeywords
+            | ExifTag::XPSubject
+    )
+}
+
+/// Decodes the value of a Windows XP* tag, trailing NULs are trimmed.
+pub(crate) fn decode_xp_text(data: &[u8]) -> String {
+    decode_utf16(data, Endianness::Little)
+        .trim_end_matches('\0')
+        .to_owned()
+}
+
+/// Encodes `s` as the value of a Windows XP* tag, i.e. NUL terminated
+/// UCS-2LE.
+pub(crate) fn encode_xp_text(s: &str) -> Vec<u8> {
+    s.encode_utf16()
+        .chain([0])
+        .flat_map(|x| x.to_le_bytes())
+        .collect()
+}
+
+/// Decodes UTF-16 text in the byte order of its BOM if any, otherwise in
+/// `endian`. Invalid code units are replaced with U+FFFD.
+pub(crate) fn decode_utf16(data: &[u8], endian: Endianness) -> String {
+    let (data, endian) = match data {
+        [0xfe, 0xff, rest @ ..] => (rest, Endianness::Big),
+        [0xff, 0xfe, rest @ ..] => (rest, Endianness::Little),
+        _ => (data, endian),
+    };
+    let units = data.chunks_exact(2).map(|x| match endian {
+        Endianness::Little => u16::from_le_bytes([x[0], x[1]]),
+        _ => u16::from_be_bytes([x[0], x[1]]),
+    });
+    char::decode_utf16(units)
+        .map(|x| x.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case(b"ASCII\0\0\0hello  \0", TextEncoding::Utf8, "hello")]
+    #[test_case(b"UNICODE\0\0h\0i", TextEncoding::Utf8, "hi")]
+    #[test_case(b"UNICODE\0\xff\xfeh\0i\0", TextEncoding::Utf8, "hi")]
+    #[test_case(b"\0\0\0\0\0\0\0\0caf\xc3\xa9", TextEncoding::Utf8, "café")]
+    #[test_case(b"\0\0\0\0\0\0\0\0caf\xe9", TextEncoding::Latin1, "café")]
+    #[test_case(b"JIS\0\0\0\0\0caf\xe9", TextEncoding::Utf8, "caf\u{fffd}")]
+    #[test_case(b"\0\0\0\0\0\0\0\0        ", TextEncoding::Utf8, "")]
+    #[test_case(b"abc", TextEncoding::Utf8, "abc")]
+    fn user_comment(data: &[u8], fallback: TextEncoding, expect: &str) {
+        assert_eq!(decode_user_comment(data, Endianness::Big, fallback), expect);
+    }
+
+    #[test_case(b"T\0i\0t\0l\0e\0\0\0", "Title")]
+    #[test_case(b"\x99\x51\x1f\x77\0\0", "写真")]
+    #[test_case(b"a\0\0\xd8", "a\u{fffd}")]
+    fn xp_text(data: &[u8], expect: &str) {
+        assert_eq!(decode_xp_text(data), expect);
+        if !expect.contains('\u{fffd}') {
+            assert_eq!(encode_xp_text(expect), data);
+        }
+    }
+
+    #[test_case("hello", Endianness::Big)]
+    #[test_case("写真 café", Endianness::Big)]
+    #[test_case("写真 café", Endianness::Little)]
+    fn encode_decode(s: &str, endian: Endianness) {
+        let data = encode_user_comment(s, endian);
+        assert_eq!(decode_user_comment(&data, endian, TextEncoding::Latin1), s);
+    }
+}
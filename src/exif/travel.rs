@@ -6,7 +6,13 @@ use nom::{
 
 use crate::{error::ParsingError, exif::TiffHeader, values::DataFormat, ExifTag};
 
-use super::{exif_exif::IFD_ENTRY_SIZE, exif_iter::SUBIFD_TAGS};
+use super::{
+    exif_exif::IFD_ENTRY_SIZE,
+    exif_iter::{MAX_SUB_IFDS, SUBIFD_TAGS},
+};
+
+// Maximum number of pages to travel in a multi-page TIFF file
+const MAX_PAGES: usize = 1024;
 
 /// Only iterates headers, don't parse entries.
 ///
@@ -30,7 +36,8 @@ pub(crate) struct EntryInfo<'a> {
     pub data_format: DataFormat,
     #[allow(unused)]
     pub data_offset: Option<u32>,
-    pub sub_ifd_offset: Option<u32>,
+    // Offsets relative to the TIFF header
+    pub sub_ifd_offsets: Vec<u32>,
 }
 
 impl<'a> IfdHeaderTravel<'a> {
@@ -72,10 +79,10 @@ impl<'a> IfdHeaderTravel<'a> {
         let component_size = data_format.component_size();
 
         // get entry data
-        let size = components_num as usize * component_size;
+        let size = (components_num as usize).saturating_mul(component_size);
         let (data, data_offset) = if size > 4 {
             let start = self.get_data_pos(value_or_offset) as usize;
-            let end = start + size;
+            let end = start.saturating_add(size);
             tracing::debug!(
                 components_num,
                 size,
@@ -95,11 +102,20 @@ impl<'a> IfdHeaderTravel<'a> {
             (entry_data, None)
         };
 
-        let sub_ifd_offset = if SUBIFD_TAGS.contains(&tag) {
-            let offset = self.get_data_pos(value_or_offset);
-            Some(offset)
+        let sub_ifd_offsets = if SUBIFD_TAGS.contains(&tag) {
+            vec![value_or_offset]
+        } else if tag == ExifTag::SubIFDs.code() && components_num > 0 {
+            if data_offset.is_some() {
+                data.chunks_exact(4)
+                    .take(MAX_SUB_IFDS)
+                    .filter_map(|x| streaming::u32::<_, ()>(self.endian)(x).ok())
+                    .map(|x| x.1)
+                    .collect()
+            } else {
+                vec![value_or_offset]
+            }
         } else {
-            None
+            Vec::new()
         };
 
         let entry = EntryInfo {
@@ -107,7 +123,7 @@ impl<'a> IfdHeaderTravel<'a> {
             data,
             data_format,
             data_offset,
-            sub_ifd_offset,
+            sub_ifd_offsets,
         };
         Ok((&[][..], Some(entry)))
     }
@@ -116,37 +132,61 @@ impl<'a> IfdHeaderTravel<'a> {
         value_or_offset.saturating_sub(self.offset)
     }
 
-    fn parse_ifd_entry_header(&self, pos: u32) -> IResult<&[u8], Option<IfdHeaderTravel<'a>>> {
+    fn parse_ifd_entry_header(&self, pos: u32) -> IResult<&[u8], Vec<IfdHeaderTravel<'a>>> {
         let (_, entry_data) =
-            nom::bytes::streaming::take(IFD_ENTRY_SIZE)(&self.ifd_data[pos as usize..])?;
+            nom::bytes::streaming::take(IFD_ENTRY_SIZE)(self.data_from(pos as usize)?)?;
 
         let (remain, entry) = self.parse_tag_entry_header(entry_data)?;
 
+        let mut sub_ifds = Vec::new();
         if let Some(entry) = entry {
             // if !cb(&entry) {
             //     return Ok((&[][..], ()));
             // }
 
-            if let Some(offset) = entry.sub_ifd_offset {
-                let tag: ExifTag = entry.tag.try_into().unwrap();
-                println!("sub-ifd: {:?}", tag);
+            for offset in entry.sub_ifd_offsets {
+                tracing::debug!(tag = ?ExifTag::try_from(entry.tag).ok(), "sub-ifd");
+                let pos = self.get_data_pos(offset);
                 let sub_ifd =
-                    IfdHeaderTravel::new(&self.ifd_data[offset as usize..], offset, self.endian);
-                return Ok((remain, Some(sub_ifd)));
+                    IfdHeaderTravel::new(self.data_from(pos as usize)?, offset, self.endian);
+                sub_ifds.push(sub_ifd);
             }
         }
 
-        Ok((remain, None))
+        Ok((remain, sub_ifds))
     }
 
+    fn data_from(&self, pos: usize) -> Result<&'a [u8], nom::Err<nom::error::Error<&'a [u8]>>> {
+        self.ifd_data
+            .get(pos..)
+            .ok_or_else(|| nom::Err::Incomplete(Needed::new(pos - self.ifd_data.len())))
+    }
+
+    /// Travels ifd0 and all the IFDs chained after it, i.e. all pages of a
+    /// multi-page TIFF file.
+    pub fn travel_pages(mut self) -> Result<(), ParsingError> {
+        for _ in 0..MAX_PAGES {
+            let next = self.travel_ifd(0)?;
+            // Only follow forward offsets, so reference loops in corrupted
+            // files are impossible.
+            if next <= self.offset {
+                return Ok(());
+            }
+            let data = self.data_from(self.get_data_pos(next) as usize)?;
+            self = IfdHeaderTravel::new(data, next, self.endian);
+        }
+        tracing::warn!(MAX_PAGES, "Ignored: too many pages");
+        Ok(())
+    }
+
+    /// Returns the offset of the next IFD, or 0 if there is none.
     #[tracing::instrument(skip(self))]
-    pub fn travel_ifd(&mut self, depth: usize) -> Result<(), ParsingError> {
-        // Currently, we ignore ifd1 data in *.tif files
+    pub fn travel_ifd(&mut self, depth: usize) -> Result<u32, ParsingError> {
+        // Nested sub-ifds (which are unexpected, and may be a reference loop
+        // in corrupted files) are ignored.
         if depth > 1 {
-            let msg = "depth shouldn't be greater than 1";
-            tracing::error!(msg);
-            debug_assert!(false, "{}", msg);
-            return Err(ParsingError::Failed(msg.into()));
+            tracing::warn!(depth, "Ignored: nested sub-ifd");
+            return Ok(0);
         }
 
         tracing::debug!(ifd_data_len = self.ifd_data.len(), offset = self.offset);
@@ -157,20 +197,19 @@ impl<'a> IfdHeaderTravel<'a> {
 
         // parse entries
         for _ in 0..entry_num {
-            let (_, sub_ifd) = self.parse_ifd_entry_header(pos as u32)?;
+            let (_, ifds) = self.parse_ifd_entry_header(pos as u32)?;
             pos += IFD_ENTRY_SIZE;
 
-            if let Some(ifd) = sub_ifd {
-                sub_ifds.push(ifd);
-            }
+            sub_ifds.extend(ifds);
         }
 
         for mut ifd in sub_ifds {
             ifd.travel_ifd(depth + 1)?;
         }
 
-        // ignore ifd1 in TIFF
-        Ok(())
+        let (_, next) =
+            streaming::u32::<_, nom::error::Error<_>>(self.endian)(self.data_from(pos)?)?;
+        Ok(next)
     }
 }
 
@@ -0,0 +1,1272 @@
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+
+use nom::number::Endianness;
+
+use crate::{
+    jpeg::{self, MarkerCode},
+    values::DataFormat,
+    EntryValue, Error, Exif, ExifTag, GPSInfo, LatLng, URational,
+};
+
+use super::{
+    check_exif_header, read_fields,
+    text::{encode_user_comment, encode_xp_text, is_xp_tag},
+    u16_at, u16_bytes, u32_bytes, RawField, TiffHeader, EXIF_IDENT,
+};
+
+const EXIF_OFFSET: u16 = 0x8769;
+const GPS_INFO: u16 = 0x8825;
+const INTEROP_OFFSET: u16 = 0xa005;
+const MAKER_NOTE: u16 = 0x927c;
+
+/// Tags which store offsets into the original TIFF data, they are meaningless
+/// after the data has been re-serialized, so they are never copied from an
+/// [`Exif`].
+const OFFSET_TAGS: [u16; 8] = [
+    EXIF_OFFSET,
+    GPS_INFO,
+    INTEROP_OFFSET,
+    0x014a, // SubIFDs
+    0x0111, // StripOffsets
+    0x0117, // StripByteCounts
+    0x0201, // ThumbnailOffset
+    0x0202, // ThumbnailLength
+];
+
+/// Builds Exif data from tag/value pairs (or from a parsed [`Exif`]), and
+/// writes it into JPEG (the APP1 segment), TIFF or HEIF files.
+///
+/// Tags are placed into the right IFD (ifd0, the Exif sub-IFD or the GPS
+/// sub-IFD) automatically according to their tag codes, the same way as
+/// [`Exif`] flattens them when reading. The data is serialized in big endian,
+/// except for TIFF files, whose byte order is kept.
+///
+/// Unknown tags & MakerNotes are re-encoded from their values by default,
+/// use [`ExifWriter::preserve_unknown`] to keep them byte for byte.
+///
+/// Errors of reading or parsing the input are returned as
+/// [`Error::ParseFailed`], while errors of writing the output (e.g. a full
+/// disk) are returned as [`Error::IOError`].
+///
+/// ## Example
+///
+/// ```rust
+/// use nom_exif::*;
+/// use std::fs::File;
+///
+/// fn main() -> Result<()> {
+///     let mut parser = MediaParser::new();
+///     let ms = MediaSource::file_path("./testdata/exif.jpg")?;
+///     let iter: ExifIter = parser.parse(ms)?;
+///     let exif: Exif = iter.into();
+///
+///     let mut writer = ExifWriter::from_exif(&exif);
+///     writer
+///         .set(ExifTag::Make, "nom-exif")
+///         .set(ExifTag::Orientation, 1_u16);
+///
+///     let mut output = Vec::new();
+///     writer.write_jpeg(File::open("./testdata/exif.jpg")?, &mut output)?;
+///
+///     let ms = MediaSource::seekable(std::io::Cursor::new(output))?;
+///     let iter: ExifIter = parser.parse(ms)?;
+///     let exif: Exif = iter.into();
+///     assert_eq!(exif.get(ExifTag::Make), Some(&"nom-exif".into()));
+///     assert_eq!(exif.get(ExifTag::Model), Some(&"vivo X90 Pro+".into()));
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExifWriter {
+    // Entries of ifd0 (including the Exif & GPS sub-IFDs), ifd1, ...
+    ifds: Vec<BTreeMap<u16, EntryValue>>,
+    preserve_unknown: bool,
+}
+
+impl ExifWriter {
+    pub fn new() -> ExifWriter {
+        ExifWriter::default()
+    }
+
+    /// Creates a writer which contains all the entries of ifd0 (including the
+    /// Exif & GPS sub-IFDs) of `exif`.
+    ///
+    /// *Note*:
+    ///
+    /// - Entries which store offsets of the original data (e.g. the thumbnail
+    ///   offset) are not copied, nor is ifd1 (the thumbnail image).
+    ///
+    /// - The MakerNote is copied as is. MakerNotes whose offsets are relative
+    ///   to the TIFF header (e.g. Canon's) may be invalid after writing,
+    ///   unless [`ExifWriter::preserve_unknown`] is enabled.
+    pub fn from_exif(exif: &Exif) -> ExifWriter {
+        let mut writer = ExifWriter::new();
+        for (code, value) in exif.ifd_entries(0) {
+            if !OFFSET_TAGS.contains(&code) {
+                writer.insert(0, code, value.to_owned());
+            }
+        }
+        writer
+    }
+
+    /// Enables (or disables) the "preserve unknown" mode, in which unknown
+    /// tags (i.e. tag codes which aren't [`ExifTag`]s) and the MakerNote of
+    /// the original Exif data are written byte for byte, instead of being
+    /// re-encoded from the values of this writer.
+    ///
+    /// In this mode, the original TIFF data is kept as a whole, and the new
+    /// IFDs are appended to it, so that values referenced by absolute offsets
+    /// (e.g. the internal offsets of MakerNotes) stay valid. The original
+    /// ifd0, Exif & GPS IFDs are zeroed, except for the preserved values, and
+    /// the Interop IFD & ifd1 (the thumbnail) are kept as well, unless this
+    /// writer has entries of ifd1.
+    ///
+    /// *Note*:
+    ///
+    /// - The mode changes how the entries are written, not which: only the
+    ///   unknown tags & the MakerNote that this writer holds (e.g. copied by
+    ///   [`ExifWriter::from_exif`]) are preserved, so removing them still
+    ///   works, but their new values are ignored if the original data has
+    ///   them.
+    ///
+    /// - The written Exif data is larger, since the original data is kept,
+    ///   which may exceed the limit of a JPEG segment (64 KB).
+    ///
+    /// ```rust
+    /// use nom_exif::*;
+    /// use std::fs::File;
+    ///
+    /// fn main() -> Result<()> {
+    ///     let mut parser = MediaParser::new();
+    ///     let ms = MediaSource::file_path("./testdata/exif.jpg")?;
+    ///     let iter: ExifIter = parser.parse(ms)?;
+    ///     let exif: Exif = iter.into();
+    ///
+    ///     let mut writer = ExifWriter::from_exif(&exif);
+    ///     writer.preserve_unknown(true).set(ExifTag::Make, "nom-exif");
+    ///
+    ///     let mut output = Vec::new();
+    ///     writer.write_jpeg(File::open("./testdata/exif.jpg")?, &mut output)?;
+    ///
+    ///     let ms = MediaSource::seekable(std::io::Cursor::new(output))?;
+    ///     let iter: ExifIter = parser.parse(ms)?;
+    ///     let written: Exif = iter.into();
+    ///     assert_eq!(written.get(ExifTag::Make), Some(&"nom-exif".into()));
+    ///     assert_eq!(written.get(ExifTag::MakerNote), exif.get(ExifTag::MakerNote));
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn preserve_unknown(&mut self, preserve: bool) -> &mut Self {
+        self.preserve_unknown = preserve;
+        self
+    }
+
+    /// Set the `value` of `tag` in ifd0 (the main image).
+    ///
+    /// When setting a [`EntryValue::Time`] for `DateTimeOriginal`,
+    /// `CreateDate` or `ModifyDate`, the corresponding `OffsetTime*` tag is
+    /// set as well, so that the time zone is kept.
+    pub fn set(&mut self, tag: ExifTag, value: impl Into<EntryValue>) -> &mut Self {
+        self.set_by_ifd_tag_code(0, tag.code(), value)
+    }
+
+    /// Set the `value` of the raw tag code `tag` in the specified `ifd` (0:
+    /// the main image, 1: the thumbnail image, etc.).
+    pub fn set_by_ifd_tag_code(
+        &mut self,
+        ifd: usize,
+        tag: u16,
+        value: impl Into<EntryValue>,
+    ) -> &mut Self {
+        let value = value.into();
+        if let (Some(offset_tag), EntryValue::Time(t)) = (offset_time_tag(tag), &value) {
+            let offset = t.format("%:z").to_string();
+            self.insert(ifd, offset_tag.code(), offset.into());
+        }
+        self.insert(ifd, tag, value);
+        self
+    }
+
+    /// Get the value of `tag` in ifd0.
+    pub fn get(&self, tag: ExifTag) -> Option<&EntryValue> {
+        self.get_by_ifd_tag_code(0, tag.code())
+    }
+
+    /// Get the value of the raw tag code `tag` in the specified `ifd`.
+    pub fn get_by_ifd_tag_code(&self, ifd: usize, tag: u16) -> Option<&EntryValue> {
+        self.ifds.get(ifd).and_then(|x| x.get(&tag))
+    }
+
+    /// Remove `tag` from ifd0, returns the removed value.
+    pub fn remove(&mut self, tag: ExifTag) -> Option<EntryValue> {
+        self.remove_by_ifd_tag_code(0, tag.code())
+    }
+
+    /// Remove the raw tag code `tag` from the specified `ifd`, returns the
+    /// removed value.
+    pub fn remove_by_ifd_tag_code(&mut self, ifd: usize, tag: u16) -> Option<EntryValue> {
+        self.ifds.get_mut(ifd).and_then(|x| x.remove(&tag))
+    }
+
+    /// Replaces the GPS info of ifd0 with `gps`, i.e. all existing GPS tags
+    /// are removed, then `GPSVersionID` (2.3.0.0), the latitude, longitude &
+    /// altitude with their refs, the optional fields which are present (speed,
+    /// timestamp, image direction, destination bearing & DOP) and
+    /// `GPSMapDatum` ("WGS-84") are set.
+    pub fn set_gps(&mut self, gps: &GPSInfo) -> &mut Self {
+        self.remove_gps();
+        let text = |c: char| EntryValue::Text(c.to_string());
+        let lat_lng = |x: &LatLng| EntryValue::URationalArray(vec![x.0, x.1, x.2]);
+
+        self.set(ExifTag::GPSVersionID, EntryValue::U8Array(vec![2, 3, 0, 0]));
+        if gps.latitude_ref != '\0' {
+            self.set(ExifTag::GPSLatitudeRef, text(gps.latitude_ref));
+        }
+        self.set(ExifTag::GPSLatitude, lat_lng(&gps.latitude));
+        if gps.longitude_ref != '\0' {
+            self.set(ExifTag::GPSLongitudeRef, text(gps.longitude_ref));
+        }
+        self.set(ExifTag::GPSLongitude, lat_lng(&gps.longitude));
+        // An altitude of 0/0 means unknown
+        if gps.altitude.1 != 0 {
+            self.set(ExifTag::GPSAltitudeRef, gps.altitude_ref)
+                .set(ExifTag::GPSAltitude, EntryValue::URational(gps.altitude));
+        }
+        if let (Some(speed_ref), Some(speed)) = (gps.speed_ref, gps.speed) {
+            self.set(ExifTag::GPSSpeedRef, text(speed_ref))
+                .set(ExifTag::GPSSpeed, EntryValue::URational(speed));
+        }
+        if let Some(date) = &gps.date_stamp {
+            self.set(ExifTag::GPSDateStamp, date.as_str());
+        }
+        if let Some([h, m, s]) = gps.time_stamp {
+            self.set(
+                ExifTag::GPSTimeStamp,
+                EntryValue::URationalArray(vec![h, m, s]),
+            );
+        }
+        if let Some(v) = gps.img_direction {
+            if let Some(r) = gps.img_direction_ref {
+                self.set(ExifTag::GPSImgDirectionRef, text(r));
+            }
+            self.set(ExifTag::GPSImgDirection, EntryValue::URational(v));
+        }
+        if let Some(v) = gps.dest_bearing {
+            if let Some(r) = gps.dest_bearing_ref {
+                self.set(ExifTag::GPSDestBearingRef, text(r));
+            }
+            self.set(ExifTag::GPSDestBearing, EntryValue::URational(v));
+        }
+        if let Some(v) = gps.dop {
+            self.set(ExifTag::GPSDOP, EntryValue::URational(v));
+        }
+        self.set(ExifTag::GPSMapDatum, "WGS-84")
+    }
+
+    /// Replaces the GPS info of ifd0 with a location in decimal degrees
+    /// (negative for south/west), and an optional altitude in meters
+    /// (negative for below sea level), see [`ExifWriter::set_gps`].
+    ///
+    /// Returns [`Error::WriteFailed`] if the location is out of range.
+    ///
+    /// ```rust
+    /// use nom_exif::*;
+    ///
+    /// let mut writer = ExifWriter::new();
+    /// writer.set_gps_decimal(48.8577, 2.295, Some(35.0)).unwrap();
+    /// assert_eq!(writer.get(ExifTag::GPSLatitudeRef), Some(&"N".into()));
+    /// assert!(writer.set_gps_decimal(91.0, 0.0, None).is_err());
+    /// ```
+    pub fn set_gps_decimal(
+        &mut self,
+        latitude: f64,
+        longitude: f64,
+        altitude: Option<f64>,
+    ) -> crate::Result<&mut Self> {
+        if !(-90.0..=90.0).contains(&latitude)
+            || !(-180.0..=180.0).contains(&longitude)
+            || altitude.is_some_and(|x| !x.is_finite() || x.abs() > u32::MAX as f64 / 1000.0)
+        {
+            return Err(Error::WriteFailed(
+                format!("invalid GPS location: {latitude}, {longitude}, {altitude:?}").into(),
+            ));
+        }
+        let gps = GPSInfo {
+            latitude_ref: if latitude < 0.0 { 'S' } else { 'N' },
+            latitude: decimal_to_dms(latitude.abs()),
+            longitude_ref: if longitude < 0.0 { 'W' } else { 'E' },
+            longitude: decimal_to_dms(longitude.abs()),
+            altitude_ref: altitude.map_or(0, |x| u8::from(x < 0.0)),
+            altitude: altitude.map_or(URational::default(), |x| {
+                ((x.abs() * 1000.0).round() as u32, 1000).into()
+            }),
+            ..Default::default()
+        };
+        Ok(self.set_gps(&gps))
+    }
+
+    /// Removes all GPS tags from ifd0.
+    pub fn remove_gps(&mut self) -> &mut Self {
+        self.retain(0, |code| sub_ifd_of(code) != Some(GPS_INFO));
+        self
+    }
+
+    /// Keeps only the entries of `ifd` whose raw tag codes are matched by
+    /// `f`.
+    pub(crate) fn retain(&mut self, ifd: usize, mut f: impl FnMut(u16) -> bool) {
+        if let Some(entries) = self.ifds.get_mut(ifd) {
+            entries.retain(|code, _| f(*code));
+        }
+    }
+
+    fn insert(&mut self, ifd: usize, tag: u16, value: EntryValue) {
+        if self.ifds.len() <= ifd {
+            self.ifds.resize_with(ifd + 1, BTreeMap::new);
+        }
+        self.ifds[ifd].insert(tag, value);
+    }
+
+    /// Serializes the entries into TIFF data, i.e. the Exif data without the
+    /// "Exif\0\0" prefix.
+    ///
+    /// Returns [`Error::WriteFailed`] if a value can't be represented in TIFF
+    /// (e.g. [`EntryValue::U64`]).
+    pub fn to_tiff_bytes(&self) -> crate::Result<Vec<u8>> {
+        let mut buf = b"MM\0\x2a\0\0\0\x08".to_vec();
+        buf.extend(self.encode_ifds(Endianness::Big, 8, EncodedEntries::default(), 0)?);
+        Ok(buf)
+    }
+
+    /// Serializes the entries into TIFF data, keeping the `original` TIFF
+    /// data in the "preserve unknown" mode, see
+    /// [`ExifWriter::preserve_unknown`].
+    fn to_tiff_bytes_with(&self, original: Option<&[u8]>) -> crate::Result<Vec<u8>> {
+        let Some(original) = original.filter(|_| self.preserve_unknown) else {
+            return self.to_tiff_bytes();
+        };
+        let (_, header) = TiffHeader::parse(original)?;
+        let mut data = original.to_vec();
+        let (extra, ifd1) =
+            self.clear_original_ifds(&mut data, header.endian, header.ifd0_offset as usize)?;
+        let next_ifd = if self.ifds.iter().skip(1).any(|x| !x.is_empty()) {
+            0
+        } else {
+            ifd1
+        };
+        self.append_ifds(&mut data, header.endian, extra, next_ifd as usize)?;
+        Ok(data)
+    }
+
+    /// Returns the entries of ifd0 and its Exif & GPS sub-IFDs of the
+    /// original TIFF `data` which are kept in the "preserve unknown" mode,
+    /// along with the offset of ifd1, then zeroes the rest of these IFDs (and
+    /// the Interop IFD if it's not kept), so that no removed value is left in
+    /// the data.
+    ///
+    /// The entries are kept with their original value fields, so that
+    /// out-of-line values are referenced at their original offsets. The
+    /// values of the image data entries of ifd0 are not zeroed either, see
+    /// [`ExifWriter::write_tiff`].
+    fn clear_original_ifds(
+        &self,
+        data: &mut [u8],
+        endian: Endianness,
+        ifd0: usize,
+    ) -> crate::Result<(EncodedEntries, u32)> {
+        let held = |code: u16| {
+            self.preserve_unknown
+                && self.ifds.first().is_some_and(|x| x.contains_key(&code))
+                && (code == MAKER_NOTE
+                    || (ExifTag::try_from(code).is_err() && !OFFSET_TAGS.contains(&code)))
+        };
+        let pointer = |fields: &[RawField], tag: u16| {
+            fields.iter().find(|x| x.code == tag).map(|x| match endian {
+                Endianness::Little => u32::from_le_bytes(x.field) as usize,
+                _ => u32::from_be_bytes(x.field) as usize,
+            })
+        };
+
+        let (fields, ifd1) = read_fields(data, ifd0, endian)?;
+        let mut ifds = vec![(ifd0, fields)];
+        for tag in [EXIF_OFFSET, GPS_INFO] {
+            if let Some(offset) = pointer(&ifds[0].1, tag) {
+                ifds.push((offset, read_fields(data, offset, endian)?.0));
+            } else {
+                ifds.push((0, Vec::new()));
+            }
+        }
+        if !self.preserve_unknown {
+            if let Some(offset) = pointer(&ifds[1].1, INTEROP_OFFSET) {
+                ifds.push((offset, read_fields(data, offset, endian)?.0));
+            }
+        }
+
+        let mut kept = Vec::new();
+        let mut zeroed = Vec::new();
+        let mut entries: [Vec<(u16, EncodedValue)>; 4] = Default::default();
+        for (i, (offset, fields)) in ifds.iter().enumerate() {
+            if fields.is_empty() {
+                continue;
+            }
+            // Fields of unknown formats are not read, but they're zeroed too
+            let count = u16_at(data, *offset, endian).unwrap_or_default() as usize;
+            zeroed.push(*offset..offset + 2 + 12 * count + 4);
+            for x in fields {
+                let keep =
+                    held(x.code) || (self.preserve_unknown && i == 1 && x.code == INTEROP_OFFSET);
+                if keep {
+                    entries[i].push((x.code, (x.format, x.count, x.field.to_vec())));
+                    if x.value.len() > 4 {
+                        kept.push(x.value.clone());
+                    }
+                } else if x.value.len() > 4 {
+                    if i == 0 && is_image_data_tag(x.code) {
+                        kept.push(x.value.clone());
+                    } else {
+                        zeroed.push(x.value.clone());
+                    }
+                }
+            }
+        }
+        for range in zeroed {
+            let end = range.end.min(data.len());
+            if !kept.iter().any(|x| x.start < end && range.start < x.end) {
+                data[range.start.min(end)..end].fill(0); // Safe-slice
+            }
+        }
+
+        let [ifd0, exif, gps, _] = entries;
+        Ok((EncodedEntries { ifd0, exif, gps }, ifd1))
+    }
+
+    /// Appends the IFDs to the end of the TIFF `data` (word aligned), and
+    /// points the header to the new ifd0.
+    fn append_ifds(
+        &self,
+        data: &mut Vec<u8>,
+        endian: Endianness,
+        extra: EncodedEntries,
+        next_ifd: usize,
+    ) -> crate::Result<()> {
+        if data.len() % 2 == 1 {
+            data.push(0);
+        }
+        let base = data.len();
+        let ifds = self.encode_ifds(endian, base, extra, next_ifd)?;
+        data.extend(ifds);
+        data[4..8].copy_from_slice(&u32_bytes(base as u32, endian)); // Safe-slice
+        Ok(())
+    }
+
+    /// Serializes the IFDs, which are going to be placed at `base` of the
+    /// TIFF data. `extra` entries (already encoded) are added into their
+    /// IFDs, the entries of this writer with the same tag codes are skipped,
+    /// and the last IFD points to `next_ifd`.
+    fn encode_ifds(
+        &self,
+        endian: Endianness,
+        base: usize,
+        extra: EncodedEntries,
+        next_ifd: usize,
+    ) -> crate::Result<Vec<u8>> {
+        let last = self.ifds.iter().rposition(|x| !x.is_empty());
+        let ifds = &self.ifds[..last.map_or(0, |x| x + 1)];
+
+        // Split ifd0 into ifd0 & its sub-IFDs
+        let EncodedEntries {
+            mut ifd0,
+            mut exif,
+            mut gps,
+        } = extra;
+        let skipped = ifd0
+            .iter()
+            .chain(exif.iter())
+            .chain(gps.iter())
+            .map(|x| x.0)
+            .collect::<Vec<_>>();
+        if let Some(entries) = ifds.first() {
+            for (code, value) in entries {
+                if skipped.contains(code) {
+                    continue;
+                }
+                let entry = (*code, encode_value(*code, value, endian)?);
+                match sub_ifd_of(*code) {
+                    Some(EXIF_OFFSET) => exif.push(entry),
+                    Some(GPS_INFO) => gps.push(entry),
+                    _ => ifd0.push(entry),
+                }
+            }
+        }
+        // Pointer entries, their values are filled in below
+        if !exif.is_empty() {
+            ifd0.push((EXIF_OFFSET, (DataFormat::U32, 1, vec![0; 4])));
+        }
+        if !gps.is_empty() {
+            ifd0.push((GPS_INFO, (DataFormat::U32, 1, vec![0; 4])));
+        }
+        ifd0.sort_by_key(|x| x.0);
+        exif.sort_by_key(|x| x.0);
+        gps.sort_by_key(|x| x.0);
+
+        let mut rest = Vec::new();
+        for entries in ifds.iter().skip(1) {
+            let entries = entries
+                .iter()
+                .map(|(code, value)| Ok((*code, encode_value(*code, value, endian)?)))
+                .collect::<crate::Result<Vec<_>>>()?;
+            rest.push(entries);
+        }
+
+        // Layout: ifd0, Exif IFD, GPS IFD, ifd1, ...
+        let mut offset = base;
+        offset += ifd_size(&ifd0);
+        let exif_offset = offset;
+        if !exif.is_empty() {
+            offset += ifd_size(&exif);
+        }
+        let gps_offset = offset;
+        if !gps.is_empty() {
+            offset += ifd_size(&gps);
+        }
+        let mut rest_offsets = Vec::with_capacity(rest.len());
+        for entries in rest.iter() {
+            rest_offsets.push(offset);
+            offset += ifd_size(entries);
+        }
+        if offset > u32::MAX as usize {
+            return Err(Error::WriteFailed("Exif data is too large".into()));
+        }
+
+        for (code, (_, _, data)) in ifd0.iter_mut() {
+            match *code {
+                EXIF_OFFSET => *data = u32_bytes(exif_offset as u32, endian).to_vec(),
+                GPS_INFO => *data = u32_bytes(gps_offset as u32, endian).to_vec(),
+                _ => (),
+            }
+        }
+
+        let mut buf = Vec::with_capacity(offset - base);
+        let mut ifd = IfdWriter {
+            buf: &mut buf,
+            base,
+            endian,
+        };
+        ifd.write(&ifd0, rest_offsets.first().copied().unwrap_or(next_ifd));
+        if !exif.is_empty() {
+            ifd.write(&exif, 0);
+        }
+        if !gps.is_empty() {
+            ifd.write(&gps, 0);
+        }
+        for (i, entries) in rest.iter().enumerate() {
+            ifd.write(
+                entries,
+                rest_offsets.get(i + 1).copied().unwrap_or(next_ifd),
+            );
+        }
+        debug_assert_eq!(buf.len(), offset - base);
+
+        Ok(buf)
+    }
+
+    /// Reads a JPEG file from `reader`, and writes it into `writer` with the
+    /// Exif data replaced by the entries of this writer. Other segments and
+    /// the image data are copied as is.
+    ///
+    /// Returns [`Error::WriteFailed`] if the Exif data is too large to fit in
+    /// a JPEG segment (64 KB).
+    pub fn write_jpeg<R: Read, W: Write>(&self, mut reader: R, writer: W) -> crate::Result<()> {
+        let mut input = Vec::new();
+        reader.read_to_end(&mut input)?;
+
+        let original = jpeg::extract_exif_data(&input).ok().and_then(|x| x.1);
+        let mut payload = EXIF_IDENT.as_bytes().to_vec();
+        payload.extend(self.to_tiff_bytes_with(original)?);
+        let app1 = MarkerCode::APP1.code();
+        jpeg::rewrite_segments(
+            &input,
+            writer,
+            |code, data| !(code == app1 && check_exif_header(data)),
+            &[(app1, &payload)],
+        )
+    }
+
+    /// Reads a TIFF file from `reader`, and writes it into `writer` with the
+    /// Exif data of ifd0 replaced by the entries of this writer.
+    ///
+    /// The new ifd0 (along with its Exif & GPS sub-IFDs) is appended to the
+    /// end of the file, so that the image data stays unchanged. Entries
+    /// describing the image data (e.g. `ImageWidth`, `StripOffsets`) and other
+    /// metadata (XMP, IPTC & ICC profiles) are kept from the original ifd0,
+    /// the same entries of this writer are ignored, and so are ifd1, ...
+    ///
+    /// The original ifd0, Exif & GPS IFDs and their values are zeroed, except
+    /// for the values of the kept entries, and for the preserved values in
+    /// the "preserve unknown" mode, see [`ExifWriter::preserve_unknown`].
+    pub fn write_tiff<R: Read, W: Write>(&self, mut reader: R, mut writer: W) -> crate::Result<()> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        let (_, header) = TiffHeader::parse(&data)?;
+        let endian = header.endian;
+        let (entries, next_ifd) = read_ifd(&data, header.ifd0_offset as usize, endian)?;
+        let kept = entries
+            .into_iter()
+            .filter(|(code, _)| is_image_data_tag(*code));
+
+        let mut ifd0 = ExifWriter {
+            ifds: self.ifds.first().cloned().into_iter().collect(),
+            preserve_unknown: self.preserve_unknown,
+        };
+        ifd0.retain(0, |code| !is_image_data_tag(code));
+
+        let ifd0_offset = header.ifd0_offset as usize;
+        let (mut extra, _) = ifd0.clear_original_ifds(&mut data, endian, ifd0_offset)?;
+        extra.ifd0.extend(kept);
+        ifd0.append_ifds(&mut data, endian, extra, next_ifd as usize)?;
+
+        writer.write_all(&data).map_err(Error::IOError)
+    }
+
+    /// Reads a HEIF/HEIC/AVIF file from `reader`, and writes it into `writer`
+    /// with the data of the Exif item replaced by the entries of this writer.
+    ///
+    /// The new Exif data is stored in a `mdat` box appended to the end of the
+    /// file, and the original Exif data is zeroed, other boxes are copied as
+    /// is.
+    ///
+    /// Returns [`Error::WriteFailed`] if the file has no Exif item (adding a
+    /// new item is not supported), or the Exif item is not stored in a single
+    /// extent of the file.
+    pub fn write_heif<R: Read, W: Write>(&self, mut reader: R, mut writer: W) -> crate::Result<()> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        let meta = crate::heif::parse_meta_box(&data)
+            .ok()
+            .and_then(|(_, meta)| meta)
+            .ok_or("meta box not found")?;
+        let Some(id) = meta.exif_item_id() else {
+            return Err(Error::WriteFailed(
+                "adding Exif data to HEIF files without an Exif item is not supported".into(),
+            ));
+        };
+
+        let original = crate::heif::extract_exif_with_meta(&data, &meta)
+            .ok()
+            .and_then(|x| x.1);
+        let tiff = self.to_tiff_bytes_with(original)?;
+
+        // The offset of the TIFF header, then "Exif\0\0" & the TIFF data
+        let mut payload = (EXIF_IDENT.len() as u32).to_be_bytes().to_vec();
+        payload.extend_from_slice(EXIF_IDENT.as_bytes());
+        payload.extend(tiff);
+        crate::heif::replace_item_data(&mut data, id, &payload)?;
+
+        writer.write_all(&data).map_err(Error::IOError)
+    }
+}
+
+impl From<&Exif> for ExifWriter {
+    fn from(value: &Exif) -> Self {
+        ExifWriter::from_exif(value)
+    }
+}
+
+/// Returns true if `code` is a tag of ifd0 describing the image data (e.g.
+/// its layout & the offsets of the strips), a DNG specific tag, or a tag of
+/// other metadata stored in TIFF files (XMP, IPTC & ICC profiles).
+pub(crate) fn is_image_data_tag(code: u16) -> bool {
+    matches!(
+        code,
+        // NewSubfileType ... Compression; PhotometricInterpretation;
+        // StripOffsets; SamplesPerPixel ... StripByteCounts;
+        // PlanarConfiguration; TileWidth ... TileByteCounts; SubIFDs;
+        // ExtraSamples, SampleFormat; ThumbnailOffset, ThumbnailLength
+        0x00fe..=0x0103
+            | 0x0106
+            | 0x0111
+            | 0x0115..=0x0117
+            | 0x011c
+            | 0x0142..=0x0145
+            | 0x014a
+            | 0x0152
+            | 0x0153
+            | 0x0201
+            | 0x0202
+            // XMLPacket; IPTC-NAA; Photoshop image resources; ICC profile
+            | 0x02bc
+            | 0x83bb
+            | 0x8649
+            | 0x8773
+            // DNGVersion ... (DNG specific tags)
+            | 0xc612..=0xc7ff
+            | 0xcd2d..=0xcd3b
+    )
+}
+
+/// Reads the entries of the IFD at `offset` as they are, returns them along
+/// with the offset of the next IFD.
+pub(crate) fn read_ifd(
+    data: &[u8],
+    offset: usize,
+    endian: Endianness,
+) -> crate::Result<(Vec<(u16, EncodedValue)>, u32)> {
+    let (fields, next) = read_fields(data, offset, endian)?;
+    let entries = fields
+        .into_iter()
+        .map(|x| (x.code, (x.format, x.count, data[x.value].to_vec()))) // Safe-slice
+        .collect();
+    Ok((entries, next))
+}
+
+/// Converts decimal degrees into degrees, minutes & seconds (1/10000
+/// precision).
+fn decimal_to_dms(v: f64) -> LatLng {
+    let mut degrees = v.trunc() as u32;
+    let minutes = v.fract() * 60.0;
+    let mut seconds = (minutes.fract() * 60.0 * 10000.0).round() as u32;
+    let mut minutes = minutes.trunc() as u32;
+    if seconds >= 60 * 10000 {
+        seconds -= 60 * 10000;
+        minutes += 1;
+    }
+    if minutes >= 60 {
+        minutes -= 60;
+        degrees += 1;
+    }
+    [(degrees, 1), (minutes, 1), (seconds, 10000)].into()
+}
+
+/// Returns the tag which stores the time zone of the time `tag`.
+fn offset_time_tag(tag: u16) -> Option<ExifTag> {
+    match tag {
+        x if x == ExifTag::ModifyDate.code() => Some(ExifTag::OffsetTime),
+        x if x == ExifTag::DateTimeOriginal.code() => Some(ExifTag::OffsetTimeOriginal),
+        x if x == ExifTag::CreateDate.code() => Some(ExifTag::OffsetTimeDigitized),
+        _ => None,
+    }
+}
+
+/// Returns the pointer tag of the sub-IFD which `code` belongs to, or `None`
+/// if it belongs to ifd0.
+pub(crate) fn sub_ifd_of(code: u16) -> Option<u16> {
+    match code {
+        0x0000..=0x001f => Some(GPS_INFO),
+        // ExposureTime, FNumber; ExposureProgram ... ISO related tags
+        0x829a..=0x829d | 0x8822..=0x8832 => Some(EXIF_OFFSET),
+        // XPTitle ... XPSubject are stored in ifd0
+        0x9c9b..=0x9c9f => None,
+        0x9000..=0xa500 => Some(EXIF_OFFSET),
+        _ => None,
+    }
+}
+
+pub(crate) type EncodedValue = (DataFormat, u32, Vec<u8>);
+
+/// Already encoded entries of ifd0 and its Exif & GPS sub-IFDs.
+#[derive(Debug, Default)]
+struct EncodedEntries {
+    ifd0: Vec<(u16, EncodedValue)>,
+    exif: Vec<(u16, EncodedValue)>,
+    gps: Vec<(u16, EncodedValue)>,
+}
+
+pub(crate) fn encode_value(
+    code: u16,
+    value: &EntryValue,
+    endian: Endianness,
+) -> crate::Result<EncodedValue> {
+    macro_rules! encode {
+        ($format:expr, $values:expr) => {{
+            let values = $values;
+            let data = values
+                .iter()
+                .flat_map(|x| match endian {
+                    Endianness::Little => x.to_le_bytes(),
+                    _ => x.to_be_bytes(),
+                })
+                .collect();
+            ($format, values.len() as u32, data)
+        }};
+    }
+    macro_rules! rational {
+        ($format:expr, $values:expr) => {{
+            let values = $values;
+            let parts = values.iter().flat_map(|x| [x.0, x.1]).collect::<Vec<_>>();
+            let (_, _, data) = encode!($format, parts);
+            ($format, values.len() as u32, data)
+        }};
+    }
+    let text = |s: String| {
+        let mut data = s.into_bytes();
+        data.push(0);
+        (DataFormat::Text, data.len() as u32, data)
+    };
+
+    let encoded = match value {
+        EntryValue::Text(s) if code == ExifTag::UserComment.code() => {
+            let data = encode_user_comment(s, endian);
+            (DataFormat::Undefined, data.len() as u32, data)
+        }
+        EntryValue::Text(s) if ExifTag::try_from(code).is_ok_and(is_xp_tag) => {
+            let data = encode_xp_text(s);
+            (DataFormat::U8, data.len() as u32, data)
+        }
+        EntryValue::Text(s) => text(s.to_owned()),
+        EntryValue::Time(t) => text(t.format("%Y:%m:%d %H:%M:%S").to_string()),
+        EntryValue::URational(x) => rational!(DataFormat::URational, [*x]),
+        EntryValue::IRational(x) => rational!(DataFormat::IRational, [*x]),
+        EntryValue::URationalArray(x) => rational!(DataFormat::URational, x),
+        EntryValue::IRationalArray(x) => rational!(DataFormat::IRational, x),
+        EntryValue::U8(x) => (DataFormat::U8, 1, vec![*x]),
+        EntryValue::U8Array(x) => (DataFormat::U8, x.len() as u32, x.to_owned()),
+        EntryValue::I8(x) => (DataFormat::I8, 1, x.to_be_bytes().to_vec()),
+        EntryValue::I8Array(x) => (
+            DataFormat::I8,
+            x.len() as u32,
+            x.iter().map(|x| *x as u8).collect(),
+        ),
+        EntryValue::Undefined(x) => (DataFormat::Undefined, x.len() as u32, x.to_owned()),
+        EntryValue::U16(x) => encode!(DataFormat::U16, [*x]),
+        EntryValue::U16Array(x) => encode!(DataFormat::U16, x),
+        EntryValue::I16(x) => encode!(DataFormat::I16, [*x]),
+        EntryValue::I16Array(x) => encode!(DataFormat::I16, x),
+        EntryValue::U32(x) => encode!(DataFormat::U32, [*x]),
+        EntryValue::U32Array(x) => encode!(DataFormat::U32, x),
+        EntryValue::I32(x) => encode!(DataFormat::I32, [*x]),
+        EntryValue::I32Array(x) => encode!(DataFormat::I32, x),
+        EntryValue::F32(x) => encode!(DataFormat::F32, [*x]),
+        EntryValue::F32Array(x) => encode!(DataFormat::F32, x),
+        EntryValue::F64(x) => encode!(DataFormat::F64, [*x]),
+        EntryValue::F64Array(x) => encode!(DataFormat::F64, x),
+        EntryValue::U64(_) | EntryValue::I64(_) => {
+            return Err(Error::WriteFailed(
+                format!("64-bit integers can't be stored in TIFF; tag: 0x{code:04x}").into(),
+            ))
+        }
+    };
+    Ok(encoded)
+}
+
+/// Size of an IFD, including its out-of-line data.
+pub(crate) fn ifd_size(entries: &[(u16, EncodedValue)]) -> usize {
+    let data_size: usize = entries
+        .iter()
+        .map(|(_, (_, _, data))| out_of_line_size(data))
+        .sum();
+    2 + 12 * entries.len() + 4 + data_size
+}
+
+/// Serializes an IFD (entries sorted by tag codes) & its out-of-line data,
+/// which is going to be placed at `base` of the TIFF data.
+pub(crate) fn encode_ifd(
+    entries: &[(u16, EncodedValue)],
+    base: usize,
+    endian: Endianness,
+    next_ifd: usize,
+) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(ifd_size(entries));
+    IfdWriter {
+        buf: &mut buf,
+        base,
+        endian,
+    }
+    .write(entries, next_ifd);
+    buf
+}
+
+fn out_of_line_size(data: &[u8]) -> usize {
+    if data.len() <= 4 {
+        0
+    } else {
+        // Keep the following values word aligned
+        data.len() + data.len() % 2
+    }
+}
+
+/// Writes IFDs into `buf`, which is going to be placed at `base` of the TIFF
+/// data.
+struct IfdWriter<'a> {
+    buf: &'a mut Vec<u8>,
+    base: usize,
+    endian: Endianness,
+}
+
+impl IfdWriter<'_> {
+    fn u16(&mut self, x: u16) {
+        self.buf.extend_from_slice(&u16_bytes(x, self.endian));
+    }
+
+    fn u32(&mut self, x: u32) {
+        self.buf.extend_from_slice(&u32_bytes(x, self.endian));
+    }
+
+    /// Writes an IFD (entries sorted by tag codes) & its out-of-line data.
+    fn write(&mut self, entries: &[(u16, EncodedValue)], next_ifd: usize) {
+        let start = self.base + self.buf.len();
+        let mut data_offset = start + 2 + 12 * entries.len() + 4;
+        let mut data = Vec::new();
+
+        self.u16(entries.len() as u16);
+        for (code, (format, count, value)) in entries {
+            self.u16(*code);
+            self.u16(*format as u16);
+            self.u32(*count);
+            if value.len() <= 4 {
+                let mut inline = [0; 4];
+                inline[..value.len()].copy_from_slice(value);
+                self.buf.extend_from_slice(&inline);
+            } else {
+                self.u32(data_offset as u32);
+                data.extend_from_slice(value);
+                if value.len() % 2 == 1 {
+                    data.push(0);
+                }
+                data_offset += out_of_line_size(value);
+            }
+        }
+        self.u32(next_ifd as u32);
+        self.buf.extend(data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        testkit::{open_sample, read_sample},
+        ExifIter, MediaParser, MediaSource,
+    };
+    use chrono::DateTime;
+    use std::io::{Cursor, Read};
+    use test_case::test_case;
+
+    fn parse_jpeg(data: Vec<u8>) -> Exif {
+        let ms = MediaSource::seekable(Cursor::new(data)).unwrap();
+        let iter: ExifIter = MediaParser::new().parse(ms).unwrap();
+        iter.into()
+    }
+
+    #[test]
+    fn write_jpeg() {
+        let mut input = Vec::new();
+        open_sample("exif.jpg")
+            .unwrap()
+            .read_to_end(&mut input)
+            .unwrap();
+        let exif = parse_jpeg(input.clone());
+
+        let t = DateTime::parse_from_rfc3339("2024-05-06T07:08:09-03:00").unwrap();
+        let mut writer = ExifWriter::from_exif(&exif);
+        writer
+            .set(ExifTag::Model, "nom-exif")
+            .set(ExifTag::DateTimeOriginal, t)
+            .set(ExifTag::ExposureTime, (1_u32, 250_u32))
+            .set(ExifTag::UserComment, "写真 café")
+            .set_by_ifd_tag_code(0, 0x9c9b, EntryValue::U8Array(vec![b'a', 0, 0, 0]));
+        assert!(writer.remove(ExifTag::YCbCrPositioning).is_some());
+
+        let mut output = Vec::new();
+        writer.write_jpeg(Cursor::new(&input), &mut output).unwrap();
+
+        // Image data is kept as is
+        let sos = |data: &[u8]| {
+            let mut pos = 2;
+            while data[pos + 1] != MarkerCode::Sos.code() {
+                pos += 2 + u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+            }
+            pos
+        };
+        assert_eq!(output[sos(&output)..], input[sos(&input)..]);
+
+        let written = parse_jpeg(output);
+        assert_eq!(written.get(ExifTag::Model), Some(&"nom-exif".into()));
+        assert_eq!(written.get(ExifTag::Make), exif.get(ExifTag::Make));
+        assert_eq!(written.get(ExifTag::DateTimeOriginal), Some(&t.into()));
+        assert_eq!(
+            written.get(ExifTag::OffsetTimeOriginal),
+            Some(&"-03:00".into())
+        );
+        assert_eq!(
+            written.get(ExifTag::ExposureTime),
+            Some(&(1_u32, 250_u32).into())
+        );
+        assert_eq!(written.get(ExifTag::YCbCrPositioning), None);
+        // Stored as UNDEFINED with the "UNICODE" character code
+        assert_eq!(written.get(ExifTag::UserComment), Some(&"写真 café".into()));
+        assert_eq!(written.get(ExifTag::XPTitle), Some(&"a".into()));
+        assert_eq!(
+            written.get_gps_info().unwrap(),
+            exif.get_gps_info().unwrap()
+        );
+
+        // Writing the written data again changes nothing
+        let rewritten = ExifWriter::from_exif(&written);
+        assert_eq!(
+            rewritten.to_tiff_bytes().unwrap(),
+            writer.to_tiff_bytes().unwrap()
+        );
+    }
+
+    #[test_case(ExifTag::ImageWidth, 1_u64.into(), false)]
+    #[test_case(ExifTag::ImageWidth, (-1_i64).into(), false)]
+    #[test_case(ExifTag::ImageWidth, 1_u32.into(), true)]
+    #[test_case(ExifTag::Make, "".into(), true)]
+    fn to_tiff_bytes(tag: ExifTag, value: EntryValue, ok: bool) {
+        let mut writer = ExifWriter::new();
+        writer.set(tag, value);
+        let res = writer.to_tiff_bytes();
+        assert_eq!(res.is_ok(), ok, "{res:?}");
+    }
+
+    #[test_case("exif.jpg")]
+    #[test_case("exif.heic")]
+    #[test_case("tif.tif")]
+    fn write_gps(path: &str) {
+        let mut input = Vec::new();
+        open_sample(path).unwrap().read_to_end(&mut input).unwrap();
+        let exif = parse_jpeg(input.clone());
+
+        let mut writer = ExifWriter::from_exif(&exif);
+        writer
+            .set(ExifTag::Make, "nom-exif")
+            .set_gps_decimal(-33.856784, 151.215297, Some(-1.5))
+            .unwrap();
+        let mut output = Vec::new();
+        let res = match path {
+            "exif.jpg" => writer.write_jpeg(Cursor::new(&input), &mut output),
+            "exif.heic" => writer.write_heif(Cursor::new(&input), &mut output),
+            _ => writer.write_tiff(Cursor::new(&input), &mut output),
+        };
+        res.unwrap();
+
+        let written = parse_jpeg(output);
+        let gps = written.get_gps_info().unwrap().unwrap();
+        assert_eq!(gps.format_iso6709(), "-33.85678+151.21530-1.500CRSWGS_84/");
+        assert_eq!(written.get(ExifTag::GPSMapDatum), Some(&"WGS-84".into()));
+        assert_eq!(written.get(ExifTag::Make), Some(&"nom-exif".into()));
+        for tag in [ExifTag::Model, ExifTag::ImageWidth, ExifTag::ImageHeight] {
+            assert_eq!(written.get(tag), exif.get(tag));
+        }
+    }
+
+    #[test]
+    fn write_tiff_keeps_image_data() {
+        let mut input = Vec::new();
+        open_sample("tif.tif")
+            .unwrap()
+            .read_to_end(&mut input)
+            .unwrap();
+        let mut writer = ExifWriter::new();
+        writer
+            .set(ExifTag::ImageDescription, "nom-exif")
+            .set(ExifTag::ImageWidth, 1_u32);
+        let mut output = Vec::new();
+        writer.write_tiff(Cursor::new(&input), &mut output).unwrap();
+
+        // The new IFDs are appended, the original ifd0 is zeroed, the image
+        // data is unchanged
+        let changed = output[8..input.len()]
+            .iter()
+            .zip(&input[8..])
+            .filter(|(a, b)| a != b)
+            .collect::<Vec<_>>();
+        assert!(!changed.is_empty());
+        assert!(changed.len() < 1024, "{} bytes changed", changed.len());
+        assert!(changed.iter().all(|(a, _)| **a == 0));
+        let before = parse_jpeg(input);
+        let written = parse_jpeg(output);
+        assert_eq!(
+            written.get(ExifTag::ImageDescription),
+            Some(&"nom-exif".into())
+        );
+        assert_eq!(
+            written.get(ExifTag::ImageWidth),
+            before.get(ExifTag::ImageWidth)
+        );
+        // StripOffsets, StripByteCounts
+        for code in [0x0111, 0x0117] {
+            assert!(written.get_by_ifd_tag_code(0, code).is_some());
+            assert_eq!(
+                written.get_by_ifd_tag_code(0, code),
+                before.get_by_ifd_tag_code(0, code)
+            );
+        }
+    }
+
+    /// The original GPS IFD & its values are not left in the file.
+    #[test_case(false)]
+    #[test_case(true; "preserve unknown")]
+    fn write_tiff_removes_gps(preserve: bool) {
+        let datum = "nom-exif datum";
+        let input = read_sample("tif.tif").unwrap();
+        let mut writer = ExifWriter::from_exif(&parse_jpeg(input.clone()));
+        writer
+            .set_gps_decimal(-33.856784, 151.215297, Some(-1.5))
+            .unwrap()
+            .set(ExifTag::GPSMapDatum, datum);
+        let mut with_gps = Vec::new();
+        writer
+            .write_tiff(Cursor::new(&input), &mut with_gps)
+            .unwrap();
+
+        let exif = parse_jpeg(with_gps.clone());
+        let endian = TiffHeader::parse(&with_gps).unwrap().1.endian;
+        let latitude = exif.get(ExifTag::GPSLatitude).unwrap();
+        let (_, _, latitude) = encode_value(ExifTag::GPSLatitude.code(), latitude, endian).unwrap();
+        let contains = |data: &[u8], x: &[u8]| data.windows(x.len()).any(|w| w == x);
+        assert!(contains(&with_gps, &latitude));
+        assert!(contains(&with_gps, datum.as_bytes()));
+
+        let mut writer = ExifWriter::from_exif(&exif);
+        writer.preserve_unknown(preserve).remove_gps();
+        let mut output = Vec::new();
+        writer
+            .write_tiff(Cursor::new(&with_gps), &mut output)
+            .unwrap();
+        assert!(!contains(&output, &latitude));
+        assert!(!contains(&output, datum.as_bytes()));
+
+        let written = parse_jpeg(output);
+        assert_eq!(written.get_gps_info().unwrap(), None);
+        for tag in [ExifTag::ImageWidth, ExifTag::Orientation] {
+            assert!(written.get(tag).is_some());
+            assert_eq!(written.get(tag), exif.get(tag));
+        }
+        // StripOffsets
+        assert_eq!(
+            written.get_by_ifd_tag_code(0, 0x0111),
+            exif.get_by_ifd_tag_code(0, 0x0111)
+        );
+    }
+
+    /// Returns the TIFF data of the Exif data of `data`.
+    fn tiff_data(path: &str, data: &[u8]) -> Vec<u8> {
+        match path {
+            "exif.jpg" => jpeg::extract_exif_data(data).unwrap().1.unwrap().to_vec(),
+            "exif.heic" => crate::heif::extract_exif_data(data)
+                .unwrap()
+                .1
+                .unwrap()
+                .to_vec(),
+            _ => data.to_vec(),
+        }
+    }
+
+    /// Returns the (tag, value field, value) of the MakerNote & unknown tags
+    /// of ifd0 and the Exif sub-IFD.
+    fn unknown_fields(tiff: &[u8]) -> Vec<(u16, [u8; 4], Vec<u8>)> {
+        let (_, header) = TiffHeader::parse(tiff).unwrap();
+        let (mut fields, _) =
+            read_fields(tiff, header.ifd0_offset as usize, header.endian).unwrap();
+        if let Some(x) = fields.iter().find(|x| x.code == EXIF_OFFSET) {
+            let offset = tiff[x.value.clone()].try_into().unwrap();
+            let offset = match header.endian {
+                Endianness::Little => u32::from_le_bytes(offset),
+                _ => u32::from_be_bytes(offset),
+            };
+            fields.extend(read_fields(tiff, offset as usize, header.endian).unwrap().0);
+        }
+        fields
+            .into_iter()
+            .filter(|x| x.code == MAKER_NOTE || ExifTag::try_from(x.code).is_err())
+            .filter(|x| !OFFSET_TAGS.contains(&x.code))
+            .map(|x| (x.code, x.field, tiff[x.value].to_vec()))
+            .collect()
+    }
+
+    #[test_case("exif.jpg")]
+    #[test_case("exif.heic")]
+    #[test_case("tif.tif")]
+    fn preserve_unknown(path: &str) {
+        let mut input = Vec::new();
+        open_sample(path).unwrap().read_to_end(&mut input).unwrap();
+        let exif = parse_jpeg(input.clone());
+
+        let mut writer = ExifWriter::from_exif(&exif);
+        writer
+            .preserve_unknown(true)
+            .set(ExifTag::Make, "nom-exif")
+            .remove_gps();
+        let mut output = Vec::new();
+        let res = match path {
+            "exif.jpg" => writer.write_jpeg(Cursor::new(&input), &mut output),
+            "exif.heic" => writer.write_heif(Cursor::new(&input), &mut output),
+            _ => writer.write_tiff(Cursor::new(&input), &mut output),
+        };
+        res.unwrap();
+
+        // Unknown tags & the MakerNote are kept byte for byte, at the same
+        // offsets
+        let before = unknown_fields(&tiff_data(path, &input));
+        let after = unknown_fields(&tiff_data(path, &output));
+        assert_eq!(after, before);
+        if path != "tif.tif" {
+            assert!(before.iter().any(|x| x.0 == MAKER_NOTE));
+        }
+
+        let written = parse_jpeg(output.clone());
+        assert_eq!(written.get(ExifTag::Make), Some(&"nom-exif".into()));
+        assert_eq!(written.get(ExifTag::Model), exif.get(ExifTag::Model));
+        assert_eq!(
+            written.get(ExifTag::MakerNote),
+            exif.get(ExifTag::MakerNote)
+        );
+        assert!(written.get_gps_info().unwrap().is_none());
+
+        // The original values are zeroed, so each of them is found only once
+        if let Some(EntryValue::Text(model)) = exif.get(ExifTag::Model) {
+            let count = |tiff: Vec<u8>| {
+                tiff.windows(model.len())
+                    .filter(|x| *x == model.as_bytes())
+                    .count()
+            };
+            assert_eq!(
+                count(tiff_data(path, &output)),
+                count(tiff_data(path, &input))
+            );
+        }
+    }
+
+    #[test_case(1.5, [(1, 1), (30, 1), (0, 10000)])]
+    #[test_case(151.215297, [(151, 1), (12, 1), (550692, 10000)])]
+    #[test_case(9.99999999, [(10, 1), (0, 1), (0, 10000)])]
+    fn decimal_to_dms(v: f64, expect: [(u32, u32); 3]) {
+        assert_eq!(super::decimal_to_dms(v), expect.into());
+    }
+
+    #[test]
+    fn segment_too_large() {
+        let mut input = Vec::new();
+        open_sample("exif.jpg")
+            .unwrap()
+            .read_to_end(&mut input)
+            .unwrap();
+        let mut writer = ExifWriter::new();
+        writer.set(ExifTag::ImageDescription, "x".repeat(0x10000));
+        let err = writer
+            .write_jpeg(Cursor::new(input), Vec::new())
+            .unwrap_err();
+        assert!(matches!(err, Error::WriteFailed(_)), "{err:?}");
+    }
+
+    #[test_case("exif.jpg")]
+    #[test_case("exif.heic")]
+    #[test_case("tif.tif")]
+    fn output_io_error(path: &str) {
+        let input = read_sample(path).unwrap();
+        let mut writer = ExifWriter::new();
+        writer.set(ExifTag::Make, "nom-exif");
+        // The output is too small to hold the file
+        let mut output = [0; 16];
+        let res = match path {
+            "exif.jpg" => writer.write_jpeg(Cursor::new(&input), &mut output[..]),
+            "exif.heic" => writer.write_heif(Cursor::new(&input), &mut output[..]),
+            _ => writer.write_tiff(Cursor::new(&input), &mut output[..]),
+        };
+        assert!(
+            matches!(&res, Err(Error::IOError(e)) if e.kind() == std::io::ErrorKind::WriteZero),
+            "{res:?}"
+        );
+    }
+}
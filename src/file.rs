@@ -5,13 +5,25 @@ use std::{
 };
 
 use crate::{
+    asf::check_asf,
+    avi::check_avi,
     bbox::{travel_header, BoxHolder},
+    dsd::{check_dff, check_dsf},
     ebml::element::parse_ebml_doc_type,
     error::{ParsedError, ParsingError},
-    exif::TiffHeader,
+    exif::{ifd_fields, u16_at, TiffHeader, IFD_ENTRY_SIZE},
+    gif::check_gif,
+    id3::check_mp3,
+    jp2::check_jp2,
     jpeg::check_jpeg,
     loader::Load,
-    slice::SubsliceRange,
+    mpegts::check_ts,
+    mxf::check_mxf,
+    parser::HEADER_PARSE_BUF_SIZE,
+    raf::check_raf,
+    values::DataFormat,
+    wav::check_wav,
+    xmp::check_xmp_sidecar,
 };
 
 const HEIF_HEIC_BRAND_NAMES: &[&[u8]] = &[
@@ -22,11 +34,18 @@ const HEIF_HEIC_BRAND_NAMES: &[&[u8]] = &[
     b"heis", // scalable
     b"hevm", // multiview sequence
     b"hevs", // scalable sequence
+    b"msf1", // image sequences, e.g.: Apple burst photos (*.heics)
     b"mif1", b"MiHE", b"miaf", b"MiHB", // HEIC file's compatible brands
 ];
 
 const HEIC_BRAND_NAMES: &[&[u8]] = &[b"heic", b"heix", b"heim", b"heis"];
 
+// AVIF is based on HEIF too, so Exif data is located in the same way
+const AVIF_BRAND_NAMES: &[&[u8]] = &[
+    b"avif", // AVIF images
+    b"avis", // AVIF image sequences
+];
+
 // TODO: Refer to the information on the website https://www.ftyps.com to add
 // other less common MP4 brands.
 const MP4_BRAND_NAMES: &[&str] = &[
@@ -36,42 +55,57 @@ const MP4_BRAND_NAMES: &[&str] = &[
 
 const QT_BRAND_NAMES: &[&str] = &["qt  ", "mqt "];
 
-#[derive(Debug, Clone, PartialEq, Eq, Copy)]
+#[derive(Debug, Clone, PartialEq, Eq, Copy, Hash)]
 pub(crate) enum Mime {
     Image(MimeImage),
     Video(MimeVideo),
+    /// Standalone XMP sidecar file (*.xmp)
+    Xmp,
 }
 
-impl Mime {
-    pub fn unwrap_image(self) -> MimeImage {
-        match self {
-            Mime::Image(val) => val,
-            Mime::Video(_) => panic!("called `Mime::unwrap_image()` on an `Mime::Video`"),
-        }
-    }
-    pub fn unwrap_video(self) -> MimeVideo {
-        match self {
-            Mime::Image(_) => panic!("called `Mime::unwrap_video()` on an `Mime::Image`"),
-            Mime::Video(val) => val,
-        }
-    }
-}
-
-#[derive(Debug, Clone, PartialEq, Eq, Copy)]
+#[derive(Debug, Clone, PartialEq, Eq, Copy, Hash)]
 pub(crate) enum MimeImage {
     Jpeg,
     Heic,
     Heif,
+    Avif,
     Tiff,
+    /// Fujifilm RAF, whose Exif data is in the embedded JPEG
+    Raf,
+    /// Pentax PEF, a TIFF based raw parsed as TIFF
+    Pef,
+    /// Samsung SRW, a TIFF based raw parsed as TIFF
+    Srw,
+    /// JPEG 2000 (*.jp2, *.jpf)
+    Jp2,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Copy)]
+#[derive(Debug, Clone, PartialEq, Eq, Copy, Hash)]
 pub(crate) enum MimeVideo {
     QuickTime,
     Mp4,
     Webm,
     Matroska,
     _3gpp,
+    /// GIF files have no Exif data, their info (e.g. dimensions, duration of
+    /// animated GIFs, and creation date from XMP) is parsed as a track.
+    Gif,
+    /// AVI (RIFF)
+    Avi,
+    /// WAV (RIFF)
+    Wav,
+    /// MP3 (MPEG audio layer III), with or without ID3v2 tags
+    Mp3,
+    /// ASF (Windows Media), e.g.: *.wmv, *.wma
+    Asf,
+    /// MPEG transport streams, including M2TS
+    MpegTs,
+    /// MXF (Material Exchange Format)
+    Mxf,
+    /// DSF (DSD Stream File)
+    Dsf,
+    /// DSDIFF (DSD Interchange File Format), i.e. *.dff
+    Dff,
 }
 
 impl TryFrom<&[u8]> for Mime {
@@ -85,10 +119,38 @@ impl TryFrom<&[u8]> for Mime {
             } else {
                 Mime::Video(MimeVideo::Matroska)
             }
-        } else if TiffHeader::parse(input).is_ok() {
-            Mime::Image(MimeImage::Tiff)
+        } else if check_gif(input) {
+            Mime::Video(MimeVideo::Gif)
+        } else if check_avi(input) {
+            Mime::Video(MimeVideo::Avi)
+        } else if check_wav(input) {
+            Mime::Video(MimeVideo::Wav)
+        } else if check_asf(input) {
+            Mime::Video(MimeVideo::Asf)
+        } else if check_ts(input) {
+            Mime::Video(MimeVideo::MpegTs)
+        } else if check_mxf(input) {
+            Mime::Video(MimeVideo::Mxf)
+        } else if check_dsf(input) {
+            Mime::Video(MimeVideo::Dsf)
+        } else if check_dff(input) {
+            Mime::Video(MimeVideo::Dff)
+        } else if input.starts_with(b"ID3") && check_mp3(input) {
+            Mime::Video(MimeVideo::Mp3)
+        } else if check_jp2(input) {
+            Mime::Image(MimeImage::Jp2)
+        } else if cfg!(feature = "raw_formats") && check_raf(input) {
+            Mime::Image(MimeImage::Raf)
+        } else if let Ok((_, header)) = TiffHeader::parse(input) {
+            Mime::Image(tiff_mime(input, &header))
         } else if check_jpeg(input).is_ok() {
             Mime::Image(MimeImage::Jpeg)
+        } else if check_xmp_sidecar(input) {
+            Mime::Xmp
+        } else if check_mp3(input) {
+            // Checked at last, since MPEG audio frame headers are less
+            // distinctive than other signatures
+            Mime::Video(MimeVideo::Mp3)
         } else {
             return Err(crate::Error::UnrecognizedFileFormat);
         };
@@ -103,7 +165,7 @@ impl TryFrom<&[u8]> for Mime {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FileFormat {
     Jpeg,
-    /// heic, heif
+    /// heic, heif, avif
     Heif,
 
     // Currently, there is not much difference between QuickTime and MP4 when
@@ -173,12 +235,244 @@ impl Display for FileFormat {
     }
 }
 
+/// Detects the type of a media file from its first bytes, without parsing
+/// it. Returns `None` if the file format is not supported by this crate.
+///
+/// `reader` can be a `File`, or a byte slice, e.g.:
+///
+/// ```rust
+/// use nom_exif::{detect, MediaFamily};
+///
+/// let t = detect(std::fs::File::open("./testdata/meta.mov").unwrap()).unwrap().unwrap();
+/// assert_eq!(t.mime(), "video/quicktime");
+/// assert_eq!(t.family(), MediaFamily::Isobmff);
+/// assert!(t.has_track());
+///
+/// assert_eq!(detect(&b"plain text"[..]).unwrap(), None);
+/// ```
+pub fn detect<R: Read>(mut reader: R) -> crate::Result<Option<MediaType>> {
+    // The same amount of data as `MediaSource` uses
+    let buf = read_header(&mut reader)?;
+    Ok(Mime::try_from(buf.as_slice())
+        .ok()
+        .map(|mime| MediaType { mime }))
+}
+
+/// The type of a media file, see [`detect`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MediaType {
+    mime: Mime,
+}
+
+/// The container family of a media file, i.e. how its metadata is
+/// structured, see [`MediaType::family`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum MediaFamily {
+    Jpeg,
+    /// TIFF & TIFF based raw formats, e.g. DNG, NEF, CR2, ARW
+    Tiff,
+    /// Fujifilm RAF
+    Raf,
+    /// ISO base media file format: HEIF, HEIC, AVIF, QuickTime, MP4, 3GPP
+    Isobmff,
+    /// JPEG 2000
+    Jp2,
+    /// EBML: WebM & Matroska
+    Ebml,
+    /// RIFF: AVI & WAV
+    Riff,
+    Gif,
+    /// MPEG audio, e.g. MP3
+    MpegAudio,
+    /// ASF (Windows Media)
+    Asf,
+    /// MPEG transport streams, including M2TS
+    MpegTs,
+    /// MXF (Material Exchange Format)
+    Mxf,
+    /// DSD audio: DSF & DSDIFF
+    Dsd,
+    /// Standalone XMP sidecar files
+    Xmp,
+}
+
+impl MediaType {
+    /// Returns the MIME type, e.g. `"image/jpeg"`, `"video/mp4"`.
+    pub fn mime(&self) -> &'static str {
+        match self.mime {
+            Mime::Image(MimeImage::Jpeg) => "image/jpeg",
+            Mime::Image(MimeImage::Heic) => "image/heic",
+            Mime::Image(MimeImage::Heif) => "image/heif",
+            Mime::Image(MimeImage::Avif) => "image/avif",
+            Mime::Image(MimeImage::Tiff) => "image/tiff",
+            Mime::Image(MimeImage::Raf) => "image/x-fujifilm-raf",
+            Mime::Image(MimeImage::Pef) => "image/x-pentax-pef",
+            Mime::Image(MimeImage::Srw) => "image/x-samsung-srw",
+            Mime::Image(MimeImage::Jp2) => "image/jp2",
+            Mime::Video(MimeVideo::QuickTime) => "video/quicktime",
+            Mime::Video(MimeVideo::Mp4) => "video/mp4",
+            Mime::Video(MimeVideo::_3gpp) => "video/3gpp",
+            Mime::Video(MimeVideo::Webm) => "video/webm",
+            Mime::Video(MimeVideo::Matroska) => "video/x-matroska",
+            Mime::Video(MimeVideo::Gif) => "image/gif",
+            Mime::Video(MimeVideo::Avi) => "video/x-msvideo",
+            Mime::Video(MimeVideo::Wav) => "audio/wav",
+            Mime::Video(MimeVideo::Mp3) => "audio/mpeg",
+            Mime::Video(MimeVideo::Asf) => "video/x-ms-asf",
+            Mime::Video(MimeVideo::MpegTs) => "video/mp2t",
+            Mime::Video(MimeVideo::Mxf) => "application/mxf",
+            Mime::Video(MimeVideo::Dsf) => "audio/x-dsf",
+            Mime::Video(MimeVideo::Dff) => "audio/x-dff",
+            Mime::Xmp => "application/rdf+xml",
+        }
+    }
+
+    /// Returns the container family.
+    pub fn family(&self) -> MediaFamily {
+        match self.mime {
+            Mime::Image(MimeImage::Jpeg) => MediaFamily::Jpeg,
+            Mime::Image(MimeImage::Heic | MimeImage::Heif | MimeImage::Avif) => {
+                MediaFamily::Isobmff
+            }
+            Mime::Image(MimeImage::Tiff | MimeImage::Pef | MimeImage::Srw) => MediaFamily::Tiff,
+            Mime::Image(MimeImage::Raf) => MediaFamily::Raf,
+            Mime::Image(MimeImage::Jp2) => MediaFamily::Jp2,
+            Mime::Video(MimeVideo::QuickTime | MimeVideo::Mp4 | MimeVideo::_3gpp) => {
+                MediaFamily::Isobmff
+            }
+            Mime::Video(MimeVideo::Webm | MimeVideo::Matroska) => MediaFamily::Ebml,
+            Mime::Video(MimeVideo::Gif) => MediaFamily::Gif,
+            Mime::Video(MimeVideo::Avi | MimeVideo::Wav) => MediaFamily::Riff,
+            Mime::Video(MimeVideo::Mp3) => MediaFamily::MpegAudio,
+            Mime::Video(MimeVideo::Asf) => MediaFamily::Asf,
+            Mime::Video(MimeVideo::MpegTs) => MediaFamily::MpegTs,
+            Mime::Video(MimeVideo::Mxf) => MediaFamily::Mxf,
+            Mime::Video(MimeVideo::Dsf | MimeVideo::Dff) => MediaFamily::Dsd,
+            Mime::Xmp => MediaFamily::Xmp,
+        }
+    }
+
+    /// Returns a human readable name of the file format, e.g. `"HEIC"`.
+    pub fn name(&self) -> &'static str {
+        crate::dump::mime_name(self.mime)
+    }
+
+    /// Returns true if the file is parsed as [`TrackInfo`](crate::TrackInfo),
+    /// see [`MediaSource::has_track`](crate::MediaSource::has_track).
+    pub fn has_track(&self) -> bool {
+        matches!(self.mime, Mime::Video(_))
+    }
+
+    /// Returns true if the file is parsed as [`ExifIter`](crate::ExifIter),
+    /// see [`MediaSource::has_exif`](crate::MediaSource::has_exif).
+    pub fn has_exif(&self) -> bool {
+        matches!(self.mime, Mime::Image(_))
+    }
+}
+
+impl Display for MediaType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.mime().fmt(f)
+    }
+}
+
 fn get_ebml_doc_type(input: &[u8]) -> crate::Result<String> {
     let mut cursor = Cursor::new(input);
     let doc = parse_ebml_doc_type(&mut cursor)?;
     Ok(doc)
 }
 
+/// Pentax PEF & Samsung SRW files use the standard TIFF signature, they are
+/// told apart by the `Make` & `Model` of ifd0. DNG files (which have a
+/// `DNGVersion` tag) made by the same vendors remain TIFF.
+const TIFF_MAKE: u16 = 0x010f;
+const TIFF_MODEL: u16 = 0x0110;
+
+// Maximum size of the header read for detecting TIFF based raw files, see
+// `read_header`
+const MAX_TIFF_HEADER_SIZE: usize = 64 * 1024;
+
+/// Reads the header of a file for detecting its type, i.e.
+/// `HEADER_PARSE_BUF_SIZE` bytes. For TIFF files, more data (up to
+/// `MAX_TIFF_HEADER_SIZE`) is read to reach the `Make` & `Model` of IFD0,
+/// which identify the TIFF based raw files.
+pub(crate) fn read_header<R: Read>(reader: &mut R) -> std::io::Result<Vec<u8>> {
+    let mut buf = Vec::with_capacity(HEADER_PARSE_BUF_SIZE);
+    reader
+        .by_ref()
+        .take(HEADER_PARSE_BUF_SIZE as u64)
+        .read_to_end(&mut buf)?;
+    while let Some(size) = more_header_size(&buf) {
+        let len = buf.len();
+        reader
+            .by_ref()
+            .take((size - len) as u64)
+            .read_to_end(&mut buf)?;
+        if buf.len() < size {
+            break;
+        }
+    }
+    Ok(buf)
+}
+
+/// Returns the size of the header to be read if `header` is not enough for
+/// detecting the file type, see [`read_header`].
+pub(crate) fn more_header_size(header: &[u8]) -> Option<usize> {
+    tiff_header_size(header).filter(|x| *x > header.len() && *x <= MAX_TIFF_HEADER_SIZE)
+}
+
+/// Returns the size of the data needed by `tiff_mime`, i.e. the entries of
+/// IFD0 and the `Make` & `Model` values. Returns `None` if `input` is not a
+/// TIFF file.
+fn tiff_header_size(input: &[u8]) -> Option<usize> {
+    let (_, header) = TiffHeader::parse(input).ok()?;
+    let (ifd0, endian) = (header.ifd0_offset as usize, header.endian);
+    let Some(num) = u16_at(input, ifd0, endian) else {
+        return Some(ifd0 + 2);
+    };
+    let entries = ifd0 + 2 + num as usize * IFD_ENTRY_SIZE;
+    let Some((fields, _)) = ifd_fields(input, ifd0, endian) else {
+        return Some(entries);
+    };
+    let size = fields
+        .iter()
+        .filter(|x| matches!(x.code, TIFF_MAKE | TIFF_MODEL))
+        .map(|x| x.value_range().end)
+        .fold(entries, usize::max);
+    Some(size)
+}
+
+fn tiff_mime(input: &[u8], header: &TiffHeader) -> MimeImage {
+    const DNG_VERSION: u16 = 0xc612;
+
+    let Some((fields, _)) = ifd_fields(input, header.ifd0_offset as usize, header.endian) else {
+        return MimeImage::Tiff;
+    };
+    if fields.iter().any(|x| x.code == DNG_VERSION) {
+        return MimeImage::Tiff;
+    }
+    let text = |code| {
+        let field = fields
+            .iter()
+            .find(|x| x.code == code && x.format == Some(DataFormat::Text))?;
+        input
+            .get(field.value_range())
+            .map(|x| x.to_ascii_uppercase())
+    };
+    let starts_with = |code, prefix: &[u8]| text(code).is_some_and(|x| x.starts_with(prefix));
+
+    // Newer Pentax cameras are made by "RICOH IMAGING COMPANY, LTD.", but
+    // their models are still named "PENTAX ..."
+    if starts_with(TIFF_MAKE, b"PENTAX") || starts_with(TIFF_MODEL, b"PENTAX") {
+        MimeImage::Pef
+    } else if starts_with(TIFF_MAKE, b"SAMSUNG") {
+        MimeImage::Srw
+    } else {
+        MimeImage::Tiff
+    }
+}
+
 fn parse_bmff_mime(input: &[u8]) -> crate::Result<Mime> {
     let (ftyp, Some(major_brand)) =
         get_ftyp_and_major_brand(input).map_err(|_| crate::Error::UnrecognizedFileFormat)?
@@ -196,11 +490,20 @@ fn parse_bmff_mime(input: &[u8]) -> crate::Result<Mime> {
         return Ok(Mime::Video(MimeVideo::QuickTime));
     }
 
+    // Check if it is an AVIF file
+    if AVIF_BRAND_NAMES.contains(&major_brand) {
+        return Ok(Mime::Image(MimeImage::Avif));
+    }
+
     // Check if it is a HEIF file
     if HEIF_HEIC_BRAND_NAMES.contains(&major_brand) {
         if HEIC_BRAND_NAMES.contains(&major_brand) {
             return Ok(Mime::Image(MimeImage::Heic));
         }
+        // e.g.: "mif1" with an "avif" compatible brand
+        if has_avif_brand(ftyp.body_data()) {
+            return Ok(Mime::Image(MimeImage::Avif));
+        }
         return Ok(Mime::Image(MimeImage::Heif));
     }
 
@@ -222,6 +525,10 @@ fn parse_bmff_mime(input: &[u8]) -> crate::Result<Mime> {
         return Ok(Mime::Video(MimeVideo::QuickTime));
     }
 
+    if has_avif_brand(compatible_brands) {
+        return Ok(Mime::Image(MimeImage::Avif));
+    }
+
     if HEIF_HEIC_BRAND_NAMES
         .iter()
         .any(|x| compatible_brands.find_substring(*x).is_some())
@@ -234,7 +541,7 @@ fn parse_bmff_mime(input: &[u8]) -> crate::Result<Mime> {
 
     if MP4_BRAND_NAMES
         .iter()
-        .any(|v| compatible_brands.subslice_range(v.as_bytes()).is_some())
+        .any(|v| compatible_brands.find_substring(v.as_bytes()).is_some())
     {
         if major_brand.starts_with(b"3gp") {
             return Ok(Mime::Video(MimeVideo::_3gpp));
@@ -255,6 +562,12 @@ fn parse_bmff_mime(input: &[u8]) -> crate::Result<Mime> {
     Err(crate::Error::UnrecognizedFileFormat)
 }
 
+fn has_avif_brand(compatible_brands: &[u8]) -> bool {
+    AVIF_BRAND_NAMES
+        .iter()
+        .any(|x| compatible_brands.find_substring(*x).is_some())
+}
+
 #[allow(deprecated)]
 fn check_bmff(input: &[u8]) -> crate::Result<FileFormat> {
     let (ftyp, Some(major_brand)) = get_ftyp_and_major_brand(input)? else {
@@ -272,7 +585,7 @@ fn check_bmff(input: &[u8]) -> crate::Result<FileFormat> {
     }
 
     // Check if it is a HEIF file
-    if HEIF_HEIC_BRAND_NAMES.contains(&major_brand) {
+    if HEIF_HEIC_BRAND_NAMES.contains(&major_brand) || AVIF_BRAND_NAMES.contains(&major_brand) {
         return Ok(FileFormat::Heif);
     }
 
@@ -293,6 +606,7 @@ fn check_bmff(input: &[u8]) -> crate::Result<FileFormat> {
 
     if HEIF_HEIC_BRAND_NAMES
         .iter()
+        .chain(AVIF_BRAND_NAMES)
         .any(|x| compatible_brands.contains(x))
     {
         return Ok(FileFormat::Heif);
@@ -351,7 +665,7 @@ fn get_compatible_brands(body: &[u8]) -> crate::Result<Vec<&[u8]>> {
 #[allow(deprecated)]
 #[cfg(test)]
 mod tests {
-    use std::ops::Deref;
+    use std::{ops::Deref, path::Path};
 
     use super::*;
     use test_case::test_case;
@@ -359,7 +673,11 @@ mod tests {
     use MimeImage::*;
     use MimeVideo::*;
 
-    use crate::testkit::{open_sample, read_sample};
+    use crate::{
+        exif::{u16_bytes, u32_bytes},
+        testkit::{bbox, open_sample, read_sample},
+    };
+    use nom::number::Endianness;
 
     #[test_case("exif.heic", Image(Heic))]
     #[test_case("exif.jpg", Image(Jpeg))]
@@ -377,6 +695,214 @@ mod tests {
         assert_eq!(m, mime);
     }
 
+    #[test_case(b"avif", b"mif1miaf", Image(Avif))]
+    #[test_case(b"avis", b"msf1iso8", Image(Avif))]
+    #[test_case(b"mif1", b"avifmiaf", Image(Avif))]
+    #[test_case(b"mif1", b"heicmiaf", Image(Heif))]
+    #[test_case(b"msf1", b"msf1iso8", Image(Heif))]
+    #[test_case(b"msf1", b"msf1avis", Image(Avif))]
+    #[test_case(b"hevc", b"msf1mif1heic", Image(Heif))]
+    fn mime_brands(major_brand: &[u8], compatible_brands: &[u8], mime: Mime) {
+        let ftyp = [major_brand, &[0; 4], compatible_brands].concat();
+        let data = [bbox(b"ftyp", &ftyp), bbox(b"meta", b"")].concat();
+
+        let m: Mime = data.deref().try_into().unwrap();
+        assert_eq!(m, mime);
+
+        let ff: FileFormat = data.deref().try_into().unwrap();
+        assert_eq!(ff, FileFormat::Heif);
+    }
+
+    #[test]
+    fn mime_avi() {
+        let data = crate::avi::tests::make_avi(1, 16, false);
+        let m: Mime = data.deref().try_into().unwrap();
+        assert_eq!(m, Video(Avi));
+    }
+
+    #[test]
+    fn mime_wav() {
+        let data = crate::wav::tests::make_wav(10, true);
+        let m: Mime = data.deref().try_into().unwrap();
+        assert_eq!(m, Video(Wav));
+    }
+
+    #[test_case(&crate::id3::tests::make_mp3(3, 16, false); "id3v2")]
+    #[test_case(&[0xFF, 0xFB, 0x90, 0x00, 0, 0]; "layer 3 frame")]
+    fn mime_mp3(data: &[u8]) {
+        let m: Mime = data.try_into().unwrap();
+        assert_eq!(m, Video(Mp3));
+    }
+
+    #[test]
+    fn mime_m4a() {
+        // Only compatible brands are well-known MP4 brands
+        let data = [&28_u32.to_be_bytes()[..], b"ftypM4A \0\0\0\0M4A mp42isom"].concat();
+        let m: Mime = data.deref().try_into().unwrap();
+        assert_eq!(m, Video(Mp4));
+    }
+
+    #[test]
+    fn mime_asf() {
+        let data = crate::asf::tests::make_asf(false, 16);
+        let m: Mime = data.deref().try_into().unwrap();
+        assert_eq!(m, Video(Asf));
+    }
+
+    #[test_case(false; "ts")]
+    #[test_case(true; "m2ts")]
+    fn mime_mpegts(m2ts: bool) {
+        let data = crate::mpegts::tests::make_ts(m2ts, true);
+        // Only the header is available when detecting
+        let m: Mime = data[..128].try_into().unwrap();
+        assert_eq!(m, Video(MpegTs));
+    }
+
+    #[test]
+    fn mime_mxf() {
+        let data = crate::mxf::tests::make_mxf(0);
+        let m: Mime = data.deref().try_into().unwrap();
+        assert_eq!(m, Video(Mxf));
+    }
+
+    #[test]
+    fn mime_dsd() {
+        let m: Mime = crate::dsd::tests::make_dsf(false)
+            .deref()
+            .try_into()
+            .unwrap();
+        assert_eq!(m, Video(Dsf));
+        let m: Mime = crate::dsd::tests::make_dff(b"DSD ", false)
+            .deref()
+            .try_into()
+            .unwrap();
+        assert_eq!(m, Video(Dff));
+    }
+
+    #[test_case(&[0xFF, 0xFE, b'a', 0, b'b', 0]; "utf16 bom")]
+    #[test_case(&[0xFF, 0xFB, 0xF0, 0x00, 0, 0]; "invalid bitrate")]
+    fn mime_not_mp3(data: &[u8]) {
+        assert!(Mime::try_from(data).is_err());
+    }
+
+    // TIFF based raw files which use the standard TIFF signature
+    #[test_case(Endianness::Big, "PENTAX Corporation", false, Pef, "image/x-pentax-pef"; "pef")]
+    #[test_case(Endianness::Little, "SAMSUNG", false, Srw, "image/x-samsung-srw"; "srw")]
+    #[test_case(Endianness::Big, "PENTAX", true, Tiff, "image/tiff"; "pentax dng")]
+    #[test_case(Endianness::Little, "Canon", false, Tiff, "image/tiff"; "other")]
+    fn mime_tiff_raw(endian: Endianness, make: &str, dng: bool, expect: MimeImage, mime: &str) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        // IFD0 with a Make entry, whose value follows the IFD, and an
+        // optional DNGVersion entry
+        let num = if dng { 2 } else { 1 };
+        let mut data = match endian {
+            Endianness::Big => b"MM\0*".to_vec(),
+            _ => b"II*\0".to_vec(),
+        };
+        data.extend(u32_bytes(8, endian));
+        data.extend(u16_bytes(num, endian));
+        data.extend(u16_bytes(0x010f, endian));
+        data.extend(u16_bytes(2, endian));
+        data.extend(u32_bytes(make.len() as u32 + 1, endian));
+        data.extend(u32_bytes(8 + 2 + num as u32 * 12 + 4, endian));
+        if dng {
+            data.extend(u16_bytes(0xc612, endian));
+            data.extend(u16_bytes(1, endian));
+            data.extend(u32_bytes(4, endian));
+            data.extend([1, 4, 0, 0]);
+        }
+        data.extend(u32_bytes(0, endian));
+        data.extend_from_slice(make.as_bytes());
+        data.push(0);
+
+        let m: Mime = data.as_slice().try_into().unwrap();
+        assert_eq!(m, Image(expect));
+        let t = super::detect(data.as_slice()).unwrap().unwrap();
+        assert_eq!(t.mime(), mime);
+        assert_eq!(t.family(), MediaFamily::Tiff);
+
+        let mut parser = crate::MediaParser::new();
+        let ms = crate::MediaSource::seekable(std::io::Cursor::new(data)).unwrap();
+        let iter: crate::ExifIter = parser.parse(ms).unwrap();
+        let exif: crate::Exif = iter.into();
+        assert_eq!(exif.get(crate::ExifTag::Make), Some(&make.into()));
+    }
+
+    /// A raw file with a typical IFD0, i.e. the values of Make & Model are
+    /// stored beyond the 128 bytes of the detection header.
+    #[test_case(Endianness::Big, "PENTAX Corporation", "PENTAX K-3 II", "image/x-pentax-pef"; "pef")]
+    #[test_case(Endianness::Little, "SAMSUNG", "NX500", "image/x-samsung-srw"; "srw")]
+    fn mime_tiff_raw_ifd0(endian: Endianness, make: &str, model: &str, mime: &str) {
+        // (tag, format, count, value), text values are stored out of line
+        let entries: &[(u16, u16, u32, u32)] = &[
+            (0x00fe, 4, 1, 1),       // NewSubfileType
+            (0x0100, 4, 1, 160),     // ImageWidth
+            (0x0101, 4, 1, 120),     // ImageLength
+            (0x0102, 3, 1, 8 << 16), // BitsPerSample
+            (0x0103, 3, 1, 1 << 16), // Compression
+            (0x0106, 3, 1, 2 << 16), // PhotometricInterpretation
+            (0x010f, 2, make.len() as u32 + 1, 0),
+            (0x0110, 2, model.len() as u32 + 1, 0),
+            (0x0111, 4, 1, 0x1000),  // StripOffsets
+            (0x0112, 3, 1, 1 << 16), // Orientation
+            (0x0115, 3, 1, 3 << 16), // SamplesPerPixel
+            (0x0116, 4, 1, 120),     // RowsPerStrip
+            (0x0117, 4, 1, 0),       // StripByteCounts
+            (0x0131, 2, 6, 0),       // Software
+        ];
+        let mut data = match endian {
+            Endianness::Big => b"MM\0*".to_vec(),
+            _ => b"II*\0".to_vec(),
+        };
+        data.extend(u32_bytes(8, endian));
+        data.extend(u16_bytes(entries.len() as u16, endian));
+        let mut values = Vec::new();
+        let values_start = 8 + 2 + entries.len() * 12 + 4;
+        for &(tag, format, count, value) in entries {
+            data.extend(u16_bytes(tag, endian));
+            data.extend(u16_bytes(format, endian));
+            data.extend(u32_bytes(count, endian));
+            let text = match tag {
+                0x010f => Some(make),
+                0x0110 => Some(model),
+                0x0131 => Some("1.00 "),
+                _ => None,
+            };
+            if let Some(text) = text {
+                data.extend(u32_bytes((values_start + values.len()) as u32, endian));
+                values.extend_from_slice(text.as_bytes());
+                values.push(0);
+            } else if format == 3 && endian == Endianness::Little {
+                data.extend(u32_bytes(value >> 16, endian));
+            } else {
+                data.extend(u32_bytes(value, endian));
+            }
+        }
+        data.extend(u32_bytes(0, endian));
+        data.extend(values);
+        assert!(data.len() > HEADER_PARSE_BUF_SIZE);
+
+        // The header alone is not enough
+        let m: Mime = data[..HEADER_PARSE_BUF_SIZE].try_into().unwrap();
+        assert_eq!(m, Image(Tiff));
+
+        let t = super::detect(data.as_slice()).unwrap().unwrap();
+        assert_eq!(t.mime(), mime);
+        let ms = crate::MediaSource::unseekable(data.as_slice()).unwrap();
+        assert_eq!(MediaType { mime: ms.mime }.mime(), mime);
+        let ms = crate::MediaSource::from_slice(&data).unwrap();
+        assert_eq!(MediaType { mime: ms.mime }.mime(), mime);
+
+        let mut parser = crate::MediaParser::new();
+        let ms = crate::MediaSource::seekable(std::io::Cursor::new(data)).unwrap();
+        assert_eq!(MediaType { mime: ms.mime }.mime(), mime);
+        let iter: crate::ExifIter = parser.parse(ms).unwrap();
+        let exif: crate::Exif = iter.into();
+        assert_eq!(exif.get(crate::ExifTag::Make), Some(&make.into()));
+        assert_eq!(exif.get(crate::ExifTag::Model), Some(&model.into()));
+    }
+
     #[test_case("exif.heic", FileFormat::Heif)]
     #[test_case("exif.jpg", FileFormat::Jpeg)]
     #[test_case("meta.mov", FileFormat::QuickTime)]
@@ -394,4 +920,31 @@ mod tests {
         let f = open_sample(path).unwrap();
         FileFormat::try_from_read(f).unwrap_err();
     }
+
+    #[test_case("exif.jpg", "image/jpeg", MediaFamily::Jpeg)]
+    #[test_case("exif.heic", "image/heic", MediaFamily::Isobmff)]
+    #[test_case("tif.tif", "image/tiff", MediaFamily::Tiff)]
+    #[test_case("meta.mov", "video/quicktime", MediaFamily::Isobmff)]
+    #[test_case("meta.mp4", "video/mp4", MediaFamily::Isobmff)]
+    #[test_case("webm_480.webm", "video/webm", MediaFamily::Ebml)]
+    #[test_case("mka.mka", "video/x-matroska", MediaFamily::Ebml)]
+    fn detect(path: &str, mime: &str, family: MediaFamily) {
+        let t = super::detect(open_sample(path).unwrap()).unwrap().unwrap();
+        assert_eq!(t.mime(), mime);
+        assert_eq!(t.to_string(), mime);
+        assert_eq!(t.family(), family);
+
+        let ms = crate::MediaSource::file_path(Path::new("testdata").join(path)).unwrap();
+        assert_eq!(t.has_track(), ms.has_track());
+        assert_eq!(t.has_exif(), ms.has_exif());
+
+        let data = read_sample(path).unwrap();
+        assert_eq!(super::detect(data.as_slice()).unwrap(), Some(t));
+    }
+
+    #[test_case(b""; "empty")]
+    #[test_case(b"plain text"; "text")]
+    fn detect_unsupported(data: &[u8]) {
+        assert_eq!(super::detect(data).unwrap(), None);
+    }
 }
@@ -0,0 +1,154 @@
+use chrono::NaiveDateTime;
+
+use crate::EntryValue;
+
+// Type id of `EntryValue::Time` values
+const TIME_TYPE: u8 = 13;
+
+/// Computes a canonical hash of a set of metadata items, see
+/// [`Exif::fingerprint`](crate::Exif::fingerprint).
+///
+/// Each item is hashed on its own with 64-bit FNV-1a, then the item hashes
+/// are sorted & combined, so the result doesn't depend on the order of the
+/// items, nor on the platform or the Rust version.
+#[derive(Debug, Default)]
+pub(crate) struct Fingerprint {
+    items: Vec<u64>,
+}
+
+impl Fingerprint {
+    /// Adds an item identified by `key` with the value `value`.
+    pub fn add(&mut self, key: &[u8], value: &EntryValue) {
+        let mut h = Fnv::default();
+        h.write_bytes(key);
+        write_value(&mut h, value);
+        self.items.push(h.0);
+    }
+
+    /// Adds an item identified by `key` with a date & time which has no time
+    /// zone, hashed the same way as [`EntryValue::Time`] values otherwise.
+    pub fn add_local_time(&mut self, key: &[u8], time: NaiveDateTime) {
+        let mut h = Fnv::default();
+        h.write_bytes(key);
+        h.write(&[TIME_TYPE]);
+        h.write_bytes(time.format("%Y-%m-%dT%H:%M:%S%.f").to_string().as_bytes());
+        self.items.push(h.0);
+    }
+
+    /// Adds an item identified by `key` with a text value.
+    pub fn add_text(&mut self, key: &[u8], text: &str) {
+        let mut h = Fnv::default();
+        h.write_bytes(key);
+        h.write_bytes(text.as_bytes());
+        self.items.push(h.0);
+    }
+
+    pub fn finish(mut self) -> u64 {
+        self.items.sort_unstable();
+        let mut h = Fnv::default();
+        for x in self.items {
+            h.write(&x.to_le_bytes());
+        }
+        h.0
+    }
+}
+
+struct Fnv(u64);
+
+impl Default for Fnv {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Fnv {
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= *b as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    /// Writes the length before the bytes, so that adjacent fields can't be
+    /// confused with each other.
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        self.write(&(bytes.len() as u64).to_le_bytes());
+        self.write(bytes);
+    }
+}
+
+fn write_value(h: &mut Fnv, value: &EntryValue) {
+    fn le<T: Copy, const N: usize>(v: &[T], f: impl Fn(T) -> [u8; N]) -> Vec<u8> {
+        v.iter().flat_map(|x| f(*x)).collect()
+    }
+    let rational = |x: (u32, u32)| {
+        let mut bytes = [0; 8];
+        bytes[..4].copy_from_slice(&x.0.to_le_bytes());
+        bytes[4..].copy_from_slice(&x.1.to_le_bytes());
+        bytes
+    };
+
+    // Type id, bytes
+    let (ty, bytes): (u8, Vec<u8>) = match value {
+        EntryValue::Text(s) => (0, s.as_bytes().to_vec()),
+        EntryValue::URational(x) => (1, rational((x.0, x.1)).to_vec()),
+        EntryValue::IRational(x) => (2, rational((x.0 as u32, x.1 as u32)).to_vec()),
+        EntryValue::U8(x) => (3, x.to_le_bytes().to_vec()),
+        EntryValue::U16(x) => (4, x.to_le_bytes().to_vec()),
+        EntryValue::U32(x) => (5, x.to_le_bytes().to_vec()),
+        EntryValue::U64(x) => (6, x.to_le_bytes().to_vec()),
+        EntryValue::I8(x) => (7, x.to_le_bytes().to_vec()),
+        EntryValue::I16(x) => (8, x.to_le_bytes().to_vec()),
+        EntryValue::I32(x) => (9, x.to_le_bytes().to_vec()),
+        EntryValue::I64(x) => (10, x.to_le_bytes().to_vec()),
+        EntryValue::F32(x) => (11, x.to_bits().to_le_bytes().to_vec()),
+        EntryValue::F64(x) => (12, x.to_bits().to_le_bytes().to_vec()),
+        EntryValue::Time(t) => (TIME_TYPE, t.to_rfc3339().into_bytes()),
+        EntryValue::Undefined(v) => (14, v.clone()),
+        EntryValue::URationalArray(v) => (15, le(v, |x| rational((x.0, x.1)))),
+        EntryValue::IRationalArray(v) => (16, le(v, |x| rational((x.0 as u32, x.1 as u32)))),
+        EntryValue::U8Array(v) => (17, v.clone()),
+        EntryValue::U16Array(v) => (18, le(v, u16::to_le_bytes)),
+        EntryValue::U32Array(v) => (19, le(v, u32::to_le_bytes)),
+        EntryValue::F64Array(v) => (20, le(v, |x: f64| x.to_bits().to_le_bytes())),
+        EntryValue::I8Array(v) => (21, le(v, i8::to_le_bytes)),
+        EntryValue::I16Array(v) => (22, le(v, i16::to_le_bytes)),
+        EntryValue::I32Array(v) => (23, le(v, i32::to_le_bytes)),
+        EntryValue::F32Array(v) => (24, le(v, |x: f32| x.to_bits().to_le_bytes())),
+    };
+    h.write(&[ty]);
+    h.write_bytes(&bytes);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fingerprint() {
+        let hash = |items: &[(&str, EntryValue)]| {
+            let mut fp = Fingerprint::default();
+            for (k, v) in items {
+                fp.add(k.as_bytes(), v);
+            }
+            fp.finish()
+        };
+
+        let a = ("Make", EntryValue::from("Canon"));
+        let b = ("Width", EntryValue::U32(4000));
+        assert_eq!(hash(&[a.clone(), b.clone()]), hash(&[b.clone(), a.clone()]));
+        assert_ne!(
+            hash(&[a.clone(), b.clone()]),
+            hash(std::slice::from_ref(&a))
+        );
+        // Same number, different types
+        assert_ne!(
+            hash(std::slice::from_ref(&b)),
+            hash(&[("Width", EntryValue::U16(4000))])
+        );
+        assert_ne!(hash(&[("ab", "c".into())]), hash(&[("a", "bc".into())]));
+        // The hash is stable
+        assert_eq!(hash(&[]), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(hash(&[a]), 0x9d4e_b4b2_52b0_7d81);
+    }
+}
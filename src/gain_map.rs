@@ -0,0 +1,531 @@
+use std::{
+    io::{Read, Seek, SeekFrom},
+    ops::Range,
+};
+
+use nom::{
+    combinator::fail,
+    number::{
+        complete::{be_u16, be_u32, u16, u32, u8},
+        Endianness,
+    },
+    sequence::tuple,
+    IResult,
+};
+
+use crate::{
+    error::{nom_error_to_parsing_error_with_state, ParsingErrorState},
+    exif::{jpeg_xmp, TiffHeader},
+    file::{Mime, MimeImage},
+    heif, jpeg,
+    motion_photo::NS_CONTAINER,
+    slice::SubsliceOffset,
+    Xmp,
+};
+
+/// Adobe HDR gain map namespace (`hdrgm:`)
+const NS_HDRGM: &str = "http://ns.adobe.com/hdr-gain-map/1.0/";
+/// Apple HDR gain map namespace (`HDRGainMap:`)
+const NS_APPLE_HDR_GAIN_MAP: &str = "http://ns.apple.com/HDRGainMap/1.0/";
+
+/// Auxiliary type (`auxC`) of the gain map image item in Apple HEIC files.
+const APPLE_GAIN_MAP_AUX_TYPE: &str = "urn:com:apple:photo:2020:aux:hdrgainmap";
+
+/// MPF tag `MPEntry`, an array of 16 bytes entries, one for each image.
+const MP_ENTRY_TAG: u16 = 0xb002;
+const MP_ENTRY_SIZE: usize = 16;
+
+/// Only the beginning of a gain map image (i.e. its APP segments) is read,
+/// and XMP items larger than this are ignored.
+const MAX_METADATA_SIZE: u64 = 64 * 1024;
+
+/// An HDR gain map, i.e. an auxiliary image which describes how to brighten
+/// the (SDR) primary image when rendering it on an HDR display.
+///
+/// Supported formats:
+///
+/// - JPEG files with Adobe gain map XMP properties (the `hdrgm` namespace,
+///   e.g. Android "Ultra HDR" photos), or with ISO 21496-1 gain map metadata.
+///   The gain map is a JPEG image after the primary image, located by the MPF
+///   index or the `Container:Directory` XMP property.
+///
+/// - HEIC files with an Apple HDR gain map auxiliary image.
+///
+/// `GainMapInfo` can be parsed from a seekable source by a
+/// [`MediaParser`](crate::MediaParser), since the headroom values are stored
+/// in the metadata of the gain map image.
+///
+/// ```rust
+/// use nom_exif::*;
+///
+/// let mut parser = MediaParser::new();
+/// let ms = MediaSource::file_path("./testdata/exif.jpg").unwrap();
+/// let res: Result<GainMapInfo> = parser.parse(ms);
+///
+/// // not an HDR photo
+/// assert!(res.is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct GainMapInfo {
+    pub kind: GainMapKind,
+
+    /// Byte range of the gain map image in the file, `None` if it can't be
+    /// located.
+    pub range: Option<Range<u64>>,
+
+    /// HDR headroom (log2) of the primary image, i.e. `hdrgm:HDRCapacityMin`,
+    /// or the base headroom of ISO 21496-1 metadata.
+    pub base_headroom: Option<f64>,
+
+    /// HDR headroom at which the gain map is fully applied, i.e. log2 value
+    /// `hdrgm:HDRCapacityMax`, the alternate headroom of ISO 21496-1
+    /// metadata, or `HDRGainMap:HDRGainMapHeadroom` of Apple gain maps.
+    pub alternate_headroom: Option<f64>,
+}
+
+/// How a [`GainMapInfo`] is described.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GainMapKind {
+    /// Adobe gain map XMP properties (`hdrgm:`)
+    Adobe,
+    /// ISO 21496-1 gain map metadata, usually along with the Adobe XMP
+    /// properties
+    Iso21496,
+    /// Apple HDR gain map auxiliary image of HEIC files
+    Apple,
+}
+
+/// Gain map markers found in the primary image.
+#[derive(Debug)]
+pub(crate) struct GainMapMarkers {
+    kind: GainMapKind,
+    location: Option<GainMapLocation>,
+    /// XMP items of a HEIC file, one of them may describe the gain map
+    xmp_ranges: Vec<Range<u64>>,
+}
+
+#[derive(Debug)]
+enum GainMapLocation {
+    Range(Range<u64>),
+    /// (distance from the start of the gain map to the end of the file,
+    /// length of the gain map)
+    FromEnd(u64, u64),
+}
+
+impl GainMapMarkers {
+    fn range(&self, file_len: u64) -> Option<Range<u64>> {
+        let range = match self.location.as_ref()? {
+            GainMapLocation::Range(range) => range.clone(),
+            GainMapLocation::FromEnd(offset, len) => {
+                let start = file_len.checked_sub(*offset)?;
+                start..start.checked_add(*len)?
+            }
+        };
+        (range.start < range.end && range.end <= file_len).then_some(range)
+    }
+
+    /// Returns the byte ranges to be read to get the gain map metadata.
+    fn metadata_ranges(&self, range: Option<&Range<u64>>, file_len: u64) -> Vec<Range<u64>> {
+        match self.kind {
+            GainMapKind::Apple => self
+                .xmp_ranges
+                .iter()
+                .filter(|x| x.end <= file_len && x.end - x.start <= MAX_METADATA_SIZE)
+                .cloned()
+                .collect(),
+            GainMapKind::Adobe | GainMapKind::Iso21496 => range
+                .map(|x| x.start..x.end.min(x.start + MAX_METADATA_SIZE))
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    /// Builds `GainMapInfo` from the data read from
+    /// [`GainMapMarkers::metadata_ranges`].
+    fn into_info(self, range: Option<Range<u64>>, metadata: &[Vec<u8>]) -> GainMapInfo {
+        let mut info = GainMapInfo {
+            kind: self.kind,
+            range,
+            base_headroom: None,
+            alternate_headroom: None,
+        };
+        for data in metadata {
+            if self.kind == GainMapKind::Apple {
+                let data = data.strip_suffix(&[0]).unwrap_or(data);
+                let xmp = std::str::from_utf8(data).ok().and_then(|x| x.parse().ok());
+                info.alternate_headroom =
+                    xmp.and_then(|x| xmp_f64(&x, NS_APPLE_HDR_GAIN_MAP, "HDRGainMapHeadroom"));
+                if info.alternate_headroom.is_some() {
+                    break;
+                }
+                continue;
+            }
+
+            let segments = match jpeg::collect_app1_segments(data) {
+                Ok((_, segments)) => segments,
+                Err(e) => {
+                    tracing::warn!(?e, "Ignored: invalid gain map image");
+                    continue;
+                }
+            };
+            if let Some((base, alternate)) = segments.iso_gain_map.and_then(parse_iso_headroom) {
+                info.base_headroom = Some(base);
+                info.alternate_headroom = Some(alternate);
+            } else if let Some(xmp) = parse_jpeg_xmp(&segments) {
+                info.base_headroom = xmp_f64(&xmp, NS_HDRGM, "HDRCapacityMin");
+                info.alternate_headroom = xmp_f64(&xmp, NS_HDRGM, "HDRCapacityMax");
+            }
+        }
+        info
+    }
+}
+
+fn parse_jpeg_xmp(segments: &jpeg::App1Segments) -> Option<Xmp> {
+    match Xmp::from_packets(&jpeg_xmp(segments)?) {
+        Ok(xmp) => Some(xmp),
+        Err(e) => {
+            tracing::warn!(?e, "Ignored: invalid XMP");
+            None
+        }
+    }
+}
+
+fn xmp_f64(xmp: &Xmp, namespace: &str, name: &str) -> Option<f64> {
+    xmp.get(namespace, name)?.as_str()?.trim().parse().ok()
+}
+
+/// Returns (base headroom, alternate headroom) of ISO 21496-1 gain map
+/// metadata, `None` if it only contains the version (i.e. the metadata of
+/// the primary image).
+fn parse_iso_headroom(input: &[u8]) -> Option<(f64, f64)> {
+    fn parse(input: &[u8]) -> IResult<&[u8], (f64, f64)> {
+        let ratio = |n: u32, d: u32| n as f64 / d as f64;
+        // minimum version, writer version, flags
+        let (remain, (_, _, flags)) = tuple((be_u16, be_u16, u8))(input)?;
+        if flags & 0x08 != 0 {
+            // with a common denominator
+            let (remain, (d, base, alternate)) = tuple((be_u32, be_u32, be_u32))(remain)?;
+            Ok((remain, (ratio(base, d), ratio(alternate, d))))
+        } else {
+            let (remain, (base_n, base_d, alt_n, alt_d)) =
+                tuple((be_u32, be_u32, be_u32, be_u32))(remain)?;
+            Ok((remain, (ratio(base_n, base_d), ratio(alt_n, alt_d))))
+        }
+    }
+    parse(input)
+        .ok()
+        .map(|x| x.1)
+        .filter(|(base, alternate)| base.is_finite() && alternate.is_finite())
+}
+
+/// Returns the byte ranges (relative to the MPF TIFF header) of the images in
+/// the MP index IFD, the first one is the primary image.
+fn parse_mpf_entries(mpf: &[u8]) -> IResult<&[u8], Vec<Range<u64>>> {
+    let (_, header) = TiffHeader::parse(mpf)?;
+    let endian: Endianness = header.endian;
+    let Some(ifd) = mpf.get(header.ifd0_offset as usize..) else {
+        return fail(mpf);
+    };
+    let (mut remain, num) = u16(endian)(ifd)?;
+    for _ in 0..num {
+        let (rem, (tag, _, count, offset)) =
+            tuple((u16(endian), u16(endian), u32(endian), u32(endian)))(remain)?;
+        remain = rem;
+        if tag != MP_ENTRY_TAG {
+            continue;
+        }
+        let Some(entries) = (offset as usize)
+            .checked_add(count as usize)
+            .and_then(|end| mpf.get(offset as usize..end))
+        else {
+            return fail(mpf);
+        };
+        let ranges = entries
+            .chunks_exact(MP_ENTRY_SIZE)
+            .map(|x| {
+                // attribute, size, offset
+                let (_, (_, size, offset)) = tuple((u32(endian), u32(endian), u32(endian)))(x)?;
+                Ok(offset as u64..offset as u64 + size as u64)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        return Ok((remain, ranges));
+    }
+    Ok((remain, Vec::new()))
+}
+
+/// Returns the location of the item with semantic "GainMap" in the
+/// `Container:Directory`, which is counted from the end of the file.
+fn directory_gain_map(xmp: &Xmp) -> Option<GainMapLocation> {
+    let items = xmp.get(NS_CONTAINER, "Directory")?.as_array()?;
+    let fields = (1..=items.len())
+        .map(|i| {
+            let field = |name| {
+                let path = format!("Directory[{i}]/Item/{name}");
+                xmp.get(NS_CONTAINER, &path)?.as_str().map(|x| x.trim())
+            };
+            let num = |name| field(name).and_then(|x| x.parse::<u64>().ok());
+            (field("Semantic"), num("Length"), num("Padding"))
+        })
+        .collect::<Vec<_>>();
+    let pos = fields.iter().position(|x| x.0 == Some("GainMap"))?;
+    let len = fields[pos].1?;
+    let offset = fields[pos..]
+        .iter()
+        .try_fold(0_u64, |acc, (_, len, padding)| {
+            acc.checked_add(len.unwrap_or_default())?
+                .checked_add(padding.unwrap_or_default())
+        })?;
+    Some(GainMapLocation::FromEnd(offset, len))
+}
+
+fn extract_jpeg_markers(input: &[u8]) -> Result<Option<GainMapMarkers>, ParsingErrorState> {
+    let (_, segments) = jpeg::collect_app1_segments(input)
+        .map_err(|e| nom_error_to_parsing_error_with_state(e, None))?;
+    let xmp = parse_jpeg_xmp(&segments);
+    let has_hdrgm = xmp
+        .as_ref()
+        .is_some_and(|x| x.get(NS_HDRGM, "Version").is_some());
+    let directory = xmp.as_ref().and_then(directory_gain_map);
+
+    let kind = if segments.iso_gain_map.is_some() {
+        GainMapKind::Iso21496
+    } else if has_hdrgm || directory.is_some() {
+        GainMapKind::Adobe
+    } else {
+        return Ok(None);
+    };
+
+    // Offsets in MPF are relative to its TIFF header
+    let mpf = segments.mpf.and_then(|mpf| {
+        let start = input.subslice_offset(mpf)? as u64;
+        let (_, entries) = parse_mpf_entries(mpf)
+            .map_err(|e| tracing::warn!(?e, "Ignored: invalid MPF segment"))
+            .ok()?;
+        let gain_map = entries.get(1)?;
+        Some(GainMapLocation::Range(
+            start + gain_map.start..start + gain_map.end,
+        ))
+    });
+
+    Ok(Some(GainMapMarkers {
+        kind,
+        location: mpf.or(directory),
+        xmp_ranges: Vec::new(),
+    }))
+}
+
+/// Extracts the gain map markers of the primary image, returns `None` if
+/// there is no gain map.
+pub(crate) fn extract_gain_map_markers(
+    mime: Mime,
+    input: &[u8],
+) -> Result<Option<GainMapMarkers>, ParsingErrorState> {
+    match mime {
+        Mime::Image(MimeImage::Jpeg) => extract_jpeg_markers(input),
+        Mime::Image(MimeImage::Heic | MimeImage::Heif) => {
+            let (_, meta) = heif::parse_meta_box(input)
+                .map_err(|e| nom_error_to_parsing_error_with_state(e, None))?;
+            let Some(meta) = meta else {
+                return Ok(None);
+            };
+            let Some(item_id) = meta.auxiliary_item(APPLE_GAIN_MAP_AUX_TYPE) else {
+                return Ok(None);
+            };
+            let to_u64 = |x: Range<usize>| x.start as u64..x.end as u64;
+            Ok(Some(GainMapMarkers {
+                kind: GainMapKind::Apple,
+                location: meta
+                    .item_data_offset(item_id)
+                    .map(|x| GainMapLocation::Range(to_u64(x))),
+                xmp_ranges: meta.xmp_data_offsets().into_iter().map(to_u64).collect(),
+            }))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Locates the gain map, and reads the headroom values from its metadata.
+pub(crate) fn read_gain_map<R: Read + Seek>(
+    reader: &mut R,
+    markers: GainMapMarkers,
+) -> crate::Result<GainMapInfo> {
+    let file_len = reader.seek(SeekFrom::End(0))?;
+    let range = markers.range(file_len);
+    let mut metadata = Vec::new();
+    for x in markers.metadata_ranges(range.as_ref(), file_len) {
+        let mut buf = vec![0; (x.end - x.start) as usize];
+        reader.seek(SeekFrom::Start(x.start))?;
+        reader.read_exact(&mut buf)?;
+        metadata.push(buf);
+    }
+    if range.is_none() {
+        tracing::warn!(kind = ?markers.kind, "gain map image not found");
+    }
+    Ok(markers.into_info(range, &metadata))
+}
+
+/// `async` version of [`read_gain_map`].
+#[cfg(feature = "async")]
+pub(crate) async fn read_gain_map_async<R>(
+    reader: &mut R,
+    markers: GainMapMarkers,
+) -> crate::Result<GainMapInfo>
+where
+    R: tokio::io::AsyncRead + tokio::io::AsyncSeek + Unpin,
+{
+    use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+    let file_len = reader.seek(SeekFrom::End(0)).await?;
+    let range = markers.range(file_len);
+    let mut metadata = Vec::new();
+    for x in markers.metadata_ranges(range.as_ref(), file_len) {
+        let mut buf = vec![0; (x.end - x.start) as usize];
+        reader.seek(SeekFrom::Start(x.start)).await?;
+        reader.read_exact(&mut buf).await?;
+        metadata.push(buf);
+    }
+    if range.is_none() {
+        tracing::warn!(kind = ?markers.kind, "gain map image not found");
+    }
+    Ok(markers.into_info(range, &metadata))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MediaParser, MediaSource};
+    use std::io::Cursor;
+    use test_case::test_case;
+
+    fn segment(marker: u8, payload: &[&[u8]]) -> Vec<u8> {
+        let len: usize = payload.iter().map(|x| x.len()).sum();
+        let mut data = vec![0xff, marker];
+        data.extend(((len + 2) as u16).to_be_bytes());
+        payload.iter().for_each(|x| data.extend(*x));
+        data
+    }
+
+    fn xmp_segment(props: &str) -> Vec<u8> {
+        let xmp = format!(
+            r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about=""
+    xmlns:hdrgm="http://ns.adobe.com/hdr-gain-map/1.0/"
+    xmlns:Container="http://ns.google.com/photos/1.0/container/"
+    xmlns:Item="http://ns.google.com/photos/1.0/container/item/"
+    {props}
+  </rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>"#
+        );
+        segment(0xe1, &[jpeg::XMP_SIGNATURE, xmp.as_bytes()])
+    }
+
+    /// Builds a big-endian MPF segment with 2 images.
+    fn mpf_segment(primary_len: u32, gain_map: Range<u32>) -> Vec<u8> {
+        let mut mpf = b"MM\0\x2a\0\0\0\x08\0\x01".to_vec();
+        // MPEntry: type UNDEFINED, 32 bytes at offset 26
+        mpf.extend([0xb0, 0x02, 0, 7, 0, 0, 0, 32, 0, 0, 0, 26]);
+        mpf.extend([0; 4]);
+        for (size, offset) in [(primary_len, 0), (gain_map.len() as u32, gain_map.start)] {
+            mpf.extend([0; 4]);
+            mpf.extend(size.to_be_bytes());
+            mpf.extend(offset.to_be_bytes());
+            mpf.extend([0; 4]);
+        }
+        segment(0xe2, &[jpeg::MPF_SIGNATURE, &mpf])
+    }
+
+    /// Builds an Ultra HDR like JPEG, returns the data & the gain map range.
+    fn ultra_hdr(mpf: bool, iso: bool) -> (Vec<u8>, Range<u64>) {
+        let image_data = [0xff, 0xda, 0, 2, 0x12, 0x34, 0xff, 0xd9];
+
+        let mut gain_map = vec![0xff, 0xd8];
+        gain_map.extend(xmp_segment(
+            r#"hdrgm:Version="1.0" hdrgm:HDRCapacityMin="0" hdrgm:HDRCapacityMax="2.3">"#,
+        ));
+        if iso {
+            // common denominator 2, base headroom 0, alternate headroom 5
+            let metadata = [0, 0, 0, 0, 0x08, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 5];
+            gain_map.extend(segment(0xe2, &[jpeg::ISO_GAIN_MAP_SIGNATURE, &metadata]));
+        }
+        gain_map.extend(image_data);
+
+        let primary = |mpf_entries: Option<(u32, Range<u32>)>| {
+            let mut data = vec![0xff, 0xd8];
+            data.extend(xmp_segment(&format!(
+                r#"hdrgm:Version="1.0">
+   <Container:Directory><rdf:Seq>
+    <rdf:li rdf:parseType="Resource">
+     <Container:Item Item:Mime="image/jpeg" Item:Semantic="Primary" Item:Length="0"/>
+    </rdf:li>
+    <rdf:li rdf:parseType="Resource">
+     <Container:Item Item:Mime="image/jpeg" Item:Semantic="GainMap" Item:Length="{}"/>
+    </rdf:li>
+   </rdf:Seq></Container:Directory>"#,
+                gain_map.len()
+            )));
+            if iso {
+                let version = [0, 0, 0, 0];
+                data.extend(segment(0xe2, &[jpeg::ISO_GAIN_MAP_SIGNATURE, &version]));
+            }
+            let mpf_start = data.len() + 4 + jpeg::MPF_SIGNATURE.len();
+            if let Some((len, range)) = mpf_entries {
+                data.extend(mpf_segment(len, range));
+            }
+            data.extend(image_data);
+            (data, mpf_start as u32)
+        };
+
+        let data = if mpf {
+            let (data, mpf_start) = primary(Some((0, 0..0)));
+            let len = data.len() as u32;
+            let gain_map_range = len - mpf_start..len - mpf_start + gain_map.len() as u32;
+            primary(Some((len, gain_map_range))).0
+        } else {
+            primary(None).0
+        };
+        let start = data.len() as u64;
+        let range = start..start + gain_map.len() as u64;
+        ([data, gain_map].concat(), range)
+    }
+
+    #[test_case(true, false, GainMapKind::Adobe, (0.0, 2.3))]
+    #[test_case(false, false, GainMapKind::Adobe, (0.0, 2.3))]
+    #[test_case(true, true, GainMapKind::Iso21496, (0.0, 2.5))]
+    fn jpeg_gain_map(mpf: bool, iso: bool, kind: GainMapKind, headroom: (f64, f64)) {
+        let (data, range) = ultra_hdr(mpf, iso);
+
+        let mut parser = MediaParser::new();
+        let ms = MediaSource::seekable(Cursor::new(data)).unwrap();
+        let info: GainMapInfo = parser.parse(ms).unwrap();
+        assert_eq!(
+            info,
+            GainMapInfo {
+                kind,
+                range: Some(range),
+                base_headroom: Some(headroom.0),
+                alternate_headroom: Some(headroom.1),
+            }
+        );
+    }
+
+    #[test_case("exif.jpg", None)]
+    #[test_case("exif.heic", Some(4544598..4934279))]
+    #[test_case("meta.mp4", None)]
+    fn sample(path: &str, range: Option<Range<u64>>) {
+        let mut parser = MediaParser::new();
+        let ms = MediaSource::file_path(format!("testdata/{path}")).unwrap();
+        let res: crate::Result<GainMapInfo> = parser.parse(ms);
+        match range {
+            Some(range) => {
+                let info = res.unwrap();
+                assert_eq!(info.kind, GainMapKind::Apple);
+                assert_eq!(info.range, Some(range));
+            }
+            None => {
+                res.unwrap_err();
+            }
+        }
+    }
+}
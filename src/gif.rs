@@ -0,0 +1,345 @@
+use crate::{
+    error::ParsingError,
+    video::{TrackInfo, TrackInfoTag},
+    xmp::{parse_xmp_date, xmp_property},
+};
+
+const HEADER_SIZE: usize = 13;
+
+const EXTENSION: u8 = 0x21;
+const IMAGE_DESCRIPTOR: u8 = 0x2C;
+const TRAILER: u8 = 0x3B;
+
+const GRAPHIC_CONTROL_LABEL: u8 = 0xF9;
+const APPLICATION_LABEL: u8 = 0xFF;
+
+const XMP_APPLICATION_ID: &[u8] = b"XMP DataXMP";
+
+/// Length of the "magic trailer" appended to XMP data, which makes the raw
+/// XMP data a valid chain of data sub-blocks.
+const XMP_MAGIC_TRAILER_SIZE: usize = 257;
+
+/// Max size of an XMP packet, to avoid exhausting memory on corrupted files.
+const MAX_XMP_SIZE: usize = 16 * 1024 * 1024;
+
+pub(crate) fn check_gif(input: &[u8]) -> bool {
+    input.starts_with(b"GIF87a") || input.starts_with(b"GIF89a")
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum State {
+    #[default]
+    Header,
+    Blocks,
+    /// Skipping the data sub-blocks of an image or an extension.
+    SubBlocks,
+}
+
+/// Parses GIF files block by block. The parsing state is kept between calls,
+/// so data sub-blocks (e.g. image data) which extend beyond the input can be
+/// skipped with `ClearAndSkip`, instead of being loaded into memory.
+///
+/// Refer to: [GIF89a Specification](https://www.w3.org/Graphics/GIF/spec-gif89a.txt)
+#[derive(Debug, Default)]
+pub(crate) struct GifParser {
+    state: State,
+    info: GifInfo,
+}
+
+#[derive(Debug, Clone, Default)]
+struct GifInfo {
+    width: u16,
+    height: u16,
+    frames: u32,
+    /// Sum of frame delays, in hundredths of a second.
+    delay: u64,
+    xmp: Option<String>,
+}
+
+impl GifParser {
+    /// Parses `input`, which starts where the last call stopped.
+    pub(crate) fn parse(&mut self, input: &[u8]) -> Result<TrackInfo, ParsingError> {
+        let mut pos = 0;
+        loop {
+            if pos > input.len() {
+                // e.g.: a color table which extends beyond the input
+                return Err(ParsingError::ClearAndSkip(pos));
+            }
+            match self.step(&input[pos..])? {
+                Step::Consumed(n) => pos += n,
+                Step::Done => return Ok(std::mem::take(&mut self.info).into()),
+                Step::Need(n) if pos > 0 => {
+                    tracing::debug!(n, "need more bytes");
+                    return Err(ParsingError::ClearAndSkip(pos));
+                }
+                Step::Need(n) => return Err(ParsingError::Need(n)),
+                // Clear the data which has been parsed, and skip the
+                // remaining part of the sub-block
+                Step::Skip(n) => return Err(ParsingError::ClearAndSkip(pos + n)),
+            }
+        }
+    }
+
+    fn step(&mut self, input: &[u8]) -> Result<Step, ParsingError> {
+        match self.state {
+            State::Header => {
+                if input.len() < HEADER_SIZE {
+                    return Ok(Step::Need(HEADER_SIZE - input.len()));
+                }
+                if !check_gif(input) {
+                    return Err("not a GIF file".into());
+                }
+                self.info.width = u16::from_le_bytes([input[6], input[7]]);
+                self.info.height = u16::from_le_bytes([input[8], input[9]]);
+                self.state = State::Blocks;
+                Ok(Step::Consumed(HEADER_SIZE + color_table_size(input[10])))
+            }
+            State::Blocks => self.parse_block(input),
+            State::SubBlocks => {
+                let Some(size) = input.first() else {
+                    return Ok(Step::Need(1));
+                };
+                if *size == 0 {
+                    self.state = State::Blocks;
+                    return Ok(Step::Consumed(1));
+                }
+                let end = 1 + *size as usize;
+                if end > input.len() {
+                    Ok(Step::Skip(end))
+                } else {
+                    Ok(Step::Consumed(end))
+                }
+            }
+        }
+    }
+
+    fn parse_block(&mut self, input: &[u8]) -> Result<Step, ParsingError> {
+        let Some(introducer) = input.first() else {
+            return Ok(Step::Need(1));
+        };
+        match *introducer {
+            TRAILER => Ok(Step::Done),
+            IMAGE_DESCRIPTOR => {
+                // Image descriptor (10 bytes), local color table, and LZW
+                // minimum code size (1 byte)
+                let Some(flags) = input.get(9) else {
+                    return Ok(Step::Need(10 - input.len()));
+                };
+                self.info.frames += 1;
+                self.state = State::SubBlocks;
+                Ok(Step::Consumed(10 + color_table_size(*flags) + 1))
+            }
+            EXTENSION => {
+                let Some(label) = input.get(1) else {
+                    return Ok(Step::Need(1));
+                };
+                match *label {
+                    GRAPHIC_CONTROL_LABEL => {
+                        // size (1 byte, always 4), packed fields (1 byte),
+                        // delay time (2 bytes), ...
+                        let Some(delay) = input.get(4..6) else {
+                            return Ok(Step::Need(6 - input.len()));
+                        };
+                        self.info.delay += u16::from_le_bytes([delay[0], delay[1]]) as u64;
+                    }
+                    APPLICATION_LABEL => {
+                        let Some(id) = input.get(2..3 + XMP_APPLICATION_ID.len()) else {
+                            return Ok(Step::Need(3 + XMP_APPLICATION_ID.len() - input.len()));
+                        };
+                        if id[0] as usize == XMP_APPLICATION_ID.len()
+                            && &id[1..] == XMP_APPLICATION_ID
+                        {
+                            return self.parse_xmp(input);
+                        }
+                    }
+                    _ => (),
+                }
+                self.state = State::SubBlocks;
+                Ok(Step::Consumed(2))
+            }
+            x => Err(ParsingError::Failed(format!(
+                "invalid GIF block: 0x{x:02x}"
+            ))),
+        }
+    }
+
+    /// Parses the XMP application extension, the XMP data is stored as raw
+    /// bytes followed by a "magic trailer", rather than split into data
+    /// sub-blocks.
+    fn parse_xmp(&mut self, input: &[u8]) -> Result<Step, ParsingError> {
+        let start = 3 + XMP_APPLICATION_ID.len();
+        let mut end = start;
+        loop {
+            let Some(size) = input.get(end) else {
+                if end > MAX_XMP_SIZE {
+                    return Err("XMP data is too big".into());
+                }
+                return Ok(Step::Need(1));
+            };
+            if *size == 0 {
+                break;
+            }
+            end += 1 + *size as usize;
+        }
+
+        let data = &input[start..end];
+        let data = if data.len() >= XMP_MAGIC_TRAILER_SIZE
+            && data[data.len() - XMP_MAGIC_TRAILER_SIZE] == 1
+        {
+            &data[..data.len() - XMP_MAGIC_TRAILER_SIZE]
+        } else {
+            data
+        };
+        match std::str::from_utf8(data) {
+            Ok(xmp) => self.info.xmp = Some(xmp.to_owned()),
+            Err(e) => tracing::warn!(?e, "invalid XMP data"),
+        }
+        Ok(Step::Consumed(end + 1))
+    }
+}
+
+enum Step {
+    Consumed(usize),
+    Need(usize),
+    /// Skip `n` bytes, which extend beyond the input.
+    Skip(usize),
+    Done,
+}
+
+fn color_table_size(flags: u8) -> usize {
+    if flags & 0x80 == 0 {
+        0
+    } else {
+        3 * (1 << ((flags & 0x07) + 1))
+    }
+}
+
+impl From<GifInfo> for TrackInfo {
+    fn from(value: GifInfo) -> Self {
+        let mut info = TrackInfo::default();
+        info.put(TrackInfoTag::ImageWidth, (value.width as u32).into());
+        info.put(TrackInfoTag::ImageHeight, (value.height as u32).into());
+        if value.delay > 0 {
+            info.put(TrackInfoTag::DurationMs, (value.delay * 10).into());
+        }
+
+        let Some(xmp) = value.xmp else {
+            return info;
+        };
+        let date = [
+            "xmp:CreateDate",
+            "photoshop:DateCreated",
+            "exif:DateTimeOriginal",
+        ]
+        .iter()
+        .find_map(|x| xmp_property(&xmp, x).and_then(parse_xmp_date));
+        if let Some(date) = date {
+            info.put(TrackInfoTag::CreateDate, date.into());
+        }
+        for (tag, name) in [
+            (TrackInfoTag::Make, "tiff:Make"),
+            (TrackInfoTag::Model, "tiff:Model"),
+            (TrackInfoTag::Software, "xmp:CreatorTool"),
+        ] {
+            if let Some(v) = xmp_property(&xmp, name).filter(|x| !x.is_empty()) {
+                info.put(tag, v.into());
+            }
+        }
+        info
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{MediaParser, MediaSource};
+
+    /// Makes an animated GIF of `frames` frames, each of which has a 0.1
+    /// second delay and `data_size` bytes of image data.
+    pub(crate) fn make_gif(xmp: Option<&str>, frames: usize, data_size: usize) -> Vec<u8> {
+        let mut data = b"GIF89a".to_vec();
+        // 320x240, global color table with 2 entries
+        data.extend_from_slice(&[0x40, 0x01, 0xF0, 0x00, 0x80, 0, 0]);
+        data.extend_from_slice(&[0; 6]);
+
+        if let Some(xmp) = xmp {
+            data.extend_from_slice(&[EXTENSION, APPLICATION_LABEL, 11]);
+            data.extend_from_slice(XMP_APPLICATION_ID);
+            data.extend_from_slice(xmp.as_bytes());
+            data.push(1);
+            data.extend((0..=255_u8).rev());
+            data.push(0);
+        }
+
+        for _ in 0..frames {
+            data.extend_from_slice(&[EXTENSION, GRAPHIC_CONTROL_LABEL, 4, 0, 10, 0, 0, 0]);
+            data.extend_from_slice(&[IMAGE_DESCRIPTOR, 0, 0, 0, 0, 0x40, 0x01, 0xF0, 0x00, 0]);
+            // LZW minimum code size
+            data.push(2);
+            for chunk in vec![0x55_u8; data_size].chunks(255) {
+                data.push(chunk.len() as u8);
+                data.extend_from_slice(chunk);
+            }
+            data.push(0);
+        }
+        // A comment extension
+        data.extend_from_slice(&[EXTENSION, 0xFE, 2, b'h', b'i', 0]);
+        data.push(TRAILER);
+        data
+    }
+
+    const XMP: &str = r#"<?xpacket begin="" id="W5M0MpCehiHzreSzNTczkc9d"?>
+<x:xmpmeta xmlns:x="adobe:ns:meta/">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about=""
+    xmlns:xmp="http://ns.adobe.com/xap/1.0/"
+    xmp:CreatorTool="Adobe Photoshop 25.0 (Macintosh)"
+    xmp:CreateDate="2024-02-02T16:09:57+08:00"/>
+ </rdf:RDF>
+</x:xmpmeta>
+<?xpacket end="w"?>"#;
+
+    fn parse(data: Vec<u8>) -> TrackInfo {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let mut parser = MediaParser::new();
+        let ms = MediaSource::unseekable(Cursor::new(data)).unwrap();
+        assert!(ms.has_track());
+        parser.parse(ms).unwrap()
+    }
+
+    #[test]
+    fn gif_xmp() {
+        let info = parse(make_gif(Some(XMP), 3, 100));
+        assert_eq!(info.get(TrackInfoTag::ImageWidth), Some(&320_u32.into()));
+        assert_eq!(info.get(TrackInfoTag::ImageHeight), Some(&240_u32.into()));
+        assert_eq!(info.get(TrackInfoTag::DurationMs), Some(&300_u64.into()));
+        assert_eq!(
+            info.get(TrackInfoTag::Software),
+            Some(&"Adobe Photoshop 25.0 (Macintosh)".into())
+        );
+        assert_eq!(
+            info.get(TrackInfoTag::CreateDate).unwrap().to_string(),
+            "2024-02-02T16:09:57+08:00"
+        );
+    }
+
+    #[test]
+    fn gif_without_xmp() {
+        let info = parse(make_gif(None, 1, 10));
+        assert_eq!(info.get(TrackInfoTag::ImageWidth), Some(&320_u32.into()));
+        assert_eq!(info.get(TrackInfoTag::DurationMs), Some(&100_u64.into()));
+        assert!(info.get(TrackInfoTag::CreateDate).is_none());
+    }
+
+    /// Image data which extends beyond the parsing buffer is skipped, so the
+    /// parsing state must be kept across calls.
+    #[test]
+    fn gif_large() {
+        let info = parse(make_gif(Some(XMP), 20, 1024 * 1024));
+        assert_eq!(info.get(TrackInfoTag::DurationMs), Some(&2000_u64.into()));
+        assert!(info.get(TrackInfoTag::CreateDate).is_some());
+    }
+}
@@ -3,13 +3,17 @@ use std::io::{Read, Seek};
 use nom::combinator::fail;
 use nom::{number::complete::be_u32, IResult};
 
-use crate::bbox::find_box;
+use crate::bbox::{find_box, find_track, parse_sample_count, BoxHeader};
 use crate::exif::Exif;
 use crate::{
     bbox::{BoxHolder, MetaBox, ParseBox},
     exif::check_exif_header,
 };
-use crate::{ExifIter, MediaParser, MediaSource};
+use crate::{strip, Error, ExifIter, MediaParser, MediaSource};
+
+/// Brands of image sequences, whose frames are stored as samples of a `pict`
+/// track (in the `moov` box) instead of items.
+const SEQUENCE_BRANDS: &[&[u8]] = &[b"msf1", b"hevc", b"hevm", b"hevs", b"avis"];
 
 /// *Deprecated*: Please use [`MediaParser`] + [`MediaSource`] instead.
 ///
@@ -42,6 +46,124 @@ pub fn parse_heif_exif<R: Read + Seek>(reader: R) -> crate::Result<Option<Exif>>
     Ok(Some(iter.into()))
 }
 
+/// Replaces the data of item `item_id` of the HEIF file `data` with
+/// `payload`, which is stored in a new `mdat` box appended to the end of the
+/// file, so that the offsets of other items stay valid. The original item
+/// data is zeroed.
+///
+/// Only items stored in the file (construction method 0) in a single extent
+/// are supported.
+pub(crate) fn replace_item_data(
+    data: &mut Vec<u8>,
+    item_id: u32,
+    payload: &[u8],
+) -> crate::Result<()> {
+    let unsupported = |reason: &str| {
+        Error::WriteFailed(
+            format!("can't replace item {item_id} of the HEIF file; {reason}").into(),
+        )
+    };
+    let boxes = strip::parse_boxes(data, 0..data.len())?;
+    // The new box can't be appended after a box which extends to the end
+    if boxes
+        .last()
+        .is_some_and(|x| data.get(x.range.start..x.range.start + 4) == Some(&[0; 4]))
+    {
+        return Err(unsupported("the last box has no size"));
+    }
+    let meta = boxes
+        .into_iter()
+        .find(|x| &x.box_type == b"meta")
+        .ok_or("meta box not found")?;
+    let (_, meta_box) = MetaBox::parse_box(&data[meta.range.clone()])?; // Safe-slice
+    let old = meta_box.item_data_offset(item_id);
+    // Full box, skip version & flags
+    let iloc = strip::find_box(data, meta.body().start + 4..meta.range.end, b"iloc")?
+        .ok_or("iloc box not found")?;
+
+    // Locate the extent of the item in `iloc`
+    let body = iloc.body();
+    let read = |pos: usize, size: usize| -> crate::Result<u64> {
+        let bytes = data
+            .get(pos..pos + size)
+            .ok_or("invalid iloc box; truncated")?;
+        Ok(bytes.iter().fold(0, |acc, x| acc << 8 | *x as u64))
+    };
+    let version = read(body.start, 1)? as u8;
+    let sizes = read(body.start + 4, 2)?;
+    let (offset_size, length_size) = ((sizes >> 12) as usize, (sizes >> 8 & 0xf) as usize);
+    let base_offset_size = (sizes >> 4 & 0xf) as usize;
+    let index_size = if version > 0 {
+        (sizes & 0xf) as usize
+    } else {
+        0
+    };
+    let id_size = if version < 2 { 2 } else { 4 };
+    let mut pos = body.start + 6;
+    let count = read(pos, id_size)?;
+    pos += id_size;
+
+    let mut extent = None;
+    for _ in 0..count {
+        let id = read(pos, id_size)?;
+        pos += id_size;
+        let construction_method = if version > 0 {
+            pos += 2;
+            read(pos - 2, 2)? & 0xf
+        } else {
+            0
+        };
+        // data_reference_index
+        pos += 2;
+        let base_offset = read(pos, base_offset_size)?;
+        pos += base_offset_size;
+        let extent_count = read(pos, 2)?;
+        pos += 2;
+        if id == item_id as u64 {
+            if construction_method != 0 {
+                return Err(unsupported("it's not stored in the file"));
+            }
+            if extent_count != 1 {
+                return Err(unsupported("it's stored in multiple extents"));
+            }
+            extent = Some((pos + index_size, base_offset));
+            break;
+        }
+        pos += extent_count as usize * (index_size + offset_size + length_size);
+    }
+    let Some((pos, base_offset)) = extent else {
+        return Err(unsupported("item location not found"));
+    };
+    if !matches!(offset_size, 4 | 8) || !matches!(length_size, 4 | 8) {
+        return Err(unsupported("unsupported offset/length size"));
+    }
+
+    if let Some(x) = old.and_then(|x| data.get_mut(x)) {
+        x.fill(0);
+    }
+    let start = (data.len() + 8) as u64;
+    let offset = start
+        .checked_sub(base_offset)
+        .ok_or_else(|| unsupported("invalid base offset"))?;
+    let length = payload.len() as u64;
+    for (value, size, pos) in [
+        (offset, offset_size, pos),
+        (length, length_size, pos + offset_size),
+    ] {
+        if size == 4 && value > u32::MAX as u64 {
+            return Err(unsupported("the file is too large"));
+        }
+        let bytes = value.to_be_bytes();
+        data[pos..pos + size].copy_from_slice(&bytes[8 - size..]); // Safe-slice
+    }
+
+    let size = u32::try_from(payload.len() + 8).map_err(|_| unsupported("item is too large"))?;
+    data.extend_from_slice(&size.to_be_bytes());
+    data.extend_from_slice(b"mdat");
+    data.extend_from_slice(payload);
+    Ok(())
+}
+
 /// Extract Exif TIFF data from the bytes of a HEIF/HEIC file.
 #[allow(unused)]
 #[tracing::instrument(skip_all)]
@@ -76,6 +198,58 @@ pub(crate) fn parse_meta_box(input: &[u8]) -> IResult<&[u8], Option<MetaBox>> {
     Ok((remain, Some(bbox)))
 }
 
+/// Returns the frame count of an image sequence (e.g. Apple burst photos),
+/// which is the sample count of the `pict` track, or `None` for still images.
+///
+/// Only the top level boxes before `mdat` are searched, so that the media
+/// data won't be loaded.
+pub(crate) fn parse_sequence_frames(input: &[u8]) -> IResult<&[u8], Option<u32>> {
+    let (mut remain, ftyp) = BoxHolder::parse(input)?;
+    if ftyp.box_type() != "ftyp" {
+        return fail(input);
+    }
+    if !is_sequence(ftyp.body_data()) {
+        return Ok((remain, None));
+    }
+
+    loop {
+        let (_, header) = BoxHeader::parse(remain)?;
+        if header.box_type == "mdat" {
+            tracing::debug!("moov box not found before mdat");
+            return Ok((remain, None));
+        }
+        let (rem, bbox) = BoxHolder::parse(remain)?;
+        remain = rem;
+        if bbox.box_type() != "moov" {
+            continue;
+        }
+
+        let frames = match find_track(bbox.body_data(), b"pict") {
+            Ok(Some(trak)) => parse_sample_count(trak.body_data()).unwrap_or_else(|e| {
+                tracing::warn!(?e, "Ignored: parse sample count failed");
+                None
+            }),
+            Ok(None) => None,
+            Err(e) => {
+                tracing::warn!(?e, "Ignored: find pict track failed");
+                None
+            }
+        };
+        tracing::debug!(?frames, "image sequence");
+        return Ok((remain, frames));
+    }
+}
+
+/// Checks the major brand & compatible brands in the `ftyp` body.
+fn is_sequence(ftyp_body: &[u8]) -> bool {
+    let major_brand = ftyp_body.get(..4).into_iter();
+    // skip minor version
+    let compatible_brands = ftyp_body.get(8..).unwrap_or_default().chunks_exact(4);
+    major_brand
+        .chain(compatible_brands)
+        .any(|x| SEQUENCE_BRANDS.contains(&x))
+}
+
 pub(crate) fn extract_exif_with_meta<'a>(
     input: &'a [u8],
     bbox: &MetaBox,
@@ -128,6 +302,65 @@ mod tests {
         parse_heif_exif(reader).expect_err("should be ParseFailed error");
     }
 
+    /// Makes a HEIF image sequence, the Exif item of the primary image is in
+    /// `mdat`, and the `pict` track has `frames` samples.
+    fn make_heics(brands: &[u8], exif: &[u8], frames: u32) -> Vec<u8> {
+        let ftyp = bbox(b"ftyp", &[brands, &[0; 4], brands].concat());
+
+        let hdlr = full_box(b"hdlr", &[&[0; 4][..], b"pict", &[0; 13]].concat());
+        let infe = bbox(
+            b"infe",
+            &[&[2, 0, 0, 0][..], &[0, 1, 0, 0], b"Exif\0"].concat(),
+        );
+        let iinf = full_box(b"iinf", &[&[0, 1][..], &infe].concat());
+        let iloc = |offset: u32| {
+            let item = [
+                &[0x44, 0, 0, 1, 0, 1, 0, 0, 0, 1][..],
+                &offset.to_be_bytes(),
+            ]
+            .concat();
+            full_box(
+                b"iloc",
+                &[&item[..], &(exif.len() as u32).to_be_bytes()].concat(),
+            )
+        };
+
+        let stsz = full_box(
+            b"stsz",
+            &[1000_u32.to_be_bytes(), frames.to_be_bytes()].concat(),
+        );
+        let stbl = bbox(b"stbl", &stsz);
+        let mdia = bbox(b"mdia", &[&hdlr[..], &bbox(b"minf", &stbl)].concat());
+        let moov = bbox(b"moov", &bbox(b"trak", &mdia));
+
+        let meta_len = full_box(b"meta", &[&hdlr[..], &iinf, &iloc(0)].concat()).len();
+        let offset = ftyp.len() + meta_len + moov.len() + 8;
+        let meta = full_box(b"meta", &[&hdlr[..], &iinf, &iloc(offset as u32)].concat());
+        [ftyp, meta, moov, bbox(b"mdat", exif)].concat()
+    }
+
+    #[test_case(b"msf1", Some(12))]
+    #[test_case(b"hevc", Some(12))]
+    #[test_case(b"heic", None)]
+    fn heics(brands: &[u8], frames: Option<u32>) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        // Exif item of exif.heic
+        let exif = read_sample("exif.heic").unwrap()[0xe2e..0xe2e + 2618].to_vec();
+        let data = make_heics(brands, &exif, 12);
+
+        let (_, n) = parse_sequence_frames(&data).unwrap();
+        assert_eq!(n, frames);
+
+        let mut parser = MediaParser::new();
+        let ms = MediaSource::seekable(std::io::Cursor::new(data)).unwrap();
+        let iter: ExifIter = parser.parse(ms).unwrap();
+        assert_eq!(iter.sequence_frame_count(), frames);
+        let exif: Exif = iter.into();
+        assert_eq!(exif.sequence_frame_count(), frames);
+        assert_eq!(exif.get(crate::ExifTag::Make).unwrap().to_string(), "Apple");
+    }
+
     #[test_case("exif-one-entry.heic", 0x24-10)]
     #[test_case("exif.heic", 0xa3a-10)]
     fn heic_exif_data(path: &str, exif_size: usize) {
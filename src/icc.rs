@@ -0,0 +1,312 @@
+use nom::bytes::streaming;
+
+use crate::{
+    error::{nom_error_to_parsing_error_with_state, ParsingErrorState},
+    exif::tiff_ifd0_data,
+    file::{Mime, MimeImage},
+    heif, jpeg,
+    parser::ParsingState,
+};
+
+/// TIFF tag `ICC_Profile` (`InterColorProfile`).
+const TIFF_ICC_TAG: u16 = 0x8773;
+
+const ICC_HEADER_SIZE: usize = 128;
+const ICC_SIGNATURE: &[u8] = b"acsp";
+
+/// An embedded ICC color profile.
+///
+/// `IccProfile` can be parsed by a [`MediaParser`](crate::MediaParser) from
+/// JPEG (APP2 `ICC_PROFILE` segments), TIFF (tag 34675) and HEIF/HEIC/AVIF
+/// (the `colr` property of the primary item) files.
+///
+/// ```rust
+/// use nom_exif::*;
+///
+/// let mut parser = MediaParser::new();
+/// let ms = MediaSource::file_path("./testdata/exif.jpg").unwrap();
+/// let icc: IccProfile = parser.parse(ms).unwrap();
+///
+/// assert_eq!(icc.color_space(), "RGB");
+/// assert_eq!(icc.description().as_deref(), Some("Display P3"));
+/// assert!(!icc.data().is_empty());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IccProfile {
+    data: Vec<u8>,
+}
+
+impl IccProfile {
+    /// Checks the profile header, `data` should be a complete profile.
+    pub(crate) fn new(data: Vec<u8>) -> crate::Result<IccProfile> {
+        let valid = data.len() >= ICC_HEADER_SIZE
+            && &data[36..40] == ICC_SIGNATURE
+            && data[12..20].is_ascii();
+        if !valid {
+            return Err("invalid ICC profile".into());
+        }
+        Ok(IccProfile { data })
+    }
+
+    /// Returns the raw profile bytes, e.g. for embedding in another file.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn into_data(self) -> Vec<u8> {
+        self.data
+    }
+
+    /// Color space of the data, e.g. "RGB", "GRAY", "CMYK".
+    pub fn color_space(&self) -> &str {
+        self.header_str(16)
+    }
+
+    /// Profile/device class, e.g. "mntr" (display), "prtr" (output), "spac"
+    /// (color space).
+    pub fn profile_class(&self) -> &str {
+        self.header_str(12)
+    }
+
+    /// Profile version, (major, minor), e.g. (4, 3).
+    pub fn version(&self) -> (u8, u8) {
+        (self.data[8], self.data[9] >> 4)
+    }
+
+    /// Returns the profile description (the `desc` tag), e.g. "sRGB
+    /// IEC61966-2.1" or "Display P3". For multilingual descriptions, English
+    /// is preferred.
+    pub fn description(&self) -> Option<String> {
+        let tag = self.find_tag(b"desc")?;
+        let (sig, body) = tag.split_at_checked(8)?;
+        match &sig[..4] {
+            b"desc" => {
+                // textDescriptionType: ASCII count & the ASCII description
+                let (count, text) = body.split_at_checked(4)?;
+                let count = u32::from_be_bytes(count.try_into().unwrap()) as usize;
+                let text = text.get(..count)?;
+                let text = text.split(|x| *x == 0).next().unwrap_or(text);
+                Some(String::from_utf8_lossy(text).into_owned())
+            }
+            b"mluc" => parse_mluc(tag),
+            _ => None,
+        }
+    }
+
+    fn header_str(&self, offset: usize) -> &str {
+        // Checked in `new`
+        let s = std::str::from_utf8(&self.data[offset..offset + 4]).unwrap_or_default();
+        s.trim_end()
+    }
+
+    /// Returns the data of a tag in the tag table.
+    fn find_tag(&self, signature: &[u8]) -> Option<&[u8]> {
+        let table = &self.data[ICC_HEADER_SIZE..];
+        let (count, entries) = table.split_at_checked(4)?;
+        let count = u32::from_be_bytes(count.try_into().unwrap()) as usize;
+        entries
+            .chunks_exact(12)
+            .take(count)
+            .find(|x| &x[..4] == signature)
+            .and_then(|x| {
+                let offset = u32::from_be_bytes(x[4..8].try_into().unwrap()) as usize;
+                let size = u32::from_be_bytes(x[8..12].try_into().unwrap()) as usize;
+                self.data.get(offset..offset.checked_add(size)?)
+            })
+    }
+}
+
+/// Parses a `multiLocalizedUnicodeType` tag, returns the English (or the
+/// first) string.
+fn parse_mluc(tag: &[u8]) -> Option<String> {
+    let be_u32 = |pos: usize| -> Option<usize> {
+        let bytes = tag.get(pos..pos + 4)?;
+        Some(u32::from_be_bytes(bytes.try_into().unwrap()) as usize)
+    };
+    let count = be_u32(8)?;
+    let record_size = be_u32(12)?;
+    if record_size < 12 {
+        return None;
+    }
+
+    // (language, length, offset)
+    let records = (0..count)
+        .map_while(|i| {
+            let pos = 16 + i * record_size;
+            let lang = tag.get(pos..pos + 2)?;
+            Some((lang, be_u32(pos + 4)?, be_u32(pos + 8)?))
+        })
+        .collect::<Vec<_>>();
+    let (_, len, offset) = records.iter().find(|x| x.0 == b"en").or(records.first())?;
+
+    let text = tag.get(*offset..offset.checked_add(*len)?)?;
+    let utf16 = text
+        .chunks_exact(2)
+        .map(|x| u16::from_be_bytes([x[0], x[1]]))
+        .take_while(|x| *x != 0)
+        .collect::<Vec<_>>();
+    Some(String::from_utf16_lossy(&utf16))
+}
+
+/// Extracts the ICC profile of an image, see [`IccProfile`] for the
+/// supported formats. Returns `None` if there is none.
+pub(crate) fn extract_icc_profile(
+    mime: Mime,
+    input: &[u8],
+    state: Option<ParsingState>,
+) -> Result<Option<Vec<u8>>, ParsingErrorState> {
+    let map_err = |e| nom_error_to_parsing_error_with_state(e, state.clone());
+    let data = match (mime, &state) {
+        (_, Some(ParsingState::DataSize(size))) => {
+            let (_, data) = streaming::take(*size)(input).map_err(map_err)?;
+            Some(data)
+        }
+        (Mime::Image(MimeImage::Jpeg), _) => {
+            let (_, segments) = jpeg::collect_app1_segments(input).map_err(map_err)?;
+            return Ok(segments.icc_profile());
+        }
+        (Mime::Image(MimeImage::Heic | MimeImage::Heif | MimeImage::Avif), _) => {
+            let (_, meta) = heif::parse_meta_box(input).map_err(map_err)?;
+            return Ok(meta.and_then(|x| x.primary_icc_profile().map(|x| x.to_vec())));
+        }
+        (Mime::Image(MimeImage::Tiff | MimeImage::Pef | MimeImage::Srw), _) => {
+            tiff_ifd0_data(input, TIFF_ICC_TAG)?
+        }
+        _ => None,
+    };
+    Ok(data.map(|x| x.to_vec()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testkit::read_sample, MediaParser, MediaSource};
+    use test_case::test_case;
+
+    /// Builds a profile with a `desc` tag.
+    fn make_icc(version: u8, desc: &[u8]) -> Vec<u8> {
+        let mut data = vec![0; ICC_HEADER_SIZE];
+        data[8] = version;
+        data[12..16].copy_from_slice(b"mntr");
+        data[16..20].copy_from_slice(b"GRAY");
+        data[36..40].copy_from_slice(ICC_SIGNATURE);
+        data.extend(1_u32.to_be_bytes());
+        data.extend(b"desc");
+        data.extend(((ICC_HEADER_SIZE + 16) as u32).to_be_bytes());
+        data.extend((desc.len() as u32).to_be_bytes());
+        data.extend(desc);
+        let size = data.len() as u32;
+        data[..4].copy_from_slice(&size.to_be_bytes());
+        data
+    }
+
+    fn text_desc(s: &str) -> Vec<u8> {
+        let mut data = b"desc\0\0\0\0".to_vec();
+        data.extend((s.len() as u32 + 1).to_be_bytes());
+        data.extend(s.as_bytes());
+        data.push(0);
+        data
+    }
+
+    fn mluc_desc(records: &[(&[u8; 4], &str)]) -> Vec<u8> {
+        let mut data = b"mluc\0\0\0\0".to_vec();
+        data.extend((records.len() as u32).to_be_bytes());
+        data.extend(12_u32.to_be_bytes());
+        let mut strings = Vec::new();
+        let base = 16 + records.len() * 12;
+        for (lang, s) in records {
+            let utf16 = s.encode_utf16().flat_map(|x| x.to_be_bytes());
+            let utf16 = utf16.collect::<Vec<_>>();
+            data.extend(*lang);
+            data.extend((utf16.len() as u32).to_be_bytes());
+            data.extend(((base + strings.len()) as u32).to_be_bytes());
+            strings.extend(utf16);
+        }
+        data.extend(strings);
+        data
+    }
+
+    #[test_case(2, text_desc("Gray Gamma 2.2"), Some("Gray Gamma 2.2"))]
+    #[test_case(4, mluc_desc(&[(b"deDE", "Grau"), (b"enUS", "Gray")]), Some("Gray"))]
+    #[test_case(4, mluc_desc(&[(b"deDE", "Grau")]), Some("Grau"); "mluc first")]
+    #[test_case(4, b"XYZ \0\0\0\0".to_vec(), None; "unknown type")]
+    fn profile(version: u8, desc: Vec<u8>, expect: Option<&str>) {
+        let icc = IccProfile::new(make_icc(version, &desc)).unwrap();
+        assert_eq!(icc.version(), (version, 0));
+        assert_eq!(icc.color_space(), "GRAY");
+        assert_eq!(icc.profile_class(), "mntr");
+        assert_eq!(icc.description().as_deref(), expect);
+    }
+
+    #[test]
+    fn invalid() {
+        IccProfile::new(vec![0; 200]).unwrap_err();
+    }
+
+    fn jpeg_with_icc(icc: &[u8], pieces: &[u8]) -> Vec<u8> {
+        let mut data = vec![0xff, 0xd8];
+        let size = icc.len().div_ceil(pieces.len());
+        let chunks = icc.chunks(size).collect::<Vec<_>>();
+        for seq in pieces {
+            let payload = [
+                b"ICC_PROFILE\0",
+                &[*seq, pieces.len() as u8][..],
+                chunks[*seq as usize - 1],
+            ]
+            .concat();
+            data.extend([0xff, 0xe2]);
+            data.extend(((payload.len() + 2) as u16).to_be_bytes());
+            data.extend(payload);
+        }
+        data.extend([0xff, 0xda, 0, 2, 0xff, 0xd9]);
+        data
+    }
+
+    fn parse(data: Vec<u8>) -> crate::Result<IccProfile> {
+        let ms = MediaSource::seekable(std::io::Cursor::new(data))?;
+        MediaParser::new().parse(ms)
+    }
+
+    #[test_case(&[1]; "one segment")]
+    #[test_case(&[2, 1, 3]; "out of order")]
+    fn jpeg(pieces: &[u8]) {
+        let icc = make_icc(2, &text_desc("Gray Gamma 2.2"));
+        let parsed = parse(jpeg_with_icc(&icc, pieces)).unwrap();
+        assert_eq!(parsed.data(), icc);
+    }
+
+    #[test]
+    fn jpeg_incomplete() {
+        // The second segment of two is missing
+        let icc = make_icc(2, &text_desc("Gray Gamma 2.2"));
+        let mut data = jpeg_with_icc(&icc, &[1, 2]);
+        let second = data
+            .windows(12)
+            .rposition(|x| x == b"ICC_PROFILE\0")
+            .unwrap();
+        data[second + 12] = 1;
+        parse(data).unwrap_err();
+    }
+
+    #[test]
+    fn tiff() {
+        let icc = make_icc(4, &mluc_desc(&[(b"enUS", "Gray")]));
+        let mut data = b"II*\0\x08\0\0\0".to_vec();
+        data.extend(1_u16.to_le_bytes());
+        data.extend(TIFF_ICC_TAG.to_le_bytes());
+        data.extend(7_u16.to_le_bytes()); // UNDEFINED
+        data.extend((icc.len() as u32).to_le_bytes());
+        data.extend(26_u32.to_le_bytes());
+        data.extend(0_u32.to_le_bytes()); // next IFD
+        data.extend(&icc);
+        assert_eq!(parse(data).unwrap().data(), icc);
+    }
+
+    #[test_case("exif.heic", "RGB")]
+    #[test_case("no-exif.jpg", "RGB")]
+    fn sample(path: &str, color_space: &str) {
+        let icc = parse(read_sample(path).unwrap()).unwrap();
+        assert_eq!(icc.color_space(), color_space);
+        assert!(icc.description().is_some());
+    }
+}
@@ -0,0 +1,892 @@
+use std::borrow::Cow;
+
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveTime};
+
+use crate::{
+    error::ParsingError,
+    video::{TrackInfo, TrackInfoTag},
+};
+
+const TAG_HEADER_SIZE: usize = 10;
+const FOOTER_SIZE: usize = 10;
+
+// Tag header flags
+const FLAG_UNSYNCHRONISATION: u8 = 0x80;
+const FLAG_EXTENDED_HEADER: u8 = 0x40;
+const FLAG_FOOTER: u8 = 0x10;
+
+/// Max size of a text frame which is loaded into memory.
+const MAX_TEXT_FRAME_SIZE: usize = 64 * 1024;
+
+/// Only the beginning of a picture frame is loaded, which is enough for
+/// getting the MIME type of the picture.
+const PICTURE_PROBE_SIZE: usize = 64;
+
+/// Max size of a tag which is unsynchronised as a whole (ID3v2.2/2.3), such
+/// tags have to be loaded into memory.
+const MAX_UNSYNC_TAG_SIZE: usize = 16 * 1024 * 1024;
+
+/// Max number of bytes to search for the first MPEG audio frame.
+const MAX_SYNC_SEARCH: usize = 64 * 1024;
+
+/// Bytes needed from the start of an MPEG audio frame to parse the Xing (or
+/// VBRI) header in it.
+const AUDIO_PROBE_SIZE: usize = 4 + 32 + 2 + 16;
+
+/// Checks for an ID3v2 tag, or an MPEG audio layer III frame for MP3 files
+/// without ID3v2 tags.
+pub(crate) fn check_mp3(input: &[u8]) -> bool {
+    if input.starts_with(b"ID3") {
+        return input.len() >= TAG_HEADER_SIZE && (2..=4).contains(&input[3]);
+    }
+    MpegFrame::parse(input).is_some_and(|x| x.layer == 3)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum State {
+    #[default]
+    Header,
+    ExtendedHeader(Tag),
+    Frames(Tag),
+    /// The whole tag is unsynchronised, so it's loaded and parsed at once.
+    UnsyncTag(Tag),
+    /// Searching for the first MPEG audio frame, which may contain a Xing
+    /// or VBRI header.
+    Audio {
+        searched: usize,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Tag {
+    version: u8,
+    /// Size of the remaining part of the tag (excluding the footer).
+    remain: usize,
+    footer: usize,
+}
+
+/// Parses MP3 files: the ID3v2 tag is parsed frame by frame, large frames
+/// (e.g. pictures) are skipped with `ClearAndSkip`, then the first MPEG audio
+/// frame is parsed for estimating the duration.
+///
+/// ID3v1 tags and ID3v2 tags appended at the end of files are not parsed.
+///
+/// Refer to: [ID3v2.3](https://id3.org/id3v2.3.0),
+/// [ID3v2.4](https://id3.org/id3v2.4.0-structure)
+#[derive(Debug, Default)]
+pub(crate) struct Mp3Parser {
+    state: State,
+    info: Mp3Info,
+    /// Stops at the end of the ID3v2 tag, for tags embedded in other formats
+    /// (e.g. DSF), which are not followed by MPEG audio frames.
+    tag_only: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Mp3Info {
+    title: Option<String>,
+    artist: Option<String>,
+    album: Option<String>,
+    software: Option<String>,
+
+    /// `TDRC` (ID3v2.4)
+    recording_time: Option<String>,
+    /// `TYER` (yyyy), `TDAT` (DDMM) & `TIME` (HHMM) (ID3v2.2/2.3)
+    year: Option<String>,
+    date: Option<String>,
+    time: Option<String>,
+
+    /// `TLEN`, in milliseconds
+    length: Option<u64>,
+    /// MIME type of the first picture
+    picture: Option<String>,
+
+    /// Estimated from the Xing/VBRI header of the first audio frame
+    duration_ms: Option<u64>,
+}
+
+enum Step {
+    Consumed(usize),
+    Need(usize),
+    /// Skip `n` bytes, which extend beyond the input.
+    Skip(usize),
+    Done,
+}
+
+impl Step {
+    fn advance(n: usize, input: &[u8]) -> Step {
+        if n > input.len() {
+            Step::Skip(n)
+        } else {
+            Step::Consumed(n)
+        }
+    }
+}
+
+impl Mp3Parser {
+    /// Creates a parser which parses only the ID3v2 tag at the start of the
+    /// input.
+    pub(crate) fn tag_only() -> Mp3Parser {
+        Mp3Parser {
+            tag_only: true,
+            ..Default::default()
+        }
+    }
+
+    /// Parses `input`, which starts where the last call stopped.
+    pub(crate) fn parse(&mut self, input: &[u8]) -> Result<TrackInfo, ParsingError> {
+        let mut pos = 0;
+        loop {
+            match self.step(&input[pos..])? {
+                Step::Consumed(n) => pos += n,
+                Step::Done => return Ok(std::mem::take(&mut self.info).into()),
+                Step::Need(n) if pos > 0 => {
+                    tracing::debug!(n, "need more bytes");
+                    return Err(ParsingError::ClearAndSkip(pos));
+                }
+                Step::Need(n) => return Err(ParsingError::Need(n)),
+                Step::Skip(n) => return Err(ParsingError::ClearAndSkip(pos + n)),
+            }
+        }
+    }
+
+    fn step(&mut self, input: &[u8]) -> Result<Step, ParsingError> {
+        match self.state {
+            State::Header => self.parse_header(input),
+            State::ExtendedHeader(mut tag) => {
+                if input.len() < 4 {
+                    return Ok(Step::Need(4 - input.len()));
+                }
+                let size = if tag.version == 4 {
+                    // Includes the size itself
+                    synchsafe(&input[..4])
+                } else {
+                    4 + u32::from_be_bytes(input[..4].try_into().unwrap()) as usize
+                };
+                let size = size.min(tag.remain);
+                tag.remain -= size;
+                self.state = State::Frames(tag);
+                Ok(Step::advance(size, input))
+            }
+            State::Frames(tag) => Ok(self.parse_frame(input, tag)),
+            State::UnsyncTag(tag) => {
+                if input.len() < tag.remain {
+                    return Ok(Step::Need(tag.remain - input.len()));
+                }
+                let data = deunsync(&input[..tag.remain]);
+                let mut frames = &data[..];
+                while let Some(header) = FrameHeader::parse(frames, tag.version) {
+                    let Some(body) = frames.get(header.header_size..header.total_size()) else {
+                        break;
+                    };
+                    self.info.put_frame(&header, body, tag.version);
+                    frames = &frames[header.total_size()..];
+                }
+                Ok(self.end_tag(tag, input))
+            }
+            State::Audio { .. } if self.tag_only => Ok(Step::Done),
+            State::Audio { searched } => {
+                let end = input.len().saturating_sub(AUDIO_PROBE_SIZE);
+                if let Some(pos) = (0..end).find(|&i| MpegFrame::parse(&input[i..]).is_some()) {
+                    self.info.duration_ms = estimate_duration(&input[pos..]);
+                    return Ok(Step::Done);
+                }
+                if searched + end >= MAX_SYNC_SEARCH {
+                    tracing::debug!("no MPEG audio frame found");
+                    return Ok(Step::Done);
+                }
+                if end == 0 {
+                    return Ok(Step::Need(AUDIO_PROBE_SIZE - input.len()));
+                }
+                self.state = State::Audio {
+                    searched: searched + end,
+                };
+                Ok(Step::Consumed(end))
+            }
+        }
+    }
+
+    fn parse_header(&mut self, input: &[u8]) -> Result<Step, ParsingError> {
+        if input.len() < TAG_HEADER_SIZE {
+            return Ok(Step::Need(TAG_HEADER_SIZE - input.len()));
+        }
+        if !input.starts_with(b"ID3") {
+            // No ID3v2 tag
+            self.state = State::Audio { searched: 0 };
+            return Ok(Step::Consumed(0));
+        }
+
+        let version = input[3];
+        if !(2..=4).contains(&version) {
+            return Err(ParsingError::Failed(format!(
+                "unsupported ID3v2 version: {version}"
+            )));
+        }
+        let flags = input[5];
+        let tag = Tag {
+            version,
+            remain: synchsafe(&input[6..10]),
+            footer: if version == 4 && flags & FLAG_FOOTER != 0 {
+                FOOTER_SIZE
+            } else {
+                0
+            },
+        };
+        tracing::debug!(?tag, flags, "ID3v2 tag");
+
+        self.state = if version < 4 && flags & FLAG_UNSYNCHRONISATION != 0 {
+            if tag.remain > MAX_UNSYNC_TAG_SIZE {
+                tracing::warn!(tag.remain, "Ignored: unsynchronised ID3v2 tag is too big");
+                return Ok(self.end_tag(tag, &input[TAG_HEADER_SIZE..]));
+            }
+            State::UnsyncTag(tag)
+        } else if version == 2 && flags & FLAG_EXTENDED_HEADER != 0 {
+            // The flag means compression in ID3v2.2, which is not supported
+            return Ok(self.end_tag(tag, &input[TAG_HEADER_SIZE..]));
+        } else if flags & FLAG_EXTENDED_HEADER != 0 {
+            State::ExtendedHeader(tag)
+        } else {
+            State::Frames(tag)
+        };
+        Ok(Step::Consumed(TAG_HEADER_SIZE))
+    }
+
+    fn parse_frame(&mut self, input: &[u8], mut tag: Tag) -> Step {
+        let header_size = FrameHeader::size(tag.version);
+        if tag.remain < header_size {
+            return self.end_tag(tag, input);
+        }
+        if input.len() < header_size {
+            return Step::Need(header_size - input.len());
+        }
+        // Padding, or corrupted data
+        let Some(header) = FrameHeader::parse(input, tag.version) else {
+            return self.end_tag(tag, input);
+        };
+        let total = header.total_size();
+        if total > tag.remain {
+            tracing::warn!(total, tag.remain, "Ignored: ID3v2 frame is too big");
+            return self.end_tag(tag, input);
+        }
+
+        let load = match frame_kind(header.id) {
+            Some(Frame::Picture) => header.size.min(PICTURE_PROBE_SIZE),
+            Some(_) if header.size <= MAX_TEXT_FRAME_SIZE => header.size,
+            _ => 0,
+        };
+        if load > 0 {
+            let Some(body) = input.get(header_size..header_size + load) else {
+                return Step::Need(header_size + load - input.len());
+            };
+            self.info.put_frame(&header, body, tag.version);
+        }
+
+        tag.remain -= total;
+        self.state = State::Frames(tag);
+        Step::advance(total, input)
+    }
+
+    /// Skips the remaining part of the tag, and starts searching for audio
+    /// frames, or stops if `tag_only`.
+    fn end_tag(&mut self, tag: Tag, input: &[u8]) -> Step {
+        self.state = State::Audio { searched: 0 };
+        if self.tag_only {
+            return Step::Done;
+        }
+        Step::advance(tag.remain + tag.footer, input)
+    }
+}
+
+/// Parses an ID3v2 tag which is completely in memory, e.g. the one embedded
+/// in the `ID32` box of MP4 files. Returns `None` if `data` doesn't start
+/// with a valid ID3v2 tag header.
+pub(crate) fn parse_id3v2_tag(data: &[u8]) -> Option<TrackInfo> {
+    if !data.starts_with(b"ID3") {
+        return None;
+    }
+    let mut parser = Mp3Parser::tag_only();
+    let mut pos = 0;
+    // Stops at the end of the tag, or if the tag is truncated
+    while let Step::Consumed(n) = parser.step(&data[pos..]).ok()? {
+        pos += n;
+    }
+    Some(parser.info.into())
+}
+
+/// Frame header of ID3v2.2 (6 bytes), or ID3v2.3/2.4 (10 bytes).
+#[derive(Debug)]
+struct FrameHeader<'a> {
+    id: &'a [u8],
+    size: usize,
+    flags: u16,
+    header_size: usize,
+}
+
+impl<'a> FrameHeader<'a> {
+    fn size(version: u8) -> usize {
+        if version == 2 {
+            6
+        } else {
+            10
+        }
+    }
+
+    /// Returns `None` for padding.
+    fn parse(input: &'a [u8], version: u8) -> Option<Self> {
+        let header_size = Self::size(version);
+        let header = input.get(..header_size)?;
+        let (id, size, flags) = match version {
+            2 => (
+                &header[..3],
+                u32::from_be_bytes([0, header[3], header[4], header[5]]) as usize,
+                0,
+            ),
+            3 => (
+                &header[..4],
+                u32::from_be_bytes(header[4..8].try_into().unwrap()) as usize,
+                u16::from_be_bytes([header[8], header[9]]),
+            ),
+            _ => (
+                &header[..4],
+                synchsafe(&header[4..8]),
+                u16::from_be_bytes([header[8], header[9]]),
+            ),
+        };
+        if !id
+            .iter()
+            .all(|x| x.is_ascii_uppercase() || x.is_ascii_digit())
+        {
+            return None;
+        }
+        Some(Self {
+            id,
+            size,
+            flags,
+            header_size,
+        })
+    }
+
+    fn total_size(&self) -> usize {
+        self.header_size.saturating_add(self.size)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Frame {
+    Title,
+    Artist,
+    Album,
+    Software,
+    RecordingTime,
+    Year,
+    Date,
+    Time,
+    Length,
+    Picture,
+}
+
+fn frame_kind(id: &[u8]) -> Option<Frame> {
+    let kind = match id {
+        b"TT2" | b"TIT2" => Frame::Title,
+        b"TP1" | b"TPE1" => Frame::Artist,
+        b"TAL" | b"TALB" => Frame::Album,
+        b"TSS" | b"TSSE" => Frame::Software,
+        b"TDRC" => Frame::RecordingTime,
+        b"TYE" | b"TYER" => Frame::Year,
+        b"TDA" | b"TDAT" => Frame::Date,
+        b"TIM" | b"TIME" => Frame::Time,
+        b"TLE" | b"TLEN" => Frame::Length,
+        b"PIC" | b"APIC" => Frame::Picture,
+        _ => return None,
+    };
+    Some(kind)
+}
+
+impl Mp3Info {
+    fn put_frame(&mut self, header: &FrameHeader, body: &[u8], version: u8) {
+        let Some(kind) = frame_kind(header.id) else {
+            return;
+        };
+        let Some(body) = frame_content(body, header.flags, version) else {
+            tracing::debug!(id = ?String::from_utf8_lossy(header.id), "Ignored: compressed or encrypted frame");
+            return;
+        };
+
+        if kind == Frame::Picture {
+            self.picture = self.picture.take().or_else(|| picture_mime(&body, version));
+            return;
+        }
+        let text = decode_text(&body);
+        match kind {
+            Frame::Title => self.title = text,
+            Frame::Artist => self.artist = text,
+            Frame::Album => self.album = text,
+            Frame::Software => self.software = text,
+            Frame::RecordingTime => self.recording_time = text,
+            Frame::Year => self.year = text,
+            Frame::Date => self.date = text,
+            Frame::Time => self.time = text,
+            Frame::Length => self.length = text.and_then(|x| x.parse().ok()),
+            Frame::Picture => unreachable!(),
+        }
+    }
+
+    /// Returns the recording time, which is `TDRC` in ID3v2.4, or combined
+    /// from `TYER`, `TDAT` & `TIME` in ID3v2.2/2.3.
+    fn create_date(&self) -> Option<DateTime<FixedOffset>> {
+        if let Some(s) = self.recording_time.as_deref() {
+            return parse_timestamp(s);
+        }
+        let year = self.year.as_deref()?;
+        let mut s = year.to_owned();
+        if let Some(date) = self.date.as_deref().filter(|x| x.len() == 4) {
+            // DDMM
+            s.push_str(&format!("-{}-{}", &date[2..], &date[..2]));
+            if let Some(time) = self.time.as_deref().filter(|x| x.len() == 4) {
+                // HHMM
+                s.push_str(&format!("T{}:{}", &time[..2], &time[2..]));
+            }
+        }
+        parse_timestamp(&s)
+    }
+}
+
+/// Removes the extra data of a frame according to its flags, returns `None`
+/// if the frame is compressed or encrypted.
+fn frame_content(body: &[u8], flags: u16, version: u8) -> Option<Cow<'_, [u8]>> {
+    match version {
+        3 => {
+            // Compression & encryption
+            if flags & 0x00C0 != 0 {
+                return None;
+            }
+            // Grouping identity
+            let body = if flags & 0x0020 != 0 {
+                body.get(1..)?
+            } else {
+                body
+            };
+            Some(Cow::Borrowed(body))
+        }
+        4 => {
+            // Compression & encryption
+            if flags & 0x000C != 0 {
+                return None;
+            }
+            let mut body = body;
+            // Grouping identity
+            if flags & 0x0040 != 0 {
+                body = body.get(1..)?;
+            }
+            // Data length indicator
+            if flags & 0x0001 != 0 {
+                body = body.get(4..)?;
+            }
+            if flags & 0x0002 != 0 {
+                Some(Cow::Owned(deunsync(body)))
+            } else {
+                Some(Cow::Borrowed(body))
+            }
+        }
+        _ => Some(Cow::Borrowed(body)),
+    }
+}
+
+/// Decodes a text frame. Only the first value is returned if there are
+/// multiple values (ID3v2.4).
+fn decode_text(body: &[u8]) -> Option<String> {
+    let (encoding, text) = body.split_first()?;
+    let s = match encoding {
+        // ISO-8859-1
+        0 => text.iter().map(|x| *x as char).collect(),
+        // UTF-16 with BOM, or UTF-16BE without BOM
+        1 | 2 => {
+            let (text, little_endian) = match text {
+                [0xFF, 0xFE, rest @ ..] => (rest, true),
+                [0xFE, 0xFF, rest @ ..] => (rest, false),
+                _ => (text, false),
+            };
+            let units = text.chunks_exact(2).map(|x| {
+                if little_endian {
+                    u16::from_le_bytes([x[0], x[1]])
+                } else {
+                    u16::from_be_bytes([x[0], x[1]])
+                }
+            });
+            char::decode_utf16(units)
+                .map(|x| x.unwrap_or(char::REPLACEMENT_CHARACTER))
+                .collect()
+        }
+        3 => String::from_utf8_lossy(text).into_owned(),
+        _ => return None,
+    };
+    let s = s.split('\0').next().unwrap_or_default().trim();
+    Some(s.to_owned()).filter(|x| !x.is_empty())
+}
+
+/// Returns the MIME type of an `APIC` frame, or converts the image format of
+/// a `PIC` frame (ID3v2.2) to a MIME type.
+fn picture_mime(body: &[u8], version: u8) -> Option<String> {
+    let data = body.get(1..)?;
+    if version == 2 {
+        let format = data.get(..3)?;
+        let mime = match format {
+            b"JPG" => "image/jpeg".to_owned(),
+            b"PNG" => "image/png".to_owned(),
+            _ => format!("image/{}", String::from_utf8_lossy(format).to_lowercase()),
+        };
+        return Some(mime);
+    }
+    let end = data.iter().position(|x| *x == 0)?;
+    let mime: String = data[..end].iter().map(|x| *x as char).collect();
+    // "image/" is implied if the MIME type is omitted
+    if mime.is_empty() {
+        Some("image/".to_owned())
+    } else {
+        Some(mime)
+    }
+}
+
+/// Parses an ID3v2 timestamp, e.g.: "yyyy", "yyyy-MM-dd",
+/// "yyyy-MM-ddTHH:mm:ss".
+///
+/// The time is treated as UTC, since there is no time zone info.
+fn parse_timestamp(s: &str) -> Option<DateTime<FixedOffset>> {
+    let (date, time) = s.split_once('T').unwrap_or((s, ""));
+    let mut date = date.split('-').map(|x| x.parse::<u32>().ok());
+    let year = date.next()??;
+    let month = date.next().unwrap_or(Some(1))?;
+    let day = date.next().unwrap_or(Some(1))?;
+    let date = NaiveDate::from_ymd_opt(year as i32, month, day)?;
+
+    let mut time = time
+        .split(':')
+        .filter(|x| !x.is_empty())
+        .map(|x| x.parse::<u32>().ok());
+    let hour = time.next().unwrap_or(Some(0))?;
+    let min = time.next().unwrap_or(Some(0))?;
+    let sec = time.next().unwrap_or(Some(0))?;
+    let time = NaiveTime::from_hms_opt(hour, min, sec)?;
+
+    Some(date.and_time(time).and_utc().fixed_offset())
+}
+
+/// Decodes a 28-bit synchsafe integer.
+fn synchsafe(data: &[u8]) -> usize {
+    data.iter()
+        .fold(0, |acc, x| (acc << 7) | (*x & 0x7F) as usize)
+}
+
+/// Reverses the unsynchronisation scheme, i.e. removes the `0x00` after each
+/// `0xFF`.
+fn deunsync(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut prev = 0;
+    for &x in data {
+        if !(prev == 0xFF && x == 0) {
+            out.push(x);
+        }
+        prev = x;
+    }
+    out
+}
+
+/// Header of an MPEG audio frame.
+#[derive(Debug, Clone, Copy)]
+struct MpegFrame {
+    /// MPEG-1
+    mpeg1: bool,
+    layer: u8,
+    sample_rate: u32,
+    mono: bool,
+    crc: bool,
+}
+
+impl MpegFrame {
+    fn parse(input: &[u8]) -> Option<Self> {
+        let header = input.get(..4)?;
+        if header[0] != 0xFF || header[1] & 0xE0 != 0xE0 {
+            return None;
+        }
+        let (mpeg1, rate_divisor) = match (header[1] >> 3) & 0x03 {
+            0 => (false, 4), // MPEG-2.5
+            2 => (false, 2),
+            3 => (true, 1),
+            _ => return None,
+        };
+        let layer = match (header[1] >> 1) & 0x03 {
+            1 => 3,
+            2 => 2,
+            3 => 1,
+            _ => return None,
+        };
+        if header[2] >> 4 == 0x0F {
+            return None;
+        }
+        let sample_rate = match (header[2] >> 2) & 0x03 {
+            0 => 44100,
+            1 => 48000,
+            2 => 32000,
+            _ => return None,
+        } / rate_divisor;
+        Some(Self {
+            mpeg1,
+            layer,
+            sample_rate,
+            mono: header[3] >> 6 == 0x03,
+            crc: header[1] & 0x01 == 0,
+        })
+    }
+
+    fn samples_per_frame(&self) -> u64 {
+        match (self.layer, self.mpeg1) {
+            (1, _) => 384,
+            (3, false) => 576,
+            _ => 1152,
+        }
+    }
+
+    /// Offset of the Xing header, which follows the side information.
+    fn xing_offset(&self) -> usize {
+        let side_info = match (self.mpeg1, self.mono) {
+            (true, false) => 32,
+            (true, true) | (false, false) => 17,
+            (false, true) => 9,
+        };
+        4 + if self.crc { 2 } else { 0 } + side_info
+    }
+}
+
+/// Estimates the duration from the frame count in the Xing (or "Info" for CBR
+/// files) or VBRI header of the first audio frame.
+fn estimate_duration(input: &[u8]) -> Option<u64> {
+    let frame = MpegFrame::parse(input)?;
+    let be_u32 = |pos: usize| -> Option<u64> {
+        let data = input.get(pos..pos + 4)?;
+        Some(u32::from_be_bytes(data.try_into().unwrap()) as u64)
+    };
+
+    let pos = frame.xing_offset();
+    let frames = match input.get(pos..pos + 4)? {
+        b"Xing" | b"Info" if be_u32(pos + 4)? & 0x01 != 0 => be_u32(pos + 8)?,
+        _ if input.get(36..40)? == b"VBRI" => be_u32(36 + 14)?,
+        _ => return None,
+    };
+    Some(frames * frame.samples_per_frame() * 1000 / frame.sample_rate as u64)
+}
+
+impl From<Mp3Info> for TrackInfo {
+    fn from(value: Mp3Info) -> Self {
+        let mut info = TrackInfo::default();
+        if let Some(date) = value.create_date() {
+            info.put(TrackInfoTag::CreateDate, date.into());
+        }
+
+        let texts = [
+            (TrackInfoTag::Title, value.title),
+            (TrackInfoTag::Artist, value.artist),
+            (TrackInfoTag::Album, value.album),
+            (TrackInfoTag::Software, value.software),
+            (TrackInfoTag::Picture, value.picture),
+        ];
+        for (tag, text) in texts {
+            if let Some(text) = text {
+                info.put(tag, text.into());
+            }
+        }
+
+        // The estimation from the audio frame is more reliable than `TLEN`
+        if let Some(duration) = value.duration_ms.or(value.length) {
+            info.put(TrackInfoTag::DurationMs, duration.into());
+        }
+        info
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{MediaParser, MediaSource};
+    use test_case::test_case;
+
+    fn synchsafe_bytes(n: usize) -> [u8; 4] {
+        [
+            (n >> 21) as u8 & 0x7F,
+            (n >> 14) as u8 & 0x7F,
+            (n >> 7) as u8 & 0x7F,
+            n as u8 & 0x7F,
+        ]
+    }
+
+    fn frame(version: u8, id: &str, body: &[u8]) -> Vec<u8> {
+        let mut frame = id.as_bytes().to_vec();
+        match version {
+            2 => frame.extend(&(body.len() as u32).to_be_bytes()[1..]),
+            3 => frame.extend((body.len() as u32).to_be_bytes()),
+            _ => frame.extend(synchsafe_bytes(body.len())),
+        }
+        if version > 2 {
+            frame.extend([0, 0]);
+        }
+        frame.extend(body);
+        frame
+    }
+
+    fn text(version: u8, id: &str, s: &str) -> Vec<u8> {
+        frame(version, id, &[&[3][..], s.as_bytes()].concat())
+    }
+
+    /// Makes an MPEG-1 layer III frame (128 kbps, 44.1 kHz, stereo), with a
+    /// Xing header if `frames` is provided.
+    fn audio_frame(frames: Option<u32>) -> Vec<u8> {
+        let mut data = vec![0; 417];
+        data[..4].copy_from_slice(&[0xFF, 0xFB, 0x90, 0x00]);
+        if let Some(frames) = frames {
+            data[36..40].copy_from_slice(b"Xing");
+            data[40..44].copy_from_slice(&1_u32.to_be_bytes());
+            data[44..48].copy_from_slice(&frames.to_be_bytes());
+        }
+        data
+    }
+
+    /// Makes an MP3 file with an ID3v2 tag of `version`, the tag contains a
+    /// picture of `picture_size` bytes.
+    pub(crate) fn make_mp3(version: u8, picture_size: usize, unsync: bool) -> Vec<u8> {
+        let picture = [&[0][..], b"image/png\0\x03\0", &vec![0xFF; picture_size]].concat();
+        let frames = match version {
+            2 => [
+                text(2, "TT2", "Title"),
+                text(2, "TP1", "Artist"),
+                text(2, "TAL", "Album"),
+                text(2, "TYE", "2019"),
+                text(2, "TDA", "3112"),
+                text(2, "TIM", "2359"),
+                frame(
+                    2,
+                    "PIC",
+                    &[&[0][..], b"PNG\x03\0", &vec![0xFF; picture_size]].concat(),
+                ),
+            ]
+            .concat(),
+            3 => [
+                text(3, "TIT2", "Title"),
+                text(3, "TPE1", "Artist"),
+                text(3, "TALB", "Album"),
+                text(3, "TYER", "2019"),
+                text(3, "TDAT", "3112"),
+                text(3, "TIME", "2359"),
+                text(3, "TSSE", "LAME 3.100"),
+                frame(3, "APIC", &picture),
+            ]
+            .concat(),
+            _ => [
+                text(4, "TIT2", "Title\0Title 2"),
+                text(4, "TPE1", "Artist"),
+                text(4, "TALB", "Album"),
+                text(4, "TDRC", "2019-12-31T23:59"),
+                text(4, "TLEN", "1000"),
+                frame(4, "APIC", &picture),
+            ]
+            .concat(),
+        };
+        let (frames, flags) = if unsync {
+            let mut data = Vec::new();
+            for x in frames {
+                data.push(x);
+                if x == 0xFF {
+                    data.push(0);
+                }
+            }
+            (data, FLAG_UNSYNCHRONISATION)
+        } else {
+            (frames, 0)
+        };
+
+        let size = frames.len() + 256;
+        [
+            &b"ID3"[..],
+            &[version, 0, flags],
+            &synchsafe_bytes(size),
+            &frames,
+            &[0; 256],
+            &audio_frame(Some(1000)),
+            &audio_frame(None),
+        ]
+        .concat()
+    }
+
+    #[test_case(2, 100, false; "v2.2")]
+    #[test_case(3, 100 * 1024, false; "v2.3")]
+    #[test_case(3, 100, true; "v2.3 unsynchronised")]
+    #[test_case(4, 100 * 1024, false; "v2.4")]
+    fn mp3(version: u8, picture_size: usize, unsync: bool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let data = make_mp3(version, picture_size, unsync);
+        let mut parser = MediaParser::new();
+        let ms = MediaSource::unseekable(Cursor::new(data)).unwrap();
+        assert!(ms.has_track());
+        let info: TrackInfo = parser.parse(ms).unwrap();
+
+        assert_eq!(info.get(TrackInfoTag::Title), Some(&"Title".into()));
+        assert_eq!(info.get(TrackInfoTag::Artist), Some(&"Artist".into()));
+        assert_eq!(info.get(TrackInfoTag::Album), Some(&"Album".into()));
+        assert_eq!(info.get(TrackInfoTag::Picture), Some(&"image/png".into()));
+        assert_eq!(
+            info.get(TrackInfoTag::CreateDate).unwrap().to_string(),
+            "2019-12-31T23:59:00+00:00"
+        );
+        // 1000 frames * 1152 samples / 44100 Hz
+        assert_eq!(info.get(TrackInfoTag::DurationMs), Some(&26122_u64.into()));
+    }
+
+    #[test_case(2, false; "v2.2")]
+    #[test_case(3, true; "v2.3 unsynchronised")]
+    #[test_case(4, false; "v2.4")]
+    fn id3v2_tag(version: u8, unsync: bool) {
+        let data = make_mp3(version, 100, unsync);
+        let info = parse_id3v2_tag(&data).unwrap();
+        assert_eq!(info.get(TrackInfoTag::Title), Some(&"Title".into()));
+        assert_eq!(info.get(TrackInfoTag::Album), Some(&"Album".into()));
+        // Audio frames after the tag are not parsed
+        let duration = (version == 4).then(|| 1000_u64.into());
+        assert_eq!(info.get(TrackInfoTag::DurationMs), duration.as_ref());
+
+        assert!(parse_id3v2_tag(&data[..20]).is_some());
+        assert!(parse_id3v2_tag(&data[10..]).is_none());
+    }
+
+    #[test]
+    fn mp3_without_tag() {
+        let data = [audio_frame(Some(100)), audio_frame(None)].concat();
+        let mut parser = MediaParser::new();
+        let ms = MediaSource::unseekable(Cursor::new(data)).unwrap();
+        let info: TrackInfo = parser.parse(ms).unwrap();
+        assert_eq!(info.get(TrackInfoTag::DurationMs), Some(&2612_u64.into()));
+        assert_eq!(info.get(TrackInfoTag::Title), None);
+    }
+
+    #[test_case(&[0, b'a', 0xE9], Some("aé"); "latin1")]
+    #[test_case(&[1, 0xFF, 0xFE, b'a', 0, 0xE9, 0], Some("aé"); "utf16 bom")]
+    #[test_case(&[2, 0, b'a', 0, 0xE9], Some("aé"); "utf16be")]
+    #[test_case(&[3, b'a', 0xC3, 0xA9, 0, b'b'], Some("aé"); "utf8 multiple values")]
+    #[test_case(&[3, 0], None; "empty")]
+    #[test_case(&[9, b'a'], None; "invalid encoding")]
+    fn text_frame(body: &[u8], expect: Option<&str>) {
+        assert_eq!(decode_text(body).as_deref(), expect);
+    }
+
+    #[test_case("2019", Some("2019-01-01T00:00:00+00:00"))]
+    #[test_case("2019-12", Some("2019-12-01T00:00:00+00:00"))]
+    #[test_case("2019-12-31T23:59:58", Some("2019-12-31T23:59:58+00:00"))]
+    #[test_case("2019-13-31", None)]
+    #[test_case("", None)]
+    fn timestamp(s: &str, expect: Option<&str>) {
+        assert_eq!(
+            parse_timestamp(s).map(|x| x.to_rfc3339()).as_deref(),
+            expect
+        );
+    }
+}
@@ -0,0 +1,457 @@
+use std::fmt::Display;
+
+use nom::{
+    bytes::complete::{tag, take},
+    number::complete::{be_u16, be_u32, u8},
+    sequence::tuple,
+    IResult,
+};
+
+use crate::{
+    error::{nom_error_to_parsing_error_with_state, ParsingErrorState},
+    jpeg, EntryValue,
+};
+
+/// Photoshop image resource ID of the IPTC-NAA record.
+const IPTC_RESOURCE_ID: u16 = 0x0404;
+
+/// Marker of an IIM dataset.
+const IIM_TAG_MARKER: u8 = 0x1c;
+
+/// IPTC-IIM metadata (captions, credits, keywords, etc.) of a JPEG file,
+/// which is stored in the Photoshop image resources of the APP13 segments.
+///
+/// Entries are kept in the order of the file, repeatable datasets (e.g.
+/// [`IptcTag::Keywords`]) appear once per value.
+///
+/// ```rust
+/// use nom_exif::*;
+///
+/// fn keywords(path: &str) -> Result<Vec<String>> {
+///     let mut parser = MediaParser::new();
+///     let iptc: Iptc = parser.parse(MediaSource::file_path(path)?)?;
+///     for entry in iptc.iter() {
+///         let (record, dataset) = (entry.record(), entry.dataset());
+///         println!("{record}:{dataset} {:?} => {}", entry.tag(), entry.value());
+///     }
+///     Ok(iptc
+///         .get_all(IptcTag::Keywords)
+///         .filter_map(|x| x.as_str().map(|x| x.to_owned()))
+///         .collect())
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Iptc {
+    entries: Vec<IptcEntry>,
+}
+
+/// A dataset of [`Iptc`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct IptcEntry {
+    record: u8,
+    dataset: u8,
+    value: EntryValue,
+}
+
+impl Iptc {
+    /// Returns the value of the first dataset of `tag`.
+    pub fn get(&self, tag: IptcTag) -> Option<&EntryValue> {
+        self.get_all(tag).next()
+    }
+
+    /// Returns the values of all datasets of `tag`, in the order of the file.
+    pub fn get_all(&self, tag: IptcTag) -> impl Iterator<Item = &EntryValue> {
+        self.entries
+            .iter()
+            .filter(move |x| x.code() == tag.code())
+            .map(|x| &x.value)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &IptcEntry> {
+        self.entries.iter()
+    }
+
+    /// Parses the IPTC-IIM datasets in the Photoshop image resource blocks
+    /// (`8BIM`). Returns `None` if there is no IPTC-NAA resource.
+    pub(crate) fn from_photoshop_resources(mut data: &[u8]) -> Option<Iptc> {
+        let iim = loop {
+            let (remain, (id, body)) = parse_resource(data).ok()?;
+            if id == IPTC_RESOURCE_ID {
+                break body;
+            }
+            data = remain;
+        };
+
+        let mut entries = Vec::new();
+        let mut remain = iim;
+        // Trailing padding or a corrupted dataset stops parsing
+        while let Ok((rest, (record, dataset, data))) = parse_dataset(remain) {
+            remain = rest;
+            entries.push(IptcEntry {
+                record,
+                dataset,
+                value: decode_value(record, dataset, data),
+            });
+        }
+        Some(Iptc { entries })
+    }
+}
+
+impl<'a> IntoIterator for &'a Iptc {
+    type Item = &'a IptcEntry;
+    type IntoIter = std::slice::Iter<'a, IptcEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter()
+    }
+}
+
+impl IptcEntry {
+    /// Record number, e.g. 2 for the application record.
+    pub fn record(&self) -> u8 {
+        self.record
+    }
+
+    /// Dataset number in the record.
+    pub fn dataset(&self) -> u8 {
+        self.dataset
+    }
+
+    /// Returns the tag, or `None` if the dataset isn't recognized.
+    pub fn tag(&self) -> Option<IptcTag> {
+        IptcTag::try_from(self.code()).ok()
+    }
+
+    /// Datasets are text, except for the version numbers (`U16`) and
+    /// [`IptcTag::CodedCharacterSet`] (`Undefined`).
+    pub fn value(&self) -> &EntryValue {
+        &self.value
+    }
+
+    fn code(&self) -> u16 {
+        (self.record as u16) << 8 | self.dataset as u16
+    }
+}
+
+/// Recognized IPTC-IIM datasets, the code is `record << 8 | dataset`. All
+/// datasets are parsed, no matter if they are defined here.
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy)]
+#[non_exhaustive]
+pub enum IptcTag {
+    ModelVersion = 0x0100,
+    CodedCharacterSet = 0x015a,
+
+    RecordVersion = 0x0200,
+    ObjectName = 0x0205,
+    EditStatus = 0x0207,
+    Urgency = 0x020a,
+    Category = 0x020f,
+    SupplementalCategories = 0x0214,
+    FixtureIdentifier = 0x0216,
+    Keywords = 0x0219,
+    SpecialInstructions = 0x0228,
+    DateCreated = 0x0237,
+    TimeCreated = 0x023c,
+    DigitalCreationDate = 0x023e,
+    DigitalCreationTime = 0x023f,
+    OriginatingProgram = 0x0241,
+    ProgramVersion = 0x0246,
+    ByLine = 0x0250,
+    ByLineTitle = 0x0255,
+    City = 0x025a,
+    SubLocation = 0x025c,
+    ProvinceState = 0x025f,
+    CountryCode = 0x0264,
+    CountryName = 0x0265,
+    OriginalTransmissionReference = 0x0267,
+    Headline = 0x0269,
+    Credit = 0x026e,
+    Source = 0x0273,
+    CopyrightNotice = 0x0274,
+    Contact = 0x0276,
+    Caption = 0x0278,
+    Writer = 0x027a,
+}
+
+const IPTC_TAGS: &[IptcTag] = &[
+    IptcTag::ModelVersion,
+    IptcTag::CodedCharacterSet,
+    IptcTag::RecordVersion,
+    IptcTag::ObjectName,
+    IptcTag::EditStatus,
+    IptcTag::Urgency,
+    IptcTag::Category,
+    IptcTag::SupplementalCategories,
+    IptcTag::FixtureIdentifier,
+    IptcTag::Keywords,
+    IptcTag::SpecialInstructions,
+    IptcTag::DateCreated,
+    IptcTag::TimeCreated,
+    IptcTag::DigitalCreationDate,
+    IptcTag::DigitalCreationTime,
+    IptcTag::OriginatingProgram,
+    IptcTag::ProgramVersion,
+    IptcTag::ByLine,
+    IptcTag::ByLineTitle,
+    IptcTag::City,
+    IptcTag::SubLocation,
+    IptcTag::ProvinceState,
+    IptcTag::CountryCode,
+    IptcTag::CountryName,
+    IptcTag::OriginalTransmissionReference,
+    IptcTag::Headline,
+    IptcTag::Credit,
+    IptcTag::Source,
+    IptcTag::CopyrightNotice,
+    IptcTag::Contact,
+    IptcTag::Caption,
+    IptcTag::Writer,
+];
+
+impl IptcTag {
+    pub const fn code(self) -> u16 {
+        self as u16
+    }
+}
+
+impl Display for IptcTag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s: &str = (*self).into();
+        Display::fmt(s, f)
+    }
+}
+
+impl From<IptcTag> for &str {
+    fn from(value: IptcTag) -> Self {
+        match value {
+            IptcTag::ModelVersion => "ModelVersion",
+            IptcTag::CodedCharacterSet => "CodedCharacterSet",
+            IptcTag::RecordVersion => "RecordVersion",
+            IptcTag::ObjectName => "ObjectName",
+            IptcTag::EditStatus => "EditStatus",
+            IptcTag::Urgency => "Urgency",
+            IptcTag::Category => "Category",
+            IptcTag::SupplementalCategories => "SupplementalCategories",
+            IptcTag::FixtureIdentifier => "FixtureIdentifier",
+            IptcTag::Keywords => "Keywords",
+            IptcTag::SpecialInstructions => "SpecialInstructions",
+            IptcTag::DateCreated => "DateCreated",
+            IptcTag::TimeCreated => "TimeCreated",
+            IptcTag::DigitalCreationDate => "DigitalCreationDate",
+            IptcTag::DigitalCreationTime => "DigitalCreationTime",
+            IptcTag::OriginatingProgram => "OriginatingProgram",
+            IptcTag::ProgramVersion => "ProgramVersion",
+            IptcTag::ByLine => "By-line",
+            IptcTag::ByLineTitle => "By-lineTitle",
+            IptcTag::City => "City",
+            IptcTag::SubLocation => "Sub-location",
+            IptcTag::ProvinceState => "Province-State",
+            IptcTag::CountryCode => "Country-PrimaryLocationCode",
+            IptcTag::CountryName => "Country-PrimaryLocationName",
+            IptcTag::OriginalTransmissionReference => "OriginalTransmissionReference",
+            IptcTag::Headline => "Headline",
+            IptcTag::Credit => "Credit",
+            IptcTag::Source => "Source",
+            IptcTag::CopyrightNotice => "CopyrightNotice",
+            IptcTag::Contact => "Contact",
+            IptcTag::Caption => "Caption-Abstract",
+            IptcTag::Writer => "Writer-Editor",
+        }
+    }
+}
+
+impl TryFrom<u16> for IptcTag {
+    type Error = crate::Error;
+
+    fn try_from(v: u16) -> Result<Self, Self::Error> {
+        IPTC_TAGS
+            .iter()
+            .find(|x| x.code() == v)
+            .copied()
+            .ok_or_else(|| format!("unrecognized IPTC dataset: 0x{v:04x}").into())
+    }
+}
+
+/// Extracts IPTC-IIM from the APP13 segments of a JPEG file. Returns `None`
+/// if there is none.
+pub(crate) fn extract_iptc(input: &[u8]) -> Result<Option<Iptc>, ParsingErrorState> {
+    let (_, segments) = jpeg::collect_app1_segments(input)
+        .map_err(|e| nom_error_to_parsing_error_with_state(e, None))?;
+    if segments.photoshop.is_empty() {
+        return Ok(None);
+    }
+    Ok(Iptc::from_photoshop_resources(&segments.photoshop.concat()))
+}
+
+/// Parses a Photoshop image resource block, returns (ID, data).
+fn parse_resource(input: &[u8]) -> IResult<&[u8], (u16, &[u8])> {
+    let (remain, (_, id, name_len)) = tuple((tag(b"8BIM"), be_u16, u8))(input)?;
+    // The name is a Pascal string, padded to an even size (including the
+    // length byte)
+    let (remain, _) = take(name_len | 1)(remain)?;
+    let (remain, size) = be_u32(remain)?;
+    let (remain, data) = take(size)(remain)?;
+    // The data is padded to an even size too
+    let remain = remain.get((size & 1) as usize..).unwrap_or(remain);
+    Ok((remain, (id, data)))
+}
+
+/// Parses an IIM dataset, returns (record, dataset, data).
+fn parse_dataset(input: &[u8]) -> IResult<&[u8], (u8, u8, &[u8])> {
+    let (remain, (_, record, dataset, size)) =
+        tuple((tag([IIM_TAG_MARKER]), u8, u8, be_u16))(input)?;
+
+    // Extended dataset: the lower 15 bits are the size of the length field
+    let (remain, size) = if size & 0x8000 != 0 {
+        let (remain, len) = take(size & 0x7fff)(remain)?;
+        if len.len() > 4 {
+            return nom::combinator::fail(input);
+        }
+        let size = len.iter().fold(0_u32, |acc, x| acc << 8 | *x as u32);
+        (remain, size)
+    } else {
+        (remain, size as u32)
+    };
+
+    let (remain, data) = take(size)(remain)?;
+    Ok((remain, (record, dataset, data)))
+}
+
+fn decode_value(record: u8, dataset: u8, data: &[u8]) -> EntryValue {
+    let code = (record as u16) << 8 | dataset as u16;
+    if code == IptcTag::CodedCharacterSet.code() {
+        return EntryValue::Undefined(data.to_vec());
+    }
+    if code == IptcTag::ModelVersion.code() || code == IptcTag::RecordVersion.code() {
+        if let Ok(v) = <[u8; 2]>::try_from(data) {
+            return EntryValue::U16(u16::from_be_bytes(v));
+        }
+    }
+    EntryValue::Text(decode_text(data))
+}
+
+/// Text is decoded as UTF-8 (which is declared by `CodedCharacterSet` in
+/// most files), falling back to Latin-1.
+fn decode_text(data: &[u8]) -> String {
+    let data = data.strip_suffix(&[0]).unwrap_or(data);
+    match std::str::from_utf8(data) {
+        Ok(s) => s.to_owned(),
+        Err(_) => data.iter().map(|x| *x as char).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MediaParser, MediaSource};
+    use test_case::test_case;
+
+    fn dataset(record: u8, dataset: u8, data: &[u8]) -> Vec<u8> {
+        let mut out = vec![IIM_TAG_MARKER, record, dataset];
+        out.extend((data.len() as u16).to_be_bytes());
+        out.extend(data);
+        out
+    }
+
+    fn resource(id: u16, name: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut out = b"8BIM".to_vec();
+        out.extend(id.to_be_bytes());
+        out.push(name.len() as u8);
+        out.extend(name);
+        if name.len() % 2 == 0 {
+            out.push(0);
+        }
+        out.extend((data.len() as u32).to_be_bytes());
+        out.extend(data);
+        if data.len() % 2 == 1 {
+            out.push(0);
+        }
+        out
+    }
+
+    /// Builds a JPEG file, the resources are split into `segments` APP13
+    /// segments.
+    fn jpeg_with_resources(resources: &[u8], segments: usize) -> Vec<u8> {
+        let mut data = vec![0xff, 0xd8];
+        let size = resources.len().div_ceil(segments);
+        for piece in resources.chunks(size) {
+            let payload = [jpeg::PHOTOSHOP_SIGNATURE, piece].concat();
+            data.extend([0xff, 0xed]);
+            data.extend(((payload.len() + 2) as u16).to_be_bytes());
+            data.extend(payload);
+        }
+        data.extend([0xff, 0xda, 0, 2, 0xff, 0xd9]);
+        data
+    }
+
+    fn sample_iim() -> Vec<u8> {
+        [
+            dataset(1, 90, b"\x1b%G"),
+            dataset(2, 0, &[0, 4]),
+            dataset(2, 120, "Café at dawn".as_bytes()),
+            dataset(2, 25, b"coffee"),
+            dataset(2, 25, b"morning"),
+            dataset(2, 80, b"Jane Doe"),
+            dataset(2, 110, b"Caf\xe9 Press"), // Latin-1
+            dataset(2, 200, b"custom"),
+        ]
+        .concat()
+    }
+
+    #[test_case(1; "one segment")]
+    #[test_case(2; "split")]
+    fn parse_jpeg(segments: usize) {
+        let resources = [
+            resource(0x0425, b"", &[0; 16]), // digest
+            resource(IPTC_RESOURCE_ID, b"IPTC", &sample_iim()),
+        ]
+        .concat();
+        let data = jpeg_with_resources(&resources, segments);
+        let ms = MediaSource::seekable(std::io::Cursor::new(data)).unwrap();
+        let iptc: Iptc = MediaParser::new().parse(ms).unwrap();
+
+        assert_eq!(iptc.iter().count(), 8);
+        assert_eq!(
+            iptc.get(IptcTag::Caption),
+            Some(&EntryValue::Text("Café at dawn".into()))
+        );
+        assert_eq!(iptc.get(IptcTag::RecordVersion), Some(&EntryValue::U16(4)));
+        assert_eq!(
+            iptc.get(IptcTag::Credit),
+            Some(&EntryValue::Text("Café Press".into()))
+        );
+        let keywords = iptc
+            .get_all(IptcTag::Keywords)
+            .map(|x| x.as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(keywords, ["coffee", "morning"]);
+
+        let custom = iptc.iter().last().unwrap();
+        assert_eq!((custom.record(), custom.dataset()), (2, 200));
+        assert_eq!(custom.tag(), None);
+        assert_eq!(
+            iptc.iter().nth(5).unwrap().tag().unwrap().to_string(),
+            "By-line"
+        );
+    }
+
+    #[test]
+    fn extended_dataset() {
+        let mut data = vec![IIM_TAG_MARKER, 2, 120, 0x80, 0x04, 0, 0, 0, 5];
+        data.extend(b"hello");
+        data.extend([0, 0]); // padding
+        let iptc = Iptc::from_photoshop_resources(&resource(IPTC_RESOURCE_ID, b"", &data)).unwrap();
+        assert_eq!(iptc.iter().count(), 1);
+        assert_eq!(
+            iptc.get(IptcTag::Caption),
+            Some(&EntryValue::Text("hello".into()))
+        );
+    }
+
+    #[test_case("exif.jpg"; "no IPTC")]
+    #[test_case("tif.tif"; "unsupported")]
+    fn no_iptc(path: &str) {
+        let ms = MediaSource::file_path(format!("./testdata/{path}")).unwrap();
+        MediaParser::new().parse::<_, _, Iptc>(ms).unwrap_err();
+    }
+}
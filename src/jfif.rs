@@ -0,0 +1,220 @@
+use nom::{
+    number::complete::{be_u16, u8},
+    sequence::tuple,
+    IResult,
+};
+
+use crate::{
+    error::{nom_error_to_parsing_error_with_state, ParsingErrorState},
+    jpeg,
+};
+
+/// JFIF information stored in the APP0 segment of a JPEG file.
+///
+/// ```rust
+/// use nom_exif::*;
+///
+/// let mut parser = MediaParser::new();
+/// let ms = MediaSource::file_path("./testdata/no-exif.jpg").unwrap();
+/// let jfif: JfifInfo = parser.parse(ms).unwrap();
+///
+/// assert_eq!(jfif.version, (1, 2));
+/// assert_eq!(jfif.density_unit, DensityUnit::DotsPerInch);
+/// assert_eq!(jfif.dpi(), Some((72.0, 72.0)));
+/// assert!(jfif.thumbnail.is_none());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JfifInfo {
+    /// (major, minor), e.g. (1, 2)
+    pub version: (u8, u8),
+
+    pub density_unit: DensityUnit,
+    /// Horizontal pixel density
+    pub x_density: u16,
+    /// Vertical pixel density
+    pub y_density: u16,
+
+    /// The embedded thumbnail, either in the JFIF segment itself or in a JFIF
+    /// extension (`JFXX`) segment.
+    pub thumbnail: Option<JfifThumbnail>,
+}
+
+/// Unit of [`JfifInfo::x_density`] & [`JfifInfo::y_density`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DensityUnit {
+    /// No unit, the densities specify the pixel aspect ratio only.
+    None,
+    DotsPerInch,
+    DotsPerCm,
+    Unknown(u8),
+}
+
+/// Format of an embedded JFIF thumbnail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JfifThumbnail {
+    /// Uncompressed 24-bit RGB pixels.
+    Rgb { width: u8, height: u8 },
+    /// 8-bit palette indices, with a 256 entries RGB palette.
+    Palette { width: u8, height: u8 },
+    /// A JPEG stream, the size is only known after decoding it.
+    Jpeg,
+}
+
+impl From<u8> for DensityUnit {
+    fn from(v: u8) -> Self {
+        match v {
+            0 => DensityUnit::None,
+            1 => DensityUnit::DotsPerInch,
+            2 => DensityUnit::DotsPerCm,
+            x => DensityUnit::Unknown(x),
+        }
+    }
+}
+
+impl JfifInfo {
+    /// Returns the (horizontal, vertical) density in dots per inch, or `None`
+    /// if the densities have no (known) unit.
+    pub fn dpi(&self) -> Option<(f64, f64)> {
+        let factor = match self.density_unit {
+            DensityUnit::DotsPerInch => 1.0,
+            DensityUnit::DotsPerCm => 2.54,
+            DensityUnit::None | DensityUnit::Unknown(_) => return None,
+        };
+        Some((
+            self.x_density as f64 * factor,
+            self.y_density as f64 * factor,
+        ))
+    }
+
+    /// Parses the payload (after the "JFIF\0" identifier) of the JFIF
+    /// segment, and the payloads (after the "JFXX\0" identifier) of the
+    /// extension segments.
+    fn parse<'a>(jfif: &'a [u8], jfxx: &[&[u8]]) -> IResult<&'a [u8], JfifInfo> {
+        let (remain, (major, minor, unit, x_density, y_density, width, height)) =
+            tuple((u8, u8, u8, be_u16, be_u16, u8, u8))(jfif)?;
+        let thumbnail = if width > 0 && height > 0 {
+            let size = width as usize * height as usize * 3;
+            // Tolerate truncated thumbnail data, only its presence matters
+            if remain.len() < size {
+                tracing::warn!(size, "JFIF thumbnail is truncated");
+            }
+            Some(JfifThumbnail::Rgb { width, height })
+        } else {
+            jfxx.iter()
+                .find_map(|x| parse_jfxx(x).ok().and_then(|x| x.1))
+        };
+        let info = JfifInfo {
+            version: (major, minor),
+            density_unit: unit.into(),
+            x_density,
+            y_density,
+            thumbnail,
+        };
+        Ok((remain, info))
+    }
+}
+
+/// Parses a JFIF extension segment, returns `None` for unknown extension
+/// codes.
+fn parse_jfxx(input: &[u8]) -> IResult<&[u8], Option<JfifThumbnail>> {
+    let (remain, code) = u8(input)?;
+    match code {
+        0x10 => Ok((remain, Some(JfifThumbnail::Jpeg))),
+        0x11 | 0x13 => {
+            let (remain, (width, height)) = tuple((u8, u8))(remain)?;
+            let thumbnail = if code == 0x11 {
+                JfifThumbnail::Palette { width, height }
+            } else {
+                JfifThumbnail::Rgb { width, height }
+            };
+            Ok((remain, Some(thumbnail)))
+        }
+        _ => Ok((&[], None)),
+    }
+}
+
+/// Extracts JFIF information from the APP0 segments of a JPEG file. Returns
+/// `None` if there is no JFIF segment.
+pub(crate) fn extract_jfif(input: &[u8]) -> Result<Option<JfifInfo>, ParsingErrorState> {
+    let (_, segments) = jpeg::collect_app1_segments(input)
+        .map_err(|e| nom_error_to_parsing_error_with_state(e, None))?;
+    let Some(jfif) = segments.jfif else {
+        return Ok(None);
+    };
+    match JfifInfo::parse(jfif, &segments.jfxx) {
+        Ok((_, info)) => Ok(Some(info)),
+        Err(_) => {
+            tracing::warn!("Ignored: invalid JFIF segment");
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testkit::read_sample, MediaParser, MediaSource};
+    use test_case::test_case;
+
+    fn app0_segment(payload: &[&[u8]]) -> Vec<u8> {
+        let len: usize = payload.iter().map(|x| x.len()).sum();
+        let mut data = vec![0xff, 0xe0];
+        data.extend(((len + 2) as u16).to_be_bytes());
+        payload.iter().for_each(|x| data.extend(*x));
+        data
+    }
+
+    fn jpeg_with_jfif(unit: u8, thumbnail: (u8, u8), jfxx: Option<&[u8]>) -> Vec<u8> {
+        let (width, height) = thumbnail;
+        let mut data = vec![0xff, 0xd8];
+        let pixels = vec![0; width as usize * height as usize * 3];
+        data.extend(app0_segment(&[
+            jpeg::JFIF_SIGNATURE,
+            &[1, 1, unit, 0, 118, 0, 59, width, height],
+            &pixels,
+        ]));
+        if let Some(ext) = jfxx {
+            data.extend(app0_segment(&[jpeg::JFXX_SIGNATURE, ext]));
+        }
+        data.extend([0xff, 0xda, 0, 2, 0xff, 0xd9]);
+        data
+    }
+
+    #[test_case(0, None)]
+    #[test_case(1, Some((118.0, 59.0)))]
+    #[test_case(2, Some((118.0 * 2.54, 59.0 * 2.54)))]
+    #[test_case(9, None)]
+    fn density(unit: u8, dpi: Option<(f64, f64)>) {
+        let data = jpeg_with_jfif(unit, (0, 0), None);
+        let info = extract_jfif(&data).unwrap().unwrap();
+        assert_eq!(info.version, (1, 1));
+        assert_eq!(info.density_unit, DensityUnit::from(unit));
+        assert_eq!((info.x_density, info.y_density), (118, 59));
+        assert_eq!(info.dpi(), dpi);
+        assert_eq!(info.thumbnail, None);
+    }
+
+    #[test_case((2, 3), None, Some(JfifThumbnail::Rgb { width: 2, height: 3 }))]
+    #[test_case((0, 0), Some(&[0x10, 0xff, 0xd8]), Some(JfifThumbnail::Jpeg))]
+    #[test_case((0, 0), Some(&[0x11, 4, 5]), Some(JfifThumbnail::Palette { width: 4, height: 5 }))]
+    #[test_case((0, 0), Some(&[0x13, 4, 5]), Some(JfifThumbnail::Rgb { width: 4, height: 5 }))]
+    #[test_case((0, 0), Some(&[0x20]), None)]
+    fn thumbnail(size: (u8, u8), jfxx: Option<&[u8]>, expect: Option<JfifThumbnail>) {
+        let data = jpeg_with_jfif(1, size, jfxx);
+        let info = extract_jfif(&data).unwrap().unwrap();
+        assert_eq!(info.thumbnail, expect);
+    }
+
+    #[test_case("no-exif.jpg", Some((1, 2)))]
+    #[test_case("exif.jpg", None)]
+    fn sample(path: &str, version: Option<(u8, u8)>) {
+        let data = read_sample(path).unwrap();
+        let info = extract_jfif(&data).unwrap();
+        assert_eq!(info.map(|x| x.version), version);
+
+        let mut parser = MediaParser::new();
+        let ms = MediaSource::file_path(format!("testdata/{path}")).unwrap();
+        let res: crate::Result<JfifInfo> = parser.parse(ms);
+        assert_eq!(res.ok().map(|x| x.version), version);
+    }
+}
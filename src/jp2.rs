@@ -0,0 +1,120 @@
+use nom::bytes::streaming;
+
+use crate::{
+    bbox::BoxHeader,
+    error::{nom_error_to_parsing_error_with_state, ParsingError, ParsingErrorState},
+    exif::EXIF_IDENT,
+};
+
+/// The JPEG 2000 signature box, which starts both *.jp2 & *.jpf (JPX) files.
+const JP2_SIGNATURE: &[u8] = b"\0\0\0\x0cjP  \r\n\x87\n";
+
+/// UUID of the box which contains Exif data, see
+/// <https://exiftool.org/TagNames/Jpeg2000.html>.
+const EXIF_UUID: &[u8] = b"JpgTiffExif->JP2";
+
+/// UUID of the box which contains XMP data.
+pub(crate) const XMP_UUID: &[u8] = &[
+    0xbe, 0x7a, 0xcf, 0xcb, 0x97, 0xa9, 0x42, 0xe8, 0x9c, 0x71, 0x99, 0x94, 0x91, 0xe3, 0xaf, 0xac,
+];
+
+pub(crate) fn check_jp2(input: &[u8]) -> bool {
+    input.starts_with(JP2_SIGNATURE)
+}
+
+/// Returns the TIFF data if `body` is the body of an Exif `uuid` box.
+pub(crate) fn exif_uuid_data(body: &[u8]) -> Option<&[u8]> {
+    let data = body.strip_prefix(EXIF_UUID)?;
+    // Some writers keep the Exif header of JPEG APP1 segments
+    Some(data.strip_prefix(EXIF_IDENT.as_bytes()).unwrap_or(data))
+}
+
+/// Extracts the TIFF data from the Exif `uuid` box.
+///
+/// A JPEG 2000 file is a sequence of top level boxes, `input` should start at
+/// a box boundary. The other boxes (e.g. the `jp2c` codestream) are skipped
+/// with a `ClearAndSkip`, so the next call starts at the next box.
+pub(crate) fn extract_exif(input: &[u8]) -> Result<Option<&[u8]>, ParsingErrorState> {
+    let map_err = |e| nom_error_to_parsing_error_with_state(e, None);
+
+    // A box size of 0 means the last box, which extends to the end of the file
+    if input.starts_with(&[0, 0, 0, 0]) {
+        return Ok(None);
+    }
+
+    let (remain, header) = BoxHeader::parse(input).map_err(map_err)?;
+    if header.box_type == "uuid" {
+        let (_, uuid) = streaming::take(EXIF_UUID.len())(remain).map_err(map_err)?;
+        if uuid == EXIF_UUID {
+            let (_, body) = streaming::take(header.body_size())(remain).map_err(map_err)?;
+            return Ok(exif_uuid_data(body));
+        }
+    }
+
+    let size = usize::try_from(header.box_size)
+        .map_err(|_| ParsingErrorState::new(ParsingError::Failed("box is too big".into()), None))?;
+    Err(ParsingErrorState::new(
+        ParsingError::ClearAndSkip(size),
+        None,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testkit::{bbox, read_sample};
+    use crate::{Exif, ExifIter, ExifTag, MediaParser, MediaSource};
+    use test_case::test_case;
+
+    /// Builds a JPEG 2000 file, the Exif `uuid` box follows a codestream of
+    /// `codestream_size` bytes.
+    fn make_jp2(tiff: &[u8], exif_header: bool, codestream_size: usize) -> Vec<u8> {
+        let mut data = JP2_SIGNATURE.to_vec();
+        data.extend(bbox(b"ftyp", b"jp2 \0\0\0\0jp2 "));
+        data.extend(bbox(b"jp2h", &bbox(b"ihdr", &[0; 14])));
+        data.extend(bbox(b"jp2c", &vec![0; codestream_size]));
+        data.extend(bbox(b"xml ", b"<x:xmpmeta xmlns:x=\"adobe:ns:meta/\"/>"));
+
+        let mut body = EXIF_UUID.to_vec();
+        if exif_header {
+            body.extend_from_slice(EXIF_IDENT.as_bytes());
+        }
+        body.extend_from_slice(tiff);
+        data.extend(bbox(b"uuid", &body));
+        data
+    }
+
+    #[test_case(false, 100)]
+    #[test_case(true, 100)]
+    #[test_case(false, 0x10_0000)]
+    fn jp2(exif_header: bool, codestream_size: usize) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let jpeg = read_sample("exif.jpg").unwrap();
+        let (_, tiff) = crate::jpeg::extract_exif_data(&jpeg).unwrap();
+        let tiff = tiff.unwrap();
+
+        let data = make_jp2(tiff, exif_header, codestream_size);
+        assert!(check_jp2(&data));
+
+        let mut parser = MediaParser::new();
+        let ms = MediaSource::seekable(std::io::Cursor::new(data)).unwrap();
+        let iter: ExifIter = parser.parse(ms).unwrap();
+        let exif: Exif = iter.into();
+        assert_eq!(exif.get(ExifTag::Model), Some(&"vivo X90 Pro+".into()));
+    }
+
+    #[test]
+    fn jp2_without_exif() {
+        let mut data = JP2_SIGNATURE.to_vec();
+        data.extend(bbox(b"ftyp", b"jp2 \0\0\0\0jp2 "));
+        data.extend_from_slice(&[0, 0, 0, 0]);
+        data.extend_from_slice(b"jp2c");
+        data.extend_from_slice(&[0; 100]);
+
+        let mut parser = MediaParser::new();
+        let ms = MediaSource::seekable(std::io::Cursor::new(data)).unwrap();
+        let res: crate::Result<ExifIter> = parser.parse(ms);
+        assert!(res.is_err());
+    }
+}
@@ -1,9 +1,46 @@
 use crate::{ExifIter, MediaParser, MediaSource};
-use std::io::{Read, Seek};
+use std::io::{Read, Seek, Write};
+use std::ops::Range;
 
 use nom::{bytes::streaming, combinator::fail, number, sequence::tuple, IResult};
 
 use crate::exif::{check_exif_header, Exif};
+use crate::xmp::xmp_property;
+
+/// Signature of the APP1 segment which contains the (standard) XMP packet.
+pub(crate) const XMP_SIGNATURE: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+
+/// Signature of the APP1 segments which contain pieces of an extended XMP
+/// packet.
+pub(crate) const EXTENDED_XMP_SIGNATURE: &[u8] = b"http://ns.adobe.com/xmp/extension/\0";
+
+/// Signature of the Photoshop APP13 segments, which contain image resource
+/// blocks, e.g. IPTC-IIM.
+pub(crate) const PHOTOSHOP_SIGNATURE: &[u8] = b"Photoshop 3.0\0";
+
+/// Identifier of the JFIF APP0 segment.
+pub(crate) const JFIF_SIGNATURE: &[u8] = b"JFIF\0";
+
+/// Identifier of the JFIF extension APP0 segments.
+pub(crate) const JFXX_SIGNATURE: &[u8] = b"JFXX\0";
+
+/// Signature of the APP2 segments which contain pieces of the ICC profile.
+pub(crate) const ICC_PROFILE_SIGNATURE: &[u8] = b"ICC_PROFILE\0";
+
+/// Signature of the APP2 segment which contains the Multi-Picture Format
+/// index, i.e. the locations of the images following the primary image.
+pub(crate) const MPF_SIGNATURE: &[u8] = b"MPF\0";
+
+/// Signature of the APP2 segment which contains ISO 21496-1 gain map
+/// metadata.
+pub(crate) const ISO_GAIN_MAP_SIGNATURE: &[u8] = b"urn:iso:std:iso:ts:21496:-1\0";
+
+/// GUID (32 hex digits), full length (u32) & offset (u32) of the piece.
+const EXTENDED_XMP_HEADER_SIZE: usize = 32 + 4 + 4;
+
+/// Max size of an extended XMP packet, to avoid exhausting memory on
+/// corrupted files.
+const MAX_EXTENDED_XMP_SIZE: usize = 16 * 1024 * 1024;
 
 /// *Deprecated*: Please use [`MediaParser`] + [`MediaSource`] instead.
 ///
@@ -62,6 +99,221 @@ pub(crate) fn extract_exif_data(input: &[u8]) -> IResult<&[u8], Option<&[u8]>> {
     Ok((remain, data))
 }
 
+/// APP1 segments collected from the bytes of a JPEG file, see
+/// [`collect_app1_segments`].
+#[derive(Debug, Default)]
+pub(crate) struct App1Segments<'a> {
+    /// Exif TIFF data pieces in order.
+    ///
+    /// Some encoders split a large Exif block (e.g. with big MakerNotes)
+    /// across multiple consecutive APP1 segments, each of them begins with an
+    /// Exif header. The pieces should be concatenated to get the complete
+    /// TIFF data.
+    pub exif: Vec<&'a [u8]>,
+
+    /// The standard XMP packet.
+    pub xmp: Option<&'a [u8]>,
+
+    /// Photoshop image resource blocks of the APP13 segments in order, large
+    /// resources may be split across several segments.
+    pub photoshop: Vec<&'a [u8]>,
+
+    /// The JFIF APP0 segment
+    pub jfif: Option<&'a [u8]>,
+
+    /// JFIF extension APP0 segments
+    pub jfxx: Vec<&'a [u8]>,
+
+    /// The MPF APP2 segment, which starts with a TIFF header
+    pub mpf: Option<&'a [u8]>,
+
+    /// The ISO 21496-1 gain map metadata APP2 segment
+    pub iso_gain_map: Option<&'a [u8]>,
+
+    extended_xmp: Vec<ExtendedXmpPiece<'a>>,
+
+    /// Pieces of the ICC profile, (sequence number, count, data)
+    icc_profile: Vec<(u8, u8, &'a [u8])>,
+
+    // Whether a non-Exif segment follows the Exif segments
+    exif_ended: bool,
+}
+
+/// A piece of an extended XMP packet, which is split across several APP1
+/// segments since it doesn't fit in one.
+#[derive(Debug)]
+struct ExtendedXmpPiece<'a> {
+    guid: &'a [u8],
+    full_len: u32,
+    offset: u32,
+    data: &'a [u8],
+}
+
+impl<'a> App1Segments<'a> {
+    fn add(&mut self, s: &Segment<'a>) {
+        if s.marker_code == MarkerCode::APP0.code() {
+            if let Some(data) = s.payload.strip_prefix(JFIF_SIGNATURE) {
+                tracing::debug!(size = data.len(), "got JFIF segment");
+                self.jfif.get_or_insert(data);
+            } else if let Some(data) = s.payload.strip_prefix(JFXX_SIGNATURE) {
+                tracing::debug!(size = data.len(), "got JFXX segment");
+                self.jfxx.push(data);
+            }
+        } else if s.marker_code == MarkerCode::APP13.code() {
+            if let Some(data) = s.payload.strip_prefix(PHOTOSHOP_SIGNATURE) {
+                tracing::debug!(size = data.len(), "got Photoshop segment");
+                self.photoshop.push(data);
+            }
+        } else if s.marker_code == MarkerCode::APP2.code() {
+            if let Some([seq, count, data @ ..]) = s.payload.strip_prefix(ICC_PROFILE_SIGNATURE) {
+                tracing::debug!(seq, count, size = data.len(), "got ICC profile segment");
+                self.icc_profile.push((*seq, *count, data));
+            } else if let Some(data) = s.payload.strip_prefix(MPF_SIGNATURE) {
+                tracing::debug!(size = data.len(), "got MPF segment");
+                self.mpf.get_or_insert(data);
+            } else if let Some(data) = s.payload.strip_prefix(ISO_GAIN_MAP_SIGNATURE) {
+                tracing::debug!(size = data.len(), "got ISO 21496-1 segment");
+                self.iso_gain_map.get_or_insert(data);
+            }
+        }
+        if s.marker_code != MarkerCode::APP1.code() {
+            self.exif_ended |= !self.exif.is_empty();
+            return;
+        }
+        if let Some(data) = exif_payload(s) {
+            if !self.exif_ended {
+                tracing::debug!(size = data.len(), "got Exif segment");
+                self.exif.push(data);
+            }
+            return;
+        }
+        self.exif_ended |= !self.exif.is_empty();
+
+        if let Some(data) = s.payload.strip_prefix(XMP_SIGNATURE) {
+            tracing::debug!(size = data.len(), "got XMP segment");
+            self.xmp.get_or_insert(data);
+        } else if let Some(data) = s.payload.strip_prefix(EXTENDED_XMP_SIGNATURE) {
+            let Some((header, data)) = data.split_at_checked(EXTENDED_XMP_HEADER_SIZE) else {
+                tracing::warn!("Ignored: truncated extended XMP segment");
+                return;
+            };
+            let (guid, header) = header.split_at(32);
+            let piece = ExtendedXmpPiece {
+                guid,
+                full_len: u32::from_be_bytes(header[..4].try_into().unwrap()),
+                offset: u32::from_be_bytes(header[4..].try_into().unwrap()),
+                data,
+            };
+            tracing::debug!(piece.offset, size = data.len(), "got extended XMP segment");
+            self.extended_xmp.push(piece);
+        }
+    }
+
+    /// Reassembles the extended XMP packet, which is referenced by the
+    /// `xmpNote:HasExtendedXMP` property (a GUID) of the standard XMP packet.
+    ///
+    /// Returns `None` if there is no extended XMP, or if some pieces of it are
+    /// missing.
+    pub fn extended_xmp(&self) -> Option<Vec<u8>> {
+        let xmp = std::str::from_utf8(self.xmp?).ok()?;
+        let guid = xmp_property(xmp, "xmpNote:HasExtendedXMP")?;
+
+        let mut pieces = self
+            .extended_xmp
+            .iter()
+            .filter(|x| x.guid == guid.as_bytes())
+            .collect::<Vec<_>>();
+        pieces.sort_by_key(|x| x.offset);
+        let full_len = pieces.first()?.full_len as usize;
+        if full_len > MAX_EXTENDED_XMP_SIZE {
+            tracing::warn!(full_len, "Ignored: extended XMP is too big");
+            return None;
+        }
+
+        let mut data = Vec::with_capacity(full_len);
+        for piece in pieces {
+            if piece.full_len as usize != full_len || piece.offset as usize != data.len() {
+                tracing::warn!(?piece, "Ignored: invalid extended XMP piece");
+                return None;
+            }
+            data.extend_from_slice(piece.data);
+        }
+        if data.len() != full_len {
+            tracing::warn!(
+                full_len,
+                len = data.len(),
+                "Ignored: incomplete extended XMP"
+            );
+            return None;
+        }
+        Some(data)
+    }
+
+    /// Reassembles the ICC profile, which is split across APP2 segments
+    /// numbered from 1.
+    ///
+    /// Returns `None` if there is no ICC profile, or if some pieces of it are
+    /// missing.
+    pub fn icc_profile(&self) -> Option<Vec<u8>> {
+        let mut pieces = self.icc_profile.clone();
+        pieces.sort_by_key(|x| x.0);
+        let count = pieces.first()?.1;
+        let complete = pieces.len() == count as usize
+            && pieces
+                .iter()
+                .enumerate()
+                .all(|(i, x)| x.0 as usize == i + 1 && x.1 == count);
+        if !complete {
+            tracing::warn!(count, "Ignored: incomplete ICC profile");
+            return None;
+        }
+        Some(pieces.iter().flat_map(|x| x.2).copied().collect())
+    }
+}
+
+/// Collects Exif & XMP data from the APP1 segments (and JFIF, ICC profile &
+/// Photoshop image resources from the APP0, APP2 & APP13 segments) of a JPEG
+/// file, searching stops at SOS.
+///
+/// Only the Exif segments directly following the first one are treated as
+/// continuations.
+pub(crate) fn collect_app1_segments(input: &[u8]) -> IResult<&[u8], App1Segments<'_>> {
+    let mut segments = App1Segments::default();
+    let mut remain = input;
+    loop {
+        let res: IResult<_, _> = tuple((streaming::tag([0xFF]), number::streaming::u8))(remain);
+        let res = res.and_then(|(rem, (_, code))| parse_segment(code, rem));
+        let (rem, segment) = match res {
+            Ok(x) => x,
+            // Tolerate corrupted data after the Exif segments
+            Err(nom::Err::Error(_) | nom::Err::Failure(_)) if !segments.exif.is_empty() => {
+                tracing::warn!("Ignored: invalid segment after Exif data");
+                break;
+            }
+            Err(e) => return Err(e),
+        };
+        remain = rem;
+        tracing::debug!(
+            marker = format!("0x{:04x}", segment.marker_code),
+            size = format!("0x{:04x}", segment.payload.len()),
+            "got segment"
+        );
+        if segment.marker_code == MarkerCode::Sos.code() {
+            break;
+        }
+        segments.add(&segment);
+    }
+    Ok((remain, segments))
+}
+
+fn exif_payload<'a>(segment: &Segment<'a>) -> Option<&'a [u8]> {
+    if segment.payload_len() > 6 && check_exif_header(segment.payload) {
+        Some(&segment.payload[6..]) // Safe-slice
+    } else {
+        None
+    }
+}
+
 struct Segment<'a> {
     marker_code: u8,
     payload: &'a [u8],
@@ -146,6 +398,10 @@ pub(crate) fn check_jpeg_exif<'a>(input: &'a [u8]) -> IResult<&'a [u8], bool> {
         }
 
         let (rem, size) = number::streaming::be_u16(rem)?;
+        // size contains the two bytes of `size` itself
+        if size < 2 {
+            return fail(rem);
+        }
         if code == MarkerCode::APP1.code() {
             const EXIF_HEADER_SIZE: u16 = 6;
             // size contains the two bytes of `size` itself
@@ -194,6 +450,147 @@ fn parse_segment(marker_code: u8, input: &[u8]) -> IResult<&[u8], Segment<'_>> {
     }
 }
 
+/// Max payload size of a JPEG segment, the 2 bytes of the size field itself
+/// are excluded.
+pub(crate) const MAX_SEGMENT_PAYLOAD: usize = 0xFFFF - 2;
+
+/// Rewrites the segments (before SOS) of the JPEG file in `input` into
+/// `output`.
+///
+/// - Segments for which `keep(marker_code, payload)` returns `false` are
+///   dropped.
+/// - `inserts` (marker code, payload) are inserted in order after SOI and the
+///   leading APP0 (JFIF) segments.
+/// - Everything starting from SOS (the image data & any trailing data, e.g.
+///   MPF images) is copied as is.
+pub(crate) fn rewrite_segments<W: Write>(
+    input: &[u8],
+    output: W,
+    keep: impl FnMut(u8, &[u8]) -> bool,
+    inserts: &[(u8, &[u8])],
+) -> crate::Result<()> {
+    if let Some((_, payload)) = inserts.iter().find(|(_, x)| x.len() > MAX_SEGMENT_PAYLOAD) {
+        return Err(crate::Error::WriteFailed(
+            format!("segment is too large: {} bytes", payload.len()).into(),
+        ));
+    }
+    let (segments, sos) = parse_segments(input)?;
+    write_segments(input, output, &segments, sos, keep, inserts).map_err(crate::Error::IOError)
+}
+
+fn write_segments<W: Write>(
+    input: &[u8],
+    mut output: W,
+    segments: &[SegmentRange],
+    sos: usize,
+    mut keep: impl FnMut(u8, &[u8]) -> bool,
+    inserts: &[(u8, &[u8])],
+) -> std::io::Result<()> {
+    output.write_all(&input[..2])?; // Safe-slice: SOI has been checked
+    let mut inserts = Some(inserts);
+    let mut write_inserts = |output: &mut W| -> std::io::Result<()> {
+        for (code, payload) in inserts.take().unwrap_or_default() {
+            output.write_all(&[0xFF, *code])?;
+            output.write_all(&(payload.len() as u16 + 2).to_be_bytes())?;
+            output.write_all(payload)?;
+        }
+        Ok(())
+    };
+
+    for segment in segments {
+        if segment.code != MarkerCode::APP0.code() {
+            write_inserts(&mut output)?;
+        }
+        if keep(segment.code, &input[segment.payload.clone()]) {
+            // Safe-slice
+            output.write_all(&input[segment.payload.start - 4..segment.payload.end])?;
+        }
+    }
+    write_inserts(&mut output)?;
+    output.write_all(&input[sos..])?; // Safe-slice
+    Ok(())
+}
+
+/// Reads the segments before SOS of a JPEG file from `reader`, returns the
+/// data read, which ends with the SOS marker. The rest of the file (i.e. the
+/// image data) is left in `reader`, so that it can be copied as a stream.
+pub(crate) fn read_segments<R: Read>(reader: &mut R) -> crate::Result<Vec<u8>> {
+    let mut data = vec![0; 2];
+    reader.read_exact(&mut data)?;
+    if data != [0xFF, MarkerCode::Soi.code()] {
+        return Err("invalid JPEG file; SOI marker not found".into());
+    }
+    loop {
+        let mut marker = [0; 2];
+        reader.read_exact(&mut marker)?;
+        data.extend_from_slice(&marker);
+        if marker[0] != 0xFF {
+            return Err("invalid JPEG marker".into());
+        }
+        if marker[1] == MarkerCode::Sos.code() {
+            return Ok(data);
+        }
+        if marker[1] == MarkerCode::Eoi.code() {
+            return Err("invalid JPEG file; SOS marker not found".into());
+        }
+
+        let mut size = [0; 2];
+        reader.read_exact(&mut size)?;
+        data.extend_from_slice(&size);
+        let size = u16::from_be_bytes(size);
+        if size < 2 {
+            return Err(format!("invalid JPEG segment size: {size}").into());
+        }
+        let len = data.len();
+        reader
+            .by_ref()
+            .take(size as u64 - 2)
+            .read_to_end(&mut data)?;
+        if data.len() - len < size as usize - 2 {
+            return Err("invalid JPEG file; segment is truncated".into());
+        }
+    }
+}
+
+/// Position of a segment (before SOS) in a JPEG file.
+pub(crate) struct SegmentRange {
+    pub code: u8,
+    /// Position of the payload in the file, the marker & size fields are
+    /// excluded
+    pub payload: Range<usize>,
+}
+
+/// Parses the segments of the JPEG file in `input`, returns the segments
+/// before SOS & the position of the SOS marker.
+pub(crate) fn parse_segments(input: &[u8]) -> crate::Result<(Vec<SegmentRange>, usize)> {
+    use nom::bytes::complete::{tag, take};
+
+    check_jpeg(input)?;
+    let mut segments = Vec::new();
+    let mut remain = &input[2..]; // Safe-slice: SOI has been checked
+    loop {
+        let pos = input.len() - remain.len();
+        let (rem, (_, code)) = tuple((tag([0xFF]), number::complete::u8))(remain)?;
+        if code == MarkerCode::Sos.code() {
+            return Ok((segments, pos));
+        }
+        if code == MarkerCode::Eoi.code() {
+            return Err("invalid JPEG file; SOS marker not found".into());
+        }
+
+        let (rem, size) = number::complete::be_u16(rem)?;
+        if size < 2 {
+            return Err(format!("invalid JPEG segment size: {size}").into());
+        }
+        let (rem, _) = take(size - 2)(rem)?;
+        segments.push(SegmentRange {
+            code,
+            payload: pos + 4..pos + 2 + size as usize,
+        });
+        remain = rem;
+    }
+}
+
 /// Read all image data after the first SOS marker & before EOI marker.
 ///
 /// The returned data might include several other SOS markers if the image is a
@@ -247,13 +644,22 @@ fn read_image_data<T: Read + Seek>(mut reader: T) -> crate::Result<Vec<u8>> {
 }
 
 /// A marker code is a byte following 0xFF that indicates the kind of marker.
-enum MarkerCode {
+pub(crate) enum MarkerCode {
     // Start of Image
     Soi = 0xD8,
 
+    // APP0 marker
+    APP0 = 0xE0,
+
     // APP1 marker
     APP1 = 0xE1,
 
+    // APP2 marker
+    APP2 = 0xE2,
+
+    // APP13 marker
+    APP13 = 0xED,
+
     // Start of Scan
     Sos = 0xDA,
 
@@ -262,7 +668,7 @@ enum MarkerCode {
 }
 
 impl MarkerCode {
-    fn code(self) -> u8 {
+    pub(crate) fn code(self) -> u8 {
         self as u8
     }
 }
@@ -274,6 +680,17 @@ mod tests {
     use crate::testkit::*;
     use test_case::test_case;
 
+    #[test]
+    fn short_app1_segment() {
+        // APP1 segment with a 2 bytes payload, followed by an Exif segment
+        let mut data = vec![0xFF, 0xD8, 0xFF, 0xE1, 0x00, 0x04, b'E', b'x'];
+        let exif = read_sample("exif.jpg").unwrap();
+        data.extend_from_slice(&exif[2..]);
+
+        let (_, found) = extract_exif_data(&data).unwrap();
+        assert!(found.is_some());
+    }
+
     #[test_case("exif.jpg", true)]
     #[test_case("broken.jpg", true)]
     #[test_case("no-exif.jpg", false)]
@@ -283,6 +700,137 @@ mod tests {
         assert_eq!(ok, has_exif);
     }
 
+    #[test_case("exif.jpg", 1)]
+    #[test_case("broken.jpg", 1)]
+    #[test_case("no-exif.jpg", 0)]
+    fn exif_segments(path: &str, num: usize) {
+        let data = read_sample(path).unwrap();
+        let (_, segments) = collect_app1_segments(&data).unwrap();
+        let pieces = segments.exif;
+        assert_eq!(pieces.len(), num);
+        let (_, first) = extract_exif_data(&data).unwrap();
+        assert_eq!(pieces.first().copied(), first);
+    }
+
+    #[test]
+    fn exif_multi_segments() {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let data = read_sample("exif.jpg").unwrap();
+        let (_, tiff) = extract_exif_data(&data).unwrap();
+        let tiff = tiff.unwrap();
+
+        // Split TIFF data into two APP1 segments
+        let (a, b) = tiff.split_at(tiff.len() / 2);
+        let mut buf = vec![0xFF, 0xD8];
+        for piece in [a, b] {
+            buf.extend([0xFF, 0xE1]);
+            buf.extend(((piece.len() + 8) as u16).to_be_bytes());
+            buf.extend(b"Exif\0\0");
+            buf.extend(piece);
+        }
+        buf.extend([0xFF, 0xDA, 0x00, 0x02]);
+
+        let (_, segments) = collect_app1_segments(&buf).unwrap();
+        let pieces = segments.exif;
+        assert_eq!(pieces, [a, b]);
+        assert_eq!(pieces.concat(), tiff);
+
+        let mut parser = MediaParser::new();
+        let ms = MediaSource::unseekable(std::io::Cursor::new(buf)).unwrap();
+        let iter: ExifIter = parser.parse(ms).unwrap();
+        let expect: ExifIter = parser
+            .parse(MediaSource::file(open_sample("exif.jpg").unwrap()).unwrap())
+            .unwrap();
+        assert_eq!(
+            iter.map(|x| format!("{x:?}")).collect::<Vec<_>>(),
+            expect.map(|x| format!("{x:?}")).collect::<Vec<_>>()
+        );
+    }
+
+    fn app1_segment(payload: &[&[u8]]) -> Vec<u8> {
+        let len: usize = payload.iter().map(|x| x.len()).sum();
+        let mut data = vec![0xFF, 0xE1];
+        data.extend(((len + 2) as u16).to_be_bytes());
+        payload.iter().for_each(|x| data.extend(*x));
+        data
+    }
+
+    fn extended_xmp_segment(guid: &str, full: &[u8], offset: usize, len: usize) -> Vec<u8> {
+        app1_segment(&[
+            EXTENDED_XMP_SIGNATURE,
+            guid.as_bytes(),
+            &(full.len() as u32).to_be_bytes(),
+            &(offset as u32).to_be_bytes(),
+            &full[offset..offset + len],
+        ])
+    }
+
+    const GUID: &str = "2B3B9F4A34E80F3E8F69D6E4A5D58C1C";
+
+    fn jpeg_with_xmp(pieces: &[(usize, usize)]) -> (Vec<u8>, String) {
+        let xmp = format!(
+            r#"<x:xmpmeta xmlns:x="adobe:ns:meta/"><rdf:RDF><rdf:Description
+ xmlns:xmpNote="http://ns.adobe.com/xmp/note/" xmpNote:HasExtendedXMP="{GUID}"/>
+</rdf:RDF></x:xmpmeta>"#
+        );
+        let extended = format!(
+            r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">{}</x:xmpmeta>"#,
+            "x".repeat(100)
+        );
+
+        let exif = read_sample("exif.jpg").unwrap();
+        let (_, tiff) = extract_exif_data(&exif).unwrap();
+
+        let mut data = vec![0xFF, 0xD8];
+        data.extend(app1_segment(&[b"Exif\0\0", tiff.unwrap()]));
+        data.extend(app1_segment(&[XMP_SIGNATURE, xmp.as_bytes()]));
+        for (offset, len) in pieces {
+            data.extend(extended_xmp_segment(
+                GUID,
+                extended.as_bytes(),
+                *offset,
+                *len,
+            ));
+        }
+        // A piece of another extended XMP
+        data.extend(extended_xmp_segment(
+            "00000000000000000000000000000000",
+            extended.as_bytes(),
+            0,
+            10,
+        ));
+        data.extend([0xFF, 0xDA, 0x00, 0x02]);
+        (data, extended)
+    }
+
+    #[test_case(&[(0, 60), (60, 88)], true; "in order")]
+    #[test_case(&[(60, 88), (0, 60)], true; "out of order")]
+    #[test_case(&[(0, 60)], false; "missing piece")]
+    #[test_case(&[(0, 60), (50, 98)], false; "overlapped")]
+    fn extended_xmp(pieces: &[(usize, usize)], complete: bool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let (data, extended) = jpeg_with_xmp(pieces);
+        let (_, segments) = collect_app1_segments(&data).unwrap();
+        assert_eq!(segments.exif.len(), 1);
+        assert!(segments.xmp.is_some());
+        assert_eq!(
+            segments.extended_xmp(),
+            complete.then(|| extended.as_bytes().to_vec())
+        );
+
+        let mut parser = MediaParser::new();
+        let ms = MediaSource::unseekable(std::io::Cursor::new(data)).unwrap();
+        let iter: ExifIter = parser.parse(ms).unwrap();
+        assert!(iter.xmp().unwrap().contains(GUID));
+        assert_eq!(iter.extended_xmp(), complete.then_some(extended.as_str()));
+
+        let exif: Exif = iter.into();
+        assert!(exif.xmp().is_some());
+        assert_eq!(exif.get(Make).and_then(|x| x.as_str()), Some("vivo"));
+    }
+
     #[test_case("exif.jpg")]
     #[allow(deprecated)]
     fn jpeg(path: &str) {
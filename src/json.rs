@@ -0,0 +1,195 @@
+//! Renders parsed metadata as JSON objects in the style of `exiftool -json`,
+//! see [`ExifIter::to_json`](crate::ExifIter::to_json) &
+//! [`TrackInfo::to_json`](crate::TrackInfo::to_json).
+
+use std::fmt::Write;
+
+use crate::EntryValue;
+
+/// Builds a JSON object line by line, the first value of a key wins, the same
+/// way as exiftool reports duplicated tags (e.g. of ifd0 & ifd1).
+#[derive(Debug, Default)]
+pub(crate) struct JsonObject {
+    keys: Vec<String>,
+    lines: Vec<String>,
+}
+
+impl JsonObject {
+    /// Adds `value` with the `key` if the key hasn't been added yet.
+    pub(crate) fn insert(&mut self, key: &str, value: &EntryValue) {
+        if self.keys.iter().any(|x| x == key) {
+            return;
+        }
+        self.keys.push(key.to_owned());
+        self.lines
+            .push(format!("  {}: {}", quote(key), exiftool_value(value)));
+    }
+
+    /// Renders the object, one key per line.
+    pub(crate) fn finish(self) -> String {
+        if self.lines.is_empty() {
+            return "{}".to_owned();
+        }
+        format!("{{\n{}\n}}", self.lines.join(",\n"))
+    }
+}
+
+/// Renders `value` as a JSON value the way exiftool prints it: numbers as
+/// JSON numbers, times as "YYYY:MM:DD HH:MM:SS", arrays separated by spaces,
+/// and binary data as a placeholder.
+pub(crate) fn exiftool_value(value: &EntryValue) -> String {
+    fn join<T>(values: &[T], f: impl Fn(&T) -> String) -> String {
+        if let [x] = values {
+            return f(x);
+        }
+        let s = values.iter().map(f).collect::<Vec<_>>().join(" ");
+        quote(&s)
+    }
+    let rational = |n: f64, d: f64| {
+        if d == 0.0 {
+            quote(if n == 0.0 { "undef" } else { "inf" })
+        } else {
+            float(n / d)
+        }
+    };
+
+    match value {
+        EntryValue::Text(s) => quote(s.trim_end()),
+        EntryValue::Time(t) => quote(&t.format("%Y:%m:%d %H:%M:%S").to_string()),
+        EntryValue::URational(x) => rational(x.0.into(), x.1.into()),
+        EntryValue::IRational(x) => rational(x.0.into(), x.1.into()),
+        EntryValue::U8(x) => x.to_string(),
+        EntryValue::U16(x) => x.to_string(),
+        EntryValue::U32(x) => x.to_string(),
+        EntryValue::U64(x) => x.to_string(),
+        EntryValue::I8(x) => x.to_string(),
+        EntryValue::I16(x) => x.to_string(),
+        EntryValue::I32(x) => x.to_string(),
+        EntryValue::I64(x) => x.to_string(),
+        EntryValue::F32(x) => float((*x).into()),
+        EntryValue::F64(x) => float(*x),
+        EntryValue::Undefined(x) => quote(&undefined_text(x)),
+        EntryValue::URationalArray(x) => join(x, |x| unquote(rational(x.0.into(), x.1.into()))),
+        EntryValue::IRationalArray(x) => join(x, |x| unquote(rational(x.0.into(), x.1.into()))),
+        EntryValue::U8Array(x) => join(x, |x| x.to_string()),
+        EntryValue::U16Array(x) => join(x, |x| x.to_string()),
+        EntryValue::U32Array(x) => join(x, |x| x.to_string()),
+        EntryValue::I8Array(x) => join(x, |x| x.to_string()),
+        EntryValue::I16Array(x) => join(x, |x| x.to_string()),
+        EntryValue::I32Array(x) => join(x, |x| x.to_string()),
+        EntryValue::F32Array(x) => join(x, |x| unquote(float((*x).into()))),
+        EntryValue::F64Array(x) => join(x, |x| unquote(float(*x))),
+    }
+}
+
+/// Renders undefined bytes as text if they are printable ASCII, or as a
+/// placeholder otherwise.
+pub(crate) fn undefined_text(x: &[u8]) -> String {
+    let text = x.strip_suffix(&[0]).unwrap_or(x);
+    match std::str::from_utf8(text) {
+        Ok(s) if !s.is_empty() && s.chars().all(|c| c == ' ' || c.is_ascii_graphic()) => {
+            s.trim_end().to_owned()
+        }
+        _ => format!("(Binary data {} bytes, use -b option to extract)", x.len()),
+    }
+}
+
+/// Renders a float in its shortest form, e.g. `1.8`, `72`; non-finite
+/// values are quoted since JSON has no representation for them.
+pub(crate) fn float(x: f64) -> String {
+    if x.is_finite() {
+        // Drop the noise of binary floats, e.g. 0.30000000000000004
+        let x = format!("{x:.10}").parse::<f64>().unwrap_or(x);
+        x.to_string()
+    } else {
+        quote(&x.to_string())
+    }
+}
+
+pub(crate) fn unquote(s: String) -> String {
+    s.trim_matches('"').to_owned()
+}
+
+/// Quotes & escapes `s` as a JSON string.
+pub(crate) fn quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testkit::open_sample, ExifIter, MediaParser, MediaSource, TrackInfo};
+    use test_case::test_case;
+
+    #[test_case("vivo ".into(), r#""vivo""#)]
+    #[test_case("a\"b\\\n".into(), r#""a\"b\\""#)]
+    #[test_case(EntryValue::URational((1, 250).into()), "0.004")]
+    #[test_case(EntryValue::URational((72, 1).into()), "72")]
+    #[test_case(EntryValue::URational((0, 0).into()), r#""undef""#)]
+    #[test_case(EntryValue::IRational((-1, 3).into()), "-0.3333333333")]
+    #[test_case(EntryValue::U8Array(vec![2, 3, 0, 0]), r#""2 3 0 0""#)]
+    #[test_case(EntryValue::U16Array(vec![8]), "8")]
+    #[test_case(EntryValue::F64(f64::NAN), r#""NaN""#)]
+    #[test_case(EntryValue::Undefined(b"0232".to_vec()), r#""0232""#)]
+    #[test_case(EntryValue::Undefined(vec![1, 2, 3]), r#""(Binary data 3 bytes, use -b option to extract)""#)]
+    fn exiftool_value(value: EntryValue, expect: &str) {
+        assert_eq!(super::exiftool_value(&value), expect);
+    }
+
+    #[test]
+    fn json_object() {
+        let mut obj = JsonObject::default();
+        assert_eq!(JsonObject::default().finish(), "{}");
+        obj.insert("Make", &"vivo".into());
+        obj.insert("ImageWidth", &EntryValue::U32(4096));
+        obj.insert("Make", &"other".into());
+        assert_eq!(
+            obj.finish(),
+            "{\n  \"Make\": \"vivo\",\n  \"ImageWidth\": 4096\n}"
+        );
+    }
+
+    #[test_case("exif.jpg")]
+    #[test_case("exif.heic")]
+    #[test_case("meta.mov")]
+    #[test_case("mkv_640x360.mkv")]
+    fn to_json(path: &str) {
+        let ms = MediaSource::file(open_sample(path).unwrap()).unwrap();
+        let mut parser = MediaParser::new();
+        let json = if ms.has_exif() {
+            let iter: ExifIter = parser.parse(ms).unwrap();
+            iter.to_json()
+        } else {
+            let info: TrackInfo = parser.parse(ms).unwrap();
+            info.to_json()
+        };
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let obj = value.as_object().unwrap();
+        match path {
+            "exif.jpg" => {
+                assert_eq!(obj["Make"], "vivo");
+                assert_eq!(obj["ExposureTime"], 0.009997);
+                assert!(obj.get("ExifOffset").is_none());
+            }
+            "exif.heic" => assert_eq!(obj["Make"], "Apple"),
+            "meta.mov" => assert_eq!(obj["GPSCoordinates"], "+27.1281+100.2508+000.000/"),
+            _ => assert_eq!(obj["Duration"], 13.346),
+        }
+    }
+}
@@ -4,12 +4,22 @@
 //! ## Supported File Types
 //!
 //! - Image
-//!   - *.heic, *.heif, etc.
+//!   - *.heic, *.heif, *.heics (image sequences), *.avif, etc.
 //!   - *.jpg, *.jpeg
-//!   - *.tiff, *.tif
+//!   - *.jp2, *.jpf (JPEG 2000)
+//!   - *.tiff, *.tif, *.dng, *.pef, *.srw
+//!   - *.orf, *.rw2, *.raf: requires `raw_formats` feature
+//!   - *.gif: parsed as [`TrackInfo`], with creation info from XMP
 //! - Video/Audio
 //!   - ISO base media file format (ISOBMFF): *.mp4, *.mov, *.3gp, etc.
 //!   - Matroska based file format: *.webm, *.mkv, *.mka, etc.
+//!   - *.avi (RIFF)
+//!   - *.wav (RIFF, with Broadcast Wave `bext`)
+//!   - *.mp3 (ID3v2)
+//!   - ASF (Windows Media): *.asf, *.wmv, *.wma
+//!   - MPEG transport streams: *.ts, *.m2ts, *.mts
+//!   - *.mxf (Material Exchange Format)
+//!   - DSD audio: *.dsf, *.dff (ID3v2)
 //!
 //! ## Key Features
 //!
@@ -29,6 +39,103 @@
 //!     *iterator* style ([`ExifIter`]) and *get* style ([`Exif`]). The former is
 //!     parse-on-demand, and therefore, more detailed error information can be
 //!     captured; the latter is simpler and easier to use.
+//!
+//!   - Manufacturer specific MakerNotes ([`MakerNote`]), e.g. the lens name &
+//!     image stabilization mode of Canon cameras, the lens ID & shutter count
+//!     of Nikon cameras, the SteadyShot mode of Sony cameras, the Live Photo
+//!     content identifier of iPhones. Fujifilm, Olympus & Panasonic
+//!     MakerNotes require the `makernotes` feature.
+//!
+//!   - XMP metadata as a queryable property tree ([`Xmp`]), which is parsed
+//!     from the same `MediaSource` as Exif or track info, or from standalone
+//!     `.xmp` sidecar files.
+//!
+//!   - IPTC-IIM datasets ([`Iptc`]) of JPEG files, e.g. captions, credits &
+//!     keywords.
+//!
+//!   - Embedded ICC color profiles ([`IccProfile`]) of JPEG, TIFF & HEIF
+//!     images.
+//!
+//!   - JFIF information ([`JfifInfo`]) of JPEG files, e.g. pixel density.
+//!
+//!   - Motion photos ([`MotionPhoto`]), i.e. JPEG images with an embedded
+//!     video (Google & Samsung).
+//!
+//!   - HDR gain maps ([`GainMapInfo`]) of JPEG (Adobe / ISO 21496-1, e.g.
+//!     Ultra HDR) & Apple HEIC photos, including the headroom values.
+//!
+//!   - Exif writing ([`ExifWriter`]): build Exif data from tag/value pairs or
+//!     a parsed [`Exif`], and write it into JPEG, TIFF & HEIF files;
+//!     geotag photos with [`ExifWriter::set_gps_decimal`]; keep unknown tags &
+//!     MakerNotes byte for byte with [`ExifWriter::preserve_unknown`].
+//!
+//!   - Metadata stripping ([`strip_metadata`]): remove Exif, XMP, IPTC and/or
+//!     ICC profiles (or only the GPS info) from JPEG, TIFF, HEIF & MP4 files,
+//!     the rest of the file is kept unchanged.
+//!
+//!   - Timestamp shifting ([`shift_timestamps`]): shift all date/time tags of
+//!     JPEG, TIFF, HEIF & MP4 files by an offset, e.g. to fix a camera's wrong
+//!     clock.
+//!
+//!   - Metadata copying ([`copy_metadata`]): transplant the Exif & XMP data of
+//!     a file (e.g. a RAW file) into a JPEG file.
+//!
+//!   - Orientation rewriting ([`set_orientation`]): change the `Orientation`
+//!     tag of JPEG & TIFF files in place, e.g. after a lossless rotation.
+//!
+//!   - XMP writing ([`Xmp::set`], [`Xmp::merge`], [`Xmp::to_packet`]): edit
+//!     XMP properties (e.g. the rating & keywords), and write the packet into
+//!     JPEG & HEIF files.
+//!
+//!   - In-place patching ([`patch_exif`], [`shift_timestamps_in_place`]):
+//!     update values which fit in their existing slots without rewriting the
+//!     whole file, e.g. for multi-GB videos.
+//!
+//!   - Thumbnail writing ([`remove_thumbnail`], [`replace_thumbnail`]): remove
+//!     or replace the embedded thumbnail (ifd1) of JPEG files.
+//!
+//!   - Video metadata writing ([`TrackWriter`]): write GPS info (`©xyz`), the
+//!     creation time & QuickTime metadata items into MP4/MOV files, `moov` is
+//!     relocated (or padded with `free` boxes) if its size changes.
+//!     [`MatroskaTagWriter`] writes the title, date & custom `SimpleTag`s of
+//!     MKV/WebM files the same way.
+//!
+//!   - JSON export ([`ExifIter::to_json`], [`TrackInfo::to_json`]): render the
+//!     metadata as a JSON object keyed by exiftool tag names with human readable
+//!     values, for pipelines built around `exiftool -json`.
+//!
+//!   - Value interpretation ([`ParsedExifEntry::interpreted_value`]): human
+//!     readable descriptions of enumerated values, e.g. `ExposureProgram` 2 as
+//!     "Normal program", and exiftool style formatting of exposure values, e.g.
+//!     `ExposureTime` as "1/250".
+//!
+//!   - Orientation helpers ([`Orientation`], [`Exif::orientation`]): decode the
+//!     orientation into a rotation & mirroring [`Transform`], and apply it to
+//!     the pixel dimensions, see also [`Exif::display_dimensions`].
+//!
+//!   - Date/time values ([`Exif::date_time`]): combine a time tag with its
+//!     fractional seconds & time zone tags; enable the `time` or `jiff` feature
+//!     to get it as a `time::OffsetDateTime` or a `jiff::Zoned`.
+//!
+//!   - Preview extraction ([`read_previews`]): the embedded thumbnails &
+//!     previews of JPEG, raw (e.g. DNG, NEF, CR2, ARW, RAF) & HEIF files, as
+//!     byte ranges & owned buffers.
+//!
+//!   - Custom tags ([`CustomTag`]): register proprietary tags on a
+//!     `MediaParser` (or an `ExifIter`), so they are reported with names &
+//!     typed values instead of being unrecognized.
+//!
+//!   - CSV/TSV export ([`CsvReport`]): render selected tags (by [`ExifTag`],
+//!     [`TrackInfoTag`] or name) of many files as CSV rows, e.g. for photo audits.
+//!
+//!   - All metadata at once ([`MediaParser::parse_all`]): collect the Exif,
+//!     XMP, ICC, IPTC, JFIF, track & GPS info in a single pass over the file.
+//!
+//!   - Progress reporting ([`MediaParser::on_progress`]): get the bytes read &
+//!     skipped while parsing, e.g. to display the progress of large MOV files.
+//!
+//!   - Format detection ([`detect`]): get the MIME type & container family of
+//!     a file from its first bytes, e.g. to route unsupported files elsewhere.
 //!   
 //! - Performance
 //!
@@ -144,6 +251,9 @@
 //!     
 //!     // From any `Read`:
 //!     // let ms = MediaSource::unseekable(stream)?;
+//!
+//!     // From data in memory, without copying:
+//!     // let ms = MediaSource::from_vec(data)?;
 //!     
 //!     Ok(())
 //! }
@@ -166,6 +276,42 @@
 //!
 //! See [`AsyncMediaSource`] & [`AsyncMediaParser`] for more information.
 //!
+//! ## Raw formats
+//!
+//! Enable `raw_formats` feature flag to parse camera raw files:
+//!
+//! - Olympus ORF (`IIRO`/`MMOR`) and Panasonic RW2 (`IIU\0`) use a modified
+//!   TIFF signature, and are parsed the same way as *.tiff files.
+//! - Fujifilm RAF: Exif data is read from the embedded JPEG.
+//!
+//! ## Serde
+//!
+//! Enable `serde` feature flag to serialize/deserialize the parsed metadata
+//! (e.g. [`Exif`], [`EntryValue`], [`GPSInfo`] & [`TrackInfo`]) with serde, e.g.
+//! into JSON:
+//!
+//! ```rust,ignore
+//! let exif: Exif = iter.into();
+//! let json = serde_json::to_string(&exif)?;
+//! let exif: Exif = serde_json::from_str(&json)?;
+//! ```
+//!
+//! ## Batch scanning
+//!
+//! Enable `batch` feature flag to parse many files in parallel (with
+//! [rayon](https://docs.rs/rayon)), see the `batch` module:
+//!
+//! ```rust,ignore
+//! use rayon::prelude::*;
+//!
+//! batch::scan_dir("./photos").for_each(|(path, res)| match res {
+//!     Ok(batch::MediaInfo::Image(exif)) => index_image(&path, exif),
+//!     Ok(batch::MediaInfo::Track(info)) => index_video(&path, info),
+//!     Ok(_) => (),
+//!     Err(e) => eprintln!("{}: {e}", path.display()),
+//! });
+//! ```
+//!
 //! ## GPS Info
 //!
 //! `ExifIter` provides a convenience method for parsing gps information. (`Exif` &
@@ -303,15 +449,48 @@
 //! ...
 //! ```
 
-pub use parser::{MediaParser, MediaSource};
-pub use video::{TrackInfo, TrackInfoTag};
+pub use cache::{CacheKey, ParseCache};
+pub use copy::copy_metadata;
+pub use dump::{DumpKind, DumpNode};
+pub use ebml::writer::MatroskaTagWriter;
+pub use gain_map::{GainMapInfo, GainMapKind};
+pub use icc::IccProfile;
+pub use iptc::{Iptc, IptcEntry, IptcTag};
+pub use jfif::{DensityUnit, JfifInfo, JfifThumbnail};
+pub use metadata::{Metadata, MetadataIter};
+pub use motion_photo::{MotionPhoto, MotionPhotoKind};
+pub use orientation::{set_orientation, Orientation, Transform};
+pub use pano::PanoInfo;
+pub use parser::{MediaParser, MediaParserBuilder, MediaSource};
+pub use parser_core::Progress;
+pub use patch::patch_exif;
+pub use preview::{read_previews, PreviewImage, PreviewKind};
+pub use report::{CsvReport, ReportColumn};
+pub use telemetry::{GpsSample, ImuSample, Telemetry};
+pub use thumbnail::{remove_thumbnail, replace_thumbnail};
+pub use track_writer::TrackWriter;
+pub use video::{CaptureMode, Chapter, TrackInfo, TrackInfoTag};
+pub use xmp::{Xmp, XmpArrayKind, XmpProperty, XmpValue};
 
 #[cfg(feature = "async")]
 pub use parser_async::{AsyncMediaParser, AsyncMediaSource};
 
-pub use exif::{Exif, ExifIter, ExifTag, GPSInfo, LatLng, ParsedExifEntry};
+pub use exif::{
+    parse_exif_bytes, scan_exif, AppleMakerNote, CanonCameraSettings, CanonExposureMode,
+    CanonMakerNote, CanonShotInfo, Composites, CustomTag, DngKind, Exif, ExifIter, ExifTag,
+    ExifWriter, GPSInfo, GeoKey, GeoKeyValue, GeoTiffInfo, IfdGroup, ImageStabilization, LatLng,
+    MakerNote, NikonLensData, NikonMakerNote, ParsedExifEntry, RawEntry, SonyLensSpec,
+    SonyMakerNote, TextEncoding, Tiepoint, UnknownTag,
+};
+pub use shift::{shift_timestamps, shift_timestamps_in_place};
+pub use strip::{strip_metadata, StripPolicy};
 pub use values::{EntryValue, IRational, URational};
 
+#[cfg(feature = "makernotes")]
+pub use exif::{
+    FilmSimulation, FilterColor, FujifilmMakerNote, OlympusMakerNote, PanasonicMakerNote,
+};
+
 #[allow(deprecated)]
 pub use exif::parse_exif;
 #[cfg(feature = "async")]
@@ -329,28 +508,66 @@ pub(crate) use skip::{Seekable, Unseekable};
 
 #[allow(deprecated)]
 pub use file::FileFormat;
+pub use file::{detect, MediaFamily, MediaType};
 
 #[allow(deprecated)]
 pub use mov::{parse_metadata, parse_mov_metadata};
 
+mod asf;
+mod avi;
+#[cfg(feature = "batch")]
+pub mod batch;
 mod bbox;
+mod binary;
 mod buffer;
+mod cache;
+mod copy;
+mod dsd;
+mod dump;
 mod ebml;
 mod error;
 mod exif;
 mod file;
+mod fingerprint;
+mod gain_map;
+mod gif;
 mod heif;
+mod icc;
+mod id3;
+mod iptc;
+mod jfif;
+mod jp2;
 mod jpeg;
+mod json;
 mod loader;
+mod metadata;
+mod motion_photo;
 mod mov;
+mod mpegts;
+mod mxf;
+mod orientation;
+mod pano;
 mod parser;
 #[cfg(feature = "async")]
 mod parser_async;
+mod parser_core;
 mod partial_vec;
+mod patch;
+mod preview;
+mod raf;
+mod report;
+mod riff;
+mod shift;
 mod skip;
 mod slice;
+mod strip;
+mod telemetry;
+mod thumbnail;
+mod track_writer;
 mod values;
 mod video;
+mod wav;
+mod xmp;
 
 #[cfg(test)]
 mod testkit;
@@ -0,0 +1,151 @@
+use std::sync::Arc;
+
+use crate::{
+    error::ParsedError,
+    exif::parse_loaded_exif_iter,
+    file::{Mime, MimeImage},
+    icc::extract_icc_profile,
+    iptc::extract_iptc,
+    jfif::extract_jfif,
+    parser_core::{icc_mime, parse_loaded, xmp_mime, Limits},
+    xmp::extract_xmp,
+    CustomTag, ExifIter, GPSInfo, IccProfile, Iptc, JfifInfo, TrackInfo, Xmp,
+};
+
+/// A kind of metadata found by
+/// [`MediaParser::parse_all`](crate::MediaParser::parse_all).
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+#[allow(clippy::large_enum_variant)]
+pub enum Metadata {
+    /// Exif data of an image
+    Exif(ExifIter),
+
+    /// XMP packets of an image, or of an XMP sidecar file
+    Xmp(Xmp),
+
+    /// ICC profile embedded in an image
+    Icc(IccProfile),
+
+    /// IPTC data of a JPEG file
+    Iptc(Iptc),
+
+    /// JFIF info of a JPEG file
+    Jfif(JfifInfo),
+
+    /// Track info of a video/audio file
+    Track(TrackInfo),
+
+    /// GPS info, from the Exif data of an image, or the track info of a
+    /// video
+    Gps(GPSInfo),
+}
+
+/// An iterator over all kinds of metadata found in a media source, see
+/// [`MediaParser::parse_all`](crate::MediaParser::parse_all).
+///
+/// The items are yielded in the order of the [`Metadata`] variants, each
+/// kind at most once.
+#[derive(Debug, Clone)]
+pub struct MetadataIter {
+    items: std::vec::IntoIter<Metadata>,
+}
+
+impl Iterator for MetadataIter {
+    type Item = Metadata;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.items.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.items.size_hint()
+    }
+}
+
+impl MetadataIter {
+    pub(crate) fn from_track_info(info: TrackInfo) -> Self {
+        let gps = info.get_gps_info().cloned();
+        let items = std::iter::once(Metadata::Track(info))
+            .chain(gps.map(Metadata::Gps))
+            .collect::<Vec<_>>();
+        Self {
+            items: items.into_iter(),
+        }
+    }
+
+    /// Collects the metadata of an image (or an XMP sidecar file), `data`
+    /// holds the whole file. Kinds which are not found, or fail to parse, are
+    /// skipped.
+    pub(crate) fn from_loaded(
+        mime: Mime,
+        data: bytes::Bytes,
+        custom_tags: Arc<Vec<CustomTag>>,
+        limits: Limits,
+    ) -> Self {
+        let mut items = Vec::new();
+        let mut gps = None;
+
+        if let Mime::Image(img) = mime {
+            match parse_loaded_exif_iter(img, &data) {
+                Ok(mut iter) => {
+                    iter.custom_tags = custom_tags;
+                    iter.limits = limits;
+                    gps = iter.parse_gps_info().ok().flatten();
+                    items.push(Metadata::Exif(iter));
+                }
+                Err(e) => tracing::debug!(?e, "Exif not collected"),
+            }
+        }
+
+        if let Ok(mime) = xmp_mime(mime) {
+            let xmp = found(parse_loaded(&data, |data, state| {
+                extract_xmp(mime, data, state)
+            }))
+            .and_then(|x| x.map(|x| Xmp::from_packets(&x)).transpose());
+            push(&mut items, "XMP", xmp, Metadata::Xmp);
+        }
+
+        if let Ok(mime) = icc_mime(mime) {
+            let icc = found(parse_loaded(&data, |data, state| {
+                extract_icc_profile(mime, data, state)
+            }))
+            .and_then(|x| x.map(IccProfile::new).transpose());
+            push(&mut items, "ICC profile", icc, Metadata::Icc);
+        }
+
+        if mime == Mime::Image(MimeImage::Jpeg) {
+            let iptc = found(parse_loaded(&data, |data, _| extract_iptc(data)));
+            push(&mut items, "IPTC", iptc, Metadata::Iptc);
+            let jfif = found(parse_loaded(&data, |data, _| extract_jfif(data)));
+            push(&mut items, "JFIF", jfif, Metadata::Jfif);
+        }
+
+        items.extend(gps.map(Metadata::Gps));
+        Self {
+            items: items.into_iter(),
+        }
+    }
+}
+
+/// Treats reaching the end of the data as not found.
+fn found<O>(res: Result<(Option<O>, usize), ParsedError>) -> crate::Result<Option<O>> {
+    match res {
+        Ok((x, _)) => Ok(x),
+        Err(e) if e.is_eof() => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn push<O>(
+    items: &mut Vec<Metadata>,
+    kind: &str,
+    res: crate::Result<Option<O>>,
+    f: impl FnOnce(O) -> Metadata,
+) {
+    match res {
+        Ok(Some(x)) => items.push(f(x)),
+        Ok(None) => (),
+        Err(e) => tracing::warn!(kind, ?e, "parse failed, skip it"),
+    }
+}
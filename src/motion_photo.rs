@@ -0,0 +1,452 @@
+use std::{
+    io::{self, Read, Seek, SeekFrom},
+    ops::Range,
+};
+
+use nom::FindSubstring;
+
+use crate::{
+    error::{nom_error_to_parsing_error_with_state, ParsingErrorState},
+    exif::jpeg_xmp,
+    jpeg, TrackInfo, Xmp,
+};
+
+/// Google camera namespace (`GCamera:`)
+const NS_GCAMERA: &str = "http://ns.google.com/photos/1.0/camera/";
+/// Google container namespace (`Container:`), items are in the
+/// `http://ns.google.com/photos/1.0/container/item/` namespace
+pub(crate) const NS_CONTAINER: &str = "http://ns.google.com/photos/1.0/container/";
+
+/// Samsung writes this marker right before the embedded video.
+const SAMSUNG_MARKER: &[u8] = b"MotionPhoto_Data";
+
+const SCAN_CHUNK_SIZE: usize = 64 * 1024;
+
+/// A motion photo (Google "Motion Photo" / "MicroVideo", or Samsung "Motion
+/// Photo"), i.e. a JPEG image with a short MP4 video appended after it.
+///
+/// `MotionPhoto` can be parsed from a seekable JPEG source by a
+/// [`MediaParser`](crate::MediaParser). Use
+/// [`MediaParser::parse_motion_photo`](crate::MediaParser::parse_motion_photo)
+/// to parse the [`TrackInfo`] of the embedded video in the same call.
+///
+/// ```rust
+/// use nom_exif::*;
+///
+/// let mut parser = MediaParser::new();
+/// let ms = MediaSource::file_path("./testdata/exif.jpg").unwrap();
+/// let photo: MotionPhoto = parser.parse(ms).unwrap();
+///
+/// assert!(!photo.is_motion_photo());
+/// assert!(photo.video_range().is_none());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MotionPhoto {
+    kind: Option<MotionPhotoKind>,
+    video_range: Option<Range<u64>>,
+    presentation_timestamp_us: Option<i64>,
+    pub(crate) track_info: Option<TrackInfo>,
+}
+
+/// How a [`MotionPhoto`] is marked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MotionPhotoKind {
+    /// `GCamera:MotionPhoto` XMP property, with the video described by the
+    /// `Container:Directory`
+    MotionPhoto,
+    /// Legacy `GCamera:MicroVideo` XMP property, with the video located by
+    /// `GCamera:MicroVideoOffset`
+    MicroVideo,
+    /// Samsung's `MotionPhoto_Data` marker before the video
+    Samsung,
+}
+
+impl MotionPhoto {
+    /// Whether the image is marked as a motion photo, by XMP properties or by
+    /// the Samsung marker.
+    pub fn is_motion_photo(&self) -> bool {
+        self.kind.is_some()
+    }
+
+    pub fn kind(&self) -> Option<MotionPhotoKind> {
+        self.kind
+    }
+
+    /// Byte range of the embedded MP4 video in the file, `None` if it's not
+    /// found.
+    pub fn video_range(&self) -> Option<Range<u64>> {
+        self.video_range.clone()
+    }
+
+    /// Timestamp (in microseconds) of the video frame corresponding to the
+    /// still image, -1 means unspecified.
+    pub fn presentation_timestamp_us(&self) -> Option<i64> {
+        self.presentation_timestamp_us
+    }
+
+    /// Track info of the embedded video, only available if it's parsed by
+    /// [`MediaParser::parse_motion_photo`](crate::MediaParser::parse_motion_photo).
+    pub fn track_info(&self) -> Option<&TrackInfo> {
+        self.track_info.as_ref()
+    }
+}
+
+/// Motion photo markers found in the XMP of a JPEG file.
+#[derive(Debug, Default)]
+pub(crate) struct MotionPhotoMarkers {
+    kind: Option<MotionPhotoKind>,
+    /// Distance from the start of the video to the end of the file
+    video_offset: Option<u64>,
+    presentation_timestamp_us: Option<i64>,
+}
+
+impl MotionPhotoMarkers {
+    fn from_xmp(xmp: &Xmp) -> MotionPhotoMarkers {
+        let get = |name| xmp.get(NS_GCAMERA, name).and_then(|x| x.as_str());
+        let get_num = |name| get(name).and_then(|x| x.trim().parse::<i64>().ok());
+
+        if get_num("MotionPhoto") == Some(1) {
+            // The video is the last item of the container, with semantic
+            // "MotionPhoto"
+            let items = xmp
+                .get(NS_CONTAINER, "Directory")
+                .and_then(|x| x.as_array())
+                .unwrap_or_default();
+            let video_offset = (1..=items.len())
+                .map(|i| {
+                    let field = |name| {
+                        let path = format!("Directory[{i}]/Item/{name}");
+                        xmp.get(NS_CONTAINER, &path)?.as_str().map(|x| x.trim())
+                    };
+                    (field("Semantic"), field("Length"), field("Padding"))
+                })
+                .filter(|(semantic, ..)| *semantic == Some("MotionPhoto"))
+                .find_map(|(_, len, padding)| {
+                    let padding = padding.and_then(|x| x.parse::<u64>().ok());
+                    len?.parse::<u64>()
+                        .ok()?
+                        .checked_add(padding.unwrap_or_default())
+                });
+            MotionPhotoMarkers {
+                kind: Some(MotionPhotoKind::MotionPhoto),
+                video_offset,
+                presentation_timestamp_us: get_num("MotionPhotoPresentationTimestampUs"),
+            }
+        } else if get_num("MicroVideo") == Some(1) {
+            MotionPhotoMarkers {
+                kind: Some(MotionPhotoKind::MicroVideo),
+                video_offset: get_num("MicroVideoOffset").and_then(|x| u64::try_from(x).ok()),
+                presentation_timestamp_us: get_num("MicroVideoPresentationTimestampUs"),
+            }
+        } else {
+            MotionPhotoMarkers::default()
+        }
+    }
+}
+
+/// Extracts motion photo markers from the XMP of a JPEG file.
+pub(crate) fn extract_motion_photo_markers(
+    input: &[u8],
+) -> Result<MotionPhotoMarkers, ParsingErrorState> {
+    let (_, segments) = jpeg::collect_app1_segments(input)
+        .map_err(|e| nom_error_to_parsing_error_with_state(e, None))?;
+    let xmp = jpeg_xmp(&segments).and_then(|x| match Xmp::from_packets(&x) {
+        Ok(xmp) => Some(xmp),
+        Err(e) => {
+            tracing::warn!(?e, "Ignored: invalid XMP");
+            None
+        }
+    });
+    Ok(xmp
+        .map(|x| MotionPhotoMarkers::from_xmp(&x))
+        .unwrap_or_default())
+}
+
+/// Locates the embedded video of a motion photo, by the offset in XMP, or by
+/// searching the Samsung marker.
+pub(crate) fn locate_motion_photo_video<R: Read + Seek>(
+    reader: &mut R,
+    markers: MotionPhotoMarkers,
+) -> crate::Result<MotionPhoto> {
+    let file_len = reader.seek(SeekFrom::End(0))?;
+    let mut kind = markers.kind;
+    let mut start = markers.video_offset.and_then(|x| file_len.checked_sub(x));
+
+    if start.is_none() {
+        reader.seek(SeekFrom::Start(0))?;
+        let mut scanner = MarkerScanner::default();
+        let mut buf = vec![0; SCAN_CHUNK_SIZE];
+        while start.is_none() {
+            let n = read_full(reader, &mut buf)?;
+            if n == 0 {
+                break;
+            }
+            start = scanner.scan(&buf[..n]);
+        }
+        if start.is_some() {
+            kind.get_or_insert(MotionPhotoKind::Samsung);
+        }
+    }
+
+    let mut video_range = None;
+    if let Some(start) = start {
+        let mut pos = start;
+        let mut header = [0; 16];
+        loop {
+            let len = header.len().min((file_len - pos) as usize);
+            reader.seek(SeekFrom::Start(pos))?;
+            reader.read_exact(&mut header[..len])?;
+            match next_box(&header[..len], pos == start, file_len - pos) {
+                Some(size) => pos += size,
+                None => break,
+            }
+        }
+        video_range = (pos > start).then_some(start..pos);
+    }
+    if video_range.is_none() && kind.is_some() {
+        tracing::warn!(?kind, ?start, "motion photo video not found");
+    }
+
+    Ok(MotionPhoto {
+        kind,
+        video_range,
+        presentation_timestamp_us: markers.presentation_timestamp_us,
+        track_info: None,
+    })
+}
+
+/// `async` version of [`locate_motion_photo_video`].
+#[cfg(feature = "async")]
+pub(crate) async fn locate_motion_photo_video_async<R>(
+    reader: &mut R,
+    markers: MotionPhotoMarkers,
+) -> crate::Result<MotionPhoto>
+where
+    R: tokio::io::AsyncRead + tokio::io::AsyncSeek + Unpin,
+{
+    use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+    let file_len = reader.seek(SeekFrom::End(0)).await?;
+    let mut kind = markers.kind;
+    let mut start = markers.video_offset.and_then(|x| file_len.checked_sub(x));
+
+    if start.is_none() {
+        reader.seek(SeekFrom::Start(0)).await?;
+        let mut scanner = MarkerScanner::default();
+        let mut buf = vec![0; SCAN_CHUNK_SIZE];
+        while start.is_none() {
+            let mut n = 0;
+            while n < buf.len() {
+                match reader.read(&mut buf[n..]).await? {
+                    0 => break,
+                    x => n += x,
+                }
+            }
+            if n == 0 {
+                break;
+            }
+            start = scanner.scan(&buf[..n]);
+        }
+        if start.is_some() {
+            kind.get_or_insert(MotionPhotoKind::Samsung);
+        }
+    }
+
+    let mut video_range = None;
+    if let Some(start) = start {
+        let mut pos = start;
+        let mut header = [0; 16];
+        loop {
+            let len = header.len().min((file_len - pos) as usize);
+            reader.seek(SeekFrom::Start(pos)).await?;
+            reader.read_exact(&mut header[..len]).await?;
+            match next_box(&header[..len], pos == start, file_len - pos) {
+                Some(size) => pos += size,
+                None => break,
+            }
+        }
+        video_range = (pos > start).then_some(start..pos);
+    }
+    if video_range.is_none() && kind.is_some() {
+        tracing::warn!(?kind, ?start, "motion photo video not found");
+    }
+
+    Ok(MotionPhoto {
+        kind,
+        video_range,
+        presentation_timestamp_us: markers.presentation_timestamp_us,
+        track_info: None,
+    })
+}
+
+/// Searches the Samsung marker in the chunks of a file, returns the offset
+/// of the video, which directly follows the marker.
+#[derive(Debug, Default)]
+struct MarkerScanner {
+    /// Offset of `tail` in the file
+    offset: u64,
+    /// The end of the previous chunk, in case the marker spans two chunks
+    tail: Vec<u8>,
+}
+
+impl MarkerScanner {
+    fn scan(&mut self, chunk: &[u8]) -> Option<u64> {
+        self.tail.extend_from_slice(chunk);
+        if let Some(pos) = self.tail.as_slice().find_substring(SAMSUNG_MARKER) {
+            return Some(self.offset + (pos + SAMSUNG_MARKER.len()) as u64);
+        }
+        let keep = self.tail.len().min(SAMSUNG_MARKER.len() - 1);
+        let consumed = self.tail.len() - keep;
+        self.tail.drain(..consumed);
+        self.offset += consumed as u64;
+        None
+    }
+}
+
+/// Returns the size of the MP4 box which starts with `header`, `None` if
+/// it's not a valid box (i.e. the end of the video). The first box should be
+/// `ftyp`.
+fn next_box(header: &[u8], first: bool, remain: u64) -> Option<u64> {
+    let size = u32::from_be_bytes(header.get(..4)?.try_into().unwrap()) as u64;
+    let box_type = header.get(4..8)?;
+    if !box_type.iter().all(|x| x.is_ascii_graphic() || *x == b' ')
+        || (first && box_type != b"ftyp")
+    {
+        return None;
+    }
+    let size = match size {
+        0 => remain,
+        1 => u64::from_be_bytes(header.get(8..16)?.try_into().unwrap()),
+        x => x,
+    };
+    (8..=remain).contains(&size).then_some(size)
+}
+
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut n = 0;
+    while n < buf.len() {
+        match reader.read(&mut buf[n..]) {
+            Ok(0) => break,
+            Ok(x) => n += x,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testkit::read_sample, MediaParser, MediaSource, TrackInfoTag};
+    use std::io::Cursor;
+    use test_case::test_case;
+
+    fn google_xmp(micro_video: bool, video_len: usize) -> String {
+        let props = if micro_video {
+            format!(
+                r#"GCamera:MicroVideo="1" GCamera:MicroVideoVersion="1"
+    GCamera:MicroVideoOffset="{video_len}" GCamera:MicroVideoPresentationTimestampUs="1500">"#
+            )
+        } else {
+            format!(
+                r#"GCamera:MotionPhoto="1" GCamera:MotionPhotoVersion="1"
+    GCamera:MotionPhotoPresentationTimestampUs="1500">
+   <Container:Directory><rdf:Seq>
+    <rdf:li rdf:parseType="Resource">
+     <Container:Item Item:Mime="image/jpeg" Item:Semantic="Primary" Item:Length="0"/>
+    </rdf:li>
+    <rdf:li rdf:parseType="Resource">
+     <Container:Item Item:Mime="video/mp4" Item:Semantic="MotionPhoto" Item:Length="{video_len}"/>
+    </rdf:li>
+   </rdf:Seq></Container:Directory>"#
+            )
+        };
+        format!(
+            r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about=""
+    xmlns:GCamera="http://ns.google.com/photos/1.0/camera/"
+    xmlns:Container="http://ns.google.com/photos/1.0/container/"
+    xmlns:Item="http://ns.google.com/photos/1.0/container/item/"
+    {props}
+  </rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>"#
+        )
+    }
+
+    /// Builds a motion photo, returns the data & the video range.
+    fn motion_photo(kind: MotionPhotoKind) -> (Vec<u8>, Range<u64>) {
+        let video = read_sample("meta.mp4").unwrap();
+        let mut data = vec![0xff, 0xd8];
+        if kind != MotionPhotoKind::Samsung {
+            let xmp = google_xmp(kind == MotionPhotoKind::MicroVideo, video.len());
+            let payload = [jpeg::XMP_SIGNATURE, xmp.as_bytes()].concat();
+            data.extend([0xff, 0xe1]);
+            data.extend(((payload.len() + 2) as u16).to_be_bytes());
+            data.extend(payload);
+        }
+        data.extend([0xff, 0xda, 0, 2, 0x12, 0x34, 0xff, 0xd9]);
+        if kind == MotionPhotoKind::Samsung {
+            data.extend(SAMSUNG_MARKER);
+        }
+        let start = data.len() as u64;
+        data.extend(&video);
+        let range = start..data.len() as u64;
+        if kind == MotionPhotoKind::Samsung {
+            // SEF trailer
+            data.extend(b"SEFH\x6b\0\0\0\x01\0\0\0");
+            data.extend(b"\x0c\0\0\0SEFT");
+        }
+        (data, range)
+    }
+
+    #[test_case(MotionPhotoKind::MotionPhoto, Some(1500))]
+    #[test_case(MotionPhotoKind::MicroVideo, Some(1500))]
+    #[test_case(MotionPhotoKind::Samsung, None)]
+    fn locate(kind: MotionPhotoKind, timestamp: Option<i64>) {
+        let (data, range) = motion_photo(kind);
+
+        let mut parser = MediaParser::new();
+        let ms = MediaSource::seekable(Cursor::new(data.clone())).unwrap();
+        let photo: MotionPhoto = parser.parse(ms).unwrap();
+        assert!(photo.is_motion_photo());
+        assert_eq!(photo.kind(), Some(kind));
+        assert_eq!(photo.video_range(), Some(range.clone()));
+        assert_eq!(photo.presentation_timestamp_us(), timestamp);
+        assert!(photo.track_info().is_none());
+
+        let ms = MediaSource::seekable(Cursor::new(data)).unwrap();
+        let photo = parser.parse_motion_photo(ms, true).unwrap();
+        assert_eq!(photo.video_range(), Some(range));
+        let expect: TrackInfo = parser
+            .parse(MediaSource::seekable(Cursor::new(read_sample("meta.mp4").unwrap())).unwrap())
+            .unwrap();
+        assert_eq!(
+            photo.track_info().unwrap().get(TrackInfoTag::CreateDate),
+            expect.get(TrackInfoTag::CreateDate)
+        );
+    }
+
+    #[test]
+    fn marker_across_chunks() {
+        let mut scanner = MarkerScanner::default();
+        let (a, b) = SAMSUNG_MARKER.split_at(5);
+        assert_eq!(scanner.scan(&[&[0; 10], a].concat()), None);
+        assert_eq!(scanner.scan(&[b, &[1; 3]].concat()), Some(10 + 16));
+    }
+
+    #[test]
+    fn not_motion_photo() {
+        let mut parser = MediaParser::new();
+        let ms = MediaSource::file_path("testdata/no-exif.jpg").unwrap();
+        let photo = parser.parse_motion_photo(ms, true).unwrap();
+        assert!(!photo.is_motion_photo());
+        assert!(photo.video_range().is_none() && photo.track_info().is_none());
+
+        let ms = MediaSource::file_path("testdata/meta.mp4").unwrap();
+        let res: crate::Result<MotionPhoto> = parser.parse(ms);
+        res.unwrap_err();
+    }
+}
@@ -4,19 +4,22 @@ use std::{
     ops::Range,
 };
 
-use chrono::DateTime;
+use chrono::{DateTime, Utc};
 use nom::{bytes::streaming, IResult};
 
 #[allow(deprecated)]
 use crate::{
     bbox::{
-        find_box, parse_video_tkhd_in_moov, travel_header, IlstBox, KeysBox, MvhdBox, ParseBox,
+        find_box, find_video_track, parse_fragment_duration, parse_frame_rate, parse_itunes_items,
+        parse_prft, parse_video_tkhd_in_moov, travel_header, BoxHeader, FragmentedTrack, IlstBox,
+        KeysBox, MvhdBox, ParseBox,
     },
     error::ParsingError,
+    id3::parse_id3v2_tag,
     loader::{BufLoader, Load},
     partial_vec::PartialVec,
     skip::Seekable,
-    video::TrackInfoTag,
+    video::{TrackInfo, TrackInfoTag},
     EntryValue, FileFormat,
 };
 
@@ -88,6 +91,9 @@ pub fn parse_metadata<R: Read + Seek>(reader: R) -> crate::Result<Vec<(String, E
 
     let map: BTreeMap<TrackInfoTag, EntryValue> = map_qt_tag_to_video_tag(entries.clone());
     let mut extras = parse_mvhd_tkhd(&moov_body);
+    // Keep the output unchanged for this deprecated API
+    extras.remove(&TrackInfoTag::Rotation);
+    extras.remove(&TrackInfoTag::FrameRate);
 
     const CREATIONDATE_KEY: &str = "com.apple.quicktime.creationdate";
     if map.contains_key(&TrackInfoTag::CreateDate) {
@@ -139,12 +145,14 @@ pub(crate) fn parse_qt(
         }
     };
 
+    let capture_frame_rate = parse_capture_frame_rate(&entries);
     let mut entries: BTreeMap<TrackInfoTag, EntryValue> = map_qt_tag_to_video_tag(entries);
     let extras = parse_mvhd_tkhd(moov_body);
     if entries.contains_key(&TrackInfoTag::CreateDate) {
         entries.remove(&TrackInfoTag::CreateDate);
     }
     entries.extend(extras);
+    apply_capture_frame_rate(&mut entries, capture_frame_rate);
 
     Ok(entries)
 }
@@ -161,9 +169,11 @@ pub(crate) fn parse_mp4(
         }
     };
 
+    let capture_frame_rate = parse_capture_frame_rate(&entries);
     let mut entries: BTreeMap<TrackInfoTag, EntryValue> = map_qt_tag_to_video_tag(entries);
     let extras = parse_mvhd_tkhd(moov_body);
     entries.extend(extras);
+    apply_capture_frame_rate(&mut entries, capture_frame_rate);
 
     // If the GPSInfo doesn't exist, then try to find GPS info from box
     // `moov/udta/©xyz`. For mp4 files, Android phones store GPS info in that
@@ -181,23 +191,104 @@ fn parse_mvhd_tkhd(moov_body: &[u8]) -> BTreeMap<TrackInfoTag, EntryValue> {
     let mut entries = BTreeMap::new();
     if let Ok((_, Some(bbox))) = find_box(moov_body, "mvhd") {
         if let Ok((_, mvhd)) = MvhdBox::parse_box(bbox.data) {
-            entries.insert(TrackInfoTag::DurationMs, mvhd.duration_ms().into());
+            // Fragmented movies may store the duration in `mvex/mehd` only
+            let duration = match parse_fragment_duration(moov_body) {
+                Ok(Some(duration)) if mvhd.duration_ms() == 0 => mvhd.to_ms(duration),
+                _ => mvhd.duration_ms(),
+            };
+            entries.insert(TrackInfoTag::DurationMs, duration.into());
 
-            entries.insert(
-                TrackInfoTag::CreateDate,
-                EntryValue::Time(mvhd.creation_time()),
-            );
+            if let Some(time) = mvhd.creation_time() {
+                entries.insert(TrackInfoTag::CreateDate, EntryValue::Time(time));
+            }
         }
     }
 
     if let Ok(Some(tkhd)) = parse_video_tkhd_in_moov(moov_body) {
         entries.insert(TrackInfoTag::ImageWidth, tkhd.width.into());
         entries.insert(TrackInfoTag::ImageHeight, tkhd.height.into());
+        if tkhd.rotation() != 0 {
+            entries.insert(TrackInfoTag::Rotation, tkhd.rotation().into());
+        }
+    }
+
+    if let Ok(Some(trak)) = find_video_track(moov_body) {
+        if let Ok(Some(rate)) = parse_frame_rate(trak.body_data()) {
+            entries.insert(TrackInfoTag::FrameRate, rate.into());
+        }
     }
 
     entries
 }
 
+/// Where the capture frame rate comes from, see [`parse_capture_frame_rate`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CaptureFrameRate {
+    /// Recorded explicitly, e.g.: Android `com.android.capture.fps`.
+    Recorded(f64),
+
+    /// Apple slow-motion recordings store frames at the capture frame rate,
+    /// and are played back at [`APPLE_SLOW_MOTION_PLAYBACK_RATE`].
+    AppleSlowMotion,
+}
+
+/// Playback frame rate of Apple slow-motion recordings.
+const APPLE_SLOW_MOTION_PLAYBACK_RATE: f64 = 30.0;
+
+/// Finds the capture frame rate in QuickTime metadata keys:
+///
+/// - `com.android.capture.fps`: written by Android for slow-motion &
+///   timelapse recordings.
+///
+/// - `com.apple.quicktime.full-frame-rate-playback-intent`: 0 indicates a
+///   slow-motion recording, which is played back at a reduced frame rate.
+///   Other Apple keys, e.g.
+///   `com.apple.quicktime.camera.framereadouttimeinmicroseconds`, describe
+///   the sensor readout rather than the frame rate, so they are ignored.
+fn parse_capture_frame_rate(entries: &[(String, EntryValue)]) -> Option<CaptureFrameRate> {
+    entries.iter().find_map(|(k, v)| match k.as_str() {
+        "com.android.capture.fps" => v
+            .as_f32()
+            .map(|x| x as f64)
+            .or_else(|| v.as_f64())
+            .filter(|x| x.is_finite() && *x > 0.0)
+            .map(CaptureFrameRate::Recorded),
+        "com.apple.quicktime.full-frame-rate-playback-intent" => {
+            let intent = v.as_u8().map(|x| x as i64).or(match v {
+                EntryValue::I8(x) => Some(*x as i64),
+                EntryValue::I16(x) => Some(*x as i64),
+                EntryValue::I32(x) => Some(*x as i64),
+                EntryValue::I64(x) => Some(*x),
+                _ => None,
+            });
+            (intent == Some(0)).then_some(CaptureFrameRate::AppleSlowMotion)
+        }
+        _ => None,
+    })
+}
+
+fn apply_capture_frame_rate(
+    entries: &mut BTreeMap<TrackInfoTag, EntryValue>,
+    capture_frame_rate: Option<CaptureFrameRate>,
+) {
+    match capture_frame_rate {
+        Some(CaptureFrameRate::Recorded(rate)) => {
+            entries.insert(TrackInfoTag::CaptureFrameRate, rate.into());
+        }
+        Some(CaptureFrameRate::AppleSlowMotion) => {
+            // The frame rate of the track is the capture frame rate
+            if let Some(rate) = entries.remove(&TrackInfoTag::FrameRate) {
+                entries.insert(TrackInfoTag::CaptureFrameRate, rate);
+                entries.insert(
+                    TrackInfoTag::FrameRate,
+                    APPLE_SLOW_MOTION_PLAYBACK_RATE.into(),
+                );
+            }
+        }
+        None => (),
+    }
+}
+
 fn map_qt_tag_to_video_tag(
     entries: Vec<(String, EntryValue)>,
 ) -> BTreeMap<TrackInfoTag, EntryValue> {
@@ -216,6 +307,8 @@ fn map_qt_tag_to_video_tag(
                 Some((TrackInfoTag::Software, v))
             } else if k == "com.apple.quicktime.location.ISO6709" {
                 Some((TrackInfoTag::GpsIso6709, v))
+            } else if k == "com.apple.quicktime.content.identifier" {
+                Some((TrackInfoTag::ContentIdentifier, v))
             } else {
                 None
             }
@@ -244,6 +337,52 @@ fn parse_mp4_gps(moov_body: &[u8]) -> Option<String> {
     None
 }
 
+/// Parses the iTunes-style metadata in `moov/udta/meta/ilst`, e.g. written
+/// by iTunes, ffmpeg & most Android phones. See [`parse_itunes_items`].
+pub(crate) fn parse_itunes_metadata(moov_body: &[u8]) -> Vec<(String, EntryValue)> {
+    let Ok((_, Some(meta))) = find_box(moov_body, "udta/meta") else {
+        return Vec::new();
+    };
+    match find_box(meta_children(meta.body_data()), "ilst") {
+        Ok((_, Some(ilst))) => parse_itunes_items(ilst.body_data()),
+        _ => Vec::new(),
+    }
+}
+
+/// Parses the ID3v2 tag in an `ID32` box, which is stored in `moov/meta` or
+/// `moov/udta/meta`, e.g. by some broadcast MP4 files.
+pub(crate) fn parse_id32_metadata(moov_body: &[u8]) -> Option<TrackInfo> {
+    ["meta", "udta/meta"].into_iter().find_map(|path| {
+        let (_, meta) = find_box(moov_body, path).ok()?;
+        let (_, id32) = find_box(meta_children(meta?.body_data()), "ID32").ok()?;
+        // Skip version, flags & language (pad + 15 bits)
+        let data = id32?.body_data().get(6..)?;
+        parse_id3v2_tag(data)
+    })
+}
+
+/// Returns the child boxes in the body of a `meta` box, which is a fullbox
+/// in MP4 files, but not in some QuickTime files.
+fn meta_children(body: &[u8]) -> &[u8] {
+    match body.get(4..8) {
+        Some(b"hdlr") => body,
+        _ => body.get(4..).unwrap_or_default(),
+    }
+}
+
+/// Returns the `TrackInfoTag` which an iTunes-style metadata item maps to.
+pub(crate) fn itunes_item_tag(key: &str) -> Option<TrackInfoTag> {
+    let tag = match key {
+        "\u{a9}nam" => TrackInfoTag::Title,
+        "\u{a9}ART" => TrackInfoTag::Artist,
+        "\u{a9}alb" => TrackInfoTag::Album,
+        "\u{a9}too" => TrackInfoTag::Software,
+        "\u{a9}cmt" | "desc" => TrackInfoTag::Description,
+        _ => return None,
+    };
+    Some(tag)
+}
+
 /// *Deprecated*: Please use [`crate::MediaParser`] instead.
 ///
 /// Analyze the byte stream in the `reader` as a MOV file, attempting to extract
@@ -323,8 +462,9 @@ pub(crate) fn extract_moov_body_from_buf(input: &[u8]) -> Result<Range<usize>, P
             skipped += h.header_size;
             false
         } else if (remain.len() as u64) < h.body_size() {
-            // stop travelling & skip unused box data
-            to_skip = h.body_size() as usize - remain.len();
+            // stop travelling & skip unused box data, which may be larger
+            // than 4 GB (e.g. `mdat` with a 64-bit `largesize`)
+            to_skip = h.body_size() - remain.len() as u64;
             false
         } else {
             // body has been read, so just consume it
@@ -335,16 +475,149 @@ pub(crate) fn extract_moov_body_from_buf(input: &[u8]) -> Result<Range<usize>, P
     .map_err(|e| convert_error(e, "search atom moov failed"))?;
 
     if to_skip > 0 {
-        return Err(ParsingError::ClearAndSkip(to_skip + input.len()));
+        let n = usize::try_from(to_skip + input.len() as u64)
+            .map_err(|_| ParsingError::Failed("box is too big to skip".into()))?;
+        return Err(ParsingError::ClearAndSkip(n));
     }
 
-    let size: usize = header.body_size().try_into().expect("must fit");
+    let size: usize = header
+        .body_size()
+        .try_into()
+        .map_err(|_| ParsingError::Failed("moov is too big".into()))?;
     let (_, body) =
         streaming::take(size)(remain).map_err(|e| convert_error(e, "moov is too small"))?;
 
     Ok(skipped..skipped + body.len())
 }
 
+/// Max size of a `moof` box which is loaded into memory, to avoid exhausting
+/// memory on corrupted files.
+const MAX_MOOF_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Scans the movie fragments (`moof`) after `moov` in fragmented MP4 files
+/// (e.g. fMP4/CMAF, DASH/HLS segments), whose `moov` contains no samples, and
+/// often no duration either. The media data (`mdat`) is skipped.
+#[derive(Debug)]
+pub(crate) struct FragmentScanner {
+    track: FragmentedTrack,
+    /// Decode time of the first fragment, in the track's time scale units.
+    start: Option<u64>,
+    /// End time of the fragments scanned so far, in the track's time scale
+    /// units.
+    end: u64,
+    /// Wall-clock time of the first `prft` box.
+    produced: Option<DateTime<Utc>>,
+    /// Info parsed from the moov body.
+    info: TrackInfo,
+}
+
+impl FragmentScanner {
+    /// Returns a scanner if `moov_body` belongs to a fragmented movie whose
+    /// duration is unknown. `info` is parsed from `moov_body`.
+    pub(crate) fn new(moov_body: &[u8], info: &TrackInfo) -> Option<FragmentScanner> {
+        if info
+            .get(TrackInfoTag::DurationMs)
+            .is_some_and(|x| x.as_u64().is_some_and(|x| x > 0))
+        {
+            return None;
+        }
+        let track = FragmentedTrack::parse(moov_body)
+            .inspect_err(|e| tracing::warn!(?e, "parse fragmented track failed"))
+            .ok()??;
+        Some(FragmentScanner {
+            track,
+            start: None,
+            end: 0,
+            produced: None,
+            info: info.clone(),
+        })
+    }
+
+    /// Parses `input`, which starts with a top level box after `moov`, or
+    /// where the last call stopped.
+    ///
+    /// Fragments can't be counted in advance, so the scan is done when the
+    /// movie fragment random access box (`mfra`) is reached. Otherwise, the
+    /// input ends before it's done, and [`Self::finish`] should be called.
+    pub(crate) fn parse(&mut self, input: &[u8]) -> Result<TrackInfo, ParsingError> {
+        let mut pos = 0;
+        loop {
+            let remain = &input[pos..];
+            let header = match BoxHeader::parse(remain) {
+                Ok((_, header)) => header,
+                Err(nom::Err::Incomplete(_)) if pos > 0 => {
+                    return Err(ParsingError::ClearAndSkip(pos))
+                }
+                Err(nom::Err::Incomplete(needed)) => {
+                    return Err(ParsingError::Need(match needed {
+                        nom::Needed::Unknown => 1,
+                        nom::Needed::Size(n) => n.get(),
+                    }))
+                }
+                // e.g.: a box which extends to the end of the file (size 0)
+                Err(_) => return Ok(self.finish()),
+            };
+
+            let box_type = header.box_type.as_str();
+            if box_type == "mfra" {
+                return Ok(self.finish());
+            }
+
+            let size = usize::try_from(header.box_size)
+                .map_err(|_| ParsingError::Failed("box is too big to skip".into()))?;
+            if matches!(box_type, "moof" | "prft") && header.box_size <= MAX_MOOF_SIZE {
+                if size > remain.len() {
+                    return Err(if pos > 0 {
+                        ParsingError::ClearAndSkip(pos)
+                    } else {
+                        ParsingError::Need(size - remain.len())
+                    });
+                }
+                self.parse_box(&header, &remain[..size]);
+            } else if size > remain.len() {
+                tracing::debug!(?box_type, size, "skip box");
+                return Err(ParsingError::ClearAndSkip(pos + size));
+            }
+            pos += size;
+        }
+    }
+
+    fn parse_box(&mut self, header: &BoxHeader, data: &[u8]) {
+        if header.box_type == "prft" {
+            match parse_prft(data) {
+                Ok(time) => self.produced = self.produced.or(time),
+                Err(e) => tracing::warn!(?e, "parse prft failed"),
+            }
+            return;
+        }
+
+        match self.track.parse_moof(&data[header.header_size..]) {
+            Ok(Some((time, duration))) => {
+                let start = time.unwrap_or(self.end);
+                self.start = Some(self.start.map_or(start, |x| x.min(start)));
+                self.end = self.end.max(start.saturating_add(duration));
+            }
+            Ok(None) => (),
+            Err(e) => tracing::warn!(?e, "parse moof failed"),
+        }
+    }
+
+    /// Returns the info parsed from `moov`, with the duration of the
+    /// fragments scanned so far.
+    pub(crate) fn finish(&mut self) -> TrackInfo {
+        let mut info = std::mem::take(&mut self.info);
+        let duration = self.end.saturating_sub(self.start.unwrap_or_default());
+        if duration > 0 && self.track.time_scale > 0 {
+            let ms = duration * 1000 / self.track.time_scale as u64;
+            info.put(TrackInfoTag::DurationMs, ms.into());
+        }
+        if let (None, Some(time)) = (info.get(TrackInfoTag::CreateDate), self.produced) {
+            info.put(TrackInfoTag::CreateDate, time.fixed_offset().into());
+        }
+        info
+    }
+}
+
 type EntriesResult<'a> = IResult<&'a [u8], Option<Vec<(String, EntryValue)>>>;
 
 fn parse_moov_body(input: &[u8]) -> EntriesResult {
@@ -352,11 +625,12 @@ fn parse_moov_body(input: &[u8]) -> EntriesResult {
         return Ok((input, None));
     };
 
-    let (_, Some(keys)) = find_box(meta.body_data(), "keys")? else {
+    let children = meta_children(meta.body_data());
+    let (_, Some(keys)) = find_box(children, "keys")? else {
         return Ok((remain, None));
     };
 
-    let (_, Some(ilst)) = find_box(meta.body_data(), "ilst")? else {
+    let (_, Some(ilst)) = find_box(children, "ilst")? else {
         return Ok((remain, None));
     };
 
@@ -408,7 +682,7 @@ fn tz_iso_8601_to_rfc3339(s: String) -> String {
 #[allow(deprecated)]
 mod tests {
     use super::*;
-    use crate::testkit::*;
+    use crate::{testkit::*, CaptureMode, TrackInfo};
     use test_case::test_case;
 
     #[test_case("meta.mov")]
@@ -501,6 +775,239 @@ mod tests {
         );
     }
 
+    #[test_case(&[], 30.0, None, Some(CaptureMode::Normal))]
+    #[test_case(&[("com.android.capture.fps", 240_f32.into())], 30.0, Some(240.0), Some(CaptureMode::SlowMotion))]
+    #[test_case(&[("com.android.capture.fps", 1_f32.into())], 30.0, Some(1.0), Some(CaptureMode::Timelapse))]
+    #[test_case(&[("com.android.capture.fps", 30_f32.into())], 29.97, Some(30.0), Some(CaptureMode::Normal))]
+    #[test_case(&[("com.apple.quicktime.full-frame-rate-playback-intent", 0_u8.into())], 240.0, Some(240.0), Some(CaptureMode::SlowMotion))]
+    #[test_case(&[("com.apple.quicktime.full-frame-rate-playback-intent", 1_u8.into())], 240.0, None, Some(CaptureMode::Normal))]
+    fn capture_mode(
+        entries: &[(&str, EntryValue)],
+        frame_rate: f64,
+        capture_frame_rate: Option<f64>,
+        mode: Option<CaptureMode>,
+    ) {
+        let entries = entries
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.clone()))
+            .collect::<Vec<_>>();
+        let mut map = BTreeMap::from([(TrackInfoTag::FrameRate, frame_rate.into())]);
+        apply_capture_frame_rate(&mut map, parse_capture_frame_rate(&entries));
+
+        let info = TrackInfo::from(map);
+        assert_eq!(
+            info.get(TrackInfoTag::CaptureFrameRate)
+                .and_then(|x| x.as_f64()),
+            capture_frame_rate
+        );
+        assert_eq!(info.capture_mode(), mode);
+    }
+
+    fn be(values: &[u32]) -> Vec<u8> {
+        values.iter().flat_map(|x| x.to_be_bytes()).collect()
+    }
+
+    /// Makes a fragmented MP4 file with a 1280x720 video track (time scale
+    /// 90000), which has `fragments` fragments of 2 seconds, starting at
+    /// `base_time`.
+    ///
+    /// The duration is recorded in `mvex/mehd` if `with_mehd`. `prft` boxes
+    /// are added if `with_prft`, and `mfra` is added if `with_mfra`.
+    fn make_fmp4(
+        fragments: u32,
+        base_time: u32,
+        with_mehd: bool,
+        with_prft: bool,
+        with_mfra: bool,
+    ) -> Vec<u8> {
+        // 2019-02-12T07:27:12Z since 1904
+        let creation_time = if with_prft { 0 } else { 3632801232 };
+        let mvhd = full_box(
+            b"mvhd",
+            &[
+                &be(&[creation_time, creation_time, 1000, 0])[..],
+                &[0; 76],
+                &be(&[2]),
+            ]
+            .concat(),
+        );
+        let matrix = be(&[0x10000, 0, 0, 0, 0x10000, 0, 0, 0, 0x40000000]);
+        let tkhd = full_box_flags(
+            b"tkhd",
+            3,
+            &[
+                &be(&[0, 0, 1, 0, 0, 0, 0, 0, 0])[..],
+                &matrix,
+                &be(&[1280 << 16, 720 << 16]),
+            ]
+            .concat(),
+        );
+        let mdhd = full_box(b"mdhd", &be(&[0, 0, 90000, 0, 0x55c40000]));
+        let hdlr = full_box(b"hdlr", &[&be(&[0])[..], b"vide", &[0; 13]].concat());
+        let trak = bbox(
+            b"trak",
+            &[tkhd, bbox(b"mdia", &[mdhd, hdlr].concat())].concat(),
+        );
+        let mut mvex = vec![full_box(b"trex", &be(&[1, 1, 3000, 0, 0]))];
+        if with_mehd {
+            mvex.insert(0, full_box(b"mehd", &be(&[fragments * 2000])));
+        }
+        let udta = bbox(
+            b"udta",
+            &bbox(
+                b"\xa9xyz",
+                &[&[0, 18, 0x15, 0xc7][..], b"+27.1281+100.2508/"].concat(),
+            ),
+        );
+        let moov = bbox(
+            b"moov",
+            &[mvhd, trak, bbox(b"mvex", &mvex.concat()), udta].concat(),
+        );
+
+        let mut data = [bbox(b"ftyp", b"isom\0\0\0\0isomiso6"), moov].concat();
+        for i in 0..fragments {
+            let time = base_time + i * 180000;
+            if with_prft {
+                // 2023-10-01T08:30:15Z since 1900
+                let ntp = (3905137815_u64 + i as u64 * 2) << 32;
+                let body = [&be(&[1])[..], &ntp.to_be_bytes(), &be(&[time])].concat();
+                data.extend(full_box(b"prft", &body));
+            }
+            // 2 samples with explicit durations
+            let traf = [
+                full_box_flags(b"tfhd", 0x020000, &be(&[1])),
+                full_box(b"tfdt", &be(&[time])),
+                full_box_flags(
+                    b"trun",
+                    0x000301,
+                    &be(&[2, 0, 3003, 100, 180000 - 3003, 100]),
+                ),
+            ];
+            let moof = [
+                full_box(b"mfhd", &be(&[i + 1])),
+                bbox(b"traf", &traf.concat()),
+            ];
+            data.extend(bbox(b"moof", &moof.concat()));
+            data.extend(bbox(b"mdat", &[0; 200]));
+        }
+        if with_mfra {
+            data.extend(bbox(b"mfra", &full_box(b"mfro", &be(&[16]))));
+        }
+        data
+    }
+
+    #[test_case(3, 0, true, false, false, 6000, "2019-02-12T07:27:12+00:00"; "mehd")]
+    #[test_case(3, 0, false, false, true, 6000, "2019-02-12T07:27:12+00:00"; "mfra")]
+    #[test_case(3, 0, false, false, false, 6000, "2019-02-12T07:27:12+00:00"; "eof")]
+    #[test_case(2, 90000 * 3600, false, true, false, 4000, "2023-10-01T08:30:15+00:00"; "prft")]
+    fn fragmented_mp4(
+        fragments: u32,
+        base_time: u32,
+        with_mehd: bool,
+        with_prft: bool,
+        with_mfra: bool,
+        duration: u64,
+        date: &str,
+    ) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let data = make_fmp4(fragments, base_time, with_mehd, with_prft, with_mfra);
+        let mut parser = crate::MediaParser::new();
+        let ms = crate::MediaSource::unseekable(std::io::Cursor::new(data)).unwrap();
+        let info: TrackInfo = parser.parse(ms).unwrap();
+
+        assert_eq!(info.get(TrackInfoTag::DurationMs), Some(&duration.into()));
+        assert_eq!(
+            info.get(TrackInfoTag::CreateDate).unwrap().to_string(),
+            date
+        );
+        assert_eq!(info.get(TrackInfoTag::ImageWidth), Some(&1280_u32.into()));
+        assert_eq!(info.get_gps_info().unwrap().latitude_ref, 'N');
+    }
+
+    #[test]
+    fn itunes_metadata() {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let item = |box_type: &[u8], value: &[u8]| {
+            bbox(
+                box_type,
+                &full_box_flags(b"data", 1, &[&[0; 4][..], value].concat()),
+            )
+        };
+        let ilst = [
+            item(b"\xa9nam", b"Title"),
+            item(b"\xa9ART", b"Artist"),
+            item(b"\xa9day", b"2019"),
+            item(b"\xa9too", b"Lavf58.76.100"),
+        ];
+        let hdlr = full_box(b"hdlr", &[&be(&[0])[..], b"mdirappl", &[0; 9]].concat());
+        let meta = full_box(b"meta", &[hdlr, bbox(b"ilst", &ilst.concat())].concat());
+        let mvhd = full_box(
+            b"mvhd",
+            &[&be(&[0, 0, 1000, 1500])[..], &[0; 76], &be(&[2])].concat(),
+        );
+        let moov = bbox(b"moov", &[mvhd, bbox(b"udta", &meta)].concat());
+        let data = [bbox(b"ftyp", b"M4A \0\0\0\0M4A mp42isom"), moov].concat();
+
+        let mut parser = crate::MediaParser::new();
+        let ms = crate::MediaSource::unseekable(std::io::Cursor::new(data)).unwrap();
+        let info: TrackInfo = parser.parse(ms).unwrap();
+
+        assert_eq!(info.get(TrackInfoTag::Title), Some(&"Title".into()));
+        assert_eq!(info.get(TrackInfoTag::Artist), Some(&"Artist".into()));
+        assert_eq!(
+            info.get(TrackInfoTag::Software),
+            Some(&"Lavf58.76.100".into())
+        );
+        assert_eq!(
+            info.metadata().map(|(k, _)| k).collect::<Vec<_>>(),
+            ["\u{a9}nam", "\u{a9}ART", "\u{a9}day", "\u{a9}too"]
+        );
+    }
+
+    #[test_case(false; "moov meta")]
+    #[test_case(true; "udta meta")]
+    fn id32_metadata(in_udta: bool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        // Language: "und", packed as 3 x 5 bits
+        let id3 = crate::id3::tests::make_mp3(4, 100, false);
+        let id32 = full_box(b"ID32", &[&[0x55, 0xC4][..], &id3].concat());
+        let hdlr = full_box(b"hdlr", &[&be(&[0])[..], b"ID32", &[0; 13]].concat());
+        let title = bbox(
+            b"\xa9nam",
+            &full_box_flags(b"data", 1, &[&[0; 4][..], b"iTunes Title"].concat()),
+        );
+        let ilst = bbox(b"ilst", &title);
+        let (meta, udta_meta) = if in_udta {
+            (Vec::new(), [id32, ilst].concat())
+        } else {
+            (full_box(b"meta", &[hdlr.clone(), id32].concat()), ilst)
+        };
+        let udta = bbox(b"udta", &full_box(b"meta", &[hdlr, udta_meta].concat()));
+        let mvhd = full_box(
+            b"mvhd",
+            &[&be(&[0, 0, 1000, 1500])[..], &[0; 76], &be(&[2])].concat(),
+        );
+        let moov = bbox(b"moov", &[mvhd, meta, udta].concat());
+        let data = [bbox(b"ftyp", b"mp42\0\0\0\0mp42isom"), moov].concat();
+
+        let mut parser = crate::MediaParser::new();
+        let ms = crate::MediaSource::unseekable(std::io::Cursor::new(data)).unwrap();
+        let info: TrackInfo = parser.parse(ms).unwrap();
+
+        // Tags parsed from the movie & iTunes-style metadata take precedence
+        assert_eq!(info.get(TrackInfoTag::Title), Some(&"iTunes Title".into()));
+        assert_eq!(info.get(TrackInfoTag::DurationMs), Some(&1500_u64.into()));
+        assert_eq!(info.get(TrackInfoTag::Artist), Some(&"Artist".into()));
+        assert_eq!(info.get(TrackInfoTag::Album), Some(&"Album".into()));
+        assert_eq!(
+            info.get(TrackInfoTag::CreateDate).unwrap().to_string(),
+            "2019-12-31T23:59:00+00:00"
+        );
+    }
+
     #[test]
     fn test_iso_8601_tz_to_rfc3339() {
         let _ = tracing_subscriber::fmt().with_test_writer().try_init();
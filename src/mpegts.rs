@@ -0,0 +1,653 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, FixedOffset};
+
+use crate::{
+    error::ParsingError,
+    video::{TrackInfo, TrackInfoTag},
+};
+
+const PACKET_SIZE: usize = 188;
+/// M2TS (e.g. AVCHD, Blu-ray) packets have a 4-byte timestamp prefix.
+const M2TS_PACKET_SIZE: usize = 192;
+const SYNC_BYTE: u8 = 0x47;
+
+const PAT_PID: u16 = 0x0000;
+const SDT_PID: u16 = 0x0011;
+const TDT_PID: u16 = 0x0014;
+
+const PAT_TABLE_ID: u8 = 0x00;
+const PMT_TABLE_ID: u8 = 0x02;
+/// SDT of the actual transport stream
+const SDT_TABLE_ID: u8 = 0x42;
+const TDT_TABLE_ID: u8 = 0x70;
+const TOT_TABLE_ID: u8 = 0x73;
+
+const REGISTRATION_DESCRIPTOR: u8 = 0x05;
+const SERVICE_DESCRIPTOR: u8 = 0x48;
+
+/// Max number of packets to scan for the tables, since SDT & TDT may be
+/// absent.
+const MAX_PACKETS: usize = 8 * 1024;
+
+/// Checks the sync bytes, and the first packet which should be PAT or SDT.
+pub(crate) fn check_ts(input: &[u8]) -> bool {
+    [PACKET_SIZE, M2TS_PACKET_SIZE].into_iter().any(|size| {
+        if !check_sync(input, size) {
+            return false;
+        }
+        let packet = &input[size - PACKET_SIZE..];
+        let pid = pid(packet);
+        let table_id = payload(packet)
+            .filter(|_| packet[1] & 0x40 != 0)
+            .and_then(|x| x.get(1 + *x.first()? as usize).copied());
+        matches!(
+            (pid, table_id),
+            (PAT_PID, Some(PAT_TABLE_ID)) | (SDT_PID, Some(SDT_TABLE_ID))
+        )
+    })
+}
+
+fn packet_size(input: &[u8]) -> Option<usize> {
+    [PACKET_SIZE, M2TS_PACKET_SIZE]
+        .into_iter()
+        .find(|&size| check_sync(input, size))
+}
+
+/// Checks the sync bytes of the packets in `input`, which should contain the
+/// header of the first packet at least.
+fn check_sync(input: &[u8], packet_size: usize) -> bool {
+    let offset = packet_size - PACKET_SIZE;
+    input.len() >= offset + 8
+        && input
+            .iter()
+            .skip(offset)
+            .step_by(packet_size)
+            .all(|x| *x == SYNC_BYTE)
+}
+
+fn pid(packet: &[u8]) -> u16 {
+    u16::from_be_bytes([packet[1] & 0x1F, packet[2]])
+}
+
+/// Returns the payload of a packet, skipping the adaptation field.
+fn payload(packet: &[u8]) -> Option<&[u8]> {
+    match (packet[3] >> 4) & 0x03 {
+        0x01 => packet.get(4..),
+        0x03 => packet.get(5 + *packet.get(4)? as usize..),
+        _ => None,
+    }
+}
+
+/// Parses MPEG transport streams packet by packet, PSI/SI tables (PAT, PMT,
+/// SDT and TDT/TOT) are reassembled from the packets, and other packets are
+/// ignored.
+///
+/// Scanning stops once all the tables are found, or [`MAX_PACKETS`] packets
+/// have been scanned.
+///
+/// Refer to: ISO/IEC 13818-1, and [ETSI EN 300 468](https://www.etsi.org/deliver/etsi_en/300400_300499/300468/01.17.01_60/en_300468v011701p.pdf)
+/// for DVB SI tables.
+#[derive(Debug, Default)]
+pub(crate) struct TsParser {
+    /// 188, or 192 for M2TS files
+    packet_size: usize,
+    packets: usize,
+    /// Incomplete sections, by PID
+    sections: HashMap<u16, Vec<u8>>,
+    info: TsInfo,
+}
+
+#[derive(Debug, Clone, Default)]
+struct TsInfo {
+    /// `(program_number, PMT PID)` in PAT
+    programs: Option<Vec<(u16, u16)>>,
+    /// By program number
+    pmts: BTreeMap<u16, Pmt>,
+    /// Services in SDT
+    services: Option<Vec<Service>>,
+    /// From TDT or TOT
+    time: Option<DateTime<FixedOffset>>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Pmt {
+    /// From the registration descriptor of the program, or the first stream
+    /// which has one
+    registration: Option<String>,
+    /// Codecs of the first video/audio streams
+    video: Option<&'static str>,
+    audio: Option<&'static str>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Service {
+    /// Same as the program number
+    id: u16,
+    provider: Option<String>,
+    name: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StreamKind {
+    Video,
+    Audio,
+}
+
+impl TsParser {
+    /// Parses `input`, which starts where the last call stopped.
+    pub(crate) fn parse(&mut self, input: &[u8]) -> Result<TrackInfo, ParsingError> {
+        if self.packet_size == 0 {
+            self.packet_size = packet_size(input).ok_or("not an MPEG-TS file")?;
+        }
+
+        let mut pos = 0;
+        while let Some(packet) = input.get(pos..pos + self.packet_size) {
+            self.parse_packet(&packet[self.packet_size - PACKET_SIZE..]);
+            pos += self.packet_size;
+            self.packets += 1;
+            if self.info.is_complete() || self.packets >= MAX_PACKETS {
+                tracing::debug!(self.packets, "MPEG-TS scan finished");
+                return Ok(self.finish());
+            }
+        }
+
+        if pos > 0 {
+            Err(ParsingError::ClearAndSkip(pos))
+        } else {
+            Err(ParsingError::Need(self.packet_size - input.len()))
+        }
+    }
+
+    /// Returns the info collected so far, e.g. when the file is shorter than
+    /// the scanned range.
+    pub(crate) fn finish(&mut self) -> TrackInfo {
+        std::mem::take(&mut self.info).into()
+    }
+
+    fn parse_packet(&mut self, packet: &[u8]) {
+        // Lost sync, or transport error
+        if packet[0] != SYNC_BYTE || packet[1] & 0x80 != 0 {
+            return;
+        }
+        let pid = pid(packet);
+        if !self.info.wants(pid) {
+            return;
+        }
+        let Some(payload) = payload(packet) else {
+            return;
+        };
+
+        if packet[1] & 0x40 != 0 {
+            // Payload unit start, the pointer field points to the start of
+            // a new section, bytes before it belong to the previous section
+            let Some((pointer, payload)) = payload.split_first() else {
+                return;
+            };
+            let Some((tail, head)) = payload.split_at_checked(*pointer as usize) else {
+                return;
+            };
+            if let Some(mut section) = self.sections.remove(&pid) {
+                section.extend_from_slice(tail);
+                self.parse_sections(pid, &section);
+            }
+            self.parse_sections(pid, head);
+        } else if let Some(mut section) = self.sections.remove(&pid) {
+            section.extend_from_slice(payload);
+            self.parse_sections(pid, &section);
+        }
+    }
+
+    /// Parses the complete sections in `data`, and keeps the incomplete one.
+    fn parse_sections(&mut self, pid: u16, mut data: &[u8]) {
+        loop {
+            // Stuffing bytes
+            if matches!(data.first(), None | Some(0xFF)) {
+                return;
+            }
+            match data.get(1..3) {
+                Some(x) if section_size(x) <= data.len() => {
+                    let (section, rest) = data.split_at(section_size(x));
+                    self.info.parse_section(pid, section);
+                    data = rest;
+                }
+                _ => {
+                    self.sections.insert(pid, data.to_vec());
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Size of a section, `len` is the 2 bytes after the table ID.
+fn section_size(len: &[u8]) -> usize {
+    3 + (u16::from_be_bytes([len[0] & 0x0F, len[1]]) as usize)
+}
+
+impl TsInfo {
+    fn wants(&self, pid: u16) -> bool {
+        match pid {
+            PAT_PID => self.programs.is_none(),
+            SDT_PID => self.services.is_none(),
+            TDT_PID => self.time.is_none(),
+            _ => self.pmt_program(pid).is_some(),
+        }
+    }
+
+    /// Returns the program number of a PMT PID whose PMT hasn't been parsed.
+    fn pmt_program(&self, pid: u16) -> Option<u16> {
+        self.programs
+            .as_ref()?
+            .iter()
+            .find(|(number, pmt_pid)| *pmt_pid == pid && !self.pmts.contains_key(number))
+            .map(|x| x.0)
+    }
+
+    fn is_complete(&self) -> bool {
+        self.programs
+            .as_ref()
+            .is_some_and(|x| x.iter().all(|(n, _)| self.pmts.contains_key(n)))
+            && self.services.is_some()
+            && self.time.is_some()
+    }
+
+    fn parse_section(&mut self, pid: u16, section: &[u8]) {
+        let table_id = section[0];
+        match (pid, table_id) {
+            (TDT_PID, TDT_TABLE_ID | TOT_TABLE_ID) => {
+                self.time = section.get(3..8).and_then(parse_utc_time);
+                return;
+            }
+            (TDT_PID, _) => return,
+            _ => (),
+        }
+
+        // Sections in the long form: 8-byte header, and 4-byte CRC
+        let Some(payload) = section.get(8..section.len().saturating_sub(4)) else {
+            return;
+        };
+        match (pid, table_id) {
+            (PAT_PID, PAT_TABLE_ID) if self.programs.is_none() => {
+                let programs = payload
+                    .chunks_exact(4)
+                    .map(|x| {
+                        (
+                            u16::from_be_bytes([x[0], x[1]]),
+                            u16::from_be_bytes([x[2] & 0x1F, x[3]]),
+                        )
+                    })
+                    // Program 0 is the network PID
+                    .filter(|(number, _)| *number != 0)
+                    .collect();
+                self.programs = Some(programs);
+            }
+            (SDT_PID, SDT_TABLE_ID) if self.services.is_none() => {
+                // Skip original_network_id & reserved
+                self.services = Some(payload.get(3..).map(parse_services).unwrap_or_default());
+            }
+            (_, PMT_TABLE_ID) => {
+                let number = u16::from_be_bytes([section[3], section[4]]);
+                if self.pmt_program(pid) == Some(number) {
+                    self.pmts.insert(number, parse_pmt(payload));
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+fn parse_pmt(payload: &[u8]) -> Pmt {
+    let mut pmt = Pmt::default();
+    let Some(len) = payload.get(2..4) else {
+        return pmt;
+    };
+    let len = u16::from_be_bytes([len[0] & 0x0F, len[1]]) as usize;
+    let Some((descriptors, mut streams)) = payload[4..].split_at_checked(len) else {
+        return pmt;
+    };
+    pmt.registration = registration(descriptors);
+
+    while let Some(header) = streams.get(..5) {
+        let len = u16::from_be_bytes([header[3] & 0x0F, header[4]]) as usize;
+        let Some(descriptors) = streams.get(5..5 + len) else {
+            break;
+        };
+        streams = &streams[5 + len..];
+
+        if pmt.registration.is_none() {
+            pmt.registration = registration(descriptors);
+        }
+        match stream_codec(header[0], descriptors) {
+            Some((StreamKind::Video, codec)) if pmt.video.is_none() => pmt.video = Some(codec),
+            Some((StreamKind::Audio, codec)) if pmt.audio.is_none() => pmt.audio = Some(codec),
+            _ => (),
+        }
+    }
+    pmt
+}
+
+/// Returns the codec of a stream, from its stream type, or its descriptors
+/// for PES private data.
+fn stream_codec(stream_type: u8, descriptors: &[u8]) -> Option<(StreamKind, &'static str)> {
+    use StreamKind::*;
+    let codec = match stream_type {
+        0x01 => (Video, "MPEG-1 Video"),
+        0x02 => (Video, "MPEG-2 Video"),
+        0x10 => (Video, "MPEG-4 Visual"),
+        0x1B => (Video, "H.264"),
+        0x24 => (Video, "H.265"),
+        0x33 => (Video, "H.266"),
+        0xEA => (Video, "VC-1"),
+        0x03 => (Audio, "MPEG-1 Audio"),
+        0x04 => (Audio, "MPEG-2 Audio"),
+        0x0F => (Audio, "AAC"),
+        0x11 => (Audio, "AAC (LATM)"),
+        0x81 => (Audio, "AC-3"),
+        0x87 => (Audio, "E-AC-3"),
+        // PES private data
+        0x06 => {
+            return Descriptors(descriptors).find_map(|(tag, data)| match (tag, data) {
+                (0x6A, _) | (REGISTRATION_DESCRIPTOR, [b'A', b'C', b'-', b'3', ..]) => {
+                    Some((Audio, "AC-3"))
+                }
+                (0x7A, _) | (REGISTRATION_DESCRIPTOR, [b'E', b'A', b'C', b'3', ..]) => {
+                    Some((Audio, "E-AC-3"))
+                }
+                (0x7B, _) => Some((Audio, "DTS")),
+                (0x7C, _) => Some((Audio, "AAC")),
+                (REGISTRATION_DESCRIPTOR, [b'O', b'p', b'u', b's', ..]) => Some((Audio, "Opus")),
+                _ => None,
+            })
+        }
+        _ => return None,
+    };
+    Some(codec)
+}
+
+/// Returns the format identifier of the registration descriptor, e.g.:
+/// "HDMV".
+fn registration(descriptors: &[u8]) -> Option<String> {
+    Descriptors(descriptors)
+        .filter(|(tag, _)| *tag == REGISTRATION_DESCRIPTOR)
+        .find_map(|(_, data)| {
+            let id = data.get(..4)?;
+            id.iter()
+                .all(|x| x.is_ascii_graphic() || *x == b' ')
+                .then(|| String::from_utf8_lossy(id).trim().to_owned())
+        })
+}
+
+fn parse_services(mut services: &[u8]) -> Vec<Service> {
+    let mut result = Vec::new();
+    while let Some(header) = services.get(..5) {
+        let id = u16::from_be_bytes([header[0], header[1]]);
+        let len = u16::from_be_bytes([header[3] & 0x0F, header[4]]) as usize;
+        let Some(descriptors) = services.get(5..5 + len) else {
+            break;
+        };
+        services = &services[5 + len..];
+
+        let names = Descriptors(descriptors)
+            .find(|(tag, _)| *tag == SERVICE_DESCRIPTOR)
+            .and_then(|(_, data)| {
+                // service_type, provider name, service name
+                let (provider, rest) = length_prefixed(data.get(1..)?)?;
+                let (name, _) = length_prefixed(rest)?;
+                Some((dvb_string(provider), dvb_string(name)))
+            });
+        if let Some((provider, name)) = names {
+            result.push(Service { id, provider, name });
+        }
+    }
+    result
+}
+
+fn length_prefixed(data: &[u8]) -> Option<(&[u8], &[u8])> {
+    let (len, data) = data.split_first()?;
+    data.split_at_checked(*len as usize)
+}
+
+/// Decodes a DVB string. Only UTF-8 and single-byte character tables are
+/// supported, the latter are decoded as ISO-8859-1.
+fn dvb_string(data: &[u8]) -> Option<String> {
+    let latin1 = |x: &[u8]| -> String { x.iter().map(|x| *x as char).collect() };
+    let s = match data.first()? {
+        0x15 => String::from_utf8_lossy(&data[1..]).into_owned(),
+        // ISO/IEC 8859 with a 2-byte table ID
+        0x10 => latin1(data.get(3..)?),
+        0x01..=0x1F => latin1(&data[1..]),
+        _ => latin1(data),
+    };
+    // Remove control codes, e.g. emphasis on/off
+    let s: String = s.chars().filter(|x| !x.is_control()).collect();
+    Some(s.trim().to_owned()).filter(|x| !x.is_empty())
+}
+
+/// Parses `UTC_time` of TDT/TOT: 16-bit MJD, and 6 BCD digits of the time.
+fn parse_utc_time(data: &[u8]) -> Option<DateTime<FixedOffset>> {
+    let mjd = u16::from_be_bytes([data[0], data[1]]) as i64;
+    let bcd = |x: u8| -> Option<i64> {
+        let (high, low) = (x >> 4, x & 0x0F);
+        (high < 10 && low < 10).then_some((high * 10 + low) as i64)
+    };
+    let (hour, min, sec) = (bcd(data[2])?, bcd(data[3])?, bcd(data[4])?);
+    if hour >= 24 || min >= 60 || sec >= 60 {
+        return None;
+    }
+    // MJD 40587 is 1970-01-01
+    let secs = (mjd - 40587) * 86400 + hour * 3600 + min * 60 + sec;
+    DateTime::from_timestamp(secs, 0).map(|x| x.fixed_offset())
+}
+
+/// Iterates over descriptors, yields `(tag, data)`.
+struct Descriptors<'a>(&'a [u8]);
+
+impl<'a> Iterator for Descriptors<'a> {
+    type Item = (u8, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (tag, rest) = self.0.split_first()?;
+        let (data, rest) = length_prefixed(rest)?;
+        self.0 = rest;
+        Some((*tag, data))
+    }
+}
+
+impl From<TsInfo> for TrackInfo {
+    fn from(value: TsInfo) -> Self {
+        let mut info = TrackInfo::default();
+        if let Some(time) = value.time {
+            info.put(TrackInfoTag::CreateDate, time.into());
+        }
+
+        // Only the first program is reported
+        let program = value
+            .programs
+            .unwrap_or_default()
+            .into_iter()
+            .map(|x| x.0)
+            .find(|x| value.pmts.contains_key(x));
+        let Some(number) = program else {
+            return info;
+        };
+        info.put(TrackInfoTag::ProgramNumber, number.into());
+
+        let pmt = &value.pmts[&number];
+        if let Some(codec) = pmt.video {
+            info.put(TrackInfoTag::VideoCodec, codec.into());
+        }
+        if let Some(codec) = pmt.audio {
+            info.put(TrackInfoTag::AudioCodec, codec.into());
+        }
+        if let Some(registration) = pmt.registration.clone() {
+            info.put(TrackInfoTag::Registration, registration.into());
+        }
+
+        let service = value
+            .services
+            .unwrap_or_default()
+            .into_iter()
+            .find(|x| x.id == number)
+            .unwrap_or_default();
+        if let Some(provider) = service.provider {
+            info.put(TrackInfoTag::ServiceProvider, provider.into());
+        }
+        if let Some(name) = service.name {
+            info.put(TrackInfoTag::ServiceName, name.into());
+        }
+        info
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{MediaParser, MediaSource};
+    use test_case::test_case;
+
+    /// Splits a section into packets of `pid`.
+    fn packets(pid: u16, section: &[u8], m2ts: bool) -> Vec<u8> {
+        let mut payload = [&[0][..], section].concat();
+        let mut result = Vec::new();
+        for (i, chunk) in payload.chunks_mut(PACKET_SIZE - 4).enumerate() {
+            if m2ts {
+                result.extend([0; 4]);
+            }
+            let pusi = if i == 0 { 0x40 } else { 0 };
+            result.extend([SYNC_BYTE, pusi | (pid >> 8) as u8, pid as u8, 0x10]);
+            result.extend(&*chunk);
+            result.extend(vec![0xFF; PACKET_SIZE - 4 - chunk.len()]);
+        }
+        result
+    }
+
+    /// Makes a long form section.
+    fn section(table_id: u8, id: u16, payload: &[u8]) -> Vec<u8> {
+        let len = (5 + payload.len() + 4) as u16;
+        [
+            &[table_id][..],
+            &(0xB000 | len).to_be_bytes(),
+            &id.to_be_bytes(),
+            &[0xC1, 0, 0],
+            payload,
+            &[0; 4],
+        ]
+        .concat()
+    }
+
+    fn descriptor(tag: u8, data: &[u8]) -> Vec<u8> {
+        [&[tag, data.len() as u8][..], data].concat()
+    }
+
+    fn null_packets(n: usize, m2ts: bool) -> Vec<u8> {
+        let mut packet = vec![0; if m2ts { 4 } else { 0 }];
+        packet.extend([SYNC_BYTE, 0x1F, 0xFF, 0x10]);
+        packet.resize(packet.len() + PACKET_SIZE - 4, 0xFF);
+        packet.repeat(n)
+    }
+
+    /// Makes a transport stream of program 1 (PMT PID 0x100), which has an
+    /// H.264 video stream and an AC-3 audio stream.
+    pub(crate) fn make_ts(m2ts: bool, with_si: bool) -> Vec<u8> {
+        let pat = section(PAT_TABLE_ID, 1, &[0, 0, 0xE0, 0x10, 0, 1, 0xE1, 0x00]);
+
+        // A long program info, so that the PMT spans two packets
+        let program_info = [
+            descriptor(REGISTRATION_DESCRIPTOR, b"HDMV\xFF\x1B"),
+            descriptor(0x88, &[0; 200]),
+        ]
+        .concat();
+        let ac3 = descriptor(0x6A, &[0]);
+        let pmt = section(
+            PMT_TABLE_ID,
+            1,
+            &[
+                &[0xE1, 0x01][..],
+                &(0xF000 | program_info.len() as u16).to_be_bytes(),
+                &program_info,
+                &[0x1B, 0xE1, 0x01, 0xF0, 0x00],
+                &[0x06, 0xE1, 0x02, 0xF0, ac3.len() as u8],
+                &ac3,
+            ]
+            .concat(),
+        );
+
+        let mut ts = [packets(PAT_PID, &pat, m2ts), packets(0x100, &pmt, m2ts)].concat();
+        if with_si {
+            let service = descriptor(
+                SERVICE_DESCRIPTOR,
+                &[
+                    &[0x01, 11][..],
+                    b"Camera Corp",
+                    &[6, 0x15],
+                    "CAM 1".as_bytes(),
+                ]
+                .concat(),
+            );
+            let sdt = section(
+                SDT_TABLE_ID,
+                1,
+                &[
+                    &[0, 1, 0xFF][..],
+                    &[0, 1, 0xFC, 0x80, service.len() as u8],
+                    &service,
+                ]
+                .concat(),
+            );
+            // 2021-03-04 05:06:07 (MJD 59277)
+            let tdt = [
+                &[TDT_TABLE_ID, 0x70, 5][..],
+                &59277_u16.to_be_bytes(),
+                &[0x05, 0x06, 0x07],
+            ]
+            .concat();
+            ts.extend(packets(SDT_PID, &sdt, m2ts));
+            ts.extend(packets(TDT_PID, &tdt, m2ts));
+        }
+        ts.extend(null_packets(100, m2ts));
+        ts
+    }
+
+    #[test_case(false, true; "ts")]
+    #[test_case(true, true; "m2ts")]
+    #[test_case(false, false; "without sdt & tdt")]
+    fn mpegts(m2ts: bool, with_si: bool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let data = make_ts(m2ts, with_si);
+        let mut parser = MediaParser::new();
+        let ms = MediaSource::unseekable(Cursor::new(data)).unwrap();
+        assert!(ms.has_track());
+        let info: TrackInfo = parser.parse(ms).unwrap();
+
+        assert_eq!(info.get(TrackInfoTag::ProgramNumber), Some(&1_u16.into()));
+        assert_eq!(info.get(TrackInfoTag::VideoCodec), Some(&"H.264".into()));
+        assert_eq!(info.get(TrackInfoTag::AudioCodec), Some(&"AC-3".into()));
+        assert_eq!(info.get(TrackInfoTag::Registration), Some(&"HDMV".into()));
+        if with_si {
+            assert_eq!(
+                info.get(TrackInfoTag::ServiceProvider),
+                Some(&"Camera Corp".into())
+            );
+            assert_eq!(info.get(TrackInfoTag::ServiceName), Some(&"CAM 1".into()));
+            assert_eq!(
+                info.get(TrackInfoTag::CreateDate).unwrap().to_string(),
+                "2021-03-04T05:06:07+00:00"
+            );
+        } else {
+            assert_eq!(info.get(TrackInfoTag::ServiceName), None);
+            assert_eq!(info.get(TrackInfoTag::CreateDate), None);
+        }
+    }
+
+    #[test_case(&[0x15, b'a', 0xC3, 0xA9], Some("aé"); "utf8")]
+    #[test_case(&[0x05, b'a', 0x86, b'b', 0x87], Some("ab"); "control codes")]
+    #[test_case(&[0x10, 0x00, 0x01, b'a'], Some("a"); "iso 8859")]
+    #[test_case(&[], None; "empty")]
+    fn dvb_strings(data: &[u8], expect: Option<&str>) {
+        assert_eq!(dvb_string(data).as_deref(), expect);
+    }
+}
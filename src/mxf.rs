@@ -0,0 +1,580 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, FixedOffset, NaiveDate};
+
+use crate::{
+    error::ParsingError,
+    video::{TrackInfo, TrackInfoTag},
+};
+
+/// SMPTE Universal Label, which is also used as the key of KLV packets.
+type Ul = [u8; 16];
+
+/// Key of the Header Partition Pack: 06.0E.2B.34.02.05.01.vv.0D.01.02.01.01.02.ss.00,
+/// where `vv` is the version & `ss` is the status (open/closed,
+/// complete/incomplete) of the partition.
+const PARTITION_PACK_KEY: [u8; 7] = [0x06, 0x0E, 0x2B, 0x34, 0x02, 0x05, 0x01];
+const HEADER_PARTITION_KEY: [u8; 6] = [0x0D, 0x01, 0x02, 0x01, 0x01, 0x02];
+
+/// Keys of local sets of structural metadata:
+/// 06.0E.2B.34.02.53.01.vv.0D.01.01.01.01.01.tt.00, where `tt` is the set
+/// type.
+const LOCAL_SET_KEY: [u8; 6] = [0x06, 0x0E, 0x2B, 0x34, 0x02, 0x53];
+const METADATA_SET_KEY: [u8; 6] = [0x0D, 0x01, 0x01, 0x01, 0x01, 0x01];
+
+// Set types
+const SEQUENCE: u8 = 0x0F;
+const IDENTIFICATION: u8 = 0x30;
+const MATERIAL_PACKAGE: u8 = 0x36;
+const TIMELINE_TRACK: u8 = 0x3B;
+const PICTURE_DESCRIPTORS: [u8; 4] = [0x27, 0x28, 0x29, 0x51];
+const SOUND_DESCRIPTORS: [u8; 3] = [0x42, 0x47, 0x48];
+/// AES3 & WAVE audio descriptors, whose essence is PCM.
+const PCM_DESCRIPTORS: [u8; 2] = [0x47, 0x48];
+
+// Local tags
+const INSTANCE_UID: u16 = 0x3C0A;
+const COMPANY_NAME: u16 = 0x3C01;
+const PRODUCT_NAME: u16 = 0x3C02;
+const VERSION_STRING: u16 = 0x3C04;
+const MODIFICATION_DATE: u16 = 0x3C06;
+const PACKAGE_NAME: u16 = 0x4402;
+const PACKAGE_TRACKS: u16 = 0x4403;
+const PACKAGE_CREATION_DATE: u16 = 0x4405;
+const TRACK_SEQUENCE: u16 = 0x4803;
+const EDIT_RATE: u16 = 0x4B01;
+const COMPONENT_DURATION: u16 = 0x0202;
+const SAMPLE_RATE: u16 = 0x3001;
+const PICTURE_ESSENCE_CODING: u16 = 0x3201;
+const STORED_HEIGHT: u16 = 0x3202;
+const STORED_WIDTH: u16 = 0x3203;
+const DISPLAY_HEIGHT: u16 = 0x3208;
+const DISPLAY_WIDTH: u16 = 0x3209;
+const FRAME_LAYOUT: u16 = 0x320C;
+
+/// Offset of `HeaderByteCount` in the value of a partition pack.
+const HEADER_BYTE_COUNT_OFFSET: usize = 32;
+const PARTITION_PACK_MIN_SIZE: usize = 88;
+
+/// Max size of the header metadata, which is loaded into memory as a whole,
+/// to avoid exhausting memory on corrupted files.
+const MAX_HEADER_METADATA_SIZE: u64 = 64 * 1024 * 1024;
+
+pub(crate) fn check_mxf(input: &[u8]) -> bool {
+    input.len() >= 16
+        && input[..7] == PARTITION_PACK_KEY
+        && input[8..14] == HEADER_PARTITION_KEY
+        && (1..=4).contains(&input[14])
+}
+
+/// Parses MXF (Material Exchange Format) files. The header metadata
+/// (Preface, Identification, packages, tracks & essence descriptors) follows
+/// the Header Partition Pack at the start of files, and is loaded into
+/// memory as a whole.
+///
+/// Refer to: [SMPTE ST 377-1](https://pub.smpte.org/doc/st377-1/)
+pub(crate) fn parse_mxf(input: &[u8]) -> Result<TrackInfo, ParsingError> {
+    let (key, header_size, size) = parse_klv_header(input)?;
+    if !check_mxf(key) {
+        return Err("not an MXF file".into());
+    }
+    if size < PARTITION_PACK_MIN_SIZE as u64 {
+        return Err(ParsingError::Failed(format!(
+            "invalid MXF partition pack size: {size}"
+        )));
+    }
+    let pack_end = header_size + size as usize;
+    if input.len() < pack_end {
+        return Err(ParsingError::Need(pack_end - input.len()));
+    }
+
+    let pos = header_size + HEADER_BYTE_COUNT_OFFSET;
+    let header_byte_count = u64::from_be_bytes(input[pos..pos + 8].try_into().unwrap());
+    if header_byte_count == 0 || header_byte_count > MAX_HEADER_METADATA_SIZE {
+        return Err(ParsingError::Failed(format!(
+            "invalid MXF header byte count: {header_byte_count}"
+        )));
+    }
+    let end = pack_end + header_byte_count as usize;
+    if input.len() < end {
+        return Err(ParsingError::Need(end - input.len()));
+    }
+
+    let sets = Klvs(&input[pack_end..end])
+        .filter_map(|(key, value)| LocalSet::parse(key, value))
+        .collect::<Vec<_>>();
+    Ok(MxfInfo::new(&sets).into())
+}
+
+/// Parses the key & the BER encoded length of a KLV packet, returns
+/// `(key, header_size, value_size)`.
+fn parse_klv_header(input: &[u8]) -> Result<(&Ul, usize, u64), ParsingError> {
+    if input.len() < 17 {
+        return Err(ParsingError::Need(17 - input.len()));
+    }
+    let key: &Ul = input[..16].try_into().unwrap();
+    let first = input[16];
+    if first < 0x80 {
+        return Ok((key, 17, first as u64));
+    }
+    let n = (first & 0x7F) as usize;
+    if n == 0 || n > 8 {
+        return Err(ParsingError::Failed(format!(
+            "invalid MXF BER length: {first:#x}"
+        )));
+    }
+    let header_size = 17 + n;
+    if input.len() < header_size {
+        return Err(ParsingError::Need(header_size - input.len()));
+    }
+    let size = input[17..header_size]
+        .iter()
+        .fold(0_u64, |size, x| size << 8 | *x as u64);
+    Ok((key, header_size, size))
+}
+
+/// Iterates over the KLV packets in the header metadata, yields `(key,
+/// value)`.
+struct Klvs<'a>(&'a [u8]);
+
+impl<'a> Iterator for Klvs<'a> {
+    type Item = (&'a Ul, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, header_size, size) = parse_klv_header(self.0).ok()?;
+        let Some(end) = usize::try_from(size)
+            .ok()
+            .and_then(|x| x.checked_add(header_size))
+            .filter(|x| *x <= self.0.len())
+        else {
+            tracing::warn!(size, "Ignored: invalid MXF KLV size");
+            return None;
+        };
+        let (klv, rest) = self.0.split_at(end);
+        self.0 = rest;
+        Some((key, &klv[header_size..]))
+    }
+}
+
+/// A local set of the header metadata, whose items are identified by 2-byte
+/// local tags.
+///
+/// Only static local tags are used, so the Primer Pack, which maps dynamic
+/// local tags to ULs, is ignored.
+#[derive(Debug)]
+struct LocalSet<'a> {
+    set_type: u8,
+    items: HashMap<u16, &'a [u8]>,
+}
+
+impl<'a> LocalSet<'a> {
+    fn parse(key: &Ul, value: &'a [u8]) -> Option<LocalSet<'a>> {
+        if key[..6] != LOCAL_SET_KEY || key[8..14] != METADATA_SET_KEY {
+            return None;
+        }
+        let mut items = HashMap::new();
+        let mut data = value;
+        while data.len() >= 4 {
+            let tag = u16::from_be_bytes([data[0], data[1]]);
+            let len = u16::from_be_bytes([data[2], data[3]]) as usize;
+            let Some((item, rest)) = data[4..].split_at_checked(len) else {
+                break;
+            };
+            items.insert(tag, item);
+            data = rest;
+        }
+        Some(LocalSet {
+            set_type: key[14],
+            items,
+        })
+    }
+
+    fn get(&self, tag: u16) -> Option<&'a [u8]> {
+        self.items.get(&tag).copied()
+    }
+
+    fn u32(&self, tag: u16) -> Option<u32> {
+        Some(u32::from_be_bytes(self.get(tag)?.try_into().ok()?))
+    }
+
+    /// A rational is stored as numerator (i32) & denominator (i32).
+    fn rational(&self, tag: u16) -> Option<(i32, i32)> {
+        let data = self.get(tag)?;
+        let num = i32::from_be_bytes(data.get(..4)?.try_into().ok()?);
+        let den = i32::from_be_bytes(data.get(4..8)?.try_into().ok()?);
+        Some((num, den)).filter(|(num, den)| *num > 0 && *den > 0)
+    }
+
+    fn string(&self, tag: u16) -> Option<String> {
+        utf16_string(self.get(tag)?)
+    }
+
+    fn timestamp(&self, tag: u16) -> Option<DateTime<FixedOffset>> {
+        parse_timestamp(self.get(tag)?)
+    }
+
+    /// Returns the strong references (instance UIDs) in a batch.
+    fn references(&self, tag: u16) -> impl Iterator<Item = &'a [u8]> {
+        // count (u32), item size (u32), then the items
+        let refs = self.get(tag).and_then(|x| x.get(8..)).unwrap_or_default();
+        refs.chunks_exact(16)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct MxfInfo {
+    duration_ms: Option<u64>,
+    create_date: Option<DateTime<FixedOffset>>,
+    title: Option<String>,
+
+    /// `CompanyName` of Identification
+    company: Option<String>,
+    /// `ProductName` & `VersionString` of Identification
+    product: Option<String>,
+
+    width: Option<u32>,
+    height: Option<u32>,
+    frame_rate: Option<f64>,
+    video_codec: Option<&'static str>,
+    audio_codec: Option<&'static str>,
+}
+
+impl MxfInfo {
+    fn new(sets: &[LocalSet]) -> MxfInfo {
+        let mut info = MxfInfo::default();
+        let by_uid = sets
+            .iter()
+            .filter_map(|x| Some((x.get(INSTANCE_UID)?, x)))
+            .collect::<HashMap<_, _>>();
+
+        // The first Identification is added by the application which
+        // created the file, others are added by the applications which
+        // modified it.
+        let identification = sets.iter().find(|x| x.set_type == IDENTIFICATION);
+        if let Some(set) = identification {
+            info.company = set.string(COMPANY_NAME);
+            info.product = match (set.string(PRODUCT_NAME), set.string(VERSION_STRING)) {
+                (Some(name), Some(version)) => Some(format!("{name} {version}")),
+                (name, _) => name,
+            };
+        }
+
+        if let Some(package) = sets.iter().find(|x| x.set_type == MATERIAL_PACKAGE) {
+            info.title = package.string(PACKAGE_NAME);
+            info.create_date = package.timestamp(PACKAGE_CREATION_DATE);
+
+            // The duration of the longest track
+            info.duration_ms = package
+                .references(PACKAGE_TRACKS)
+                .filter_map(|uid| {
+                    let track = by_uid.get(uid).filter(|x| x.set_type == TIMELINE_TRACK)?;
+                    let (num, den) = track.rational(EDIT_RATE)?;
+                    let sequence = by_uid
+                        .get(track.get(TRACK_SEQUENCE)?)
+                        .filter(|x| x.set_type == SEQUENCE)?;
+                    let duration = sequence.get(COMPONENT_DURATION)?;
+                    // -1 means the duration is unknown, e.g.: the file is
+                    // being written
+                    let duration =
+                        u64::try_from(i64::from_be_bytes(duration.try_into().ok()?)).ok()?;
+                    Some(duration.saturating_mul(1000) * den as u64 / num as u64)
+                })
+                .max();
+        }
+        if info.create_date.is_none() {
+            info.create_date = identification.and_then(|x| x.timestamp(MODIFICATION_DATE));
+        }
+
+        if let Some(set) = sets
+            .iter()
+            .find(|x| PICTURE_DESCRIPTORS.contains(&x.set_type))
+        {
+            info.width = set.u32(DISPLAY_WIDTH).or(set.u32(STORED_WIDTH));
+            let height = set.u32(DISPLAY_HEIGHT).or(set.u32(STORED_HEIGHT));
+            // Heights of SeparateFields (1) & SegmentedFrame (4) layouts
+            // are field heights
+            info.height = match set.get(FRAME_LAYOUT) {
+                Some([1 | 4]) => height.map(|x| x * 2),
+                _ => height,
+            };
+            info.frame_rate = set.rational(SAMPLE_RATE).map(|(num, den)| {
+                // Keep 3 decimal places, e.g.: 29.97
+                (num as f64 / den as f64 * 1000.0).round() / 1000.0
+            });
+            info.video_codec = set.get(PICTURE_ESSENCE_CODING).and_then(video_codec);
+        }
+        if let Some(set) = sets
+            .iter()
+            .find(|x| SOUND_DESCRIPTORS.contains(&x.set_type))
+        {
+            if PCM_DESCRIPTORS.contains(&set.set_type) {
+                info.audio_codec = Some("PCM");
+            }
+        }
+        info
+    }
+}
+
+/// Returns the codec name of a picture essence coding UL.
+fn video_codec(ul: &[u8]) -> Option<&'static str> {
+    if ul.len() != 16 || !ul.starts_with(&[0x06, 0x0E, 0x2B, 0x34, 0x04]) {
+        return None;
+    }
+    let name = match &ul[8..14] {
+        [0x04, 0x01, 0x02, 0x01, ..] => "Uncompressed",
+        [0x04, 0x01, 0x02, 0x02, 0x01, 0x01..=0x0F] => "MPEG-2",
+        [0x04, 0x01, 0x02, 0x02, 0x01, 0x20..=0x2F] => "MPEG-4 Visual",
+        [0x04, 0x01, 0x02, 0x02, 0x01, 0x30..=0x3F] => "H.264",
+        [0x04, 0x01, 0x02, 0x02, 0x02, _] => "DV",
+        [0x04, 0x01, 0x02, 0x02, 0x03, 0x01] => "JPEG 2000",
+        [0x04, 0x01, 0x02, 0x02, 0x03, 0x06] => "ProRes",
+        [0x04, 0x01, 0x02, 0x02, 0x71, _] => "VC-3",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// Parses a timestamp: year (u16), month, day, hour, minute, second & 1/250
+/// second.
+///
+/// The time is treated as UTC, since MXF timestamps should be in UTC.
+fn parse_timestamp(data: &[u8]) -> Option<DateTime<FixedOffset>> {
+    let [y0, y1, month, day, hour, minute, second, qmsec] = data.try_into().ok()?;
+    let time = NaiveDate::from_ymd_opt(
+        u16::from_be_bytes([y0, y1]) as i32,
+        month as u32,
+        day as u32,
+    )?
+    .and_hms_milli_opt(hour as u32, minute as u32, second as u32, qmsec as u32 * 4)?;
+    Some(time.and_utc().fixed_offset())
+}
+
+/// Decodes a UTF-16BE string, which may be null-terminated.
+fn utf16_string(data: &[u8]) -> Option<String> {
+    let units = data
+        .chunks_exact(2)
+        .map(|x| u16::from_be_bytes([x[0], x[1]]))
+        .take_while(|x| *x != 0);
+    let s: String = char::decode_utf16(units)
+        .map(|x| x.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect();
+    Some(s.trim().to_owned()).filter(|x| !x.is_empty())
+}
+
+impl From<MxfInfo> for TrackInfo {
+    fn from(value: MxfInfo) -> Self {
+        let mut info = TrackInfo::default();
+        if let Some(duration) = value.duration_ms {
+            info.put(TrackInfoTag::DurationMs, duration.into());
+        }
+        if let Some(date) = value.create_date {
+            info.put(TrackInfoTag::CreateDate, date.into());
+        }
+        if let (Some(width), Some(height)) = (value.width, value.height) {
+            info.put(TrackInfoTag::ImageWidth, width.into());
+            info.put(TrackInfoTag::ImageHeight, height.into());
+        }
+        if let Some(rate) = value.frame_rate {
+            info.put(TrackInfoTag::FrameRate, rate.into());
+        }
+
+        let texts = [
+            (TrackInfoTag::Title, value.title),
+            (TrackInfoTag::Make, value.company),
+            (TrackInfoTag::Software, value.product),
+            (
+                TrackInfoTag::VideoCodec,
+                value.video_codec.map(String::from),
+            ),
+            (
+                TrackInfoTag::AudioCodec,
+                value.audio_codec.map(String::from),
+            ),
+        ];
+        for (tag, text) in texts {
+            if let Some(text) = text {
+                info.put(tag, text.into());
+            }
+        }
+        info
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{MediaParser, MediaSource};
+    use test_case::test_case;
+
+    fn klv(key: &[u8], value: &[u8]) -> Vec<u8> {
+        // 4-byte BER length, as most writers do
+        let len = &(value.len() as u32).to_be_bytes()[1..];
+        [key, &[0x83], len, value].concat()
+    }
+
+    fn local_set(set_type: u8, uid: u8, items: &[(u16, Vec<u8>)]) -> Vec<u8> {
+        let mut key = [0; 16];
+        key[..6].copy_from_slice(&LOCAL_SET_KEY);
+        key[6..8].copy_from_slice(&[0x01, 0x01]);
+        key[8..14].copy_from_slice(&METADATA_SET_KEY);
+        key[14] = set_type;
+
+        let mut value = Vec::new();
+        for (tag, data) in [(INSTANCE_UID, [uid; 16].to_vec())].iter().chain(items) {
+            value.extend(tag.to_be_bytes());
+            value.extend((data.len() as u16).to_be_bytes());
+            value.extend(data);
+        }
+        klv(&key, &value)
+    }
+
+    fn utf16(s: &str) -> Vec<u8> {
+        s.encode_utf16().flat_map(|x| x.to_be_bytes()).collect()
+    }
+
+    fn rational(num: i32, den: i32) -> Vec<u8> {
+        [num.to_be_bytes(), den.to_be_bytes()].concat()
+    }
+
+    /// Makes an MXF file of 1080i 25 fps MPEG-2 video, which lasts 10
+    /// seconds, created at 2015-04-01T10:20:30.500Z.
+    pub(crate) fn make_mxf(frame_layout: u8) -> Vec<u8> {
+        let timestamp = [0x07, 0xDF, 4, 1, 10, 20, 30, 125].to_vec();
+        let mpeg2 = [
+            0x06, 0x0E, 0x2B, 0x34, 0x04, 0x01, 0x01, 0x03, 0x04, 0x01, 0x02, 0x02, 0x01, 0x04,
+            0x03, 0x00,
+        ];
+        let tracks = [
+            &2_u32.to_be_bytes()[..],
+            &16_u32.to_be_bytes(),
+            &[3; 16],
+            &[5; 16],
+        ]
+        .concat();
+        let height: u32 = if frame_layout == 1 { 540 } else { 1080 };
+
+        let metadata = [
+            // Primer Pack
+            klv(
+                &[
+                    0x06, 0x0E, 0x2B, 0x34, 0x02, 0x05, 0x01, 0x01, 0x0D, 0x01, 0x02, 0x01, 0x01,
+                    0x05, 0x01, 0x00,
+                ],
+                &[0, 0, 0, 0, 0, 0, 0, 18],
+            ),
+            local_set(0x2F, 1, &[(0x3B02, timestamp.clone())]),
+            local_set(
+                IDENTIFICATION,
+                2,
+                &[
+                    (COMPANY_NAME, utf16("Sony")),
+                    (PRODUCT_NAME, utf16("PMW-500")),
+                    (VERSION_STRING, utf16("2.10\0")),
+                    (MODIFICATION_DATE, [0x07, 0xDF, 4, 2, 0, 0, 0, 0].to_vec()),
+                ],
+            ),
+            local_set(
+                MATERIAL_PACKAGE,
+                6,
+                &[
+                    (PACKAGE_NAME, utf16("Clip0001")),
+                    (PACKAGE_TRACKS, tracks),
+                    (PACKAGE_CREATION_DATE, timestamp),
+                ],
+            ),
+            // Picture & sound tracks
+            local_set(
+                TIMELINE_TRACK,
+                3,
+                &[
+                    (EDIT_RATE, rational(25, 1)),
+                    (TRACK_SEQUENCE, [4; 16].to_vec()),
+                ],
+            ),
+            local_set(
+                SEQUENCE,
+                4,
+                &[(COMPONENT_DURATION, 250_i64.to_be_bytes().to_vec())],
+            ),
+            local_set(
+                TIMELINE_TRACK,
+                5,
+                &[
+                    (EDIT_RATE, rational(48000, 1)),
+                    (TRACK_SEQUENCE, [7; 16].to_vec()),
+                ],
+            ),
+            local_set(
+                SEQUENCE,
+                7,
+                &[(COMPONENT_DURATION, (-1_i64).to_be_bytes().to_vec())],
+            ),
+            local_set(
+                0x51,
+                8,
+                &[
+                    (SAMPLE_RATE, rational(25, 1)),
+                    (PICTURE_ESSENCE_CODING, mpeg2.to_vec()),
+                    (STORED_WIDTH, 1920_u32.to_be_bytes().to_vec()),
+                    (STORED_HEIGHT, height.to_be_bytes().to_vec()),
+                    (FRAME_LAYOUT, vec![frame_layout]),
+                ],
+            ),
+            local_set(0x48, 9, &[(SAMPLE_RATE, rational(25, 1))]),
+        ]
+        .concat();
+
+        let mut pack = [0; PARTITION_PACK_MIN_SIZE];
+        pack[..4].copy_from_slice(&[0, 1, 0, 3]);
+        pack[HEADER_BYTE_COUNT_OFFSET..HEADER_BYTE_COUNT_OFFSET + 8]
+            .copy_from_slice(&(metadata.len() as u64).to_be_bytes());
+        let pack = klv(
+            &[
+                0x06, 0x0E, 0x2B, 0x34, 0x02, 0x05, 0x01, 0x01, 0x0D, 0x01, 0x02, 0x01, 0x01, 0x02,
+                0x04, 0x00,
+            ],
+            &pack,
+        );
+        // Essence
+        [pack, metadata, vec![0; 1024]].concat()
+    }
+
+    #[test_case(0, 1080; "progressive")]
+    #[test_case(1, 1080; "separate fields")]
+    fn mxf(frame_layout: u8, height: u32) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let data = make_mxf(frame_layout);
+        let mut parser = MediaParser::new();
+        let ms = MediaSource::unseekable(Cursor::new(data)).unwrap();
+        assert!(ms.has_track());
+        let info: TrackInfo = parser.parse(ms).unwrap();
+
+        assert_eq!(info.get(TrackInfoTag::DurationMs), Some(&10000_u64.into()));
+        assert_eq!(
+            info.get(TrackInfoTag::CreateDate).unwrap().to_string(),
+            "2015-04-01T10:20:30.500+00:00"
+        );
+        assert_eq!(info.get(TrackInfoTag::Make), Some(&"Sony".into()));
+        assert_eq!(
+            info.get(TrackInfoTag::Software),
+            Some(&"PMW-500 2.10".into())
+        );
+        assert_eq!(info.get(TrackInfoTag::Title), Some(&"Clip0001".into()));
+        assert_eq!(info.get(TrackInfoTag::ImageWidth), Some(&1920_u32.into()));
+        assert_eq!(info.get(TrackInfoTag::ImageHeight), Some(&height.into()));
+        assert_eq!(info.get(TrackInfoTag::FrameRate), Some(&25_f64.into()));
+        assert_eq!(info.get(TrackInfoTag::VideoCodec), Some(&"MPEG-2".into()));
+        assert_eq!(info.get(TrackInfoTag::AudioCodec), Some(&"PCM".into()));
+    }
+
+    #[test]
+    fn mxf_invalid_header_byte_count() {
+        let mut data = make_mxf(0);
+        let pos = 20 + HEADER_BYTE_COUNT_OFFSET;
+        data[pos..pos + 8].copy_from_slice(&u64::MAX.to_be_bytes());
+        assert!(matches!(parse_mxf(&data), Err(ParsingError::Failed(_))));
+    }
+}
@@ -0,0 +1,304 @@
+use std::io::{Read, Write};
+
+use crate::{
+    exif::{ifd_fields, u16_at, u16_bytes, TiffHeader},
+    file::{Mime, MimeImage},
+    strip::exif_tiff_ranges,
+    values::DataFormat,
+    Error,
+};
+
+const ORIENTATION: u16 = 0x0112;
+
+/// Image orientation, as defined by the Exif `Orientation` tag.
+///
+/// Each variant describes how the stored pixels must be transformed to be
+/// presented upright, see [`Orientation::transform`].
+///
+/// ## Example
+///
+/// ```rust
+/// use nom_exif::*;
+///
+/// let orientation = Orientation::try_from(6).unwrap();
+/// assert_eq!(orientation, Orientation::Rotate90);
+/// assert_eq!(orientation.transform(), Transform { rotation: 90, mirror: false });
+/// assert_eq!(orientation.apply_to_dimensions(4032, 3024), (3024, 4032));
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Orientation {
+    /// 1: no transformation is needed
+    #[default]
+    Horizontal = 1,
+    /// 2: mirror horizontally
+    MirrorHorizontal = 2,
+    /// 3: rotate 180 degrees
+    Rotate180 = 3,
+    /// 4: mirror vertically
+    MirrorVertical = 4,
+    /// 5: mirror horizontally, then rotate 270 degrees clockwise
+    MirrorHorizontalRotate270 = 5,
+    /// 6: rotate 90 degrees clockwise
+    Rotate90 = 6,
+    /// 7: mirror horizontally, then rotate 90 degrees clockwise
+    MirrorHorizontalRotate90 = 7,
+    /// 8: rotate 270 degrees clockwise
+    Rotate270 = 8,
+}
+
+/// The transformation to present an image upright: mirror the stored pixels
+/// horizontally (if `mirror` is true), then rotate them clockwise by
+/// `rotation` degrees.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Transform {
+    /// Clockwise rotation in degrees, one of 0, 90, 180 & 270.
+    pub rotation: u16,
+    /// Whether to mirror horizontally before rotating.
+    pub mirror: bool,
+}
+
+impl Orientation {
+    /// Returns the Exif `Orientation` value (1 ~ 8).
+    pub const fn value(self) -> u16 {
+        self as u16
+    }
+
+    /// Returns the transformation to present the image upright.
+    pub const fn transform(self) -> Transform {
+        let (rotation, mirror) = match self {
+            Orientation::Horizontal => (0, false),
+            Orientation::MirrorHorizontal => (0, true),
+            Orientation::Rotate180 => (180, false),
+            Orientation::MirrorVertical => (180, true),
+            Orientation::MirrorHorizontalRotate270 => (270, true),
+            Orientation::Rotate90 => (90, false),
+            Orientation::MirrorHorizontalRotate90 => (90, true),
+            Orientation::Rotate270 => (270, false),
+        };
+        Transform { rotation, mirror }
+    }
+
+    /// Returns true if width & height are swapped when presenting the image,
+    /// i.e. the transformation includes a 90/270 degrees rotation.
+    pub const fn swaps_dimensions(self) -> bool {
+        self.transform().rotation % 180 != 0
+    }
+
+    /// Returns the `(width, height)` of the presented image, for an image
+    /// stored as `width` x `height` pixels.
+    pub const fn apply_to_dimensions(self, width: u32, height: u32) -> (u32, u32) {
+        if self.swaps_dimensions() {
+            (height, width)
+        } else {
+            (width, height)
+        }
+    }
+}
+
+impl TryFrom<u16> for Orientation {
+    type Error = crate::Error;
+
+    fn try_from(v: u16) -> Result<Self, Self::Error> {
+        let orientation = match v {
+            1 => Orientation::Horizontal,
+            2 => Orientation::MirrorHorizontal,
+            3 => Orientation::Rotate180,
+            4 => Orientation::MirrorVertical,
+            5 => Orientation::MirrorHorizontalRotate270,
+            6 => Orientation::Rotate90,
+            7 => Orientation::MirrorHorizontalRotate90,
+            8 => Orientation::Rotate270,
+            o => return Err(format!("invalid orientation: {o}").into()),
+        };
+        Ok(orientation)
+    }
+}
+
+impl From<Orientation> for u16 {
+    fn from(v: Orientation) -> Self {
+        v.value()
+    }
+}
+
+/// Reads a JPEG or TIFF file from `reader`, and writes it into `writer` with
+/// the value of its `Orientation` tag (in ifd0) replaced by `orientation`,
+/// e.g. to normalize the orientation after a lossless rotation.
+///
+/// The value is patched in place, so the rest of the file (including the
+/// image data & the other metadata) is kept byte for byte.
+///
+/// Returns [`Error::WriteFailed`] if:
+///
+/// - `orientation` is not a valid Exif orientation (1 ~ 8);
+/// - the file is not a JPEG or TIFF file;
+/// - the file has no `Orientation` tag, since adding a tag requires
+///   rewriting the Exif data, see [`ExifWriter`](crate::ExifWriter).
+///
+/// ## Example
+///
+/// ```rust
+/// use nom_exif::*;
+/// use std::fs::File;
+///
+/// fn main() -> Result<()> {
+///     let mut output = Vec::new();
+///     set_orientation(File::open("./testdata/tif.tif")?, &mut output, 6)?;
+///
+///     let ms = MediaSource::seekable(std::io::Cursor::new(output))?;
+///     let iter: ExifIter = MediaParser::new().parse(ms)?;
+///     let exif: Exif = iter.into();
+///     assert_eq!(exif.get(ExifTag::Orientation), Some(&6u16.into()));
+///     Ok(())
+/// }
+/// ```
+pub fn set_orientation<R: Read, W: Write>(
+    mut reader: R,
+    mut writer: W,
+    orientation: u16,
+) -> crate::Result<()> {
+    if !(1..=8).contains(&orientation) {
+        return Err(Error::WriteFailed(
+            format!("invalid orientation: {orientation}").into(),
+        ));
+    }
+
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    let mime = Mime::try_from(&data[..])?;
+    if !matches!(
+        mime,
+        Mime::Image(MimeImage::Jpeg | MimeImage::Tiff | MimeImage::Pef | MimeImage::Srw)
+    ) {
+        return Err(Error::WriteFailed(
+            format!("setting orientation of {mime:?} files is not supported").into(),
+        ));
+    }
+
+    // Only the first Exif block of JPEG files is used by readers
+    let range = exif_tiff_ranges(&data, mime)?.into_iter().next();
+    let patched = match range {
+        Some(range) => patch_orientation(&mut data[range], orientation)?, // Safe-slice
+        None => false,
+    };
+    if !patched {
+        return Err(Error::WriteFailed("Orientation tag not found".into()));
+    }
+
+    writer.write_all(&data)?;
+    Ok(())
+}
+
+/// Replaces the value of the `Orientation` entry in ifd0 of the TIFF data,
+/// returns false if there is no such entry.
+fn patch_orientation(data: &mut [u8], orientation: u16) -> crate::Result<bool> {
+    let (_, header) = TiffHeader::parse(data)?;
+    let endian = header.endian;
+
+    let ifd0 = header.ifd0_offset as usize;
+    if u16_at(data, ifd0, endian).is_none() {
+        return Err("invalid TIFF data; ifd0 not found".into());
+    }
+    let (fields, _) =
+        ifd_fields(data, ifd0, endian).ok_or("invalid TIFF data; ifd0 is truncated")?;
+    let Some(field) = fields.iter().find(|x| x.code == ORIENTATION) else {
+        return Ok(false);
+    };
+    // The value (a SHORT) is stored in the first 2 bytes of the value field
+    if field.format != Some(DataFormat::U16) {
+        return Err(Error::WriteFailed(
+            "invalid Orientation tag; not a SHORT value".into(),
+        ));
+    }
+    let value = data
+        .get_mut(field.pos + 8..field.pos + 10)
+        .ok_or("invalid TIFF data; ifd0 is truncated")?;
+    value.copy_from_slice(&u16_bytes(orientation, endian));
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{
+        testkit::read_sample, Exif, ExifIter, ExifTag, ExifWriter, MediaParser, MediaSource,
+    };
+    use test_case::test_case;
+
+    fn parse_exif(data: Vec<u8>) -> Exif {
+        let ms = MediaSource::seekable(Cursor::new(data)).unwrap();
+        let iter: ExifIter = MediaParser::new().parse(ms).unwrap();
+        iter.into()
+    }
+
+    fn jpeg_with_orientation() -> Vec<u8> {
+        let mut writer = ExifWriter::new();
+        writer
+            .set(ExifTag::Make, "nom-exif")
+            .set(ExifTag::Orientation, 1u16);
+        let mut output = Vec::new();
+        writer
+            .write_jpeg(
+                Cursor::new(read_sample("no-exif.jpg").unwrap()),
+                &mut output,
+            )
+            .unwrap();
+        output
+    }
+
+    #[test_case(jpeg_with_orientation())]
+    #[test_case(read_sample("tif.tif").unwrap())]
+    fn rewrite_orientation(data: Vec<u8>) {
+        for orientation in [6, 8, 1] {
+            let mut output = Vec::new();
+            set_orientation(Cursor::new(&data), &mut output, orientation).unwrap();
+            assert_eq!(output.len(), data.len());
+            // Only the 2 bytes of the value are changed
+            let diff = data.iter().zip(&output).filter(|(a, b)| a != b).count();
+            assert!(diff <= 2);
+
+            let exif = parse_exif(output);
+            assert_eq!(exif.get(ExifTag::Orientation), Some(&orientation.into()));
+        }
+    }
+
+    #[test]
+    fn orientation() {
+        for v in 1..=8u16 {
+            let o = Orientation::try_from(v).unwrap();
+            assert_eq!(u16::from(o), v);
+            assert_eq!(o.swaps_dimensions(), v >= 5);
+            // Mirrored orientations are the even ones below 5 & the odd ones
+            // above
+            assert_eq!(o.transform().mirror, (v < 5) == (v % 2 == 0));
+        }
+        assert!(Orientation::try_from(0).is_err());
+        assert!(Orientation::try_from(9).is_err());
+        assert_eq!(
+            Orientation::MirrorVertical.transform(),
+            Transform {
+                rotation: 180,
+                mirror: true
+            }
+        );
+        assert_eq!(Orientation::Rotate180.apply_to_dimensions(4, 3), (4, 3));
+        assert_eq!(Orientation::Rotate270.apply_to_dimensions(4, 3), (3, 4));
+    }
+
+    #[test]
+    fn rewrite_orientation_failed() {
+        let data = jpeg_with_orientation();
+        let set = |data: Vec<u8>, orientation| {
+            set_orientation(Cursor::new(data), &mut Vec::new(), orientation)
+        };
+        assert!(set(data.clone(), 0).is_err());
+        assert!(set(data, 9).is_err());
+        // No Orientation tag
+        assert!(set(read_sample("exif.jpg").unwrap(), 6).is_err());
+        assert!(set(read_sample("no-exif.jpg").unwrap(), 6).is_err());
+        // Unsupported file type
+        assert!(set(read_sample("exif.heic").unwrap(), 6).is_err());
+    }
+}
@@ -0,0 +1,162 @@
+use crate::Xmp;
+
+/// Photo sphere / panorama information stored in the GPano XMP namespace
+/// ([`Xmp::NS_GPANO`]), see [`Xmp::pano_info`].
+///
+/// See: [Photo Sphere XMP
+/// Metadata](https://developers.google.com/streetview/spherical-metadata).
+///
+/// ```rust
+/// use nom_exif::*;
+///
+/// let xmp: Xmp = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+///  <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+///   <rdf:Description rdf:about=""
+///     xmlns:GPano="http://ns.google.com/photos/1.0/panorama/"
+///     GPano:ProjectionType="equirectangular"
+///     GPano:FullPanoWidthPixels="8192"
+///     GPano:FullPanoHeightPixels="4096"
+///     GPano:PoseHeadingDegrees="90.5"/>
+///  </rdf:RDF>
+/// </x:xmpmeta>"#.parse().unwrap();
+///
+/// let pano = xmp.pano_info().unwrap();
+/// assert!(pano.is_equirectangular());
+/// assert_eq!(pano.full_pano_size(), Some((8192, 4096)));
+/// assert_eq!(pano.pose_heading_degrees, Some(90.5));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PanoInfo {
+    /// e.g. "equirectangular", "cylindrical"
+    pub projection_type: Option<String>,
+    pub use_panorama_viewer: Option<bool>,
+
+    pub full_pano_width_pixels: Option<u32>,
+    pub full_pano_height_pixels: Option<u32>,
+    /// Size of the image, i.e. the cropped area of the full panorama
+    pub cropped_area_image_width_pixels: Option<u32>,
+    pub cropped_area_image_height_pixels: Option<u32>,
+    /// Position of the cropped area in the full panorama
+    pub cropped_area_left_pixels: Option<u32>,
+    pub cropped_area_top_pixels: Option<u32>,
+
+    /// Compass heading of the center of the image, 0 is north
+    pub pose_heading_degrees: Option<f64>,
+    pub pose_pitch_degrees: Option<f64>,
+    pub pose_roll_degrees: Option<f64>,
+
+    pub initial_view_heading_degrees: Option<f64>,
+    pub initial_view_pitch_degrees: Option<f64>,
+    pub initial_view_roll_degrees: Option<f64>,
+    pub initial_horizontal_fov_degrees: Option<f64>,
+}
+
+impl PanoInfo {
+    /// Whether the image should be rendered as an equirectangular panorama.
+    pub fn is_equirectangular(&self) -> bool {
+        self.projection_type
+            .as_deref()
+            .is_some_and(|x| x.eq_ignore_ascii_case("equirectangular"))
+    }
+
+    /// Returns (FullPanoWidthPixels, FullPanoHeightPixels).
+    pub fn full_pano_size(&self) -> Option<(u32, u32)> {
+        Some((self.full_pano_width_pixels?, self.full_pano_height_pixels?))
+    }
+
+    /// Returns `None` if there is no GPano property.
+    pub(crate) fn from_xmp(xmp: &Xmp) -> Option<PanoInfo> {
+        if !xmp.iter().any(|x| x.namespace == Xmp::NS_GPANO) {
+            return None;
+        }
+        let get = |name| {
+            xmp.get(Xmp::NS_GPANO, name)
+                .and_then(|x| x.as_str())
+                .map(|x| x.trim())
+        };
+        let get_u32 = |name| get(name).and_then(|x| x.parse::<u32>().ok());
+        let get_f64 = |name| get(name).and_then(|x| x.parse::<f64>().ok());
+
+        Some(PanoInfo {
+            projection_type: get("ProjectionType").map(|x| x.to_owned()),
+            use_panorama_viewer: get("UsePanoramaViewer").and_then(|x| {
+                match x.to_ascii_lowercase().as_str() {
+                    "true" | "1" => Some(true),
+                    "false" | "0" => Some(false),
+                    _ => None,
+                }
+            }),
+            full_pano_width_pixels: get_u32("FullPanoWidthPixels"),
+            full_pano_height_pixels: get_u32("FullPanoHeightPixels"),
+            cropped_area_image_width_pixels: get_u32("CroppedAreaImageWidthPixels"),
+            cropped_area_image_height_pixels: get_u32("CroppedAreaImageHeightPixels"),
+            cropped_area_left_pixels: get_u32("CroppedAreaLeftPixels"),
+            cropped_area_top_pixels: get_u32("CroppedAreaTopPixels"),
+            pose_heading_degrees: get_f64("PoseHeadingDegrees"),
+            pose_pitch_degrees: get_f64("PosePitchDegrees"),
+            pose_roll_degrees: get_f64("PoseRollDegrees"),
+            initial_view_heading_degrees: get_f64("InitialViewHeadingDegrees"),
+            initial_view_pitch_degrees: get_f64("InitialViewPitchDegrees"),
+            initial_view_roll_degrees: get_f64("InitialViewRollDegrees"),
+            initial_horizontal_fov_degrees: get_f64("InitialHorizontalFOVDegrees"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pano_info() {
+        // GPano properties may be serialized as elements too
+        let xmp: Xmp = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about=""
+    xmlns:GPano="http://ns.google.com/photos/1.0/panorama/"
+    GPano:UsePanoramaViewer="True"
+    GPano:CroppedAreaImageWidthPixels="4000"
+    GPano:CroppedAreaImageHeightPixels="2000"
+    GPano:CroppedAreaLeftPixels="96"
+    GPano:CroppedAreaTopPixels="1024">
+   <GPano:ProjectionType>cylindrical</GPano:ProjectionType>
+   <GPano:PosePitchDegrees>-1.5</GPano:PosePitchDegrees>
+   <GPano:InitialHorizontalFOVDegrees> 75 </GPano:InitialHorizontalFOVDegrees>
+   <GPano:FullPanoWidthPixels>x</GPano:FullPanoWidthPixels>
+  </rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>"#
+            .parse()
+            .unwrap();
+        let pano = xmp.pano_info().unwrap();
+        assert_eq!(
+            pano,
+            PanoInfo {
+                projection_type: Some("cylindrical".into()),
+                use_panorama_viewer: Some(true),
+                cropped_area_image_width_pixels: Some(4000),
+                cropped_area_image_height_pixels: Some(2000),
+                cropped_area_left_pixels: Some(96),
+                cropped_area_top_pixels: Some(1024),
+                pose_pitch_degrees: Some(-1.5),
+                initial_horizontal_fov_degrees: Some(75.0),
+                ..Default::default()
+            }
+        );
+        assert!(!pano.is_equirectangular());
+        assert_eq!(pano.full_pano_size(), None);
+    }
+
+    #[test]
+    fn no_pano_info() {
+        let xmp: Xmp = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about="" xmlns:xmp="http://ns.adobe.com/xap/1.0/"
+    xmp:Rating="5"/>
+ </rdf:RDF>
+</x:xmpmeta>"#
+            .parse()
+            .unwrap();
+        assert_eq!(xmp.pano_info(), None);
+    }
+}
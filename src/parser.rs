@@ -1,23 +1,38 @@
 use std::{
-    cmp::{max, min},
     fmt::{Debug, Display},
     fs::File,
     io::{self, Read, Seek},
     marker::PhantomData,
     net::TcpStream,
-    ops::Range,
     path::Path,
+    sync::{atomic::AtomicBool, Arc},
+    time::Duration,
 };
 
+use bytes::Bytes;
+
 use crate::{
-    buffer::Buffers,
-    error::{ParsedError, ParsingError, ParsingErrorState},
+    cache::{CacheKey, CacheableOutput, ParseCache},
+    dump::{dump_image, dump_unsupported, BoxScanner, DumpNode, MAX_LOAD_SIZE},
+    error::{ParsedError, ParsingErrorState},
     exif::{parse_exif_iter, TiffHeader},
-    file::Mime,
-    partial_vec::PartialVec,
+    file::{read_header, Mime, MimeVideo},
+    gain_map::{extract_gain_map_markers, read_gain_map},
+    icc::extract_icc_profile,
+    iptc::extract_iptc,
+    jfif::extract_jfif,
+    metadata::MetadataIter,
+    motion_photo::{extract_motion_photo_markers, locate_motion_photo_video},
+    parser_core::{
+        exif_mime, gain_map_mime, icc_mime, iptc_mime, jfif_mime, motion_photo_mime,
+        telemetry_mime, track_mime, xmp_mime, Action, Limits, ParserCore, Progress,
+    },
     skip::Skip,
-    video::parse_track_info,
-    ExifIter, Seekable, TrackInfo, Unseekable,
+    telemetry::{find_telemetry_tracks, read_telemetry},
+    video::{parse_track_info, TrackParsers},
+    xmp::extract_xmp,
+    CustomTag, ExifIter, GainMapInfo, IccProfile, Iptc, JfifInfo, MotionPhoto, Seekable, Telemetry,
+    TrackInfo, Unseekable, Xmp,
 };
 
 /// `MediaSource` represents a media data source that can be parsed by
@@ -27,6 +42,9 @@ use crate::{
 ///   a MediaSource from a file
 ///
 /// - Use `MediaSource::tcp_stream(stream)` to create a MediaSource from a `TcpStream`
+/// - Use `MediaSource::from_vec(data)`, `MediaSource::from_bytes(bytes)` or
+///   `MediaSource::from_slice(data)` to create a MediaSource from data in
+///   memory
 /// - In other cases:
 ///
 ///   - Use `MediaSource::seekable(reader)` to create a MediaSource from a `Read + Seek`
@@ -44,6 +62,11 @@ use crate::{
 pub struct MediaSource<R, S = Seekable> {
     pub(crate) reader: R,
     pub(crate) buf: Vec<u8>,
+    // The whole data of an in-memory source
+    pub(crate) data: Option<Bytes>,
+    // A handle of the `TcpStream` source to set its read timeout, see
+    // `MediaParserBuilder::timeout`
+    pub(crate) stream: Option<TcpStream>,
     pub(crate) mime: Mime,
     phantom: PhantomData<S>,
 }
@@ -59,20 +82,18 @@ impl<R, S: Skip<R>> Debug for MediaSource<R, S> {
 }
 
 // Should be enough for parsing header
-const HEADER_PARSE_BUF_SIZE: usize = 128;
+pub(crate) const HEADER_PARSE_BUF_SIZE: usize = 128;
 
 impl<R: Read, S: Skip<R>> MediaSource<R, S> {
     fn build(mut reader: R) -> crate::Result<Self> {
         // TODO: reuse MediaParser to parse header
-        let mut buf = Vec::with_capacity(HEADER_PARSE_BUF_SIZE);
-        reader
-            .by_ref()
-            .take(HEADER_PARSE_BUF_SIZE as u64)
-            .read_to_end(&mut buf)?;
+        let buf = read_header(&mut reader)?;
         let mime: Mime = buf.as_slice().try_into()?;
         Ok(Self {
             reader,
             buf,
+            data: None,
+            stream: None,
             mime,
             phantom: PhantomData,
         })
@@ -80,7 +101,7 @@ impl<R: Read, S: Skip<R>> MediaSource<R, S> {
 
     pub fn has_track(&self) -> bool {
         match self.mime {
-            Mime::Image(_) => false,
+            Mime::Image(_) | Mime::Xmp => false,
             Mime::Video(_) => true,
         }
     }
@@ -88,7 +109,7 @@ impl<R: Read, S: Skip<R>> MediaSource<R, S> {
     pub fn has_exif(&self) -> bool {
         match self.mime {
             Mime::Image(_) => true,
-            Mime::Video(_) => false,
+            Mime::Video(_) | Mime::Xmp => false,
         }
     }
 }
@@ -116,8 +137,62 @@ impl MediaSource<File, Seekable> {
 }
 
 impl MediaSource<TcpStream, Unseekable> {
+    /// Creates a `MediaSource` from a `TcpStream`.
+    ///
+    /// If the parser has a [`MediaParserBuilder::timeout`], the read timeout
+    /// of the stream is set to the remaining time before each read, so that a
+    /// stalled stream can't block the parsing. The original read timeout is
+    /// restored after parsing.
     pub fn tcp_stream(stream: TcpStream) -> crate::Result<Self> {
-        Self::unseekable(stream)
+        let handle = stream.try_clone()?;
+        let mut ms = Self::unseekable(stream)?;
+        ms.stream = Some(handle);
+        Ok(ms)
+    }
+}
+
+impl MediaSource<io::Empty, Seekable> {
+    /// Creates a `MediaSource` from [`Bytes`], which is used as the parsing
+    /// buffer without copying. The [`ExifIter`] parsed from it shares the
+    /// same memory rather than copying it.
+    ///
+    /// Since the data has been loaded entirely,
+    /// [`MediaParserBuilder::max_buffer`] doesn't apply to it.
+    ///
+    /// ```rust
+    /// use nom_exif::*;
+    ///
+    /// let data = std::fs::read("./testdata/exif.jpg").unwrap();
+    /// let ms = MediaSource::from_bytes(data.into()).unwrap();
+    /// let iter: ExifIter = MediaParser::new().parse(ms).unwrap();
+    /// let exif: Exif = iter.into();
+    /// assert_eq!(exif.get(ExifTag::Make).and_then(|x| x.as_str()), Some("vivo"));
+    /// ```
+    pub fn from_bytes(bytes: Bytes) -> crate::Result<Self> {
+        let mime = read_header(&mut &bytes[..])?.as_slice().try_into()?;
+        Ok(Self {
+            reader: io::empty(),
+            buf: Vec::new(),
+            data: Some(bytes),
+            stream: None,
+            mime,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Creates a `MediaSource` from a `Vec<u8>`, which is converted into
+    /// [`Bytes`] without copying, see [`Self::from_bytes`].
+    pub fn from_vec(data: Vec<u8>) -> crate::Result<Self> {
+        Self::from_bytes(data.into())
+    }
+
+    /// Creates a `MediaSource` from a slice, see [`Self::from_bytes`].
+    ///
+    /// The data is copied once, since the parsed results (e.g. [`ExifIter`])
+    /// don't borrow the input. Prefer [`Self::from_bytes`] or
+    /// [`Self::from_vec`] if you own the data.
+    pub fn from_slice(data: &[u8]) -> crate::Result<Self> {
+        Self::from_bytes(Bytes::copy_from_slice(data))
     }
 }
 
@@ -127,32 +202,66 @@ pub(crate) const MIN_GROW_SIZE: usize = 4096;
 // Max size of APP1 is 0xFFFF
 pub(crate) const MAX_GROW_SIZE: usize = 63 * 1024;
 
-pub(crate) trait Buf {
-    fn buffer(&self) -> &[u8];
-    fn clear(&mut self);
-
-    fn set_position(&mut self, pos: usize);
-    #[allow(unused)]
-    fn position(&self) -> usize;
-}
-
 #[derive(Debug, Clone)]
 pub(crate) enum ParsingState {
     TiffHeader(TiffHeader),
-    HeifExifSize(usize),
+    /// Exif data size, the orientation of the primary item (if any), and the
+    /// frame count of an image sequence (if any)
+    HeifExifSize(usize, Option<u16>, Option<u32>),
+    /// The buffer starts at the JPEG embedded in a RAF file
+    RafJpeg,
+    /// The buffer starts at the data (e.g. an XMP packet) of the given size
+    DataSize(usize),
 }
 
 impl Display for ParsingState {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             ParsingState::TiffHeader(h) => Display::fmt(&format!("ParsingState: {h:?})"), f),
-            ParsingState::HeifExifSize(n) => Display::fmt(&format!("ParsingState: {n}"), f),
+            ParsingState::HeifExifSize(n, ..) => Display::fmt(&format!("ParsingState: {n}"), f),
+            ParsingState::RafJpeg => Display::fmt("ParsingState: RAF JPEG", f),
+            ParsingState::DataSize(n) => Display::fmt(&format!("ParsingState: data {n}"), f),
         }
     }
 }
 
-pub(crate) trait BufParser: Buf + Debug {
-    fn fill_buf<R: Read>(&mut self, reader: &mut R, size: usize) -> io::Result<usize>;
+/// Performs the I/O actions requested by [`ParserCore`] with a `Read`.
+pub(crate) trait BufParser: Debug {
+    fn core(&self) -> &ParserCore;
+    fn core_mut(&mut self) -> &mut ParserCore;
+
+    /// Called before each read from the media source.
+    fn before_read(&self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn fill_buf<R: Read>(&mut self, reader: &mut R, size: usize) -> io::Result<usize> {
+        let size = self.core().fill_size(size)?;
+        self.before_read()?;
+        let buf = self.core_mut().buf_mut();
+        buf.reserve_exact(size);
+
+        let n = match reader.take(size as u64).read_to_end(buf) {
+            Ok(n) => n,
+            // The read timeout set by `before_read`
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) && self.core().remaining_time().is_some() =>
+            {
+                return Err(self.core().timed_out_error());
+            }
+            Err(e) => return Err(e),
+        };
+        if n == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        self.core_mut().filled(n);
+
+        Ok(n)
+    }
+
     fn load_and_parse<R: Read, S: Skip<R>, P, O>(
         &mut self,
         reader: &mut R,
@@ -178,37 +287,23 @@ pub(crate) trait BufParser: Buf + Debug {
     where
         P: FnMut(&[u8], usize, Option<ParsingState>) -> Result<O, ParsingErrorState>,
     {
-        if offset >= self.buffer().len() {
+        if offset >= self.core().buffer().len() {
             self.fill_buf(reader, MIN_GROW_SIZE)?;
         }
 
         let mut parsing_state: Option<ParsingState> = None;
         loop {
-            let res = parse(self.buffer(), offset, parsing_state.take());
-            match res {
-                Ok(o) => return Ok(o),
-                Err(es) => {
-                    tracing::debug!(?es);
-                    parsing_state = es.state;
-
-                    match es.err {
-                        ParsingError::ClearAndSkip(n) => {
-                            self.clear_and_skip::<R, S>(reader, n)?;
-                        }
-                        ParsingError::Need(i) => {
-                            tracing::debug!(need = i, "need more bytes");
-                            let to_read = max(i, MIN_GROW_SIZE);
-                            let to_read = min(to_read, MAX_GROW_SIZE);
-
-                            let n = self.fill_buf(reader, to_read)?;
-                            if n == 0 {
-                                return Err(ParsedError::NoEnoughBytes);
-                            }
-                            tracing::debug!(n, "actual read");
-                        }
-                        ParsingError::Failed(s) => return Err(ParsedError::Failed(s)),
+            let res = parse(self.core().buffer(), offset, parsing_state.take());
+            match self.core().step(res, &mut parsing_state)? {
+                Action::Done(o) => return Ok(o),
+                Action::Fill(size) => {
+                    let n = self.fill_buf(reader, size)?;
+                    if n == 0 {
+                        return Err(ParsedError::NoEnoughBytes);
                     }
+                    tracing::debug!(n, "actual read");
                 }
+                Action::Skip(n) => self.clear_and_skip::<R, S>(reader, n)?,
             }
         }
     }
@@ -219,37 +314,26 @@ pub(crate) trait BufParser: Buf + Debug {
         reader: &mut R,
         n: usize,
     ) -> Result<(), ParsedError> {
-        tracing::debug!("ClearAndSkip");
-        if n <= self.buffer().len() {
-            tracing::debug!(n, "skip by set_position");
-            self.set_position(n);
+        let Some(skip_n) = self.core_mut().start_skip(n) else {
             return Ok(());
-        }
-
-        let skip_n = n - self.buffer().len();
-        tracing::debug!(skip_n, "clear and skip bytes");
-        self.clear();
+        };
 
-        let done = S::skip_by_seek(reader, skip_n.try_into().unwrap())?;
+        let done = S::skip_by_seek(reader, skip_n as u64)?;
         if !done {
             tracing::debug!(skip_n, "skip by using our buffer");
             let mut skipped = 0;
-            while skipped < skip_n {
-                let n = self.fill_buf(reader, skip_n - skipped)?;
-                skipped += n;
-                if skipped <= skip_n {
-                    self.clear();
-                } else {
-                    let remain = skipped - skip_n;
-                    self.set_position(self.buffer().len() - remain);
+            loop {
+                skipped += self.fill_buf(reader, skip_n - skipped)?;
+                if self.core_mut().consume_skipped(skipped, skip_n) {
                     break;
                 }
             }
         } else {
             tracing::debug!(skip_n, "skip with seek");
+            self.core_mut().seek_skipped(skip_n);
         }
 
-        if self.buffer().is_empty() {
+        if self.core().buffer().is_empty() {
             self.fill_buf(reader, MIN_GROW_SIZE)?;
         }
         Ok(())
@@ -257,59 +341,124 @@ pub(crate) trait BufParser: Buf + Debug {
 }
 
 impl BufParser for MediaParser {
-    fn fill_buf<R: Read>(&mut self, reader: &mut R, size: usize) -> io::Result<usize> {
-        self.buf_mut().reserve_exact(size);
+    fn core(&self) -> &ParserCore {
+        &self.core
+    }
 
-        let n = reader.take(size as u64).read_to_end(self.buf_mut())?;
-        if n == 0 {
-            return Err(std::io::ErrorKind::UnexpectedEof.into());
-        }
+    fn core_mut(&mut self) -> &mut ParserCore {
+        &mut self.core
+    }
 
-        Ok(n)
+    fn before_read(&self) -> io::Result<()> {
+        if let (Some((stream, _)), Some(remaining)) = (&self.stream, self.core.remaining_time()) {
+            // A zero timeout is rejected by `set_read_timeout`
+            stream.set_read_timeout(Some(remaining.max(Duration::from_millis(1))))?;
+        }
+        Ok(())
     }
 }
 
-impl Buf for MediaParser {
-    fn buffer(&self) -> &[u8] {
-        &self.buf()[self.position..]
-    }
+pub trait ParseOutput<R, S>: Sized {
+    fn parse(parser: &mut MediaParser, ms: MediaSource<R, S>) -> crate::Result<Self>;
+}
 
-    fn clear(&mut self) {
-        self.buf_mut().clear();
+impl<R: Read, S: Skip<R>> ParseOutput<R, S> for ExifIter {
+    fn parse(parser: &mut MediaParser, mut ms: MediaSource<R, S>) -> crate::Result<Self> {
+        let img = exif_mime(ms.mime)?;
+        let mut iter = parse_exif_iter::<R, S>(parser, img, &mut ms.reader)?;
+        iter.custom_tags = parser.custom_tags.clone();
+        iter.limits = parser.core.limits();
+        Ok(iter)
     }
+}
 
-    fn set_position(&mut self, pos: usize) {
-        self.position = pos;
+impl<R: Read, S: Skip<R>> ParseOutput<R, S> for TrackInfo {
+    fn parse(parser: &mut MediaParser, mut ms: MediaSource<R, S>) -> crate::Result<Self> {
+        let v = track_mime(ms.mime)?;
+        let mut parsers = TrackParsers::new(parser.core.limits());
+        let res = parser.load_and_parse::<R, S, _, _>(ms.reader.by_ref(), |data, _| {
+            parse_track_info(data, v, &mut parsers).map_err(|e| ParsingErrorState::new(e, None))
+        });
+        match res {
+            Err(e) if e.is_eof() => parsers.finish(v).ok_or(e.into()),
+            res => Ok(res?),
+        }
     }
+}
 
-    fn position(&self) -> usize {
-        self.position
+impl<R: Read, S: Skip<R>> ParseOutput<R, S> for Xmp {
+    fn parse(parser: &mut MediaParser, mut ms: MediaSource<R, S>) -> crate::Result<Self> {
+        let mime = xmp_mime(ms.mime)?;
+        let res = parser.load_and_parse::<R, S, _, _>(ms.reader.by_ref(), |data, state| {
+            extract_xmp(mime, data, state)
+        });
+        match res {
+            Ok(Some(packets)) => Xmp::from_packets(&packets),
+            Ok(None) => Err("XMP not found".into()),
+            Err(e) if e.is_eof() => Err("XMP not found".into()),
+            Err(e) => Err(e.into()),
+        }
     }
 }
 
-pub trait ParseOutput<R, S>: Sized {
-    fn parse(parser: &mut MediaParser, ms: MediaSource<R, S>) -> crate::Result<Self>;
+impl<R: Read, S: Skip<R>> ParseOutput<R, S> for Iptc {
+    fn parse(parser: &mut MediaParser, mut ms: MediaSource<R, S>) -> crate::Result<Self> {
+        iptc_mime(ms.mime)?;
+        let iptc = parser
+            .load_and_parse::<R, S, _, _>(ms.reader.by_ref(), |data, _| extract_iptc(data))?;
+        iptc.ok_or_else(|| "IPTC not found".into())
+    }
 }
 
-impl<R: Read, S: Skip<R>> ParseOutput<R, S> for ExifIter {
+impl<R: Read, S: Skip<R>> ParseOutput<R, S> for JfifInfo {
     fn parse(parser: &mut MediaParser, mut ms: MediaSource<R, S>) -> crate::Result<Self> {
-        if !ms.has_exif() {
-            return Err(crate::Error::ParseFailed("no Exif data here".into()));
-        }
-        parse_exif_iter::<R, S>(parser, ms.mime.unwrap_image(), &mut ms.reader)
+        jfif_mime(ms.mime)?;
+        let jfif = parser
+            .load_and_parse::<R, S, _, _>(ms.reader.by_ref(), |data, _| extract_jfif(data))?;
+        jfif.ok_or_else(|| "JFIF not found".into())
     }
 }
 
-impl<R: Read, S: Skip<R>> ParseOutput<R, S> for TrackInfo {
+impl<R: Read, S: Skip<R>> ParseOutput<R, S> for IccProfile {
     fn parse(parser: &mut MediaParser, mut ms: MediaSource<R, S>) -> crate::Result<Self> {
-        if !ms.has_track() {
-            return Err(crate::Error::ParseFailed("no track info here".into()));
-        }
-        let out = parser.load_and_parse::<R, S, _, _>(ms.reader.by_ref(), |data, _| {
-            parse_track_info(data, ms.mime.unwrap_video())
-                .map_err(|e| ParsingErrorState::new(e, None))
+        let mime = icc_mime(ms.mime)?;
+        let data = parser.load_and_parse::<R, S, _, _>(ms.reader.by_ref(), |data, state| {
+            extract_icc_profile(mime, data, state)
         })?;
-        Ok(out)
+        IccProfile::new(data.ok_or("ICC profile not found")?)
+    }
+}
+
+impl<R: Read + Seek> ParseOutput<R, Seekable> for MotionPhoto {
+    fn parse(parser: &mut MediaParser, mut ms: MediaSource<R, Seekable>) -> crate::Result<Self> {
+        motion_photo_mime(ms.mime)?;
+        let markers = parser
+            .load_and_parse::<R, Seekable, _, _>(ms.reader.by_ref(), |data, _| {
+                extract_motion_photo_markers(data)
+            })?;
+        locate_motion_photo_video(&mut ms.reader, markers)
+    }
+}
+
+impl<R: Read + Seek> ParseOutput<R, Seekable> for GainMapInfo {
+    fn parse(parser: &mut MediaParser, mut ms: MediaSource<R, Seekable>) -> crate::Result<Self> {
+        let mime = gain_map_mime(ms.mime)?;
+        let markers = parser
+            .load_and_parse::<R, Seekable, _, _>(ms.reader.by_ref(), |data, _| {
+                extract_gain_map_markers(mime, data)
+            })?;
+        read_gain_map(&mut ms.reader, markers.ok_or("HDR gain map not found")?)
+    }
+}
+
+impl<R: Read + Seek> ParseOutput<R, Seekable> for Telemetry {
+    fn parse(parser: &mut MediaParser, mut ms: MediaSource<R, Seekable>) -> crate::Result<Self> {
+        telemetry_mime(ms.mime)?;
+        let tracks = parser
+            .load_and_parse::<R, Seekable, _, _>(ms.reader.by_ref(), |data, _| {
+                find_telemetry_tracks(data).map_err(|e| ParsingErrorState::new(e, None))
+            })?;
+        read_telemetry(&mut ms.reader, tracks)
     }
 }
 
@@ -363,43 +512,131 @@ impl<R: Read, S: Skip<R>> ParseOutput<R, S> for TrackInfo {
 ///     [(27, 1), (7, 1), (68, 100)].into(),
 /// );
 /// ```
+#[derive(Debug, Default)]
 pub struct MediaParser {
-    bb: Buffers,
-    buf: Option<Vec<u8>>,
-    position: usize,
+    core: ParserCore,
+    custom_tags: Arc<Vec<CustomTag>>,
+    // The `TcpStream` source being parsed with a timeout, and its original
+    // read timeout
+    stream: Option<(TcpStream, Option<Duration>)>,
 }
 
-impl Debug for MediaParser {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("MediaParser")
-            .field("buffers", &self.bb)
-            .field("buf len", &self.buf.as_ref().map(|x| x.len()))
-            .field("position", &self.position)
-            .finish_non_exhaustive()
-    }
+/// Builds a [`MediaParser`] (or an `AsyncMediaParser`) with custom resource
+/// limits, e.g. to bound the memory & CPU used per parse when processing
+/// untrusted uploads.
+///
+/// By default the parsing buffer starts with 4 KiB, and grows as needed
+/// without limit. The structural limits default to values which are far
+/// beyond what real files need.
+///
+/// ```rust
+/// use nom_exif::*;
+///
+/// let mut parser = MediaParser::builder()
+///     .max_buffer(1024 * 1024)
+///     .initial_buffer(64 * 1024)
+///     .max_ifd_entries(512)
+///     .build();
+/// let ms = MediaSource::file_path("./testdata/exif.jpg").unwrap();
+/// let iter: ExifIter = parser.parse(ms).unwrap();
+///
+/// // Too small for the metadata segments of exif.jpg
+/// let mut parser = MediaParser::builder().max_buffer(8 * 1024).build();
+/// let ms = MediaSource::file_path("./testdata/exif.jpg").unwrap();
+/// let res: Result<ExifIter> = parser.parse(ms);
+/// assert!(res.is_err());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MediaParserBuilder {
+    limits: Limits,
 }
 
-impl Default for MediaParser {
-    fn default() -> Self {
-        Self {
-            bb: Buffers::new(),
-            buf: None,
-            position: 0,
+impl MediaParserBuilder {
+    /// Sets the max size (in bytes) of the parsing buffer, parsing fails with
+    /// an error if the metadata doesn't fit in it.
+    ///
+    /// The data skipped by reading (e.g. the `mdat` box of an unseekable MP4
+    /// stream) is read in chunks of at most this size.
+    ///
+    /// [`MediaParser::parse_all`] loads images entirely, so they must fit in
+    /// the buffer.
+    pub fn max_buffer(mut self, size: usize) -> Self {
+        self.limits.max_buffer = size;
+        self
+    }
+
+    /// Sets the number of bytes (4 KiB by default) to read & reserve before
+    /// parsing, it's capped to [`Self::max_buffer`].
+    pub fn initial_buffer(mut self, size: usize) -> Self {
+        self.limits.initial_buffer = size;
+        self
+    }
+
+    /// Sets the max number of entries (1024 by default) which are parsed in
+    /// an IFD, the remaining entries of larger IFDs are ignored.
+    pub fn max_ifd_entries(mut self, n: usize) -> Self {
+        self.limits.max_ifd_entries = n;
+        self
+    }
+
+    /// Sets the max nesting depth of IFDs (8 by default), e.g. ifd0 → Exif
+    /// sub-IFD → Interop IFD is 3 levels deep. Deeper IFDs are ignored.
+    pub fn max_ifd_depth(mut self, n: usize) -> Self {
+        self.limits.max_ifd_depth = n;
+        self
+    }
+
+    /// Sets the max nesting depth (16 by default) of the ISOBMFF boxes which
+    /// are scanned by [`MediaParser::debug_dump`], deeper boxes are not
+    /// listed.
+    pub fn max_box_depth(mut self, n: usize) -> Self {
+        self.limits.max_box_depth = n;
+        self
+    }
+
+    /// Sets the max data size (64 MiB by default) of the EBML elements (e.g.
+    /// `Tags` & `Chapters`) which are loaded when parsing WebM/Matroska
+    /// files. Larger elements are skipped.
+    pub fn max_ebml_element_size(mut self, size: usize) -> Self {
+        self.limits.max_ebml_element_size = size;
+        self
+    }
+
+    /// Sets the max duration of each parsing (no limit by default), parsing
+    /// fails with an I/O error of kind [`io::ErrorKind::TimedOut`] once it's
+    /// exceeded.
+    ///
+    /// The timeout is checked before reading from the media source. For
+    /// sources created by [`MediaSource::tcp_stream`], the remaining time is
+    /// applied to each read as the read timeout of the stream as well, so a
+    /// stalled stream fails in time. Other blocking reads which never return
+    /// can't be interrupted by `MediaParser`, set a read timeout on them if
+    /// possible. `AsyncMediaParser` applies the timeout to each read.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.limits.timeout = Some(timeout);
+        self
+    }
+
+    /// Creates the parser.
+    pub fn build(self) -> MediaParser {
+        MediaParser {
+            core: ParserCore::new(self.limits()),
+            custom_tags: Arc::default(),
+            stream: None,
         }
     }
-}
 
-pub(crate) trait ShareBuf {
-    fn share_buf(&mut self, range: Range<usize>) -> PartialVec;
-}
+    /// Creates an `AsyncMediaParser` with the same limits.
+    #[cfg(feature = "async")]
+    pub fn build_async(self) -> crate::AsyncMediaParser {
+        crate::AsyncMediaParser::with_core(ParserCore::new(self.limits()))
+    }
 
-impl ShareBuf for MediaParser {
-    fn share_buf(&mut self, mut range: Range<usize>) -> PartialVec {
-        let buf = self.buf.take().unwrap();
-        let vec = self.bb.release_to_share(buf);
-        range.start += self.position;
-        range.end += self.position;
-        PartialVec::new(vec, range)
+    fn limits(&self) -> Limits {
+        Limits {
+            initial_buffer: self.limits.initial_buffer.min(self.limits.max_buffer),
+            ..self.limits
+        }
     }
 }
 
@@ -408,6 +645,40 @@ impl MediaParser {
         Self::default()
     }
 
+    /// Returns a [`MediaParserBuilder`] to create a parser with custom
+    /// buffer limits.
+    pub fn builder() -> MediaParserBuilder {
+        MediaParserBuilder::default()
+    }
+
+    /// Registers a custom tag, so that the entries of [`ExifIter`]s parsed by
+    /// this parser are reported with its name (and decoded in its data
+    /// format), see [`CustomTag`] & [`ExifIter::register_tag`].
+    pub fn register_tag(&mut self, tag: CustomTag) {
+        Arc::make_mut(&mut self.custom_tags).push(tag);
+    }
+
+    /// Registers a callback which is called with the [`Progress`] of each
+    /// parsing, whenever some bytes are read from or skipped in the media
+    /// source. Useful for displaying the progress of parsing large files,
+    /// e.g. a MOV file which stores its metadata at the end.
+    ///
+    /// The callback is called on the thread which parses the media source,
+    /// it should return quickly. It replaces the previously registered one.
+    pub fn on_progress(&mut self, f: impl FnMut(Progress) + Send + 'static) {
+        self.core.set_progress_callback(Some(Box::new(f)));
+    }
+
+    /// Sets a flag to cancel the parsing: once it's set to `true` (e.g. by
+    /// another thread), the ongoing parsing is aborted before reading more
+    /// data, and returns [`Error::Cancelled`](crate::Error::Cancelled).
+    ///
+    /// The flag is checked by every parsing of this parser, reset it to
+    /// `false` before parsing again.
+    pub fn cancel_on(&mut self, flag: Arc<AtomicBool>) {
+        self.core.set_cancel_flag(Some(flag));
+    }
+
     /// `MediaParser`/`AsyncMediaParser` comes with its own buffer management,
     /// so that buffers can be reused during multiple parsing processes to
     /// avoid frequent memory allocations. Therefore, try to reuse a
@@ -427,56 +698,298 @@ impl MediaParser {
     ///
     /// - For [`TrackInfo`] as parse output, you don't need to worry about
     ///   this, because `TrackInfo` dosn't reference the parsing buffer.
+    ///
+    /// ## Malformed input
+    ///
+    /// Parsing never panics, no matter how the input is truncated or
+    /// corrupted, so it's safe to be used on untrusted files. Malformed data
+    /// results in an `Err`, or in entries being skipped (e.g. Exif entries
+    /// with invalid offsets, or IFD reference loops). The same applies to
+    /// iterating an [`ExifIter`] and to `AsyncMediaParser`.
     pub fn parse<R: Read, S, O: ParseOutput<R, S>>(
         &mut self,
         mut ms: MediaSource<R, S>,
     ) -> crate::Result<O> {
-        self.reset();
-        self.acquire_buf();
-
-        self.buf_mut().append(&mut ms.buf);
+        self.begin(&mut ms);
         let res = self.do_parse(ms);
 
-        self.reset();
+        self.end();
         res
     }
 
-    fn do_parse<R: Read, S, O: ParseOutput<R, S>>(
+    /// Parses all kinds of metadata found in `ms` in a single pass, rather
+    /// than parsing each kind with [`Self::parse`], which reads the source
+    /// again every time.
+    ///
+    /// - Images (and XMP sidecar files) are loaded into memory once, then the
+    ///   Exif data, XMP packets, ICC profile, IPTC & JFIF info (whichever are
+    ///   supported by the format) are extracted from it. The [`ExifIter`]
+    ///   shares the loaded data. The whole image must fit in the parsing
+    ///   buffer, i.e. parsing fails if the image is larger than
+    ///   [`MediaParserBuilder::max_buffer`]; use [`Self::parse`] for each kind
+    ///   instead if the buffer is limited.
+    /// - For videos & audios, the [`TrackInfo`] is parsed.
+    ///
+    /// The GPS info (if any) of the Exif data or the track info is yielded
+    /// as [`Metadata::Gps`] too. Kinds which are not found, or fail to parse,
+    /// are skipped; `Err` is returned only if the source can't be read.
+    ///
+    /// ```rust
+    /// use nom_exif::*;
+    ///
+    /// let mut parser = MediaParser::new();
+    /// let ms = MediaSource::file_path("./testdata/exif.jpg").unwrap();
+    /// for item in parser.parse_all(ms).unwrap() {
+    ///     match item {
+    ///         Metadata::Exif(iter) => assert!(iter.count() > 0),
+    ///         Metadata::Gps(gps) => assert_eq!(gps.latitude_ref, 'N'),
+    ///         Metadata::Track(_) => unreachable!(),
+    ///         _ => (),
+    ///     }
+    /// }
+    /// ```
+    pub fn parse_all<R: Read, S: Skip<R>>(
         &mut self,
         mut ms: MediaSource<R, S>,
-    ) -> Result<O, crate::Error> {
-        self.fill_buf(&mut ms.reader, INIT_BUF_SIZE)?;
-        let res = ParseOutput::parse(self, ms)?;
-        Ok(res)
+    ) -> crate::Result<MetadataIter> {
+        if ms.has_track() {
+            let info: TrackInfo = self.parse(ms)?;
+            return Ok(MetadataIter::from_track_info(info));
+        }
+
+        self.begin(&mut ms);
+        let res = loop {
+            match self.fill_buf(&mut ms.reader, MAX_GROW_SIZE) {
+                Ok(_) => (),
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                    let len = self.core.buffer().len();
+                    break Ok(self.core.share_buf(0..len).data);
+                }
+                // The buffer is full, which is fine if the image ends here
+                Err(e) if e.kind() == io::ErrorKind::OutOfMemory => {
+                    match ms.reader.read(&mut [0]) {
+                        Ok(0) => {
+                            let len = self.core.buffer().len();
+                            break Ok(self.core.share_buf(0..len).data);
+                        }
+                        Ok(_) => break Err(e),
+                        Err(e) => break Err(e),
+                    }
+                }
+                Err(e) => break Err(e),
+            }
+        };
+        self.end();
+
+        Ok(MetadataIter::from_loaded(
+            ms.mime,
+            res?,
+            self.custom_tags.clone(),
+            self.core.limits(),
+        ))
+    }
+
+    /// Same as [`MediaParser::parse`], but the result is looked up in `cache`
+    /// by `key` first. `open` is only called (and the source is only read)
+    /// if there is no cached result. Successful results are stored in
+    /// `cache`, errors are not.
+    ///
+    /// For [`ExifIter`], the cached object doesn't reference the parsing
+    /// buffer, see [`ExifIter::into_owned_static`].
+    pub fn parse_cached<R: Read, S, O, F>(
+        &mut self,
+        cache: &ParseCache,
+        key: CacheKey,
+        open: F,
+    ) -> crate::Result<O>
+    where
+        O: ParseOutput<R, S> + CacheableOutput,
+        F: FnOnce() -> crate::Result<MediaSource<R, S>>,
+    {
+        if let Some(out) = cache.get::<O>(&key) {
+            tracing::debug!(?key, "parse result cache hit");
+            return Ok(out);
+        }
+
+        let out = self.parse::<R, S, O>(open()?)?.into_cacheable();
+        cache.insert(key, out.clone());
+        Ok(out)
+    }
+
+    /// Parses the file at `path` with caching, the cache key is made by
+    /// [`CacheKey::for_file`].
+    pub fn parse_file_cached<O, P>(&mut self, cache: &ParseCache, path: P) -> crate::Result<O>
+    where
+        O: ParseOutput<File, Seekable> + CacheableOutput,
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let key = CacheKey::for_file(path)?;
+        self.parse_cached(cache, key, || MediaSource::file_path(path))
     }
 
-    fn reset(&mut self) {
-        // Ensure buf has been released
-        if let Some(buf) = self.buf.take() {
-            self.bb.release(buf);
+    /// Parses a [`MotionPhoto`], if `with_track_info` is true, the
+    /// [`TrackInfo`] of the embedded video is parsed too (the video is loaded
+    /// into memory for this), see [`MotionPhoto::track_info`].
+    ///
+    /// `parser.parse::<_, _, MotionPhoto>(ms)` is the same as
+    /// `parser.parse_motion_photo(ms, false)`.
+    pub fn parse_motion_photo<R: Read + Seek>(
+        &mut self,
+        ms: MediaSource<R, Seekable>,
+        with_track_info: bool,
+    ) -> crate::Result<MotionPhoto> {
+        let MediaSource {
+            mut reader,
+            buf,
+            data,
+            stream,
+            mime,
+            ..
+        } = ms;
+        let ms = MediaSource {
+            reader: &mut reader,
+            buf,
+            data,
+            stream,
+            mime,
+            phantom: PhantomData::<Seekable>,
+        };
+        let mut photo: MotionPhoto = self.parse(ms)?;
+
+        if let Some(range) = photo.video_range().filter(|_| with_track_info) {
+            let mut video = Vec::new();
+            reader.seek(io::SeekFrom::Start(range.start))?;
+            reader
+                .take(range.end - range.start)
+                .read_to_end(&mut video)?;
+            match self.parse(MediaSource::from_vec(video)?) {
+                Ok(info) => photo.track_info = Some(info),
+                Err(e) => tracing::warn!(?e, "parse motion photo video failed"),
+            }
         }
+        Ok(photo)
+    }
+
+    /// Dumps the structure tree that the parser sees in `ms`: segments,
+    /// boxes, IFDs and entries, with their offsets and sizes. This is meant
+    /// for debugging, e.g. the dump can be attached when reporting a file
+    /// which can't be parsed.
+    ///
+    /// Images are loaded into memory (at most 64 MiB) for dumping. For
+    /// ISOBMFF videos, the top-level boxes are scanned one by one and only
+    /// the container boxes (e.g. `moov`) are loaded, other boxes (e.g.
+    /// `mdat`) are skipped.
+    ///
+    /// ```rust
+    /// use nom_exif::*;
+    ///
+    /// let mut parser = MediaParser::new();
+    /// let ms = MediaSource::file_path("./testdata/exif.jpg").unwrap();
+    /// let root = parser.debug_dump(ms).unwrap();
+    ///
+    /// let make = root.find(DumpKind::Entry, "Make").unwrap();
+    /// assert_eq!(make.note.as_deref(), Some("vivo"));
+    /// println!("{root}");
+    /// ```
+    pub fn debug_dump<R: Read, S: Skip<R>>(
+        &mut self,
+        mut ms: MediaSource<R, S>,
+    ) -> crate::Result<DumpNode> {
+        self.begin(&mut ms);
+        let res = self.do_dump(ms);
 
-        // Reset position
-        self.set_position(0);
+        self.end();
+        res
     }
 
-    pub(crate) fn buf(&self) -> &Vec<u8> {
-        match self.buf.as_ref() {
-            Some(b) => b,
-            None => panic!("no buf here"),
+    fn do_dump<R: Read, S: Skip<R>>(
+        &mut self,
+        mut ms: MediaSource<R, S>,
+    ) -> crate::Result<DumpNode> {
+        match ms.mime {
+            Mime::Image(img) => {
+                let truncated = loop {
+                    let len = self.core.buffer().len();
+                    if len >= MAX_LOAD_SIZE {
+                        break true;
+                    }
+                    match self.fill_buf(&mut ms.reader, len.max(MAX_GROW_SIZE)) {
+                        Ok(_) => (),
+                        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break false,
+                        Err(e) if e.kind() == io::ErrorKind::OutOfMemory => break true,
+                        Err(e) => return Err(e.into()),
+                    }
+                };
+                Ok(dump_image(
+                    img,
+                    self.core.buffer(),
+                    truncated,
+                    self.core.limits().max_box_depth,
+                ))
+            }
+            Mime::Video(
+                MimeVideo::Webm
+                | MimeVideo::Matroska
+                | MimeVideo::Gif
+                | MimeVideo::Avi
+                | MimeVideo::Wav
+                | MimeVideo::Mp3
+                | MimeVideo::Asf
+                | MimeVideo::MpegTs
+                | MimeVideo::Mxf
+                | MimeVideo::Dsf
+                | MimeVideo::Dff,
+            )
+            | Mime::Xmp => Ok(dump_unsupported(ms.mime)),
+            Mime::Video(_) => {
+                let mut scanner = BoxScanner::new(self.core.limits().max_box_depth);
+                let res = self
+                    .load_and_parse::<R, S, _, _>(ms.reader.by_ref(), |data, _| scanner.scan(data));
+                Ok(scanner.finish(ms.mime, res))
+            }
         }
     }
 
-    fn buf_mut(&mut self) -> &mut Vec<u8> {
-        match self.buf.as_mut() {
-            Some(b) => b,
-            None => panic!("no buf here"),
+    fn begin<R, S>(&mut self, ms: &mut MediaSource<R, S>) {
+        match ms.data.take() {
+            Some(data) => self.core.begin_shared(data),
+            None => self.core.begin(&mut ms.buf),
+        }
+        if let Some(stream) = ms
+            .stream
+            .take()
+            .filter(|_| self.core.limits().timeout.is_some())
+        {
+            if let Ok(timeout) = stream.read_timeout() {
+                self.stream = Some((stream, timeout));
+            }
         }
     }
 
-    fn acquire_buf(&mut self) {
-        assert!(self.buf.is_none());
-        self.buf = Some(self.bb.acquire());
+    fn end(&mut self) {
+        self.core.reset();
+        if let Some((stream, timeout)) = self.stream.take() {
+            let _ = stream.set_read_timeout(timeout);
+        }
+    }
+
+    fn do_parse<R: Read, S, O: ParseOutput<R, S>>(
+        &mut self,
+        mut ms: MediaSource<R, S>,
+    ) -> Result<O, crate::Error> {
+        // In-memory sources have been loaded entirely
+        if self.core.buffer().len() < self.core.limits().initial_buffer {
+            if let Err(e) = self.fill_buf(&mut ms.reader, self.core.limits().initial_buffer) {
+                // The whole file (e.g. a tiny GIF) may have been read as the
+                // header
+                if e.kind() != io::ErrorKind::UnexpectedEof || self.core.buffer().is_empty() {
+                    return Err(e.into());
+                }
+            }
+        }
+        let res = ParseOutput::parse(self, ms)?;
+        Ok(res)
     }
 }
 
@@ -562,8 +1075,399 @@ mod tests {
         }
     }
 
-    use crate::testkit::open_sample;
-    use crate::{EntryValue, ExifTag, TrackInfoTag};
+    #[cfg(feature = "serde")]
+    #[test_case("meta.mov")]
+    #[test_case("mkv_640x360.mkv")]
+    fn serde_track_info(path: &str) {
+        let mut parser = parser();
+        let ms = MediaSource::file_path(Path::new("testdata").join(path)).unwrap();
+        let info: TrackInfo = parser.parse(ms).unwrap();
+
+        let json = serde_json::to_string(&info).unwrap();
+        let de: TrackInfo = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            de.iter().collect::<Vec<_>>(),
+            info.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(de.get_gps_info(), info.get_gps_info());
+        assert_eq!(de.chapters(), info.chapters());
+        assert_eq!(de.fingerprint(), info.fingerprint());
+        assert_eq!(serde_json::to_string(&de).unwrap(), json);
+    }
+
+    #[test]
+    fn fingerprint() {
+        let mut parser = parser();
+        let mut exif = |path: &str| {
+            let ms = MediaSource::file_path(Path::new("testdata").join(path)).unwrap();
+            let iter: ExifIter = parser.parse(ms).unwrap();
+            Exif::from(iter).fingerprint()
+        };
+        assert_eq!(exif("exif.jpg"), exif("exif.jpg"));
+        assert_ne!(exif("exif.jpg"), exif("exif.heic"));
+
+        let mut track = |path: &str| {
+            let ms = MediaSource::file_path(Path::new("testdata").join(path)).unwrap();
+            let info: TrackInfo = parser.parse(ms).unwrap();
+            info.fingerprint()
+        };
+        assert_eq!(track("meta.mov"), track("meta.mov"));
+        assert_ne!(track("meta.mov"), track("meta.mp4"));
+    }
+
+    #[test_case("meta.mov")]
+    #[test_case("meta.mp4")]
+    #[test_case("embedded-in-heic.mov")]
+    #[test_case("mkv_640x360.mkv")]
+    fn buffer_limits(path: &str) {
+        let info = |parser: &mut MediaParser| -> crate::Result<TrackInfo> {
+            let ms = MediaSource::unseekable(open_sample(path).unwrap()).unwrap();
+            parser.parse(ms)
+        };
+        let expect = info(&mut parser()).unwrap();
+
+        // Skipped data is read in chunks
+        let mut limited = MediaParser::builder()
+            .max_buffer(64 * 1024)
+            .initial_buffer(1024 * 1024)
+            .build();
+        assert_eq!(limited.core.limits().initial_buffer, 64 * 1024);
+        let got = info(&mut limited).unwrap();
+        assert_eq!(
+            got.iter().collect::<Vec<_>>(),
+            expect.iter().collect::<Vec<_>>()
+        );
+
+        let mut tiny = MediaParser::builder().max_buffer(256).build();
+        assert!(info(&mut tiny).is_err());
+        // The parser is still usable after an error
+        let ms = MediaSource::file_path("testdata/exif-one-entry.heic").unwrap();
+        let _: ExifIter = limited.parse(ms).unwrap();
+    }
+
+    #[test_case("meta.mov")]
+    #[test_case("mkv_640x360.mkv")]
+    #[test_case("meta.mp4")]
+    fn in_memory_track_info(path: &str) {
+        let data = crate::testkit::read_sample(path).unwrap();
+        let ms = MediaSource::from_slice(&data).unwrap();
+        assert!(ms.has_track());
+        let info: TrackInfo = parser().parse(ms).unwrap();
+
+        let ms = MediaSource::file_path(Path::new("testdata").join(path)).unwrap();
+        let expect: TrackInfo = parser().parse(ms).unwrap();
+        assert_eq!(
+            info.iter().collect::<Vec<_>>(),
+            expect.iter().collect::<Vec<_>>()
+        );
+
+        assert!(MediaSource::from_vec(b"not a media file".to_vec()).is_err());
+    }
+
+    #[test_case("exif.jpg")]
+    #[test_case("exif.heic")]
+    #[test_case("no-exif.jpg")]
+    #[test_case("tif.tif")]
+    #[test_case("meta.mov")]
+    #[test_case("mkv_640x360.mkv")]
+    fn parse_all(path: &str) {
+        use crate::Metadata;
+
+        let path = Path::new("testdata").join(path);
+        let ms = || MediaSource::file_path(&path).unwrap();
+        let mut parser = parser();
+        let items: Vec<_> = parser.parse_all(ms()).unwrap().collect();
+        let kinds: Vec<_> = items.iter().map(std::mem::discriminant).collect();
+        let has = |x: Option<Metadata>| x.map(|x| std::mem::discriminant(&x));
+
+        // The same kinds are found as parsing them one by one
+        let exif = parser.parse::<_, _, ExifIter>(ms()).ok();
+        let gps = exif
+            .as_ref()
+            .and_then(|x| x.parse_gps_info().ok().flatten())
+            .map(Metadata::Gps);
+        let track = parser.parse::<_, _, TrackInfo>(ms()).ok();
+        let track_gps = track
+            .as_ref()
+            .and_then(|x| x.get_gps_info().cloned())
+            .map(Metadata::Gps);
+        let expect: Vec<_> = [
+            has(exif.map(Metadata::Exif)),
+            has(parser.parse(ms()).ok().map(Metadata::Xmp)),
+            has(parser.parse(ms()).ok().map(Metadata::Icc)),
+            has(parser.parse(ms()).ok().map(Metadata::Iptc)),
+            has(parser.parse(ms()).ok().map(Metadata::Jfif)),
+            has(track.map(Metadata::Track)),
+            has(gps.or(track_gps)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        assert_eq!(kinds, expect);
+
+        for item in items {
+            match item {
+                Metadata::Exif(iter) => {
+                    let expect: ExifIter = parser.parse(ms()).unwrap();
+                    assert_eq!(iter.count(), expect.count());
+                }
+                Metadata::Track(info) => {
+                    let expect: TrackInfo = parser.parse(ms()).unwrap();
+                    assert_eq!(info.fingerprint(), expect.fingerprint());
+                }
+                _ => (),
+            }
+        }
+    }
+
+    #[test_case("exif.jpg")]
+    #[test_case("tif.tif")]
+    fn parse_all_max_buffer(path: &str) {
+        let len = std::fs::metadata(Path::new("testdata").join(path))
+            .unwrap()
+            .len() as usize;
+        let ms = || MediaSource::unseekable(open_sample(path).unwrap()).unwrap();
+
+        // The image is loaded entirely, the metadata alone doesn't suffice
+        let mut limited = MediaParser::builder().max_buffer(len - 1).build();
+        let err = limited.parse_all(ms()).unwrap_err();
+        assert!(err.to_string().contains("limit exceeded"), "{err}");
+        let _: ExifIter = limited.parse(ms()).unwrap();
+
+        let mut parser = MediaParser::builder().max_buffer(len).build();
+        assert!(parser
+            .parse_all(ms())
+            .unwrap()
+            .any(|x| matches!(x, crate::Metadata::Exif(_))));
+    }
+
+    #[test]
+    fn structural_limits() {
+        let groups = |parser: &mut MediaParser| {
+            let ms = MediaSource::file_path("testdata/exif.jpg").unwrap();
+            let iter: ExifIter = parser.parse(ms).unwrap();
+            iter.map(|x| x.ifd_group()).collect::<Vec<_>>()
+        };
+        let count =
+            |groups: &[crate::IfdGroup], group| groups.iter().filter(|x| **x == group).count();
+
+        let all = groups(&mut parser());
+        assert!(count(&all, crate::IfdGroup::Ifd0) > 3);
+        assert!(count(&all, crate::IfdGroup::ExifIfd) > 3);
+
+        // The next IFD is still followed
+        let got = groups(&mut MediaParser::builder().max_ifd_entries(3).build());
+        assert_eq!(count(&got, crate::IfdGroup::Ifd0), 3);
+        assert_eq!(
+            count(&got, crate::IfdGroup::Ifd1),
+            count(&all, crate::IfdGroup::Ifd1).min(3)
+        );
+
+        let got = groups(&mut MediaParser::builder().max_ifd_depth(1).build());
+        assert_eq!(
+            count(&got, crate::IfdGroup::Ifd0),
+            count(&all, crate::IfdGroup::Ifd0)
+        );
+        assert_eq!(count(&got, crate::IfdGroup::ExifIfd), 0);
+        assert_eq!(count(&got, crate::IfdGroup::Gps), 0);
+
+        let mut parser = MediaParser::builder().max_ebml_element_size(16).build();
+        let ms = MediaSource::file_path("testdata/mkv_640x360.mkv").unwrap();
+        let res: crate::Result<TrackInfo> = parser.parse(ms);
+        assert!(res.is_err());
+
+        let mut parser = MediaParser::builder().max_box_depth(1).build();
+        let ms = MediaSource::file_path("testdata/meta.mov").unwrap();
+        let root = parser.debug_dump(ms).unwrap();
+        let moov = root.find(crate::DumpKind::Box, "moov").unwrap();
+        assert!(moov.children.iter().all(|x| x.children.is_empty()));
+        assert!(root.find(crate::DumpKind::Box, "mdia").is_none());
+    }
+
+    #[case("meta.mov", true)]
+    #[case("meta.mov", false)]
+    #[case("exif.jpg", true)]
+    fn progress(path: &str, seekable: bool) {
+        fn parse<R: Read, S: Skip<R>>(parser: &mut MediaParser, ms: MediaSource<R, S>) {
+            if ms.has_track() {
+                let _: TrackInfo = parser.parse(ms).unwrap();
+            } else {
+                let _: ExifIter = parser.parse(ms).unwrap();
+            }
+        }
+
+        let reports = Arc::new(Mutex::new(Vec::<Progress>::new()));
+        let r = reports.clone();
+        let mut parser = MediaParser::new();
+        parser.on_progress(move |p| r.lock().unwrap().push(p));
+
+        let f = open_sample(path).unwrap();
+        let size = f.metadata().unwrap().len();
+        if seekable {
+            parse(&mut parser, MediaSource::seekable(f).unwrap());
+        } else {
+            parse(&mut parser, MediaSource::unseekable(f).unwrap());
+        }
+
+        let reports = reports.lock().unwrap();
+        assert!(reports.windows(2).all(|x| {
+            x[0].bytes_read <= x[1].bytes_read && x[0].bytes_skipped <= x[1].bytes_skipped
+        }));
+        let last = reports.last().unwrap();
+        assert!(last.bytes_read > 0);
+        assert!(last.bytes_read + last.bytes_skipped <= size);
+        if !seekable {
+            assert_eq!(last.bytes_skipped, 0);
+        }
+    }
+
+    #[case("exif.jpg")]
+    #[case("meta.mov")]
+    #[case("mkv_640x360.mkv")]
+    fn cancel(path: &str) {
+        use std::sync::atomic::Ordering;
+
+        let flag = Arc::new(AtomicBool::new(false));
+        let mut parser = MediaParser::new();
+        parser.cancel_on(flag.clone());
+        let ms = || MediaSource::unseekable(open_sample(path).unwrap()).unwrap();
+
+        // Cancelled before parsing
+        flag.store(true, Ordering::Relaxed);
+        let res: crate::Result<MetadataIter> = parser.parse_all(ms());
+        assert!(matches!(res, Err(crate::Error::Cancelled)), "{res:?}");
+
+        // Cancelled during parsing
+        flag.store(false, Ordering::Relaxed);
+        let f = flag.clone();
+        parser.on_progress(move |_| f.store(true, Ordering::Relaxed));
+        let res: crate::Result<MetadataIter> = parser.parse_all(ms());
+        assert!(matches!(res, Err(crate::Error::Cancelled)), "{res:?}");
+
+        flag.store(false, Ordering::Relaxed);
+        parser.on_progress(|_| ());
+        assert!(parser.parse_all(ms()).is_ok());
+    }
+
+    #[test]
+    fn timeout() {
+        /// Reads slowly, in small chunks.
+        struct SlowReader(File);
+        impl Read for SlowReader {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                std::thread::sleep(Duration::from_millis(2));
+                let n = buf.len().min(4096);
+                self.0.read(&mut buf[..n])
+            }
+        }
+
+        let mut parser = MediaParser::builder()
+            .timeout(Duration::from_millis(50))
+            .build();
+        let ms = MediaSource::unseekable(SlowReader(open_sample("meta.mov").unwrap())).unwrap();
+        let res: crate::Result<TrackInfo> = parser.parse(ms);
+        assert!(
+            matches!(&res, Err(crate::Error::IOError(e)) if e.kind() == io::ErrorKind::TimedOut),
+            "{res:?}"
+        );
+
+        // The deadline is reset for each parsing
+        let ms = MediaSource::file_path("testdata/meta.mov").unwrap();
+        let _: TrackInfo = parser.parse(ms).unwrap();
+    }
+
+    #[test]
+    fn timeout_tcp_stream() {
+        use std::net::TcpListener;
+        use std::time::Instant;
+
+        // A stream which stalls after sending the beginning of the file
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (done_tx, done_rx) = std::sync::mpsc::channel::<()>();
+        let server = std::thread::spawn(move || {
+            let (mut conn, _) = listener.accept().unwrap();
+            let data = crate::testkit::read_sample("meta.mov").unwrap();
+            io::Write::write_all(&mut conn, &data[..32 * 1024]).unwrap();
+            // Keep the connection open until the parsing is done
+            let _ = done_rx.recv();
+        });
+
+        let stream = TcpStream::connect(addr).unwrap();
+        let mut parser = MediaParser::builder()
+            .timeout(Duration::from_millis(200))
+            .build();
+        let ms = MediaSource::tcp_stream(stream.try_clone().unwrap()).unwrap();
+        let start = Instant::now();
+        let res: crate::Result<TrackInfo> = parser.parse(ms);
+        assert!(
+            matches!(&res, Err(crate::Error::IOError(e)) if e.kind() == io::ErrorKind::TimedOut),
+            "{res:?}"
+        );
+        assert!(start.elapsed() < Duration::from_secs(5));
+        // The original read timeout is restored
+        assert_eq!(stream.read_timeout().unwrap(), None);
+
+        done_tx.send(()).unwrap();
+        server.join().unwrap();
+    }
+
+    #[test_case("exif.jpg")]
+    #[test_case("exif.heic")]
+    #[test_case("meta.mov")]
+    #[test_case("meta.mp4")]
+    #[test_case("mkv_640x360.mkv")]
+    fn binary_cache(path: &str) {
+        let mut parser = parser();
+        let ms = MediaSource::file_path(Path::new("testdata").join(path)).unwrap();
+        let bytes = if ms.has_exif() {
+            let iter: ExifIter = parser.parse(ms).unwrap();
+            let exif: Exif = iter.into();
+            let bytes = exif.to_bytes();
+            let de = Exif::from_bytes(&bytes).unwrap();
+            assert_eq!(
+                (&de).into_iter().collect::<Vec<_>>(),
+                (&exif).into_iter().collect::<Vec<_>>()
+            );
+            assert_eq!(de.get_gps_info().unwrap(), exif.get_gps_info().unwrap());
+            assert_eq!(de.xmp(), exif.xmp());
+            assert_eq!(de.fingerprint(), exif.fingerprint());
+            assert_eq!(de.to_bytes(), bytes);
+            assert!(TrackInfo::from_bytes(&bytes).is_err());
+            bytes
+        } else {
+            let info: TrackInfo = parser.parse(ms).unwrap();
+            let bytes = info.to_bytes();
+            let de = TrackInfo::from_bytes(&bytes).unwrap();
+            assert_eq!(
+                de.iter().collect::<Vec<_>>(),
+                info.iter().collect::<Vec<_>>()
+            );
+            assert_eq!(de.get_gps_info(), info.get_gps_info());
+            assert_eq!(
+                de.metadata().collect::<Vec<_>>(),
+                info.metadata().collect::<Vec<_>>()
+            );
+            assert_eq!(de.chapters(), info.chapters());
+            assert_eq!(de.fingerprint(), info.fingerprint());
+            assert_eq!(de.to_bytes(), bytes);
+            assert!(Exif::from_bytes(&bytes).is_err());
+            bytes
+        };
+
+        // Corrupted data
+        for n in [0, 5, bytes.len() / 2, bytes.len() - 1] {
+            assert!(Exif::from_bytes(&bytes[..n]).is_err());
+            assert!(TrackInfo::from_bytes(&bytes[..n]).is_err());
+        }
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(Exif::from_bytes(&trailing).is_err());
+        assert!(TrackInfo::from_bytes(&trailing).is_err());
+    }
+
+    use crate::testkit::{open_sample, read_sample};
+    use crate::{EntryValue, Exif, ExifIter, ExifTag, TrackInfoTag};
     use chrono::DateTime;
     use test_case::test_case;
 
@@ -573,11 +1477,17 @@ mod tests {
     #[test_case("mkv_640x360.mkv", ImageHeight, 360_u32.into())]
     #[test_case("mkv_640x360.mkv", DurationMs, 13346_u64.into())]
     #[test_case("mkv_640x360.mkv", CreateDate, DateTime::parse_from_str("2008-08-08T08:08:08Z", "%+").unwrap().into())]
+    #[test_case("mkv_640x360.mkv", Software, "Lavf57.83.100".into())]
+    #[test_case("webm_480.webm", Software, "Lavf57.83.100".into())]
     #[test_case("meta.mov", Make, "Apple".into())]
     #[test_case("meta.mov", Model, "iPhone X".into())]
     #[test_case("meta.mov", GpsIso6709, "+27.1281+100.2508+000.000/".into())]
+    #[test_case("meta.mov", FrameRate, 60_f64.into())]
+    #[test_case("embedded-in-heic.mov", ContentIdentifier, "DA1A7EE8-0925-4C9F-9266-DDA3F0BB80F0".into())]
+    #[test_case("meta.mp4", FrameRate, 30_f64.into())]
     #[test_case("meta.mp4", ImageWidth, 1920_u32.into())]
     #[test_case("meta.mp4", ImageHeight, 1080_u32.into())]
+    #[test_case("meta.mp4", Rotation, 90_u32.into())]
     #[test_case("meta.mp4", DurationMs, 1063_u64.into())]
     #[test_case("meta.mp4", GpsIso6709, "+27.2939+112.6932/".into())]
     #[test_case("meta.mp4", CreateDate, DateTime::parse_from_str("2024-02-03T07:05:38Z", "%+").unwrap().into())]
@@ -592,4 +1502,350 @@ mod tests {
         let info: TrackInfo = parser.parse(mf).unwrap();
         assert_eq!(info.get(tag).unwrap(), &v);
     }
+
+    /// `Info` & `Tracks` placed after a large `Cluster` should be reached by
+    /// seeking with the `SeekHead` index.
+    #[test]
+    fn webm_seek_head() {
+        use std::io::{Cursor, SeekFrom};
+
+        struct CountingReader {
+            inner: Cursor<Vec<u8>>,
+            read: usize,
+        }
+
+        impl Read for CountingReader {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                let n = self.inner.read(buf)?;
+                self.read += n;
+                Ok(n)
+            }
+        }
+
+        impl Seek for CountingReader {
+            fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+                self.inner.seek(pos)
+            }
+        }
+
+        const CLUSTER_SIZE: usize = 4 * 1024 * 1024;
+        let sample = read_sample("mkv_640x360.mkv").unwrap();
+        let (ebml, info, tracks) = (&sample[..47], &sample[130..227], &sample[229..387]);
+
+        let seek = |id: [u8; 4], pos: usize| {
+            let mut v = vec![0x4D, 0xBB, 0x92, 0x53, 0xAB, 0x84];
+            v.extend_from_slice(&id);
+            v.extend_from_slice(&[0x53, 0xAC, 0x88]);
+            v.extend_from_slice(&(pos as u64).to_be_bytes());
+            v
+        };
+        // SeekHead (47 bytes) + Cluster header (12 bytes) + Cluster data
+        let info_pos = 47 + 12 + CLUSTER_SIZE;
+        let mut seek_head = vec![0x11, 0x4D, 0x9B, 0x74, 0xAA];
+        seek_head.extend(seek([0x15, 0x49, 0xA9, 0x66], info_pos));
+        seek_head.extend(seek([0x16, 0x54, 0xAE, 0x6B], info_pos + info.len()));
+
+        let mut data = ebml.to_vec();
+        // Segment with unknown size
+        data.extend_from_slice(&[0x18, 0x53, 0x80, 0x67, 0x01, 0xFF, 0xFF, 0xFF]);
+        data.extend_from_slice(&[0xFF, 0xFF, 0xFF, 0xFF]);
+        data.extend(seek_head);
+        data.extend_from_slice(&[0x1F, 0x43, 0xB6, 0x75, 0x01, 0, 0, 0]);
+        data.extend_from_slice(&(CLUSTER_SIZE as u32).to_be_bytes());
+        data.resize(data.len() + CLUSTER_SIZE, 0);
+        data.extend_from_slice(info);
+        data.extend_from_slice(tracks);
+
+        let mut parser = parser();
+        let mut reader = CountingReader {
+            inner: Cursor::new(data),
+            read: 0,
+        };
+        let ms = MediaSource::seekable(&mut reader).unwrap();
+        let info: TrackInfo = parser.parse(ms).unwrap();
+        assert_eq!(info.get(ImageWidth), Some(&640_u32.into()));
+        assert_eq!(info.get(DurationMs), Some(&13346_u64.into()));
+        assert!(reader.read < 1024 * 1024, "read {} bytes", reader.read);
+    }
+
+    /// A >4 GB recording: `mdat` with `largesize`, version 1 `mvhd`, `tkhd` &
+    /// `mdhd` with 64-bit times, and `co64` offsets beyond 4 GB.
+    #[test]
+    fn large_mp4() {
+        use crate::testkit::{bbox, full_box, full_box_v1, table, SparseFile};
+
+        const MDAT_SIZE: u64 = 5 * 1024 * 1024 * 1024;
+        // 2050-01-01T00:00:00Z, which doesn't fit in 32 bits
+        const CREATION_TIME: u64 = 4_607_452_800;
+        let be64 = |x: u64| x.to_be_bytes();
+
+        let ftyp = bbox(b"ftyp", b"isom\0\0\0\0isom");
+        let mut mdat = 1_u32.to_be_bytes().to_vec();
+        mdat.extend_from_slice(b"mdat");
+        mdat.extend_from_slice(&(MDAT_SIZE + 16).to_be_bytes());
+        let mdat_start = (ftyp.len() + mdat.len()) as u64;
+
+        // CAMM GPS packet, placed beyond 4 GB
+        let packet = [&0_u16.to_le_bytes()[..], &5_u16.to_le_bytes()]
+            .concat()
+            .into_iter()
+            .chain([22.5_f64, 114.0, 10.0].iter().flat_map(|x| x.to_le_bytes()))
+            .collect::<Vec<_>>();
+        let packet_offset = mdat_start + 4_500_000_000;
+
+        // 6000 seconds in microseconds, which doesn't fit in 32 bits
+        let mvhd = full_box_v1(
+            b"mvhd",
+            &[
+                &be64(CREATION_TIME)[..],
+                &be64(CREATION_TIME),
+                &1_000_000_u32.to_be_bytes(),
+                &be64(6_000_000_000),
+                &[0; 76],
+                &3_u32.to_be_bytes(),
+            ]
+            .concat(),
+        );
+
+        let mut matrix = [0_u8; 36];
+        matrix[..4].copy_from_slice(&0x10000_u32.to_be_bytes());
+        matrix[16..20].copy_from_slice(&0x10000_u32.to_be_bytes());
+        matrix[32..].copy_from_slice(&0x40000000_u32.to_be_bytes());
+        let tkhd = full_box_v1(
+            b"tkhd",
+            &[
+                &be64(CREATION_TIME)[..],
+                &be64(CREATION_TIME),
+                &1_u32.to_be_bytes(),
+                &[0; 4],
+                &be64(6_000_000_000),
+                &[0; 16],
+                &matrix,
+                &(3840_u32 << 16).to_be_bytes(),
+                &(2160_u32 << 16).to_be_bytes(),
+            ]
+            .concat(),
+        );
+        let hdlr = full_box(b"hdlr", &[&[0; 4][..], b"vide", &[0; 13]].concat());
+        let video = bbox(b"trak", &[tkhd, bbox(b"mdia", &hdlr)].concat());
+
+        let mdhd = full_box_v1(
+            b"mdhd",
+            &[
+                &be64(CREATION_TIME)[..],
+                &be64(CREATION_TIME),
+                &1000_u32.to_be_bytes(),
+                &be64(6_000_000),
+                &[0; 4],
+            ]
+            .concat(),
+        );
+        let stbl = [
+            full_box(
+                b"stsd",
+                &[&1_u32.to_be_bytes()[..], &bbox(b"camm", &[0; 8])].concat(),
+            ),
+            full_box(b"stts", &table(&[&[1, 1000]])),
+            full_box(b"stsc", &table(&[&[1, 1, 1]])),
+            full_box(
+                b"stsz",
+                &[&0_u32.to_be_bytes()[..], &table(&[&[packet.len() as u32]])].concat(),
+            ),
+            full_box(
+                b"co64",
+                &[&1_u32.to_be_bytes()[..], &be64(packet_offset)].concat(),
+            ),
+        ]
+        .concat();
+        let mdia = bbox(
+            b"mdia",
+            &[mdhd, bbox(b"minf", &bbox(b"stbl", &stbl))].concat(),
+        );
+        let camm = bbox(b"trak", &mdia);
+        let moov = bbox(b"moov", &[mvhd, video, camm].concat());
+
+        let mut file = SparseFile::new(0);
+        file.put(0, [ftyp, mdat].concat());
+        file.put(packet_offset, packet);
+        file.put(mdat_start + MDAT_SIZE, moov);
+
+        let mut parser = parser();
+        let ms = MediaSource::seekable(&mut file).unwrap();
+        let info: TrackInfo = parser.parse(ms).unwrap();
+        assert_eq!(info.get(DurationMs), Some(&6_000_000_u64.into()));
+        assert_eq!(info.get(ImageWidth), Some(&3840_u32.into()));
+        assert_eq!(info.get(ImageHeight), Some(&2160_u32.into()));
+        assert_eq!(
+            info.get(CreateDate),
+            Some(
+                &DateTime::parse_from_str("2050-01-01T00:00:00Z", "%+")
+                    .unwrap()
+                    .into()
+            )
+        );
+        assert!(file.read < 1024 * 1024, "read {} bytes", file.read);
+
+        file.seek(io::SeekFrom::Start(0)).unwrap();
+        let ms = MediaSource::seekable(&mut file).unwrap();
+        let telemetry: Telemetry = parser.parse(ms).unwrap();
+        let gps = telemetry.gps();
+        assert_eq!(gps.len(), 1);
+        assert_eq!((gps[0].latitude, gps[0].longitude), (22.5, 114.0));
+    }
+
+    /// AVIF files locate Exif data the same way as HEIF files, so an AVIF
+    /// file is made from a HEIC sample by replacing the brands.
+    #[test_case(b"avif")]
+    #[test_case(b"avis")]
+    fn avif(brand: &[u8]) {
+        let heic = read_sample("exif.heic").unwrap();
+        let mut avif = heic.clone();
+        // ftyp: "heic" major brand, "mif1MiHEmiafMiHBheic" compatible brands
+        avif[8..12].copy_from_slice(brand);
+        avif[32..36].copy_from_slice(brand);
+
+        let mut parser = parser();
+        let ms = MediaSource::seekable(std::io::Cursor::new(avif)).unwrap();
+        assert!(ms.has_exif());
+        let exif: Exif = parser.parse::<_, _, ExifIter>(ms).unwrap().into();
+
+        let ms = MediaSource::seekable(std::io::Cursor::new(heic)).unwrap();
+        let expect: Exif = parser.parse::<_, _, ExifIter>(ms).unwrap().into();
+        assert_eq!(exif.get(ExifTag::Make), Some(&"Apple".into()));
+        assert_eq!(
+            exif.get(ExifTag::DateTimeOriginal),
+            expect.get(ExifTag::DateTimeOriginal)
+        );
+        assert_eq!(exif.get_gps_info().unwrap(), expect.get_gps_info().unwrap());
+    }
+
+    #[test_case("exif.jpg", (3072, 4096))]
+    #[test_case("exif.heic", (3024, 4032))]
+    #[test_case("meta.mp4", (1080, 1920))]
+    #[test_case("meta.mov", (720, 1280))]
+    #[test_case("mkv_640x360.mkv", (640, 360))]
+    fn display_dimensions(path: &str, dimensions: (u32, u32)) {
+        let mut parser = parser();
+
+        let ms = MediaSource::file(open_sample(path).unwrap()).unwrap();
+        let res = if ms.has_exif() {
+            let iter: ExifIter = parser.parse(ms).unwrap();
+            let exif: Exif = iter.into();
+            exif.display_dimensions()
+        } else {
+            let info: TrackInfo = parser.parse(ms).unwrap();
+            info.display_dimensions()
+        };
+        assert_eq!(res, Some(dimensions));
+    }
+
+    fn parse_anything(parser: &mut MediaParser, data: &[u8]) {
+        let Ok(ms) = MediaSource::seekable(std::io::Cursor::new(data)) else {
+            return;
+        };
+        if ms.has_exif() {
+            if let Ok(iter) = parser.parse::<_, _, ExifIter>(ms) {
+                let _ = iter.parse_gps_info();
+                let exif: Exif = iter.clone().into();
+                let _ = exif.display_dimensions();
+                iter.for_each(|x| {
+                    let _ = format!("{x:?}");
+                });
+            }
+        } else if ms.has_track() {
+            if let Ok(info) = parser.parse::<_, _, TrackInfo>(ms) {
+                let _ = info.display_dimensions();
+                info.iter().for_each(|x| {
+                    let _ = format!("{x:?}");
+                });
+            }
+            if let Ok(ms) = MediaSource::seekable(std::io::Cursor::new(data)) {
+                if let Ok(telemetry) = parser.parse::<_, _, Telemetry>(ms) {
+                    let _ = telemetry.gps_at(1.0);
+                }
+            }
+        }
+    }
+
+    /// Parsing randomly corrupted samples should never panic.
+    #[test_case("3gp_640x360.3gp")]
+    #[test_case("broken.jpg")]
+    #[test_case("embedded-in-heic.mov")]
+    #[test_case("exif-one-entry.heic")]
+    #[test_case("exif.heic")]
+    #[test_case("exif.jpg")]
+    #[test_case("meta.mov")]
+    #[test_case("meta.mp4")]
+    #[test_case("mka.mka")]
+    #[test_case("mkv_640x360.mkv")]
+    #[test_case("tif.tif")]
+    #[test_case("webm_480.webm")]
+    fn no_panic_on_corrupted_input(path: &str) {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        // Metadata usually resides near the head or the tail of a file
+        const REGION: usize = 64 * 1024;
+        const ROUNDS: usize = 300;
+
+        let mut rng = StdRng::seed_from_u64(0x6e6f6d);
+        let mut data = read_sample(path).unwrap();
+        let len = data.len();
+        let region = len.min(REGION);
+        let mut parser = MediaParser::new();
+        let mut failures = Vec::new();
+
+        for round in 0..ROUNDS {
+            let changes = (0..rng.gen_range(1..16))
+                .map(|_| {
+                    let pos = rng.gen_range(0..region);
+                    let pos = if rng.gen() { pos } else { len - 1 - pos };
+                    (pos, data[pos])
+                })
+                .collect::<Vec<_>>();
+            for (pos, _) in changes.iter() {
+                data[*pos] = rng.gen();
+            }
+            let end = if rng.gen_ratio(1, 4) {
+                rng.gen_range(0..len)
+            } else {
+                len
+            };
+
+            let res = catch_unwind(AssertUnwindSafe(|| {
+                parse_anything(&mut parser, &data[..end])
+            }));
+            if res.is_err() {
+                failures.push(round);
+                // The parser may be in an inconsistent state
+                parser = MediaParser::new();
+            }
+
+            for (pos, b) in changes.into_iter().rev() {
+                data[pos] = b;
+            }
+        }
+
+        assert!(failures.is_empty(), "panicked rounds: {failures:?}");
+    }
+
+    #[test]
+    fn register_tag() {
+        // ifd0 with a proprietary entry: 0xabcd, UNDEFINED, "ok\0"
+        let mut data = b"II*\0\x08\0\0\0\x01\0".to_vec();
+        data.extend([0xcd, 0xab, 7, 0, 3, 0, 0, 0, b'o', b'k', 0, 0, 0, 0, 0, 0]);
+
+        let mut parser = MediaParser::new();
+        parser.register_tag(CustomTag {
+            data_format: Some(2),
+            ..CustomTag::new(0xabcd, "CameraInfo")
+        });
+        for _ in 0..2 {
+            let ms = MediaSource::seekable(std::io::Cursor::new(&data)).unwrap();
+            let mut iter: ExifIter = parser.parse(ms).unwrap();
+            let entry = iter.next().unwrap();
+            assert_eq!(entry.name(), Some("CameraInfo"));
+            assert_eq!(entry.get_value(), Some(&"ok".into()));
+        }
+    }
 }
@@ -1,10 +1,9 @@
 use std::{
-    cmp::{max, min},
     fmt::Debug,
     io::{self},
     marker::PhantomData,
-    ops::Range,
     path::Path,
+    sync::{atomic::AtomicBool, Arc},
 };
 
 use tokio::{
@@ -13,15 +12,27 @@ use tokio::{
 };
 
 use crate::{
-    buffer::Buffers,
-    error::{ParsedError, ParsingError, ParsingErrorState},
+    dump::{dump_image, dump_unsupported, BoxScanner, DumpNode, MAX_LOAD_SIZE},
+    error::{ParsedError, ParsingErrorState},
     exif::parse_exif_iter_async,
-    file::Mime,
-    parser::{Buf, ParsingState, ShareBuf, INIT_BUF_SIZE, MAX_GROW_SIZE, MIN_GROW_SIZE},
-    partial_vec::PartialVec,
+    file::{more_header_size, Mime, MimeVideo},
+    gain_map::{extract_gain_map_markers, read_gain_map_async},
+    icc::extract_icc_profile,
+    iptc::extract_iptc,
+    jfif::extract_jfif,
+    metadata::MetadataIter,
+    motion_photo::{extract_motion_photo_markers, locate_motion_photo_video_async},
+    parser::{ParsingState, MAX_GROW_SIZE, MIN_GROW_SIZE},
+    parser_core::{
+        exif_mime, gain_map_mime, icc_mime, iptc_mime, jfif_mime, motion_photo_mime,
+        telemetry_mime, track_mime, xmp_mime, Action, ParserCore, Progress,
+    },
     skip::AsyncSkip,
-    video::parse_track_info,
-    ExifIter, Seekable, TrackInfo, Unseekable,
+    telemetry::{find_telemetry_tracks, read_telemetry_async},
+    video::{parse_track_info, TrackParsers},
+    xmp::extract_xmp,
+    CustomTag, ExifIter, GainMapInfo, IccProfile, Iptc, JfifInfo, MotionPhoto, Seekable, Telemetry,
+    TrackInfo, Unseekable, Xmp,
 };
 
 // Should be enough for parsing header
@@ -42,6 +53,17 @@ impl<R: AsyncRead + Unpin, S: AsyncSkip<R>> AsyncMediaSource<R, S> {
             .take(HEADER_PARSE_BUF_SIZE as u64)
             .read_to_end(&mut buf)
             .await?;
+        // The same as `read_header` of sync sources
+        while let Some(size) = more_header_size(&buf) {
+            let len = buf.len();
+            (&mut reader)
+                .take((size - len) as u64)
+                .read_to_end(&mut buf)
+                .await?;
+            if buf.len() < size {
+                break;
+            }
+        }
         let mime: Mime = buf.as_slice().try_into()?;
         Ok(Self {
             reader,
@@ -53,7 +75,7 @@ impl<R: AsyncRead + Unpin, S: AsyncSkip<R>> AsyncMediaSource<R, S> {
 
     pub fn has_track(&self) -> bool {
         match self.mime {
-            Mime::Image(_) => false,
+            Mime::Image(_) | Mime::Xmp => false,
             Mime::Video(_) => true,
         }
     }
@@ -61,7 +83,7 @@ impl<R: AsyncRead + Unpin, S: AsyncSkip<R>> AsyncMediaSource<R, S> {
     pub fn has_exif(&self) -> bool {
         match self.mime {
             Mime::Image(_) => true,
-            Mime::Video(_) => false,
+            Mime::Video(_) | Mime::Xmp => false,
         }
     }
 }
@@ -88,20 +110,45 @@ impl AsyncMediaSource<File, Seekable> {
     }
 }
 
-pub(crate) trait AsyncBufParser: Buf + Debug {
+/// Performs the I/O actions requested by [`ParserCore`] with an `AsyncRead`,
+/// see [`BufParser`](crate::parser::BufParser) for the sync version.
+pub(crate) trait AsyncBufParser: Debug {
+    fn core(&self) -> &ParserCore;
+    fn core_mut(&mut self) -> &mut ParserCore;
+
     async fn fill_buf<R: AsyncRead + Unpin>(
         &mut self,
         reader: &mut R,
         size: usize,
-    ) -> io::Result<usize>;
+    ) -> io::Result<usize> {
+        let size = self.core().fill_size(size)?;
+        let remaining = self.core().remaining_time();
+        let buf = self.core_mut().buf_mut();
+        buf.reserve_exact(size);
+
+        let mut reader = reader.take(size as u64);
+        let read = reader.read_to_end(buf);
+        let n = match remaining {
+            Some(t) => tokio::time::timeout(t, read)
+                .await
+                .map_err(|_| self.core().timed_out_error())??,
+            None => read.await?,
+        };
+        if n == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        self.core_mut().filled(n);
+
+        Ok(n)
+    }
 
     async fn load_and_parse<R: AsyncRead + Unpin, S: AsyncSkip<R>, P, O>(
         &mut self,
         reader: &mut R,
-        parse: P,
+        mut parse: P,
     ) -> Result<O, ParsedError>
     where
-        P: Fn(&[u8], Option<ParsingState>) -> Result<O, ParsingErrorState>,
+        P: FnMut(&[u8], Option<ParsingState>) -> Result<O, ParsingErrorState>,
     {
         self.load_and_parse_with_offset::<R, S, _, _>(
             reader,
@@ -115,43 +162,29 @@ pub(crate) trait AsyncBufParser: Buf + Debug {
     async fn load_and_parse_with_offset<R: AsyncRead + Unpin, S: AsyncSkip<R>, P, O>(
         &mut self,
         reader: &mut R,
-        parse: P,
+        mut parse: P,
         offset: usize,
     ) -> Result<O, ParsedError>
     where
-        P: Fn(&[u8], usize, Option<ParsingState>) -> Result<O, ParsingErrorState>,
+        P: FnMut(&[u8], usize, Option<ParsingState>) -> Result<O, ParsingErrorState>,
     {
-        if offset >= self.buffer().len() {
+        if offset >= self.core().buffer().len() {
             self.fill_buf(reader, MIN_GROW_SIZE).await?;
         }
 
         let mut parsing_state: Option<ParsingState> = None;
         loop {
-            let res = parse(self.buffer(), offset, parsing_state.take());
-            match res {
-                Ok(o) => return Ok(o),
-                Err(es) => {
-                    tracing::debug!(?es);
-                    parsing_state = es.state;
-
-                    match es.err {
-                        ParsingError::ClearAndSkip(n) => {
-                            self.clear_and_skip::<R, S>(reader, n).await?;
-                        }
-                        ParsingError::Need(i) => {
-                            tracing::debug!(need = i, "need more bytes");
-                            let to_read = max(i, MIN_GROW_SIZE);
-                            let to_read = min(to_read, MAX_GROW_SIZE);
-
-                            let n = self.fill_buf(reader, to_read).await?;
-                            if n == 0 {
-                                return Err(ParsedError::NoEnoughBytes);
-                            }
-                            tracing::debug!(actual_read = n, "has been read");
-                        }
-                        ParsingError::Failed(s) => return Err(ParsedError::Failed(s)),
+            let res = parse(self.core().buffer(), offset, parsing_state.take());
+            match self.core().step(res, &mut parsing_state)? {
+                Action::Done(o) => return Ok(o),
+                Action::Fill(size) => {
+                    let n = self.fill_buf(reader, size).await?;
+                    if n == 0 {
+                        return Err(ParsedError::NoEnoughBytes);
                     }
+                    tracing::debug!(n, "actual read");
                 }
+                Action::Skip(n) => self.clear_and_skip::<R, S>(reader, n).await?,
             }
         }
     }
@@ -162,37 +195,26 @@ pub(crate) trait AsyncBufParser: Buf + Debug {
         reader: &mut R,
         n: usize,
     ) -> Result<(), ParsedError> {
-        tracing::debug!("ClearAndSkip");
-        if n <= self.buffer().len() {
-            tracing::debug!(n, "skip by set_position");
-            self.set_position(n);
+        let Some(skip_n) = self.core_mut().start_skip(n) else {
             return Ok(());
-        }
-
-        let skip_n = n - self.buffer().len();
-        tracing::debug!(skip_n, "clear and skip bytes");
-        self.clear();
+        };
 
-        let done = S::skip_by_seek(reader, skip_n.try_into().unwrap()).await?;
+        let done = S::skip_by_seek(reader, skip_n as u64).await?;
         if !done {
             tracing::debug!(skip_n, "skip by using our buffer");
             let mut skipped = 0;
-            while skipped < skip_n {
-                let n = self.fill_buf(reader, skip_n - skipped).await?;
-                skipped += n;
-                if skipped <= skip_n {
-                    self.clear();
-                } else {
-                    let remain = skipped - skip_n;
-                    self.set_position(self.buffer().len() - remain);
+            loop {
+                skipped += self.fill_buf(reader, skip_n - skipped).await?;
+                if self.core_mut().consume_skipped(skipped, skip_n) {
                     break;
                 }
             }
         } else {
             tracing::debug!(skip_n, "skip with seek");
+            self.core_mut().seek_skipped(skip_n);
         }
 
-        if self.buffer().is_empty() {
+        if self.core().buffer().is_empty() {
             self.fill_buf(reader, MIN_GROW_SIZE).await?;
         }
         Ok(())
@@ -211,31 +233,136 @@ impl<R: AsyncRead + Unpin + Send, S: AsyncSkip<R> + Send> AsyncParseOutput<R, S>
         parser: &mut AsyncMediaParser,
         mut ms: AsyncMediaSource<R, S>,
     ) -> crate::Result<Self> {
-        if !ms.has_exif() {
-            return Err(crate::Error::ParseFailed("no Exif data here".into()));
-        }
-        parse_exif_iter_async::<R, S>(parser, ms.mime.unwrap_image(), &mut ms.reader).await
+        let img = exif_mime(ms.mime)?;
+        let mut iter = parse_exif_iter_async::<R, S>(parser, img, &mut ms.reader).await?;
+        iter.custom_tags = parser.custom_tags.clone();
+        iter.limits = parser.core.limits();
+        Ok(iter)
     }
 }
 
 impl<R: AsyncRead + Unpin + Send, S: AsyncSkip<R> + Send> AsyncParseOutput<R, S> for TrackInfo {
     async fn parse(
         parser: &mut AsyncMediaParser,
-        ms: AsyncMediaSource<R, S>,
+        mut ms: AsyncMediaSource<R, S>,
     ) -> crate::Result<Self> {
-        let mut ms = ms;
-        let out = match ms.mime {
-            Mime::Image(_) => return Err("not a track".into()),
-            Mime::Video(v) => {
-                parser
-                    .load_and_parse::<R, S, _, _>(&mut ms.reader, |data, _| {
-                        parse_track_info(data, v).map_err(|e| ParsingErrorState::new(e, None))
-                    })
-                    .await?
-            }
-        };
+        let v = track_mime(ms.mime)?;
+        let mut parsers = TrackParsers::new(parser.core.limits());
+        let out = parser
+            .load_and_parse::<R, S, _, _>(&mut ms.reader, |data, _| {
+                parse_track_info(data, v, &mut parsers).map_err(|e| ParsingErrorState::new(e, None))
+            })
+            .await;
+        match out {
+            Err(e) if e.is_eof() => parsers.finish(v).ok_or(e.into()),
+            res => Ok(res?),
+        }
+    }
+}
+
+impl<R: AsyncRead + Unpin + Send, S: AsyncSkip<R> + Send> AsyncParseOutput<R, S> for Xmp {
+    async fn parse(
+        parser: &mut AsyncMediaParser,
+        mut ms: AsyncMediaSource<R, S>,
+    ) -> crate::Result<Self> {
+        let mime = xmp_mime(ms.mime)?;
+        let out = parser
+            .load_and_parse::<R, S, _, _>(&mut ms.reader, |data, state| {
+                extract_xmp(mime, data, state)
+            })
+            .await;
+        match out {
+            Ok(Some(packets)) => Xmp::from_packets(&packets),
+            Ok(None) => Err("XMP not found".into()),
+            Err(e) if e.is_eof() => Err("XMP not found".into()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+impl<R: AsyncRead + Unpin + Send, S: AsyncSkip<R> + Send> AsyncParseOutput<R, S> for Iptc {
+    async fn parse(
+        parser: &mut AsyncMediaParser,
+        mut ms: AsyncMediaSource<R, S>,
+    ) -> crate::Result<Self> {
+        iptc_mime(ms.mime)?;
+        let iptc = parser
+            .load_and_parse::<R, S, _, _>(&mut ms.reader, |data, _| extract_iptc(data))
+            .await?;
+        iptc.ok_or_else(|| "IPTC not found".into())
+    }
+}
+
+impl<R: AsyncRead + Unpin + Send, S: AsyncSkip<R> + Send> AsyncParseOutput<R, S> for JfifInfo {
+    async fn parse(
+        parser: &mut AsyncMediaParser,
+        mut ms: AsyncMediaSource<R, S>,
+    ) -> crate::Result<Self> {
+        jfif_mime(ms.mime)?;
+        let jfif = parser
+            .load_and_parse::<R, S, _, _>(&mut ms.reader, |data, _| extract_jfif(data))
+            .await?;
+        jfif.ok_or_else(|| "JFIF not found".into())
+    }
+}
+
+impl<R: AsyncRead + Unpin + Send, S: AsyncSkip<R> + Send> AsyncParseOutput<R, S> for IccProfile {
+    async fn parse(
+        parser: &mut AsyncMediaParser,
+        mut ms: AsyncMediaSource<R, S>,
+    ) -> crate::Result<Self> {
+        let mime = icc_mime(ms.mime)?;
+        let data = parser
+            .load_and_parse::<R, S, _, _>(&mut ms.reader, |data, state| {
+                extract_icc_profile(mime, data, state)
+            })
+            .await?;
+        IccProfile::new(data.ok_or("ICC profile not found")?)
+    }
+}
+
+impl<R: AsyncRead + AsyncSeek + Unpin + Send> AsyncParseOutput<R, Seekable> for MotionPhoto {
+    async fn parse(
+        parser: &mut AsyncMediaParser,
+        mut ms: AsyncMediaSource<R, Seekable>,
+    ) -> crate::Result<Self> {
+        motion_photo_mime(ms.mime)?;
+        let markers = parser
+            .load_and_parse::<R, Seekable, _, _>(&mut ms.reader, |data, _| {
+                extract_motion_photo_markers(data)
+            })
+            .await?;
+        locate_motion_photo_video_async(&mut ms.reader, markers).await
+    }
+}
 
-        Ok(out)
+impl<R: AsyncRead + AsyncSeek + Unpin + Send> AsyncParseOutput<R, Seekable> for GainMapInfo {
+    async fn parse(
+        parser: &mut AsyncMediaParser,
+        mut ms: AsyncMediaSource<R, Seekable>,
+    ) -> crate::Result<Self> {
+        let mime = gain_map_mime(ms.mime)?;
+        let markers = parser
+            .load_and_parse::<R, Seekable, _, _>(&mut ms.reader, |data, _| {
+                extract_gain_map_markers(mime, data)
+            })
+            .await?;
+        read_gain_map_async(&mut ms.reader, markers.ok_or("HDR gain map not found")?).await
+    }
+}
+
+impl<R: AsyncRead + AsyncSeek + Unpin + Send> AsyncParseOutput<R, Seekable> for Telemetry {
+    async fn parse(
+        parser: &mut AsyncMediaParser,
+        mut ms: AsyncMediaSource<R, Seekable>,
+    ) -> crate::Result<Self> {
+        telemetry_mime(ms.mime)?;
+        let tracks = parser
+            .load_and_parse::<R, Seekable, _, _>(&mut ms.reader, |data, _| {
+                find_telemetry_tracks(data).map_err(|e| ParsingErrorState::new(e, None))
+            })
+            .await?;
+        read_telemetry_async(&mut ms.reader, tracks).await
     }
 }
 
@@ -286,20 +413,10 @@ impl<R: AsyncRead + Unpin + Send, S: AsyncSkip<R> + Send> AsyncParseOutput<R, S>
 ///     Ok(())
 /// }
 /// ```
+#[derive(Debug, Default)]
 pub struct AsyncMediaParser {
-    bb: Buffers,
-    buf: Option<Vec<u8>>,
-    position: usize,
-}
-
-impl Debug for AsyncMediaParser {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("MediaParser")
-            .field("buffers", &self.bb)
-            .field("buf len", &self.buf.as_ref().map(|x| x.len()))
-            .field("position", &self.position)
-            .finish_non_exhaustive()
-    }
+    core: ParserCore,
+    custom_tags: Arc<Vec<CustomTag>>,
 }
 
 impl<R, S: AsyncSkip<R>> Debug for AsyncMediaSource<R, S> {
@@ -312,29 +429,46 @@ impl<R, S: AsyncSkip<R>> Debug for AsyncMediaSource<R, S> {
     }
 }
 
-impl Default for AsyncMediaParser {
-    fn default() -> Self {
+impl AsyncMediaParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a parser with custom buffer limits, see
+    /// [`MediaParserBuilder::build_async`](crate::MediaParserBuilder::build_async).
+    pub(crate) fn with_core(core: ParserCore) -> Self {
         Self {
-            bb: Buffers::new(),
-            buf: None,
-            position: 0,
+            core,
+            custom_tags: Arc::default(),
         }
     }
-}
 
-impl ShareBuf for AsyncMediaParser {
-    fn share_buf(&mut self, mut range: Range<usize>) -> PartialVec {
-        let buf = self.buf.take().unwrap();
-        let vec = self.bb.release_to_share(buf);
-        range.start += self.position;
-        range.end += self.position;
-        PartialVec::new(vec, range)
+    /// Registers a custom tag, so that the entries of [`ExifIter`]s parsed by
+    /// this parser are reported with its name (and decoded in its data
+    /// format), see [`CustomTag`] & [`ExifIter::register_tag`].
+    pub fn register_tag(&mut self, tag: CustomTag) {
+        Arc::make_mut(&mut self.custom_tags).push(tag);
     }
-}
 
-impl AsyncMediaParser {
-    pub fn new() -> Self {
-        Self::default()
+    /// Registers a callback which is called with the [`Progress`] of each
+    /// parsing, whenever some bytes are read from or skipped in the media
+    /// source. Useful for displaying the progress of parsing large files,
+    /// e.g. a MOV file which stores its metadata at the end.
+    ///
+    /// The callback is called on the thread which parses the media source,
+    /// it should return quickly. It replaces the previously registered one.
+    pub fn on_progress(&mut self, f: impl FnMut(Progress) + Send + 'static) {
+        self.core.set_progress_callback(Some(Box::new(f)));
+    }
+
+    /// Sets a flag to cancel the parsing: once it's set to `true` (e.g. by
+    /// another thread), the ongoing parsing is aborted before reading more
+    /// data, and returns [`Error::Cancelled`](crate::Error::Cancelled).
+    ///
+    /// The flag is checked by every parsing of this parser, reset it to
+    /// `false` before parsing again.
+    pub fn cancel_on(&mut self, flag: Arc<AtomicBool>) {
+        self.core.set_cancel_flag(Some(flag));
     }
 
     /// `MediaParser`/`AsyncMediaParser` comes with its own buffer management,
@@ -360,86 +494,182 @@ impl AsyncMediaParser {
         &mut self,
         mut ms: AsyncMediaSource<R, S>,
     ) -> crate::Result<O> {
-        self.reset();
-        self.acquire_buf();
-
-        self.buf_mut().append(&mut ms.buf);
+        self.core.begin(&mut ms.buf);
         let res = self.do_parse(ms).await;
 
-        self.reset();
+        self.core.reset();
         res
     }
 
-    async fn do_parse<R: AsyncRead + Unpin, S, O: AsyncParseOutput<R, S>>(
+    /// Parses all kinds of metadata found in `ms` in a single pass, see
+    /// [`MediaParser::parse_all`](crate::MediaParser::parse_all).
+    pub async fn parse_all<R: AsyncRead + Unpin + Send, S: AsyncSkip<R> + Send>(
         &mut self,
         mut ms: AsyncMediaSource<R, S>,
-    ) -> Result<O, crate::Error> {
-        self.fill_buf(&mut ms.reader, INIT_BUF_SIZE).await?;
-        let res = O::parse(self, ms).await?;
-        Ok(res)
-    }
-
-    fn reset(&mut self) {
-        // Ensure buf has been released
-        if let Some(buf) = self.buf.take() {
-            self.bb.release(buf);
+    ) -> crate::Result<MetadataIter> {
+        if ms.has_track() {
+            let info: TrackInfo = self.parse(ms).await?;
+            return Ok(MetadataIter::from_track_info(info));
         }
 
-        // Reset position
-        self.set_position(0);
-    }
-
-    fn buf(&self) -> &Vec<u8> {
-        self.buf.as_ref().unwrap()
-    }
-
-    fn acquire_buf(&mut self) {
-        assert!(self.buf.is_none());
-        self.buf = Some(self.bb.acquire());
-    }
-
-    fn buf_mut(&mut self) -> &mut Vec<u8> {
-        self.buf.as_mut().unwrap()
+        self.core.begin(&mut ms.buf);
+        let res = loop {
+            match self.fill_buf(&mut ms.reader, MAX_GROW_SIZE).await {
+                Ok(_) => (),
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                    let len = self.core.buffer().len();
+                    break Ok(self.core.share_buf(0..len).data);
+                }
+                // The buffer is full, which is fine if the image ends here
+                Err(e) if e.kind() == io::ErrorKind::OutOfMemory => {
+                    match ms.reader.read(&mut [0]).await {
+                        Ok(0) => {
+                            let len = self.core.buffer().len();
+                            break Ok(self.core.share_buf(0..len).data);
+                        }
+                        Ok(_) => break Err(e),
+                        Err(e) => break Err(e),
+                    }
+                }
+                Err(e) => break Err(e),
+            }
+        };
+        self.core.reset();
+
+        Ok(MetadataIter::from_loaded(
+            ms.mime,
+            res?,
+            self.custom_tags.clone(),
+            self.core.limits(),
+        ))
     }
-}
 
-impl AsyncBufParser for AsyncMediaParser {
-    async fn fill_buf<R: AsyncRead + Unpin>(
+    /// Parses a [`MotionPhoto`], see
+    /// [`MediaParser::parse_motion_photo`](crate::MediaParser::parse_motion_photo).
+    pub async fn parse_motion_photo<R: AsyncRead + AsyncSeek + Unpin + Send>(
         &mut self,
-        reader: &mut R,
-        size: usize,
-    ) -> io::Result<usize> {
-        self.buf_mut().reserve_exact(size);
+        ms: AsyncMediaSource<R, Seekable>,
+        with_track_info: bool,
+    ) -> crate::Result<MotionPhoto> {
+        use tokio::io::AsyncSeekExt;
 
-        let n = reader.take(size as u64).read_to_end(self.buf_mut()).await?;
-        if n == 0 {
-            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        let AsyncMediaSource {
+            mut reader,
+            buf,
+            mime,
+            ..
+        } = ms;
+        let ms = AsyncMediaSource {
+            reader: &mut reader,
+            buf,
+            mime,
+            phantom: PhantomData::<Seekable>,
+        };
+        let mut photo: MotionPhoto = self.parse(ms).await?;
+
+        if let Some(range) = photo.video_range().filter(|_| with_track_info) {
+            let mut video = Vec::new();
+            reader.seek(io::SeekFrom::Start(range.start)).await?;
+            (&mut reader)
+                .take(range.end - range.start)
+                .read_to_end(&mut video)
+                .await?;
+            let ms = AsyncMediaSource::seekable(io::Cursor::new(video)).await?;
+            match self.parse(ms).await {
+                Ok(info) => photo.track_info = Some(info),
+                Err(e) => tracing::warn!(?e, "parse motion photo video failed"),
+            }
         }
+        Ok(photo)
+    }
 
-        // let n = reader.read_buf(&mut self.buf).await?;
-        // if n == 0 {
-        //     return Err(std::io::ErrorKind::UnexpectedEof.into());
-        // }
+    /// Dumps the structure tree that the parser sees in `ms`, see
+    /// [`MediaParser::debug_dump`](crate::MediaParser::debug_dump).
+    pub async fn debug_dump<R: AsyncRead + Unpin, S: AsyncSkip<R>>(
+        &mut self,
+        mut ms: AsyncMediaSource<R, S>,
+    ) -> crate::Result<DumpNode> {
+        self.core.begin(&mut ms.buf);
+        let res = self.do_dump(ms).await;
 
-        Ok(n)
+        self.core.reset();
+        res
     }
-}
 
-impl Buf for AsyncMediaParser {
-    fn buffer(&self) -> &[u8] {
-        &self.buf()[self.position()..]
+    async fn do_dump<R: AsyncRead + Unpin, S: AsyncSkip<R>>(
+        &mut self,
+        mut ms: AsyncMediaSource<R, S>,
+    ) -> crate::Result<DumpNode> {
+        match ms.mime {
+            Mime::Image(img) => {
+                let truncated = loop {
+                    let len = self.core.buffer().len();
+                    if len >= MAX_LOAD_SIZE {
+                        break true;
+                    }
+                    match self.fill_buf(&mut ms.reader, len.max(MAX_GROW_SIZE)).await {
+                        Ok(_) => (),
+                        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break false,
+                        Err(e) if e.kind() == io::ErrorKind::OutOfMemory => break true,
+                        Err(e) => return Err(e.into()),
+                    }
+                };
+                Ok(dump_image(
+                    img,
+                    self.core.buffer(),
+                    truncated,
+                    self.core.limits().max_box_depth,
+                ))
+            }
+            Mime::Video(
+                MimeVideo::Webm
+                | MimeVideo::Matroska
+                | MimeVideo::Gif
+                | MimeVideo::Avi
+                | MimeVideo::Wav
+                | MimeVideo::Mp3
+                | MimeVideo::Asf
+                | MimeVideo::MpegTs
+                | MimeVideo::Mxf
+                | MimeVideo::Dsf
+                | MimeVideo::Dff,
+            )
+            | Mime::Xmp => Ok(dump_unsupported(ms.mime)),
+            Mime::Video(_) => {
+                let mut scanner = BoxScanner::new(self.core.limits().max_box_depth);
+                let res = self
+                    .load_and_parse::<R, S, _, _>(&mut ms.reader, |data, _| scanner.scan(data))
+                    .await;
+                Ok(scanner.finish(ms.mime, res))
+            }
+        }
     }
 
-    fn clear(&mut self) {
-        self.buf_mut().clear();
+    async fn do_parse<R: AsyncRead + Unpin, S, O: AsyncParseOutput<R, S>>(
+        &mut self,
+        mut ms: AsyncMediaSource<R, S>,
+    ) -> Result<O, crate::Error> {
+        if let Err(e) = self
+            .fill_buf(&mut ms.reader, self.core.limits().initial_buffer)
+            .await
+        {
+            // The whole file (e.g. a tiny GIF) may have been read as the header
+            if e.kind() != io::ErrorKind::UnexpectedEof || self.core.buffer().is_empty() {
+                return Err(e.into());
+            }
+        }
+        let res = O::parse(self, ms).await?;
+        Ok(res)
     }
+}
 
-    fn set_position(&mut self, pos: usize) {
-        self.position = pos;
+impl AsyncBufParser for AsyncMediaParser {
+    fn core(&self) -> &ParserCore {
+        &self.core
     }
 
-    fn position(&self) -> usize {
-        self.position
+    fn core_mut(&mut self) -> &mut ParserCore {
+        &mut self.core
     }
 }
 
@@ -554,4 +784,70 @@ mod tests {
         let info: TrackInfo = parser.parse(ms).await.unwrap();
         assert_eq!(info.get(tag).unwrap(), &v);
     }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    #[test_case("exif.jpg")]
+    #[test_case("exif.heic")]
+    #[test_case("broken.jpg")]
+    #[test_case("tif.tif")]
+    async fn sync_async_parity(path: &str) {
+        let path = Path::new("testdata").join(path);
+        let mut parser = crate::MediaParser::new();
+        let iter: ExifIter = parser
+            .parse(crate::MediaSource::file_path(&path).unwrap())
+            .unwrap();
+        let expect: crate::Exif = iter.into();
+
+        let mut parser = AsyncMediaParser::new();
+        let ms = AsyncMediaSource::file_path(&path).await.unwrap();
+        let iter: ExifIter = parser.parse(ms).await.unwrap();
+        assert_eq!(crate::Exif::from(iter), expect);
+
+        let f = File::open(&path).await.unwrap();
+        let ms = AsyncMediaSource::unseekable(f).await.unwrap();
+        let iter: ExifIter = parser.parse(ms).await.unwrap();
+        assert_eq!(crate::Exif::from(iter), expect);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    #[test_case("exif.jpg")]
+    #[test_case("exif.heic")]
+    #[test_case("meta.mov")]
+    #[test_case("webm_480.webm")]
+    async fn debug_dump_parity(path: &str) {
+        let path = Path::new("testdata").join(path);
+        let mut parser = crate::MediaParser::new();
+        let expect = parser
+            .debug_dump(crate::MediaSource::file_path(&path).unwrap())
+            .unwrap();
+
+        let mut parser = AsyncMediaParser::new();
+        let ms = AsyncMediaSource::file_path(&path).await.unwrap();
+        assert_eq!(parser.debug_dump(ms).await.unwrap(), expect);
+
+        let f = File::open(&path).await.unwrap();
+        let ms = AsyncMediaSource::unseekable(f).await.unwrap();
+        assert_eq!(parser.debug_dump(ms).await.unwrap(), expect);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn timeout() {
+        use tokio::io::AsyncWriteExt;
+
+        // A stream which stalls after sending the beginning of the file
+        let data = crate::testkit::read_sample("meta.mov").unwrap();
+        let (mut tx, rx) = tokio::io::duplex(64 * 1024);
+        tx.write_all(&data[..32 * 1024]).await.unwrap();
+
+        let mut parser = crate::MediaParser::builder()
+            .timeout(std::time::Duration::from_millis(100))
+            .build_async();
+        let ms = AsyncMediaSource::unseekable(rx).await.unwrap();
+        let res: crate::Result<TrackInfo> = parser.parse(ms).await;
+        assert!(
+            matches!(&res, Err(crate::Error::IOError(e)) if e.kind() == io::ErrorKind::TimedOut),
+            "{res:?}"
+        );
+        drop(tx);
+    }
 }
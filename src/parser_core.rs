@@ -0,0 +1,558 @@
+use std::{
+    fmt::Debug,
+    io,
+    ops::Range,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use bytes::Bytes;
+
+use crate::{
+    buffer::Buffers,
+    error::{cancelled_error, ParsedError, ParsingError, ParsingErrorState},
+    file::{Mime, MimeImage, MimeVideo},
+    parser::{ParsingState, INIT_BUF_SIZE, MAX_GROW_SIZE, MIN_GROW_SIZE},
+    partial_vec::PartialVec,
+};
+
+/// The I/O free part of [`MediaParser`](crate::MediaParser) and
+/// `AsyncMediaParser`.
+///
+/// `ParserCore` owns the parsing buffer, and drives the parsing state
+/// machine: it translates results of the parse functions into [`Action`]s,
+/// which tell the caller what to read next. `MediaParser` and
+/// `AsyncMediaParser` only perform these actions with their own readers, so
+/// that both of them share exactly the same parsing logic.
+pub(crate) struct ParserCore {
+    bb: Buffers,
+    buf: Option<Buf>,
+    position: usize,
+
+    // Offset (in the media source) of the first byte of `buf`
+    offset: u64,
+
+    limits: Limits,
+
+    progress: Progress,
+    on_progress: Option<ProgressCallback>,
+
+    cancel: Option<Arc<AtomicBool>>,
+
+    // When the current parsing process times out, see `Limits::timeout`
+    deadline: Option<Instant>,
+}
+
+/// The parsing buffer.
+enum Buf {
+    /// Data read from the media source, acquired from [`Buffers`]
+    Owned(Vec<u8>),
+    /// The whole data of an in-memory source, shared without copying
+    Shared(Bytes),
+}
+
+impl Buf {
+    fn len(&self) -> usize {
+        match self {
+            Buf::Owned(x) => x.len(),
+            Buf::Shared(x) => x.len(),
+        }
+    }
+}
+
+pub(crate) type ProgressCallback = Box<dyn FnMut(Progress) + Send>;
+
+/// Progress of a parsing process, see
+/// [`MediaParser::on_progress`](crate::MediaParser::on_progress).
+///
+/// `bytes_read + bytes_skipped` is the current position in the media source,
+/// e.g. a GUI can divide it by the file size to display the progress.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Progress {
+    /// Number of bytes read from the media source (including the bytes
+    /// which are read to be skipped, for unseekable sources)
+    pub bytes_read: u64,
+
+    /// Number of bytes skipped by seeking the media source
+    pub bytes_skipped: u64,
+}
+
+/// Resource limits of a parser, see
+/// [`MediaParserBuilder`](crate::MediaParserBuilder).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Limits {
+    /// Number of bytes to read before parsing
+    pub initial_buffer: usize,
+    /// Max number of bytes in the parsing buffer
+    pub max_buffer: usize,
+    /// Max number of entries parsed in an IFD
+    pub max_ifd_entries: usize,
+    /// Max nesting depth of IFDs (sub-IFDs included)
+    pub max_ifd_depth: usize,
+    /// Max nesting depth of ISOBMFF boxes
+    pub max_box_depth: usize,
+    /// Max data size of the EBML elements which are loaded
+    pub max_ebml_element_size: usize,
+    /// Max duration of a parsing process
+    pub timeout: Option<Duration>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            initial_buffer: INIT_BUF_SIZE,
+            max_buffer: usize::MAX,
+            max_ifd_entries: 1024,
+            max_ifd_depth: 8,
+            max_box_depth: 16,
+            max_ebml_element_size: 64 * 1024 * 1024,
+            timeout: None,
+        }
+    }
+}
+
+/// What to do next, see [`ParserCore::step`].
+#[derive(Debug)]
+pub(crate) enum Action<O> {
+    /// Parsing completed
+    Done(O),
+
+    /// Read at most the given number of bytes into the buffer
+    Fill(usize),
+
+    /// Skip the given number of bytes from the current position, see
+    /// [`ParserCore::start_skip`]
+    Skip(usize),
+}
+
+impl Debug for ParserCore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ParserCore")
+            .field("buffers", &self.bb)
+            .field("buf len", &self.buf.as_ref().map(Buf::len))
+            .field("position", &self.position)
+            .field("offset", &self.offset)
+            .field("limits", &self.limits)
+            .field("progress", &self.progress)
+            .field("cancel", &self.cancel)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for ParserCore {
+    fn default() -> Self {
+        Self::new(Limits::default())
+    }
+}
+
+impl ParserCore {
+    pub fn new(limits: Limits) -> Self {
+        Self {
+            bb: Buffers::new(),
+            buf: None,
+            position: 0,
+            offset: 0,
+            limits,
+            progress: Progress::default(),
+            on_progress: None,
+            cancel: None,
+            deadline: None,
+        }
+    }
+
+    pub fn set_cancel_flag(&mut self, flag: Option<Arc<AtomicBool>>) {
+        self.cancel = flag;
+    }
+
+    /// Returns the time left before the current parsing process times out,
+    /// or `None` if there is no timeout.
+    pub fn remaining_time(&self) -> Option<Duration> {
+        self.deadline
+            .map(|x| x.saturating_duration_since(Instant::now()))
+    }
+
+    /// The error returned once the parsing times out.
+    pub fn timed_out_error(&self) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::TimedOut,
+            format!(
+                "parsing timed out: {:?}",
+                self.limits.timeout.unwrap_or_default()
+            ),
+        )
+    }
+
+    pub fn set_progress_callback(&mut self, f: Option<ProgressCallback>) {
+        self.on_progress = f;
+    }
+
+    /// Records that `n` bytes have been read into the buffer.
+    pub fn filled(&mut self, n: usize) {
+        self.progress.bytes_read += n as u64;
+        self.report_progress();
+    }
+
+    fn report_progress(&mut self) {
+        if let Some(f) = self.on_progress.as_mut() {
+            f(self.progress);
+        }
+    }
+
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    /// Returns the number of bytes which can be read into the buffer, at
+    /// most `size`. Returns an error if the buffer is full, i.e. it has
+    /// reached the max size.
+    pub fn fill_size(&self, size: usize) -> io::Result<usize> {
+        if self
+            .cancel
+            .as_ref()
+            .is_some_and(|x| x.load(Ordering::Relaxed))
+        {
+            return Err(cancelled_error());
+        }
+        if matches!(self.buf, Some(Buf::Shared(_))) {
+            // Nothing more can be read from an in-memory source
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        if self.remaining_time() == Some(Duration::ZERO) {
+            return Err(self.timed_out_error());
+        }
+        let remain = self.limits.max_buffer.saturating_sub(self.buf().len());
+        if remain == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::OutOfMemory,
+                format!(
+                    "parsing buffer limit exceeded: {} bytes",
+                    self.limits.max_buffer
+                ),
+            ));
+        }
+        Ok(size.min(remain))
+    }
+
+    /// Prepares for a new parsing process, `header` is the data which has
+    /// been read by the media source.
+    pub fn begin(&mut self, header: &mut Vec<u8>) {
+        self.start(header.len());
+        let mut buf = self.bb.acquire();
+        buf.reserve(self.limits.initial_buffer.min(self.limits.max_buffer));
+        buf.append(header);
+        self.buf = Some(Buf::Owned(buf));
+    }
+
+    /// Prepares for a new parsing process of an in-memory source, `data` is
+    /// used as the buffer without copying.
+    pub fn begin_shared(&mut self, data: Bytes) {
+        self.start(data.len());
+        self.buf = Some(Buf::Shared(data));
+    }
+
+    fn start(&mut self, bytes_read: usize) {
+        self.reset();
+        assert!(self.buf.is_none());
+        self.deadline = self.limits.timeout.map(|x| Instant::now() + x);
+        self.progress = Progress {
+            bytes_read: bytes_read as u64,
+            bytes_skipped: 0,
+        };
+        self.report_progress();
+    }
+
+    pub fn reset(&mut self) {
+        // Ensure buf has been released
+        if let Some(Buf::Owned(buf)) = self.buf.take() {
+            self.bb.release(buf);
+        }
+
+        // Reset position
+        self.set_position(0);
+        self.offset = 0;
+    }
+
+    pub fn buf(&self) -> &[u8] {
+        match self.buf.as_ref() {
+            Some(Buf::Owned(b)) => b,
+            Some(Buf::Shared(b)) => b,
+            None => panic!("no buf here"),
+        }
+    }
+
+    /// The buffer to be filled by the reader.
+    pub fn buf_mut(&mut self) -> &mut Vec<u8> {
+        if let Some(Buf::Shared(data)) = self.buf.as_mut() {
+            // Not expected, since nothing is read for in-memory sources (see
+            // `Self::fill_size`), copy the data if it's still shared
+            let data = Vec::from(std::mem::take(data));
+            self.buf = Some(Buf::Owned(data));
+        }
+        match self.buf.as_mut() {
+            Some(Buf::Owned(b)) => b,
+            _ => panic!("no buf here"),
+        }
+    }
+
+    /// Unread data in the buffer.
+    pub fn buffer(&self) -> &[u8] {
+        &self.buf()[self.position..]
+    }
+
+    pub fn clear(&mut self) {
+        self.offset += self.buf().len() as u64;
+        if let Some(Buf::Shared(_)) = self.buf {
+            self.buf = Some(Buf::Owned(self.bb.acquire()));
+        }
+        self.buf_mut().clear();
+        self.set_position(0);
+    }
+
+    pub fn set_position(&mut self, pos: usize) {
+        self.position = pos;
+    }
+
+    #[allow(unused)]
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Offset (in the media source) of the unread data.
+    pub fn source_offset(&self) -> u64 {
+        self.offset + self.position as u64
+    }
+
+    /// Records that `n` bytes have been skipped by seeking the reader, after
+    /// [`Self::start_skip`].
+    pub fn seek_skipped(&mut self, n: usize) {
+        self.offset += n as u64;
+        self.progress.bytes_skipped += n as u64;
+        self.report_progress();
+    }
+
+    /// Translates a parse result into the next action. `state` is updated
+    /// with the parsing state carried by the error (if any).
+    pub fn step<O>(
+        &self,
+        res: Result<O, ParsingErrorState>,
+        state: &mut Option<ParsingState>,
+    ) -> Result<Action<O>, ParsedError> {
+        let es = match res {
+            Ok(o) => return Ok(Action::Done(o)),
+            Err(es) => es,
+        };
+
+        tracing::debug!(?es);
+        *state = es.state;
+        match es.err {
+            ParsingError::ClearAndSkip(n) => Ok(Action::Skip(n)),
+            ParsingError::Need(i) => {
+                tracing::debug!(need = i, "need more bytes");
+                Ok(Action::Fill(i.clamp(MIN_GROW_SIZE, MAX_GROW_SIZE)))
+            }
+            ParsingError::Failed(s) => Err(ParsedError::Failed(s)),
+        }
+    }
+
+    /// Skips `n` bytes of the unread data. Returns the number of bytes which need to be skipped
+    /// from the reader, if the buffered data is not enough.
+    ///
+    /// If the reader can't seek, the caller should fill the buffer and call
+    /// [`Self::consume_skipped`] repeatedly, until it returns true.
+    pub fn start_skip(&mut self, n: usize) -> Option<usize> {
+        tracing::debug!("ClearAndSkip");
+        if n <= self.buffer().len() {
+            tracing::debug!(n, "skip by set_position");
+            self.set_position(self.position + n);
+            return None;
+        }
+
+        let skip_n = n - self.buffer().len();
+        tracing::debug!(skip_n, "clear and skip bytes");
+        self.clear();
+        Some(skip_n)
+    }
+
+    /// Drops the data which has been read for skipping, `skipped` is the
+    /// total number of bytes read so far. Returns true if the skipping is
+    /// done.
+    pub fn consume_skipped(&mut self, skipped: usize, skip_n: usize) -> bool {
+        if skipped <= skip_n {
+            self.clear();
+            skipped == skip_n
+        } else {
+            let remain = skipped - skip_n;
+            self.set_position(self.buffer().len() - remain);
+            true
+        }
+    }
+
+    /// Shares the given range of the unread data without copying. The buffer
+    /// is handed over, so this should be the last step of a parsing process.
+    pub fn share_buf(&mut self, mut range: Range<usize>) -> PartialVec {
+        let vec = match self.buf.take().unwrap() {
+            Buf::Owned(buf) => self.bb.release_to_share(buf),
+            Buf::Shared(data) => data,
+        };
+        range.start += self.position;
+        range.end += self.position;
+        PartialVec::new(vec, range)
+    }
+}
+
+/// Runs `parse` over `data`, which holds the whole media source, so nothing
+/// needs to be read. Returns the output along with the offset of the data
+/// which `parse` succeeded with.
+pub(crate) fn parse_loaded<O>(
+    data: &[u8],
+    mut parse: impl FnMut(&[u8], Option<ParsingState>) -> Result<O, ParsingErrorState>,
+) -> Result<(O, usize), ParsedError> {
+    let mut pos = 0;
+    let mut state = None;
+    loop {
+        let es = match parse(&data[pos..], state.take()) {
+            Ok(o) => return Ok((o, pos)),
+            Err(es) => es,
+        };
+        state = es.state;
+        match es.err {
+            ParsingError::ClearAndSkip(n) if n <= data.len() - pos => pos += n,
+            ParsingError::ClearAndSkip(_) | ParsingError::Need(_) => {
+                return Err(ParsedError::NoEnoughBytes)
+            }
+            ParsingError::Failed(s) => return Err(ParsedError::Failed(s)),
+        }
+    }
+}
+
+pub(crate) fn exif_mime(mime: Mime) -> crate::Result<MimeImage> {
+    match mime {
+        Mime::Image(img) => Ok(img),
+        Mime::Video(_) | Mime::Xmp => Err(crate::Error::ParseFailed("no Exif data here".into())),
+    }
+}
+
+pub(crate) fn track_mime(mime: Mime) -> crate::Result<MimeVideo> {
+    match mime {
+        Mime::Video(v) => Ok(v),
+        Mime::Image(_) | Mime::Xmp => Err(crate::Error::ParseFailed("no track info here".into())),
+    }
+}
+
+pub(crate) fn telemetry_mime(mime: Mime) -> crate::Result<()> {
+    match mime {
+        Mime::Video(MimeVideo::QuickTime | MimeVideo::Mp4 | MimeVideo::_3gpp) => Ok(()),
+        _ => Err(crate::Error::ParseFailed(
+            "telemetry is only supported for ISOBMFF files".into(),
+        )),
+    }
+}
+
+pub(crate) fn iptc_mime(mime: Mime) -> crate::Result<()> {
+    match mime {
+        Mime::Image(MimeImage::Jpeg) => Ok(()),
+        _ => Err(crate::Error::ParseFailed(
+            "IPTC is only supported for JPEG files".into(),
+        )),
+    }
+}
+
+pub(crate) fn motion_photo_mime(mime: Mime) -> crate::Result<()> {
+    match mime {
+        Mime::Image(MimeImage::Jpeg) => Ok(()),
+        _ => Err(crate::Error::ParseFailed(
+            "motion photos are only supported for JPEG files".into(),
+        )),
+    }
+}
+
+pub(crate) fn gain_map_mime(mime: Mime) -> crate::Result<Mime> {
+    match mime {
+        Mime::Image(MimeImage::Jpeg | MimeImage::Heic | MimeImage::Heif) => Ok(mime),
+        _ => Err(crate::Error::ParseFailed(
+            "HDR gain maps are only supported for JPEG & HEIC files".into(),
+        )),
+    }
+}
+
+pub(crate) fn jfif_mime(mime: Mime) -> crate::Result<()> {
+    match mime {
+        Mime::Image(MimeImage::Jpeg) => Ok(()),
+        _ => Err(crate::Error::ParseFailed(
+            "JFIF is only supported for JPEG files".into(),
+        )),
+    }
+}
+
+pub(crate) fn icc_mime(mime: Mime) -> crate::Result<Mime> {
+    match mime {
+        Mime::Image(
+            MimeImage::Jpeg
+            | MimeImage::Tiff
+            | MimeImage::Pef
+            | MimeImage::Srw
+            | MimeImage::Heic
+            | MimeImage::Heif
+            | MimeImage::Avif,
+        ) => Ok(mime),
+        _ => Err(crate::Error::ParseFailed(
+            "ICC profile is not supported for this format".into(),
+        )),
+    }
+}
+
+pub(crate) fn xmp_mime(mime: Mime) -> crate::Result<Mime> {
+    match mime {
+        Mime::Image(_)
+        | Mime::Video(MimeVideo::QuickTime | MimeVideo::Mp4 | MimeVideo::_3gpp)
+        | Mime::Xmp => Ok(mime),
+        _ => Err(crate::Error::ParseFailed(
+            "XMP is not supported for this format".into(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn step_and_skip() {
+        let mut core = ParserCore::default();
+        let mut state = None;
+
+        core.begin(&mut vec![0; 10]);
+        let res: Result<(), _> = Err(ParsingErrorState::new(ParsingError::Need(1), None));
+        assert!(matches!(
+            core.step(res, &mut state),
+            Ok(Action::Fill(MIN_GROW_SIZE))
+        ));
+
+        // Skip within the buffer
+        assert_eq!(core.start_skip(2), None);
+        assert_eq!(core.start_skip(2), None);
+        assert_eq!(core.buffer().len(), 6);
+        assert_eq!(core.source_offset(), 4);
+
+        // Skip by reading: 6 bytes buffered, 10 bytes to be read from reader
+        assert_eq!(core.start_skip(16), Some(10));
+        assert!(core.buffer().is_empty());
+        core.buf_mut().extend_from_slice(&[0; 8]);
+        assert!(!core.consume_skipped(8, 10));
+        core.buf_mut().extend_from_slice(&[1, 1, 2, 3]);
+        assert!(core.consume_skipped(12, 10));
+        assert_eq!(core.buffer(), &[2, 3]);
+        assert_eq!(core.source_offset(), 20);
+
+        let res: Result<(), _> = Err(ParsingErrorState::new(
+            ParsingError::Failed("x".into()),
+            None,
+        ));
+        assert!(core.step(res, &mut state).is_err());
+        core.reset();
+    }
+}
@@ -4,11 +4,12 @@ use std::borrow::Borrow;
 use std::fmt::Debug;
 use std::ops::Deref;
 use std::ops::Range;
-use std::sync::Arc;
+
+use bytes::Bytes;
 
 #[derive(Clone, PartialEq, Eq, Default)]
 pub(crate) struct PartialVec {
-    pub(crate) data: Arc<Vec<u8>>,
+    pub(crate) data: Bytes,
     pub(crate) range: Range<usize>,
 }
 
@@ -22,7 +23,7 @@ impl Debug for PartialVec {
 }
 
 impl PartialVec {
-    pub(crate) fn new(vec: Arc<Vec<u8>>, range: Range<usize>) -> PartialVec {
+    pub(crate) fn new(vec: Bytes, range: Range<usize>) -> PartialVec {
         assert!(range.end <= vec.len());
         PartialVec { data: vec, range }
     }
@@ -36,7 +37,7 @@ impl PartialVec {
     //     Vec::from(self.data.clone())
     // }
 
-    pub(crate) fn from_arc_vec_slice(vec: Arc<Vec<u8>>, subslice: &[u8]) -> PartialVec {
+    pub(crate) fn from_bytes_slice(vec: Bytes, subslice: &[u8]) -> PartialVec {
         let range = vec
             .subslice_range(subslice)
             .expect("subslice should be a sub slice of self");
@@ -45,7 +46,7 @@ impl PartialVec {
 
     pub(crate) fn from_vec_range(vec: Vec<u8>, range: Range<usize>) -> PartialVec {
         assert!(range.end <= vec.len());
-        Self::new(Arc::new(vec), range)
+        Self::new(Bytes::from(vec), range)
     }
 
     pub(crate) fn partial(&self, subslice: &[u8]) -> AssociatedInput {
@@ -76,14 +77,14 @@ impl From<Vec<u8>> for PartialVec {
     }
 }
 
-impl From<(Arc<Vec<u8>>, &[u8])> for PartialVec {
-    fn from(value: (Arc<Vec<u8>>, &[u8])) -> Self {
-        Self::from_arc_vec_slice(value.0, value.1)
+impl From<(Bytes, &[u8])> for PartialVec {
+    fn from(value: (Bytes, &[u8])) -> Self {
+        Self::from_bytes_slice(value.0, value.1)
     }
 }
 
-impl From<(Arc<Vec<u8>>, Range<usize>)> for PartialVec {
-    fn from(value: (Arc<Vec<u8>>, Range<usize>)) -> Self {
+impl From<(Bytes, Range<usize>)> for PartialVec {
+    fn from(value: (Bytes, Range<usize>)) -> Self {
         Self::new(value.0, value.1)
     }
 }
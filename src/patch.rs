@@ -0,0 +1,395 @@
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+
+use nom::number::Endianness;
+
+use crate::{
+    bbox::{MetaBox, ParseBox},
+    exif::{
+        check_exif_header, check_exif_header2, encode_value, sub_ifd_of, TiffHeader, EXIF_IDENT,
+    },
+    file::{read_header, Mime, MimeImage, MimeVideo},
+    jpeg::MarkerCode,
+    strip::{walk_boxes, BoxHeader},
+    track_writer::patch_track,
+    values::DataFormat,
+    EntryValue, Error, ExifTag,
+};
+
+/// Patches the value of the Exif entry `tag` of a JPEG, TIFF, HEIF or
+/// MP4/QuickTime file in place, i.e. only the bytes of the value are written into `file`, the rest
+/// of the file is neither read nor rewritten (except for the parts needed to
+/// locate the Exif data).
+///
+/// This only works when the new value fits in the existing slot:
+///
+/// - The entry must exist, in ifd0 or in its Exif/GPS sub-IFD, according to
+///   the tag code (the same way as [`ExifWriter`](crate::ExifWriter) places
+///   tags).
+/// - The new value must have the same data format as the existing one.
+/// - Text values can be shorter than the existing ones (the rest is filled
+///   with NULs), other values must have the same number of components, e.g.
+///   a `DateTimeOriginal` string, or the rationals of `ExposureTime`.
+///
+/// For MP4/QuickTime files, the track metadata is patched in the `moov` box
+/// instead, the new text values must have the same size as the existing
+/// ones:
+///
+/// - `CreateDate` & `ModifyDate`: the creation/modification times of the
+///   `mvhd`, `tkhd` & `mdhd` boxes, `CreateDate` also sets the
+///   `com.apple.quicktime.creationdate` item (if any).
+/// - `Make`, `Model` & `Software`: the `com.apple.quicktime.*` items.
+/// - `GPSInfo`: a location in ISO 6709 format (e.g.
+///   `"+27.1281+100.2508+000.000/"`), which is written into the `©xyz` atoms
+///   & the `com.apple.quicktime.location.ISO6709` item.
+///
+/// Returns [`Error::WriteFailed`] if the file type is not supported, or the
+/// value doesn't fit. Unlike [`ExifWriter::set`](crate::ExifWriter::set),
+/// the corresponding `OffsetTime*` tag is not touched when setting a time.
+///
+/// ## Example
+///
+/// ```rust
+/// use nom_exif::*;
+/// use std::io::Cursor;
+///
+/// fn main() -> Result<()> {
+///     let mut file = Cursor::new(std::fs::read("./testdata/exif.jpg")?);
+///     patch_exif(&mut file, ExifTag::Make, "VIVO")?;
+///
+///     file.set_position(0);
+///     let iter: ExifIter = MediaParser::new().parse(MediaSource::seekable(file)?)?;
+///     let exif: Exif = iter.into();
+///     assert_eq!(exif.get(ExifTag::Make), Some(&"VIVO".into()));
+///     Ok(())
+/// }
+/// ```
+pub fn patch_exif<F: Read + Write + Seek>(
+    mut file: F,
+    tag: ExifTag,
+    value: impl Into<EntryValue>,
+) -> crate::Result<()> {
+    let base = match sniff_mime(&mut file)? {
+        Mime::Image(MimeImage::Jpeg) => jpeg_exif_base(&mut file)?,
+        Mime::Image(MimeImage::Tiff | MimeImage::Pef | MimeImage::Srw) => Some(0),
+        Mime::Image(MimeImage::Heic | MimeImage::Heif | MimeImage::Avif) => {
+            heif_exif_base(&mut file)?
+        }
+        Mime::Video(MimeVideo::QuickTime | MimeVideo::Mp4 | MimeVideo::_3gpp) => {
+            return patch_track(&mut file, tag, &value.into());
+        }
+        mime => {
+            return Err(Error::WriteFailed(
+                format!("patching Exif data of {mime:?} files is not supported").into(),
+            ))
+        }
+    };
+    let base = base.ok_or_else(|| Error::WriteFailed("no Exif data found".into()))?;
+    let (_, header) = TiffHeader::parse(&read_at(&mut file, base, 8)?)?;
+    let endian = header.endian;
+
+    let code = tag.code();
+    let not_found = || Error::WriteFailed(format!("Exif tag {tag} not found").into());
+    let mut ifd = header.ifd0_offset;
+    if let Some(pointer) = sub_ifd_of(code) {
+        let entry = find_entry(&mut file, base, endian, ifd, pointer)?;
+        ifd = entry.ok_or_else(not_found)?.value;
+    }
+    let entry = find_entry(&mut file, base, endian, ifd, code)?.ok_or_else(not_found)?;
+
+    let (format, count, mut data) = encode_value(code, &value.into(), endian)?;
+    let fits = format as u16 == entry.format
+        && match format {
+            DataFormat::Text => count <= entry.count,
+            _ => count == entry.count,
+        };
+    if !fits {
+        return Err(Error::WriteFailed(
+            format!("the value doesn't fit in the existing slot of {tag}").into(),
+        ));
+    }
+    data.resize(entry.count as usize * format.component_size(), 0);
+
+    let pos = if data.len() > 4 {
+        base + entry.value as u64
+    } else {
+        entry.pos + 8
+    };
+    file.seek(SeekFrom::Start(pos))?;
+    file.write_all(&data)?;
+    file.flush()?;
+    Ok(())
+}
+
+/// An IFD entry, `pos` is the file offset of the entry.
+struct Entry {
+    pos: u64,
+    format: u16,
+    count: u32,
+    value: u32,
+}
+
+/// Finds the entry `code` in the IFD at `ifd` (relative to the TIFF header at
+/// `base`).
+fn find_entry<F: Read + Seek>(
+    file: &mut F,
+    base: u64,
+    endian: Endianness,
+    ifd: u32,
+    code: u16,
+) -> crate::Result<Option<Entry>> {
+    let u16_of = |x: &[u8]| match endian {
+        Endianness::Little => u16::from_le_bytes([x[0], x[1]]),
+        _ => u16::from_be_bytes([x[0], x[1]]),
+    };
+    let u32_of = |x: &[u8]| match endian {
+        Endianness::Little => u32::from_le_bytes([x[0], x[1], x[2], x[3]]),
+        _ => u32::from_be_bytes([x[0], x[1], x[2], x[3]]),
+    };
+
+    let start = base + ifd as u64;
+    let num = u16_of(&read_at(file, start, 2)?) as usize;
+    let entries = read_at(file, start + 2, num * 12)?;
+    let entry = entries
+        .chunks_exact(12)
+        .enumerate()
+        .find(|(_, x)| u16_of(x) == code)
+        .map(|(i, x)| Entry {
+            pos: start + 2 + i as u64 * 12,
+            format: u16_of(&x[2..]),
+            count: u32_of(&x[4..]),
+            value: u32_of(&x[8..]),
+        });
+    Ok(entry)
+}
+
+/// Reads `len` bytes at `pos` of `file`.
+pub(crate) fn read_at<F: Read + Seek>(
+    file: &mut F,
+    pos: u64,
+    len: usize,
+) -> crate::Result<Vec<u8>> {
+    file.seek(SeekFrom::Start(pos))?;
+    let mut buf = vec![0; len];
+    file.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+/// Detects the file type by the header of `file`.
+pub(crate) fn sniff_mime<F: Read + Seek>(file: &mut F) -> crate::Result<Mime> {
+    file.seek(SeekFrom::Start(0))?;
+    Mime::try_from(&read_header(file)?[..])
+}
+
+/// Returns the top level boxes of `file`, only the box headers are read.
+pub(crate) fn top_level_boxes<F: Read + Seek>(file: &mut F) -> crate::Result<Vec<BoxHeader>> {
+    let len = file.seek(SeekFrom::End(0))?;
+    walk_boxes(0..len, |pos, len| read_at(file, pos, len))
+}
+
+/// Returns the file range of the first top level box of `box_type`, other
+/// boxes are skipped by seeking.
+pub(crate) fn find_top_level_box<F: Read + Seek>(
+    file: &mut F,
+    box_type: &[u8; 4],
+) -> crate::Result<Option<Range<u64>>> {
+    Ok(top_level_boxes(file)?
+        .into_iter()
+        .find(|x| &x.box_type == box_type)
+        .map(|x| x.range))
+}
+
+/// Returns the file offset of the TIFF header in the Exif APP1 segment of a
+/// JPEG file, segments are skipped by seeking.
+fn jpeg_exif_base<F: Read + Seek>(file: &mut F) -> crate::Result<Option<u64>> {
+    let mut pos = 2;
+    loop {
+        let marker = read_at(file, pos, 2)?;
+        if marker[0] != 0xFF {
+            return Err("invalid JPEG marker".into());
+        }
+        let code = marker[1];
+        if code == MarkerCode::Sos.code() || code == MarkerCode::Eoi.code() {
+            return Ok(None);
+        }
+        // Fill bytes & markers without a payload
+        if code == 0xFF {
+            pos += 1;
+            continue;
+        }
+        if (0xD0..=0xD7).contains(&code) || code == 0x01 {
+            pos += 2;
+            continue;
+        }
+
+        let len = read_at(file, pos + 2, 2)?;
+        let len = u16::from_be_bytes([len[0], len[1]]) as u64;
+        if code == MarkerCode::APP1.code() && len >= 2 + EXIF_IDENT.len() as u64 {
+            let ident = read_at(file, pos + 4, EXIF_IDENT.len())?;
+            if check_exif_header(&ident) {
+                return Ok(Some(pos + 4 + EXIF_IDENT.len() as u64));
+            }
+        }
+        pos += 2 + len;
+    }
+}
+
+/// Returns the file offset of the TIFF header in the Exif item of a HEIF
+/// file, only the `meta` box is read.
+fn heif_exif_base<F: Read + Seek>(file: &mut F) -> crate::Result<Option<u64>> {
+    let Some(meta) = find_top_level_box(file, b"meta")? else {
+        return Ok(None);
+    };
+    let data = read_at(file, meta.start, (meta.end - meta.start) as usize)?;
+    let (_, meta) = MetaBox::parse_box(&data)?;
+    let Some(item) = meta.exif_data_offset() else {
+        return Ok(None);
+    };
+
+    // The item starts with the offset of the TIFF header (4 bytes), then the
+    // Exif header
+    let head = read_at(file, item.start as u64, 4 + EXIF_IDENT.len())?;
+    if check_exif_header2(&head).is_err() {
+        return Ok(None);
+    }
+    Ok(Some(item.start as u64 + head.len() as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{
+        testkit::read_sample, values::Rational, Exif, ExifIter, MediaParser, MediaSource,
+        TrackInfo, TrackInfoTag,
+    };
+    use test_case::test_case;
+
+    fn time(s: &str) -> EntryValue {
+        chrono::DateTime::parse_from_rfc3339(s).unwrap().into()
+    }
+
+    fn parse_exif(data: Vec<u8>) -> Exif {
+        let ms = MediaSource::seekable(Cursor::new(data)).unwrap();
+        let iter: ExifIter = MediaParser::new().parse(ms).unwrap();
+        iter.into()
+    }
+
+    #[test_case("exif.jpg", ExifTag::Make, "VIVO".into())]
+    #[test_case("exif.jpg", ExifTag::Model, "X90".into(); "shorter text")]
+    #[test_case(
+        "exif.jpg",
+        ExifTag::DateTimeOriginal,
+        time("2024-01-02T03:04:05+08:00")
+    )]
+    #[test_case(
+        "exif.jpg",
+        ExifTag::ExposureTime,
+        EntryValue::URational(Rational(1, 250))
+    )]
+    #[test_case("exif.jpg", ExifTag::GPSAltitudeRef, 1u8.into(); "inline")]
+    #[test_case("exif.heic", ExifTag::Model, "iPhone".into())]
+    #[test_case("tif.tif", ExifTag::ImageDescription, "Courtesy".into())]
+    fn patch(path: &str, tag: ExifTag, value: EntryValue) {
+        let data = read_sample(path).unwrap();
+        let mut file = Cursor::new(data.clone());
+        patch_exif(&mut file, tag, value.clone()).unwrap();
+        let output = file.into_inner();
+        assert_eq!(output.len(), data.len());
+
+        let exif = parse_exif(output);
+        assert_eq!(exif.get(tag), Some(&value));
+        // Other entries are kept
+        let original = parse_exif(data);
+        assert_eq!(
+            exif.get(ExifTag::ImageWidth),
+            original.get(ExifTag::ImageWidth)
+        );
+    }
+
+    #[test_case("meta.mov", TrackInfoTag::Make, "Nokia".into())]
+    #[test_case("meta.mov", TrackInfoTag::Software, "13.0.0".into())]
+    #[test_case(
+        "meta.mov",
+        TrackInfoTag::GpsIso6709,
+        "+48.8577+002.2950+035.000/".into()
+    )]
+    #[test_case("meta.mp4", TrackInfoTag::GpsIso6709, "-33.8568+151.2153/".into())]
+    #[test_case(
+        "meta.mov",
+        TrackInfoTag::CreateDate,
+        time("2024-01-02T03:04:05+08:00")
+    )]
+    fn patch_track(path: &str, tag: TrackInfoTag, value: EntryValue) {
+        let exif_tag = match tag {
+            TrackInfoTag::Make => ExifTag::Make,
+            TrackInfoTag::Software => ExifTag::Software,
+            TrackInfoTag::GpsIso6709 => ExifTag::GPSInfo,
+            _ => ExifTag::CreateDate,
+        };
+        let data = read_sample(path).unwrap();
+        let mut file = Cursor::new(data.clone());
+        patch_exif(&mut file, exif_tag, value.clone()).unwrap();
+        let output = file.into_inner();
+        assert_eq!(output.len(), data.len());
+
+        let parse = |data| -> TrackInfo {
+            let ms = MediaSource::seekable(Cursor::new(data)).unwrap();
+            MediaParser::new().parse(ms).unwrap()
+        };
+        let info = parse(output);
+        assert_eq!(info.get(tag), Some(&value));
+        // Other entries are kept
+        let original = parse(data);
+        for tag in [TrackInfoTag::Model, TrackInfoTag::DurationMs] {
+            assert_eq!(info.get(tag), original.get(tag));
+        }
+    }
+
+    #[test]
+    fn patch_track_times() {
+        use crate::strip::find_box;
+
+        let data = read_sample("meta.mov").unwrap();
+        let mut file = Cursor::new(data.clone());
+        let modified = time("2024-01-02T03:04:05+08:00");
+        patch_exif(&mut file, ExifTag::ModifyDate, modified).unwrap();
+        let output = file.into_inner();
+
+        // mvhd of meta.mov is version 0: creation & modification times (4
+        // bytes each) follow the version & flags
+        let secs = (1_704_135_845_u64 + 2_082_844_800) as u32;
+        let moov = find_box(&output, 0..output.len(), b"moov")
+            .unwrap()
+            .unwrap();
+        let mvhd = find_box(&output, moov.body(), b"mvhd").unwrap().unwrap();
+        let body = mvhd.body().start;
+        assert_eq!(output[body + 8..body + 12], secs.to_be_bytes());
+        // The creation time is kept
+        assert_eq!(output[body + 4..body + 8], data[body + 4..body + 8]);
+        let changed = output.iter().zip(&data).filter(|(a, b)| a != b).count();
+        assert!(changed <= 4 * 9, "{changed} bytes changed");
+
+        // Out of the range of 32-bit times
+        let mut file = Cursor::new(data.clone());
+        let res = patch_exif(&mut file, ExifTag::CreateDate, time("2100-01-01T00:00:00Z"));
+        assert!(res.is_err());
+        assert_eq!(file.into_inner(), data);
+    }
+
+    #[test_case("exif.jpg", ExifTag::Make, "vivo vivo vivo".into(); "too long")]
+    #[test_case("exif.jpg", ExifTag::Make, 1u16.into(); "different format")]
+    #[test_case("exif.jpg", ExifTag::Copyright, "a".into(); "not found")]
+    #[test_case("no-exif.jpg", ExifTag::Make, "a".into(); "no exif")]
+    #[test_case("meta.mp4", ExifTag::Copyright, "a".into(); "unsupported")]
+    #[test_case("meta.mp4", ExifTag::Make, "a".into(); "no track item")]
+    #[test_case("meta.mov", ExifTag::Make, "Samsung".into(); "longer track item")]
+    #[test_case("meta.mov", ExifTag::GPSInfo, "+27.1281+100.2508/".into(); "shorter location")]
+    fn patch_failed(path: &str, tag: ExifTag, value: EntryValue) {
+        let data = read_sample(path).unwrap();
+        let mut file = Cursor::new(data.clone());
+        patch_exif(&mut file, tag, value).unwrap_err();
+        assert_eq!(file.into_inner(), data);
+    }
+}
@@ -0,0 +1,545 @@
+use std::collections::HashSet;
+use std::io::Read;
+use std::ops::Range;
+
+use nom::number::Endianness;
+
+use crate::{
+    bbox::{MetaBox, ParseBox},
+    exif::{read_fields, RawField, TiffHeader},
+    file::{Mime, MimeImage},
+    jpeg, raf,
+    strip::{exif_tiff_ranges, find_box},
+    values::DataFormat,
+};
+
+const NEW_SUBFILE_TYPE: u16 = 0x00fe;
+const COMPRESSION: u16 = 0x0103;
+const MAKE: u16 = 0x010f;
+const STRIP_OFFSETS: u16 = 0x0111;
+const STRIP_BYTE_COUNTS: u16 = 0x0117;
+const SUB_IFDS: u16 = 0x014a;
+const THUMBNAIL_OFFSET: u16 = 0x0201;
+const THUMBNAIL_LENGTH: u16 = 0x0202;
+const EXIF_OFFSET: u16 = 0x8769;
+const MAKER_NOTE: u16 = 0x927c;
+
+/// `PreviewIFD` of Nikon MakerNotes
+const NIKON_PREVIEW_IFD: u16 = 0x0011;
+/// `PreviewImage` of Sony MakerNotes
+const SONY_PREVIEW_IMAGE: u16 = 0x2001;
+
+/// Nested SubIFDs deeper than this are ignored.
+const MAX_SUB_IFD_DEPTH: usize = 4;
+
+/// An embedded preview image, see [`read_previews`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreviewImage {
+    pub kind: PreviewKind,
+
+    /// Byte range of the image data in the file.
+    pub range: Range<u64>,
+
+    /// `(width, height)` of the image, read from the JPEG frame header, or
+    /// the `ispe` property of HEIF items. `None` if it's unknown.
+    pub dimensions: Option<(u32, u32)>,
+
+    /// The image data, see [`PreviewKind`] for its format.
+    pub data: Vec<u8>,
+}
+
+/// Where a [`PreviewImage`] is found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PreviewKind {
+    /// The JPEG thumbnail of ifd1, usually 160x120 pixels.
+    Thumbnail,
+
+    /// A JPEG image of an IFD other than ifd1, e.g. a reduced-resolution
+    /// image in the SubIFDs of DNG & NEF files, or ifd0 of CR2 files.
+    Ifd,
+
+    /// A JPEG image in the MakerNote, i.e. Nikon `PreviewIFD` & Sony
+    /// `PreviewImage`.
+    MakerNote,
+
+    /// The embedded JPEG image of Fujifilm RAF files.
+    RafJpeg,
+
+    /// A thumbnail item of the primary image of HEIF files (i.e. an item with
+    /// a `thmb` reference), e.g. an `hvc1` item.
+    ///
+    /// **Note**: The data is the coded data of the item, which can't be
+    /// decoded without the decoder configuration (e.g. the `hvcC` property)
+    /// of the item, unless the item type is `jpeg`.
+    HeifThumbnail { item_type: String },
+}
+
+/// Reads an image file from `reader`, and returns the embedded preview images
+/// in it, the largest one first, so that a preview can be shown without
+/// decoding the main image.
+///
+/// Supported files & previews:
+///
+/// - JPEG files: the thumbnail of ifd1
+/// - TIFF based raw files (e.g. DNG, NEF, CR2, ARW): JPEG images of the IFDs
+///   & SubIFDs, and the previews of Nikon & Sony MakerNotes
+/// - Fujifilm RAF files: the embedded JPEG image & its thumbnail
+/// - HEIF/HEIC files: the thumbnail items of the primary image, and the
+///   thumbnail of ifd1 if any
+///
+/// Images which can't be displayed as they are (e.g. the lossless JPEG raw
+/// data of DNG files) are ignored. Returns an empty `Vec` if there is no
+/// preview.
+///
+/// ## Example
+///
+/// ```rust
+/// use nom_exif::*;
+/// use std::fs::File;
+///
+/// fn main() -> Result<()> {
+///     let previews = read_previews(File::open("./testdata/exif.jpg")?)?;
+///     let thumbnail = &previews[0];
+///     assert_eq!(thumbnail.kind, PreviewKind::Thumbnail);
+///     assert!(thumbnail.data.starts_with(&[0xff, 0xd8]));
+///     Ok(())
+/// }
+/// ```
+pub fn read_previews<R: Read>(mut reader: R) -> crate::Result<Vec<PreviewImage>> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    let mime = Mime::try_from(&data[..])?;
+
+    let mut found = Vec::new();
+    match mime {
+        Mime::Image(MimeImage::Raf) => {
+            if let Ok((_, (offset, len))) = raf::parse_raf_header(&data) {
+                let start = offset as usize;
+                let range = start..start.saturating_add(len as usize);
+                if let Some(jpeg) = data.get(range.clone()) {
+                    if let Some(dimensions) = jpeg_dimensions(jpeg) {
+                        found.push((PreviewKind::RafJpeg, range.clone(), Some(dimensions)));
+                    }
+                    let tiffs =
+                        exif_tiff_ranges(jpeg, Mime::Image(MimeImage::Jpeg)).unwrap_or_default();
+                    for tiff in tiffs {
+                        found.extend(tiff_previews(&data, start + tiff.start..start + tiff.end));
+                    }
+                }
+            }
+        }
+        Mime::Image(MimeImage::Heic | MimeImage::Heif | MimeImage::Avif) => {
+            found.extend(heif_thumbnails(&data)?);
+            for tiff in exif_tiff_ranges(&data, mime)? {
+                found.extend(tiff_previews(&data, tiff));
+            }
+        }
+        _ => {
+            for tiff in exif_tiff_ranges(&data, mime)? {
+                found.extend(tiff_previews(&data, tiff));
+            }
+        }
+    }
+
+    // The same image may be referenced twice, e.g. by ifd1 & a SubIFD
+    let mut ranges = HashSet::new();
+    found.retain(|x| ranges.insert(x.1.clone()));
+    found.sort_by_key(|x| std::cmp::Reverse(x.1.len()));
+
+    let previews = found
+        .into_iter()
+        .map(|(kind, range, dimensions)| PreviewImage {
+            kind,
+            data: data[range.clone()].to_vec(), // Safe-slice: checked when found
+            range: range.start as u64..range.end as u64,
+            dimensions,
+        })
+        .collect();
+    Ok(previews)
+}
+
+type Found = (PreviewKind, Range<usize>, Option<(u32, u32)>);
+
+/// Returns the thumbnail items of the primary image of a HEIF file.
+fn heif_thumbnails(data: &[u8]) -> crate::Result<Vec<Found>> {
+    let Some(meta) = find_box(data, 0..data.len(), b"meta")? else {
+        return Ok(Vec::new());
+    };
+    let (_, meta) = MetaBox::parse_box(&data[meta.range])?; // Safe-slice
+
+    let found = meta
+        .primary_thumbnail_items()
+        .into_iter()
+        .filter_map(|id| {
+            let range = meta.item_data_offset(id)?;
+            data.get(range.clone())?;
+            let item_type = meta.item_type(id).unwrap_or_default().to_owned();
+            Some((
+                PreviewKind::HeifThumbnail { item_type },
+                range,
+                meta.item_extents(id),
+            ))
+        })
+        .collect();
+    Ok(found)
+}
+
+/// Returns the previews referenced by the TIFF data at `tiff` of the file,
+/// the ranges are relative to the file.
+fn tiff_previews(data: &[u8], tiff: Range<usize>) -> Vec<Found> {
+    let Some(tiff_data) = data.get(tiff.clone()) else {
+        return Vec::new();
+    };
+    let Ok((_, header)) = TiffHeader::parse(tiff_data) else {
+        return Vec::new();
+    };
+    let mut walker = IfdWalker {
+        tiff: tiff_data,
+        endian: header.endian,
+        visited: HashSet::new(),
+        found: Vec::new(),
+    };
+    walker.walk_pages(header.ifd0_offset);
+    walker
+        .found
+        .into_iter()
+        .map(|(kind, range, dimensions)| {
+            (
+                kind,
+                range.start + tiff.start..range.end + tiff.start,
+                dimensions,
+            )
+        })
+        .collect()
+}
+
+/// Visits the IFDs of TIFF data to find the JPEG images they reference.
+struct IfdWalker<'a> {
+    tiff: &'a [u8],
+    endian: Endianness,
+    visited: HashSet<u32>,
+    found: Vec<Found>,
+}
+
+impl IfdWalker<'_> {
+    /// Reads the IFD at `offset`, returns `None` if it's invalid or has been
+    /// visited (to avoid loops).
+    fn read(&mut self, offset: u32) -> Option<(Vec<RawField>, u32)> {
+        if offset == 0 || !self.visited.insert(offset) {
+            return None;
+        }
+        read_fields(self.tiff, offset as usize, self.endian).ok()
+    }
+
+    fn walk_pages(&mut self, ifd0: u32) {
+        let mut make = String::new();
+        let mut offset = ifd0;
+        let mut page = 0;
+        while let Some((fields, next)) = self.read(offset) {
+            if page == 0 {
+                if let Some(x) = find(&fields, MAKE) {
+                    make = String::from_utf8_lossy(&self.tiff[x.value.clone()]).into_owned();
+                    // Safe-slice
+                }
+            }
+            let kind = if page == 1 {
+                PreviewKind::Thumbnail
+            } else {
+                PreviewKind::Ifd
+            };
+            self.check_ifd(self.tiff, &fields, self.endian, 0, kind);
+            self.walk_sub_ifds(&fields, 0);
+
+            let exif = find(&fields, EXIF_OFFSET).and_then(|x| self.uint(x));
+            if let Some((exif, _)) = exif.and_then(|x| self.read(x)) {
+                if let Some(x) = find(&exif, MAKER_NOTE) {
+                    self.maker_note(&make, x.value.clone());
+                }
+            }
+            offset = next;
+            page += 1;
+        }
+    }
+
+    fn walk_sub_ifds(&mut self, fields: &[RawField], depth: usize) {
+        if depth >= MAX_SUB_IFD_DEPTH {
+            return;
+        }
+        let Some(sub_ifds) = find(fields, SUB_IFDS) else {
+            return;
+        };
+        for offset in uints(self.tiff, sub_ifds, self.endian) {
+            if let Some((fields, _)) = self.read(offset) {
+                self.check_ifd(self.tiff, &fields, self.endian, 0, PreviewKind::Ifd);
+                self.walk_sub_ifds(&fields, depth + 1);
+            }
+        }
+    }
+
+    /// Finds the previews in the MakerNote at `value` of the TIFF data.
+    fn maker_note(&mut self, make: &str, value: Range<usize>) {
+        let tiff = self.tiff;
+        let data = &tiff[value.clone()]; // Safe-slice: checked by read_fields
+        if make.starts_with("NIKON") && data.starts_with(b"Nikon\0\x02") {
+            // A TIFF structure, the offsets are relative to its header
+            let base = value.start + 10;
+            let Some(nested) = tiff.get(base..) else {
+                return;
+            };
+            let Ok((_, header)) = TiffHeader::parse(nested) else {
+                return;
+            };
+            let endian = header.endian;
+            let Ok((fields, _)) = read_fields(nested, header.ifd0_offset as usize, endian) else {
+                return;
+            };
+            let preview_ifd = find(&fields, NIKON_PREVIEW_IFD)
+                .and_then(|x| uints(nested, x, endian).first().copied());
+            if let Some(Ok((fields, _))) =
+                preview_ifd.map(|x| read_fields(nested, x as usize, endian))
+            {
+                self.check_ifd(nested, &fields, endian, base, PreviewKind::MakerNote);
+            }
+        } else if make.starts_with("SONY") {
+            let offset = if data.starts_with(b"SONY DSC ") || data.starts_with(b"SONY CAM ") {
+                value.start + 12
+            } else {
+                value.start
+            };
+            let Ok((fields, _)) = read_fields(tiff, offset, self.endian) else {
+                return;
+            };
+            if let Some(x) = find(&fields, SONY_PREVIEW_IMAGE) {
+                self.push(PreviewKind::MakerNote, x.value.clone());
+            }
+        }
+    }
+
+    /// Finds the JPEG image referenced by an IFD of `tiff`, which is located
+    /// at `base` of the TIFF data being walked.
+    fn check_ifd(
+        &mut self,
+        tiff: &[u8],
+        fields: &[RawField],
+        endian: Endianness,
+        base: usize,
+        kind: PreviewKind,
+    ) {
+        let uint = |code| {
+            let values = uints(tiff, find(fields, code)?, endian);
+            match values[..] {
+                [x] => Some(x as usize),
+                _ => None,
+            }
+        };
+        let (offset, len) = match (uint(THUMBNAIL_OFFSET), uint(THUMBNAIL_LENGTH)) {
+            (Some(offset), Some(len)) => (offset, len),
+            _ => {
+                // A single strip image, which is a preview if it's an
+                // old-style JPEG (e.g. ifd0 of CR2 files), or a
+                // reduced-resolution JPEG (e.g. DNG previews)
+                let reduced = uint(NEW_SUBFILE_TYPE).is_some_and(|x| x & 1 == 1);
+                match uint(COMPRESSION) {
+                    Some(6) => (),
+                    Some(7) if reduced => (),
+                    _ => return,
+                }
+                let (Some(offset), Some(len)) = (uint(STRIP_OFFSETS), uint(STRIP_BYTE_COUNTS))
+                else {
+                    return;
+                };
+                (offset, len)
+            }
+        };
+        let start = base.saturating_add(offset);
+        self.push(kind, start..start.saturating_add(len));
+    }
+
+    /// Adds the JPEG image at `range` of the TIFF data, if it's valid.
+    fn push(&mut self, kind: PreviewKind, range: Range<usize>) {
+        let Some(dimensions) = self.tiff.get(range.clone()).and_then(jpeg_dimensions) else {
+            tracing::debug!(?kind, ?range, "Ignored: not a displayable JPEG image");
+            return;
+        };
+        self.found.push((kind, range, Some(dimensions)));
+    }
+
+    fn uint(&self, field: &RawField) -> Option<u32> {
+        uints(self.tiff, field, self.endian).first().copied()
+    }
+}
+
+fn find(fields: &[RawField], code: u16) -> Option<&RawField> {
+    fields.iter().find(|x| x.code == code)
+}
+
+/// Returns the values of a SHORT or LONG entry.
+fn uints(tiff: &[u8], field: &RawField, endian: Endianness) -> Vec<u32> {
+    let data = &tiff[field.value.clone()]; // Safe-slice: checked by read_fields
+    match field.format {
+        DataFormat::U16 => data
+            .chunks_exact(2)
+            .map(|x| match endian {
+                Endianness::Little => u16::from_le_bytes([x[0], x[1]]),
+                _ => u16::from_be_bytes([x[0], x[1]]),
+            })
+            .map(u32::from)
+            .collect(),
+        DataFormat::U32 => data
+            .chunks_exact(4)
+            .map(|x| {
+                let bytes = [x[0], x[1], x[2], x[3]];
+                match endian {
+                    Endianness::Little => u32::from_le_bytes(bytes),
+                    _ => u32::from_be_bytes(bytes),
+                }
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Returns the (width, height) of a JPEG image, or `None` if `data` is not a
+/// JPEG image which can be displayed, e.g. the lossless JPEG raw data of DNG
+/// & CR2 files.
+fn jpeg_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    let (segments, _) = jpeg::parse_segments(data).ok()?;
+    // SOF markers, except DHT, JPG & DAC
+    let sof = segments
+        .iter()
+        .find(|x| (0xc0..=0xcf).contains(&x.code) && ![0xc4, 0xc8, 0xcc].contains(&x.code))?;
+    // Only baseline, extended sequential & progressive (Huffman) JPEG
+    if ![0xc0, 0xc1, 0xc2].contains(&sof.code) {
+        return None;
+    }
+    let header = data.get(sof.payload.clone())?.get(1..5)?;
+    let height = u16::from_be_bytes([header[0], header[1]]);
+    let width = u16::from_be_bytes([header[2], header[3]]);
+    Some((width.into(), height.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        exif::{build_ifd, tiff_with_maker_note},
+        testkit::{open_sample, read_sample},
+    };
+    use std::io::Cursor;
+    use test_case::test_case;
+
+    /// A minimal baseline JPEG image of `width` x `height` pixels.
+    fn tiny_jpeg(width: u16, height: u16) -> Vec<u8> {
+        let mut data = vec![0xff, 0xd8, 0xff, 0xc0, 0, 11, 8];
+        data.extend(height.to_be_bytes());
+        data.extend(width.to_be_bytes());
+        data.extend([1, 1, 0x11, 0]);
+        data.extend([0xff, 0xda, 0, 8, 1, 1, 0, 0, 0x3f, 0]);
+        data.extend([0xff, 0xd9]);
+        data
+    }
+
+    #[test]
+    fn jpeg_dimensions() {
+        assert_eq!(
+            super::jpeg_dimensions(&tiny_jpeg(160, 120)),
+            Some((160, 120))
+        );
+        // Lossless JPEG
+        let mut lossless = tiny_jpeg(160, 120);
+        lossless[3] = 0xc3;
+        assert_eq!(super::jpeg_dimensions(&lossless), None);
+        assert_eq!(super::jpeg_dimensions(b"not a jpeg"), None);
+    }
+
+    #[test_case("exif.jpg")]
+    #[test_case("exif.heic")]
+    #[test_case("tif.tif")]
+    #[test_case("no-exif.jpg")]
+    fn previews(path: &str) {
+        let previews = read_previews(open_sample(path).unwrap()).unwrap();
+        match path {
+            "exif.jpg" => {
+                assert_eq!(previews.len(), 1);
+                assert_eq!(previews[0].kind, PreviewKind::Thumbnail);
+            }
+            _ => assert!(previews.is_empty()),
+        }
+        let data = read_sample(path).unwrap();
+        for x in previews {
+            assert_eq!(
+                data[x.range.start as usize..x.range.end as usize],
+                x.data[..]
+            );
+            assert!(x.dimensions.is_some());
+        }
+    }
+
+    #[test]
+    fn heif_thumbnail() {
+        // Turn the `auxl` reference (from item 0x34 to the primary item) into
+        // a `thmb` reference
+        let mut data = read_sample("exif.heic").unwrap();
+        let pos = data.windows(4).position(|x| x == b"auxl").unwrap();
+        data[pos..pos + 4].copy_from_slice(b"thmb");
+
+        let previews = read_previews(Cursor::new(&data)).unwrap();
+        assert_eq!(previews.len(), 1);
+        let preview = &previews[0];
+        assert_eq!(
+            preview.kind,
+            PreviewKind::HeifThumbnail {
+                item_type: "hvc1".to_owned()
+            }
+        );
+        assert!(preview.dimensions.is_some());
+        assert_eq!(
+            data[preview.range.start as usize..preview.range.end as usize],
+            preview.data[..]
+        );
+    }
+
+    #[test]
+    fn sony_maker_note() {
+        let jpeg = tiny_jpeg(1616, 1080);
+        let tiff = tiff_with_maker_note("SONY", |offset| {
+            build_ifd(offset, &[(SONY_PREVIEW_IMAGE, 7, jpeg.len() as u32, &jpeg)])
+        });
+        let previews = read_previews(Cursor::new(&tiff)).unwrap();
+        assert_eq!(previews.len(), 1);
+        assert_eq!(previews[0].kind, PreviewKind::MakerNote);
+        assert_eq!(previews[0].dimensions, Some((1616, 1080)));
+        assert_eq!(previews[0].data, jpeg);
+    }
+
+    #[test]
+    fn nikon_maker_note() {
+        let jpeg = tiny_jpeg(640, 424);
+        let tiff = tiff_with_maker_note("NIKON CORPORATION", |_| {
+            // Offsets are relative to the nested TIFF header
+            let mut nested = b"II*\0\x08\0\0\0".to_vec();
+            let ifd0 = build_ifd(8, &[(NIKON_PREVIEW_IFD, 13, 1, &[0; 4])]);
+            let preview_ifd = 8 + ifd0.len() as u32;
+            let ifd0 = build_ifd(8, &[(NIKON_PREVIEW_IFD, 13, 1, &preview_ifd.to_le_bytes())]);
+            nested.extend(ifd0);
+            let jpeg_offset = preview_ifd + 2 + 2 * 12 + 4;
+            nested.extend(build_ifd(
+                preview_ifd as usize,
+                &[
+                    (THUMBNAIL_OFFSET, 4, 1, &jpeg_offset.to_le_bytes()),
+                    (THUMBNAIL_LENGTH, 4, 1, &(jpeg.len() as u32).to_le_bytes()),
+                ],
+            ));
+            nested.extend(&jpeg);
+
+            let mut data = b"Nikon\0\x02\x10\0\0".to_vec();
+            data.extend(nested);
+            data
+        });
+        let previews = read_previews(Cursor::new(&tiff)).unwrap();
+        assert_eq!(previews.len(), 1);
+        assert_eq!(previews[0].kind, PreviewKind::MakerNote);
+        assert_eq!(previews[0].data, jpeg);
+    }
+}
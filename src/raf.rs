@@ -0,0 +1,80 @@
+use nom::{bytes::streaming, number::streaming::be_u32, sequence::tuple, IResult};
+
+use crate::{
+    error::{nom_error_to_parsing_error_with_state, ParsingError, ParsingErrorState},
+    parser::ParsingState,
+};
+
+const RAF_MAGIC: &[u8] = b"FUJIFILMCCD-RAW ";
+
+/// Position of the embedded JPEG's offset & length in the RAF header.
+const JPEG_INFO_POS: usize = 0x54;
+
+pub(crate) fn check_raf(input: &[u8]) -> bool {
+    input.starts_with(RAF_MAGIC)
+}
+
+/// Parses the Fuji specific header of a RAF file, returns the offset and the
+/// length of the embedded JPEG, which contains the Exif data.
+///
+/// See <https://exiftool.org/TagNames/FujiFilm.html#RAF>.
+pub(crate) fn parse_raf_header(input: &[u8]) -> IResult<&[u8], (u32, u32)> {
+    let (remain, _) = streaming::tag(RAF_MAGIC)(input)?;
+    let (remain, _) = streaming::take(JPEG_INFO_POS - RAF_MAGIC.len())(remain)?;
+    tuple((be_u32, be_u32))(remain)
+}
+
+/// Returns a `ClearAndSkip` to the embedded JPEG, the Exif data can then be
+/// extracted the same way as a JPEG file, see [`ParsingState::RafJpeg`].
+pub(crate) fn skip_to_jpeg(input: &[u8], state: Option<ParsingState>) -> ParsingErrorState {
+    match parse_raf_header(input) {
+        Ok((_, (offset, _))) => ParsingErrorState::new(
+            ParsingError::ClearAndSkip(offset as usize),
+            Some(ParsingState::RafJpeg),
+        ),
+        Err(e) => nom_error_to_parsing_error_with_state(e, state),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "raw_formats")]
+    use crate::{testkit::read_sample, Exif, ExifIter, ExifTag, MediaParser, MediaSource};
+    #[cfg(feature = "raw_formats")]
+    use test_case::test_case;
+
+    /// Wraps `jpeg` in a RAF file, the JPEG starts at `offset`.
+    fn make_raf(jpeg: &[u8], offset: usize) -> Vec<u8> {
+        let mut data = RAF_MAGIC.to_vec();
+        data.extend_from_slice(b"0201");
+        data.resize(JPEG_INFO_POS, 0);
+        data.extend_from_slice(&(offset as u32).to_be_bytes());
+        data.extend_from_slice(&(jpeg.len() as u32).to_be_bytes());
+        data.resize(offset, 0);
+        data.extend_from_slice(jpeg);
+        data
+    }
+
+    #[test]
+    fn raf_header() {
+        let data = make_raf(b"\xff\xd8\xff\xd9", 0x94);
+        assert!(check_raf(&data));
+        assert_eq!(parse_raf_header(&data).unwrap().1, (0x94, 4));
+        assert!(parse_raf_header(&data[..0x50]).unwrap_err().is_incomplete());
+    }
+
+    #[cfg(feature = "raw_formats")]
+    #[test_case(0x94)]
+    #[test_case(0x10_0000)]
+    fn raf(offset: usize) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let data = make_raf(&read_sample("exif.jpg").unwrap(), offset);
+        let mut parser = MediaParser::new();
+        let ms = MediaSource::seekable(std::io::Cursor::new(data)).unwrap();
+        let iter: ExifIter = parser.parse(ms).unwrap();
+        let exif: Exif = iter.into();
+        assert_eq!(exif.get(ExifTag::Model), Some(&"vivo X90 Pro+".into()));
+    }
+}
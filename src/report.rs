@@ -0,0 +1,351 @@
+//! Renders selected tags of many files as CSV/TSV rows (see [`CsvReport`]),
+//! and the human readable reports of the `Display` implementations of
+//! [`Exif`], [`ExifIter`](crate::ExifIter) & [`TrackInfo`].
+
+use std::{fmt::Display, path::Path};
+
+use crate::{
+    exif::print_conv,
+    json::{exiftool_value, undefined_text, unquote},
+    EntryValue, Exif, ExifTag, TrackInfo, TrackInfoTag,
+};
+
+/// A column of a [`CsvReport`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ReportColumn {
+    /// An Exif tag (in ifd0), it's empty for files parsed as [`TrackInfo`].
+    Exif(ExifTag),
+
+    /// A track info tag, it's empty for files parsed as [`Exif`].
+    Track(TrackInfoTag),
+
+    /// A tag name, which is looked up as an [`ExifTag`] (see
+    /// [`ExifTag::from_str`](std::str::FromStr::from_str)) for images, and as
+    /// a [`TrackInfoTag`] or a metadata key (see [`TrackInfo::metadata`]) for
+    /// videos & audios, case-insensitively.
+    ///
+    /// So that a column like `Make` works for both images & videos.
+    Name(String),
+}
+
+impl From<ExifTag> for ReportColumn {
+    fn from(value: ExifTag) -> Self {
+        Self::Exif(value)
+    }
+}
+
+impl From<TrackInfoTag> for ReportColumn {
+    fn from(value: TrackInfoTag) -> Self {
+        Self::Track(value)
+    }
+}
+
+impl From<&str> for ReportColumn {
+    fn from(value: &str) -> Self {
+        Self::Name(value.to_owned())
+    }
+}
+
+impl From<String> for ReportColumn {
+    fn from(value: String) -> Self {
+        Self::Name(value)
+    }
+}
+
+impl Display for ReportColumn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReportColumn::Exif(tag) => tag.fmt(f),
+            ReportColumn::Track(tag) => tag.fmt(f),
+            ReportColumn::Name(name) => name.fmt(f),
+        }
+    }
+}
+
+/// Renders a set of selected tags from many files as CSV (or TSV) rows, one
+/// row per file, in the style of `exiftool -csv`.
+///
+/// The first column is always `SourceFile`. Values are rendered the same way
+/// as [`ExifIter::to_json`](crate::ExifIter::to_json), e.g. `ExposureTime`
+/// as `0.01` and times as `2023:07:09 20:36:33`; missing values are empty.
+///
+/// ```rust
+/// use nom_exif::*;
+///
+/// let mut parser = MediaParser::new();
+/// let mut report = CsvReport::new([
+///     ReportColumn::from("Make"),
+///     ReportColumn::from("Model"),
+///     ExifTag::FNumber.into(),
+///     TrackInfoTag::DurationMs.into(),
+/// ]);
+///
+/// for path in ["./testdata/exif.jpg", "./testdata/meta.mov"] {
+///     let ms = MediaSource::file_path(path).unwrap();
+///     if ms.has_exif() {
+///         let iter: ExifIter = parser.parse(ms).unwrap();
+///         report.add_exif(path, &iter.into());
+///     } else {
+///         let info: TrackInfo = parser.parse(ms).unwrap();
+///         report.add_track_info(path, &info);
+///     }
+/// }
+///
+/// assert_eq!(
+///     report.finish(),
+///     "SourceFile,Make,Model,FNumber,DurationMs\n\
+///      ./testdata/exif.jpg,vivo,vivo X90 Pro+,1.75,\n\
+///      ./testdata/meta.mov,Apple,iPhone X,,500\n"
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct CsvReport {
+    columns: Vec<ReportColumn>,
+    delimiter: char,
+    out: String,
+}
+
+impl CsvReport {
+    /// Creates a comma separated report with the header row.
+    pub fn new<C: Into<ReportColumn>>(columns: impl IntoIterator<Item = C>) -> Self {
+        Self::with_delimiter(columns, ',')
+    }
+
+    /// Creates a tab separated report with the header row. Tabs & line breaks
+    /// in values are replaced with spaces, since TSV has no quoting.
+    pub fn tsv<C: Into<ReportColumn>>(columns: impl IntoIterator<Item = C>) -> Self {
+        Self::with_delimiter(columns, '\t')
+    }
+
+    fn with_delimiter<C: Into<ReportColumn>>(
+        columns: impl IntoIterator<Item = C>,
+        delimiter: char,
+    ) -> Self {
+        let mut report = Self {
+            columns: columns.into_iter().map(Into::into).collect(),
+            delimiter,
+            out: String::new(),
+        };
+        let header: Vec<_> = std::iter::once("SourceFile".to_owned())
+            .chain(report.columns.iter().map(|c| c.to_string()))
+            .collect();
+        report.push_row(header);
+        report
+    }
+
+    /// Adds a row for an image file.
+    pub fn add_exif(&mut self, file: impl AsRef<Path>, exif: &Exif) {
+        let values: Vec<_> = self
+            .columns
+            .iter()
+            .map(|c| {
+                let tag = match c {
+                    ReportColumn::Exif(tag) => Some(*tag),
+                    ReportColumn::Track(_) => None,
+                    ReportColumn::Name(name) => name.parse().ok(),
+                };
+                tag.and_then(|tag| exif.get(tag))
+            })
+            .map(|v| v.map(csv_value).unwrap_or_default())
+            .collect();
+        self.push_file_row(file.as_ref(), values);
+    }
+
+    /// Adds a row for a video/audio file.
+    pub fn add_track_info(&mut self, file: impl AsRef<Path>, info: &TrackInfo) {
+        let values: Vec<_> = self
+            .columns
+            .iter()
+            .map(|c| match c {
+                ReportColumn::Exif(_) => None,
+                ReportColumn::Track(tag) => info.get(*tag),
+                ReportColumn::Name(name) => TrackInfoTag::ALL
+                    .iter()
+                    .find(|tag| tag.to_string().eq_ignore_ascii_case(name))
+                    .and_then(|tag| info.get(*tag))
+                    .or_else(|| {
+                        info.metadata()
+                            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                            .map(|(_, v)| v)
+                    }),
+            })
+            .map(|v| v.map(csv_value).unwrap_or_default())
+            .collect();
+        self.push_file_row(file.as_ref(), values);
+    }
+
+    /// Returns the rendered report, each row ends with a `\n`.
+    pub fn finish(self) -> String {
+        self.out
+    }
+
+    fn push_file_row(&mut self, file: &Path, values: Vec<String>) {
+        let row: Vec<_> = std::iter::once(file.to_string_lossy().into_owned())
+            .chain(values)
+            .collect();
+        self.push_row(row);
+    }
+
+    fn push_row(&mut self, row: Vec<String>) {
+        for (i, field) in row.iter().enumerate() {
+            if i > 0 {
+                self.out.push(self.delimiter);
+            }
+            if self.delimiter == '\t' {
+                self.out
+                    .extend(field.chars().map(|c| if c.is_control() { ' ' } else { c }));
+            } else if field.contains([self.delimiter, '"', '\n', '\r']) {
+                self.out.push('"');
+                self.out.push_str(&field.replace('"', "\"\""));
+                self.out.push('"');
+            } else {
+                self.out.push_str(field);
+            }
+        }
+        self.out.push('\n');
+    }
+}
+
+/// Renders `value` like [`exiftool_value`], but without the JSON quoting.
+fn csv_value(value: &EntryValue) -> String {
+    match value {
+        EntryValue::Text(s) => s.trim_end().to_owned(),
+        EntryValue::Undefined(x) => undefined_text(x),
+        v => unquote(exiftool_value(v)),
+    }
+}
+
+/// Renders `value` for a human, i.e. the interpretation of the `tag` (see
+/// [`ParsedExifEntry::interpreted_value`](crate::ParsedExifEntry::interpreted_value))
+/// if any, or the value rendered in the style of exiftool.
+pub(crate) fn pretty_value(tag: Option<ExifTag>, value: &EntryValue) -> String {
+    tag.and_then(|tag| print_conv::interpret(tag, value))
+        .unwrap_or_else(|| csv_value(value))
+}
+
+/// Writes a group of `(name, value)` rows in the style of `exiftool -g`, e.g.:
+///
+/// ```text
+/// ---- IFD0 ----
+/// Make                            : vivo
+/// ```
+///
+/// Nothing is written if there is no row.
+pub(crate) fn write_group(
+    f: &mut std::fmt::Formatter<'_>,
+    group: impl Display,
+    rows: impl IntoIterator<Item = (String, String)>,
+) -> std::fmt::Result {
+    let mut rows = rows.into_iter().peekable();
+    if rows.peek().is_none() {
+        return Ok(());
+    }
+    writeln!(f, "---- {group} ----")?;
+    for (name, value) in rows {
+        // Keep one row per line, the same way as exiftool
+        let value: String = value
+            .chars()
+            .map(|c| if c.is_control() { '.' } else { c })
+            .collect();
+        writeln!(f, "{name:<32}: {value}")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case(',', "a", "a")]
+    #[test_case(',', "a,b", "\"a,b\"")]
+    #[test_case(',', "say \"hi\"", "\"say \"\"hi\"\"\"")]
+    #[test_case(',', "a\nb", "\"a\nb\"")]
+    #[test_case('\t', "a,\"b\"", "a,\"b\"")]
+    #[test_case('\t', "a\tb\nc", "a b c")]
+    fn escape(delimiter: char, field: &str, expect: &str) {
+        let mut report = CsvReport::with_delimiter(Vec::<ReportColumn>::new(), delimiter);
+        report.out.clear();
+        report.push_row(vec![field.to_owned()]);
+        assert_eq!(report.finish(), format!("{expect}\n"));
+    }
+
+    #[test]
+    fn columns() {
+        let mut writer = crate::ExifWriter::new();
+        writer
+            .set(ExifTag::Make, "Canon ")
+            .set(ExifTag::ExposureTime, (1_u32, 250_u32))
+            .set(ExifTag::UserComment, "a\tb");
+        let data = writer.to_tiff_bytes().unwrap();
+        let exif: Exif = crate::parse_exif_bytes(&data).unwrap().into();
+
+        let mut report = CsvReport::tsv([
+            ReportColumn::from("make"),
+            ExifTag::ExposureTime.into(),
+            TrackInfoTag::Make.into(),
+            "UserComment".into(),
+            "NoSuchTag".into(),
+        ]);
+        report.add_exif("a b.jpg", &exif);
+        assert_eq!(
+            report.finish(),
+            "SourceFile\tmake\tExposureTime\tMake\tUserComment\tNoSuchTag\n\
+             a b.jpg\tCanon\t0.004\t\ta b\t\n"
+        );
+    }
+
+    #[test]
+    fn display_exif() {
+        let ms = crate::MediaSource::file_path("./testdata/exif.jpg").unwrap();
+        let iter: crate::ExifIter = crate::MediaParser::new().parse(ms).unwrap();
+
+        let s = iter.to_string();
+        assert!(s.starts_with("---- IFD0 ----\nImageWidth                      : 3072\n"));
+        assert!(s.contains("\n---- ExifIFD ----\n"));
+        assert!(s.contains("\nExposureProgram                 : Normal program\n"));
+        assert!(s.contains("\n---- GPS ----\nGPSLatitudeRef                  : North\n"));
+        assert!(s.contains("\n---- IFD1 ----\n"));
+        // The iterator is not advanced
+        assert_eq!(iter.to_string(), s);
+
+        let exif: Exif = iter.into();
+        let s = exif.to_string();
+        assert!(s.starts_with("---- IFD0 ----\n"));
+        assert!(s.contains("\nModel                           : vivo X90 Pro+\n"));
+        assert!(s.contains("\nExposureTime                    : 1/100\n"));
+        assert!(!s.contains("ExifIFD"));
+        assert!(s.contains("\n---- IFD1 ----\n"));
+    }
+
+    #[test]
+    fn display_track_info() {
+        let ms = crate::MediaSource::file_path("./testdata/meta.mov").unwrap();
+        let mut info: TrackInfo = crate::MediaParser::new().parse(ms).unwrap();
+        info.chapters = vec![
+            crate::Chapter {
+                edition: 0,
+                start_ms: 0,
+                end_ms: Some(3_723_004),
+                title: Some("Intro\n".into()),
+            },
+            crate::Chapter {
+                edition: 0,
+                start_ms: 3_723_004,
+                end_ms: None,
+                title: None,
+            },
+        ];
+
+        let s = info.to_string();
+        assert!(s.starts_with("---- Track ----\nMake                            : Apple\n"));
+        assert!(s.contains("\nCreateDate                      : 2024:02:02 08:09:57\n"));
+        assert!(s.ends_with(
+            "---- Chapters ----\n\
+             Chapter 1                       : 0:00:00.000 - 1:02:03.004 Intro.\n\
+             Chapter 2                       : 1:02:03.004\n"
+        ));
+    }
+}
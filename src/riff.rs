@@ -0,0 +1,288 @@
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime};
+
+use crate::{
+    error::ParsingError,
+    video::{TrackInfo, TrackInfoTag},
+};
+
+const HEADER_SIZE: usize = 12;
+const CHUNK_HEADER_SIZE: usize = 8;
+
+/// Max size of a chunk which is loaded into memory (e.g. `LIST INFO`), to
+/// avoid exhausting memory on corrupted files.
+const MAX_CHUNK_SIZE: usize = 16 * 1024 * 1024;
+
+/// Checks the RIFF header, and the `form_type` of it, e.g.: "AVI ", "WAVE".
+pub(crate) fn check_riff(input: &[u8], form_type: &[u8; 4]) -> bool {
+    input.len() >= HEADER_SIZE && input.starts_with(b"RIFF") && &input[8..12] == form_type
+}
+
+/// A RIFF form, e.g.: AVI, WAVE. It decides which top level chunks should be
+/// loaded, and collects info from them.
+pub(crate) trait RiffForm: Default + Into<TrackInfo> {
+    const FORM_TYPE: &'static [u8; 4];
+
+    /// Returns true if the top level chunk should be loaded, `list_type` is
+    /// provided for `LIST` chunks.
+    fn wants(id: &[u8; 4], list_type: Option<&[u8]>) -> bool;
+
+    /// Parses a loaded top level chunk, `data` includes the list type of
+    /// `LIST` chunks.
+    fn parse_chunk(&mut self, id: &[u8; 4], data: &[u8]);
+
+    /// Called for top level chunks which are not loaded, e.g. the media data.
+    fn skip_chunk(&mut self, _id: &[u8; 4], _size: u32) {}
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum State {
+    #[default]
+    Header,
+    Chunks,
+}
+
+/// Parses RIFF files chunk by chunk. Only the chunks wanted by the form `T`
+/// are loaded, other chunks (e.g. the media data) are skipped with
+/// `ClearAndSkip`.
+///
+/// Refer to: [RIFF](https://learn.microsoft.com/en-us/windows/win32/xaudio2/resource-interchange-file-format--riff-)
+#[derive(Debug, Default)]
+pub(crate) struct RiffParser<T> {
+    state: State,
+    /// Size of the remaining chunks in the RIFF chunk.
+    remain: u64,
+    info: T,
+}
+
+enum Step {
+    Consumed(usize),
+    Need(usize),
+    /// Skip `n` bytes, which extend beyond the input.
+    Skip(usize),
+    Done,
+}
+
+impl<T: RiffForm> RiffParser<T> {
+    /// Parses `input`, which starts where the last call stopped.
+    pub(crate) fn parse(&mut self, input: &[u8]) -> Result<TrackInfo, ParsingError> {
+        let mut pos = 0;
+        loop {
+            match self.step(&input[pos..])? {
+                Step::Consumed(n) => pos += n,
+                Step::Done => return Ok(std::mem::take(&mut self.info).into()),
+                Step::Need(n) if pos > 0 => {
+                    tracing::debug!(n, "need more bytes");
+                    return Err(ParsingError::ClearAndSkip(pos));
+                }
+                Step::Need(n) => return Err(ParsingError::Need(n)),
+                Step::Skip(n) => return Err(ParsingError::ClearAndSkip(pos + n)),
+            }
+        }
+    }
+
+    fn step(&mut self, input: &[u8]) -> Result<Step, ParsingError> {
+        match self.state {
+            State::Header => {
+                if input.len() < HEADER_SIZE {
+                    return Ok(Step::Need(HEADER_SIZE - input.len()));
+                }
+                if !check_riff(input, T::FORM_TYPE) {
+                    return Err("invalid RIFF header".into());
+                }
+                // The RIFF size includes the form type
+                self.remain = (u32_at(input, 4) as u64).saturating_sub(4);
+                self.state = State::Chunks;
+                Ok(Step::Consumed(HEADER_SIZE))
+            }
+            State::Chunks => {
+                if self.remain < CHUNK_HEADER_SIZE as u64 {
+                    return Ok(Step::Done);
+                }
+                let step = self.parse_chunk(input)?;
+                if let Step::Consumed(n) | Step::Skip(n) = step {
+                    self.remain = self.remain.saturating_sub(n as u64);
+                }
+                Ok(step)
+            }
+        }
+    }
+
+    fn parse_chunk(&mut self, input: &[u8]) -> Result<Step, ParsingError> {
+        if input.len() < CHUNK_HEADER_SIZE {
+            return Ok(Step::Need(CHUNK_HEADER_SIZE - input.len()));
+        }
+        let id: &[u8; 4] = input[..4].try_into().unwrap();
+        let size = u32_at(input, 4);
+        let total = chunk_total_size(input);
+
+        let list_type = if id == b"LIST" {
+            let Some(list_type) = input.get(CHUNK_HEADER_SIZE..HEADER_SIZE) else {
+                return Ok(Step::Need(HEADER_SIZE - input.len()));
+            };
+            Some(list_type)
+        } else {
+            None
+        };
+
+        if T::wants(id, list_type) {
+            if total > MAX_CHUNK_SIZE {
+                return Err(ParsingError::Failed(format!(
+                    "RIFF chunk is too big: {total}"
+                )));
+            }
+            if total > input.len() {
+                return Ok(Step::Need(total - input.len()));
+            }
+            self.info.parse_chunk(
+                id,
+                &input[CHUNK_HEADER_SIZE..CHUNK_HEADER_SIZE + size as usize],
+            );
+            return Ok(Step::Consumed(total));
+        }
+
+        tracing::debug!(id = ?String::from_utf8_lossy(id), total, "skip chunk");
+        self.info.skip_chunk(id, size);
+        if total > input.len() {
+            Ok(Step::Skip(total))
+        } else {
+            Ok(Step::Consumed(total))
+        }
+    }
+}
+
+/// Tags of the `INFO` list, which are shared by RIFF forms.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct InfoList {
+    /// `ISFT`
+    pub software: Option<String>,
+    /// `ICRD`
+    pub create_date: Option<DateTime<FixedOffset>>,
+    /// `ICMT`
+    pub comment: Option<String>,
+}
+
+impl InfoList {
+    /// Parses the sub-chunks of an `INFO` list.
+    pub fn parse(&mut self, list: &[u8]) {
+        for (id, data) in Chunks(list) {
+            match id {
+                b"ISFT" => self.software = to_text(data),
+                b"ICMT" => self.comment = to_text(data),
+                b"ICRD" => {
+                    if let Some(date) = parse_date(&to_string(data)) {
+                        self.create_date = Some(date);
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    pub fn put_into(self, info: &mut TrackInfo) {
+        if let Some(software) = self.software {
+            info.put(TrackInfoTag::Software, software.into());
+        }
+        if let Some(comment) = self.comment {
+            info.put(TrackInfoTag::Description, comment.into());
+        }
+        if let Some(date) = self.create_date {
+            info.put(TrackInfoTag::CreateDate, date.into());
+        }
+    }
+}
+
+/// Parses a date, e.g. `ICRD`, which should be "YYYY-MM-DD", but some
+/// encoders write a time or use other formats.
+///
+/// The date is treated as UTC, since there is no time zone info.
+pub(crate) fn parse_date(s: &str) -> Option<DateTime<FixedOffset>> {
+    let time = [
+        "%Y-%m-%d %H:%M:%S",
+        "%Y:%m:%d %H:%M:%S",
+        "%a %b %d %H:%M:%S %Y",
+    ]
+    .iter()
+    .find_map(|fmt| NaiveDateTime::parse_from_str(s, fmt).ok())
+    .or_else(|| {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            .ok()
+            .and_then(|x| x.and_hms_opt(0, 0, 0))
+    })?;
+    Some(time.and_utc().fixed_offset())
+}
+
+/// Iterates over the sub-chunks of a list, yields `(id, data)`.
+pub(crate) struct Chunks<'a>(pub &'a [u8]);
+
+impl<'a> Iterator for Chunks<'a> {
+    type Item = (&'a [u8; 4], &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0.len() < CHUNK_HEADER_SIZE {
+            return None;
+        }
+        let id: &[u8; 4] = self.0[..4].try_into().unwrap();
+        let size = u32_at(self.0, 4) as usize;
+        let data = self
+            .0
+            .get(CHUNK_HEADER_SIZE..CHUNK_HEADER_SIZE.saturating_add(size))?;
+        self.0 = self.0.get(chunk_total_size(self.0)..).unwrap_or_default();
+        Some((id, data))
+    }
+}
+
+/// Size of the chunk at the start of `input`, including the header and the
+/// pad byte.
+fn chunk_total_size(input: &[u8]) -> usize {
+    let size = u32_at(input, 4) as usize;
+    CHUNK_HEADER_SIZE
+        .saturating_add(size)
+        .saturating_add(size & 1)
+}
+
+pub(crate) fn u32_at(input: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes(input[pos..pos + 4].try_into().unwrap())
+}
+
+/// Converts a null-terminated (or null-padded) string.
+pub(crate) fn to_string(data: &[u8]) -> String {
+    let end = data.iter().position(|x| *x == 0).unwrap_or(data.len());
+    String::from_utf8_lossy(&data[..end]).trim().to_owned()
+}
+
+/// Same as [`to_string`], but returns `None` for empty strings.
+pub(crate) fn to_text(data: &[u8]) -> Option<String> {
+    Some(to_string(data)).filter(|x| !x.is_empty())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    pub(crate) fn chunk(id: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut chunk = id.to_vec();
+        chunk.extend((data.len() as u32).to_le_bytes());
+        chunk.extend(data);
+        if data.len() % 2 == 1 {
+            chunk.push(0);
+        }
+        chunk
+    }
+
+    pub(crate) fn list(list_type: &[u8], chunks: &[Vec<u8>]) -> Vec<u8> {
+        chunk(b"LIST", &[list_type, &chunks.concat()].concat())
+    }
+
+    pub(crate) fn le(values: &[u32]) -> Vec<u8> {
+        values.iter().flat_map(|x| x.to_le_bytes()).collect()
+    }
+
+    #[test_case("2005-12-31", Some("2005-12-31T00:00:00+00:00"))]
+    #[test_case("2005:12:31 12:00:00", Some("2005-12-31T12:00:00+00:00"))]
+    #[test_case("Sat Dec 31 12:00:00 2005", Some("2005-12-31T12:00:00+00:00"))]
+    #[test_case("2005", None)]
+    fn date(s: &str, expect: Option<&str>) {
+        assert_eq!(parse_date(s).map(|x| x.to_rfc3339()).as_deref(), expect);
+    }
+}
@@ -0,0 +1,616 @@
+use std::collections::HashSet;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use nom::number::Endianness;
+
+use crate::{
+    exif::{ifd_fields, u16_at, u32_at, u32_bytes, TiffHeader},
+    file::{Mime, MimeImage, MimeVideo},
+    patch::{find_top_level_box, read_at, sniff_mime},
+    strip::{exif_tiff_ranges, find_box, parse_boxes, RawBox},
+    Error,
+};
+
+const MAX_PAGES: usize = 1024;
+const MAX_BOX_DEPTH: usize = 8;
+
+const MODIFY_DATE: u16 = 0x0132;
+const DATE_TIME_ORIGINAL: u16 = 0x9003;
+const CREATE_DATE: u16 = 0x9004;
+const EXIF_OFFSET: u16 = 0x8769;
+const GPS_INFO: u16 = 0x8825;
+const GPS_TIME_STAMP: u16 = 0x0007;
+const GPS_DATE_STAMP: u16 = 0x001d;
+
+const EXIF_TIME_FORMAT: &str = "%Y:%m:%d %H:%M:%S";
+const GPS_DATE_FORMAT: &str = "%Y:%m:%d";
+const CREATIONDATE_KEY: &[u8] = b"com.apple.quicktime.creationdate";
+
+/// Reads a file from `reader`, and writes it into `writer` with all of its
+/// date/time tags shifted by `offset`, e.g. to fix the timestamps of photos
+/// taken by a camera with a wrong clock. The rest of the file is kept
+/// unchanged.
+///
+/// The shifted tags are:
+///
+/// - JPEG, TIFF & HEIF/HEIC/AVIF: `ModifyDate`, `DateTimeOriginal`,
+///   `CreateDate`, and `GPSDateStamp` & `GPSTimeStamp` of Exif data.
+///   `OffsetTime*` tags are kept, since the time zone doesn't change.
+///
+/// - MP4/QuickTime: the creation & modification times of the movie, tracks &
+///   media headers (`mvhd`, `tkhd` & `mdhd`), and the
+///   `com.apple.quicktime.creationdate` & `©day` metadata.
+///
+/// All timestamps are rewritten in place, so the file size doesn't change.
+/// Returns [`Error::WriteFailed`] for other file types, or if a shifted time
+/// can't be stored in its original format (e.g. the year goes beyond 9999).
+///
+/// ## Example
+///
+/// ```rust
+/// use nom_exif::*;
+/// use std::fs::File;
+///
+/// fn main() -> Result<()> {
+///     let mut output = Vec::new();
+///     shift_timestamps(
+///         File::open("./testdata/exif.jpg")?,
+///         &mut output,
+///         chrono::Duration::hours(-1),
+///     )?;
+///
+///     let ms = MediaSource::seekable(std::io::Cursor::new(output))?;
+///     let iter: ExifIter = MediaParser::new().parse(ms)?;
+///     let exif: Exif = iter.into();
+///     let time = exif.get(ExifTag::DateTimeOriginal).unwrap().as_time().unwrap();
+///     assert_eq!(time.to_rfc3339(), "2023-07-09T19:36:33+08:00");
+///     Ok(())
+/// }
+/// ```
+pub fn shift_timestamps<R: Read, W: Write>(
+    mut reader: R,
+    mut writer: W,
+    offset: Duration,
+) -> crate::Result<()> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+
+    match Mime::try_from(&data[..])? {
+        Mime::Video(MimeVideo::QuickTime | MimeVideo::Mp4 | MimeVideo::_3gpp) => {
+            let range = 0..data.len();
+            shift_boxes(&mut data, range, offset, 0)?;
+        }
+        mime @ Mime::Image(
+            MimeImage::Jpeg
+            | MimeImage::Tiff
+            | MimeImage::Pef
+            | MimeImage::Srw
+            | MimeImage::Heic
+            | MimeImage::Heif
+            | MimeImage::Avif,
+        ) => {
+            for range in exif_tiff_ranges(&data, mime)? {
+                shift_tiff(&mut data[range], offset)?; // Safe-slice
+            }
+        }
+        mime => {
+            return Err(Error::WriteFailed(
+                format!("shifting timestamps of {mime:?} files is not supported").into(),
+            ))
+        }
+    }
+
+    writer.write_all(&data)?;
+    Ok(())
+}
+
+/// The same as [`shift_timestamps`], but the timestamps are shifted in place,
+/// i.e. only the changed parts are written into `file`, without rewriting the
+/// whole file.
+///
+/// For MP4/QuickTime files, only the `moov` box is read & rewritten (the
+/// media data is skipped by seeking), which is much faster for large videos.
+/// Image files are read entirely, and only their Exif data is rewritten.
+///
+/// ## Example
+///
+/// ```rust
+/// use nom_exif::*;
+/// use std::io::Cursor;
+///
+/// fn main() -> Result<()> {
+///     let mut file = Cursor::new(std::fs::read("./testdata/meta.mov")?);
+///     shift_timestamps_in_place(&mut file, chrono::Duration::days(1))?;
+///
+///     file.set_position(0);
+///     let info: TrackInfo = MediaParser::new().parse(MediaSource::seekable(file)?)?;
+///     let time = info.get(TrackInfoTag::CreateDate).unwrap().as_time().unwrap();
+///     assert_eq!(time.to_rfc3339(), "2024-02-03T08:09:57+00:00");
+///     Ok(())
+/// }
+/// ```
+pub fn shift_timestamps_in_place<F: Read + Write + Seek>(
+    mut file: F,
+    offset: Duration,
+) -> crate::Result<()> {
+    match sniff_mime(&mut file)? {
+        Mime::Video(MimeVideo::QuickTime | MimeVideo::Mp4 | MimeVideo::_3gpp) => {
+            let moov = find_top_level_box(&mut file, b"moov")?.ok_or("moov box not found")?;
+            let mut data = read_at(&mut file, moov.start, (moov.end - moov.start) as usize)?;
+            let range = 0..data.len();
+            shift_boxes(&mut data, range, offset, 0)?;
+
+            file.seek(SeekFrom::Start(moov.start))?;
+            file.write_all(&data)?;
+        }
+        mime @ Mime::Image(
+            MimeImage::Jpeg
+            | MimeImage::Tiff
+            | MimeImage::Pef
+            | MimeImage::Srw
+            | MimeImage::Heic
+            | MimeImage::Heif
+            | MimeImage::Avif,
+        ) => {
+            file.seek(SeekFrom::Start(0))?;
+            let mut data = Vec::new();
+            file.read_to_end(&mut data)?;
+            // All blocks are shifted before writing, so the file is kept
+            // unchanged on errors
+            let ranges = exif_tiff_ranges(&data, mime)?;
+            for range in ranges.iter() {
+                shift_tiff(&mut data[range.clone()], offset)?; // Safe-slice
+            }
+            for range in ranges {
+                file.seek(SeekFrom::Start(range.start as u64))?;
+                file.write_all(&data[range])?; // Safe-slice
+            }
+        }
+        mime => {
+            return Err(Error::WriteFailed(
+                format!("shifting timestamps of {mime:?} files is not supported").into(),
+            ))
+        }
+    }
+    file.flush()?;
+    Ok(())
+}
+
+fn out_of_range() -> Error {
+    Error::WriteFailed("shifted time is out of range".into())
+}
+
+/// Shifts a date/time string in place, if it's in one of `formats`. The
+/// shifted time is formatted in the same format.
+fn shift_text(
+    bytes: &mut [u8],
+    formats: &[&str],
+    naive_formats: &[&str],
+    offset: Duration,
+) -> crate::Result<()> {
+    let Ok(s) = std::str::from_utf8(bytes) else {
+        return Ok(());
+    };
+    // Only the formats which round-trip exactly are accepted, so that the
+    // shifted string is in the same format (and has the same length)
+    let mut shifted = None;
+    for fmt in formats {
+        if let Ok(t) = DateTime::parse_from_str(s, fmt) {
+            if t.format(fmt).to_string() == s {
+                let t = t.checked_add_signed(offset).ok_or_else(out_of_range)?;
+                shifted = Some(t.format(fmt).to_string());
+                break;
+            }
+        }
+    }
+    for fmt in naive_formats.iter().filter(|_| shifted.is_none()) {
+        if let Ok(t) = NaiveDateTime::parse_from_str(s, fmt) {
+            if t.format(fmt).to_string() == s {
+                let t = t.checked_add_signed(offset).ok_or_else(out_of_range)?;
+                shifted = Some(t.format(fmt).to_string());
+                break;
+            }
+        }
+    }
+
+    match shifted {
+        Some(x) if x.len() == bytes.len() => bytes.copy_from_slice(x.as_bytes()),
+        Some(_) => return Err(out_of_range()),
+        None => (),
+    }
+    Ok(())
+}
+
+/// Shifts the time tags in the IFDs of the TIFF data in place.
+fn shift_tiff(data: &mut [u8], offset: Duration) -> crate::Result<()> {
+    let (_, header) = TiffHeader::parse(data)?;
+    let mut tiff = TiffShifter {
+        data,
+        endian: header.endian,
+        offset,
+        visited: HashSet::new(),
+    };
+
+    let mut ifd = header.ifd0_offset as usize;
+    for _ in 0..MAX_PAGES {
+        if ifd == 0 || !tiff.visited.insert(ifd) {
+            break;
+        }
+        let entries = tiff.entries(ifd)?;
+        for (tag, range) in entries.iter() {
+            match *tag {
+                MODIFY_DATE => tiff.shift_time(range.clone())?,
+                EXIF_OFFSET | GPS_INFO => {
+                    let sub_ifd = tiff.u32_at(range.start).unwrap_or_default() as usize;
+                    if tiff.visited.insert(sub_ifd) {
+                        tiff.shift_sub_ifd(*tag, sub_ifd)?;
+                    }
+                }
+                _ => (),
+            }
+        }
+        ifd = tiff.next_ifd(ifd).unwrap_or_default() as usize;
+    }
+    Ok(())
+}
+
+struct TiffShifter<'a> {
+    data: &'a mut [u8],
+    endian: Endianness,
+    offset: Duration,
+    // Offsets of the visited IFDs, to avoid loops
+    visited: HashSet<usize>,
+}
+
+impl TiffShifter<'_> {
+    fn u32_at(&self, pos: usize) -> Option<u32> {
+        u32_at(self.data, pos, self.endian)
+    }
+
+    fn set_u32(&mut self, pos: usize, v: u32) {
+        self.data[pos..pos + 4].copy_from_slice(&u32_bytes(v, self.endian)); // Safe-slice
+    }
+
+    fn next_ifd(&self, ifd: usize) -> Option<u32> {
+        let (_, next_pos) = ifd_fields(self.data, ifd, self.endian)?;
+        self.u32_at(next_pos)
+    }
+
+    /// Returns the tags & value ranges of the entries in the IFD at `ifd`,
+    /// entries whose values are out of the data are skipped.
+    fn entries(&self, ifd: usize) -> crate::Result<Vec<(u16, Range<usize>)>> {
+        if u16_at(self.data, ifd, self.endian).is_none() {
+            return Err("invalid TIFF data; IFD not found".into());
+        }
+        let (fields, _) =
+            ifd_fields(self.data, ifd, self.endian).ok_or("invalid TIFF data; IFD is truncated")?;
+        Ok(fields
+            .into_iter()
+            .filter(|x| x.format.is_some())
+            .map(|x| (x.code, x.value_range()))
+            .filter(|x| x.1.end <= self.data.len())
+            .collect())
+    }
+
+    fn shift_sub_ifd(&mut self, tag: u16, ifd: usize) -> crate::Result<()> {
+        let entries = self.entries(ifd)?;
+        let find = |code| entries.iter().find(|x| x.0 == code).map(|x| x.1.clone());
+        if tag == EXIF_OFFSET {
+            for range in [DATE_TIME_ORIGINAL, CREATE_DATE]
+                .into_iter()
+                .filter_map(find)
+            {
+                self.shift_time(range)?;
+            }
+        } else if let Some(time) = find(GPS_TIME_STAMP).filter(|x| x.len() == 24) {
+            self.shift_gps_time(find(GPS_DATE_STAMP), time)?;
+        }
+        Ok(())
+    }
+
+    /// Shifts an ASCII time value, e.g. "2023:07:09 20:23:45".
+    fn shift_time(&mut self, range: Range<usize>) -> crate::Result<()> {
+        let bytes = &mut self.data[range]; // Safe-slice
+        let len = bytes.iter().position(|x| *x == 0).unwrap_or(bytes.len());
+        shift_text(&mut bytes[..len], &[], &[EXIF_TIME_FORMAT], self.offset)
+    }
+
+    /// Shifts `GPSTimeStamp` (hour, minute & second rationals), and
+    /// `GPSDateStamp` (e.g. "2023:07:09") if any, together in UTC.
+    fn shift_gps_time(
+        &mut self,
+        date: Option<Range<usize>>,
+        time: Range<usize>,
+    ) -> crate::Result<()> {
+        let rationals = (0..3)
+            .map(|i| {
+                let pos = time.start + i * 8;
+                (
+                    self.u32_at(pos).unwrap_or_default(),
+                    self.u32_at(pos + 4).unwrap_or_default(),
+                )
+            })
+            .collect::<Vec<_>>();
+        if rationals.iter().any(|x| x.1 == 0) {
+            return Ok(());
+        }
+        let (sec, sec_den) = rationals[2];
+        let Some(t) = NaiveTime::from_hms_opt(
+            rationals[0].0 / rationals[0].1,
+            rationals[1].0 / rationals[1].1,
+            sec / sec_den,
+        ) else {
+            return Ok(());
+        };
+
+        let date = date.and_then(|range| {
+            let bytes = &self.data[range.clone()]; // Safe-slice
+            let s = std::str::from_utf8(bytes).ok()?.trim_end_matches('\0');
+            let date = NaiveDate::parse_from_str(s, GPS_DATE_FORMAT).ok()?;
+            (date.format(GPS_DATE_FORMAT).to_string() == s).then_some((range.start, date))
+        });
+        // Without a date, only the time of the day is shifted
+        let shifted = date
+            .map_or(NaiveDate::default(), |x| x.1)
+            .and_time(t)
+            .checked_add_signed(self.offset)
+            .ok_or_else(out_of_range)?;
+
+        if let Some((pos, _)) = date {
+            let s = shifted.format(GPS_DATE_FORMAT).to_string();
+            if s.len() != 10 {
+                return Err(out_of_range());
+            }
+            self.data[pos..pos + 10].copy_from_slice(s.as_bytes()); // Safe-slice
+        }
+
+        // Fractions of the second are kept
+        let shifted = shifted.time();
+        let secs = shifted
+            .second()
+            .checked_mul(sec_den)
+            .and_then(|x| x.checked_add(sec % sec_den));
+        let (sec, sec_den) = secs.map_or((shifted.second(), 1), |x| (x, sec_den));
+        for (i, (num, den)) in [(shifted.hour(), 1), (shifted.minute(), 1), (sec, sec_den)]
+            .into_iter()
+            .enumerate()
+        {
+            self.set_u32(time.start + i * 8, num);
+            self.set_u32(time.start + i * 8 + 4, den);
+        }
+        Ok(())
+    }
+}
+
+/// Shifts the timestamps in the boxes of MP4/QuickTime files in place.
+fn shift_boxes(
+    data: &mut [u8],
+    range: Range<usize>,
+    offset: Duration,
+    depth: usize,
+) -> crate::Result<()> {
+    for bbox in parse_boxes(data, range)? {
+        match &bbox.box_type {
+            b"mvhd" | b"tkhd" | b"mdhd" => shift_header_times(data, &bbox, offset)?,
+            b"meta" => shift_metadata(data, &bbox, offset)?,
+            // QuickTime user data text, i.e. the text size (2 bytes), the
+            // language code (2 bytes), then the text
+            b"\xa9day" => {
+                let body = bbox.body();
+                if let Some(size) = data.get(body.start..body.start + 2) {
+                    let size = u16::from_be_bytes(size.try_into().unwrap()) as usize;
+                    let text = body.start + 4..body.start + 4 + size;
+                    if text.end <= body.end {
+                        shift_date_text(&mut data[text], offset)?; // Safe-slice
+                    }
+                }
+            }
+            b"moov" | b"trak" | b"mdia" | b"udta" if depth < MAX_BOX_DEPTH => {
+                shift_boxes(data, bbox.body(), offset, depth + 1)?
+            }
+            _ => (),
+        }
+    }
+    Ok(())
+}
+
+/// Shifts an ISO 8601 date/time string, e.g. "2019-02-12T15:27:12+0800".
+fn shift_date_text(bytes: &mut [u8], offset: Duration) -> crate::Result<()> {
+    shift_text(
+        bytes,
+        &["%Y-%m-%dT%H:%M:%S%z", "%Y-%m-%dT%H:%M:%S%:z"],
+        &["%Y-%m-%dT%H:%M:%SZ", "%Y-%m-%dT%H:%M:%S"],
+        offset,
+    )
+}
+
+/// Shifts the creation & modification times of `mvhd`, `tkhd` & `mdhd`,
+/// which are seconds since midnight, January 1, 1904 in UTC, 0 means unknown.
+fn shift_header_times(data: &mut [u8], bbox: &RawBox, offset: Duration) -> crate::Result<()> {
+    let body = bbox.body();
+    // Full box, 64-bit times for version 1
+    let size = match data.get(body.start) {
+        Some(1) => 8,
+        Some(_) => 4,
+        None => return Ok(()),
+    };
+    for i in 0..2 {
+        let pos = body.start + 4 + i * size;
+        let Some(bytes) = data.get_mut(pos..pos + size) else {
+            break;
+        };
+        let mut buf = [0; 8];
+        buf[8 - size..].copy_from_slice(bytes); // Safe-slice
+        let secs = u64::from_be_bytes(buf);
+        if secs == 0 {
+            continue;
+        }
+        let secs = i64::try_from(secs)
+            .ok()
+            .and_then(|x| x.checked_add(offset.num_seconds()))
+            .and_then(|x| u64::try_from(x).ok())
+            .filter(|x| size == 8 || *x <= u32::MAX as u64)
+            .ok_or_else(out_of_range)?;
+        bytes.copy_from_slice(&secs.to_be_bytes()[8 - size..]); // Safe-slice
+    }
+    Ok(())
+}
+
+/// Shifts the `com.apple.quicktime.creationdate` & `©day` items in a `meta`
+/// box.
+fn shift_metadata(data: &mut [u8], meta: &RawBox, offset: Duration) -> crate::Result<()> {
+    let children = meta.meta_children(data)?;
+    let Some(ilst) = find_box(data, children.clone(), b"ilst")? else {
+        return Ok(());
+    };
+
+    // 1-based index of the creation date key, entries of `keys` are: size
+    // (4 bytes), namespace (4 bytes), then the key name
+    let mut key_index = None;
+    if let Some(keys) = find_box(data, children, b"keys")? {
+        let keys = keys.body();
+        let mut pos = keys.start + 8;
+        let mut index = 1_u32;
+        while pos + 8 <= keys.end {
+            let size = u32::from_be_bytes(data[pos..pos + 4].try_into().unwrap()) as usize; // Safe-slice
+            if size < 8 || pos + size > keys.end {
+                return Err("invalid keys box".into());
+            }
+            if &data[pos + 8..pos + size] == CREATIONDATE_KEY {
+                key_index = Some(index);
+            }
+            pos += size;
+            index += 1;
+        }
+    }
+
+    for item in parse_boxes(data, ilst.body())? {
+        let is_date = &item.box_type == b"\xa9day"
+            || key_index.is_some_and(|x| x.to_be_bytes() == item.box_type);
+        if !is_date {
+            continue;
+        }
+        // The value box: type (4 bytes), locale (4 bytes), then the value
+        if let Some(value) = find_box(data, item.body(), b"data")? {
+            let value = value.body();
+            if value.start + 8 <= value.end {
+                shift_date_text(&mut data[value.start + 8..value.end], offset)?;
+                // Safe-slice
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        testkit::read_sample, Exif, ExifIter, ExifTag, MediaParser, MediaSource, TrackInfo,
+        TrackInfoTag,
+    };
+    use std::io::Cursor;
+    use test_case::test_case;
+
+    fn shift(path: &str, offset: Duration) -> (Vec<u8>, Vec<u8>) {
+        let input = read_sample(path).unwrap();
+        let mut output = Vec::new();
+        shift_timestamps(Cursor::new(&input), &mut output, offset).unwrap();
+        assert_eq!(output.len(), input.len());
+        (input, output)
+    }
+
+    #[test_case("exif.jpg")]
+    #[test_case("exif.heic")]
+    fn shift_exif(path: &str) {
+        let offset = Duration::hours(-13) - Duration::seconds(5);
+        let (input, output) = shift(path, offset);
+        let parse = |data| -> Exif {
+            let ms = MediaSource::seekable(Cursor::new(data)).unwrap();
+            let iter: ExifIter = MediaParser::new().parse(ms).unwrap();
+            iter.into()
+        };
+        let before = parse(input);
+        let after = parse(output);
+        for tag in [
+            ExifTag::ModifyDate,
+            ExifTag::DateTimeOriginal,
+            ExifTag::CreateDate,
+        ] {
+            let time = before.get(tag).and_then(|x| x.as_time()).unwrap();
+            assert_eq!(after.get(tag), Some(&(time + offset).into()));
+        }
+        for tag in [ExifTag::Make, ExifTag::OffsetTimeOriginal] {
+            assert_eq!(after.get(tag), before.get(tag));
+        }
+
+        let gps_time = |exif: &Exif| {
+            let date = exif.get(ExifTag::GPSDateStamp)?.to_string();
+            let time = exif.get(ExifTag::GPSTimeStamp)?.as_urational_array()?;
+            let secs = time.iter().fold(0, |acc, x| acc * 60 + x.0 / x.1);
+            let date = NaiveDate::parse_from_str(&date, "%Y:%m:%d").ok()?;
+            Some(date.and_hms_opt(0, 0, 0)? + Duration::seconds(secs as i64))
+        };
+        assert_eq!(gps_time(&after), gps_time(&before).map(|x| x + offset));
+    }
+
+    #[test_case("meta.mov")]
+    #[test_case("meta.mp4")]
+    fn shift_track(path: &str) {
+        let offset = Duration::days(365);
+        let (input, output) = shift(path, offset);
+        let parse = |data| -> TrackInfo {
+            let ms = MediaSource::seekable(Cursor::new(data)).unwrap();
+            MediaParser::new().parse(ms).unwrap()
+        };
+        let before = parse(input);
+        let after = parse(output);
+        let time = before
+            .get(TrackInfoTag::CreateDate)
+            .and_then(|x| x.as_time())
+            .unwrap();
+        assert_eq!(
+            after.get(TrackInfoTag::CreateDate),
+            Some(&(time + offset).into())
+        );
+        assert_eq!(
+            after.get(TrackInfoTag::DurationMs),
+            before.get(TrackInfoTag::DurationMs)
+        );
+    }
+
+    #[test_case("exif.jpg")]
+    #[test_case("exif.heic")]
+    #[test_case("tif.tif")]
+    #[test_case("meta.mov")]
+    #[test_case("meta.mp4")]
+    fn shift_in_place(path: &str) {
+        let offset = Duration::minutes(-90);
+        let (_, expect) = shift(path, offset);
+        let mut file = Cursor::new(read_sample(path).unwrap());
+        shift_timestamps_in_place(&mut file, offset).unwrap();
+        assert!(file.into_inner() == expect);
+    }
+
+    #[test_case("2019-02-12T15:27:12+0800", "2019-02-13T00:27:12+0800")]
+    #[test_case("2019-02-12T15:27:12+08:00", "2019-02-13T00:27:12+08:00")]
+    #[test_case("2019-02-12T15:27:12Z", "2019-02-13T00:27:12Z")]
+    #[test_case("2019", "2019")]
+    fn shift_date_text(s: &str, expect: &str) {
+        let mut bytes = s.as_bytes().to_vec();
+        super::shift_date_text(&mut bytes, Duration::hours(9)).unwrap();
+        assert_eq!(String::from_utf8(bytes).unwrap(), expect);
+    }
+
+    #[test]
+    fn out_of_range() {
+        let mut bytes = b"9999:12:31 23:59:59".to_vec();
+        let res = shift_text(&mut bytes, &[], &[EXIF_TIME_FORMAT], Duration::seconds(1));
+        assert!(res.is_err());
+
+        let res = shift_timestamps(Cursor::new(b"GIF89a\0\0\0\0"), Vec::new(), Duration::zero());
+        assert!(res.is_err());
+    }
+}
@@ -91,12 +91,12 @@ impl<R: Seek> Skip<R> for Seekable {
     #[inline]
     fn skip(reader: &mut R, skip: u64) -> io::Result<()> {
         // println!("seekable...");
-        reader.seek_relative(skip.try_into().unwrap())
+        reader.seek_relative(seek_offset(skip)?)
     }
 
     #[inline]
     fn skip_by_seek(reader: &mut R, skip: u64) -> io::Result<bool> {
-        reader.seek_relative(skip.try_into().unwrap())?;
+        reader.seek_relative(seek_offset(skip)?)?;
         Ok(true)
     }
 
@@ -105,6 +105,13 @@ impl<R: Seek> Skip<R> for Seekable {
     }
 }
 
+/// Converts a skip length into a relative seek offset, fails if it's too
+/// large (which usually means the input is corrupted).
+fn seek_offset(skip: u64) -> io::Result<i64> {
+    i64::try_from(skip)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "skip size overflow"))
+}
+
 #[cfg(feature = "async")]
 impl<R: AsyncRead + Unpin + Send> AsyncSkip<R> for Unseekable {
     #[inline]
@@ -121,7 +128,10 @@ impl<R: AsyncRead + Unpin + Send> AsyncSkip<R> for Unseekable {
 impl<R: AsyncSeek + Unpin + Send> AsyncSkip<R> for Seekable {
     #[inline]
     async fn skip_by_seek(reader: &mut R, skip: u64) -> io::Result<bool> {
-        match reader.seek(std::io::SeekFrom::Current(skip as i64)).await {
+        match reader
+            .seek(std::io::SeekFrom::Current(seek_offset(skip)?))
+            .await
+        {
             Ok(_) => Ok(true),
             Err(e) => Err(e),
         }
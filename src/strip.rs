@@ -0,0 +1,1107 @@
+use std::collections::HashSet;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::ops::{BitOr, BitOrAssign, Range};
+
+use nom::number::Endianness;
+
+use crate::{
+    bbox::{InfeBox, MetaBox, ParseBox},
+    exif::{
+        check_exif_header, check_exif_header2, ifd_fields, u16_at, u16_bytes, u32_at, IfdField,
+        TiffHeader, EXIF_IDENT,
+    },
+    file::{Mime, MimeImage, MimeVideo},
+    jp2::XMP_UUID,
+    jpeg::{
+        self, MarkerCode, EXTENDED_XMP_SIGNATURE, ICC_PROFILE_SIGNATURE, PHOTOSHOP_SIGNATURE,
+        XMP_SIGNATURE,
+    },
+    patch::{read_at, sniff_mime, top_level_boxes},
+    Error,
+};
+
+// Maximum number of pages to strip in a multi-page TIFF file
+const MAX_PAGES: usize = 1024;
+const MAX_IFD_DEPTH: usize = 8;
+const MAX_BOX_DEPTH: usize = 8;
+
+const EXIF_OFFSET: u16 = 0x8769;
+const GPS_INFO: u16 = 0x8825;
+const INTEROP_OFFSET: u16 = 0xa005;
+
+/// Kinds of metadata to be removed by [`strip_metadata`], which can be
+/// combined with `|`, e.g. `StripPolicy::EXIF | StripPolicy::XMP`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct StripPolicy(u8);
+
+impl StripPolicy {
+    /// Exif data, including GPS info & MakerNotes.
+    ///
+    /// For TIFF files, only the Exif & GPS sub-IFDs and the descriptive tags
+    /// (e.g. `Make`, `Model`, `Artist`) are removed, tags describing the
+    /// image data (e.g. `ImageWidth`, `Orientation`) are kept.
+    ///
+    /// For MP4/QuickTime files, it means the user data (`udta`) & metadata
+    /// (`meta`) boxes, e.g. the device model & GPS location.
+    pub const EXIF: StripPolicy = StripPolicy(1);
+    pub const XMP: StripPolicy = StripPolicy(1 << 1);
+    /// IPTC-IIM datasets, along with the Photoshop resources containing them
+    pub const IPTC: StripPolicy = StripPolicy(1 << 2);
+    /// Embedded ICC color profiles
+    pub const ICC: StripPolicy = StripPolicy(1 << 3);
+    /// GPS info only, i.e. the GPS IFD of Exif data, and the location atoms
+    /// of MP4/QuickTime files (`©xyz`, `loci` & the
+    /// `com.apple.quicktime.location.*` metadata), all other metadata is
+    /// kept. It's implied by [`StripPolicy::EXIF`].
+    ///
+    /// *Note*: Locations recorded in XMP (e.g. `exif:GPSLatitude`) are not
+    /// removed.
+    pub const GPS: StripPolicy = StripPolicy(1 << 4);
+    pub const ALL: StripPolicy = StripPolicy(0b11111);
+
+    pub const fn empty() -> StripPolicy {
+        StripPolicy(0)
+    }
+
+    /// Returns true if all kinds of metadata in `other` are contained.
+    pub const fn contains(self, other: StripPolicy) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for StripPolicy {
+    type Output = StripPolicy;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        StripPolicy(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for StripPolicy {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// Reads a file from `reader`, and writes it into `writer` with the metadata
+/// selected by `policy` removed, the rest of the file is kept unchanged.
+///
+/// Supported file types:
+///
+/// - JPEG: the metadata segments are removed. Only the segments before the
+///   image data are read into memory, the image data is copied in chunks.
+///
+/// - TIFF & HEIF/HEIC/AVIF: the metadata is removed in place, i.e. the
+///   entries/boxes are dropped & their data is zeroed, so that the offsets of
+///   the image data stay valid and the file size doesn't change. These files
+///   are read into memory entirely.
+///
+/// - MP4/QuickTime: the metadata boxes are dropped, and the chunk offsets are
+///   adjusted accordingly. Only the `moov` box is read into memory, the media
+///   data is copied in chunks, so files of any size can be stripped.
+///
+/// Returns [`Error::WriteFailed`] for other file types.
+///
+/// ## Example
+///
+/// ```rust
+/// use nom_exif::*;
+/// use std::fs::File;
+///
+/// fn main() -> Result<()> {
+///     let mut output = Vec::new();
+///     strip_metadata(
+///         File::open("./testdata/exif.jpg")?,
+///         &mut output,
+///         StripPolicy::EXIF | StripPolicy::XMP,
+///     )?;
+///
+///     let ms = MediaSource::seekable(std::io::Cursor::new(output))?;
+///     let iter: Result<ExifIter> = MediaParser::new().parse(ms);
+///     assert!(iter.is_err());
+///     Ok(())
+/// }
+/// ```
+pub fn strip_metadata<R: Read + Seek, W: Write>(
+    mut reader: R,
+    mut writer: W,
+    policy: StripPolicy,
+) -> crate::Result<()> {
+    let mime = sniff_mime(&mut reader)?;
+    reader.seek(SeekFrom::Start(0))?;
+    match mime {
+        Mime::Image(MimeImage::Jpeg) => return strip_jpeg(reader, writer, policy),
+        Mime::Video(MimeVideo::QuickTime | MimeVideo::Mp4 | MimeVideo::_3gpp) => {
+            return strip_bmff(reader, writer, policy)
+        }
+        _ => (),
+    }
+
+    // The entries/boxes of TIFF & HEIF files may refer to any part of the
+    // file, so the whole file is loaded
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    if policy.contains(StripPolicy::GPS) {
+        strip_gps(&mut data, mime)?;
+    }
+
+    match mime {
+        Mime::Image(MimeImage::Tiff | MimeImage::Pef | MimeImage::Srw) => {
+            strip_tiff(&mut data, |tag| {
+                tiff_tag_policy(tag).is_some_and(|x| policy.contains(x))
+            })?
+        }
+        Mime::Image(MimeImage::Heic | MimeImage::Heif | MimeImage::Avif) => {
+            strip_heif(&mut data, policy)?
+        }
+        mime => {
+            return Err(Error::WriteFailed(
+                format!("stripping metadata from {mime:?} files is not supported").into(),
+            ))
+        }
+    }
+
+    writer.write_all(&data)?;
+    Ok(())
+}
+
+/// Strips the metadata segments of JPEG files, only the segments before SOS
+/// are read into memory, the image data is copied in chunks.
+fn strip_jpeg<R: Read, W: Write>(
+    mut reader: R,
+    mut writer: W,
+    policy: StripPolicy,
+) -> crate::Result<()> {
+    let mut data = jpeg::read_segments(&mut reader)?;
+    if policy.contains(StripPolicy::GPS) {
+        strip_gps(&mut data, Mime::Image(MimeImage::Jpeg))?;
+    }
+
+    let keep = |code: u8, payload: &[u8]| {
+        let strip = if code == MarkerCode::APP1.code() {
+            (policy.contains(StripPolicy::EXIF) && check_exif_header(payload))
+                || (policy.contains(StripPolicy::XMP)
+                    && (payload.starts_with(XMP_SIGNATURE)
+                        || payload.starts_with(EXTENDED_XMP_SIGNATURE)))
+        } else if code == MarkerCode::APP2.code() {
+            policy.contains(StripPolicy::ICC) && payload.starts_with(ICC_PROFILE_SIGNATURE)
+        } else if code == MarkerCode::APP13.code() {
+            policy.contains(StripPolicy::IPTC) && payload.starts_with(PHOTOSHOP_SIGNATURE)
+        } else {
+            false
+        };
+        !strip
+    };
+    jpeg::rewrite_segments(&data, &mut writer, keep, &[])?;
+    io::copy(&mut reader, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Returns the kind of metadata of a TIFF tag in ifd0 (or other top level
+/// IFDs).
+fn tiff_tag_policy(tag: u16) -> Option<StripPolicy> {
+    match tag {
+        // Sub-IFDs; ImageDescription, Make, Model; Software, ModifyDate;
+        // Artist, HostComputer; Copyright; XPTitle ... XPSubject
+        EXIF_OFFSET
+        | GPS_INFO
+        | 0x010e..=0x0110
+        | 0x0131
+        | 0x0132
+        | 0x013b
+        | 0x013c
+        | 0x8298
+        | 0x9c9b..=0x9c9f => Some(StripPolicy::EXIF),
+        0x02bc => Some(StripPolicy::XMP),
+        // IPTC-NAA; Photoshop image resources
+        0x83bb | 0x8649 => Some(StripPolicy::IPTC),
+        0x8773 => Some(StripPolicy::ICC),
+        _ => None,
+    }
+}
+
+/// Removes the entries whose tags are matched by `strip` from the top level
+/// IFDs (i.e. ifd0, ifd1, ...) of the TIFF data in place.
+fn strip_tiff(data: &mut [u8], strip: impl Fn(u16) -> bool + Copy) -> crate::Result<()> {
+    let (_, header) = TiffHeader::parse(data)?;
+    let mut tiff = TiffStripper {
+        data,
+        endian: header.endian,
+        visited: HashSet::new(),
+    };
+
+    let mut offset = header.ifd0_offset as usize;
+    for _ in 0..MAX_PAGES {
+        if offset == 0 || !tiff.visited.insert(offset) {
+            break;
+        }
+        offset = tiff.strip_ifd(offset, strip)?;
+    }
+    Ok(())
+}
+
+struct TiffStripper<'a> {
+    data: &'a mut [u8],
+    endian: Endianness,
+    // Offsets of the visited IFDs, to avoid loops
+    visited: HashSet<usize>,
+}
+
+impl TiffStripper<'_> {
+    fn zero(&mut self, range: Range<usize>) {
+        let end = range.end.min(self.data.len());
+        if let Some(x) = self.data.get_mut(range.start..end) {
+            x.fill(0);
+        }
+    }
+
+    /// Removes the entries whose tags are matched by `strip` from the IFD at
+    /// `offset` in place, then returns the offset of the next IFD.
+    fn strip_ifd(&mut self, offset: usize, strip: impl Fn(u16) -> bool) -> crate::Result<usize> {
+        if u16_at(self.data, offset, self.endian).is_none() {
+            return Err("invalid TIFF data; IFD not found".into());
+        }
+        let start = offset + 2;
+        let (fields, end) = ifd_fields(self.data, offset, self.endian)
+            .ok_or("invalid TIFF data; IFD is truncated")?;
+        let next =
+            u32_at(self.data, end, self.endian).ok_or("invalid TIFF data; IFD is truncated")?;
+
+        let mut kept = Vec::with_capacity(end - start);
+        for field in fields {
+            if strip(field.code) {
+                self.zero_entry(&field, 0);
+            } else {
+                kept.extend_from_slice(&self.data[field.pos..field.pos + 12]); // Safe-slice
+            }
+        }
+
+        let num = u16_bytes((kept.len() / 12) as u16, self.endian);
+        self.data[offset..start].copy_from_slice(&num); // Safe-slice
+        self.data[start..start + kept.len()].copy_from_slice(&kept); // Safe-slice
+        let next_pos = start + kept.len();
+        self.data.copy_within(end..end + 4, next_pos);
+        self.zero(next_pos + 4..end + 4);
+
+        Ok(next as usize)
+    }
+
+    /// Zeroes the data of the entry which is stored out of the entry, and the
+    /// sub-IFD it points to, if any.
+    fn zero_entry(&mut self, field: &IfdField, depth: usize) {
+        if field.size() > 4 {
+            self.zero(field.value_range());
+        }
+
+        let offset = field.value as usize;
+        if matches!(field.code, EXIF_OFFSET | GPS_INFO | INTEROP_OFFSET)
+            && depth < MAX_IFD_DEPTH
+            && self.visited.insert(offset)
+        {
+            self.zero_ifd(offset, depth + 1);
+        }
+    }
+
+    fn zero_ifd(&mut self, offset: usize, depth: usize) {
+        let Some(num) = u16_at(self.data, offset, self.endian) else {
+            return;
+        };
+        if let Some((fields, _)) = ifd_fields(self.data, offset, self.endian) {
+            for field in &fields {
+                self.zero_entry(field, depth);
+            }
+        }
+        self.zero(offset..offset + 2 + num as usize * 12 + 4);
+    }
+}
+
+/// Removes the GPS IFD of Exif data in place, see [`StripPolicy::GPS`].
+fn strip_gps(data: &mut [u8], mime: Mime) -> crate::Result<()> {
+    for range in exif_tiff_ranges(data, mime)? {
+        strip_tiff(&mut data[range], |tag| tag == GPS_INFO)?; // Safe-slice
+    }
+    Ok(())
+}
+
+/// Returns the ranges of the TIFF data (i.e. starting with the TIFF header)
+/// of the Exif blocks in a JPEG, TIFF or HEIF file.
+pub(crate) fn exif_tiff_ranges(data: &[u8], mime: Mime) -> crate::Result<Vec<Range<usize>>> {
+    let mut ranges = Vec::new();
+    match mime {
+        Mime::Image(MimeImage::Jpeg) => {
+            let (segments, _) = jpeg::parse_segments(data)?;
+            for segment in segments {
+                let payload = segment.payload;
+                if segment.code == MarkerCode::APP1.code()
+                    && data.get(payload.clone()).is_some_and(check_exif_header)
+                {
+                    ranges.push(payload.start + EXIF_IDENT.len()..payload.end);
+                }
+            }
+        }
+        Mime::Image(MimeImage::Tiff | MimeImage::Pef | MimeImage::Srw) => {
+            ranges.push(0..data.len())
+        }
+        Mime::Image(MimeImage::Heic | MimeImage::Heif | MimeImage::Avif) => {
+            let meta = find_box(data, 0..data.len(), b"meta")?.ok_or("meta box not found")?;
+            let (_, meta) = MetaBox::parse_box(&data[meta.range])?; // Safe-slice
+
+            // The item starts with the offset of the TIFF header (4 bytes),
+            // then the Exif header
+            let tiff = meta
+                .exif_data_offset()
+                .filter(|x| {
+                    data.get(x.clone())
+                        .is_some_and(|x| check_exif_header2(x).is_ok())
+                })
+                .map(|x| x.start + 4 + EXIF_IDENT.len()..x.end);
+            ranges.extend(tiff);
+        }
+        _ => (),
+    }
+    Ok(ranges)
+}
+
+/// Turns the location atoms (`©xyz` & `loci`) into `free` boxes, and removes
+/// the location metadata in `meta` boxes.
+pub(crate) fn strip_location_boxes(
+    data: &mut [u8],
+    range: Range<usize>,
+    depth: usize,
+) -> crate::Result<()> {
+    for bbox in parse_boxes(data, range)? {
+        match &bbox.box_type {
+            b"\xa9xyz" | b"loci" => bbox.free(data),
+            b"meta" => strip_location_metadata(data, &bbox)?,
+            b"moov" | b"trak" | b"udta" if depth < MAX_BOX_DEPTH => {
+                strip_location_boxes(data, bbox.body(), depth + 1)?
+            }
+            _ => (),
+        }
+    }
+    Ok(())
+}
+
+/// Removes the `com.apple.quicktime.location.*` keys & their values from a
+/// `meta` box, then pads the `meta` box with a `free` box, so that its size
+/// doesn't change. For iTunes-style metadata (without `keys`), the `©xyz`
+/// items are turned into `free` boxes.
+fn strip_location_metadata(data: &mut [u8], meta: &RawBox) -> crate::Result<()> {
+    let range = meta.meta_children(data)?;
+    let start = range.start;
+    let children = parse_boxes(data, range)?;
+    let find = |box_type: &[u8; 4]| children.iter().find(|x| &x.box_type == box_type);
+    let Some(ilst) = find(b"ilst") else {
+        return Ok(());
+    };
+    let Some(keys) = find(b"keys") else {
+        for item in parse_boxes(data, ilst.body())? {
+            if &item.box_type == b"\xa9xyz" {
+                item.free(data);
+            }
+        }
+        return Ok(());
+    };
+
+    // Full box, then the entry count
+    let keys_body = keys.body();
+    let mut entries = Vec::new();
+    let mut pos = keys_body.start + 8;
+    while pos + 8 <= keys_body.end {
+        let size = u32::from_be_bytes(data[pos..pos + 4].try_into().unwrap()) as usize; // Safe-slice
+        if size < 8 || pos + size > keys_body.end {
+            return Err("invalid keys box".into());
+        }
+        entries.push(pos..pos + size);
+        pos += size;
+    }
+    // 1-based indices of the location keys
+    let removed = entries
+        .iter()
+        .enumerate()
+        .filter(|(_, x)| data[x.start + 8..x.end].starts_with(b"com.apple.quicktime.location"))
+        .map(|(i, _)| i as u32 + 1)
+        .collect::<Vec<_>>();
+    if removed.is_empty() {
+        return Ok(());
+    }
+
+    let new_box = |box_type: &[u8; 4], body: Vec<u8>| {
+        let mut bytes = ((body.len() + 8) as u32).to_be_bytes().to_vec();
+        bytes.extend_from_slice(box_type);
+        bytes.extend(body);
+        bytes
+    };
+    let mut keys_body_bytes = data[keys_body.start..keys_body.start + 4].to_vec(); // Safe-slice
+    keys_body_bytes.extend(((entries.len() - removed.len()) as u32).to_be_bytes());
+    for (i, entry) in entries.iter().enumerate() {
+        if !removed.contains(&(i as u32 + 1)) {
+            keys_body_bytes.extend_from_slice(&data[entry.clone()]); // Safe-slice
+        }
+    }
+    let new_keys = new_box(b"keys", keys_body_bytes);
+
+    // Items of `ilst` are identified by the indices of their keys
+    let mut ilst_body_bytes = Vec::new();
+    for item in parse_boxes(data, ilst.body())? {
+        let index = u32::from_be_bytes(item.box_type);
+        if removed.contains(&index) {
+            continue;
+        }
+        let shift = removed.iter().filter(|x| **x < index).count() as u32;
+        let mut bytes = data[item.range].to_vec(); // Safe-slice
+        bytes[4..8].copy_from_slice(&(index - shift).to_be_bytes()); // Safe-slice
+        ilst_body_bytes.extend(bytes);
+    }
+    let new_ilst = new_box(b"ilst", ilst_body_bytes);
+
+    let mut bytes = Vec::new();
+    for child in children.iter() {
+        match &child.box_type {
+            b"keys" => bytes.extend_from_slice(&new_keys),
+            b"ilst" => bytes.extend_from_slice(&new_ilst),
+            _ => bytes.extend_from_slice(&data[child.range.clone()]), // Safe-slice
+        }
+    }
+    let end = children.last().map_or(start, |x| x.range.end);
+    // At least a key entry (>= 8 bytes) has been removed
+    let padding = end - start - bytes.len();
+    bytes.extend(new_box(b"free", vec![0; padding - 8]));
+    data[start..end].copy_from_slice(&bytes); // Safe-slice
+    Ok(())
+}
+
+/// An ISOBMFF box in the data being edited in place.
+pub(crate) struct RawBox {
+    pub box_type: [u8; 4],
+    pub header_size: usize,
+    // Including the header
+    pub range: Range<usize>,
+}
+
+impl RawBox {
+    pub fn body(&self) -> Range<usize> {
+        self.range.start + self.header_size..self.range.end
+    }
+
+    /// Returns the range of the children of a `meta` box. QuickTime `meta` is
+    /// not a full box, while ISO `meta` is.
+    pub fn meta_children(&self, data: &[u8]) -> crate::Result<Range<usize>> {
+        let body = self.body();
+        let start = match data.get(body.start + 4..body.start + 8) {
+            Some(b"hdlr") => body.start,
+            _ => body.start + 4,
+        };
+        if start > body.end {
+            return Err("invalid meta box".into());
+        }
+        Ok(start..body.end)
+    }
+
+    /// Turns the box into a `free` box with its body zeroed.
+    pub fn free(&self, data: &mut [u8]) {
+        data[self.range.start + 4..self.range.start + 8].copy_from_slice(b"free"); // Safe-slice
+        data[self.body()].fill(0); // Safe-slice
+    }
+}
+
+/// Returns the boxes in `data[range]`.
+pub(crate) fn parse_boxes(data: &[u8], range: Range<usize>) -> crate::Result<Vec<RawBox>> {
+    let range = range.start as u64..range.end as u64;
+    let boxes = walk_boxes(range, |pos, len| {
+        let pos = pos as usize;
+        data.get(pos..pos + len)
+            .ok_or_else(|| "invalid box header; largesize is truncated".into())
+    })?;
+    Ok(boxes
+        .into_iter()
+        .map(|x| RawBox {
+            box_type: x.box_type,
+            header_size: x.header_size as usize,
+            range: x.range.start as usize..x.range.end as usize,
+        })
+        .collect())
+}
+
+/// A box located by [`walk_boxes`].
+pub(crate) struct BoxHeader {
+    pub box_type: [u8; 4],
+    pub header_size: u64,
+    // Including the header
+    pub range: Range<u64>,
+    /// The box size is 0, i.e. it extends to the end of the parent.
+    pub to_end: bool,
+}
+
+/// Returns the boxes in `range`, only the box headers are read, by calling
+/// `read(pos, len)`.
+pub(crate) fn walk_boxes<B: AsRef<[u8]>>(
+    range: Range<u64>,
+    mut read: impl FnMut(u64, usize) -> crate::Result<B>,
+) -> crate::Result<Vec<BoxHeader>> {
+    let mut boxes = Vec::new();
+    let mut pos = range.start;
+    while pos + 8 <= range.end {
+        let header = read(pos, 8)?;
+        let header = header.as_ref();
+        let box_type = header[4..8].try_into().unwrap(); // Safe-slice
+        let to_end = header[..4] == [0; 4]; // Safe-slice
+        let (header_size, size) = match u32::from_be_bytes(header[..4].try_into().unwrap()) {
+            // Extends to the end
+            0 => (8, range.end - pos),
+            1 => {
+                let size = read(pos + 8, 8)?;
+                (16, u64::from_be_bytes(size.as_ref().try_into().unwrap()))
+            }
+            x => (8, x as u64),
+        };
+        if size < header_size || size > range.end - pos {
+            return Err(format!("invalid box size: {size}").into());
+        }
+        boxes.push(BoxHeader {
+            box_type,
+            header_size,
+            range: pos..pos + size,
+            to_end,
+        });
+        pos += size;
+    }
+    Ok(boxes)
+}
+
+/// Strips the metadata boxes of MP4/QuickTime files. The top level boxes are
+/// located by seeking: the metadata boxes are dropped, `moov` (and top level
+/// `udta`/`meta`) boxes are rebuilt in memory, and the other boxes (e.g.
+/// `mdat`) are copied in chunks.
+fn strip_bmff<R: Read + Seek, W: Write>(
+    mut reader: R,
+    mut writer: W,
+    policy: StripPolicy,
+) -> crate::Result<()> {
+    let mut boxes = Vec::new();
+    for bbox in top_level_boxes(&mut reader)? {
+        let body = bbox.range.start + bbox.header_size..bbox.range.end;
+        let head = if &bbox.box_type == b"uuid" {
+            let len = (body.end - body.start).min(XMP_UUID.len() as u64);
+            read_at(&mut reader, body.start, len as usize)?
+        } else {
+            Vec::new()
+        };
+        if is_metadata_box(&bbox.box_type, &head, policy) {
+            continue;
+        }
+        let data = match &bbox.box_type {
+            b"moov" | b"udta" | b"meta" => Some(rebuild_top_level_box(&mut reader, &bbox, policy)?),
+            _ => None,
+        };
+        boxes.push((bbox, data));
+    }
+
+    // New offsets of the kept boxes, for adjusting the chunk offsets
+    let mut layout = Vec::with_capacity(boxes.len());
+    let mut pos = 0;
+    for (bbox, data) in boxes.iter() {
+        layout.push((bbox.range.clone(), pos));
+        pos += data
+            .as_ref()
+            .map_or(bbox.range.end - bbox.range.start, |x| x.len() as u64);
+    }
+    let map = |offset: u64| {
+        layout
+            .iter()
+            .find(|(range, _)| range.contains(&offset))
+            .map_or(offset, |(range, pos)| pos + (offset - range.start))
+    };
+
+    for (bbox, data) in boxes.iter_mut() {
+        match data {
+            Some(data) => {
+                if &bbox.box_type == b"moov" {
+                    let range = 0..data.len();
+                    map_chunk_offsets(data, range, &map, 0)?;
+                }
+                writer.write_all(data)?;
+            }
+            None => {
+                let len = bbox.range.end - bbox.range.start;
+                reader.seek(SeekFrom::Start(bbox.range.start))?;
+                if io::copy(&mut reader.by_ref().take(len), &mut writer)? < len {
+                    return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+                }
+            }
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Returns true if the box is to be removed according to `policy`, `body` is
+/// (the start of) the box body.
+fn is_metadata_box(box_type: &[u8; 4], body: &[u8], policy: StripPolicy) -> bool {
+    match box_type {
+        b"udta" | b"meta" => policy.contains(StripPolicy::EXIF),
+        b"XMP_" => policy.contains(StripPolicy::XMP),
+        b"uuid" => policy.contains(StripPolicy::XMP) && body.starts_with(XMP_UUID),
+        _ => false,
+    }
+}
+
+/// Reads a top level box, and returns it with the metadata selected by
+/// `policy` removed.
+fn rebuild_top_level_box<R: Read + Seek>(
+    reader: &mut R,
+    bbox: &BoxHeader,
+    policy: StripPolicy,
+) -> crate::Result<Vec<u8>> {
+    let len = bbox.range.end - bbox.range.start;
+    let mut data = read_at(reader, bbox.range.start, len as usize)?;
+    if bbox.to_end {
+        // Sized explicitly, since the boxes after it may be changed
+        let size = u32::try_from(len).map_err(|_| "the box is too big")?;
+        data[..4].copy_from_slice(&size.to_be_bytes()); // Safe-slice
+    }
+    let range = 0..data.len();
+    if policy.contains(StripPolicy::GPS) {
+        strip_location_boxes(&mut data, range.clone(), 0)?;
+    }
+    // ISO `meta` boxes are full boxes, their children are kept
+    if &bbox.box_type == b"meta" {
+        return Ok(data);
+    }
+    let raw = RawBox {
+        box_type: bbox.box_type,
+        header_size: bbox.header_size as usize,
+        range,
+    };
+    rebuild_box(&data, &raw, policy, 0)
+}
+
+/// Rebuilds `bbox` (a `moov`, `trak` or `udta` box) of `data` with the
+/// metadata boxes dropped, the box size is updated accordingly.
+fn rebuild_box(
+    data: &[u8],
+    bbox: &RawBox,
+    policy: StripPolicy,
+    depth: usize,
+) -> crate::Result<Vec<u8>> {
+    let body = bbox.body();
+    let children = parse_boxes(data, body.clone())?;
+    let mut bytes = Vec::with_capacity(bbox.range.len());
+    for child in children.iter() {
+        let child_body = &data[child.body()]; // Safe-slice
+        if is_metadata_box(&child.box_type, child_body, policy) {
+            continue;
+        }
+        if matches!(&child.box_type, b"trak" | b"udta") && depth < MAX_BOX_DEPTH {
+            bytes.extend(rebuild_box(data, child, policy, depth + 1)?);
+        } else {
+            bytes.extend_from_slice(&data[child.range.clone()]); // Safe-slice
+        }
+    }
+    // Trailing bytes which are too short for a box, e.g. the terminator of
+    // QuickTime `udta` boxes
+    let end = children.last().map_or(body.start, |x| x.range.end);
+    bytes.extend_from_slice(&data[end..body.end]); // Safe-slice
+
+    let size = (bytes.len() + bbox.header_size) as u64;
+    let mut header = if bbox.header_size == 16 {
+        let mut header = 1_u32.to_be_bytes().to_vec();
+        header.extend_from_slice(&bbox.box_type);
+        header.extend_from_slice(&size.to_be_bytes());
+        header
+    } else {
+        let size = u32::try_from(size).map_err(|_| "the box is too big")?;
+        let mut header = size.to_be_bytes().to_vec();
+        header.extend_from_slice(&bbox.box_type);
+        header
+    };
+    header.extend(bytes);
+    Ok(header)
+}
+
+/// Applies `map` to the chunk offsets (`stco` & `co64` boxes) of the tracks
+/// in `data[range]`.
+fn map_chunk_offsets(
+    data: &mut [u8],
+    range: Range<usize>,
+    map: &impl Fn(u64) -> u64,
+    depth: usize,
+) -> crate::Result<()> {
+    for bbox in parse_boxes(data, range)? {
+        let size = match &bbox.box_type {
+            b"moov" | b"trak" | b"mdia" | b"minf" | b"stbl" if depth < MAX_BOX_DEPTH => {
+                map_chunk_offsets(data, bbox.body(), map, depth + 1)?;
+                continue;
+            }
+            b"stco" => 4,
+            b"co64" => 8,
+            _ => continue,
+        };
+        // Full box, then the entry count
+        let body = bbox.body();
+        let count = data
+            .get(body.start + 4..body.start + 8)
+            .map(|x| u32::from_be_bytes(x.try_into().unwrap()) as usize)
+            .ok_or("invalid chunk offset box")?;
+        let start = body.start + 8;
+        if count.saturating_mul(size) > body.end.saturating_sub(start) {
+            return Err("invalid chunk offset box".into());
+        }
+        for pos in (start..start + count * size).step_by(size) {
+            let entry = &mut data[pos..pos + size]; // Safe-slice
+            if size == 4 {
+                let offset = map(u32::from_be_bytes(entry.try_into().unwrap()) as u64);
+                let offset = u32::try_from(offset).map_err(|_| "chunk offset out of range")?;
+                entry.copy_from_slice(&offset.to_be_bytes());
+            } else {
+                let offset = map(u64::from_be_bytes(entry.try_into().unwrap()));
+                entry.copy_from_slice(&offset.to_be_bytes());
+            }
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn find_box(
+    data: &[u8],
+    range: Range<usize>,
+    box_type: &[u8; 4],
+) -> crate::Result<Option<RawBox>> {
+    Ok(parse_boxes(data, range)?
+        .into_iter()
+        .find(|x| &x.box_type == box_type))
+}
+
+/// Strips the Exif & XMP items and the ICC profiles of HEIF files in place:
+///
+/// - The item info entries are moved to the end of `iinf` as `free` boxes,
+///   and the item data is zeroed.
+/// - `colr` properties are turned into `free` boxes, the indices of the other
+///   properties are kept.
+fn strip_heif(data: &mut [u8], policy: StripPolicy) -> crate::Result<()> {
+    let meta = find_box(data, 0..data.len(), b"meta")?.ok_or("meta box not found")?;
+    let (_, meta_box) = MetaBox::parse_box(&data[meta.range.clone()])?; // Safe-slice
+                                                                        // Full box, skip version & flags
+    let meta_body = meta.body().start + 4..meta.range.end;
+
+    if policy.contains(StripPolicy::EXIF) || policy.contains(StripPolicy::XMP) {
+        if let Some(iinf) = find_box(data, meta_body.clone(), b"iinf")? {
+            let removed = strip_iinf(data, &iinf, |infe| match infe.item_type.as_deref() {
+                Some("Exif") => policy.contains(StripPolicy::EXIF),
+                Some("mime") => {
+                    policy.contains(StripPolicy::XMP)
+                        && infe.content_type.as_deref() == Some("application/rdf+xml")
+                }
+                _ => false,
+            })?;
+            for id in removed {
+                if let Some(range) = meta_box.item_data_offset(id) {
+                    let end = range.end.min(data.len());
+                    if let Some(x) = data.get_mut(range.start..end) {
+                        x.fill(0);
+                    }
+                }
+            }
+        }
+    }
+
+    if policy.contains(StripPolicy::ICC) {
+        let ipco = match find_box(data, meta_body, b"iprp")? {
+            Some(iprp) => find_box(data, iprp.body(), b"ipco")?,
+            None => None,
+        };
+        if let Some(ipco) = ipco {
+            for prop in parse_boxes(data, ipco.body())? {
+                let body = &data[prop.body()]; // Safe-slice
+                if &prop.box_type == b"colr"
+                    && (body.starts_with(b"prof") || body.starts_with(b"rICC"))
+                {
+                    prop.free(data);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Removes the item info entries matched by `strip` from `iinf`, returns the
+/// ids of the removed items.
+fn strip_iinf(
+    data: &mut [u8],
+    iinf: &RawBox,
+    strip: impl Fn(&InfeBox) -> bool,
+) -> crate::Result<Vec<u32>> {
+    let body = iinf.body();
+    let version = *data.get(body.start).ok_or("invalid iinf box")?;
+    let count_size = if version > 0 { 4 } else { 2 };
+    let entries = body.start + 4 + count_size..body.end;
+    if entries.start > entries.end {
+        return Err("invalid iinf box".into());
+    }
+
+    let mut kept = Vec::new();
+    let mut removed = Vec::new();
+    for entry in parse_boxes(data, entries.clone())? {
+        let bytes = &data[entry.range.clone()]; // Safe-slice
+        match InfeBox::parse_box(bytes) {
+            Ok((_, infe)) if strip(&infe) => removed.push((infe.id, entry)),
+            _ => kept.push(bytes.to_vec()),
+        }
+    }
+    if removed.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // Kept entries first, so that the entry count still covers them, then the
+    // removed ones as `free` boxes (headers are kept for the box sizes)
+    let count = kept.len();
+    let mut ids = Vec::with_capacity(removed.len());
+    let mut freed = Vec::with_capacity(removed.len());
+    for (id, entry) in removed {
+        let mut bytes = data[entry.range.clone()].to_vec(); // Safe-slice
+        bytes[4..8].copy_from_slice(b"free"); // Safe-slice
+        bytes[entry.header_size..].fill(0); // Safe-slice
+        freed.push(bytes);
+        ids.push(id);
+    }
+    let mut pos = entries.start;
+    for bytes in kept.into_iter().chain(freed) {
+        data[pos..pos + bytes.len()].copy_from_slice(&bytes); // Safe-slice
+        pos += bytes.len();
+    }
+
+    let count_pos = body.start + 4;
+    let count = if count_size == 4 {
+        (count as u32).to_be_bytes().to_vec()
+    } else {
+        (count as u16).to_be_bytes().to_vec()
+    };
+    data[count_pos..count_pos + count_size].copy_from_slice(&count); // Safe-slice
+    Ok(ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testkit::read_sample, Exif, ExifIter, ExifTag, MediaParser, MediaSource};
+    use std::io::Cursor;
+    use test_case::test_case;
+
+    fn strip(path: &str, policy: StripPolicy) -> (Vec<u8>, Vec<u8>) {
+        let input = read_sample(path).unwrap();
+        let mut output = Vec::new();
+        strip_metadata(Cursor::new(&input), &mut output, policy).unwrap();
+        (input, output)
+    }
+
+    fn parse_exif(data: Vec<u8>) -> crate::Result<Exif> {
+        let ms = MediaSource::seekable(Cursor::new(data))?;
+        let iter: ExifIter = MediaParser::new().parse(ms)?;
+        Ok(iter.into())
+    }
+
+    #[test_case(StripPolicy::ALL, false)]
+    #[test_case(StripPolicy::EXIF, false)]
+    #[test_case(StripPolicy::XMP | StripPolicy::ICC, true)]
+    fn strip_jpeg(policy: StripPolicy, has_exif: bool) {
+        let (input, output) = strip("exif.jpg", policy);
+        assert_eq!(parse_exif(output.clone()).is_ok(), has_exif);
+        assert!(output.len() < input.len());
+    }
+
+    #[test]
+    fn strip_heif() {
+        let (input, output) = strip("exif.heic", StripPolicy::EXIF | StripPolicy::ICC);
+        assert_eq!(output.len(), input.len());
+        assert!(parse_exif(output.clone()).is_err());
+
+        let ms = MediaSource::seekable(Cursor::new(output)).unwrap();
+        let icc: crate::Result<crate::IccProfile> = MediaParser::new().parse(ms);
+        assert!(icc.is_err());
+
+        let (_, output) = strip("exif.heic", StripPolicy::XMP);
+        let exif = parse_exif(output).unwrap();
+        assert_eq!(exif.get(ExifTag::Make), Some(&"Apple".into()));
+    }
+
+    #[test]
+    fn strip_tiff() {
+        let (input, output) = strip("tif.tif", StripPolicy::EXIF);
+        assert_eq!(output.len(), input.len());
+        let before = parse_exif(input).unwrap();
+        let after = parse_exif(output).unwrap();
+        assert!(before.get(ExifTag::ImageDescription).is_some());
+        assert_eq!(after.get(ExifTag::ImageDescription), None);
+        for tag in [ExifTag::ImageWidth, ExifTag::Orientation] {
+            assert!(after.get(tag).is_some());
+            assert_eq!(after.get(tag), before.get(tag));
+        }
+    }
+
+    #[test]
+    fn strip_mov() {
+        let (input, output) = strip("meta.mov", StripPolicy::ALL);
+        // The `meta` box is dropped, the media data is kept
+        assert_eq!(output.len(), input.len() - 441);
+        assert_eq!(output[..765_703], input[..765_703]);
+
+        let ms = MediaSource::seekable(Cursor::new(output)).unwrap();
+        let info: crate::TrackInfo = MediaParser::new().parse(ms).unwrap();
+        assert_eq!(info.get(crate::TrackInfoTag::Make), None);
+        assert_eq!(info.get(crate::TrackInfoTag::GpsIso6709), None);
+        assert!(info.get(crate::TrackInfoTag::DurationMs).is_some());
+    }
+
+    #[test_case("exif.jpg")]
+    #[test_case("exif.heic")]
+    fn strip_exif_gps(path: &str) {
+        let (input, output) = strip(path, StripPolicy::GPS);
+        assert_eq!(output.len(), input.len());
+        let before = parse_exif(input).unwrap();
+        let after = parse_exif(output).unwrap();
+        assert!(before.get_gps_info().unwrap().is_some());
+        assert_eq!(after.get_gps_info().unwrap(), None);
+        assert_eq!(after.get(ExifTag::GPSLatitude), None);
+        for tag in [ExifTag::Make, ExifTag::Model, ExifTag::ExposureTime] {
+            assert!(after.get(tag).is_some());
+            assert_eq!(after.get(tag), before.get(tag));
+        }
+    }
+
+    #[test_case("meta.mov")]
+    #[test_case("meta.mp4")]
+    fn strip_track_gps(path: &str) {
+        use crate::{TrackInfo, TrackInfoTag};
+
+        let (input, output) = strip(path, StripPolicy::GPS);
+        assert_eq!(output.len(), input.len());
+        let parse = |data| -> TrackInfo {
+            let ms = MediaSource::seekable(Cursor::new(data)).unwrap();
+            MediaParser::new().parse(ms).unwrap()
+        };
+        let before = parse(input);
+        let after = parse(output);
+        assert!(before.get(TrackInfoTag::GpsIso6709).is_some());
+        assert_eq!(after.get(TrackInfoTag::GpsIso6709), None);
+        assert_eq!(after.get_gps_info(), None);
+        for tag in [
+            TrackInfoTag::Make,
+            TrackInfoTag::Model,
+            TrackInfoTag::CreateDate,
+        ] {
+            assert_eq!(after.get(tag), before.get(tag));
+        }
+    }
+
+    /// Counts the bytes written, and keeps the first few of them.
+    #[derive(Default)]
+    struct Sink {
+        len: u64,
+        max_write: usize,
+        head: Vec<u8>,
+    }
+
+    impl Write for Sink {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let n = buf.len().min(4096usize.saturating_sub(self.head.len()));
+            self.head.extend_from_slice(&buf[..n]);
+            self.len += buf.len() as u64;
+            self.max_write = self.max_write.max(buf.len());
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// A fast start file (`moov` before `mdat`) with a huge `mdat` box, the
+    /// media data is streamed, and the chunk offsets are moved along with it.
+    #[test_case(1 << 20)]
+    #[test_case(5 << 30; "over 4 GB")]
+    fn strip_large_mov(mdat_size: u64) {
+        use crate::testkit::{bbox, full_box, table, SparseFile};
+
+        let ftyp = bbox(b"ftyp", b"qt  \0\0\0\0qt  ");
+        let udta = bbox(
+            b"udta",
+            &bbox(b"\xa9xyz", b"\0\x12\x15\xc7+22.5000+114.0000/"),
+        );
+        let stbl = |offset: u32| {
+            let stco = full_box(b"stco", &table(&[&[offset]]));
+            bbox(b"mdia", &bbox(b"minf", &bbox(b"stbl", &stco)))
+        };
+        let moov_size = (8 + udta.len() + 8 + stbl(0).len()) as u32;
+        let mdat_start = ftyp.len() as u32 + moov_size;
+        let chunk = mdat_start + 16;
+        let moov = bbox(
+            b"moov",
+            &[udta.clone(), bbox(b"trak", &stbl(chunk))].concat(),
+        );
+        let mut mdat = 1_u32.to_be_bytes().to_vec();
+        mdat.extend_from_slice(b"mdat");
+        mdat.extend_from_slice(&(mdat_size + 16).to_be_bytes());
+
+        let mut file = SparseFile::new(0);
+        file.put(0, [ftyp.clone(), moov, mdat].concat());
+        file.put(chunk as u64, b"sample".to_vec());
+        let len = mdat_start as u64 + mdat_size + 16;
+        file.put(len - 3, b"end".to_vec());
+
+        let mut sink = Sink::default();
+        strip_metadata(&mut file, &mut sink, StripPolicy::EXIF).unwrap();
+        assert_eq!(sink.len, len - udta.len() as u64);
+        // Written in chunks, regardless of the file size
+        assert!(
+            sink.max_write <= 64 * 1024,
+            "wrote {} bytes",
+            sink.max_write
+        );
+        // Each byte is read once, except for the headers
+        assert!(file.read < len + 1024, "read {} bytes", file.read);
+
+        let output = &sink.head;
+        assert_eq!(output[..ftyp.len()], ftyp);
+        let moov_end = mdat_start as usize - udta.len();
+        let moov = find_box(output, 0..moov_end, b"moov").unwrap().unwrap();
+        assert!(find_box(output, moov.body(), b"udta").unwrap().is_none());
+        let new_chunk = chunk as usize - udta.len();
+        assert_eq!(
+            output[moov.range.end - 4..moov.range.end],
+            (new_chunk as u32).to_be_bytes()
+        );
+        assert_eq!(&output[new_chunk..new_chunk + 6], b"sample");
+    }
+
+    /// Only the segments before SOS are loaded, the image data is streamed.
+    #[test]
+    fn strip_large_jpeg() {
+        use crate::testkit::SparseFile;
+
+        let (input, output) = strip("exif.jpg", StripPolicy::ALL);
+        let removed = (input.len() - output.len()) as u64;
+        let len = input.len() as u64 + (256 << 20);
+        let mut file = SparseFile::new(len);
+        file.put(0, input);
+        file.put(len - 3, b"end".to_vec());
+
+        let mut sink = Sink::default();
+        strip_metadata(&mut file, &mut sink, StripPolicy::ALL).unwrap();
+        assert_eq!(sink.len, len - removed);
+        // The kept segments are written at once, the image data in chunks
+        assert!(
+            sink.max_write <= 128 * 1024,
+            "wrote {} bytes",
+            sink.max_write
+        );
+        assert!(file.read < len + 1024, "read {} bytes", file.read);
+        assert_eq!(sink.head, output[..4096]);
+    }
+
+    #[test]
+    fn unsupported() {
+        let res = strip_metadata(Cursor::new(b"GIF89a\0\0\0\0"), Vec::new(), StripPolicy::ALL);
+        assert!(res.is_err());
+    }
+}
@@ -0,0 +1,565 @@
+use std::io::{self, Read, Seek, SeekFrom};
+
+#[cfg(feature = "async")]
+use tokio::io::{AsyncRead, AsyncSeek};
+
+use crate::{
+    bbox::{BoxHolder, TrackSamples},
+    error::ParsingError,
+    mov::extract_moov_body_from_buf,
+};
+
+mod camm;
+mod dji;
+mod gpmf;
+
+/// Samples larger than this are ignored, since telemetry samples are usually
+/// quite small.
+const MAX_SAMPLE_SIZE: u32 = 4 * 1024 * 1024;
+
+/// Time-synced telemetry of a video, i.e. GPS, gyroscope & accelerometer
+/// samples extracted from its timed metadata tracks.
+///
+/// Currently supported sources are:
+///
+/// - GoPro GPMF tracks (`gpmd`): `GPS5`, `GYRO` & `ACCL` streams
+/// - CAMM tracks (`camm`), e.g. Insta360 & Google Street View videos
+/// - DJI subtitle tracks, e.g. `[latitude: 22.5] [longitude: 113.9]` or
+///   `GPS(113.9,22.5,18)`
+///
+/// All samples are sorted by time, which is the offset in seconds from the
+/// start of the video timeline (edit lists are not applied). Sensor axes are
+/// reported as-is, in the device's own coordinate system.
+///
+/// Only ISOBMFF files (*.mp4, *.mov, etc.) are supported, and the
+/// [`MediaSource`](crate::MediaSource) must be seekable, since telemetry
+/// samples are spread over the whole file.
+///
+/// ## Example
+///
+/// ```rust
+/// use nom_exif::*;
+///
+/// fn main() -> Result<()> {
+///     let mut parser = MediaParser::new();
+///
+///     let ms = MediaSource::file_path("./testdata/meta.mp4")?;
+///     let telemetry: Telemetry = parser.parse(ms)?;
+///
+///     for frame in [0.0, 0.5, 1.0] {
+///         if let Some(gps) = telemetry.gps_at(frame) {
+///             println!("{frame}: {}, {}", gps.latitude, gps.longitude);
+///         }
+///     }
+///     assert!(telemetry.is_empty());
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Telemetry {
+    gps: Vec<GpsSample>,
+    gyro: Vec<ImuSample>,
+    accel: Vec<ImuSample>,
+}
+
+/// A GPS sample of [`Telemetry`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GpsSample {
+    /// Offset in seconds from the start of the video.
+    pub time: f64,
+
+    /// Latitude in degrees, positive for north.
+    pub latitude: f64,
+
+    /// Longitude in degrees, positive for east.
+    pub longitude: f64,
+
+    /// Altitude in meters, if available.
+    pub altitude: Option<f64>,
+}
+
+/// A gyroscope (rad/s) or accelerometer (m/s²) sample of [`Telemetry`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImuSample {
+    /// Offset in seconds from the start of the video.
+    pub time: f64,
+
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Telemetry {
+    /// GPS samples, sorted by time.
+    pub fn gps(&self) -> &[GpsSample] {
+        &self.gps
+    }
+
+    /// Gyroscope samples in rad/s, sorted by time.
+    pub fn gyro(&self) -> &[ImuSample] {
+        &self.gyro
+    }
+
+    /// Accelerometer samples in m/s², sorted by time.
+    pub fn accel(&self) -> &[ImuSample] {
+        &self.accel
+    }
+
+    /// Returns true if no samples have been found.
+    pub fn is_empty(&self) -> bool {
+        self.gps.is_empty() && self.gyro.is_empty() && self.accel.is_empty()
+    }
+
+    /// Returns the GPS position at `time` (in seconds), linearly interpolated
+    /// between the two nearest samples.
+    ///
+    /// Returns `None` if `time` is out of the range of the samples.
+    pub fn gps_at(&self, time: f64) -> Option<GpsSample> {
+        sample_at(&self.gps, time)
+    }
+
+    /// Returns the gyroscope value at `time` (in seconds), see
+    /// [`Self::gps_at`].
+    pub fn gyro_at(&self, time: f64) -> Option<ImuSample> {
+        sample_at(&self.gyro, time)
+    }
+
+    /// Returns the accelerometer value at `time` (in seconds), see
+    /// [`Self::gps_at`].
+    pub fn accel_at(&self, time: f64) -> Option<ImuSample> {
+        sample_at(&self.accel, time)
+    }
+
+    fn sort(&mut self) {
+        self.gps.sort_by(|a, b| a.time.total_cmp(&b.time));
+        self.gyro.sort_by(|a, b| a.time.total_cmp(&b.time));
+        self.accel.sort_by(|a, b| a.time.total_cmp(&b.time));
+    }
+}
+
+trait Interpolate: Copy {
+    fn time(&self) -> f64;
+
+    /// `ratio` is in the range of [0, 1).
+    fn interpolate(&self, next: &Self, ratio: f64) -> Self;
+}
+
+fn lerp(a: f64, b: f64, ratio: f64) -> f64 {
+    a + (b - a) * ratio
+}
+
+impl Interpolate for GpsSample {
+    fn time(&self) -> f64 {
+        self.time
+    }
+
+    fn interpolate(&self, next: &Self, ratio: f64) -> Self {
+        // Take the short way when crossing the antimeridian
+        let mut next_lon = next.longitude;
+        if next_lon - self.longitude > 180.0 {
+            next_lon -= 360.0;
+        } else if self.longitude - next_lon > 180.0 {
+            next_lon += 360.0;
+        }
+        let mut longitude = lerp(self.longitude, next_lon, ratio);
+        if longitude > 180.0 {
+            longitude -= 360.0;
+        } else if longitude < -180.0 {
+            longitude += 360.0;
+        }
+
+        GpsSample {
+            time: lerp(self.time, next.time, ratio),
+            latitude: lerp(self.latitude, next.latitude, ratio),
+            longitude,
+            altitude: self
+                .altitude
+                .zip(next.altitude)
+                .map(|(a, b)| lerp(a, b, ratio)),
+        }
+    }
+}
+
+impl Interpolate for ImuSample {
+    fn time(&self) -> f64 {
+        self.time
+    }
+
+    fn interpolate(&self, next: &Self, ratio: f64) -> Self {
+        ImuSample {
+            time: lerp(self.time, next.time, ratio),
+            x: lerp(self.x, next.x, ratio),
+            y: lerp(self.y, next.y, ratio),
+            z: lerp(self.z, next.z, ratio),
+        }
+    }
+}
+
+fn sample_at<T: Interpolate>(samples: &[T], time: f64) -> Option<T> {
+    // number of samples whose time <= `time`
+    let idx = samples.partition_point(|x| x.time() <= time);
+    let prev = samples.get(idx.checked_sub(1)?)?;
+    if prev.time() == time {
+        return Some(*prev);
+    }
+
+    let next = samples.get(idx)?;
+    let ratio = (time - prev.time()) / (next.time() - prev.time());
+    Some(prev.interpolate(next, ratio))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TrackKind {
+    Gpmf,
+    Camm,
+    DjiText,
+}
+
+/// A timed metadata track which may contain telemetry samples.
+#[derive(Debug)]
+pub(crate) struct TelemetryTrack {
+    kind: TrackKind,
+    samples: TrackSamples,
+}
+
+/// Finds all telemetry tracks in moov, `input` should start with the first
+/// box of the file.
+#[tracing::instrument(skip_all)]
+pub(crate) fn find_telemetry_tracks(input: &[u8]) -> Result<Vec<TelemetryTrack>, ParsingError> {
+    let range = extract_moov_body_from_buf(input)?;
+
+    let mut tracks = Vec::new();
+    let mut remain = &input[range];
+    while !remain.is_empty() {
+        let Ok((rem, bbox)) = BoxHolder::parse(remain) else {
+            tracing::warn!("invalid box in moov, stop searching tracks");
+            break;
+        };
+        remain = rem;
+        if bbox.box_type() != "trak" {
+            continue;
+        }
+
+        let samples = match TrackSamples::parse(bbox.body_data()) {
+            Ok(samples) => samples,
+            Err(e) => {
+                tracing::warn!(?e, "Ignored: parse track samples failed");
+                continue;
+            }
+        };
+        let kind = match samples.format.as_str() {
+            "gpmd" => TrackKind::Gpmf,
+            "camm" => TrackKind::Camm,
+            "tx3g" | "text" => TrackKind::DjiText,
+            _ => continue,
+        };
+        tracing::debug!(?kind, samples = samples.samples.len(), "telemetry track");
+        tracks.push(TelemetryTrack { kind, samples });
+    }
+
+    Ok(tracks)
+}
+
+impl TelemetryTrack {
+    /// Returns `(offset, size, time, duration)` of the samples to be read,
+    /// time & duration are in seconds.
+    fn samples(&self) -> impl Iterator<Item = (u64, usize, f64, f64)> + '_ {
+        let time_scale = self.samples.time_scale;
+        if time_scale == 0 {
+            tracing::warn!(?self.kind, "Ignored: invalid time scale");
+        }
+
+        self.samples
+            .samples
+            .iter()
+            .take_while(move |_| time_scale != 0)
+            .filter(|x| {
+                if x.size > MAX_SAMPLE_SIZE {
+                    tracing::warn!(size = x.size, "Ignored: sample is too big");
+                    return false;
+                }
+                true
+            })
+            .map(move |x| {
+                let scale = time_scale as f64;
+                (
+                    x.offset,
+                    x.size as usize,
+                    x.time as f64 / scale,
+                    x.duration as f64 / scale,
+                )
+            })
+    }
+}
+
+/// Decodes telemetry samples, the sample data is read by the caller, see
+/// [`read_telemetry`].
+struct TelemetryDecoder {
+    telemetry: Telemetry,
+    dji: dji::TextDecoder,
+}
+
+impl TelemetryDecoder {
+    fn new() -> Self {
+        Self {
+            telemetry: Telemetry::default(),
+            dji: dji::TextDecoder::new(),
+        }
+    }
+
+    fn decode(&mut self, kind: TrackKind, data: &[u8], time: f64, duration: f64) {
+        let out = &mut self.telemetry;
+        match kind {
+            TrackKind::Gpmf => gpmf::decode(data, time, duration, out),
+            TrackKind::Camm => camm::decode(data, time, out),
+            TrackKind::DjiText => self.dji.decode(data, time, out),
+        }
+    }
+
+    fn finish(mut self) -> Telemetry {
+        self.telemetry.sort();
+        self.telemetry
+    }
+}
+
+/// Reads & decodes samples of `tracks` from `reader`.
+#[tracing::instrument(skip_all)]
+pub(crate) fn read_telemetry<R: Read + Seek>(
+    reader: &mut R,
+    tracks: Vec<TelemetryTrack>,
+) -> crate::Result<Telemetry> {
+    let mut decoder = TelemetryDecoder::new();
+    let mut buf = Vec::new();
+
+    for track in tracks.iter() {
+        for (offset, size, time, duration) in track.samples() {
+            buf.resize(size, 0);
+            reader.seek(SeekFrom::Start(offset))?;
+            match reader.read_exact(&mut buf) {
+                Ok(()) => (),
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                    tracing::warn!(?track.kind, "sample data is truncated");
+                    break;
+                }
+                Err(e) => return Err(e.into()),
+            }
+            decoder.decode(track.kind, &buf, time, duration);
+        }
+    }
+
+    Ok(decoder.finish())
+}
+
+/// `async` version of [`read_telemetry`].
+#[cfg(feature = "async")]
+#[tracing::instrument(skip_all)]
+pub(crate) async fn read_telemetry_async<R: AsyncRead + AsyncSeek + Unpin>(
+    reader: &mut R,
+    tracks: Vec<TelemetryTrack>,
+) -> crate::Result<Telemetry> {
+    use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+    let mut decoder = TelemetryDecoder::new();
+    let mut buf = Vec::new();
+
+    for track in tracks.iter() {
+        for (offset, size, time, duration) in track.samples() {
+            buf.resize(size, 0);
+            reader.seek(SeekFrom::Start(offset)).await?;
+            match reader.read_exact(&mut buf).await {
+                Ok(_) => (),
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                    tracing::warn!(?track.kind, "sample data is truncated");
+                    break;
+                }
+                Err(e) => return Err(e.into()),
+            }
+            decoder.decode(track.kind, &buf, time, duration);
+        }
+    }
+
+    Ok(decoder.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        testkit::{bbox, full_box, table},
+        MediaParser, MediaSource,
+    };
+    use std::io::Cursor;
+    use test_case::test_case;
+
+    /// Makes an mp4 file with a single track, each sample is put into its own
+    /// chunk.
+    pub(crate) fn make_mp4(format: &[u8], samples: &[Vec<u8>], time_scale: u32) -> Vec<u8> {
+        let ftyp = bbox(b"ftyp", b"isom\0\0\0\0isom");
+        let mdat = bbox(b"mdat", &samples.concat());
+
+        let mut offset = (ftyp.len() + 8) as u32;
+        let mut offsets = Vec::new();
+        for x in samples {
+            offsets.push([offset]);
+            offset += x.len() as u32;
+        }
+        let offsets = offsets.iter().map(|x| &x[..]).collect::<Vec<_>>();
+        let sizes = samples.iter().map(|x| [x.len() as u32]).collect::<Vec<_>>();
+        let sizes = sizes.iter().map(|x| &x[..]).collect::<Vec<_>>();
+
+        let stbl = [
+            full_box(
+                b"stsd",
+                &[&1_u32.to_be_bytes()[..], &bbox(format, &[0; 8])].concat(),
+            ),
+            full_box(b"stts", &table(&[&[samples.len() as u32, time_scale]])),
+            full_box(b"stsc", &table(&[&[1, 1, 1]])),
+            full_box(
+                b"stsz",
+                &[&0_u32.to_be_bytes()[..], &table(&sizes)].concat(),
+            ),
+            full_box(b"stco", &table(&offsets)),
+        ]
+        .concat();
+        let mdhd = full_box(
+            b"mdhd",
+            &[0, 0, time_scale, samples.len() as u32 * time_scale, 0]
+                .iter()
+                .flat_map(|x: &u32| x.to_be_bytes())
+                .collect::<Vec<_>>(),
+        );
+        let mdia = bbox(
+            b"mdia",
+            &[mdhd, bbox(b"minf", &bbox(b"stbl", &stbl))].concat(),
+        );
+        let moov = bbox(b"moov", &bbox(b"trak", &mdia));
+
+        [ftyp, mdat, moov].concat()
+    }
+
+    fn parse(data: Vec<u8>) -> Telemetry {
+        let mut parser = MediaParser::new();
+        let ms = MediaSource::seekable(Cursor::new(data)).unwrap();
+        parser.parse(ms).unwrap()
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn async_telemetry() {
+        use crate::{AsyncMediaParser, AsyncMediaSource};
+
+        let data = make_mp4(b"gpmd", &[gpmf::tests::payload()], 1000);
+        let mut parser = AsyncMediaParser::new();
+        let ms = AsyncMediaSource::seekable(Cursor::new(data.clone()))
+            .await
+            .unwrap();
+        let telemetry: Telemetry = parser.parse(ms).await.unwrap();
+        assert!(!telemetry.is_empty());
+        assert_eq!(telemetry, parse(data));
+    }
+
+    #[test]
+    fn camm_track() {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let samples = vec![
+            camm::tests::gps_packet(22.5, 113.9, 10.0),
+            camm::tests::imu_packet(2, [0.1, 0.2, 0.3]),
+            camm::tests::imu_packet(3, [0.0, 9.8, 0.0]),
+            camm::tests::gps_packet(22.6, 114.1, 20.0),
+        ];
+        let telemetry = parse(make_mp4(b"camm", &samples, 1000));
+
+        assert_eq!(telemetry.gps().len(), 2);
+        assert_eq!(telemetry.gyro().len(), 1);
+        assert_eq!(telemetry.accel().len(), 1);
+        assert_eq!(telemetry.gps()[1].time, 3.0);
+        assert_eq!(telemetry.gyro()[0].time, 1.0);
+        assert_eq!(telemetry.accel()[0].y, 9.800000190734863);
+
+        let gps = telemetry.gps_at(1.5).unwrap();
+        assert!((gps.latitude - 22.55).abs() < 1e-9);
+        assert!((gps.longitude - 114.0).abs() < 1e-9);
+        assert_eq!(gps.altitude, Some(15.0));
+        assert_eq!(telemetry.gps_at(3.5), None);
+    }
+
+    #[test]
+    fn gpmf_track() {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let samples = vec![gpmf::tests::payload(), gpmf::tests::payload()];
+        let telemetry = parse(make_mp4(b"gpmd", &samples, 1000));
+
+        // 2 GPS5 & 4 GYRO rows per sample
+        assert_eq!(telemetry.gps().len(), 4);
+        assert_eq!(telemetry.gyro().len(), 8);
+        assert_eq!(telemetry.gyro()[5].time, 1.25);
+        assert_eq!(telemetry.gps()[2].latitude, 22.5);
+    }
+
+    #[test]
+    fn dji_track() {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let samples = vec![
+            dji::tests::text_sample("[latitude: 22.5] [longitude: 113.9] [abs_alt: 50.0]"),
+            dji::tests::text_sample("GPS(114.1,22.7,18) BAROMETER:59.4"),
+        ];
+        let telemetry = parse(make_mp4(b"tx3g", &samples, 30));
+        assert_eq!(
+            telemetry.gps(),
+            &[
+                GpsSample {
+                    time: 0.0,
+                    latitude: 22.5,
+                    longitude: 113.9,
+                    altitude: Some(50.0)
+                },
+                GpsSample {
+                    time: 1.0,
+                    latitude: 22.7,
+                    longitude: 114.1,
+                    altitude: Some(18.0)
+                }
+            ]
+        );
+    }
+
+    #[test_case("meta.mp4")]
+    #[test_case("meta.mov")]
+    fn no_telemetry(path: &str) {
+        let mut parser = MediaParser::new();
+        let ms = MediaSource::file(crate::testkit::open_sample(path).unwrap()).unwrap();
+        let telemetry: Telemetry = parser.parse(ms).unwrap();
+        assert!(telemetry.is_empty());
+    }
+
+    #[test]
+    fn interpolate_across_antimeridian() {
+        let telemetry = Telemetry {
+            gps: vec![
+                GpsSample {
+                    time: 0.0,
+                    latitude: 0.0,
+                    longitude: 179.0,
+                    altitude: None,
+                },
+                GpsSample {
+                    time: 1.0,
+                    latitude: 1.0,
+                    longitude: -179.0,
+                    altitude: Some(1.0),
+                },
+            ],
+            ..Default::default()
+        };
+
+        let gps = telemetry.gps_at(0.75).unwrap();
+        assert_eq!(gps.latitude, 0.75);
+        assert_eq!(gps.longitude, -179.5);
+        assert_eq!(gps.altitude, None);
+        assert_eq!(telemetry.gps_at(1.0), telemetry.gps().last().copied());
+        assert_eq!(telemetry.gps_at(-0.1), None);
+    }
+}
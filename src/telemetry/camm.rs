@@ -0,0 +1,123 @@
+use nom::{
+    number::complete::{le_f32, le_f64, le_i32, le_u16},
+    sequence::tuple,
+    IResult,
+};
+
+use super::{GpsSample, ImuSample, Telemetry};
+
+// Packet types, see https://developers.google.com/streetview/publish/camm-spec
+const GYRO: u16 = 2;
+const ACCELERATION: u16 = 3;
+const GPS: u16 = 5;
+const GPS_FULL: u16 = 6;
+
+/// Decodes a CAMM packet (one per sample), values are little-endian.
+pub(crate) fn decode(data: &[u8], time: f64, out: &mut Telemetry) {
+    if let Err(e) = decode_packet(data, time, out) {
+        tracing::warn!(?e, "Ignored: invalid CAMM packet");
+    }
+}
+
+fn decode_packet<'a>(data: &'a [u8], time: f64, out: &mut Telemetry) -> IResult<&'a [u8], ()> {
+    // reserved & packet type
+    let (remain, (_, packet_type)) = tuple((le_u16, le_u16))(data)?;
+    match packet_type {
+        GYRO | ACCELERATION => {
+            let (remain, (x, y, z)) = tuple((le_f32, le_f32, le_f32))(remain)?;
+            let sample = ImuSample {
+                time,
+                x: x as f64,
+                y: y as f64,
+                z: z as f64,
+            };
+            if packet_type == GYRO {
+                out.gyro.push(sample);
+            } else {
+                out.accel.push(sample);
+            }
+            Ok((remain, ()))
+        }
+        GPS => {
+            let (remain, (latitude, longitude, altitude)) =
+                tuple((le_f64, le_f64, le_f64))(remain)?;
+            out.gps.push(GpsSample {
+                time,
+                latitude,
+                longitude,
+                altitude: Some(altitude),
+            });
+            Ok((remain, ()))
+        }
+        GPS_FULL => {
+            let (remain, (_gps_time, fix_type, latitude, longitude, altitude)) =
+                tuple((le_f64, le_i32, le_f64, le_f64, le_f32))(remain)?;
+            // 0: no fix
+            if fix_type != 0 {
+                out.gps.push(GpsSample {
+                    time,
+                    latitude,
+                    longitude,
+                    altitude: (fix_type >= 3).then_some(altitude as f64),
+                });
+            }
+            Ok((remain, ()))
+        }
+        _ => Ok((remain, ())),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) fn gps_packet(latitude: f64, longitude: f64, altitude: f64) -> Vec<u8> {
+        [0, GPS]
+            .iter()
+            .flat_map(|x| x.to_le_bytes())
+            .chain(
+                [latitude, longitude, altitude]
+                    .iter()
+                    .flat_map(|x| x.to_le_bytes()),
+            )
+            .collect()
+    }
+
+    pub(crate) fn imu_packet(packet_type: u16, values: [f32; 3]) -> Vec<u8> {
+        [0, packet_type]
+            .iter()
+            .flat_map(|x| x.to_le_bytes())
+            .chain(values.iter().flat_map(|x| x.to_le_bytes()))
+            .collect()
+    }
+
+    #[test]
+    fn gps_full() {
+        let mut data = [0, GPS_FULL]
+            .iter()
+            .flat_map(|x| x.to_le_bytes())
+            .collect::<Vec<_>>();
+        data.extend_from_slice(&0_f64.to_le_bytes());
+        data.extend_from_slice(&2_i32.to_le_bytes());
+        data.extend_from_slice(&22.5_f64.to_le_bytes());
+        data.extend_from_slice(&113.9_f64.to_le_bytes());
+        data.extend_from_slice(&10_f32.to_le_bytes());
+
+        let mut telemetry = Telemetry::default();
+        decode(&data, 1.0, &mut telemetry);
+        // 2D fix, altitude is not available
+        assert_eq!(
+            telemetry.gps,
+            vec![GpsSample {
+                time: 1.0,
+                latitude: 22.5,
+                longitude: 113.9,
+                altitude: None
+            }]
+        );
+
+        // truncated
+        decode(&data[..20], 2.0, &mut telemetry);
+        assert_eq!(telemetry.gps.len(), 1);
+    }
+}
@@ -0,0 +1,109 @@
+use regex::Regex;
+
+use super::{GpsSample, Telemetry};
+
+const NUM: &str = r"([-+]?\d+(?:\.\d+)?)";
+
+/// Decodes GPS info from subtitle samples written by DJI drones & cameras,
+/// e.g.:
+///
+/// - `... [latitude: 22.5] [longitude: 113.9] [rel_alt: 1.3 abs_alt: 50.0]`
+/// - `... GPS(113.9,22.5,18) BAROMETER:59.4` (longitude first)
+pub(crate) struct TextDecoder {
+    latitude: Regex,
+    longitude: Regex,
+    altitude: Regex,
+    gps: Regex,
+}
+
+impl TextDecoder {
+    pub fn new() -> Self {
+        Self {
+            latitude: Regex::new(&format!(r"latitude\s*:\s*{NUM}")).unwrap(),
+            // "longtitude" is a typo in some firmwares
+            longitude: Regex::new(&format!(r"longt?itude\s*:\s*{NUM}")).unwrap(),
+            altitude: Regex::new(&format!(r"(?:abs_alt|altitude)\s*:\s*{NUM}")).unwrap(),
+            gps: Regex::new(&format!(r"GPS\s*\(\s*{NUM}\s*,\s*{NUM}\s*(?:,\s*{NUM})?")).unwrap(),
+        }
+    }
+
+    /// `data` is a text sample, i.e. a 16 bits text length followed by the
+    /// text.
+    pub fn decode(&self, data: &[u8], time: f64, out: &mut Telemetry) {
+        let Some(len) = data
+            .get(..2)
+            .map(|x| u16::from_be_bytes([x[0], x[1]]) as usize)
+        else {
+            return;
+        };
+        let Some(text) = data.get(2..2 + len) else {
+            return;
+        };
+        let text = String::from_utf8_lossy(text);
+
+        if let Some(sample) = self.parse(&text, time) {
+            // (0, 0) means there is no GPS signal
+            if sample.latitude != 0.0 || sample.longitude != 0.0 {
+                out.gps.push(sample);
+            }
+        }
+    }
+
+    fn parse(&self, text: &str, time: f64) -> Option<GpsSample> {
+        let num =
+            |re: &Regex, idx| -> Option<f64> { re.captures(text)?.get(idx)?.as_str().parse().ok() };
+
+        if let (Some(latitude), Some(longitude)) = (num(&self.latitude, 1), num(&self.longitude, 1))
+        {
+            return Some(GpsSample {
+                time,
+                latitude,
+                longitude,
+                altitude: num(&self.altitude, 1),
+            });
+        }
+
+        Some(GpsSample {
+            time,
+            latitude: num(&self.gps, 2)?,
+            longitude: num(&self.gps, 1)?,
+            altitude: num(&self.gps, 3),
+        })
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    pub(crate) fn text_sample(text: &str) -> Vec<u8> {
+        let mut data = (text.len() as u16).to_be_bytes().to_vec();
+        data.extend_from_slice(text.as_bytes());
+        data
+    }
+
+    #[test_case(
+        "FrameCnt: 1 [iso: 100] [latitude: 22.543210] [longitude: -113.901234] [rel_alt: 1.300 abs_alt: 112.618]",
+        Some((22.54321, -113.901234, Some(112.618)))
+    )]
+    #[test_case("[latitude : 22.5] [longtitude : 113.9]", Some((22.5, 113.9, None)))]
+    #[test_case("F/2.8, SS 320, ISO 100, EV 0, GPS (113.9286, 22.5249, 18), D 1.2m", Some((22.5249, 113.9286, Some(18.0))))]
+    #[test_case("[latitude: 0.000000] [longitude: 0.000000]", None)]
+    #[test_case("Hello, world", None)]
+    fn dji_text(text: &str, expect: Option<(f64, f64, Option<f64>)>) {
+        let mut telemetry = Telemetry::default();
+        TextDecoder::new().decode(&text_sample(text), 1.0, &mut telemetry);
+
+        let expect = expect
+            .map(|(latitude, longitude, altitude)| GpsSample {
+                time: 1.0,
+                latitude,
+                longitude,
+                altitude,
+            })
+            .into_iter()
+            .collect::<Vec<_>>();
+        assert_eq!(telemetry.gps, expect);
+    }
+}
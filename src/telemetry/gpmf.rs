@@ -0,0 +1,296 @@
+use super::{GpsSample, ImuSample, Telemetry};
+
+/// A GPMF KLV (key, length, value) item, see
+/// https://github.com/gopro/gpmf-parser#klv-design.
+#[derive(Debug)]
+struct Klv<'a> {
+    key: [u8; 4],
+    value_type: u8,
+    repeat: usize,
+    data: &'a [u8],
+}
+
+impl<'a> Klv<'a> {
+    fn parse(input: &'a [u8]) -> Option<(Klv<'a>, &'a [u8])> {
+        let header = input.get(..8)?;
+        let struct_size = header[5] as usize;
+        let repeat = u16::from_be_bytes([header[6], header[7]]) as usize;
+        let len = struct_size * repeat;
+        let data = input.get(8..8 + len)?;
+        // data is padded to 4 bytes
+        let remain = input.get(8 + ((len + 3) & !3)..).unwrap_or_default();
+
+        let klv = Klv {
+            key: [header[0], header[1], header[2], header[3]],
+            value_type: header[4],
+            repeat,
+            data,
+        };
+        Some((klv, remain))
+    }
+
+    /// Nested KLVs, for items with a value type of 0.
+    fn children(&self) -> impl Iterator<Item = Klv<'a>> {
+        let mut remain = if self.value_type == 0 { self.data } else { &[] };
+        std::iter::from_fn(move || {
+            let (klv, rem) = Klv::parse(remain)?;
+            remain = rem;
+            Some(klv)
+        })
+    }
+
+    /// All values as f64, big-endian. Returns an empty vec for unsupported
+    /// value types.
+    fn values(&self) -> Vec<f64> {
+        let size = match self.value_type {
+            b'b' | b'B' => 1,
+            b's' | b'S' => 2,
+            b'l' | b'L' | b'f' => 4,
+            b'd' | b'j' | b'J' => 8,
+            _ => return Vec::new(),
+        };
+        self.data
+            .chunks_exact(size)
+            .map(|x| match self.value_type {
+                b'b' => x[0] as i8 as f64,
+                b'B' => x[0] as f64,
+                b's' => i16::from_be_bytes([x[0], x[1]]) as f64,
+                b'S' => u16::from_be_bytes([x[0], x[1]]) as f64,
+                b'l' => i32::from_be_bytes([x[0], x[1], x[2], x[3]]) as f64,
+                b'L' => u32::from_be_bytes([x[0], x[1], x[2], x[3]]) as f64,
+                b'f' => f32::from_be_bytes([x[0], x[1], x[2], x[3]]) as f64,
+                b'd' => f64::from_be_bytes(x.try_into().unwrap_or_default()),
+                b'j' => i64::from_be_bytes(x.try_into().unwrap_or_default()) as f64,
+                _ => u64::from_be_bytes(x.try_into().unwrap_or_default()) as f64,
+            })
+            .collect()
+    }
+
+    /// Values grouped by structure (e.g. one row for one 3-axis sample),
+    /// with `scale` applied.
+    fn rows(&self, scale: &[f64]) -> Vec<Vec<f64>> {
+        let values = self.values();
+        if values.is_empty() || self.repeat == 0 {
+            return Vec::new();
+        }
+        let n = values.len() / self.repeat;
+        if n == 0 {
+            return Vec::new();
+        }
+
+        values
+            .chunks_exact(n)
+            .map(|row| {
+                row.iter()
+                    .enumerate()
+                    .map(|(i, v)| {
+                        // SCAL has either one value for all elements, or one
+                        // value for each element
+                        let s = scale.get(i).or(scale.first()).copied().unwrap_or(1.0);
+                        if s == 0.0 {
+                            *v
+                        } else {
+                            v / s
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// Decodes a GPMF payload, which covers the time range of
+/// `[time, time + duration)`.
+pub(crate) fn decode(data: &[u8], time: f64, duration: f64, out: &mut Telemetry) {
+    let mut remain = data;
+    while let Some((devc, rem)) = Klv::parse(remain) {
+        remain = rem;
+        if &devc.key != b"DEVC" {
+            continue;
+        }
+        for strm in devc.children().filter(|x| &x.key == b"STRM") {
+            decode_stream(&strm, time, duration, out);
+        }
+    }
+}
+
+fn decode_stream(strm: &Klv, time: f64, duration: f64, out: &mut Telemetry) {
+    let mut scale = Vec::new();
+    let mut gps_fix = None;
+
+    for klv in strm.children() {
+        let rows = || {
+            let rows = klv.rows(&scale);
+            let n = rows.len() as f64;
+            // samples are evenly distributed over the payload duration
+            rows.into_iter()
+                .enumerate()
+                .map(move |(i, row)| (time + duration * i as f64 / n, row))
+        };
+
+        match &klv.key {
+            b"SCAL" => scale = klv.values(),
+            b"GPSF" => gps_fix = klv.values().first().copied(),
+            b"GPS5" => {
+                // 0: no lock
+                if gps_fix == Some(0.0) {
+                    continue;
+                }
+                out.gps.extend(
+                    rows()
+                        .filter(|(_, x)| x.len() >= 3)
+                        .map(|(t, x)| GpsSample {
+                            time: t,
+                            latitude: x[0],
+                            longitude: x[1],
+                            altitude: Some(x[2]),
+                        }),
+                );
+            }
+            b"GYRO" | b"ACCL" => {
+                let samples = rows()
+                    .filter(|(_, x)| x.len() >= 3)
+                    .map(|(t, x)| ImuSample {
+                        time: t,
+                        x: x[0],
+                        y: x[1],
+                        z: x[2],
+                    });
+                if &klv.key == b"GYRO" {
+                    out.gyro.extend(samples);
+                } else {
+                    out.accel.extend(samples);
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    fn klv(key: &[u8; 4], value_type: u8, struct_size: u8, data: &[u8]) -> Vec<u8> {
+        let repeat = if struct_size == 0 {
+            0
+        } else {
+            data.len() / struct_size as usize
+        };
+        let mut res = key.to_vec();
+        res.extend_from_slice(&[value_type, struct_size]);
+        res.extend_from_slice(&(repeat as u16).to_be_bytes());
+        res.extend_from_slice(data);
+        res.resize((res.len() + 3) & !3, 0);
+        res
+    }
+
+    fn nested(key: &[u8; 4], children: &[Vec<u8>]) -> Vec<u8> {
+        let data = children.concat();
+        let mut res = key.to_vec();
+        res.extend_from_slice(&[0, 1]);
+        res.extend_from_slice(&(data.len() as u16).to_be_bytes());
+        res.extend_from_slice(&data);
+        res
+    }
+
+    fn be_bytes<T: Copy, const N: usize>(values: &[T], f: fn(T) -> [u8; N]) -> Vec<u8> {
+        values.iter().flat_map(|x| f(*x)).collect()
+    }
+
+    /// A GPMF payload with 2 GPS5 & 4 GYRO samples.
+    pub(crate) fn payload() -> Vec<u8> {
+        let gps = nested(
+            b"STRM",
+            &[
+                klv(b"STNM", b'c', 1, b"GPS (Lat., Long., Alt., 2D, 3D)"),
+                klv(b"GPSF", b'L', 4, &3_u32.to_be_bytes()),
+                klv(
+                    b"SCAL",
+                    b'l',
+                    4,
+                    &be_bytes(&[10, 10, 1, 1, 1], i32::to_be_bytes),
+                ),
+                klv(
+                    b"GPS5",
+                    b'l',
+                    20,
+                    &be_bytes(
+                        &[225, 1139, 50, 0, 0, 226, 1140, 51, 0, 0],
+                        i32::to_be_bytes,
+                    ),
+                ),
+            ],
+        );
+        let gyro = nested(
+            b"STRM",
+            &[
+                klv(b"SCAL", b's', 2, &100_i16.to_be_bytes()),
+                klv(
+                    b"GYRO",
+                    b's',
+                    6,
+                    &be_bytes(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12], i16::to_be_bytes),
+                ),
+            ],
+        );
+        nested(
+            b"DEVC",
+            &[klv(b"DVID", b'L', 4, &1_u32.to_be_bytes()), gps, gyro],
+        )
+    }
+
+    #[test]
+    fn gpmf_payload() {
+        let mut telemetry = Telemetry::default();
+        decode(&payload(), 10.0, 1.0, &mut telemetry);
+
+        assert_eq!(
+            telemetry.gps,
+            vec![
+                GpsSample {
+                    time: 10.0,
+                    latitude: 22.5,
+                    longitude: 113.9,
+                    altitude: Some(50.0)
+                },
+                GpsSample {
+                    time: 10.5,
+                    latitude: 22.6,
+                    longitude: 114.0,
+                    altitude: Some(51.0)
+                }
+            ]
+        );
+        assert_eq!(telemetry.gyro.len(), 4);
+        assert_eq!(
+            telemetry.gyro[3],
+            ImuSample {
+                time: 10.75,
+                x: 0.1,
+                y: 0.11,
+                z: 0.12
+            }
+        );
+        assert!(telemetry.accel.is_empty());
+    }
+
+    #[test]
+    fn gps_no_lock() {
+        let strm = nested(
+            b"STRM",
+            &[
+                klv(b"GPSF", b'L', 4, &0_u32.to_be_bytes()),
+                klv(b"GPS5", b'l', 20, &[0; 20]),
+            ],
+        );
+        let mut telemetry = Telemetry::default();
+        decode(&nested(b"DEVC", &[strm]), 0.0, 1.0, &mut telemetry);
+        assert!(telemetry.is_empty());
+
+        // truncated payload
+        let data = payload();
+        decode(&data[..data.len() / 2], 0.0, 1.0, &mut telemetry);
+        assert!(telemetry.is_empty());
+    }
+}
@@ -1,4 +1,8 @@
-use std::{fs::File, io::Read, path::Path};
+use std::{
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    path::Path,
+};
 
 use crate::exif::Exif;
 use crate::exif::ExifTag::*;
@@ -31,6 +35,101 @@ pub fn open_sample_w(path: &str) -> Result<File, std::io::Error> {
     File::create(p)
 }
 
+/// Makes an ISOBMFF box.
+pub fn bbox(box_type: &[u8], body: &[u8]) -> Vec<u8> {
+    let mut data = ((body.len() + 8) as u32).to_be_bytes().to_vec();
+    data.extend_from_slice(box_type);
+    data.extend_from_slice(body);
+    data
+}
+
+/// Makes an ISOBMFF full box of version 0.
+pub fn full_box(box_type: &[u8], body: &[u8]) -> Vec<u8> {
+    bbox(box_type, &[&[0, 0, 0, 0], body].concat())
+}
+
+/// Makes an ISOBMFF full box of version 0 with the given (24-bit) flags.
+pub fn full_box_flags(box_type: &[u8], flags: u32, body: &[u8]) -> Vec<u8> {
+    bbox(
+        box_type,
+        &[&(flags & 0xFF_FFFF).to_be_bytes()[..], body].concat(),
+    )
+}
+
+/// Makes an ISOBMFF full box of version 1, i.e. with 64-bit times.
+pub fn full_box_v1(box_type: &[u8], body: &[u8]) -> Vec<u8> {
+    bbox(box_type, &[&[1, 0, 0, 0], body].concat())
+}
+
+/// Makes a sample table (e.g. `stts`), prefixed with the entry count.
+pub fn table(entries: &[&[u32]]) -> Vec<u8> {
+    let mut data = (entries.len() as u32).to_be_bytes().to_vec();
+    for x in entries.iter().flat_map(|x| x.iter()) {
+        data.extend_from_slice(&x.to_be_bytes());
+    }
+    data
+}
+
+/// A virtual file for testing large (e.g. >4 GB) files without allocating
+/// them, it's all zeros except for the data put into it.
+pub struct SparseFile {
+    len: u64,
+    chunks: Vec<(u64, Vec<u8>)>,
+    pos: u64,
+    /// Total number of bytes read
+    pub read: u64,
+}
+
+impl SparseFile {
+    pub fn new(len: u64) -> Self {
+        Self {
+            len,
+            chunks: Vec::new(),
+            pos: 0,
+            read: 0,
+        }
+    }
+
+    /// Puts `data` at `offset`, the file is extended if necessary.
+    pub fn put(&mut self, offset: u64, data: Vec<u8>) {
+        self.len = self.len.max(offset + data.len() as u64);
+        self.chunks.push((offset, data));
+    }
+}
+
+impl Read for SparseFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = (self.len.saturating_sub(self.pos)).min(buf.len() as u64) as usize;
+        let (start, end) = (self.pos, self.pos + n as u64);
+        buf[..n].fill(0);
+        for (offset, data) in self.chunks.iter() {
+            let chunk_end = offset + data.len() as u64;
+            if *offset >= end || chunk_end <= start {
+                continue;
+            }
+            let from = start.max(*offset);
+            let to = end.min(chunk_end);
+            buf[(from - start) as usize..(to - start) as usize]
+                .copy_from_slice(&data[(from - offset) as usize..(to - offset) as usize]);
+        }
+        self.pos = end;
+        self.read += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for SparseFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(x) => Some(x),
+            SeekFrom::End(x) => self.len.checked_add_signed(x),
+            SeekFrom::Current(x) => self.pos.checked_add_signed(x),
+        };
+        self.pos = pos.ok_or_else(|| io::Error::from(io::ErrorKind::InvalidInput))?;
+        Ok(self.pos)
+    }
+}
+
 #[allow(deprecated)]
 pub fn sorted_exif_entries(exif: &Exif) -> Vec<String> {
     let mut entries = exif
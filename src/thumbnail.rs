@@ -0,0 +1,333 @@
+use std::io::{Read, Write};
+
+use nom::number::Endianness;
+
+use crate::{
+    exif::{
+        check_exif_header, encode_ifd, ifd_fields, ifd_size, read_ifd, u16_at, u16_bytes, u32_at,
+        u32_bytes, EncodedValue, TiffHeader, EXIF_IDENT,
+    },
+    file::{Mime, MimeImage},
+    jpeg::{self, MarkerCode},
+    values::DataFormat,
+    Error,
+};
+
+const COMPRESSION: u16 = 0x0103;
+const THUMBNAIL_OFFSET: u16 = 0x0201;
+const THUMBNAIL_LENGTH: u16 = 0x0202;
+const EXIF_OFFSET: u16 = 0x8769;
+const GPS_INFO: u16 = 0x8825;
+const INTEROP_OFFSET: u16 = 0xa005;
+
+/// Reads a JPEG file from `reader`, and writes it into `writer` with the
+/// embedded thumbnail (ifd1 of the Exif data) removed. Other Exif entries,
+/// other segments and the image data are kept unchanged.
+///
+/// The Exif APP1 segment is shrunk when the thumbnail is located at the end
+/// of the Exif data (which is the usual case). Otherwise the thumbnail data
+/// is zeroed, so that the offsets of other entries (e.g. the ones inside
+/// MakerNotes) stay valid.
+///
+/// Returns [`Error::WriteFailed`] if the file is not a JPEG file, or it has
+/// no Exif data. A file without a thumbnail is copied as is.
+///
+/// ## Example
+///
+/// ```rust
+/// use nom_exif::*;
+/// use std::fs::File;
+///
+/// fn main() -> Result<()> {
+///     let mut output = Vec::new();
+///     remove_thumbnail(File::open("./testdata/exif.jpg")?, &mut output)?;
+///
+///     let ms = MediaSource::seekable(std::io::Cursor::new(output))?;
+///     let iter: ExifIter = MediaParser::new().parse(ms)?;
+///     let exif: Exif = iter.into();
+///     assert_eq!(exif.get_by_ifd_tag_code(1, 0x0201), None);
+///     assert_eq!(exif.get(ExifTag::Make), Some(&"vivo".into()));
+///     Ok(())
+/// }
+/// ```
+pub fn remove_thumbnail<R: Read, W: Write>(reader: R, writer: W) -> crate::Result<()> {
+    rewrite_exif(reader, writer, |tiff| {
+        let mut tiff = Tiff::parse(tiff)?;
+        tiff.remove_ifd1();
+        Ok(tiff.data)
+    })
+}
+
+/// Reads a JPEG file from `reader`, and writes it into `writer` with the
+/// embedded thumbnail replaced by `thumbnail` (a JPEG image, usually 160x120
+/// pixels).
+///
+/// The old thumbnail is removed the same way as [`remove_thumbnail`], then a
+/// new ifd1 (keeping the other entries of the old one, e.g. the resolution)
+/// and the new thumbnail are appended to the end of the Exif data, so that
+/// the offsets of other entries stay valid. A thumbnail is added if the file
+/// has none.
+///
+/// Returns [`Error::WriteFailed`] if the file is not a JPEG file, it has no
+/// Exif data, `thumbnail` is not a JPEG image, or the Exif data becomes too
+/// large to fit in a JPEG segment (64 KB).
+pub fn replace_thumbnail<R: Read, W: Write>(
+    reader: R,
+    writer: W,
+    thumbnail: &[u8],
+) -> crate::Result<()> {
+    if !thumbnail.starts_with(&[0xFF, MarkerCode::Soi.code()]) {
+        return Err(Error::WriteFailed(
+            "the thumbnail is not a JPEG image".into(),
+        ));
+    }
+    rewrite_exif(reader, writer, |tiff| {
+        let mut tiff = Tiff::parse(tiff)?;
+        let mut entries = match tiff.ifd1() {
+            Some(ifd1) => read_ifd(&tiff.data, ifd1 as usize, tiff.endian)?.0,
+            None => Vec::new(),
+        };
+        tiff.remove_ifd1();
+
+        entries.retain(|x| ![COMPRESSION, THUMBNAIL_OFFSET, THUMBNAIL_LENGTH].contains(&x.0));
+        let endian = tiff.endian;
+        let u32_value =
+            |x: u32| -> EncodedValue { (DataFormat::U32, 1, u32_bytes(x, endian).to_vec()) };
+        // JPEG compression
+        let compression = u16_bytes(6, endian);
+        entries.push((COMPRESSION, (DataFormat::U16, 1, compression.to_vec())));
+        entries.push((THUMBNAIL_OFFSET, u32_value(0)));
+        entries.push((THUMBNAIL_LENGTH, u32_value(thumbnail.len() as u32)));
+        entries.sort_by_key(|x| x.0);
+
+        // Word aligned
+        if tiff.data.len() % 2 == 1 {
+            tiff.data.push(0);
+        }
+        let base = tiff.data.len();
+        let offset = base + ifd_size(&entries);
+        if let Some(entry) = entries.iter_mut().find(|x| x.0 == THUMBNAIL_OFFSET) {
+            entry.1 = u32_value(offset as u32);
+        }
+        let ifd1 = encode_ifd(&entries, base, endian, 0);
+        tiff.data.extend(ifd1);
+        tiff.data.extend_from_slice(thumbnail);
+        tiff.set_u32(tiff.ifd0_next_pos, base as u32);
+        Ok(tiff.data)
+    })
+}
+
+/// Replaces the TIFF data of the Exif APP1 segment of a JPEG file with the
+/// result of `f`.
+fn rewrite_exif<R: Read, W: Write>(
+    mut reader: R,
+    writer: W,
+    f: impl FnOnce(&[u8]) -> crate::Result<Vec<u8>>,
+) -> crate::Result<()> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    let mime = Mime::try_from(&data[..])?;
+    if mime != Mime::Image(MimeImage::Jpeg) {
+        return Err(Error::WriteFailed(
+            format!("writing thumbnails into {mime:?} files is not supported").into(),
+        ));
+    }
+
+    let app1 = MarkerCode::APP1.code();
+    let (segments, _) = jpeg::parse_segments(&data)?;
+    let exif = segments
+        .iter()
+        .filter(|x| x.code == app1)
+        .map(|x| &data[x.payload.clone()]) // Safe-slice
+        .find(|x| check_exif_header(x))
+        .ok_or_else(|| Error::WriteFailed("no Exif data found".into()))?;
+
+    let mut payload = EXIF_IDENT.as_bytes().to_vec();
+    payload.extend(f(&exif[EXIF_IDENT.len()..])?); // Safe-slice
+    jpeg::rewrite_segments(
+        &data,
+        writer,
+        |code, data| !(code == app1 && check_exif_header(data)),
+        &[(app1, &payload)],
+    )
+}
+
+/// TIFF data of the Exif data.
+struct Tiff {
+    data: Vec<u8>,
+    endian: Endianness,
+    ifd0: usize,
+    // Position of the next IFD pointer of ifd0
+    ifd0_next_pos: usize,
+}
+
+impl Tiff {
+    fn parse(data: &[u8]) -> crate::Result<Tiff> {
+        let (_, header) = TiffHeader::parse(data)?;
+        let mut tiff = Tiff {
+            data: data.to_vec(),
+            endian: header.endian,
+            ifd0: header.ifd0_offset as usize,
+            ifd0_next_pos: 0,
+        };
+        let num = u16_at(&tiff.data, tiff.ifd0, tiff.endian)
+            .ok_or("invalid TIFF data; ifd0 not found")?;
+        tiff.ifd0_next_pos = tiff.ifd0 + 2 + num as usize * 12;
+        if tiff.ifd0_next_pos + 4 > tiff.data.len() {
+            return Err("invalid TIFF data; ifd0 is truncated".into());
+        }
+        Ok(tiff)
+    }
+
+    fn set_u32(&mut self, pos: usize, x: u32) {
+        self.data[pos..pos + 4].copy_from_slice(&u32_bytes(x, self.endian)); // Safe-slice
+    }
+
+    fn ifd1(&self) -> Option<u32> {
+        u32_at(&self.data, self.ifd0_next_pos, self.endian).filter(|x| *x != 0)
+    }
+
+    /// Returns the end of the data used by the IFD at `ifd` & its sub-IFDs.
+    fn used_end(&self, ifd: usize, depth: usize) -> usize {
+        let Some((fields, next_pos)) = ifd_fields(&self.data, ifd, self.endian) else {
+            return 0;
+        };
+        let mut end = next_pos + 4;
+        for field in fields {
+            // Inline values occupy the whole value field
+            end = end.max(field.value_range().end.max(field.pos + 12));
+            if depth < 2 && [EXIF_OFFSET, GPS_INFO, INTEROP_OFFSET].contains(&field.code) {
+                end = end.max(self.used_end(field.value as usize, depth + 1));
+            }
+        }
+        end
+    }
+
+    /// Unlinks ifd1 (and the IFDs after it) from ifd0, then truncates the
+    /// data of ifd1 & the thumbnail if they are located at the end, or zeroes
+    /// the thumbnail otherwise.
+    fn remove_ifd1(&mut self) {
+        let Some(ifd1) = self.ifd1().map(|x| x as usize) else {
+            return;
+        };
+        self.set_u32(self.ifd0_next_pos, 0);
+
+        let thumbnail = ifd_fields(&self.data, ifd1, self.endian).and_then(|(fields, _)| {
+            let value = |code| {
+                fields
+                    .iter()
+                    .find(|x| x.code == code)
+                    .map(|x| x.value as usize)
+            };
+            let offset = value(THUMBNAIL_OFFSET)?;
+            let end = offset.checked_add(value(THUMBNAIL_LENGTH)?)?;
+            (end <= self.data.len()).then_some(offset..end)
+        });
+        let cut = thumbnail.as_ref().map_or(ifd1, |x| x.start.min(ifd1));
+        if self.used_end(self.ifd0, 0).max(8) <= cut {
+            self.data.truncate(cut);
+        } else if let Some(thumbnail) = thumbnail {
+            self.data[thumbnail].fill(0); // Safe-slice
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{testkit::read_sample, Exif, ExifIter, ExifTag, MediaParser, MediaSource};
+
+    fn parse(data: &[u8]) -> (Exif, Vec<u8>) {
+        let ms = MediaSource::seekable(Cursor::new(data.to_vec())).unwrap();
+        let iter: ExifIter = MediaParser::new().parse(ms).unwrap();
+        let (segments, _) = jpeg::parse_segments(data).unwrap();
+        let tiff = segments
+            .iter()
+            .map(|x| &data[x.payload.clone()])
+            .find(|x| check_exif_header(x))
+            .unwrap()[EXIF_IDENT.len()..]
+            .to_vec();
+        (iter.into(), tiff)
+    }
+
+    fn thumbnail(exif: &Exif, tiff: &[u8]) -> Option<Vec<u8>> {
+        let offset = exif.get_by_ifd_tag_code(1, THUMBNAIL_OFFSET)?.as_u32()? as usize;
+        let len = exif.get_by_ifd_tag_code(1, THUMBNAIL_LENGTH)?.as_u32()? as usize;
+        Some(tiff[offset..offset + len].to_vec())
+    }
+
+    #[test]
+    fn remove() {
+        let data = read_sample("exif.jpg").unwrap();
+        let mut output = Vec::new();
+        remove_thumbnail(Cursor::new(&data), &mut output).unwrap();
+
+        let (before, before_tiff) = parse(&data);
+        let (after, after_tiff) = parse(&output);
+        assert!(thumbnail(&before, &before_tiff).is_some());
+        assert_eq!(thumbnail(&after, &after_tiff), None);
+        // The APP1 segment is shrunk
+        assert_eq!(after_tiff.len(), 1409);
+        assert_eq!(data.len() - output.len(), before_tiff.len() - 1409);
+        for tag in [
+            ExifTag::Make,
+            ExifTag::DateTimeOriginal,
+            ExifTag::GPSLatitude,
+        ] {
+            assert_eq!(after.get(tag), before.get(tag));
+        }
+
+        // No thumbnail
+        let mut again = Vec::new();
+        remove_thumbnail(Cursor::new(&output), &mut again).unwrap();
+        assert_eq!(again, output);
+    }
+
+    #[test]
+    fn replace() {
+        let data = read_sample("exif.jpg").unwrap();
+        let (before, before_tiff) = parse(&data);
+        let old = thumbnail(&before, &before_tiff).unwrap();
+        let new = [&old[..], b"new"].concat();
+
+        let mut output = Vec::new();
+        replace_thumbnail(Cursor::new(&data), &mut output, &new).unwrap();
+        let (after, after_tiff) = parse(&output);
+        assert_eq!(thumbnail(&after, &after_tiff), Some(new.clone()));
+        for code in [0x011a, 0x011b] {
+            // XResolution & YResolution are kept
+            assert_eq!(
+                after.get_by_ifd_tag_code(1, code),
+                before.get_by_ifd_tag_code(1, code)
+            );
+        }
+        assert_eq!(after.get(ExifTag::Make), before.get(ExifTag::Make));
+
+        // Add a thumbnail
+        let mut removed = Vec::new();
+        remove_thumbnail(Cursor::new(&data), &mut removed).unwrap();
+        let mut output = Vec::new();
+        replace_thumbnail(Cursor::new(&removed), &mut output, &old).unwrap();
+        let (after, after_tiff) = parse(&output);
+        assert_eq!(thumbnail(&after, &after_tiff), Some(old));
+        assert_eq!(
+            after.get_by_ifd_tag_code(1, COMPRESSION),
+            Some(&6_u16.into())
+        );
+    }
+
+    #[test]
+    fn failed() {
+        let data = read_sample("exif.jpg").unwrap();
+        replace_thumbnail(Cursor::new(&data), &mut Vec::new(), b"not a jpeg").unwrap_err();
+        // Too large
+        replace_thumbnail(Cursor::new(&data), &mut Vec::new(), &data[..70000]).unwrap_err();
+
+        let data = read_sample("no-exif.jpg").unwrap();
+        remove_thumbnail(Cursor::new(&data), &mut Vec::new()).unwrap_err();
+        let data = read_sample("exif.heic").unwrap();
+        remove_thumbnail(Cursor::new(&data), &mut Vec::new()).unwrap_err();
+    }
+}
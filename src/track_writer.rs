@@ -0,0 +1,850 @@
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+
+use chrono::{DateTime, FixedOffset};
+
+use crate::{
+    file::{Mime, MimeVideo},
+    patch::{find_top_level_box, read_at, sniff_mime, top_level_boxes},
+    strip::{find_box, parse_boxes, strip_location_boxes},
+    EntryValue, Error, ExifTag, GPSInfo,
+};
+
+const MAX_BOX_DEPTH: usize = 8;
+
+const LOCATION_KEY: &str = "com.apple.quicktime.location.ISO6709";
+const CREATIONDATE_KEY: &str = "com.apple.quicktime.creationdate";
+
+/// Seconds from midnight, January 1, 1904 (the epoch of MP4/QuickTime
+/// times) to the Unix epoch.
+const MAC_EPOCH_OFFSET: i64 = 2_082_844_800;
+
+/// Language code of the `©xyz` atoms written by Android phones.
+const XYZ_LANGUAGE: [u8; 2] = [0x15, 0xc7];
+
+/// Writes GPS info, the creation time & QuickTime metadata items into
+/// MP4/QuickTime (`*.mp4`, `*.mov`, `*.3gp`) files, i.e. the metadata read
+/// as [`TrackInfo`](crate::TrackInfo).
+///
+/// Only the `moov` box is rebuilt, the media data is kept as it is:
+///
+/// - If the new `moov` box fits in the space of the old one (including the
+///   `free` boxes following it), it's written in place, and the rest of the
+///   space is filled with a `free` box.
+/// - Otherwise, the new `moov` box is appended to the end of the file (or
+///   written in place if it's already the last box), and the old one is
+///   turned into a `free` box, so that the chunk offsets of the media data
+///   stay valid.
+///
+/// ## Example
+///
+/// ```rust
+/// use nom_exif::*;
+/// use std::fs::File;
+///
+/// fn main() -> Result<()> {
+///     let mut writer = TrackWriter::new();
+///     writer
+///         .set_gps_decimal(48.8577, 2.295, Some(35.0))?
+///         .set_item("com.apple.quicktime.make", "nom-exif");
+///
+///     let mut output = Vec::new();
+///     writer.write(File::open("./testdata/meta.mov")?, &mut output)?;
+///
+///     let ms = MediaSource::seekable(std::io::Cursor::new(output))?;
+///     let info: TrackInfo = MediaParser::new().parse(ms)?;
+///     assert_eq!(
+///         info.get(TrackInfoTag::GpsIso6709),
+///         Some(&"+48.8577+002.2950+035.000/".into())
+///     );
+///     assert_eq!(info.get(TrackInfoTag::Make), Some(&"nom-exif".into()));
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TrackWriter {
+    /// `Some(None)` means removing the GPS info.
+    gps: Option<Option<String>>,
+    creation_time: Option<DateTime<FixedOffset>>,
+    /// Metadata items to be set (or removed if the value is `None`), in the
+    /// order of setting.
+    items: Vec<(String, Option<String>)>,
+}
+
+impl TrackWriter {
+    pub fn new() -> TrackWriter {
+        TrackWriter::default()
+    }
+
+    /// Sets the GPS info, which is written into the `moov/udta/©xyz` atom &
+    /// the `com.apple.quicktime.location.ISO6709` metadata item in ISO 6709
+    /// format. The existing location atoms & items are removed.
+    pub fn set_gps(&mut self, gps: &GPSInfo) -> &mut Self {
+        self.gps = Some(Some(gps.format_iso6709()));
+        self
+    }
+
+    /// Sets the GPS info with a location in decimal degrees (negative for
+    /// south/west), and an optional altitude in meters (negative for below
+    /// sea level), see [`TrackWriter::set_gps`].
+    ///
+    /// Returns [`Error::WriteFailed`] if the location is out of range.
+    ///
+    /// ```rust
+    /// use nom_exif::*;
+    ///
+    /// let mut writer = TrackWriter::new();
+    /// assert!(writer.set_gps_decimal(48.8577, 2.295, None).is_ok());
+    /// assert!(writer.set_gps_decimal(0.0, 181.0, None).is_err());
+    /// ```
+    pub fn set_gps_decimal(
+        &mut self,
+        latitude: f64,
+        longitude: f64,
+        altitude: Option<f64>,
+    ) -> crate::Result<&mut Self> {
+        if !(-90.0..=90.0).contains(&latitude)
+            || !(-180.0..=180.0).contains(&longitude)
+            || altitude.is_some_and(|x| !x.is_finite())
+        {
+            return Err(Error::WriteFailed(
+                format!("invalid GPS location: {latitude}, {longitude}, {altitude:?}").into(),
+            ));
+        }
+        // The same format as Apple devices, e.g. "+27.1281+100.2508+000.000/"
+        let altitude = altitude.map(|x| format!("{x:+08.3}")).unwrap_or_default();
+        self.gps = Some(Some(format!(
+            "{latitude:+08.4}{longitude:+09.4}{altitude}/"
+        )));
+        Ok(self)
+    }
+
+    /// Removes the GPS info, i.e. the location atoms (`©xyz` & `loci`) and
+    /// the `com.apple.quicktime.location.*` metadata items.
+    pub fn remove_gps(&mut self) -> &mut Self {
+        self.gps = Some(None);
+        self
+    }
+
+    /// Sets the creation time of the movie, tracks & media headers (`mvhd`,
+    /// `tkhd` & `mdhd`, which are in UTC), and the existing
+    /// `com.apple.quicktime.creationdate` & `©day` metadata (with the time
+    /// zone kept).
+    pub fn set_creation_time(&mut self, time: DateTime<FixedOffset>) -> &mut Self {
+        self.creation_time = Some(time);
+        self
+    }
+
+    /// Sets a text item of the QuickTime metadata (`moov/meta/keys` &
+    /// `moov/meta/ilst`), e.g. `com.apple.quicktime.make`. The item is added
+    /// if it doesn't exist.
+    pub fn set_item(&mut self, key: impl Into<String>, value: impl Into<String>) -> &mut Self {
+        self.edit_item(key.into(), Some(value.into()))
+    }
+
+    /// Removes an item of the QuickTime metadata, see
+    /// [`TrackWriter::set_item`].
+    pub fn remove_item(&mut self, key: impl Into<String>) -> &mut Self {
+        self.edit_item(key.into(), None)
+    }
+
+    fn edit_item(&mut self, key: String, value: Option<String>) -> &mut Self {
+        self.items.retain(|(k, _)| *k != key);
+        self.items.push((key, value));
+        self
+    }
+
+    /// Reads a MP4/QuickTime file from `reader`, and writes it into `writer`
+    /// with the metadata updated.
+    ///
+    /// Returns [`Error::WriteFailed`] if the file is not a MP4/QuickTime
+    /// file, or a time can't be stored in its original format.
+    pub fn write<R: Read, W: Write>(&self, mut reader: R, mut writer: W) -> crate::Result<()> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        let mut file = Cursor::new(data);
+        self.write_in_place(&mut file)?;
+        writer.write_all(file.get_ref())?;
+        Ok(())
+    }
+
+    /// The same as [`TrackWriter::write`], but the metadata is updated in
+    /// place, i.e. only the `moov` box is read & rewritten (and the old one
+    /// is freed if it's relocated), the media data is skipped by seeking.
+    pub fn write_in_place<F: Read + Write + Seek>(&self, mut file: F) -> crate::Result<()> {
+        let mime = sniff_mime(&mut file)?;
+        if !matches!(
+            mime,
+            Mime::Video(MimeVideo::QuickTime | MimeVideo::Mp4 | MimeVideo::_3gpp)
+        ) {
+            return Err(Error::WriteFailed(
+                format!("writing track metadata of {mime:?} files is not supported").into(),
+            ));
+        }
+
+        let boxes = top_level_boxes(&mut file)?;
+        let index = boxes
+            .iter()
+            .position(|x| &x.box_type == b"moov")
+            .ok_or("moov box not found")?;
+        let moov = &boxes[index];
+        let data = read_at(
+            &mut file,
+            moov.range.start,
+            (moov.range.end - moov.range.start) as usize,
+        )?;
+        let mut new_moov = self.rebuild_moov(data)?;
+
+        let followed = boxes[index + 1..] // Safe-slice
+            .iter()
+            .take_while(|x| matches!(&x.box_type, b"free" | b"skip"))
+            .collect::<Vec<_>>();
+        let space = followed.last().map_or(moov.range.end, |x| x.range.end) - moov.range.start;
+        let is_last = followed.len() == boxes.len() - index - 1;
+        let len = new_moov.len() as u64;
+
+        if len == space || len + 8 <= space {
+            file.seek(SeekFrom::Start(moov.range.start))?;
+            file.write_all(&new_moov)?;
+            if len < space {
+                file.write_all(&new_box(b"free", &vec![0; (space - len - 8) as usize])?)?;
+            }
+        } else if is_last {
+            // The file grows, a gap of less than 8 bytes is covered by an
+            // empty `free` box
+            if len < space {
+                new_moov.extend(new_box(b"free", &[])?);
+            }
+            file.seek(SeekFrom::Start(moov.range.start))?;
+            file.write_all(&new_moov)?;
+        } else {
+            // The last box has to be sized before appending
+            let last = boxes.last().unwrap();
+            if last.to_end {
+                let size = u32::try_from(last.range.end - last.range.start)
+                    .ok()
+                    .filter(|_| last.header_size == 8)
+                    .ok_or_else(|| Error::WriteFailed("the last box is too big".into()))?;
+                file.seek(SeekFrom::Start(last.range.start))?;
+                file.write_all(&size.to_be_bytes())?;
+            }
+            file.seek(SeekFrom::Start(last.range.end))?;
+            file.write_all(&new_moov)?;
+
+            file.seek(SeekFrom::Start(moov.range.start + 4))?;
+            file.write_all(b"free")?;
+            file.seek(SeekFrom::Start(moov.range.start + moov.header_size))?;
+            let body_size = moov.range.end - moov.range.start - moov.header_size;
+            std::io::copy(&mut std::io::repeat(0).take(body_size), &mut file)?;
+        }
+        file.flush()?;
+        Ok(())
+    }
+
+    /// Rebuilds the `moov` box (`data`) with the metadata updated.
+    fn rebuild_moov(&self, mut data: Vec<u8>) -> crate::Result<Vec<u8>> {
+        let range = 0..data.len();
+        // The new location is added after all of the old ones are removed
+        if self.gps.is_some() {
+            strip_location_boxes(&mut data, range.clone(), 0)?;
+        }
+        if let Some(time) = self.creation_time {
+            let secs =
+                u64::try_from(time.timestamp() + MAC_EPOCH_OFFSET).map_err(|_| out_of_range())?;
+            set_header_times(&mut data, range.clone(), 0, secs, 0)?;
+        }
+
+        let moov = find_box(&data, range, b"moov")?.ok_or("moov box not found")?;
+        let children = parse_boxes(&data, moov.body())?;
+        let find = |box_type: &[u8; 4]| {
+            children
+                .iter()
+                .find(|x| &x.box_type == box_type)
+                .map(|x| x.range.clone())
+        };
+        let (meta, udta) = (find(b"meta"), find(b"udta"));
+        let box_data = |range: &Option<Range<usize>>| range.clone().map(|x| &data[x]); // Safe-slice
+        let mut new_meta = self.rebuild_meta(box_data(&meta))?;
+        let mut new_udta = self.rebuild_udta(box_data(&udta))?;
+
+        let mut body = Vec::new();
+        for child in children.iter() {
+            if Some(&child.range) == meta.as_ref() {
+                body.extend(new_meta.take().unwrap_or_default());
+            } else if Some(&child.range) == udta.as_ref() {
+                body.extend(new_udta.take().unwrap_or_default());
+            } else {
+                body.extend_from_slice(&data[child.range.clone()]); // Safe-slice
+            }
+        }
+        // Newly created boxes
+        body.extend(new_meta.unwrap_or_default());
+        body.extend(new_udta.unwrap_or_default());
+        new_box(b"moov", &body)
+    }
+
+    /// Rebuilds the QuickTime `meta` box (`data`, or a new one if it's
+    /// `None`) with the items updated. Returns `None` if there is nothing to
+    /// write into a new box.
+    fn rebuild_meta(&self, data: Option<&[u8]>) -> crate::Result<Option<Vec<u8>>> {
+        let mut edits = self
+            .items
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.clone(), true))
+            .collect::<Vec<_>>();
+        if let Some(Some(gps)) = &self.gps {
+            edits.push((LOCATION_KEY, Some(gps.clone()), true));
+        }
+        if let Some(time) = self.creation_time {
+            let text = time.format("%Y-%m-%dT%H:%M:%S%z").to_string();
+            edits.push((CREATIONDATE_KEY, Some(text), false));
+        }
+
+        let (prefix, children, mut entries) = match data {
+            Some(data) => parse_meta(data)?,
+            None if edits.iter().any(|x| x.1.is_some()) => (Vec::new(), Vec::new(), Vec::new()),
+            None => return Ok(None),
+        };
+        let has = |box_type: &[u8; 4]| children.iter().any(|x| &x.0 == box_type);
+        if has(b"ilst") && !has(b"keys") {
+            return Err(Error::WriteFailed(
+                "moov/meta box without keys is not supported".into(),
+            ));
+        }
+        let has_handler = has(b"hdlr");
+
+        for (key, value, add) in edits {
+            let pos = entries.iter().position(|x| x.0 == key.as_bytes());
+            match (pos, value) {
+                (Some(pos), Some(value)) => entries[pos].1 = text_item(&value)?,
+                (None, Some(value)) if add => entries.push((key.into(), text_item(&value)?)),
+                (Some(pos), None) => {
+                    entries.remove(pos);
+                }
+                _ => (),
+            }
+        }
+
+        // Items of `ilst` are identified by the 1-based indices of their keys
+        let mut keys = vec![0; 4];
+        keys.extend((entries.len() as u32).to_be_bytes());
+        let mut ilst = Vec::new();
+        for (i, (key, item)) in entries.iter().enumerate() {
+            keys.extend((key.len() as u32 + 8).to_be_bytes());
+            keys.extend_from_slice(b"mdta");
+            keys.extend_from_slice(key);
+            ilst.extend(new_box(&(i as u32 + 1).to_be_bytes(), item)?);
+        }
+        let (keys, ilst) = (new_box(b"keys", &keys)?, new_box(b"ilst", &ilst)?);
+
+        let mut body = prefix;
+        let mut replaced = false;
+        for (box_type, bytes) in children {
+            match &box_type {
+                b"keys" => {
+                    body.extend_from_slice(&keys);
+                    replaced = true;
+                }
+                b"ilst" => body.extend_from_slice(&ilst),
+                b"free" => (),
+                _ => body.extend(bytes),
+            }
+        }
+        if !replaced {
+            if !has_handler {
+                body.extend(mdta_handler()?);
+            }
+            body.extend(keys);
+            body.extend(ilst);
+        }
+        new_box(b"meta", &body).map(Some)
+    }
+
+    /// Rebuilds the `moov/udta` box (`data`, or a new one if it's `None`)
+    /// with the `©xyz` & `©day` atoms updated. Returns `None` if there is
+    /// nothing to write into a new box.
+    fn rebuild_udta(&self, data: Option<&[u8]>) -> crate::Result<Option<Vec<u8>>> {
+        let gps = self.gps.clone().flatten();
+        if data.is_none() && gps.is_none() {
+            return Ok(None);
+        }
+
+        let mut body = Vec::new();
+        if let Some(data) = data {
+            let udta = parse_boxes(data, 0..data.len())?;
+            let udta = udta.first().ok_or("invalid udta box")?;
+            for child in parse_boxes(data, udta.body())? {
+                let bytes = &data[child.range.clone()]; // Safe-slice
+                match (&child.box_type, self.creation_time) {
+                    (b"\xa9day", Some(time)) => {
+                        // Keep the language code
+                        let language = data
+                            .get(child.body().start + 2..child.body().start + 4)
+                            .map_or(XYZ_LANGUAGE, |x| x.try_into().unwrap());
+                        let text = time.format("%Y-%m-%dT%H:%M:%S%z").to_string();
+                        body.extend(new_box(b"\xa9day", &udta_text(&text, language)?)?);
+                    }
+                    // Removed by `strip_location_boxes` already if the GPS
+                    // info is changed
+                    (b"free", _) => (),
+                    _ => body.extend_from_slice(bytes),
+                }
+            }
+        }
+        if let Some(gps) = gps {
+            body.extend(new_box(b"\xa9xyz", &udta_text(&gps, XYZ_LANGUAGE)?)?);
+        }
+        new_box(b"udta", &body).map(Some)
+    }
+}
+
+/// A key & the body of its `ilst` item.
+type MetaEntry = (Vec<u8>, Vec<u8>);
+/// The full box header (if any), the children & the entries of a `meta` box.
+type ParsedMeta = (Vec<u8>, Vec<([u8; 4], Vec<u8>)>, Vec<MetaEntry>);
+
+/// Parses a QuickTime `meta` box, returns the full box header (if any), its
+/// children, and the key/item pairs, the items are the bodies of the `ilst`
+/// item boxes.
+fn parse_meta(data: &[u8]) -> crate::Result<ParsedMeta> {
+    let meta = parse_boxes(data, 0..data.len())?;
+    let meta = meta.first().ok_or("invalid meta box")?;
+    let range = meta.meta_children(data)?;
+    let prefix = data[meta.body().start..range.start].to_vec(); // Safe-slice
+    let children = parse_boxes(data, range)?;
+
+    let mut keys = Vec::new();
+    if let Some(bbox) = children.iter().find(|x| &x.box_type == b"keys") {
+        // Full box, then the entry count. Entries of `keys` are: size (4
+        // bytes), namespace (4 bytes), then the key name
+        let range = bbox.body();
+        let mut pos = range.start + 8;
+        while pos + 8 <= range.end {
+            let size = u32::from_be_bytes(data[pos..pos + 4].try_into().unwrap()) as usize; // Safe-slice
+            if size < 8 || pos + size > range.end {
+                return Err("invalid keys box".into());
+            }
+            keys.push(data[pos + 8..pos + size].to_vec()); // Safe-slice
+            pos += size;
+        }
+    }
+    let mut items = Vec::new();
+    if let Some(bbox) = children.iter().find(|x| &x.box_type == b"ilst") {
+        for item in parse_boxes(data, bbox.body())? {
+            items.push((u32::from_be_bytes(item.box_type), item.body()));
+        }
+    }
+
+    // Keys without items are dropped, since readers pair keys & items in
+    // order
+    let entries = keys
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, key)| {
+            let (_, body) = items.iter().find(|x| x.0 == i as u32 + 1)?;
+            Some((key, data[body.clone()].to_vec())) // Safe-slice
+        })
+        .collect();
+    let children = children
+        .into_iter()
+        .map(|x| (x.box_type, data[x.range].to_vec())) // Safe-slice
+        .collect();
+    Ok((prefix, children, entries))
+}
+
+/// Patches the track metadata of a MP4/QuickTime file in place for
+/// [`patch_exif`](crate::patch_exif), only the `moov` box is read, and only
+/// the changed bytes of it are written.
+pub(crate) fn patch_track<F: Read + Write + Seek>(
+    file: &mut F,
+    tag: ExifTag,
+    value: &EntryValue,
+) -> crate::Result<()> {
+    let moov = find_top_level_box(file, b"moov")?.ok_or("moov box not found")?;
+    let data = read_at(file, moov.start, (moov.end - moov.start) as usize)?;
+    let mut patched = data.clone();
+    let range = 0..data.len();
+    let invalid = || Error::WriteFailed(format!("invalid value for {tag}: {value}").into());
+
+    let found = match tag {
+        ExifTag::CreateDate | ExifTag::ModifyDate => {
+            let time = value.as_time().ok_or_else(invalid)?;
+            let secs =
+                u64::try_from(time.timestamp() + MAC_EPOCH_OFFSET).map_err(|_| out_of_range())?;
+            let field = if tag == ExifTag::CreateDate { 0 } else { 1 };
+            set_header_times(&mut patched, range.clone(), field, secs, 0)?;
+            if tag == ExifTag::CreateDate {
+                // Both formats are used in the wild, e.g. "+08:00" & "+0800"
+                let texts = ["%Y-%m-%dT%H:%M:%S%:z", "%Y-%m-%dT%H:%M:%S%z"]
+                    .map(|x| time.format(x).to_string());
+                patch_meta_text(&mut patched, range, CREATIONDATE_KEY, &texts, tag)?;
+            }
+            true
+        }
+        ExifTag::Make | ExifTag::Model | ExifTag::Software => {
+            let key = match tag {
+                ExifTag::Make => "com.apple.quicktime.make",
+                ExifTag::Model => "com.apple.quicktime.model",
+                _ => "com.apple.quicktime.software",
+            };
+            let text = value.as_str().ok_or_else(invalid)?;
+            patch_meta_text(&mut patched, range, key, &[text], tag)?
+        }
+        ExifTag::GPSInfo => {
+            let text = value.as_str().ok_or_else(invalid)?;
+            let xyz = patch_xyz(&mut patched, range.clone(), text, tag, 0)?;
+            patch_meta_text(&mut patched, range, LOCATION_KEY, &[text], tag)? || xyz
+        }
+        _ => {
+            return Err(Error::WriteFailed(
+                format!("patching {tag} of MP4/QuickTime files is not supported").into(),
+            ))
+        }
+    };
+    if !found {
+        return Err(Error::WriteFailed(format!("{tag} not found").into()));
+    }
+
+    let changed = |(a, b): (&u8, &u8)| a != b;
+    let Some(start) = data.iter().zip(&patched).position(changed) else {
+        return Ok(());
+    };
+    let end = data.len()
+        - data
+            .iter()
+            .rev()
+            .zip(patched.iter().rev())
+            .position(changed)
+            .unwrap();
+    file.seek(SeekFrom::Start(moov.start + start as u64))?;
+    file.write_all(&patched[start..end])?; // Safe-slice
+    file.flush()?;
+    Ok(())
+}
+
+fn not_fit(tag: ExifTag) -> Error {
+    Error::WriteFailed(format!("the value doesn't fit in the existing slot of {tag}").into())
+}
+
+/// Sets the text value of the item `key` of the QuickTime metadata
+/// (`moov/meta`) in place, i.e. the first one of `texts` of the same size as
+/// the existing value. Returns false if the item doesn't exist.
+fn patch_meta_text(
+    data: &mut [u8],
+    range: Range<usize>,
+    key: &str,
+    texts: &[impl AsRef<str>],
+    tag: ExifTag,
+) -> crate::Result<bool> {
+    let moov = find_box(data, range, b"moov")?.ok_or("moov box not found")?;
+    let Some(meta) = find_box(data, moov.body(), b"meta")? else {
+        return Ok(false);
+    };
+    let children = meta.meta_children(data)?;
+    let (Some(keys), Some(ilst)) = (
+        find_box(data, children.clone(), b"keys")?,
+        find_box(data, children, b"ilst")?,
+    ) else {
+        return Ok(false);
+    };
+
+    // Full box, then the entry count
+    let keys_body = keys.body();
+    let mut index = None;
+    let mut pos = keys_body.start + 8;
+    for i in 1.. {
+        if pos + 8 > keys_body.end {
+            break;
+        }
+        let size = u32::from_be_bytes(data[pos..pos + 4].try_into().unwrap()) as usize; // Safe-slice
+        if size < 8 || pos + size > keys_body.end {
+            return Err("invalid keys box".into());
+        }
+        if &data[pos + 8..pos + size] == key.as_bytes() {
+            index = Some(i as u32);
+            break;
+        }
+        pos += size;
+    }
+    let Some(index) = index else {
+        return Ok(false);
+    };
+
+    // Items of `ilst` are identified by the 1-based indices of their keys,
+    // the value follows the type indicator & locale of the `data` box
+    let Some(item) = parse_boxes(data, ilst.body())?
+        .into_iter()
+        .find(|x| u32::from_be_bytes(x.box_type) == index)
+    else {
+        return Ok(false);
+    };
+    let value = find_box(data, item.body(), b"data")?
+        .map(|x| x.body().start + 8..x.range.end)
+        .filter(|x| x.start <= x.end)
+        .ok_or("invalid metadata item")?;
+    let text = texts
+        .iter()
+        .map(|x| x.as_ref())
+        .find(|x| x.len() == value.len())
+        .ok_or_else(|| not_fit(tag))?;
+    data[value].copy_from_slice(text.as_bytes()); // Safe-slice
+    Ok(true)
+}
+
+/// Sets the text of the `©xyz` atoms in place, the new text must have the
+/// same size. Returns false if there is no such atom.
+fn patch_xyz(
+    data: &mut [u8],
+    range: Range<usize>,
+    text: &str,
+    tag: ExifTag,
+    depth: usize,
+) -> crate::Result<bool> {
+    let mut found = false;
+    for bbox in parse_boxes(data, range)? {
+        match &bbox.box_type {
+            b"\xa9xyz" => {
+                // The text size (2 bytes), the language code (2 bytes), then
+                // the text
+                let body = bbox.body();
+                let size = data
+                    .get(body.start..body.start + 2)
+                    .map(|x| u16::from_be_bytes([x[0], x[1]]) as usize)
+                    .filter(|x| body.start + 4 + x <= body.end)
+                    .ok_or("invalid user data text")?;
+                if size != text.len() {
+                    return Err(not_fit(tag));
+                }
+                data[body.start + 4..body.start + 4 + size].copy_from_slice(text.as_bytes()); // Safe-slice
+                found = true;
+            }
+            b"moov" | b"trak" | b"udta" if depth < MAX_BOX_DEPTH => {
+                found |= patch_xyz(data, bbox.body(), text, tag, depth + 1)?;
+            }
+            _ => (),
+        }
+    }
+    Ok(found)
+}
+
+/// Sets the creation (`field` 0) or modification (`field` 1) times of
+/// `mvhd`, `tkhd` & `mdhd` boxes in place.
+fn set_header_times(
+    data: &mut [u8],
+    range: Range<usize>,
+    field: usize,
+    secs: u64,
+    depth: usize,
+) -> crate::Result<()> {
+    for bbox in parse_boxes(data, range)? {
+        match &bbox.box_type {
+            b"mvhd" | b"tkhd" | b"mdhd" => {
+                let body = bbox.body();
+                // Full box, 64-bit times for version 1
+                let size = match data.get(body.start) {
+                    Some(1) => 8,
+                    Some(_) => 4,
+                    None => continue,
+                };
+                if size == 4 && secs > u32::MAX as u64 {
+                    return Err(out_of_range());
+                }
+                let pos = body.start + 4 + field * size;
+                if let Some(bytes) = data.get_mut(pos..pos + size) {
+                    bytes.copy_from_slice(&secs.to_be_bytes()[8 - size..]); // Safe-slice
+                }
+            }
+            b"moov" | b"trak" | b"mdia" if depth < MAX_BOX_DEPTH => {
+                set_header_times(data, bbox.body(), field, secs, depth + 1)?
+            }
+            _ => (),
+        }
+    }
+    Ok(())
+}
+
+fn out_of_range() -> Error {
+    Error::WriteFailed("time is out of range".into())
+}
+
+fn new_box(box_type: &[u8; 4], body: &[u8]) -> crate::Result<Vec<u8>> {
+    let size =
+        u32::try_from(body.len() + 8).map_err(|_| Error::WriteFailed("box is too big".into()))?;
+    let mut bytes = size.to_be_bytes().to_vec();
+    bytes.extend_from_slice(box_type);
+    bytes.extend_from_slice(body);
+    Ok(bytes)
+}
+
+/// The body of an `ilst` item with a UTF-8 value, i.e. a `data` box: type
+/// indicator (4 bytes), locale (4 bytes), then the value.
+fn text_item(text: &str) -> crate::Result<Vec<u8>> {
+    let mut body = 1_u32.to_be_bytes().to_vec();
+    body.extend([0; 4]);
+    body.extend_from_slice(text.as_bytes());
+    new_box(b"data", &body)
+}
+
+/// QuickTime user data text, i.e. the text size (2 bytes), the language code
+/// (2 bytes), then the text.
+fn udta_text(text: &str, language: [u8; 2]) -> crate::Result<Vec<u8>> {
+    let size = u16::try_from(text.len())
+        .map_err(|_| Error::WriteFailed("user data text is too long".into()))?;
+    let mut body = size.to_be_bytes().to_vec();
+    body.extend(language);
+    body.extend_from_slice(text.as_bytes());
+    Ok(body)
+}
+
+/// The `hdlr` box of QuickTime metadata: version & flags, predefined,
+/// handler type `mdta`, reserved (12 bytes), then an empty name.
+fn mdta_handler() -> crate::Result<Vec<u8>> {
+    let mut body = vec![0; 8];
+    body.extend_from_slice(b"mdta");
+    body.extend([0; 13]);
+    new_box(b"hdlr", &body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        testkit::read_sample, EntryValue, MediaParser, MediaSource, TrackInfo, TrackInfoTag,
+    };
+    use test_case::test_case;
+
+    fn parse_track(data: Vec<u8>) -> TrackInfo {
+        let ms = MediaSource::seekable(Cursor::new(data)).unwrap();
+        MediaParser::new().parse(ms).unwrap()
+    }
+
+    fn time(s: &str) -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339(s).unwrap()
+    }
+
+    #[test_case("meta.mov")]
+    #[test_case("meta.mp4")]
+    #[test_case("3gp_640x360.3gp")]
+    fn write_track(path: &str) {
+        let data = read_sample(path).unwrap();
+        let mut writer = TrackWriter::new();
+        writer
+            .set_gps_decimal(-33.8568, 151.2153, Some(-3.5))
+            .unwrap()
+            .set_creation_time(time("2024-05-06T07:08:09+08:00"))
+            .set_item("com.apple.quicktime.make", "nom-exif")
+            .set_item("com.apple.quicktime.model", "writer");
+        let mut output = Vec::new();
+        writer.write(Cursor::new(&data), &mut output).unwrap();
+
+        // The same as writing in place
+        let mut file = Cursor::new(data.clone());
+        writer.write_in_place(&mut file).unwrap();
+        assert_eq!(file.into_inner(), output);
+
+        let info = parse_track(output);
+        assert_eq!(
+            info.get(TrackInfoTag::GpsIso6709),
+            Some(&"-33.8568+151.2153-003.500/".into())
+        );
+        assert_eq!(
+            info.get(TrackInfoTag::CreateDate),
+            Some(&EntryValue::Time(time("2024-05-05T23:08:09+00:00")))
+        );
+        assert_eq!(info.get(TrackInfoTag::Make), Some(&"nom-exif".into()));
+        assert_eq!(info.get(TrackInfoTag::Model), Some(&"writer".into()));
+        let original = parse_track(data);
+        assert_eq!(
+            info.get(TrackInfoTag::DurationMs),
+            original.get(TrackInfoTag::DurationMs)
+        );
+
+        // Removing
+        let mut writer = TrackWriter::new();
+        writer.remove_gps().remove_item("com.apple.quicktime.make");
+        let mut output = Vec::new();
+        writer
+            .write(Cursor::new(with_items(path)), &mut output)
+            .unwrap();
+        let info = parse_track(output);
+        assert_eq!(info.get(TrackInfoTag::GpsIso6709), None);
+        assert_eq!(info.get(TrackInfoTag::Make), None);
+        assert_eq!(info.get(TrackInfoTag::Model), Some(&"writer".into()));
+    }
+
+    /// Returns the sample with GPS info & the make/model items.
+    fn with_items(path: &str) -> Vec<u8> {
+        let mut writer = TrackWriter::new();
+        writer
+            .set_gps_decimal(48.8577, 2.295, None)
+            .unwrap()
+            .set_item("com.apple.quicktime.make", "nom-exif")
+            .set_item("com.apple.quicktime.model", "writer");
+        let mut output = Vec::new();
+        writer
+            .write(Cursor::new(read_sample(path).unwrap()), &mut output)
+            .unwrap();
+        output
+    }
+
+    /// A fast start file, i.e. `ftyp`, `moov` (with `mvhd` of meta.mp4),
+    /// then `free` boxes of `free_size` & `mdat`.
+    fn fast_start(free_size: Option<usize>) -> (Vec<u8>, Range<usize>) {
+        let sample = read_sample("meta.mp4").unwrap();
+        let moov = find_box(&sample, 0..sample.len(), b"moov")
+            .unwrap()
+            .unwrap();
+        let mvhd = find_box(&sample, moov.body(), b"mvhd").unwrap().unwrap();
+
+        let mut data = new_box(b"ftyp", b"isom\0\0\x02\0isomiso2mp41").unwrap();
+        data.extend(new_box(b"moov", &sample[mvhd.range]).unwrap());
+        if let Some(size) = free_size {
+            data.extend(new_box(b"free", &vec![0; size - 8]).unwrap());
+        }
+        let start = data.len();
+        data.extend(new_box(b"mdat", &[0xab; 1000]).unwrap());
+        (data.clone(), start..data.len())
+    }
+
+    #[test_case(None, false; "relocated")]
+    #[test_case(Some(8), false; "free box is too small")]
+    #[test_case(Some(1024), true; "in free space")]
+    fn write_fast_start(free_size: Option<usize>, in_place: bool) {
+        let (data, mdat) = fast_start(free_size);
+        let mut writer = TrackWriter::new();
+        writer.set_gps_decimal(48.8577, 2.295, None).unwrap();
+        let mut file = Cursor::new(data.clone());
+        writer.write_in_place(&mut file).unwrap();
+        let output = file.into_inner();
+
+        // The media data is not moved
+        assert_eq!(output[mdat.clone()], data[mdat.clone()]);
+        if in_place {
+            assert_eq!(output.len(), data.len());
+        } else {
+            assert!(output.len() > data.len());
+            let boxes = parse_boxes(&output, 0..output.len()).unwrap();
+            let types = boxes.iter().map(|x| &x.box_type).collect::<Vec<_>>();
+            assert_eq!(types[1], b"free");
+            assert_eq!(types.last().unwrap(), &b"moov");
+        }
+        let info = parse_track(output);
+        assert_eq!(
+            info.get(TrackInfoTag::GpsIso6709),
+            Some(&"+48.8577+002.2950/".into())
+        );
+    }
+
+    #[test]
+    fn write_track_failed() {
+        let writer = TrackWriter::new();
+        let data = read_sample("exif.jpg").unwrap();
+        assert!(writer.write(Cursor::new(data), &mut Vec::new()).is_err());
+
+        // mvhd of meta.mp4 is version 0, i.e. 32-bit times
+        let mut writer = TrackWriter::new();
+        writer.set_creation_time(time("2100-01-01T00:00:00+00:00"));
+        let data = read_sample("meta.mp4").unwrap();
+        assert!(writer.write(Cursor::new(data), &mut Vec::new()).is_err());
+    }
+}
@@ -5,14 +5,20 @@ use chrono::{
 };
 
 use nom::{multi::many_m_n, number::Endianness};
-#[cfg(feature = "json_dump")]
-use serde::{Deserialize, Serialize, Serializer};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use crate::ExifTag;
+use crate::{exif::print_conv, ExifTag};
 
 /// Represent a parsed entry value.
+///
+/// With the `serde` feature, values are serialized in the externally tagged
+/// form, e.g. `{"URational":[1,250]}`, and times in RFC 3339, so that they
+/// can be deserialized losslessly. Use `Display` for human readable text.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub enum EntryValue {
     Text(String),
     URational(URational),
@@ -37,8 +43,14 @@ pub enum EntryValue {
     URationalArray(Vec<URational>),
     IRationalArray(Vec<IRational>),
 
+    U8Array(Vec<u8>),
     U16Array(Vec<u16>),
     U32Array(Vec<u32>),
+    I8Array(Vec<i8>),
+    I16Array(Vec<i16>),
+    I32Array(Vec<i32>),
+    F32Array(Vec<f32>),
+    F64Array(Vec<f64>),
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -57,9 +69,6 @@ pub(crate) enum ParseEntryError {
 
     #[error("data is invalid: {0}")]
     InvalidData(String),
-
-    #[error("data format is unsupported (please file a bug): {0}")]
-    Unsupported(String),
 }
 
 impl From<chrono::ParseError> for ParseEntryError {
@@ -70,7 +79,7 @@ impl From<chrono::ParseError> for ParseEntryError {
 
 use ParseEntryError as Error;
 
-impl EntryData<'_> {
+impl<'a> EntryData<'a> {
     // Ensure that the returned Vec is not empty.
     fn try_as_rationals<T: TryFromBytes>(&self) -> Result<Vec<Rational<T>>, Error> {
         if self.components_num == 0 {
@@ -84,6 +93,19 @@ impl EntryData<'_> {
         }
         Ok(vec)
     }
+
+    /// Parses all the components with `parser`, `name` is used in the error
+    /// message.
+    fn try_as_array<T>(
+        &self,
+        parser: impl FnMut(&'a [u8]) -> nom::IResult<&'a [u8], T>,
+        name: &str,
+    ) -> Result<Vec<T>, Error> {
+        let n = self.components_num as usize;
+        let (_, v) = many_m_n(n, n, parser)(self.data)
+            .map_err(|e| ParseEntryError::InvalidData(format!("parse {name} error: {e:?}")))?;
+        Ok(v)
+    }
 }
 
 impl EntryValue {
@@ -161,41 +183,29 @@ impl EntryValue {
         match data_format {
             DataFormat::U8 => match components_num {
                 1 => Ok(Self::U8(data[0])),
-                x => Err(Error::Unsupported(format!(
-                    "usigned byte with {x} components"
-                ))),
+                _ => Ok(Self::U8Array(data.to_vec())),
             },
-            DataFormat::Text => Ok(EntryValue::Text(
+            DataFormat::Text | DataFormat::Utf8 => Ok(EntryValue::Text(
                 get_cstr(data).map_err(|e| Error::InvalidData(e.to_string()))?,
             )),
             DataFormat::U16 => {
                 if components_num == 1 {
                     Ok(Self::U16(u16::try_from_bytes(data, endian)?))
                 } else {
-                    let (_, v) = many_m_n::<_, _, nom::error::Error<_>, _>(
-                        components_num as usize,
-                        components_num as usize,
+                    Ok(Self::U16Array(entry.try_as_array(
                         nom::number::complete::u16(endian),
-                    )(data)
-                    .map_err(|e| {
-                        ParseEntryError::InvalidData(format!("parse U16Array error: {e:?}"))
-                    })?;
-                    Ok(Self::U16Array(v))
+                        "U16Array",
+                    )?))
                 }
             }
             DataFormat::U32 => {
                 if components_num == 1 {
                     Ok(Self::U32(u32::try_from_bytes(data, endian)?))
                 } else {
-                    let (_, v) = many_m_n::<_, _, nom::error::Error<_>, _>(
-                        components_num as usize,
-                        components_num as usize,
+                    Ok(Self::U32Array(entry.try_as_array(
                         nom::number::complete::u32(endian),
-                    )(data)
-                    .map_err(|e| {
-                        ParseEntryError::InvalidData(format!("parse U32Array error: {e:?}"))
-                    })?;
-                    Ok(Self::U32Array(v))
+                        "U32Array",
+                    )?))
                 }
             }
             DataFormat::URational => {
@@ -208,23 +218,31 @@ impl EntryValue {
             }
             DataFormat::I8 => match components_num {
                 1 => Ok(Self::I8(data[0] as i8)),
-                x => Err(Error::Unsupported(format!(
-                    "signed byte with {x} components"
-                ))),
+                _ => Ok(Self::I8Array(
+                    entry.try_as_array(nom::number::complete::i8, "I8Array")?,
+                )),
             },
             DataFormat::Undefined => Ok(Self::Undefined(data.to_vec())),
-            DataFormat::I16 => match components_num {
-                1 => Ok(Self::I16(i16::try_from_bytes(data, endian)?)),
-                x => Err(Error::Unsupported(format!(
-                    "signed short with {x} components"
-                ))),
-            },
-            DataFormat::I32 => match components_num {
-                1 => Ok(Self::I32(i32::try_from_bytes(data, endian)?)),
-                x => Err(Error::Unsupported(format!(
-                    "signed long with {x} components"
-                ))),
-            },
+            DataFormat::I16 => {
+                if components_num == 1 {
+                    Ok(Self::I16(i16::try_from_bytes(data, endian)?))
+                } else {
+                    Ok(Self::I16Array(entry.try_as_array(
+                        nom::number::complete::i16(endian),
+                        "I16Array",
+                    )?))
+                }
+            }
+            DataFormat::I32 => {
+                if components_num == 1 {
+                    Ok(Self::I32(i32::try_from_bytes(data, endian)?))
+                } else {
+                    Ok(Self::I32Array(entry.try_as_array(
+                        nom::number::complete::i32(endian),
+                        "I32Array",
+                    )?))
+                }
+            }
             DataFormat::IRational => {
                 let rationals = entry.try_as_rationals::<i32>()?;
                 if rationals.len() == 1 {
@@ -233,21 +251,33 @@ impl EntryValue {
                     Ok(Self::IRationalArray(rationals))
                 }
             }
-            DataFormat::F32 => match components_num {
-                1 => Ok(Self::F32(f32::try_from_bytes(data, endian)?)),
-                x => Err(Error::Unsupported(format!("float with {x} components"))),
-            },
-            DataFormat::F64 => match components_num {
-                1 => Ok(Self::F64(f64::try_from_bytes(data, endian)?)),
-                x => Err(Error::Unsupported(format!("double with {x} components"))),
-            },
+            DataFormat::F32 => {
+                if components_num == 1 {
+                    Ok(Self::F32(f32::try_from_bytes(data, endian)?))
+                } else {
+                    Ok(Self::F32Array(entry.try_as_array(
+                        nom::number::complete::f32(endian),
+                        "F32Array",
+                    )?))
+                }
+            }
+            DataFormat::F64 => {
+                if components_num == 1 {
+                    Ok(Self::F64(f64::try_from_bytes(data, endian)?))
+                } else {
+                    Ok(Self::F64Array(entry.try_as_array(
+                        nom::number::complete::f64(endian),
+                        "F64Array",
+                    )?))
+                }
+            }
         }
     }
 
     fn variant_default(data_format: DataFormat) -> EntryValue {
         match data_format {
             DataFormat::U8 => Self::U8(0),
-            DataFormat::Text => Self::Text(String::default()),
+            DataFormat::Text | DataFormat::Utf8 => Self::Text(String::default()),
             DataFormat::U16 => Self::U16(0),
             DataFormat::U32 => Self::U32(0),
             DataFormat::URational => Self::URational(URational::default()),
@@ -275,6 +305,35 @@ impl EntryValue {
         }
     }
 
+    /// Returns a [`EntryValue::Time`] value as a [`jiff::Timestamp`].
+    #[cfg(feature = "jiff")]
+    pub fn as_timestamp(&self) -> Option<jiff::Timestamp> {
+        let t = self.as_time()?;
+        jiff::Timestamp::new(t.timestamp(), t.timestamp_subsec_nanos() as i32).ok()
+    }
+
+    /// Returns a [`EntryValue::Time`] value as a [`jiff::Zoned`], in a fixed
+    /// offset time zone of the value's UTC offset.
+    ///
+    /// ```rust
+    /// use nom_exif::*;
+    ///
+    /// let ms = MediaSource::file_path("./testdata/meta.mov").unwrap();
+    /// let info: TrackInfo = MediaParser::new().parse(ms).unwrap();
+    /// let t = info.get(TrackInfoTag::CreateDate).and_then(|x| x.as_zoned()).unwrap();
+    /// assert_eq!(t.datetime().to_string(), "2024-02-02T08:09:57");
+    /// assert_eq!(t.offset().seconds(), 0);
+    /// ```
+    #[cfg(feature = "jiff")]
+    pub fn as_zoned(&self) -> Option<jiff::Zoned> {
+        let t = self.as_time()?;
+        let offset = jiff::tz::Offset::from_seconds(t.offset().local_minus_utc()).ok()?;
+        Some(
+            self.as_timestamp()?
+                .to_zoned(jiff::tz::TimeZone::fixed(offset)),
+        )
+    }
+
     pub fn as_u8(&self) -> Option<u8> {
         match self {
             EntryValue::U8(v) => Some(*v),
@@ -324,6 +383,93 @@ impl EntryValue {
         }
     }
 
+    pub fn as_f32(&self) -> Option<f32> {
+        match self {
+            EntryValue::F32(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            EntryValue::F64(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64_array(&self) -> Option<&[f64]> {
+        if let EntryValue::F64Array(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_u8_array(&self) -> Option<&[u8]> {
+        if let EntryValue::U8Array(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_u16_array(&self) -> Option<&[u16]> {
+        if let EntryValue::U16Array(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_u32_array(&self) -> Option<&[u32]> {
+        if let EntryValue::U32Array(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_i8_array(&self) -> Option<&[i8]> {
+        if let EntryValue::I8Array(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_i16_array(&self) -> Option<&[i16]> {
+        if let EntryValue::I16Array(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_i32_array(&self) -> Option<&[i32]> {
+        if let EntryValue::I32Array(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_f32_array(&self) -> Option<&[f32]> {
+        if let EntryValue::F32Array(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the raw bytes of an [`EntryValue::Undefined`] value.
+    pub fn as_undefined(&self) -> Option<&[u8]> {
+        if let EntryValue::Undefined(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+
     pub fn as_urational(&self) -> Option<URational> {
         if let EntryValue::URational(v) = self {
             Some(*v)
@@ -369,8 +515,15 @@ impl EntryValue {
 /// |-----------------+---------------+---------------+----------------+-----------------+-------------------+--------------|
 /// | Format          |     undefined |  signed short |    signed long | signed rational |      single float | double float |
 /// | Bytes/component |             1 |             2 |              4 |               8 |                 4 |            8 |
+///
+/// | Value           |           129 |
+/// |-----------------+---------------|
+/// | Format          |  UTF-8 string |
+/// | Bytes/component |             1 |
 /// ```
 ///
+/// UTF-8 (129) is introduced by Exif 3.0.
+///
 /// See: [Exif](https://www.media.mit.edu/pia/Research/deepview/exif.html).
 #[repr(u16)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -388,12 +541,13 @@ pub(crate) enum DataFormat {
     IRational = 10,
     F32 = 11,
     F64 = 12,
+    Utf8 = 129,
 }
 
 impl DataFormat {
     pub fn component_size(&self) -> usize {
         match self {
-            Self::U8 | Self::I8 | Self::Text | Self::Undefined => 1,
+            Self::U8 | Self::I8 | Self::Text | Self::Utf8 | Self::Undefined => 1,
             Self::U16 | Self::I16 => 2,
             Self::U32 | Self::I32 | Self::F32 => 4,
             Self::URational | Self::IRational | Self::F64 => 8,
@@ -404,6 +558,14 @@ impl DataFormat {
 impl TryFrom<u16> for DataFormat {
     type Error = Error;
     fn try_from(v: u16) -> Result<Self, Self::Error> {
+        // IFD (13) is an unsigned long holding the offset of a sub-IFD, see
+        // TIFF Technical Note 1.
+        if v == 13 {
+            return Ok(Self::U32);
+        }
+        if v == Self::Utf8 as u16 {
+            return Ok(Self::Utf8);
+        }
         if v >= Self::U8 as u16 && v <= Self::F64 as u16 {
             Ok(unsafe { std::mem::transmute::<u16, Self>(v) })
         } else {
@@ -412,16 +574,6 @@ impl TryFrom<u16> for DataFormat {
     }
 }
 
-#[cfg(feature = "json_dump")]
-impl Serialize for EntryValue {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        serializer.serialize_str(&self.to_string())
-    }
-}
-
 impl Display for EntryValue {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -470,6 +622,12 @@ impl Display for EntryValue {
             }
             EntryValue::U32Array(v) => array_to_string("U32Array", v, f),
             EntryValue::U16Array(v) => array_to_string("U16Array", v, f),
+            EntryValue::U8Array(v) => array_to_string("U8Array", v, f),
+            EntryValue::I8Array(v) => array_to_string("I8Array", v, f),
+            EntryValue::I16Array(v) => array_to_string("I16Array", v, f),
+            EntryValue::I32Array(v) => array_to_string("I32Array", v, f),
+            EntryValue::F32Array(v) => array_to_string("F32Array", v, f),
+            EntryValue::F64Array(v) => array_to_string("F64Array", v, f),
         }
     }
 }
@@ -608,14 +766,14 @@ impl From<(i32, i32)> for EntryValue {
     }
 }
 
-// #[cfg_attr(feature = "json_dump", derive(Serialize, Deserialize))]
+// #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 // #[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
 // pub struct URational(pub u32, pub u32);
 
 pub type URational = Rational<u32>;
 pub type IRational = Rational<i32>;
 
-#[cfg_attr(feature = "json_dump", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
 pub struct Rational<T>(pub T, pub T);
 
@@ -626,6 +784,99 @@ where
     pub fn as_float(&self) -> f64 {
         std::convert::Into::<f64>::into(self.0) / std::convert::Into::<f64>::into(self.1)
     }
+
+    /// Returns the value as `f64`, or `None` if the denominator is 0.
+    pub fn as_f64(&self) -> Option<f64> {
+        (self.1.into() != 0.0).then(|| self.as_float())
+    }
+
+    /// Formats the value as an exposure time in seconds, e.g. "1/250 s",
+    /// "0.5 s", "2 s".
+    ///
+    /// ```rust
+    /// use nom_exif::*;
+    ///
+    /// assert_eq!(URational::from((10, 2500)).format_exposure_time().unwrap(), "1/250 s");
+    /// assert_eq!(URational::from((5, 10)).format_exposure_time().unwrap(), "0.5 s");
+    /// ```
+    pub fn format_exposure_time(&self) -> Option<String> {
+        let secs = self.as_f64()?;
+        Some(format!("{} s", print_conv::exposure_time(secs)))
+    }
+
+    /// Formats the value as an F number, e.g. "f/1.8".
+    ///
+    /// ```rust
+    /// use nom_exif::*;
+    ///
+    /// assert_eq!(URational::from((18, 10)).format_f_number().unwrap(), "f/1.8");
+    /// ```
+    pub fn format_f_number(&self) -> Option<String> {
+        let x = self.as_f64()?;
+        Some(format!("f/{}", print_conv::f_number(x)))
+    }
+}
+
+macro_rules! impl_rational {
+    ($t:ty) => {
+        impl Rational<$t> {
+            /// Returns the value in its lowest terms, e.g. 10/2500 => 1/250,
+            /// and with a positive denominator. Values whose denominator is 0
+            /// are returned as is.
+            pub fn simplify(&self) -> Self {
+                let (mut n, mut d) = (self.0 as i64, self.1 as i64);
+                if d < 0 {
+                    (n, d) = (-n, -d);
+                }
+                let g = gcd(n.unsigned_abs(), d.unsigned_abs()) as i64;
+                if g == 0 {
+                    return *self;
+                }
+                match (<$t>::try_from(n / g), <$t>::try_from(d / g)) {
+                    (Ok(n), Ok(d)) => Self(n, d),
+                    _ => *self,
+                }
+            }
+
+            /// Compares the values of two rationals, e.g. 1/2 == 2/4, returns
+            /// `None` if any of the denominators is 0.
+            ///
+            /// Note that `==` compares the numerators & denominators instead.
+            pub fn cmp_value(&self, other: &Self) -> Option<std::cmp::Ordering> {
+                if self.1 == 0 || other.1 == 0 {
+                    return None;
+                }
+                // Widened before flipping the signs, which may not fit in
+                // the original type, e.g. i32::MIN/-1
+                let widen = |x: &Self| {
+                    let (n, d) = (x.0 as i128, x.1 as i128);
+                    if d < 0 {
+                        (-n, -d)
+                    } else {
+                        (n, d)
+                    }
+                };
+                let (a, b) = (widen(self), widen(other));
+                Some((a.0 * b.1).cmp(&(b.0 * a.1)))
+            }
+        }
+    };
+}
+
+impl_rational!(u32);
+impl_rational!(i32);
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+impl<T: Display> Display for Rational<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.0, self.1)
+    }
 }
 
 impl<T> From<(T, T)> for Rational<T>
@@ -863,7 +1114,10 @@ pub(crate) fn decode_rational<T: TryFromBytes>(
 
 #[cfg(test)]
 mod tests {
+    use std::cmp::Ordering;
+
     use chrono::{Local, NaiveDateTime, TimeZone};
+    use test_case::test_case;
 
     use super::*;
 
@@ -898,4 +1152,114 @@ mod tests {
         assert_eq!(t1, t2);
         assert!(t3 > t2);
     }
+
+    #[test]
+    fn utf8_entry() {
+        assert_eq!(DataFormat::try_from(129).unwrap(), DataFormat::Utf8);
+        assert!(DataFormat::try_from(128).is_err());
+
+        let data = "Café 写真\0".as_bytes();
+        let entry = EntryData {
+            endian: Endianness::Big,
+            tag: ExifTag::ImageTitle.code(),
+            data,
+            data_format: DataFormat::Utf8,
+            components_num: data.len() as u32,
+        };
+        let value = EntryValue::parse(&entry, &None).unwrap();
+        assert_eq!(value, EntryValue::Text("Café 写真".into()));
+    }
+
+    #[test]
+    fn f64_array_entry() {
+        let data = [1.5_f64, -2.0, 30.0]
+            .iter()
+            .flat_map(|x| x.to_le_bytes())
+            .collect::<Vec<_>>();
+        let entry = EntryData {
+            endian: Endianness::Little,
+            tag: ExifTag::ModelPixelScale.code(),
+            data: &data,
+            data_format: DataFormat::F64,
+            components_num: 3,
+        };
+        let value = EntryValue::parse(&entry, &None).unwrap();
+        assert_eq!(value.as_f64_array(), Some([1.5, -2.0, 30.0].as_slice()));
+        assert_eq!(value.to_string(), "F64Array[1.5, -2, 30]");
+    }
+
+    #[test]
+    fn rational_helpers() {
+        assert_eq!(URational::from((10, 2500)).simplify(), (1, 250).into());
+        assert_eq!(URational::from((0, 5)).simplify(), (0, 1).into());
+        assert_eq!(URational::from((0, 0)).simplify(), (0, 0).into());
+        assert_eq!(IRational::from((6, -4)).simplify(), (-3, 2).into());
+        assert_eq!(
+            IRational::from((i32::MIN, -1)).simplify(),
+            (i32::MIN, -1).into()
+        );
+
+        let half = URational::from((1, 2));
+        assert_eq!(half.cmp_value(&(2, 4).into()), Some(Ordering::Equal));
+        assert_eq!(half.cmp_value(&(2, 3).into()), Some(Ordering::Less));
+        assert_eq!(half.cmp_value(&(1, 0).into()), None);
+        assert_eq!(
+            IRational::from((1, -2)).cmp_value(&(-1, 3).into()),
+            Some(Ordering::Less)
+        );
+        assert_eq!(
+            URational::from((u32::MAX, 1)).cmp_value(&(1, u32::MAX).into()),
+            Some(Ordering::Greater)
+        );
+
+        assert_eq!(half.as_f64(), Some(0.5));
+        assert_eq!(URational::from((1, 0)).as_f64(), None);
+        assert_eq!(half.to_string(), "1/2");
+        assert_eq!(<(u32, u32)>::from(half), (1, 2));
+        assert_eq!(URational::from((8, 1)).format_f_number().unwrap(), "f/8.0");
+        assert_eq!(
+            URational::from((2, 1)).format_exposure_time().unwrap(),
+            "2 s"
+        );
+    }
+
+    #[test_case((i32::MIN, -1), (i32::MAX, 1), Ordering::Greater; "min over minus one")]
+    #[test_case((i32::MIN, -1), (i32::MIN, 1), Ordering::Greater; "min signs")]
+    #[test_case((i32::MIN, 1), (i32::MAX, -1), Ordering::Less; "min & max")]
+    #[test_case((i32::MIN, i32::MIN), (1, 1), Ordering::Equal; "min over min")]
+    #[test_case((1, -3), (-1, 3), Ordering::Equal; "negative denominator")]
+    #[test_case((-1, -3), (1, 4), Ordering::Greater; "negative both")]
+    #[test_case((3, -4), (-2, -3), Ordering::Less; "negative denominators")]
+    fn rational_cmp_value(a: (i32, i32), b: (i32, i32), expect: Ordering) {
+        let (a, b) = (IRational::from(a), IRational::from(b));
+        assert_eq!(a.cmp_value(&b), Some(expect));
+        assert_eq!(b.cmp_value(&a), Some(expect.reverse()));
+    }
+
+    #[test_case(DataFormat::I8, 3, &[0xff, 0x02, 0x80], EntryValue::I8Array(vec![-1, 2, -128]))]
+    #[test_case(DataFormat::I8, 2, &[0xff, 0x02, 0, 0], EntryValue::I8Array(vec![-1, 2]); "i8 padded")]
+    #[test_case(DataFormat::I16, 2, &[0xff, 0xfe, 0x00, 0x03], EntryValue::I16Array(vec![-2, 3]))]
+    #[test_case(DataFormat::I32, 2, &[0xff, 0xff, 0xff, 0xff, 0, 0, 1, 0], EntryValue::I32Array(vec![-1, 256]))]
+    #[test_case(DataFormat::F32, 2, &[0x3f, 0xc0, 0, 0, 0xc0, 0, 0, 0], EntryValue::F32Array(vec![1.5, -2.0]))]
+    #[test_case(DataFormat::U16, 3, &[0, 8, 0, 8, 0, 8], EntryValue::U16Array(vec![8, 8, 8]))]
+    #[test_case(DataFormat::U32, 2, &[0, 0, 0, 1, 0, 0, 1, 0], EntryValue::U32Array(vec![1, 256]))]
+    fn array_entry(data_format: DataFormat, components_num: u32, data: &[u8], v: EntryValue) {
+        let entry = EntryData {
+            endian: Endianness::Big,
+            tag: 0xffff,
+            data,
+            data_format,
+            components_num,
+        };
+        assert_eq!(EntryValue::parse(&entry, &None).unwrap(), v);
+
+        if data_format.component_size() > 1 {
+            // Truncated data
+            let entry = EntryData {
+                data: &data[..data.len() - 1],
+                ..entry
+            };
+            assert!(EntryValue::parse(&entry, &None).is_err());
+        }
+    }
 }
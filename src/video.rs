@@ -3,11 +3,30 @@ use std::{
     fmt::Display,
 };
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 use crate::{
-    ebml::webm::parse_webm,
+    asf::parse_asf,
+    avi::AviParser,
+    binary,
+    dsd::{DffParser, DsfParser},
+    ebml::webm::WebmParser,
     error::ParsingError,
     file::MimeVideo,
-    mov::{extract_moov_body_from_buf, parse_mp4, parse_qt},
+    fingerprint::Fingerprint,
+    gif::GifParser,
+    id3::Mp3Parser,
+    json::JsonObject,
+    mov::{
+        extract_moov_body_from_buf, itunes_item_tag, parse_id32_metadata, parse_itunes_metadata,
+        parse_mp4, parse_qt, FragmentScanner,
+    },
+    mpegts::TsParser,
+    mxf::parse_mxf,
+    parser_core::Limits,
+    report::{pretty_value, write_group},
+    wav::WavParser,
     EntryValue, GPSInfo,
 };
 
@@ -16,6 +35,7 @@ use crate::{
 ///
 /// Different variants of `TrackInfoTag` may have different value types, please
 /// refer to the documentation of each variant.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone, PartialEq, Eq, Copy, PartialOrd, Ord, Hash)]
 #[non_exhaustive]
 pub enum TrackInfoTag {
@@ -40,18 +60,133 @@ pub enum TrackInfoTag {
     /// Its value is an `EntryValue::U32`.
     ImageHeight,
 
+    /// Clockwise rotation in degrees (90, 180 or 270) which should be applied
+    /// when presenting the video track. Its value is an `EntryValue::U32`.
+    ///
+    /// This entry is absent if the video track is not rotated.
+    Rotation,
+
+    /// Playback frame rate of the video track in frames per second, its value
+    /// is an `EntryValue::F64`.
+    FrameRate,
+
+    /// Frame rate at which the video was captured, its value is an
+    /// `EntryValue::F64`.
+    ///
+    /// This entry is present only if the capture frame rate is recorded,
+    /// e.g. for slow-motion or timelapse recordings, see
+    /// [`TrackInfo::capture_mode`].
+    CaptureFrameRate,
+
     /// Its value is an `EntryValue::Text`, location presented in ISO6709.
     ///
     /// If you need a parsed [`GPSInfo`] which provides more detailed GPS info,
     /// please use [`TrackInfo::get_gps_info`].
     GpsIso6709,
+
+    /// Video codec, its value is an `EntryValue::Text`, e.g.: the FourCC
+    /// ("MJPG", "XVID") of AVI files, or the codec name ("H.264") of MPEG-TS
+    /// & MXF files.
+    ///
+    /// Currently only provided for AVI, MPEG-TS and MXF files.
+    VideoCodec,
+
+    /// Its value is an `EntryValue::Text`, e.g.: the `Description` of
+    /// Broadcast Wave files, or `ICMT` of RIFF `INFO` lists.
+    Description,
+
+    /// Its value is an `EntryValue::Text`.
+    Title,
+
+    /// Its value is an `EntryValue::Text`.
+    Artist,
+
+    /// Its value is an `EntryValue::Text`.
+    Album,
+
+    /// MIME type of the embedded picture (e.g. the cover art of MP3 files),
+    /// its value is an `EntryValue::Text`.
+    ///
+    /// This entry is absent if there is no embedded picture.
+    Picture,
+
+    /// Audio codec, e.g.: "AAC", "AC-3". Its value is an `EntryValue::Text`.
+    ///
+    /// Currently only provided for MPEG-TS, MXF and DSD (DSF/DSDIFF) files,
+    /// "DSD" or "DST" (compressed) for the latter.
+    AudioCodec,
+
+    /// Program number of the (first) program in an MPEG-TS file, its value is
+    /// an `EntryValue::U16`.
+    ProgramNumber,
+
+    /// Format identifier of the registration descriptor in an MPEG-TS file,
+    /// e.g.: "HDMV" for AVCHD/Blu-ray streams. Its value is an
+    /// `EntryValue::Text`.
+    Registration,
+
+    /// Service provider name in the SDT of an MPEG-TS file, its value is an
+    /// `EntryValue::Text`.
+    ServiceProvider,
+
+    /// Service name in the SDT of an MPEG-TS file, its value is an
+    /// `EntryValue::Text`.
+    ServiceName,
+
+    /// Projection of the video track, e.g.: "equirectangular" for 360
+    /// videos, or "rectangular". Its value is an `EntryValue::Text`.
+    ///
+    /// Currently only provided for WebM/Matroska files which have a
+    /// `Projection` element.
+    Projection,
+
+    /// Colour primaries of the video track as defined in ITU-T H.273, e.g.:
+    /// 1 (BT.709), 9 (BT.2020). Its value is an `EntryValue::U32`.
+    ///
+    /// Currently only provided for WebM/Matroska files, and absent if it's
+    /// unspecified.
+    ColorPrimaries,
+
+    /// Transfer characteristics of the video track as defined in ITU-T
+    /// H.273, e.g.: 16 (PQ) or 18 (HLG) for HDR videos. Its value is an
+    /// `EntryValue::U32`.
+    ///
+    /// Currently only provided for WebM/Matroska files, and absent if it's
+    /// unspecified.
+    TransferCharacteristics,
+
+    /// Matrix coefficients of the video track as defined in ITU-T H.273,
+    /// e.g.: 9 (BT.2020 non-constant luminance). Its value is an
+    /// `EntryValue::U32`.
+    ///
+    /// Currently only provided for WebM/Matroska files, and absent if it's
+    /// unspecified.
+    MatrixCoefficients,
+
+    /// Types of the block additions of the video track, e.g.: "dvcC" or
+    /// "dvvC" for Dolby Vision, separated by ", " if there are multiple.
+    /// Its value is an `EntryValue::Text`.
+    ///
+    /// Currently only provided for WebM/Matroska files which have
+    /// `BlockAdditionMapping` elements.
+    BlockAdditionMapping,
+
+    /// Identifier shared by the image & the video of an Apple Live Photo,
+    /// see [`AppleMakerNote::content_identifier`](crate::AppleMakerNote).
+    /// Its value is an `EntryValue::Text`.
+    ///
+    /// Currently only provided for QuickTime files.
+    ContentIdentifier,
 }
 
 /// Represents parsed track info.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone, Default)]
 pub struct TrackInfo {
     entries: BTreeMap<TrackInfoTag, EntryValue>,
     gps_info: Option<GPSInfo>,
+    metadata: Vec<(String, EntryValue)>,
+    pub(crate) chapters: Vec<Chapter>,
 }
 
 impl TrackInfo {
@@ -66,15 +201,270 @@ impl TrackInfo {
         self.gps_info.as_ref()
     }
 
+    /// Returns the `(width, height)` a viewer should present, i.e. the stored
+    /// pixel dimensions with [`TrackInfoTag::Rotation`] applied.
+    ///
+    /// Returns `None` if the dimensions are unknown.
+    pub fn display_dimensions(&self) -> Option<(u32, u32)> {
+        let width = self.get(TrackInfoTag::ImageWidth)?.as_u32()?;
+        let height = self.get(TrackInfoTag::ImageHeight)?.as_u32()?;
+        let rotation = self
+            .get(TrackInfoTag::Rotation)
+            .and_then(|x| x.as_u32())
+            .unwrap_or(0);
+        if rotation % 180 == 90 {
+            Some((height, width))
+        } else {
+            Some((width, height))
+        }
+    }
+
+    /// Returns the [`CaptureMode`] derived from [`TrackInfoTag::FrameRate`]
+    /// and [`TrackInfoTag::CaptureFrameRate`].
+    ///
+    /// Returns `None` if the frame rate is unknown.
+    pub fn capture_mode(&self) -> Option<CaptureMode> {
+        let rate = self.get(TrackInfoTag::FrameRate)?.as_f64()?;
+        let Some(capture_rate) = self
+            .get(TrackInfoTag::CaptureFrameRate)
+            .and_then(|x| x.as_f64())
+        else {
+            return Some(CaptureMode::Normal);
+        };
+
+        // Tolerate small differences, e.g.: 29.97 vs 30
+        if capture_rate > rate * 1.5 {
+            Some(CaptureMode::SlowMotion)
+        } else if capture_rate < rate / 1.5 {
+            Some(CaptureMode::Timelapse)
+        } else {
+            Some(CaptureMode::Normal)
+        }
+    }
+
+    /// Renders the entries as a JSON object with human readable values, like
+    /// the objects printed by `exiftool -json` (without `SourceFile`).
+    ///
+    /// Keys are the names of [`TrackInfoTag`]s, except for those named
+    /// differently by exiftool: `Duration` (in seconds), `VideoFrameRate` &
+    /// `GPSCoordinates` (in ISO 6709).
+    ///
+    /// ```rust
+    /// use nom_exif::*;
+    ///
+    /// let ms = MediaSource::file_path("./testdata/meta.mov").unwrap();
+    /// let info: TrackInfo = MediaParser::new().parse(ms).unwrap();
+    /// let json = info.to_json();
+    /// assert!(json.contains(r#""Model": "iPhone X""#));
+    /// ```
+    pub fn to_json(&self) -> String {
+        let mut obj = JsonObject::default();
+        for (tag, value) in self.iter() {
+            match (tag, value.as_u64()) {
+                (TrackInfoTag::DurationMs, Some(ms)) => {
+                    obj.insert("Duration", &EntryValue::F64(ms as f64 / 1000.0))
+                }
+                (TrackInfoTag::FrameRate, _) => obj.insert("VideoFrameRate", value),
+                (TrackInfoTag::GpsIso6709, _) => obj.insert("GPSCoordinates", value),
+                _ => obj.insert(&tag.to_string(), value),
+            }
+        }
+        obj.finish()
+    }
+
     /// Get an iterator for `(&TrackInfoTag, &EntryValue)`. The parsed
     /// `GPSInfo` is not included.
     pub fn iter(&self) -> impl Iterator<Item = (&TrackInfoTag, &EntryValue)> {
         self.entries.iter()
     }
 
+    /// Get an iterator for all `(key, value)` items of the file's metadata,
+    /// in the order they are stored, so that no item is lost when it has no
+    /// corresponding `TrackInfoTag`.
+    ///
+    /// Currently only provided for MP4/M4A/MOV files, whose iTunes-style
+    /// items (`moov/udta/meta/ilst`) are keyed by their atom types, e.g.
+    /// `©nam`, `©ART`, `©day`, `covr` & `©too`. Custom items are keyed by
+    /// `----:<mean>:<name>`, e.g. `----:com.apple.iTunes:iTunSMPB`.
+    ///
+    /// ```rust
+    /// use nom_exif::*;
+    ///
+    /// let ms = MediaSource::file_path("./testdata/meta.mp4").unwrap();
+    /// let mut parser = MediaParser::new();
+    /// let info: TrackInfo = parser.parse(ms).unwrap();
+    ///
+    /// for (key, value) in info.metadata() {
+    ///     println!("{key}: {value}");
+    /// }
+    /// ```
+    pub fn metadata(&self) -> impl Iterator<Item = (&str, &EntryValue)> {
+        self.metadata.iter().map(|(k, v)| (k.as_str(), v))
+    }
+
+    /// Returns the chapters of the file in the order they are stored, nested
+    /// chapters follow their parents. Hidden (or disabled) chapters and
+    /// editions are not included.
+    ///
+    /// Currently only provided for WebM/Matroska files.
+    pub fn chapters(&self) -> &[Chapter] {
+        &self.chapters
+    }
+
+    /// Returns a canonical hash of the parsed tags, metadata items &
+    /// chapters, so that identical metadata can be detected cheaply, e.g. for
+    /// deduplication, see [`Exif::fingerprint`](crate::Exif::fingerprint).
+    pub fn fingerprint(&self) -> u64 {
+        let mut fp = Fingerprint::default();
+        for (tag, value) in self.iter() {
+            fp.add(tag.to_string().as_bytes(), value);
+        }
+        for (key, value) in self.metadata() {
+            fp.add(format!("metadata:{key}").as_bytes(), value);
+        }
+        for c in self.chapters() {
+            let chapter = format!("{}/{}/{:?}/{:?}", c.edition, c.start_ms, c.end_ms, c.title);
+            fp.add_text(b"chapter", &chapter);
+        }
+        fp.finish()
+    }
+
+    /// Encodes the parsed metadata in a compact, versioned binary format, so
+    /// that it can be cached and reloaded with [`TrackInfo::from_bytes`], see
+    /// [`Exif::to_bytes`](crate::Exif::to_bytes).
+    ///
+    /// Tags are keyed by their names, so that data encoded by an older
+    /// version of this crate can still be decoded.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = binary::Writer::new(binary::Kind::TrackInfo);
+        w.put(&self.entries.len());
+        for (tag, value) in self.entries.iter() {
+            w.put(&tag.to_string()).put(value);
+        }
+        w.put(&self.gps_info)
+            .put(&self.metadata)
+            .put(&self.chapters);
+        w.finish()
+    }
+
+    /// Decodes a `TrackInfo` encoded by [`TrackInfo::to_bytes`].
+    ///
+    /// Returns an error if the data is corrupted, or was encoded by an
+    /// incompatible version of this crate. Tags unknown to this version are
+    /// skipped.
+    pub fn from_bytes(data: &[u8]) -> crate::Result<TrackInfo> {
+        let mut r = binary::Reader::new(data, binary::Kind::TrackInfo)?;
+        let entries = r
+            .get::<Vec<(String, EntryValue)>>()?
+            .into_iter()
+            .filter_map(|(name, value)| {
+                let tag = TrackInfoTag::ALL.iter().find(|t| t.to_string() == name)?;
+                Some((*tag, value))
+            })
+            .collect();
+        let info = TrackInfo {
+            entries,
+            gps_info: r.get()?,
+            metadata: r.get()?,
+            chapters: r.get()?,
+        };
+        r.finish()?;
+        Ok(info)
+    }
+
     pub(crate) fn put(&mut self, tag: TrackInfoTag, value: EntryValue) {
         self.entries.insert(tag, value);
     }
+
+    /// Parses `gps_info` from [`TrackInfoTag::GpsIso6709`].
+    fn parse_gps_info(&mut self) {
+        if let Some(gps) = self.get(TrackInfoTag::GpsIso6709) {
+            self.gps_info = gps.as_str().and_then(|s| s.parse().ok());
+        }
+    }
+}
+
+/// Renders the tags, metadata items & chapters in the style of
+/// `exiftool -g`, e.g. `println!("{info}")` prints:
+///
+/// ```text
+/// ---- Track ----
+/// Make                            : Apple
+/// Model                           : iPhone X
+/// ...
+/// ---- Metadata ----
+/// ©too                            : Lavf58.76.100
+/// ---- Chapters ----
+/// Chapter 1                       : 0:00:00.000 - 0:00:05.000 Intro
+/// ```
+impl Display for TrackInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let rows = self
+            .iter()
+            .map(|(tag, value)| (tag.to_string(), pretty_value(None, value)));
+        write_group(f, "Track", rows)?;
+
+        let rows = self
+            .metadata()
+            .map(|(key, value)| (key.to_owned(), pretty_value(None, value)));
+        write_group(f, "Metadata", rows)?;
+
+        let rows = self.chapters().iter().enumerate().map(|(i, c)| {
+            let mut value = format_ms(c.start_ms);
+            if let Some(end) = c.end_ms {
+                value = format!("{value} - {}", format_ms(end));
+            }
+            if let Some(title) = c.title.as_ref() {
+                value = format!("{value} {title}");
+            }
+            (format!("Chapter {}", i + 1), value)
+        });
+        write_group(f, "Chapters", rows)
+    }
+}
+
+/// Formats milliseconds as `H:MM:SS.mmm`.
+fn format_ms(ms: u64) -> String {
+    let s = ms / 1000;
+    format!(
+        "{}:{:02}:{:02}.{:03}",
+        s / 3600,
+        s / 60 % 60,
+        s % 60,
+        ms % 1000
+    )
+}
+
+/// A chapter of a video/audio file, see [`TrackInfo::chapters`].
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chapter {
+    /// Index of the edition which the chapter belongs to. Matroska files may
+    /// have several editions, e.g. for different cuts of a movie.
+    pub edition: usize,
+
+    /// Start time in milliseconds.
+    pub start_ms: u64,
+
+    /// End time in milliseconds, if available.
+    pub end_ms: Option<u64>,
+
+    /// Title of the chapter, if available.
+    pub title: Option<String>,
+}
+
+/// How a video was captured relative to how it is played back, see
+/// [`TrackInfo::capture_mode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CaptureMode {
+    /// Captured at the playback frame rate.
+    Normal,
+
+    /// Captured at a higher frame rate than the playback frame rate.
+    SlowMotion,
+
+    /// Captured at a lower frame rate than the playback frame rate.
+    Timelapse,
 }
 
 /// Parse video/audio info from `reader`. The file format will be detected
@@ -85,6 +475,13 @@ impl TrackInfo {
 ///
 /// - ISO base media file format (ISOBMFF): *.mp4, *.mov, *.3gp, etc.
 /// - Matroska based file format: *.webm, *.mkv, *.mka, etc.
+/// - *.avi (RIFF)
+/// - *.wav (RIFF, with Broadcast Wave `bext`)
+/// - *.mp3 (ID3v2)
+/// - ASF (Windows Media): *.asf, *.wmv, *.wma
+/// - MPEG transport streams: *.ts, *.m2ts, *.mts
+/// - *.mxf (Material Exchange Format)
+/// - DSD audio: *.dsf, *.dff (ID3v2)
 ///
 /// ## Explanation of the generic parameters of this function:
 ///
@@ -139,31 +536,104 @@ impl TrackInfo {
 pub(crate) fn parse_track_info(
     input: &[u8],
     mime_video: MimeVideo,
+    parsers: &mut TrackParsers,
 ) -> Result<TrackInfo, ParsingError> {
     let mut info: TrackInfo = match mime_video {
         crate::file::MimeVideo::QuickTime
         | crate::file::MimeVideo::_3gpp
         | crate::file::MimeVideo::Mp4 => {
-            let range = extract_moov_body_from_buf(input)?;
-            let moov_body = &input[range];
+            if let Some(scanner) = parsers.fragments.as_mut() {
+                scanner.parse(input)?
+            } else {
+                let range = extract_moov_body_from_buf(input)?;
+                let moov_body = &input[range.clone()];
+
+                let mut entries = match mime_video {
+                    MimeVideo::QuickTime => parse_qt(moov_body)?,
 
-            match mime_video {
-                MimeVideo::QuickTime => parse_qt(moov_body)?.into(),
+                    MimeVideo::Mp4 | MimeVideo::_3gpp => parse_mp4(moov_body)?,
+                    _ => unreachable!(),
+                };
+                let metadata = parse_itunes_metadata(moov_body);
+                for (key, value) in metadata.iter() {
+                    if let Some(tag) = itunes_item_tag(key) {
+                        entries.entry(tag).or_insert_with(|| value.clone());
+                    }
+                }
+                if let Some(id3) = parse_id32_metadata(moov_body) {
+                    for (tag, value) in id3 {
+                        entries.entry(tag).or_insert(value);
+                    }
+                }
+                let mut info = TrackInfo::from(entries);
+                info.metadata = metadata;
 
-                MimeVideo::Mp4 | MimeVideo::_3gpp => parse_mp4(moov_body)?.into(),
-                _ => unreachable!(),
+                // The duration of fragmented movies is scanned from the
+                // movie fragments after `moov`
+                if let Some(scanner) = FragmentScanner::new(moov_body, &info) {
+                    parsers.fragments = Some(scanner);
+                    return Err(ParsingError::ClearAndSkip(range.end));
+                }
+                info
             }
         }
         crate::file::MimeVideo::Webm | crate::file::MimeVideo::Matroska => {
-            parse_webm(input)?.into()
+            parsers.webm.parse(input)?.into()
         }
+        crate::file::MimeVideo::Gif => parsers.gif.parse(input)?,
+        crate::file::MimeVideo::Avi => parsers.avi.parse(input)?,
+        crate::file::MimeVideo::Wav => parsers.wav.parse(input)?,
+        crate::file::MimeVideo::Mp3 => parsers.mp3.parse(input)?,
+        crate::file::MimeVideo::Asf => parse_asf(input)?,
+        crate::file::MimeVideo::MpegTs => parsers.ts.parse(input)?,
+        crate::file::MimeVideo::Mxf => parse_mxf(input)?,
+        crate::file::MimeVideo::Dsf => parsers.dsf.parse(input)?,
+        crate::file::MimeVideo::Dff => parsers.dff.parse(input)?,
     };
 
-    if let Some(gps) = info.get(TrackInfoTag::GpsIso6709) {
-        info.gps_info = gps.as_str().and_then(|s| s.parse().ok());
+    info.parse_gps_info();
+    Ok(info)
+}
+
+/// Parsers which keep their states between calls of [`parse_track_info`].
+#[derive(Debug, Default)]
+pub(crate) struct TrackParsers {
+    webm: WebmParser,
+    gif: GifParser,
+    avi: AviParser,
+    wav: WavParser,
+    mp3: Mp3Parser,
+    ts: TsParser,
+    dsf: DsfParser,
+    dff: DffParser,
+    fragments: Option<FragmentScanner>,
+}
+
+impl TrackParsers {
+    pub(crate) fn new(limits: Limits) -> Self {
+        Self {
+            webm: WebmParser::new(limits.max_ebml_element_size),
+            ..Default::default()
+        }
     }
 
-    Ok(info)
+    /// Returns the info collected so far when the input ends before the
+    /// parser is done, for formats which are scanned within a limited range
+    /// (e.g. MPEG-TS, fragmented MP4), rather than parsed to a definite end,
+    /// or whose tags at the end may be truncated (DSF/DSDIFF).
+    pub(crate) fn finish(&mut self, mime_video: MimeVideo) -> Option<TrackInfo> {
+        let mut info = match mime_video {
+            MimeVideo::MpegTs => self.ts.finish(),
+            MimeVideo::Dsf => self.dsf.finish()?,
+            MimeVideo::Dff => self.dff.finish()?,
+            MimeVideo::QuickTime | MimeVideo::_3gpp | MimeVideo::Mp4 => {
+                self.fragments.as_mut()?.finish()
+            }
+            _ => return None,
+        };
+        info.parse_gps_info();
+        Some(info)
+    }
 }
 
 impl IntoIterator for TrackInfo {
@@ -180,10 +650,45 @@ impl From<BTreeMap<TrackInfoTag, EntryValue>> for TrackInfo {
         Self {
             entries,
             gps_info: None,
+            metadata: Vec::new(),
+            chapters: Vec::new(),
         }
     }
 }
 
+impl TrackInfoTag {
+    pub(crate) const ALL: &'static [TrackInfoTag] = &[
+        TrackInfoTag::Make,
+        TrackInfoTag::Model,
+        TrackInfoTag::Software,
+        TrackInfoTag::CreateDate,
+        TrackInfoTag::DurationMs,
+        TrackInfoTag::ImageWidth,
+        TrackInfoTag::ImageHeight,
+        TrackInfoTag::Rotation,
+        TrackInfoTag::FrameRate,
+        TrackInfoTag::CaptureFrameRate,
+        TrackInfoTag::GpsIso6709,
+        TrackInfoTag::VideoCodec,
+        TrackInfoTag::Description,
+        TrackInfoTag::Title,
+        TrackInfoTag::Artist,
+        TrackInfoTag::Album,
+        TrackInfoTag::Picture,
+        TrackInfoTag::AudioCodec,
+        TrackInfoTag::ProgramNumber,
+        TrackInfoTag::Registration,
+        TrackInfoTag::ServiceProvider,
+        TrackInfoTag::ServiceName,
+        TrackInfoTag::Projection,
+        TrackInfoTag::ColorPrimaries,
+        TrackInfoTag::TransferCharacteristics,
+        TrackInfoTag::MatrixCoefficients,
+        TrackInfoTag::BlockAdditionMapping,
+        TrackInfoTag::ContentIdentifier,
+    ];
+}
+
 impl Display for TrackInfoTag {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let s: &str = (*self).into();
@@ -201,7 +706,27 @@ impl From<TrackInfoTag> for &str {
             TrackInfoTag::DurationMs => "DurationMs",
             TrackInfoTag::ImageWidth => "ImageWidth",
             TrackInfoTag::ImageHeight => "ImageHeight",
+            TrackInfoTag::Rotation => "Rotation",
+            TrackInfoTag::FrameRate => "FrameRate",
+            TrackInfoTag::CaptureFrameRate => "CaptureFrameRate",
             TrackInfoTag::GpsIso6709 => "GpsIso6709",
+            TrackInfoTag::VideoCodec => "VideoCodec",
+            TrackInfoTag::Description => "Description",
+            TrackInfoTag::Title => "Title",
+            TrackInfoTag::Artist => "Artist",
+            TrackInfoTag::Album => "Album",
+            TrackInfoTag::Picture => "Picture",
+            TrackInfoTag::AudioCodec => "AudioCodec",
+            TrackInfoTag::ProgramNumber => "ProgramNumber",
+            TrackInfoTag::Registration => "Registration",
+            TrackInfoTag::ServiceProvider => "ServiceProvider",
+            TrackInfoTag::ServiceName => "ServiceName",
+            TrackInfoTag::Projection => "Projection",
+            TrackInfoTag::ColorPrimaries => "ColorPrimaries",
+            TrackInfoTag::TransferCharacteristics => "TransferCharacteristics",
+            TrackInfoTag::MatrixCoefficients => "MatrixCoefficients",
+            TrackInfoTag::BlockAdditionMapping => "BlockAdditionMapping",
+            TrackInfoTag::ContentIdentifier => "ContentIdentifier",
         }
     }
 }
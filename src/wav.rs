@@ -0,0 +1,187 @@
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveTime};
+
+use crate::{
+    riff::{check_riff, to_text, u32_at, InfoList, RiffForm, RiffParser},
+    video::{TrackInfo, TrackInfoTag},
+};
+
+pub(crate) fn check_wav(input: &[u8]) -> bool {
+    check_riff(input, WavInfo::FORM_TYPE)
+}
+
+/// Parses WAV (RIFF) files chunk by chunk. Only the format chunk (`fmt `),
+/// the Broadcast Wave extension (`bext`) and the `INFO` list are loaded, the
+/// audio data (`data`) is skipped.
+///
+/// Refer to: [EBU Tech 3285](https://tech.ebu.ch/docs/tech/tech3285.pdf)
+pub(crate) type WavParser = RiffParser<WavInfo>;
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct WavInfo {
+    /// Average bytes per second, from `fmt `
+    byte_rate: u32,
+    /// Size of the `data` chunk
+    data_size: Option<u32>,
+
+    /// `Description` in `bext`
+    description: Option<String>,
+    /// `OriginationDate` & `OriginationTime` in `bext`
+    origination: Option<DateTime<FixedOffset>>,
+
+    info_list: InfoList,
+}
+
+/// Size of the fixed fields of `bext` which are parsed, i.e. up to
+/// `OriginationTime`.
+const BEXT_MIN_SIZE: usize = 338;
+
+impl RiffForm for WavInfo {
+    const FORM_TYPE: &'static [u8; 4] = b"WAVE";
+
+    fn wants(id: &[u8; 4], list_type: Option<&[u8]>) -> bool {
+        matches!(id, b"fmt " | b"bext") || list_type == Some(b"INFO")
+    }
+
+    fn parse_chunk(&mut self, id: &[u8; 4], data: &[u8]) {
+        match id {
+            // WAVEFORMAT
+            b"fmt " if data.len() >= 12 => self.byte_rate = u32_at(data, 8),
+            b"bext" if data.len() >= BEXT_MIN_SIZE => {
+                self.description = to_text(&data[..256]);
+                self.origination = parse_origination(&data[320..330], &data[330..338]);
+            }
+            b"LIST" => self.info_list.parse(&data[4..]),
+            _ => (),
+        }
+    }
+
+    fn skip_chunk(&mut self, id: &[u8; 4], size: u32) {
+        if id == b"data" {
+            self.data_size = Some(size);
+        }
+    }
+}
+
+/// Parses `OriginationDate` ("yyyy:mm:dd") & `OriginationTime` ("hh:mm:ss"),
+/// any of '-', '_', ':', ' ' or '.' may be used as separators.
+///
+/// The time is treated as UTC, since there is no time zone info.
+fn parse_origination(date: &[u8], time: &[u8]) -> Option<DateTime<FixedOffset>> {
+    let digits = |x: &[u8]| -> Vec<u8> { x.iter().copied().filter(u8::is_ascii_digit).collect() };
+    let date =
+        NaiveDate::parse_from_str(std::str::from_utf8(&digits(date)).ok()?, "%Y%m%d").ok()?;
+    // The time may be absent
+    let time = std::str::from_utf8(&digits(time))
+        .ok()
+        .and_then(|x| NaiveTime::parse_from_str(x, "%H%M%S").ok())
+        .unwrap_or_default();
+    Some(date.and_time(time).and_utc().fixed_offset())
+}
+
+impl From<WavInfo> for TrackInfo {
+    fn from(value: WavInfo) -> Self {
+        let mut info = TrackInfo::default();
+
+        if let Some(size) = value.data_size.filter(|_| value.byte_rate > 0) {
+            let duration = size as u64 * 1000 / value.byte_rate as u64;
+            info.put(TrackInfoTag::DurationMs, duration.into());
+        }
+
+        // `bext` takes precedence over `INFO`
+        let description = value.description;
+        let origination = value.origination;
+        value.info_list.put_into(&mut info);
+        if let Some(description) = description {
+            info.put(TrackInfoTag::Description, description.into());
+        }
+        if let Some(date) = origination {
+            info.put(TrackInfoTag::CreateDate, date.into());
+        }
+        info
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{
+        riff::tests::{chunk, le, list},
+        MediaParser, MediaSource,
+    };
+    use test_case::test_case;
+
+    fn bext(description: &str, date: &str, time: &str) -> Vec<u8> {
+        let mut data = vec![0; 602];
+        data[..description.len()].copy_from_slice(description.as_bytes());
+        data[256..256 + 9].copy_from_slice(b"Recorder1");
+        data[320..330].copy_from_slice(date.as_bytes());
+        data[330..338].copy_from_slice(time.as_bytes());
+        chunk(b"bext", &data)
+    }
+
+    /// Makes a WAV file of 16-bit stereo PCM at 48 kHz, which lasts
+    /// `duration_ms` milliseconds.
+    pub(crate) fn make_wav(duration_ms: u32, with_bext: bool) -> Vec<u8> {
+        let fmt = chunk(
+            b"fmt ",
+            &[&[1, 0, 2, 0][..], &le(&[48000, 48000 * 4]), &[4, 0, 16, 0]].concat(),
+        );
+        let data = chunk(b"data", &vec![0; (48 * 4 * duration_ms) as usize]);
+        let info = list(
+            b"INFO",
+            &[
+                chunk(b"ISFT", b"Lavf58.76.100\0"),
+                chunk(b"ICMT", b"Comment\0"),
+                chunk(b"ICRD", b"2021-05-06\0"),
+            ],
+        );
+
+        let mut body = vec![b"WAVE".to_vec()];
+        if with_bext {
+            body.push(bext("Interview, take 2", "2023-10-01", "08:30:15"));
+        }
+        body.extend([fmt, data, info]);
+        chunk(b"RIFF", &body.concat())
+    }
+
+    #[test_case(true, "Interview, take 2", "2023-10-01T08:30:15+00:00"; "bext")]
+    #[test_case(false, "Comment", "2021-05-06T00:00:00+00:00"; "info only")]
+    fn wav(with_bext: bool, description: &str, date: &str) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let data = make_wav(1500, with_bext);
+        let mut parser = MediaParser::new();
+        let ms = MediaSource::unseekable(Cursor::new(data)).unwrap();
+        assert!(ms.has_track());
+        let info: TrackInfo = parser.parse(ms).unwrap();
+
+        assert_eq!(info.get(TrackInfoTag::DurationMs), Some(&1500_u64.into()));
+        assert_eq!(
+            info.get(TrackInfoTag::Software),
+            Some(&"Lavf58.76.100".into())
+        );
+        assert_eq!(
+            info.get(TrackInfoTag::Description),
+            Some(&description.into())
+        );
+        assert_eq!(
+            info.get(TrackInfoTag::CreateDate).unwrap().to_string(),
+            date
+        );
+    }
+
+    #[test_case("2023-10-01", "08:30:15", Some("2023-10-01T08:30:15+00:00"))]
+    #[test_case("2023:10:01", "08.30.15", Some("2023-10-01T08:30:15+00:00"))]
+    #[test_case("2023_10_01", "\0\0\0\0\0\0\0\0", Some("2023-10-01T00:00:00+00:00"))]
+    #[test_case("\0\0\0\0\0\0\0\0\0\0", "08:30:15", None)]
+    fn origination(date: &str, time: &str, expect: Option<&str>) {
+        assert_eq!(
+            parse_origination(date.as_bytes(), time.as_bytes())
+                .map(|x| x.to_rfc3339())
+                .as_deref(),
+            expect
+        );
+    }
+}
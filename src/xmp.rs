@@ -0,0 +1,1434 @@
+use std::{
+    borrow::Cow,
+    io::{Read, Write},
+    str::FromStr,
+};
+
+use chrono::{DateTime, FixedOffset, Local, LocalResult, NaiveDateTime, TimeZone};
+use nom::{bytes::streaming, FindSubstring};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bbox::{find_box, BoxHeader},
+    error::{nom_error_to_parsing_error_with_state, ParsingError, ParsingErrorState},
+    exif::{check_exif_header, data_or_skip, jpeg_xmp, tiff_ifd0_data},
+    file::{Mime, MimeImage, MimeVideo},
+    heif,
+    jp2::XMP_UUID,
+    jpeg::{self, MarkerCode},
+    parser::ParsingState,
+    raf, PanoInfo,
+};
+
+/// XMP packets embedded in an image file.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct XmpPackets {
+    pub standard: String,
+    /// Extended XMP of JPEG files, which doesn't fit in one APP1 segment.
+    pub extended: Option<String>,
+}
+
+const RDF_NS: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+const XML_NS: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// TIFF tag `XMLPacket`, which contains the XMP packet of TIFF files.
+const TIFF_XMP_TAG: u16 = 0x02bc;
+
+/// Elements nested deeper than this are rejected.
+const MAX_XML_DEPTH: usize = 64;
+
+/// Parsed XMP metadata, a tree of properties.
+///
+/// Properties are addressed by their namespace URI and a path, e.g.
+/// `Rating` in [`Xmp::NS_XMP`], `subject[1]` in [`Xmp::NS_DC`], or
+/// `LocationCreated/City` in the IPTC extension namespace, see [`Xmp::get`].
+///
+/// `Xmp` can be parsed by a [`MediaParser`](crate::MediaParser) from JPEG
+/// (APP1 segments, including the extended XMP), HEIF/HEIC/AVIF (the XMP
+/// item), TIFF (tag 700), JPEG 2000 and MP4/MOV (`uuid` box, or
+/// `moov/udta/XMP_`) files, standalone XMP sidecar files (*.xmp, detected by
+/// content), or from an XMP packet string with `str::parse`.
+///
+/// It can be edited (e.g. [`Xmp::set_rating`], [`Xmp::add_keywords`]),
+/// serialized with [`Xmp::to_packet`], and written into JPEG & HEIF files
+/// with [`Xmp::write_jpeg`] & [`Xmp::write_heif`].
+///
+/// ```rust
+/// use nom_exif::*;
+///
+/// let xmp: Xmp = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+///  <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+///   <rdf:Description rdf:about=""
+///     xmlns:xmp="http://ns.adobe.com/xap/1.0/"
+///     xmlns:dc="http://purl.org/dc/elements/1.1/"
+///     xmp:Rating="4">
+///    <dc:subject><rdf:Bag><rdf:li>sea</rdf:li><rdf:li>sky</rdf:li></rdf:Bag></dc:subject>
+///   </rdf:Description>
+///  </rdf:RDF>
+/// </x:xmpmeta>"#.parse().unwrap();
+///
+/// assert_eq!(xmp.get(Xmp::NS_XMP, "Rating").unwrap().as_str(), Some("4"));
+/// assert_eq!(xmp.get(Xmp::NS_DC, "subject[2]").unwrap().as_str(), Some("sky"));
+/// assert_eq!(xmp.get(Xmp::NS_DC, "subject").unwrap().as_array().unwrap().len(), 2);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Xmp {
+    properties: Vec<XmpProperty>,
+}
+
+/// A property of [`Xmp`], or a field of a structure.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XmpProperty {
+    /// Namespace URI of the property
+    pub namespace: String,
+    /// Name of the property, without the namespace prefix
+    pub name: String,
+    pub value: XmpValue,
+}
+
+/// Value of an [`XmpProperty`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum XmpValue {
+    /// A simple value, or a URI (`rdf:resource`)
+    Text(String),
+    /// A structure, i.e. a set of fields
+    Struct(Vec<XmpProperty>),
+    /// An array (`rdf:Bag`, `rdf:Seq` or `rdf:Alt`)
+    Array(XmpArrayKind, Vec<XmpValue>),
+    /// A language alternative (an `rdf:Alt` whose items have `xml:lang`),
+    /// stored as (language, text) pairs
+    LangAlt(Vec<(String, String)>),
+}
+
+/// Kind of an XMP array.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XmpArrayKind {
+    /// Unordered array (`rdf:Bag`)
+    Bag,
+    /// Ordered array (`rdf:Seq`)
+    Seq,
+    /// Alternative array (`rdf:Alt`)
+    Alt,
+}
+
+impl Xmp {
+    /// XMP basic namespace (`xmp:`), e.g. `Rating`, `CreateDate`
+    pub const NS_XMP: &'static str = "http://ns.adobe.com/xap/1.0/";
+    /// XMP media management namespace (`xmpMM:`)
+    pub const NS_XMP_MM: &'static str = "http://ns.adobe.com/xap/1.0/mm/";
+    /// Dublin Core namespace (`dc:`), e.g. `subject` (keywords), `title`
+    pub const NS_DC: &'static str = "http://purl.org/dc/elements/1.1/";
+    /// Exif namespace (`exif:`)
+    pub const NS_EXIF: &'static str = "http://ns.adobe.com/exif/1.0/";
+    /// TIFF namespace (`tiff:`)
+    pub const NS_TIFF: &'static str = "http://ns.adobe.com/tiff/1.0/";
+    /// Photoshop namespace (`photoshop:`)
+    pub const NS_PHOTOSHOP: &'static str = "http://ns.adobe.com/photoshop/1.0/";
+    /// Google photo sphere namespace (`GPano:`)
+    pub const NS_GPANO: &'static str = "http://ns.google.com/photos/1.0/panorama/";
+
+    /// Returns the value at `path` in `namespace`.
+    ///
+    /// `path` is a list of names separated by `/`, each name may be followed
+    /// by a 1-based array index, e.g. `"History[2]/action"`. Fields of
+    /// structures are matched by name, fields in `namespace` are preferred.
+    pub fn get(&self, namespace: &str, path: &str) -> Option<&XmpValue> {
+        let mut segments = path.split('/');
+        let (name, index) = parse_path_segment(segments.next()?)?;
+        let mut value = find_property(&self.properties, namespace, name)?;
+        value = value.index(index)?;
+        for segment in segments {
+            let (name, index) = parse_path_segment(segment)?;
+            let XmpValue::Struct(fields) = value else {
+                return None;
+            };
+            let field = find_property(fields, namespace, name)
+                .or_else(|| fields.iter().find(|x| x.name == name).map(|x| &x.value));
+            value = field?.index(index)?;
+        }
+        Some(value)
+    }
+
+    /// Iterates over the top level properties.
+    pub fn iter(&self) -> impl Iterator<Item = &XmpProperty> {
+        self.properties.iter()
+    }
+
+    /// Returns the photo sphere / panorama information (the GPano
+    /// namespace), `None` if there is no GPano property.
+    pub fn pano_info(&self) -> Option<PanoInfo> {
+        PanoInfo::from_xmp(self)
+    }
+
+    /// Parses the standard XMP packet, and merges the properties of the
+    /// extended XMP packet (if any) into it.
+    pub(crate) fn from_packets(packets: &XmpPackets) -> crate::Result<Xmp> {
+        let mut xmp: Xmp = packets.standard.parse()?;
+        if let Some(extended) = packets.extended.as_deref() {
+            match extended.parse::<Xmp>() {
+                Ok(ext) => xmp.properties.extend(ext.properties),
+                Err(e) => tracing::warn!(?e, "Ignored: invalid extended XMP"),
+            }
+        }
+        Ok(xmp)
+    }
+
+    /// Sets the top level property `name` in `namespace`, an existing
+    /// property with the same name is replaced.
+    ///
+    /// ```rust
+    /// use nom_exif::*;
+    ///
+    /// let mut xmp = Xmp::default();
+    /// xmp.set(Xmp::NS_XMP, "Label", XmpValue::Text("Red".into()))
+    ///     .set_rating(4)
+    ///     .add_keywords(["sea", "sky"]);
+    ///
+    /// let xmp: Xmp = xmp.to_packet().parse().unwrap();
+    /// assert_eq!(xmp.get(Xmp::NS_XMP, "Rating").unwrap().as_str(), Some("4"));
+    /// assert_eq!(xmp.get(Xmp::NS_DC, "subject[2]").unwrap().as_str(), Some("sky"));
+    /// ```
+    pub fn set(&mut self, namespace: &str, name: &str, value: XmpValue) -> &mut Self {
+        match self
+            .properties
+            .iter_mut()
+            .find(|x| x.namespace == namespace && x.name == name)
+        {
+            Some(property) => property.value = value,
+            None => self.properties.push(XmpProperty {
+                namespace: namespace.to_owned(),
+                name: name.to_owned(),
+                value,
+            }),
+        }
+        self
+    }
+
+    /// Removes the top level property `name` in `namespace`, returns its
+    /// value if any.
+    pub fn remove(&mut self, namespace: &str, name: &str) -> Option<XmpValue> {
+        let pos = self
+            .properties
+            .iter()
+            .position(|x| x.namespace == namespace && x.name == name)?;
+        Some(self.properties.remove(pos).value)
+    }
+
+    /// Sets `xmp:Rating`, e.g. 0 ~ 5 stars, or -1 for rejected.
+    pub fn set_rating(&mut self, rating: i32) -> &mut Self {
+        self.set(Self::NS_XMP, "Rating", XmpValue::Text(rating.to_string()))
+    }
+
+    /// Adds keywords to `dc:subject`, the keywords which already exist are
+    /// skipped.
+    pub fn add_keywords<I, S>(&mut self, keywords: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut items = match self.remove(Self::NS_DC, "subject") {
+            Some(XmpValue::Array(_, items)) => items,
+            Some(XmpValue::Text(s)) => vec![XmpValue::Text(s)],
+            _ => Vec::new(),
+        };
+        for keyword in keywords {
+            let keyword = XmpValue::Text(keyword.into());
+            if !items.contains(&keyword) {
+                items.push(keyword);
+            }
+        }
+        self.set(
+            Self::NS_DC,
+            "subject",
+            XmpValue::Array(XmpArrayKind::Bag, items),
+        )
+    }
+
+    /// Merges the top level properties of `other` into this one, the
+    /// properties which exist in both are replaced by the ones of `other`.
+    pub fn merge(&mut self, other: &Xmp) -> &mut Self {
+        for property in other.properties.iter() {
+            self.set(&property.namespace, &property.name, property.value.clone());
+        }
+        self
+    }
+
+    /// Serializes the properties into an XMP packet (RDF/XML wrapped in
+    /// `xpacket` processing instructions).
+    ///
+    /// Namespace prefixes are not preserved when parsing, so the well-known
+    /// namespaces get their usual prefixes (e.g. `dc`, `xmp`), and the other
+    /// ones are named `ns1`, `ns2`, ...
+    pub fn to_packet(&self) -> String {
+        let mut namespaces = Vec::new();
+        collect_namespaces(&self.properties, &mut namespaces);
+        let prefixes = namespaces
+            .iter()
+            .scan(0, |n, ns| {
+                let prefix = match NS_PREFIXES.iter().find(|x| x.1 == *ns) {
+                    Some((prefix, _)) => prefix.to_string(),
+                    None => {
+                        *n += 1;
+                        format!("ns{n}")
+                    }
+                };
+                Some((prefix, ns.to_string()))
+            })
+            .collect::<Vec<_>>();
+
+        let mut s = String::from(
+            "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n\
+             <x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\n",
+        );
+        s.push_str(&format!(" <rdf:RDF xmlns:rdf=\"{RDF_NS}\">\n"));
+        s.push_str("  <rdf:Description rdf:about=\"\"");
+        for (prefix, ns) in prefixes.iter() {
+            s.push_str(&format!("\n    xmlns:{prefix}=\"{}\"", escape_xml(ns)));
+        }
+        s.push_str(">\n");
+        let writer = XmlWriter {
+            prefixes: &prefixes,
+        };
+        for property in self.properties.iter() {
+            writer.write_property(&mut s, property, 3);
+        }
+        s.push_str("  </rdf:Description>\n </rdf:RDF>\n</x:xmpmeta>\n<?xpacket end=\"w\"?>");
+        s
+    }
+
+    /// Reads a JPEG file from `reader`, and writes it into `writer` with the
+    /// XMP data replaced by the packet of this `Xmp` (the extended XMP, if
+    /// any, is removed). Other segments and the image data are copied as is.
+    ///
+    /// Returns [`Error::WriteFailed`](crate::Error::WriteFailed) if the
+    /// packet is too large to fit in a JPEG segment (64 KB).
+    pub fn write_jpeg<R: Read, W: Write>(&self, mut reader: R, writer: W) -> crate::Result<()> {
+        let mut input = Vec::new();
+        reader.read_to_end(&mut input)?;
+
+        let app1 = MarkerCode::APP1.code();
+        // The Exif segment is moved along, since it should be the first one
+        // after SOI (and APP0)
+        let (segments, _) = jpeg::parse_segments(&input)?;
+        let exif = segments
+            .iter()
+            .map(|x| (x.code, &input[x.payload.clone()])) // Safe-slice
+            .find(|(code, payload)| *code == app1 && check_exif_header(payload));
+
+        let mut payload = jpeg::XMP_SIGNATURE.to_vec();
+        payload.extend_from_slice(self.to_packet().as_bytes());
+        let inserts = exif
+            .into_iter()
+            .chain([(app1, &payload[..])])
+            .collect::<Vec<_>>();
+        jpeg::rewrite_segments(
+            &input,
+            writer,
+            |code, data| {
+                !(code == app1
+                    && (check_exif_header(data)
+                        || data.starts_with(jpeg::XMP_SIGNATURE)
+                        || data.starts_with(jpeg::EXTENDED_XMP_SIGNATURE)))
+            },
+            &inserts,
+        )
+    }
+
+    /// Reads a HEIF/HEIC/AVIF file from `reader`, and writes it into `writer`
+    /// with the data of the XMP item (a `mime` item of `application/rdf+xml`)
+    /// replaced by the packet of this `Xmp`.
+    ///
+    /// The packet is stored in a `mdat` box appended to the end of the file,
+    /// and the original XMP data is zeroed, other boxes are copied as is.
+    ///
+    /// Returns [`Error::WriteFailed`](crate::Error::WriteFailed) if the file
+    /// has no XMP item (adding a new item is not supported), or the XMP item
+    /// is not stored in a single extent of the file.
+    pub fn write_heif<R: Read, W: Write>(&self, mut reader: R, mut writer: W) -> crate::Result<()> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        let meta = heif::parse_meta_box(&data)
+            .ok()
+            .and_then(|(_, meta)| meta)
+            .ok_or("meta box not found")?;
+        let Some(id) = meta.xmp_item_id() else {
+            return Err(crate::Error::WriteFailed(
+                "adding XMP data to HEIF files without an XMP item is not supported".into(),
+            ));
+        };
+        heif::replace_item_data(&mut data, id, self.to_packet().as_bytes())?;
+
+        writer.write_all(&data)?;
+        Ok(())
+    }
+}
+
+/// Prefixes of the well-known namespaces, used for serializing.
+const NS_PREFIXES: [(&str, &str); 17] = [
+    ("xmp", Xmp::NS_XMP),
+    ("xmpMM", Xmp::NS_XMP_MM),
+    ("xmpRights", "http://ns.adobe.com/xap/1.0/rights/"),
+    ("stEvt", "http://ns.adobe.com/xap/1.0/sType/ResourceEvent#"),
+    ("stRef", "http://ns.adobe.com/xap/1.0/sType/ResourceRef#"),
+    ("dc", Xmp::NS_DC),
+    ("exif", Xmp::NS_EXIF),
+    ("exifEX", "http://cipa.jp/exif/1.0/"),
+    ("aux", "http://ns.adobe.com/exif/1.0/aux/"),
+    ("tiff", Xmp::NS_TIFF),
+    ("photoshop", Xmp::NS_PHOTOSHOP),
+    ("crs", "http://ns.adobe.com/camera-raw-settings/1.0/"),
+    (
+        "Iptc4xmpCore",
+        "http://iptc.org/std/Iptc4xmpCore/1.0/xmlns/",
+    ),
+    ("Iptc4xmpExt", "http://iptc.org/std/Iptc4xmpExt/2008-02-29/"),
+    ("GPano", Xmp::NS_GPANO),
+    ("GCamera", "http://ns.google.com/photos/1.0/camera/"),
+    ("hdrgm", "http://ns.adobe.com/hdr-gain-map/1.0/"),
+];
+
+/// Collects the namespaces of the properties & the fields of structures.
+fn collect_namespaces<'a>(properties: &'a [XmpProperty], namespaces: &mut Vec<&'a str>) {
+    fn collect_value<'a>(value: &'a XmpValue, namespaces: &mut Vec<&'a str>) {
+        match value {
+            XmpValue::Struct(fields) => collect_namespaces(fields, namespaces),
+            XmpValue::Array(_, items) => items.iter().for_each(|x| collect_value(x, namespaces)),
+            XmpValue::Text(_) | XmpValue::LangAlt(_) => (),
+        }
+    }
+    for property in properties {
+        let ns = property.namespace.as_str();
+        if !ns.is_empty() && ns != RDF_NS && !namespaces.contains(&ns) {
+            namespaces.push(ns);
+        }
+        collect_value(&property.value, namespaces);
+    }
+}
+
+/// Serializes properties as RDF/XML elements.
+struct XmlWriter<'a> {
+    /// (prefix, URI)
+    prefixes: &'a [(String, String)],
+}
+
+impl XmlWriter<'_> {
+    fn qname(&self, property: &XmpProperty) -> String {
+        if property.namespace == RDF_NS {
+            return format!("rdf:{}", property.name);
+        }
+        match self.prefixes.iter().find(|x| x.1 == property.namespace) {
+            Some((prefix, _)) => format!("{prefix}:{}", property.name),
+            None => property.name.clone(),
+        }
+    }
+
+    fn write_property(&self, s: &mut String, property: &XmpProperty, depth: usize) {
+        let qname = self.qname(property);
+        self.write_value(s, &qname, "", &property.value, depth);
+    }
+
+    /// Writes `value` as the element `qname` (with `attrs`).
+    fn write_value(
+        &self,
+        s: &mut String,
+        qname: &str,
+        attrs: &str,
+        value: &XmpValue,
+        depth: usize,
+    ) {
+        let indent = " ".repeat(depth);
+        match value {
+            XmpValue::Text(text) => {
+                s.push_str(&format!(
+                    "{indent}<{qname}{attrs}>{}</{qname}>\n",
+                    escape_xml(text)
+                ));
+            }
+            XmpValue::Struct(fields) => {
+                s.push_str(&format!(
+                    "{indent}<{qname}{attrs} rdf:parseType=\"Resource\">\n"
+                ));
+                for field in fields {
+                    self.write_property(s, field, depth + 1);
+                }
+                s.push_str(&format!("{indent}</{qname}>\n"));
+            }
+            XmpValue::Array(kind, items) => {
+                let kind = match kind {
+                    XmpArrayKind::Bag => "rdf:Bag",
+                    XmpArrayKind::Seq => "rdf:Seq",
+                    XmpArrayKind::Alt => "rdf:Alt",
+                };
+                s.push_str(&format!("{indent}<{qname}{attrs}>\n{indent} <{kind}>\n"));
+                for item in items {
+                    self.write_value(s, "rdf:li", "", item, depth + 2);
+                }
+                s.push_str(&format!("{indent} </{kind}>\n{indent}</{qname}>\n"));
+            }
+            XmpValue::LangAlt(items) => {
+                s.push_str(&format!("{indent}<{qname}{attrs}>\n{indent} <rdf:Alt>\n"));
+                for (lang, text) in items {
+                    let attrs = format!(" xml:lang=\"{}\"", escape_xml(lang));
+                    let text = XmpValue::Text(text.to_owned());
+                    self.write_value(s, "rdf:li", &attrs, &text, depth + 2);
+                }
+                s.push_str(&format!("{indent} </rdf:Alt>\n{indent}</{qname}>\n"));
+            }
+        }
+    }
+}
+
+/// Escapes the special characters of XML text & attribute values.
+fn escape_xml(s: &str) -> Cow<'_, str> {
+    if !s.contains(['&', '<', '>', '"']) {
+        return Cow::Borrowed(s);
+    }
+    let mut escaped = String::with_capacity(s.len() + 16);
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+impl FromStr for Xmp {
+    type Err = crate::Error;
+
+    /// Parses an XMP packet, which is serialized as RDF/XML.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let root = XmlReader::new(s).document()?;
+        let rdf = find_element(&root, RDF_NS, "RDF").ok_or("rdf:RDF not found in XMP")?;
+        let properties = rdf
+            .children
+            .iter()
+            .filter(|x| x.is(RDF_NS, "Description"))
+            .flat_map(description_properties)
+            .collect();
+        Ok(Xmp { properties })
+    }
+}
+
+impl XmpValue {
+    /// Returns the text of a simple value, or the default (`x-default`, or
+    /// the first) text of a language alternative.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            XmpValue::Text(s) => Some(s),
+            XmpValue::LangAlt(items) => items
+                .iter()
+                .find(|x| x.0 == "x-default")
+                .or(items.first())
+                .map(|x| x.1.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Returns the items of an array.
+    pub fn as_array(&self) -> Option<&[XmpValue]> {
+        match self {
+            XmpValue::Array(_, items) => Some(items),
+            _ => None,
+        }
+    }
+
+    /// Returns the fields of a structure.
+    pub fn as_struct(&self) -> Option<&[XmpProperty]> {
+        match self {
+            XmpValue::Struct(fields) => Some(fields),
+            _ => None,
+        }
+    }
+
+    /// Returns the text of a language alternative in `lang`, e.g. "en-US".
+    pub fn lang(&self, lang: &str) -> Option<&str> {
+        match self {
+            XmpValue::LangAlt(items) => items
+                .iter()
+                .find(|x| x.0.eq_ignore_ascii_case(lang))
+                .map(|x| x.1.as_str()),
+            _ => None,
+        }
+    }
+
+    fn index(&self, index: Option<usize>) -> Option<&XmpValue> {
+        match index {
+            Some(i) => self.as_array()?.get(i.checked_sub(1)?),
+            None => Some(self),
+        }
+    }
+}
+
+/// Splits a path segment into the name and the (1-based) array index.
+fn parse_path_segment(segment: &str) -> Option<(&str, Option<usize>)> {
+    match segment.strip_suffix(']') {
+        Some(s) => {
+            let (name, index) = s.split_once('[')?;
+            Some((name, Some(index.parse().ok()?)))
+        }
+        None => Some((segment, None)),
+    }
+}
+
+fn find_property<'a>(
+    properties: &'a [XmpProperty],
+    namespace: &str,
+    name: &str,
+) -> Option<&'a XmpValue> {
+    properties
+        .iter()
+        .find(|x| x.namespace == namespace && x.name == name)
+        .map(|x| &x.value)
+}
+
+/// Returns the properties of an `rdf:Description` element, or the fields of
+/// a structure, which are serialized as the attributes & child elements.
+fn description_properties(e: &Element) -> Vec<XmpProperty> {
+    let attrs = e
+        .attrs
+        .iter()
+        .filter(|x| !x.ns.is_empty() && x.ns != RDF_NS && x.ns != XML_NS)
+        .map(|x| XmpProperty {
+            namespace: x.ns.clone(),
+            name: x.name.clone(),
+            value: XmpValue::Text(x.value.clone()),
+        });
+    let children = e.children.iter().map(|x| XmpProperty {
+        namespace: x.ns.clone(),
+        name: x.name.clone(),
+        value: property_value(x),
+    });
+    attrs.chain(children).collect()
+}
+
+fn property_value(e: &Element) -> XmpValue {
+    if let Some(uri) = e.attr(RDF_NS, "resource") {
+        return XmpValue::Text(uri.to_owned());
+    }
+    if e.attr(RDF_NS, "parseType") == Some("Resource") {
+        return struct_value(description_properties(e));
+    }
+    match e.children.first() {
+        Some(child) if child.is(RDF_NS, "Bag") => array_value(XmpArrayKind::Bag, child),
+        Some(child) if child.is(RDF_NS, "Seq") => array_value(XmpArrayKind::Seq, child),
+        Some(child) if child.is(RDF_NS, "Alt") => array_value(XmpArrayKind::Alt, child),
+        Some(child) if child.is(RDF_NS, "Description") => {
+            struct_value(description_properties(child))
+        }
+        _ => {
+            // A structure may also be serialized as the attributes of the
+            // property element
+            let fields = description_properties(e);
+            if fields.is_empty() {
+                XmpValue::Text(e.text.clone())
+            } else {
+                struct_value(fields)
+            }
+        }
+    }
+}
+
+/// A structure with an `rdf:value` field is a qualified simple value, the
+/// qualifiers are dropped.
+fn struct_value(fields: Vec<XmpProperty>) -> XmpValue {
+    match fields
+        .iter()
+        .position(|x| x.namespace == RDF_NS && x.name == "value")
+    {
+        Some(pos) => fields.into_iter().nth(pos).unwrap().value,
+        None => XmpValue::Struct(fields),
+    }
+}
+
+fn array_value(kind: XmpArrayKind, e: &Element) -> XmpValue {
+    let items = e.children.iter().filter(|x| x.is(RDF_NS, "li"));
+    if kind == XmpArrayKind::Alt {
+        let langs = items
+            .clone()
+            .map(|x| Some((x.attr(XML_NS, "lang")?.to_owned(), property_value(x))))
+            .map(|x| match x {
+                Some((lang, XmpValue::Text(text))) => Some((lang, text)),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>();
+        if let Some(langs) = langs.filter(|x| !x.is_empty()) {
+            return XmpValue::LangAlt(langs);
+        }
+    }
+    XmpValue::Array(kind, items.map(property_value).collect())
+}
+
+/// Depth-first search for an element, including `e` itself.
+fn find_element<'a>(e: &'a Element, ns: &str, name: &str) -> Option<&'a Element> {
+    if e.is(ns, name) {
+        return Some(e);
+    }
+    e.children.iter().find_map(|x| find_element(x, ns, name))
+}
+
+/// An XML element, with resolved namespaces.
+#[derive(Debug)]
+struct Element {
+    ns: String,
+    name: String,
+    /// Attributes, excluding namespace declarations
+    attrs: Vec<Attribute>,
+    children: Vec<Element>,
+    text: String,
+}
+
+#[derive(Debug)]
+struct Attribute {
+    ns: String,
+    name: String,
+    value: String,
+}
+
+impl Element {
+    fn is(&self, ns: &str, name: &str) -> bool {
+        self.ns == ns && self.name == name
+    }
+
+    fn attr(&self, ns: &str, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|x| x.ns == ns && x.name == name)
+            .map(|x| x.value.as_str())
+    }
+}
+
+/// A minimal XML parser, which is just enough for RDF/XML in XMP packets.
+///
+/// DTDs are not supported, and only the predefined & character entities are
+/// decoded.
+struct XmlReader<'a> {
+    s: &'a str,
+    pos: usize,
+    /// Namespace declarations in scope, (prefix, URI)
+    namespaces: Vec<(&'a str, String)>,
+}
+
+impl<'a> XmlReader<'a> {
+    fn new(s: &'a str) -> Self {
+        Self {
+            s: s.strip_prefix('\u{feff}').unwrap_or(s),
+            pos: 0,
+            namespaces: Vec::new(),
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.s[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn skip_past(&mut self, end: &str) -> Result<(), String> {
+        let n = self
+            .rest()
+            .find(end)
+            .ok_or_else(|| format!("unexpected end of XML, expecting {end:?}"))?;
+        self.pos += n + end.len();
+        Ok(())
+    }
+
+    fn read_name(&mut self) -> Result<&'a str, String> {
+        let rest = self.rest();
+        let n = rest
+            .find(|c: char| c.is_whitespace() || matches!(c, '/' | '>' | '='))
+            .unwrap_or(rest.len());
+        if n == 0 {
+            return Err(format!("invalid XML name at {}", self.pos));
+        }
+        self.pos += n;
+        Ok(&rest[..n])
+    }
+
+    /// Parses the root element, the prolog (XML declaration, `xpacket`
+    /// processing instructions, comments, etc.) is skipped.
+    fn document(&mut self) -> Result<Element, String> {
+        loop {
+            self.skip_whitespace();
+            let rest = self.rest();
+            if rest.starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if rest.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if rest.starts_with("<!") {
+                self.skip_past(">")?;
+            } else if rest.starts_with('<') {
+                return self.element(0);
+            } else {
+                return Err("XML root element not found".into());
+            }
+        }
+    }
+
+    fn element(&mut self, depth: usize) -> Result<Element, String> {
+        if depth > MAX_XML_DEPTH {
+            return Err("XML elements are nested too deeply".into());
+        }
+        self.pos += 1; // '<'
+        let qname = self.read_name()?;
+        let scope = self.namespaces.len();
+
+        let mut attrs = Vec::new();
+        let empty = loop {
+            self.skip_whitespace();
+            let rest = self.rest();
+            if rest.starts_with("/>") {
+                self.pos += 2;
+                break true;
+            } else if rest.starts_with('>') {
+                self.pos += 1;
+                break false;
+            }
+
+            let name = self.read_name()?;
+            self.skip_whitespace();
+            if !self.rest().starts_with('=') {
+                return Err(format!("invalid XML attribute {name:?}"));
+            }
+            self.pos += 1;
+            self.skip_whitespace();
+            let quote = self
+                .rest()
+                .chars()
+                .next()
+                .filter(|c| *c == '"' || *c == '\'')
+                .ok_or_else(|| format!("unquoted XML attribute {name:?}"))?;
+            self.pos += 1;
+            let len = self
+                .rest()
+                .find(quote)
+                .ok_or("unexpected end of XML attribute")?;
+            let value = decode_entities(&self.rest()[..len]).into_owned();
+            self.pos += len + 1;
+
+            if name == "xmlns" {
+                self.namespaces.push(("", value));
+            } else if let Some(prefix) = name.strip_prefix("xmlns:") {
+                self.namespaces.push((prefix, value));
+            } else {
+                attrs.push((name, value));
+            }
+        };
+
+        let (ns, name) = self.resolve(qname, true);
+        let attrs = attrs
+            .into_iter()
+            .map(|(qname, value)| {
+                let (ns, name) = self.resolve(qname, false);
+                Attribute { ns, name, value }
+            })
+            .collect();
+        let mut element = Element {
+            ns,
+            name,
+            attrs,
+            children: Vec::new(),
+            text: String::new(),
+        };
+        if !empty {
+            self.content(&mut element, depth)?;
+        }
+        self.namespaces.truncate(scope);
+        Ok(element)
+    }
+
+    /// Parses the content of `element`, up to (and including) its end tag.
+    fn content(&mut self, element: &mut Element, depth: usize) -> Result<(), String> {
+        loop {
+            let rest = self.rest();
+            let n = rest.find('<').ok_or("unexpected end of XML")?;
+            element.text.push_str(&decode_entities(&rest[..n]));
+            self.pos += n;
+
+            let rest = self.rest();
+            if rest.starts_with("</") {
+                return self.skip_past(">");
+            } else if rest.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+                let n = cdata.find("]]>").ok_or("unexpected end of CDATA")?;
+                element.text.push_str(&cdata[..n]);
+                self.pos += rest.len() - cdata.len() + n + 3;
+            } else if rest.starts_with("<?") {
+                self.skip_past("?>")?;
+            } else {
+                element.children.push(self.element(depth + 1)?);
+            }
+        }
+    }
+
+    /// Splits `qname` into (namespace URI, local name). Unprefixed attributes
+    /// have no namespace.
+    fn resolve(&self, qname: &str, is_element: bool) -> (String, String) {
+        let (prefix, name) = qname.split_once(':').unwrap_or(("", qname));
+        let ns = if prefix == "xml" {
+            XML_NS.to_owned()
+        } else if prefix.is_empty() && !is_element {
+            String::new()
+        } else {
+            self.namespaces
+                .iter()
+                .rev()
+                .find(|x| x.0 == prefix)
+                .map(|x| x.1.clone())
+                .unwrap_or_default()
+        };
+        (ns, name.to_owned())
+    }
+}
+
+/// Decodes the predefined entities (`&amp;` etc.) and character references
+/// (`&#169;`, `&#xA9;`), unknown entities are kept as is.
+fn decode_entities(s: &str) -> Cow<'_, str> {
+    if !s.contains('&') {
+        return Cow::Borrowed(s);
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(pos) = rest.find('&') {
+        out.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+        let decoded = rest
+            .find(';')
+            .and_then(|end| Some((decode_entity(&rest[1..end])?, end)));
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    Cow::Owned(out)
+}
+
+fn decode_entity(name: &str) -> Option<char> {
+    match name {
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "amp" => Some('&'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        _ => {
+            let code = name.strip_prefix('#')?;
+            let code = match code.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => code.parse().ok()?,
+            };
+            char::from_u32(code)
+        }
+    }
+}
+
+/// Extracts the XMP packets of a file, see [`Xmp`] for the supported
+/// formats.
+///
+/// Returns `None` if the file contains no XMP. ISOBMFF files (MP4, MOV &
+/// JPEG 2000) are scanned box by box, `input` should start at a box
+/// boundary.
+pub(crate) fn extract_xmp(
+    mime: Mime,
+    input: &[u8],
+    state: Option<ParsingState>,
+) -> Result<Option<XmpPackets>, ParsingErrorState> {
+    let map_err = |e| nom_error_to_parsing_error_with_state(e, state.clone());
+    let data = match (mime, &state) {
+        (_, Some(ParsingState::DataSize(size))) => {
+            let (_, data) = streaming::take(*size)(input).map_err(map_err)?;
+            Some(data)
+        }
+        (Mime::Image(MimeImage::Jpeg), _) | (Mime::Image(MimeImage::Raf), Some(_)) => {
+            let (_, segments) = jpeg::collect_app1_segments(input).map_err(map_err)?;
+            return Ok(jpeg_xmp(&segments));
+        }
+        (Mime::Image(MimeImage::Raf), None) => return Err(raf::skip_to_jpeg(input, None)),
+        (Mime::Image(MimeImage::Heic | MimeImage::Heif | MimeImage::Avif), _) => {
+            let (_, meta) = heif::parse_meta_box(input).map_err(map_err)?;
+            let Some(range) = meta.and_then(|x| x.xmp_data_offset()) else {
+                return Ok(None);
+            };
+            data_or_skip(input, range.start, range.len())?
+        }
+        (Mime::Image(MimeImage::Tiff | MimeImage::Pef | MimeImage::Srw), _) => {
+            tiff_ifd0_data(input, TIFF_XMP_TAG)?
+        }
+        (Mime::Xmp, _) => Some(extract_sidecar_xmp(input)?),
+        (Mime::Image(MimeImage::Jp2), _)
+        | (Mime::Video(MimeVideo::QuickTime | MimeVideo::Mp4 | MimeVideo::_3gpp), _) => {
+            extract_bmff_xmp(input)?
+        }
+        _ => None,
+    };
+
+    let Some(data) = data else {
+        return Ok(None);
+    };
+    // Some writers pad the packet with NULs
+    let data = data.strip_suffix(&[0]).unwrap_or(data);
+    match String::from_utf8(data.to_vec()) {
+        Ok(standard) => Ok(Some(XmpPackets {
+            standard,
+            extended: None,
+        })),
+        Err(_) => Err(ParsingErrorState::new(
+            ParsingError::Failed("invalid XMP data".into()),
+            None,
+        )),
+    }
+}
+
+const UTF8_BOM: &[u8] = b"\xef\xbb\xbf";
+
+/// Root elements of an XMP packet, `x:xapmeta` is used by old versions of
+/// Photoshop, and some writers omit the `x:xmpmeta` wrapper.
+const XMP_ROOT_TAGS: [&str; 3] = ["x:xmpmeta", "x:xapmeta", "rdf:RDF"];
+
+/// Checks whether `input` is the beginning of an XMP sidecar file, i.e. an
+/// XMP packet optionally preceded by a BOM & an XML declaration.
+pub(crate) fn check_xmp_sidecar(input: &[u8]) -> bool {
+    let trim = |x: &[u8]| -> usize { x.iter().take_while(|b| b.is_ascii_whitespace()).count() };
+    let mut s = input.strip_prefix(UTF8_BOM).unwrap_or(input);
+    s = &s[trim(s)..];
+    if s.starts_with(b"<?xml") {
+        let Some(end) = s.find_substring("?>") else {
+            return false;
+        };
+        s = &s[end + 2..];
+        s = &s[trim(s)..];
+    }
+    s.starts_with(b"<?xpacket")
+        || XMP_ROOT_TAGS.iter().any(|tag| {
+            s.strip_prefix(b"<")
+                .is_some_and(|x| x.starts_with(tag.as_bytes()))
+        })
+}
+
+/// Returns the XMP packet of a sidecar file, which ends with the closing tag
+/// of the root element.
+fn extract_sidecar_xmp(input: &[u8]) -> Result<&[u8], ParsingErrorState> {
+    let end = XMP_ROOT_TAGS.iter().find_map(|tag| {
+        let close = format!("</{tag}>");
+        input
+            .find_substring(close.as_str())
+            .map(|x| x + close.len())
+    });
+    let Some(end) = end else {
+        return Err(ParsingErrorState::new(ParsingError::Need(1), None));
+    };
+    let data = &input[..end];
+    Ok(data.strip_prefix(UTF8_BOM).unwrap_or(data))
+}
+
+/// Finds the XMP `uuid` box (MP4 & JPEG 2000), or the `moov/udta/XMP_` box
+/// (QuickTime). Other boxes are skipped with a `ClearAndSkip`.
+fn extract_bmff_xmp(input: &[u8]) -> Result<Option<&[u8]>, ParsingErrorState> {
+    let map_err = |e| nom_error_to_parsing_error_with_state(e, None);
+
+    // A box size of 0 means the last box, which extends to the end of the file
+    if input.starts_with(&[0, 0, 0, 0]) {
+        return Ok(None);
+    }
+
+    let (remain, header) = BoxHeader::parse(input).map_err(map_err)?;
+    match header.box_type.as_str() {
+        "uuid" => {
+            let (_, uuid) = streaming::take(XMP_UUID.len())(remain).map_err(map_err)?;
+            if uuid == XMP_UUID {
+                let (_, body) = streaming::take(header.body_size())(remain).map_err(map_err)?;
+                return Ok(Some(&body[XMP_UUID.len()..]));
+            }
+        }
+        "moov" => {
+            let (_, body) = streaming::take(header.body_size())(remain).map_err(map_err)?;
+            if let Ok((_, Some(xmp))) = find_box(body, "udta/XMP_") {
+                return Ok(Some(xmp.body_data()));
+            }
+        }
+        _ => (),
+    }
+
+    let size = usize::try_from(header.box_size)
+        .map_err(|_| ParsingErrorState::new(ParsingError::Failed("box is too big".into()), None))?;
+    Err(ParsingErrorState::new(
+        ParsingError::ClearAndSkip(size),
+        None,
+    ))
+}
+
+/// Finds the value of a simple XMP property, e.g. `xmp:CreateDate`, which
+/// may be serialized either as an attribute (`xmp:CreateDate="..."`) or as an
+/// element (`<xmp:CreateDate>...</xmp:CreateDate>`).
+///
+/// This is not a full RDF/XML parser, only simple (non-structured) properties
+/// are supported, and XML entities are not decoded.
+pub(crate) fn xmp_property<'a>(xmp: &'a str, name: &str) -> Option<&'a str> {
+    let attr = format!("{name}=");
+    let mut rest = xmp;
+    while let Some(pos) = rest.find(&attr) {
+        let preceded_by_space = rest[..pos]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_ascii_whitespace());
+        rest = &rest[pos + attr.len()..];
+        if !preceded_by_space {
+            continue;
+        }
+        let Some(quote) = rest.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+            continue;
+        };
+        let value = &rest[1..];
+        return value.find(quote).map(|end| value[..end].trim());
+    }
+
+    let start = format!("<{name}>");
+    let end = format!("</{name}>");
+    let pos = xmp.find(&start)? + start.len();
+    let len = xmp[pos..].find(&end)?;
+    Some(xmp[pos..pos + len].trim())
+}
+
+/// Parses an XMP date, e.g.: "2024-02-02T08:09:57+08:00". A date without time
+/// zone is treated as local time, the same as Exif dates.
+pub(crate) fn parse_xmp_date(s: &str) -> Option<DateTime<FixedOffset>> {
+    if let Ok(t) = DateTime::parse_from_rfc3339(s) {
+        return Some(t);
+    }
+    // Seconds are optional in XMP dates
+    if let Ok(t) = DateTime::parse_from_str(s, "%Y-%m-%dT%H:%M%:z") {
+        return Some(t);
+    }
+
+    let t = NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f")
+        .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M"))
+        .ok()?;
+    match Local.from_local_datetime(&t) {
+        LocalResult::Single(t) => Some(t.fixed_offset()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testkit::{bbox, read_sample};
+    use test_case::test_case;
+
+    const XMP: &str = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about=""
+    xmlns:xmp="http://ns.adobe.com/xap/1.0/"
+    xmp:MetadataDate="2024-02-03T10:00:00+08:00"
+    xmp:CreateDate='2024-02-02T08:09:57+08:00'>
+   <xmp:CreatorTool>Adobe Photoshop 25.0</xmp:CreatorTool>
+  </rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>"#;
+
+    #[test_case("xmp:CreateDate", Some("2024-02-02T08:09:57+08:00"))]
+    #[test_case("xmp:MetadataDate", Some("2024-02-03T10:00:00+08:00"))]
+    #[test_case("xmp:CreatorTool", Some("Adobe Photoshop 25.0"))]
+    #[test_case("Date", None)]
+    #[test_case("xmp:ModifyDate", None)]
+    fn property(name: &str, value: Option<&str>) {
+        assert_eq!(xmp_property(XMP, name), value);
+    }
+
+    const TREE: &str = r#"<?xpacket begin="" id="W5M0MpCehiHzreSzNTczkc9d"?>
+<x:xmpmeta xmlns:x="adobe:ns:meta/">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about=""
+    xmlns:xmp="http://ns.adobe.com/xap/1.0/"
+    xmlns:GPano="http://ns.google.com/photos/1.0/panorama/"
+    xmp:Rating="5" GPano:ProjectionType="equirectangular">
+   <!-- comment -->
+   <xmp:Label>Tom &amp; Jerry &#x263A;</xmp:Label>
+   <xmp:Nickname><![CDATA[a<b]]></xmp:Nickname>
+  </rdf:Description>
+  <rdf:Description rdf:about=""
+    xmlns:dc="http://purl.org/dc/elements/1.1/"
+    xmlns:exif="http://ns.adobe.com/exif/1.0/"
+    xmlns:xmpMM="http://ns.adobe.com/xap/1.0/mm/"
+    xmlns:stEvt="http://ns.adobe.com/xap/1.0/sType/ResourceEvent#">
+   <dc:subject><rdf:Bag><rdf:li>sea</rdf:li><rdf:li>sky</rdf:li></rdf:Bag></dc:subject>
+   <dc:title><rdf:Alt>
+    <rdf:li xml:lang="x-default">Sunset</rdf:li>
+    <rdf:li xml:lang="fr-FR">Coucher de soleil</rdf:li>
+   </rdf:Alt></dc:title>
+   <dc:source rdf:resource="https://example.com/a.jpg"/>
+   <exif:Flash exif:Fired="False" exif:Mode="2"/>
+   <dc:creator rdf:parseType="Resource"><rdf:value>Jane</rdf:value><xmp:Role>photographer</xmp:Role></dc:creator>
+   <xmpMM:History><rdf:Seq>
+    <rdf:li stEvt:action="created"/>
+    <rdf:li rdf:parseType="Resource"><stEvt:action>saved</stEvt:action></rdf:li>
+   </rdf:Seq></xmpMM:History>
+   <xmpMM:DerivedFrom><rdf:Description stEvt:instanceID="xmp.iid:1"/></xmpMM:DerivedFrom>
+  </rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>
+<?xpacket end="w"?>"#;
+
+    #[test_case(Xmp::NS_XMP, "Rating", Some("5"))]
+    #[test_case(Xmp::NS_GPANO, "ProjectionType", Some("equirectangular"))]
+    #[test_case(Xmp::NS_XMP, "Label", Some("Tom & Jerry \u{263a}"))]
+    #[test_case(Xmp::NS_XMP, "Nickname", Some("a<b"))]
+    #[test_case(Xmp::NS_DC, "subject[2]", Some("sky"))]
+    #[test_case(Xmp::NS_DC, "subject[3]", None)]
+    #[test_case(Xmp::NS_DC, "subject[0]", None)]
+    #[test_case(Xmp::NS_DC, "title", Some("Sunset"))]
+    #[test_case(Xmp::NS_DC, "source", Some("https://example.com/a.jpg"))]
+    #[test_case(Xmp::NS_EXIF, "Flash/Mode", Some("2"))]
+    #[test_case(Xmp::NS_DC, "creator", Some("Jane"); "qualified value")]
+    #[test_case(Xmp::NS_XMP_MM, "History[1]/action", Some("created"))]
+    #[test_case(Xmp::NS_XMP_MM, "History[2]/action", Some("saved"))]
+    #[test_case(Xmp::NS_XMP_MM, "DerivedFrom/instanceID", Some("xmp.iid:1"))]
+    #[test_case(Xmp::NS_DC, "Rating", None; "wrong namespace")]
+    #[test_case(Xmp::NS_XMP, "Rating/x", None; "not a struct")]
+    fn tree(namespace: &str, path: &str, expect: Option<&str>) {
+        let xmp: Xmp = TREE.parse().unwrap();
+        assert_eq!(xmp.get(namespace, path).and_then(|x| x.as_str()), expect);
+    }
+
+    #[test]
+    fn tree_values() {
+        let xmp: Xmp = TREE.parse().unwrap();
+        assert_eq!(xmp.iter().count(), 11);
+
+        let subject = xmp.get(Xmp::NS_DC, "subject").unwrap();
+        assert_eq!(
+            subject,
+            &XmpValue::Array(
+                XmpArrayKind::Bag,
+                vec![XmpValue::Text("sea".into()), XmpValue::Text("sky".into())]
+            )
+        );
+        let title = xmp.get(Xmp::NS_DC, "title").unwrap();
+        assert_eq!(title.lang("fr-fr"), Some("Coucher de soleil"));
+        let flash = xmp.get(Xmp::NS_EXIF, "Flash").unwrap();
+        assert_eq!(flash.as_struct().unwrap().len(), 2);
+    }
+
+    #[test_case(""; "empty")]
+    #[test_case(r#"<x:xmpmeta xmlns:x="adobe:ns:meta/"/>"#; "no rdf")]
+    #[test_case(r#"<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">"#; "truncated")]
+    #[test_case("<a b=c/>"; "unquoted attribute")]
+    fn invalid(s: &str) {
+        s.parse::<Xmp>().unwrap_err();
+    }
+
+    #[test]
+    fn too_deep() {
+        let s = format!("{}{}", "<a>".repeat(100), "</a>".repeat(100));
+        s.parse::<Xmp>().unwrap_err();
+    }
+
+    fn parse_file(data: Vec<u8>) -> crate::Result<Xmp> {
+        let ms = crate::MediaSource::seekable(std::io::Cursor::new(data))?;
+        crate::MediaParser::new().parse(ms)
+    }
+
+    fn tiff_with_xmp(xmp: &[u8]) -> Vec<u8> {
+        let mut data = b"II*\0\x08\0\0\0".to_vec();
+        data.extend(1_u16.to_le_bytes());
+        data.extend(TIFF_XMP_TAG.to_le_bytes());
+        data.extend(1_u16.to_le_bytes()); // BYTE
+        data.extend((xmp.len() as u32).to_le_bytes());
+        data.extend(26_u32.to_le_bytes());
+        data.extend(0_u32.to_le_bytes()); // next IFD
+        data.extend(xmp);
+        data
+    }
+
+    fn jpeg_with_xmp(xmp: &[u8]) -> Vec<u8> {
+        let mut data = vec![0xff, 0xd8, 0xff, 0xe1];
+        let payload = [jpeg::XMP_SIGNATURE, xmp].concat();
+        data.extend(((payload.len() + 2) as u16).to_be_bytes());
+        data.extend(payload);
+        data.extend([0xff, 0xda, 0, 2, 0xff, 0xd9]);
+        data
+    }
+
+    fn mp4_with_xmp(xmp: &[u8], quicktime: bool) -> Vec<u8> {
+        if quicktime {
+            let udta = bbox(b"udta", &bbox(b"XMP_", xmp));
+            [
+                bbox(b"ftyp", b"qt  \0\0\0\0qt  "),
+                bbox(b"mdat", &[0; 100_000]),
+                bbox(b"moov", &udta),
+            ]
+            .concat()
+        } else {
+            [
+                bbox(b"ftyp", b"isom\0\0\0\0isommp41"),
+                bbox(b"mdat", &[0; 100_000]),
+                bbox(b"uuid", &[XMP_UUID, xmp].concat()),
+            ]
+            .concat()
+        }
+    }
+
+    #[test_case("tiff")]
+    #[test_case("jpeg")]
+    #[test_case("mp4")]
+    #[test_case("mov")]
+    fn extract(format: &str) {
+        let xmp = TREE.as_bytes();
+        let data = match format {
+            "tiff" => tiff_with_xmp(xmp),
+            "jpeg" => jpeg_with_xmp(xmp),
+            "mp4" => mp4_with_xmp(xmp, false),
+            "mov" => mp4_with_xmp(xmp, true),
+            _ => unreachable!(),
+        };
+        let xmp = parse_file(data).unwrap();
+        assert_eq!(xmp, TREE.parse().unwrap());
+    }
+
+    #[test_case(TREE.as_bytes(); "xpacket")]
+    #[test_case(&[UTF8_BOM, b"<?xml version=\"1.0\"?>\n", TREE.as_bytes()].concat(); "xml declaration")]
+    fn sidecar(data: &[u8]) {
+        assert!(check_xmp_sidecar(data));
+        let xmp = parse_file(data.to_vec()).unwrap();
+        assert_eq!(xmp, TREE.parse().unwrap());
+
+        let ms = crate::MediaSource::seekable(std::io::Cursor::new(data.to_vec())).unwrap();
+        assert!(!ms.has_exif() && !ms.has_track());
+        let res: crate::Result<crate::ExifIter> = crate::MediaParser::new().parse(ms);
+        res.unwrap_err();
+    }
+
+    #[test_case(b"<?xml version=\"1.0\"?><svg/>"; "svg")]
+    #[test_case(b"<html><x:xmpmeta/></html>"; "html")]
+    fn not_sidecar(data: &[u8]) {
+        assert!(!check_xmp_sidecar(data));
+    }
+
+    #[test]
+    fn extract_heic() {
+        let xmp = parse_file(read_sample("exif.heic").unwrap()).unwrap();
+        let tool = xmp.get(Xmp::NS_XMP, "CreatorTool").unwrap();
+        assert_eq!(tool.as_str(), Some("15.5"));
+    }
+
+    #[test_case("exif.jpg"; "jpeg")]
+    #[test_case("meta.mp4"; "mp4")]
+    #[test_case("webm_480.webm"; "unsupported")]
+    fn no_xmp(path: &str) {
+        parse_file(read_sample(path).unwrap()).unwrap_err();
+    }
+
+    #[test]
+    fn to_packet() {
+        let xmp: Xmp = TREE.parse().unwrap();
+        let packet = xmp.to_packet();
+        assert!(packet.contains("xmlns:dc=\"http://purl.org/dc/elements/1.1/\""));
+        assert!(packet.contains("<xmp:Label>Tom &amp; Jerry \u{263a}</xmp:Label>"));
+        assert_eq!(packet.parse::<Xmp>().unwrap(), xmp);
+
+        // Unknown namespaces
+        let mut xmp = Xmp::default();
+        xmp.set("http://example.com/a/", "a", XmpValue::Text("<1>".into()))
+            .set("http://example.com/b/", "b", XmpValue::Text("\"2\"".into()));
+        let packet = xmp.to_packet();
+        assert!(packet.contains("<ns2:b>&quot;2&quot;</ns2:b>"));
+        assert_eq!(packet.parse::<Xmp>().unwrap(), xmp);
+    }
+
+    #[test]
+    fn edit() {
+        let mut xmp: Xmp = TREE.parse().unwrap();
+        xmp.set_rating(3).add_keywords(["sky", "sun"]);
+        assert_eq!(xmp.get(Xmp::NS_XMP, "Rating").unwrap().as_str(), Some("3"));
+        let subject = xmp.get(Xmp::NS_DC, "subject").unwrap().as_array().unwrap();
+        let subject = subject
+            .iter()
+            .map(|x| x.as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(subject, ["sea", "sky", "sun"]);
+        assert_eq!(xmp.iter().count(), 11);
+
+        assert_eq!(
+            xmp.remove(Xmp::NS_XMP, "Rating"),
+            Some(XmpValue::Text("3".into()))
+        );
+        assert_eq!(xmp.remove(Xmp::NS_XMP, "Rating"), None);
+
+        let mut other = Xmp::default();
+        other
+            .set_rating(1)
+            .set(Xmp::NS_XMP, "Label", XmpValue::Text("Red".into()));
+        xmp.merge(&other);
+        assert_eq!(xmp.get(Xmp::NS_XMP, "Rating").unwrap().as_str(), Some("1"));
+        assert_eq!(xmp.get(Xmp::NS_XMP, "Label").unwrap().as_str(), Some("Red"));
+        assert_eq!(xmp.iter().count(), 11);
+    }
+
+    #[test_case(read_sample("exif.jpg").unwrap(); "no xmp")]
+    #[test_case(jpeg_with_xmp(TREE.as_bytes()); "replace xmp")]
+    fn write_jpeg(data: Vec<u8>) {
+        let mut xmp = Xmp::default();
+        xmp.set_rating(5);
+        let mut output = Vec::new();
+        xmp.write_jpeg(std::io::Cursor::new(&data), &mut output)
+            .unwrap();
+        assert_eq!(parse_file(output.clone()).unwrap(), xmp);
+
+        // The Exif data is kept, as the first segment
+        let ms = crate::MediaSource::seekable(std::io::Cursor::new(data)).unwrap();
+        let exif: crate::Result<crate::ExifIter> = crate::MediaParser::new().parse(ms);
+        if let Ok(exif) = exif {
+            let (segments, _) = jpeg::parse_segments(&output).unwrap();
+            assert!(check_exif_header(&output[segments[0].payload.clone()]));
+            let ms = crate::MediaSource::seekable(std::io::Cursor::new(output)).unwrap();
+            let written: crate::ExifIter = crate::MediaParser::new().parse(ms).unwrap();
+            assert_eq!(written.count(), exif.count());
+        }
+    }
+
+    #[test]
+    fn write_heif() {
+        let data = read_sample("exif.heic").unwrap();
+        let mut xmp = parse_file(data.clone()).unwrap();
+        xmp.set_rating(2).add_keywords(["heif"]);
+        let mut output = Vec::new();
+        xmp.write_heif(std::io::Cursor::new(data), &mut output)
+            .unwrap();
+        assert_eq!(parse_file(output.clone()).unwrap(), xmp);
+
+        let ms = crate::MediaSource::seekable(std::io::Cursor::new(output)).unwrap();
+        let exif: crate::ExifIter = crate::MediaParser::new().parse(ms).unwrap();
+        let exif: crate::Exif = exif.into();
+        assert!(exif.get(crate::ExifTag::Model).is_some());
+
+        // No XMP item
+        let data = read_sample("exif-one-entry.heic").unwrap();
+        assert!(parse_file(data.clone()).is_err());
+        xmp.write_heif(std::io::Cursor::new(data), &mut Vec::new())
+            .unwrap_err();
+    }
+
+    #[test_case("2024-02-02T08:09:57+08:00", Some("2024-02-02T08:09:57+08:00"))]
+    #[test_case("2024-02-02T08:09:57.123Z", Some("2024-02-02T08:09:57.123+00:00"))]
+    #[test_case("2024-02-02T08:09+08:00", Some("2024-02-02T08:09:00+08:00"))]
+    #[test_case("2024-02-02", None)]
+    fn date(s: &str, expect: Option<&str>) {
+        assert_eq!(parse_xmp_date(s).map(|t| t.to_rfc3339()).as_deref(), expect);
+    }
+}